@@ -1,3 +1,6 @@
 fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/agent.proto").expect("failed to compile proto/agent.proto");
+
     tauri_build::build()
 }
@@ -0,0 +1,143 @@
+//! Self-metrics of RedSys Desktop Agent
+//!
+//! The agent runs for weeks on provider machines, so leaks show up in the
+//! field long before they show up in development. [`collect`] reports what
+//! the agent itself uses, for `get_agent_metrics`:
+//! - resident memory and uptime of the agent process
+//! - alive runtime tasks and running supervised subsystems; a task count
+//!   that keeps growing means tasks are leaked
+//! - depths of the in-process channels; a channel that stays full means a
+//!   subscriber is stuck
+//! - latency of the last Docker daemon check
+//! - restarts of the Docker events stream; frequent restarts mean the daemon
+//!   or the connection to it is flaky
+//!
+//! ## References
+//! - [Tokio runtime metrics](https://docs.rs/tokio/latest/tokio/runtime/struct.RuntimeMetrics.html)
+
+use tokio::{runtime::Handle, task};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sysinfo::{ProcessesToUpdate, System};
+
+use crate::backend::BackendClient;
+use crate::docker::DockerService;
+use crate::docker_monitor::DockerMonitor;
+use crate::events::Bus;
+use crate::supervisor::Supervisor;
+
+/// Messages buffered in the in-process channels.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ChannelDepths {
+    /// Internal event bus, forwarded to the frontend
+    pub event_bus: usize,
+
+    /// Docker events stream
+    pub docker_events: usize,
+
+    /// Commands received from the backend
+    pub backend_commands: usize,
+}
+
+/// Resource usage of the agent itself.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct AgentMetrics {
+    /// Resident set size of the agent process, if it could be read
+    pub rss_bytes: Option<u64>,
+
+    /// Seconds since the agent process started
+    pub uptime_secs: u64,
+
+    /// Tasks alive on the async runtime
+    pub runtime_tasks: Option<usize>,
+
+    /// Supervised subsystems currently running
+    pub running_subsystems: usize,
+
+    /// Subsystem restarts since the agent started
+    pub subsystem_restarts: u32,
+
+    /// Messages waiting in the in-process channels
+    pub channels: ChannelDepths,
+
+    /// Duration of the last Docker daemon check in milliseconds
+    pub last_poll_latency_ms: Option<u64>,
+
+    /// Times the Docker events stream was restarted after it terminated
+    pub docker_events_reconnects: u64,
+
+    /// When the metrics were collected
+    pub collected_at: DateTime<Utc>,
+}
+
+/// Resident set size in bytes and run time in seconds of this process.
+/// Blocking.
+fn process_usage() -> Option<(u64, u64)> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    let process = system.process(pid)?;
+    Some((process.memory(), process.run_time()))
+}
+
+/// Collects the agent's own metrics.
+pub async fn collect(
+    supervisor: &Supervisor,
+    bus: &Bus,
+    docker_monitor: &DockerMonitor,
+    docker_service: &DockerService,
+    backend_client: &BackendClient,
+) -> AgentMetrics {
+    let usage = task::spawn_blocking(process_usage).await.ok().flatten();
+    AgentMetrics {
+        rss_bytes: usage.map(|(rss, _)| rss),
+        uptime_secs: usage.map(|(_, run_time)| run_time).unwrap_or_default(),
+        runtime_tasks: Handle::try_current().ok().map(|handle| handle.metrics().num_alive_tasks()),
+        running_subsystems: supervisor.running(),
+        subsystem_restarts: supervisor.total_restarts(),
+        channels: ChannelDepths {
+            event_bus: bus.queued(),
+            docker_events: docker_service.queued_events(),
+            backend_commands: backend_client.queued_commands(),
+        },
+        last_poll_latency_ms: docker_monitor.last_check_duration().map(|duration| duration.as_millis() as u64),
+        docker_events_reconnects: docker_service.events_reconnects(),
+        collected_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio_util::sync::CancellationToken;
+    use crate::docker_monitor::DockerStatus;
+    use crate::events::Event;
+    use crate::types::DockerStatusChangedPayload;
+
+    #[tokio::test]
+    async fn test_collect_reports_own_process() {
+        let bus = Bus::new();
+        let _subscriber = bus.subscribe();
+        bus.publish(Event::DaemonStatus(DockerStatusChangedPayload::new(DockerStatus::Paused)));
+        let docker_monitor = Arc::new(DockerMonitor::new(CancellationToken::new()));
+
+        let metrics = collect(
+            &Supervisor::new(CancellationToken::new()),
+            &bus,
+            &docker_monitor,
+            &DockerService::new(CancellationToken::new()),
+            &BackendClient::new(None, docker_monitor.clone(), CancellationToken::new()),
+        )
+        .await;
+
+        assert!(metrics.rss_bytes.is_some_and(|rss| rss > 0));
+        assert!(metrics.runtime_tasks.is_some());
+        assert_eq!(metrics.channels.event_bus, 1);
+        assert_eq!(metrics.running_subsystems, 0);
+        assert_eq!(metrics.last_poll_latency_ms, None);
+        assert_eq!(metrics.docker_events_reconnects, 0);
+    }
+}
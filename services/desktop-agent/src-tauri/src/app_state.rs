@@ -0,0 +1,296 @@
+//! Application state for RedSys Desktop Agent
+//!
+//! [`AppStateStore`] holds the [`AppState`] served by `get_application_state`,
+//! the status server and the gRPC API. `main` creates one store and shares it
+//! through Tauri managed state, so every agent instance and every test works
+//! on its own state instead of a process-wide global.
+//!
+//! ## Sections
+//! Besides the metadata, the state holds a [`DockerSection`], a
+//! [`SystemSection`] and a [`JobsSection`](crate::types::JobsSection), each
+//! with its own timestamp, and the [`RequirementsReport`] of the startup
+//! check.
+//! [`AppStateStore::start_sync`] keeps them current from the Docker monitor
+//! and the event bus, so one `get_application_state` call returns a
+//! coherent snapshot of the whole agent.
+//!
+//! ## Updates
+//! Every update bumps [`AppState::revision`]. Callers change single fields
+//! with [`AppStateStore::patch`]; a patch that names the revision it was
+//! based on is rejected with [`AppError::Conflict`] if another update landed
+//! in between, so racing callers cannot silently overwrite each other.
+//!
+//! ## Events
+//! Every update publishes `app-state-changed` with the new revision and the
+//! changed fields, so listeners keep their copy current without polling
+//! `get_application_state`. In-process code can [`AppStateStore::subscribe`]
+//! instead.
+
+use std::sync::Arc;
+use chrono::Utc;
+use tokio::sync::{broadcast::error::RecvError, watch};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::docker_monitor::DockerStatus;
+use crate::error::{AppError, AppResult};
+use crate::events::{Bus, Event, JobEvent, SystemMetric};
+use crate::requirements::RequirementsReport;
+use crate::types::{AppState, AppStateChanged, AppStatePatch, DockerSection, SystemSection};
+
+/// Shared, thread-safe holder of the [`AppState`].
+#[derive(Debug)]
+pub struct AppStateStore {
+    /// Current state, broadcast to in-process subscribers
+    state: watch::Sender<AppState>,
+
+    /// Event bus for `app-state-changed`
+    bus: Arc<Bus>,
+}
+
+impl AppStateStore {
+    /// Creates a store holding the default state.
+    pub fn new() -> Self {
+        Self {
+            state: watch::Sender::new(AppState::default()),
+            bus: Arc::new(Bus::new()),
+        }
+    }
+
+    /// Shares the event bus that state changes are published to.
+    pub fn with_event_bus(self, bus: Arc<Bus>) -> Self {
+        Self { bus, ..self }
+    }
+
+    /// Gets a copy of the current state.
+    pub async fn get(&self) -> AppState {
+        self.state.borrow().clone()
+    }
+
+    /// Subscribes to state updates.
+    ///
+    /// The receiver sees the latest state; intermediate states are skipped
+    /// when updates land faster than it reads them.
+    pub fn subscribe(&self) -> watch::Receiver<AppState> {
+        self.state.subscribe()
+    }
+
+    /// Replaces the whole state, keeping the revision count going.
+    pub async fn replace(&self, state: AppState) {
+        // Replacing cannot conflict
+        let _ = self.update(|current| {
+            *current = state;
+            Ok(())
+        });
+    }
+
+    /// Applies a field-level update and returns the updated state.
+    ///
+    /// Fails with [`AppError::Conflict`] if `patch.base_revision` is set and
+    /// the state has been updated since that revision.
+    pub async fn patch(&self, patch: AppStatePatch) -> AppResult<AppState> {
+        self.update(|state| {
+            if let Some(expected) = patch.base_revision {
+                if expected != state.revision {
+                    return Err(AppError::Conflict { expected, found: state.revision });
+                }
+            }
+            state.apply(patch);
+            state.last_updated = Utc::now();
+            Ok(())
+        })
+    }
+
+    /// Stores the result of a requirements check.
+    ///
+    /// Also publishes `requirements-unmet` if a requirement is not met.
+    pub async fn record_requirements(&self, report: RequirementsReport) {
+        let met = report.met;
+        // Recording cannot conflict
+        let _ = self.update(|state| {
+            state.requirements = Some(report.clone());
+            Ok(())
+        });
+        if !met {
+            self.bus.publish(Event::RequirementsUnmet(report));
+        }
+    }
+
+    /// Keeps the Docker, system and jobs sections current until cancelled.
+    ///
+    /// The Docker section follows `docker_status`; the system and jobs
+    /// sections follow `system-metrics` and `job-state-changed` on the bus.
+    pub async fn start_sync(
+        self: Arc<Self>,
+        mut docker_status: watch::Receiver<DockerStatus>,
+        cancellation_token: CancellationToken,
+    ) {
+        info!("Starting application state sync");
+        let mut events = self.bus.subscribe();
+        self.sync_docker(docker_status.borrow_and_update().clone());
+
+        loop {
+            tokio::select! {
+                changed = docker_status.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let status = docker_status.borrow_and_update().clone();
+                    self.sync_docker(status);
+                }
+                received = events.recv() => match received {
+                    Ok(envelope) => match envelope.event {
+                        Event::SystemMetric(SystemMetric::Host(metrics)) => {
+                            let _ = self.update(|state| {
+                                state.system = SystemSection {
+                                    updated_at: Some(metrics.sampled_at),
+                                    metrics: Some(metrics),
+                                };
+                                Ok(())
+                            });
+                        }
+                        Event::Job(JobEvent::StateChanged(change)) => {
+                            let _ = self.update(|state| {
+                                state.jobs.record(change);
+                                Ok(())
+                            });
+                        }
+                        _ => {}
+                    },
+                    Err(RecvError::Lagged(missed)) => warn!("Application state sync lagged, {} events dropped", missed),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = cancellation_token.cancelled() => {
+                    info!("Application state sync received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Stores a new daemon status in the Docker section.
+    fn sync_docker(&self, status: DockerStatus) {
+        if self.state.borrow().docker.status == status {
+            return;
+        }
+        let _ = self.update(|state| {
+            state.docker = DockerSection {
+                status,
+                updated_at: Some(Utc::now()),
+            };
+            Ok(())
+        });
+    }
+
+    /// Runs `change` on the state, bumps the revision and publishes the diff.
+    fn update(&self, change: impl FnOnce(&mut AppState) -> AppResult<()>) -> AppResult<AppState> {
+        let mut result = None;
+        self.state.send_if_modified(|state| {
+            let previous = state.clone();
+            match change(state) {
+                Ok(()) => {
+                    state.revision = previous.revision + 1;
+                    result = Some(Ok((previous, state.clone())));
+                    true
+                }
+                Err(e) => {
+                    result = Some(Err(e));
+                    false
+                }
+            }
+        });
+        let (previous, state) = result.expect("send_if_modified runs the closure")?;
+        self.bus.publish(Event::AppState(AppStateChanged::between(&previous, &state)));
+        Ok(state)
+    }
+}
+
+impl Default for AppStateStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AppMetadataPatch;
+
+    #[tokio::test]
+    async fn test_stores_are_independent() {
+        let first = AppStateStore::new();
+        let second = AppStateStore::new();
+
+        let mut state = first.get().await;
+        state.app_metadata.name = "Test Agent".to_string();
+        first.replace(state).await;
+
+        assert_eq!(first.get().await.app_metadata.name, "Test Agent");
+        assert_eq!(second.get().await.app_metadata.name, "RedSys Desktop Agent");
+    }
+
+    #[tokio::test]
+    async fn test_patch_detects_conflicts() {
+        let store = AppStateStore::new();
+        let base_revision = store.get().await.revision;
+        let patch = |description: &str| AppStatePatch {
+            base_revision: Some(base_revision),
+            app_metadata: Some(AppMetadataPatch {
+                description: Some(description.to_string()),
+                ..AppMetadataPatch::default()
+            }),
+        };
+
+        let updated = store.patch(patch("first")).await.unwrap();
+        assert_eq!(updated.revision, base_revision + 1);
+
+        let result = store.patch(patch("second")).await;
+        assert!(matches!(result, Err(AppError::Conflict { expected, found }) if expected == base_revision && found == base_revision + 1));
+        assert_eq!(store.get().await.app_metadata.description, "first");
+    }
+
+    #[tokio::test]
+    async fn test_updates_publish_changes() {
+        let bus = Arc::new(Bus::new());
+        let store = AppStateStore::new().with_event_bus(bus.clone());
+        let mut events = bus.subscribe();
+        let mut state = store.subscribe();
+
+        let patch = AppStatePatch {
+            app_metadata: Some(AppMetadataPatch {
+                name: Some("Render Node".to_string()),
+                ..AppMetadataPatch::default()
+            }),
+            ..AppStatePatch::default()
+        };
+        store.patch(patch).await.unwrap();
+
+        let Event::AppState(change) = events.recv().await.unwrap().event else {
+            panic!("expected an app state event");
+        };
+        assert_eq!(change.revision, 1);
+        assert_eq!(change.changes.keys().collect::<Vec<_>>(), ["app_metadata.name"]);
+        assert!(state.has_changed().unwrap());
+        assert_eq!(state.borrow_and_update().app_metadata.name, "Render Node");
+    }
+
+    #[tokio::test]
+    async fn test_unmet_requirements_are_published() {
+        let bus = Arc::new(Bus::new());
+        let store = AppStateStore::new().with_event_bus(bus.clone());
+        let mut events = bus.subscribe();
+        let report = RequirementsReport {
+            met: false,
+            unmet: Vec::new(),
+            checked_at: Utc::now(),
+        };
+        store.record_requirements(report.clone()).await;
+
+        assert!(matches!(events.recv().await.unwrap().event, Event::AppState(_)));
+        let Event::RequirementsUnmet(published) = events.recv().await.unwrap().event else {
+            panic!("expected a requirements event");
+        };
+        assert_eq!(published, report);
+        assert_eq!(store.get().await.requirements, Some(report));
+    }
+}
@@ -0,0 +1,251 @@
+//! Run-at-login management for RedSys Desktop Agent
+//!
+//! A monitoring agent that only runs when launched by hand misses most
+//! daemon downtime, so the agent can register itself to start at login:
+//! - **Linux**: XDG autostart entry `~/.config/autostart/<identifier>.desktop`
+//! - **macOS**: launch agent `~/Library/LaunchAgents/<identifier>.plist`
+//! - **Windows**: `HKCU\Software\Microsoft\Windows\CurrentVersion\Run` value
+//!
+//! Login launches pass [`AUTOSTART_ARG`], and the agent then starts hidden in
+//! the tray instead of opening its window.
+//!
+//! ## References
+//! - [XDG Autostart Specification](https://specifications.freedesktop.org/autostart-spec/latest/)
+//! - [launchd.plist](https://keith.github.io/xcode-man-pages/launchd.plist.5.html)
+//! - [Run and RunOnce Registry Keys](https://learn.microsoft.com/en-us/windows/win32/setupapi/run-and-runonce-registry-keys)
+
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::error::{AppError, AppResult};
+#[cfg(not(target_os = "windows"))]
+use crate::APP_IDENTIFIER;
+
+/// Argument passed by login launches.
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+/// Whether this process was started at login.
+pub fn launched_at_login() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+/// Path of the executable to register.
+///
+/// AppImages run from a temporary mount, so the image itself is registered.
+fn executable() -> AppResult<PathBuf> {
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    Ok(std::env::current_exe()?)
+}
+
+/// Registers the agent to start at login.
+pub fn enable() -> AppResult<()> {
+    let executable = executable()?;
+    platform::enable(&executable)?;
+    info!("Autostart enabled for {}", executable.display());
+    Ok(())
+}
+
+/// Removes the login registration; does nothing if none exists.
+pub fn disable() -> AppResult<()> {
+    platform::disable()?;
+    info!("Autostart disabled");
+    Ok(())
+}
+
+/// Whether the agent is registered to start at login.
+pub fn is_enabled() -> AppResult<bool> {
+    platform::is_enabled()
+}
+
+/// Builds the XDG autostart entry.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn desktop_entry(executable: &std::path::Path) -> String {
+    // Exec arguments are quoted with `"`, escaping `"`, `` ` ``, `$` and `\`
+    // with a backslash. Values are unescaped once more as strings before
+    // the Exec rules apply, so that backslash is written doubled. A literal
+    // `%` is written `%%`, since `%` starts field codes.
+    let mut quoted = String::from('"');
+    for c in executable.display().to_string().chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\\\\\"),
+            '"' | '`' | '$' => {
+                quoted.push_str("\\\\");
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=RedSys Desktop Agent\n\
+         Exec={quoted} {AUTOSTART_ARG}\n\
+         X-GNOME-Autostart-enabled=true\n\
+         NoDisplay=true\n"
+    )
+}
+
+/// Builds the launch agent property list.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn launch_agent_plist(label: &str, executable: &std::path::Path) -> String {
+    let escape = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{executable}</string>
+        <string>{AUTOSTART_ARG}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        label = escape(label),
+        executable = escape(&executable.display().to_string()),
+    )
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    /// Location of the login registration file.
+    fn location() -> AppResult<PathBuf> {
+        #[cfg(target_os = "linux")]
+        let location = dirs::config_dir().map(|dir| dir.join("autostart").join(format!("{APP_IDENTIFIER}.desktop")));
+        #[cfg(target_os = "macos")]
+        let location = dirs::home_dir()
+            .map(|dir| dir.join("Library").join("LaunchAgents").join(format!("{APP_IDENTIFIER}.plist")));
+
+        location.ok_or_else(|| AppError::Configuration("Cannot determine the autostart directory".to_string()))
+    }
+
+    pub(super) fn enable(executable: &Path) -> AppResult<()> {
+        let location = location()?;
+        if let Some(parent) = location.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        #[cfg(target_os = "linux")]
+        let contents = desktop_entry(executable);
+        #[cfg(target_os = "macos")]
+        let contents = launch_agent_plist(APP_IDENTIFIER, executable);
+
+        std::fs::write(location, contents)?;
+        Ok(())
+    }
+
+    pub(super) fn disable() -> AppResult<()> {
+        match std::fs::remove_file(location()?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn is_enabled() -> AppResult<bool> {
+        Ok(location()?.exists())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::path::Path;
+    use std::process::Command;
+
+    use super::*;
+
+    /// Registry key holding per-user login items.
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+    /// Registry value name of the agent.
+    const VALUE_NAME: &str = "RedSys Desktop Agent";
+
+    /// Runs `reg.exe`, returning whether it succeeded.
+    fn reg(args: &[&str]) -> AppResult<bool> {
+        let status = Command::new("reg").args(args).output()?.status;
+        Ok(status.success())
+    }
+
+    pub(super) fn enable(executable: &Path) -> AppResult<()> {
+        let command = format!("\"{}\" {AUTOSTART_ARG}", executable.display());
+        if reg(&["add", RUN_KEY, "/v", VALUE_NAME, "/t", "REG_SZ", "/d", &command, "/f"])? {
+            Ok(())
+        } else {
+            Err(AppError::Permission("Failed to write the Run registry value".to_string()))
+        }
+    }
+
+    pub(super) fn disable() -> AppResult<()> {
+        // Fails when the value does not exist, which is the desired state
+        if is_enabled()? && !reg(&["delete", RUN_KEY, "/v", VALUE_NAME, "/f"])? {
+            return Err(AppError::Permission("Failed to delete the Run registry value".to_string()));
+        }
+        Ok(())
+    }
+
+    pub(super) fn is_enabled() -> AppResult<bool> {
+        reg(&["query", RUN_KEY, "/v", VALUE_NAME])
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::path::Path;
+
+    use super::*;
+
+    fn unsupported<T>() -> AppResult<T> {
+        Err(AppError::Configuration(format!("Autostart is not supported on {}", std::env::consts::OS)))
+    }
+
+    pub(super) fn enable(_executable: &Path) -> AppResult<()> {
+        unsupported()
+    }
+
+    pub(super) fn disable() -> AppResult<()> {
+        unsupported()
+    }
+
+    pub(super) fn is_enabled() -> AppResult<bool> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_desktop_entry_quotes_executable() {
+        let entry = desktop_entry(Path::new("/opt/Red Sys/agent$1"));
+        assert!(entry.contains("Exec=\"/opt/Red Sys/agent\\\\$1\" --autostart\n"));
+        let entry = desktop_entry(Path::new("/opt/a\\b/100%/\"agent\""));
+        assert!(entry.contains("Exec=\"/opt/a\\\\\\\\b/100%%/\\\\\"agent\\\\\"\" --autostart\n"));
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+    }
+
+    #[test]
+    fn test_launch_agent_plist_escapes_values() {
+        let plist = launch_agent_plist("tn.com.redsys-desktop-agent", Path::new("/Applications/R&D.app/agent"));
+        assert!(plist.contains("<string>/Applications/R&amp;D.app/agent</string>"));
+        assert!(plist.contains("<key>RunAtLoad</key>\n    <true/>"));
+    }
+}
@@ -0,0 +1,388 @@
+//! Docker daemon availability for RedSys Desktop Agent
+//!
+//! The RedSys platform ranks providers by how reliably their Docker daemon
+//! runs. [`AvailabilityTracker`] follows the daemon state transitions and
+//! appends every finished period, with its state and duration, to
+//! `availability.jsonl` in the local data directory, so the timeline
+//! survives agent restarts. Periods older than [`RETENTION`] are dropped at
+//! startup.
+//!
+//! [`AvailabilityStats`] summarize a window of the timeline:
+//! - **Uptime**: share of the observed time the daemon was running
+//! - **Outages**: stretches of `Stopped`, `Error` or `Restarting`, counted
+//!   once even when the state changes in between
+//! - **Restarts**: daemon restarts, i.e. `Restarting` periods followed by
+//!   `Running`, that came back within the window
+//!
+//! Time while the agent was not running, monitoring was paused or the first
+//! check was still in progress is not observed and counts neither way.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::status::{DaemonState, StatusTransition};
+use crate::APP_IDENTIFIER;
+
+/// Timeline file name in the local data directory.
+const TIMELINE_FILE_NAME: &str = "availability.jsonl";
+
+/// How long periods are kept, and the longest stats window.
+pub const RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Shortest stats window.
+pub const MIN_WINDOW: Duration = Duration::from_secs(60);
+
+/// A finished period in one daemon state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AvailabilityPeriod {
+    /// Daemon state during the period
+    pub state: DaemonState,
+
+    /// When the period started
+    pub started_at: DateTime<Utc>,
+
+    /// Length of the period in milliseconds
+    pub duration_ms: u64,
+}
+
+impl AvailabilityPeriod {
+    fn ended_at(&self) -> DateTime<Utc> {
+        self.started_at + TimeDelta::milliseconds(self.duration_ms as i64)
+    }
+}
+
+/// Availability of the Docker daemon over a window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct AvailabilityStats {
+    /// Start of the window
+    pub from: DateTime<Utc>,
+
+    /// End of the window
+    pub to: DateTime<Utc>,
+
+    /// Seconds of the window the daemon state was known
+    pub observed_secs: f64,
+
+    /// Share of the observed time the daemon was running, `None` if nothing
+    /// was observed
+    pub uptime_percent: Option<f64>,
+
+    /// Outages overlapping the window
+    pub outages: u32,
+
+    /// Mean time the daemon was down per outage, `None` without outages
+    pub mean_outage_secs: Option<f64>,
+
+    /// Daemon restarts completed within the window
+    pub restarts: u32,
+}
+
+/// Whether the daemon counts as up in `state`; `None` if the state says
+/// nothing about availability.
+fn available(state: DaemonState) -> Option<bool> {
+    match state {
+        DaemonState::Running => Some(true),
+        DaemonState::Stopped | DaemonState::Error | DaemonState::Restarting => Some(false),
+        DaemonState::Unknown | DaemonState::Checking | DaemonState::Paused => None,
+    }
+}
+
+/// Summarizes the `periods`, in order, between `from` and `to`.
+fn stats<'a, I>(periods: I, from: DateTime<Utc>, to: DateTime<Utc>) -> AvailabilityStats
+where
+    I: IntoIterator<Item = &'a AvailabilityPeriod>,
+{
+    let (mut up_ms, mut down_ms, mut outages, mut restarts) = (0, 0, 0, 0);
+    let mut in_outage = false;
+    let mut previous = None;
+    for period in periods {
+        let restarted = previous == Some(DaemonState::Restarting) && period.state == DaemonState::Running;
+        previous = Some(period.state);
+        if restarted && period.started_at >= from && period.started_at < to {
+            restarts += 1;
+        }
+        let start = period.started_at.max(from);
+        let end = period.ended_at().min(to);
+        if end <= start {
+            continue;
+        }
+        let length_ms = (end - start).num_milliseconds();
+        match available(period.state) {
+            Some(true) => {
+                up_ms += length_ms;
+                in_outage = false;
+            }
+            Some(false) => {
+                down_ms += length_ms;
+                if !in_outage {
+                    outages += 1;
+                    in_outage = true;
+                }
+            }
+            // An outage continues across a restart of the agent
+            None => {}
+        }
+    }
+
+    let observed_ms = up_ms + down_ms;
+    AvailabilityStats {
+        from,
+        to,
+        observed_secs: observed_ms as f64 / 1000.0,
+        uptime_percent: (observed_ms > 0).then(|| up_ms as f64 * 100.0 / observed_ms as f64),
+        outages,
+        mean_outage_secs: (outages > 0).then(|| down_ms as f64 / 1000.0 / outages as f64),
+        restarts,
+    }
+}
+
+/// Recorded periods and the one in progress.
+#[derive(Debug, Default)]
+struct Timeline {
+    /// Finished periods, oldest first
+    periods: VecDeque<AvailabilityPeriod>,
+
+    /// State since the last transition
+    current: Option<(DaemonState, DateTime<Utc>)>,
+}
+
+/// Records the daemon state timeline and computes availability stats.
+#[derive(Debug)]
+pub struct AvailabilityTracker {
+    /// Timeline file, `None` to keep the timeline in memory only
+    path: Option<PathBuf>,
+
+    /// Periods within the retention
+    timeline: Mutex<Timeline>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl AvailabilityTracker {
+    /// Creates a tracker, loading the periods recorded in `path` earlier.
+    ///
+    /// A missing or unreadable file starts an empty timeline.
+    pub fn new(path: Option<PathBuf>, cancellation_token: CancellationToken) -> Self {
+        let mut periods = VecDeque::new();
+        if let Some(path) = &path {
+            match Self::load(path) {
+                Ok(loaded) => periods = loaded,
+                Err(e) => warn!("Failed to load availability timeline from {}: {}", path.display(), e),
+            }
+        }
+        Self {
+            path,
+            timeline: Mutex::new(Timeline { periods, current: None }),
+            cancellation_token,
+        }
+    }
+
+    /// Returns the default timeline file in the platform local data dir.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join(APP_IDENTIFIER).join(TIMELINE_FILE_NAME))
+    }
+
+    /// Reads the periods within the retention, rewriting the file if older
+    /// ones were dropped.
+    fn load(path: &Path) -> AppResult<VecDeque<AvailabilityPeriod>> {
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(VecDeque::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let cutoff = Utc::now() - TimeDelta::from_std(RETENTION).unwrap_or_default();
+        let mut periods = VecDeque::new();
+        let mut dropped = 0;
+        for line in std::io::BufReader::new(file).lines() {
+            let line = line?;
+            match serde_json::from_str::<AvailabilityPeriod>(&line) {
+                Ok(period) if period.ended_at() >= cutoff => periods.push_back(period),
+                // Expired, or cut off by a crash while writing
+                _ => dropped += 1,
+            }
+        }
+        if dropped > 0 {
+            debug!("Dropping {} expired or invalid availability periods", dropped);
+            let mut contents = String::new();
+            for period in &periods {
+                contents.push_str(&serde_json::to_string(period)?);
+                contents.push('\n');
+            }
+            let temp_path = path.with_extension("tmp");
+            std::fs::write(&temp_path, contents)?;
+            std::fs::rename(&temp_path, path)?;
+        }
+        Ok(periods)
+    }
+
+    /// Appends a finished period to the timeline file.
+    fn append(path: &Path, period: &AvailabilityPeriod) -> AppResult<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(period)?)?;
+        Ok(())
+    }
+
+    /// Ends the period in progress at `at` and starts one in `state`, if any.
+    fn record(&self, state: Option<DaemonState>, at: DateTime<Utc>) {
+        let mut timeline = self.timeline.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = std::mem::replace(&mut timeline.current, state.map(|state| (state, at)));
+        let Some((previous_state, started_at)) = previous else {
+            return;
+        };
+        let period = AvailabilityPeriod {
+            state: previous_state,
+            started_at,
+            duration_ms: (at - started_at).num_milliseconds().max(0) as u64,
+        };
+        if let Some(path) = &self.path {
+            if let Err(e) = Self::append(path, &period) {
+                warn!("Failed to record availability period in {}: {}", path.display(), e);
+            }
+        }
+        timeline.periods.push_back(period);
+        let cutoff = at - TimeDelta::from_std(RETENTION).unwrap_or_default();
+        while timeline.periods.front().is_some_and(|period| period.ended_at() < cutoff) {
+            timeline.periods.pop_front();
+        }
+    }
+
+    /// Records `transitions` until cancelled, closing the period in progress
+    /// on shutdown.
+    pub async fn start(self: Arc<Self>, mut transitions: broadcast::Receiver<StatusTransition>) {
+        info!("Recording Docker daemon availability");
+        loop {
+            tokio::select! {
+                received = transitions.recv() => match received {
+                    Ok(transition) => self.record(Some(transition.to), transition.at),
+                    Err(RecvError::Lagged(missed)) => warn!("Availability tracking lagged, {} transitions dropped", missed),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Availability tracking received cancellation signal, shutting down");
+                    self.record(None, Utc::now());
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Availability over the last `window`, up to now.
+    ///
+    /// Fails if `window` is shorter than [`MIN_WINDOW`] or longer than
+    /// [`RETENTION`].
+    pub fn stats(&self, window: Duration) -> AppResult<AvailabilityStats> {
+        if window < MIN_WINDOW || window > RETENTION {
+            return Err(AppError::Configuration(format!(
+                "Availability window must be between {}s and {}s",
+                MIN_WINDOW.as_secs(),
+                RETENTION.as_secs()
+            )));
+        }
+        let to = Utc::now();
+        let from = to - TimeDelta::from_std(window).unwrap_or_default();
+        let timeline = self.timeline.lock().unwrap_or_else(|e| e.into_inner());
+        let current = timeline.current.map(|(state, started_at)| AvailabilityPeriod {
+            state,
+            started_at,
+            duration_ms: (to - started_at).num_milliseconds().max(0) as u64,
+        });
+        Ok(stats(timeline.periods.iter().chain(current.as_ref()), from, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn period(state: DaemonState, started_min: i64, minutes: u64) -> AvailabilityPeriod {
+        AvailabilityPeriod {
+            state,
+            started_at: DateTime::from_timestamp(1_700_000_000 + started_min * 60, 0).unwrap(),
+            duration_ms: minutes * 60 * 1000,
+        }
+    }
+
+    #[test]
+    fn test_stats_merge_outages_and_skip_unobserved_time() {
+        let periods = [
+            period(DaemonState::Checking, 0, 1),
+            period(DaemonState::Running, 1, 50),
+            period(DaemonState::Restarting, 51, 1),
+            period(DaemonState::Stopped, 52, 5),
+            // Agent not running for 10 minutes
+            period(DaemonState::Checking, 67, 1),
+            period(DaemonState::Stopped, 68, 4),
+            period(DaemonState::Running, 72, 28),
+            period(DaemonState::Error, 100, 10),
+        ];
+        let from = periods[0].started_at;
+
+        let all = stats(&periods, from, from + TimeDelta::minutes(110));
+        assert_eq!(all.observed_secs, 98.0 * 60.0);
+        assert_eq!(all.outages, 2);
+        assert_eq!(all.mean_outage_secs, Some(10.0 * 60.0));
+        assert_eq!(all.uptime_percent, Some(78.0 * 100.0 / 98.0));
+
+        // Clipped to the first running period
+        let clipped = stats(&periods, from + TimeDelta::minutes(10), from + TimeDelta::minutes(20));
+        assert_eq!(clipped.uptime_percent, Some(100.0));
+        assert_eq!(clipped.mean_outage_secs, None);
+
+        assert_eq!(stats(&periods[..0], from, from + TimeDelta::minutes(1)).uptime_percent, None);
+    }
+
+    #[test]
+    fn test_stats_count_restarts_within_window() {
+        let periods = [
+            period(DaemonState::Running, 0, 10),
+            period(DaemonState::Restarting, 10, 1),
+            period(DaemonState::Running, 11, 19),
+            period(DaemonState::Restarting, 30, 1),
+            period(DaemonState::Running, 31, 19),
+            // Did not come back up by itself
+            period(DaemonState::Restarting, 50, 1),
+            period(DaemonState::Stopped, 51, 9),
+        ];
+        let from = periods[0].started_at;
+
+        let all = stats(&periods, from, from + TimeDelta::minutes(60));
+        assert_eq!(all.restarts, 2);
+        assert_eq!(all.outages, 3);
+
+        // Only the second restart came back within the window
+        let clipped = stats(&periods, from + TimeDelta::minutes(20), from + TimeDelta::minutes(60));
+        assert_eq!(clipped.restarts, 1);
+    }
+
+    #[test]
+    fn test_tracker_persists_periods() {
+        let path = std::env::temp_dir().join(format!("redsys-availability-test-{}.jsonl", std::process::id()));
+        let tracker = AvailabilityTracker::new(Some(path.clone()), CancellationToken::new());
+        let now = Utc::now();
+        tracker.record(Some(DaemonState::Running), now - TimeDelta::minutes(30));
+        tracker.record(Some(DaemonState::Stopped), now - TimeDelta::minutes(10));
+        tracker.record(None, now);
+
+        let reloaded = AvailabilityTracker::new(Some(path.clone()), CancellationToken::new());
+        let stats = reloaded.stats(Duration::from_secs(3600)).unwrap();
+        assert_eq!(stats.outages, 1);
+        assert_eq!(stats.uptime_percent.map(f64::round), Some(67.0));
+        assert!(reloaded.stats(Duration::from_secs(1)).is_err());
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,845 @@
+//! RedSys backend connection for RedSys Desktop Agent
+//!
+//! Maintains a WebSocket connection to the RedSys platform. The agent
+//! identifies itself with a `hello` message, sends periodic heartbeats with
+//! its [`DockerStatus`] and [`Capabilities`], plus one right after every
+//! Docker status change, and receives commands such as job assignments.
+//! With a [`CapabilityProfiler`], the full [`CapabilityProfile`] follows the
+//! `hello` and every new revision of it.
+//!
+//! Credentials come from `[backend]` or, when unset there, from the identity
+//! stored by [provider registration](crate::provider).
+//!
+//! ## Protocol
+//! JSON text frames tagged by `type`:
+//! - Agent → backend: `hello`, `heartbeat`, `capability_profile`, `command_ack`
+//! - Backend → agent: `job_assignment`, `job_cancellation`; unknown types are
+//!   acknowledged as rejected
+//!
+//! Commands are acknowledged once the subsystem handling them reports the
+//! outcome with [`BackendClient::acknowledge`], e.g. after a job assignment
+//! was submitted, so a rejected job is acknowledged with its error.
+//!
+//! The URL must use `wss://` unless it points at a loopback host, since the
+//! `hello` carries the agent token.
+//!
+//! ## Resilience
+//! - Connection failures reconnect with exponential backoff (1s doubling up
+//!   to 60s), reset once a connection is established
+//! - A missed heartbeat write tears the connection down and reconnects
+//! - [`BackendClient::reconnect`] replaces the connection right away, e.g.
+//!   after the network changed
+//! - Connection state changes emit `backend-connection-changed`
+//!
+//! ## References
+//! - [tokio-tungstenite](https://docs.rs/tokio-tungstenite/latest/tokio_tungstenite/)
+//! - [WebSocket Protocol](https://www.rfc-editor.org/rfc/rfc6455)
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::{sync::{broadcast, mpsc, watch, Mutex, RwLock}, task, time::{interval, Duration}};
+use tokio_util::sync::CancellationToken;
+use tokio_tungstenite::tungstenite::http::Uri;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, info, info_span, warn};
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::capability_profile::{CapabilityProfile, CapabilityProfiler};
+use crate::clock::{self, DriftMonitor};
+use crate::config::BackendConfig;
+use crate::disk_pressure::{DiskPressure, DiskPressureMonitor};
+use crate::docker::{next_backoff, DockerFlavor, DockerInfo, DockerService};
+use crate::docker_monitor::{DockerMonitor, DockerStatus};
+use crate::provider::ProviderIdentity;
+use crate::gpu::{self, GpuInfo, GpuRuntime};
+use crate::telemetry;
+
+/// Initial delay before reconnecting to the backend.
+const BACKEND_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound for the reconnect delay.
+const BACKEND_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Time allowed for the WebSocket handshake.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Capacity of the received command channel.
+const COMMAND_CHANNEL_CAPACITY: usize = 32;
+
+/// Checks that `url` is a WebSocket URL that uses TLS unless its host is
+/// loopback.
+pub fn check_url(url: &str) -> Result<(), String> {
+    let uri: Uri = url.parse().map_err(|e| format!("backend.url '{url}' is not a valid URL: {e}"))?;
+    let loopback = uri.host().is_some_and(|host| {
+        host.eq_ignore_ascii_case("localhost")
+            || host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+    });
+    match uri.scheme_str() {
+        Some("wss") => Ok(()),
+        Some("ws") if loopback => Ok(()),
+        Some("ws") => Err(format!("backend.url '{url}' must use wss:// unless it points at localhost")),
+        _ => Err(format!("backend.url '{url}' must start with ws:// or wss://")),
+    }
+}
+
+/// Backend connection settings.
+#[derive(Debug, Clone)]
+pub struct BackendSettings {
+    /// WebSocket URL of the RedSys platform (`ws://` or `wss://`)
+    pub url: String,
+
+    /// Agent identifier issued by the platform
+    pub agent_id: String,
+
+    /// Agent token issued by the platform
+    pub token: String,
+
+    /// Interval between heartbeats
+    pub heartbeat_interval: Duration,
+}
+
+impl BackendSettings {
+    /// Builds the settings from the `[backend]` config section.
+    ///
+    /// Credentials in the config take precedence over the stored provider
+    /// `identity`. Returns `None` when no backend URL is configured or no
+    /// credentials are available.
+    pub fn from_config(config: &BackendConfig, identity: Option<&ProviderIdentity>) -> Option<Self> {
+        let (agent_id, token) = match (&config.agent_id, &config.token) {
+            (Some(agent_id), Some(token)) => (agent_id.clone(), token.clone()),
+            _ => {
+                let identity = identity?;
+                (identity.agent_id.clone(), identity.token.clone())
+            }
+        };
+        Some(Self {
+            url: config.url.clone()?,
+            agent_id,
+            token,
+            heartbeat_interval: Duration::from_secs(config.heartbeat_interval_secs),
+        })
+    }
+}
+
+/// Host capabilities reported to the platform.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Capabilities {
+    /// Operating system, e.g. `linux`
+    pub os: String,
+
+    /// CPU architecture, e.g. `x86_64`
+    pub arch: String,
+
+    /// Logical CPUs available to the agent
+    pub cpus: usize,
+
+    /// Agent version
+    pub agent_version: String,
+
+    /// Docker Engine version, if the daemon is running
+    pub docker_version: Option<String>,
+
+    /// GPUs and their driver and compute stack versions
+    pub gpu: GpuInfo,
+
+    /// Whether Docker can run GPU containers
+    pub gpu_runtime: GpuRuntime,
+
+    /// Whether the host should not receive new jobs, e.g. on low disk space
+    pub degraded: bool,
+
+    /// Whether Docker runs rootless, which rules out privileged containers,
+    /// ports below 1024 and, on cgroup v1, resource limits
+    pub rootless: bool,
+
+    /// Watched partitions below the free space warning threshold
+    pub disk_pressure: Vec<DiskPressure>,
+
+    /// System clock offset against NTP in milliseconds, positive when the
+    /// clock is ahead; `None` until measured
+    pub clock_offset_ms: Option<i64>,
+}
+
+impl Capabilities {
+    /// Detects the capabilities of this host.
+    ///
+    /// `gpu` comes from [`gpu::detect`], which is too slow to repeat for
+    /// every heartbeat.
+    pub fn detect(docker_status: &DockerStatus, gpu: &GpuInfo, gpu_runtime: GpuRuntime) -> Self {
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpus: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            agent_version: env!("CARGO_PKG_VERSION").to_string(),
+            docker_version: match docker_status {
+                DockerStatus::Running { version, .. } => Some(version.clone()),
+                _ => None,
+            },
+            gpu: gpu.clone(),
+            gpu_runtime,
+            degraded: matches!(docker_status, DockerStatus::Degraded { .. }),
+            rootless: false,
+            disk_pressure: Vec::new(),
+            clock_offset_ms: None,
+        }
+    }
+
+    /// Marks the host rootless according to the daemon information.
+    pub fn with_docker_info(self, info: &DockerInfo) -> Self {
+        Self {
+            rootless: info.flavor == DockerFlavor::Rootless,
+            ..self
+        }
+    }
+
+    /// Reports partitions under disk pressure, marking the host degraded if any.
+    pub fn with_disk_pressure(self, disk_pressure: Vec<DiskPressure>) -> Self {
+        Self {
+            degraded: self.degraded || !disk_pressure.is_empty(),
+            disk_pressure,
+            ..self
+        }
+    }
+
+    /// Reports the clock offset, marking the host degraded if it drifted by
+    /// [`clock::DRIFT_CRITICAL`] or more.
+    pub fn with_clock_offset(self, offset: Option<TimeDelta>) -> Self {
+        Self {
+            degraded: self.degraded || offset.is_some_and(|offset| offset.abs() >= clock::DRIFT_CRITICAL),
+            clock_offset_ms: offset.map(|offset| offset.num_milliseconds()),
+            ..self
+        }
+    }
+}
+
+/// Messages sent to the backend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentMessage {
+    /// First message after connecting
+    Hello {
+        agent_id: String,
+        token: String,
+        capabilities: Capabilities,
+    },
+
+    /// Periodic liveness report
+    Heartbeat {
+        docker: DockerStatus,
+        capabilities: Capabilities,
+        sent_at: DateTime<Utc>,
+    },
+
+    /// Host capabilities for job matching, after `hello` and on change
+    CapabilityProfile {
+        profile: CapabilityProfile,
+    },
+
+    /// Acknowledges a received command
+    CommandAck {
+        command_id: String,
+        accepted: bool,
+        error: Option<String>,
+    },
+}
+
+/// Commands received from the backend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BackendCommand {
+    /// Assigns a job to this agent
+    JobAssignment {
+        command_id: String,
+        job: serde_json::Value,
+    },
+
+    /// Cancels a job assigned earlier
+    JobCancellation {
+        command_id: String,
+        job_id: String,
+    },
+}
+
+impl BackendCommand {
+    /// Identifier used to acknowledge the command.
+    pub fn command_id(&self) -> &str {
+        match self {
+            BackendCommand::JobAssignment { command_id, .. } | BackendCommand::JobCancellation { command_id, .. } => {
+                command_id
+            }
+        }
+    }
+}
+
+/// State of the backend connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendConnectionState {
+    /// No backend configured
+    Disabled,
+
+    /// Waiting to (re)connect
+    Disconnected,
+
+    /// Handshake in progress
+    Connecting,
+
+    /// Connected and identified
+    Connected,
+}
+
+/// Payload of the `backend-connection-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendConnectionChanged {
+    /// New connection state
+    pub state: BackendConnectionState,
+
+    /// Backend URL
+    pub url: String,
+}
+
+/// Outcome of parsing a received text frame.
+#[derive(Debug, PartialEq)]
+enum Incoming {
+    /// A known command
+    Command(BackendCommand),
+
+    /// A message that could not be handled, with its command ID if present
+    Rejected { command_id: Option<String>, error: String },
+}
+
+/// Parses a text frame from the backend.
+fn parse_incoming(text: &str) -> Incoming {
+    match serde_json::from_str::<BackendCommand>(text) {
+        Ok(command) => Incoming::Command(command),
+        Err(e) => {
+            let command_id = serde_json::from_str::<serde_json::Value>(text)
+                .ok()
+                .and_then(|value| value.get("command_id")?.as_str().map(str::to_string));
+            Incoming::Rejected {
+                command_id,
+                error: format!("Unsupported command: {e}"),
+            }
+        }
+    }
+}
+
+/// WebSocket client for the RedSys platform.
+#[derive(Debug)]
+pub struct BackendClient {
+    /// Connection settings, `None` when no backend is configured
+    settings: Option<BackendSettings>,
+
+    /// Source of the Docker status for heartbeats
+    docker_monitor: Arc<DockerMonitor>,
+
+    /// Source of disk pressure for the capability report
+    disk_pressure: Option<Arc<DiskPressureMonitor>>,
+
+    /// Source of the daemon information for the capability report
+    docker_service: Option<Arc<DockerService>>,
+
+    /// Source of the clock offset for the capability report
+    clock: Option<Arc<DriftMonitor>>,
+
+    /// Source of the capability profile sent after `hello`
+    capability_profiler: Option<Arc<CapabilityProfiler>>,
+
+    /// Current connection state
+    state: RwLock<BackendConnectionState>,
+
+    /// Broadcasts received commands to subsystems
+    commands: broadcast::Sender<BackendCommand>,
+
+    /// Queues command acknowledgements for the connection
+    acks: mpsc::UnboundedSender<AgentMessage>,
+
+    /// Acknowledgements waiting to be sent, kept across reconnects
+    pending_acks: Mutex<mpsc::UnboundedReceiver<AgentMessage>>,
+
+    /// Counts reconnect requests; the connection loop reconnects when it
+    /// changes after a connection attempt started
+    reconnect_requested: watch::Sender<u64>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: Arc<CancellationToken>,
+}
+
+impl BackendClient {
+    /// Creates a backend client; `settings` of `None` disables the connection.
+    pub fn new(
+        settings: Option<BackendSettings>,
+        docker_monitor: Arc<DockerMonitor>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        let state = if settings.is_some() {
+            BackendConnectionState::Disconnected
+        } else {
+            BackendConnectionState::Disabled
+        };
+        let (acks, pending_acks) = mpsc::unbounded_channel();
+        Self {
+            settings,
+            docker_monitor,
+            disk_pressure: None,
+            docker_service: None,
+            clock: None,
+            capability_profiler: None,
+            state: RwLock::new(state),
+            commands: broadcast::channel(COMMAND_CHANNEL_CAPACITY).0,
+            acks,
+            pending_acks: Mutex::new(pending_acks),
+            reconnect_requested: watch::Sender::new(0),
+            cancellation_token: Arc::new(cancellation_token),
+        }
+    }
+
+    /// Reports disk pressure in the capabilities, marking the agent degraded
+    /// while a watched partition is low on space.
+    pub fn with_disk_pressure(self, disk_pressure: Arc<DiskPressureMonitor>) -> Self {
+        Self {
+            disk_pressure: Some(disk_pressure),
+            ..self
+        }
+    }
+
+    /// Reports whether Docker runs rootless and can run GPU containers in the
+    /// capabilities, from the cached `docker info`.
+    pub fn with_docker_service(self, docker_service: Arc<DockerService>) -> Self {
+        Self {
+            docker_service: Some(docker_service),
+            ..self
+        }
+    }
+
+    /// Reports the system clock offset in the capabilities, marking the agent
+    /// degraded while the clock is far off.
+    pub fn with_clock_monitor(self, clock: Arc<DriftMonitor>) -> Self {
+        Self {
+            clock: Some(clock),
+            ..self
+        }
+    }
+
+    /// Sends the capability profile after `hello` and whenever it changes.
+    pub fn with_capability_profiler(self, capability_profiler: Arc<CapabilityProfiler>) -> Self {
+        Self {
+            capability_profiler: Some(capability_profiler),
+            ..self
+        }
+    }
+
+    /// Gets the current connection state.
+    pub async fn connection_state(&self) -> BackendConnectionState {
+        *self.state.read().await
+    }
+
+    /// Replaces the connection now, skipping a pending backoff.
+    ///
+    /// A connection opened over a network interface that went away can look
+    /// alive until the next heartbeat write fails.
+    /// Requests made before the current connection attempt started are
+    /// already served by it and do not replace it again.
+    pub fn reconnect(&self) {
+        self.reconnect_requested.send_modify(|requests| *requests += 1);
+    }
+
+    /// Subscribes to commands received from the backend.
+    ///
+    /// The subscriber handling a command must report its outcome with
+    /// [`acknowledge`](Self::acknowledge).
+    pub fn subscribe_commands(&self) -> broadcast::Receiver<BackendCommand> {
+        self.commands.subscribe()
+    }
+
+    /// Acknowledges command `command_id` as handled, or as rejected with the
+    /// error of `result`.
+    ///
+    /// Sent over the current connection, or the next one while disconnected.
+    pub fn acknowledge(&self, command_id: &str, result: Result<(), String>) {
+        let ack = AgentMessage::CommandAck {
+            command_id: command_id.to_string(),
+            accepted: result.is_ok(),
+            error: result.err(),
+        };
+        // The receiver lives as long as the client
+        let _ = self.acks.send(ack);
+    }
+
+    /// Backend commands buffered for the slowest subscriber.
+    pub fn queued_commands(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Updates the connection state and notifies the frontend on change.
+    async fn set_state(&self, app_handle: &tauri::AppHandle, url: &str, state: BackendConnectionState) {
+        let mut guard = self.state.write().await;
+        if *guard == state {
+            return;
+        }
+        *guard = state;
+        info!("Backend connection {:?}", state);
+
+        let payload = BackendConnectionChanged {
+            state,
+            url: url.to_string(),
+        };
+        if let Err(e) = telemetry::emit(app_handle, "backend-connection-changed", &payload) {
+            error!("Failed to emit backend-connection-changed event: {e}");
+        }
+    }
+
+    /// Builds the current capability report.
+    async fn capabilities(&self, docker: &DockerStatus, gpu: &GpuInfo) -> Capabilities {
+        let gpu_runtime = match &self.docker_service {
+            Some(docker_service) => GpuRuntime::detect(gpu, docker_service).await,
+            None => GpuRuntime::unavailable("No Docker service to check GPU runtimes with"),
+        };
+        let mut capabilities = Capabilities::detect(docker, gpu, gpu_runtime);
+        if let Some(info) = self.docker_service.as_ref().and_then(|docker_service| docker_service.cached_info()) {
+            capabilities = capabilities.with_docker_info(&info);
+        }
+        if let Some(clock) = &self.clock {
+            capabilities = capabilities.with_clock_offset(clock.offset());
+        }
+        match &self.disk_pressure {
+            Some(disk_pressure) => capabilities.with_disk_pressure(disk_pressure.degraded()),
+            None => capabilities,
+        }
+    }
+
+    /// Builds a heartbeat from the current Docker status.
+    async fn heartbeat(&self, gpu: &GpuInfo) -> AgentMessage {
+        let docker = self.docker_monitor.get_current_status().await;
+        let capabilities = self.capabilities(&docker, gpu).await;
+        AgentMessage::Heartbeat {
+            docker,
+            capabilities,
+            sent_at: Utc::now(),
+        }
+    }
+
+    /// Starts maintaining the backend connection.
+    ///
+    /// Does nothing if no backend is configured.
+    pub async fn start(self: Arc<Self>, app_handle: tauri::AppHandle) {
+        let Some(settings) = self.settings.clone() else {
+            info!("No RedSys backend configured, backend connection disabled");
+            return;
+        };
+        if let Err(e) = check_url(&settings.url) {
+            error!("Backend connection disabled: {}", e);
+            return;
+        }
+        let cancellation_token = self.cancellation_token.clone();
+        let mut pending_acks = self.pending_acks.lock().await;
+        let mut reconnects = self.reconnect_requested.subscribe();
+
+        let mut backoff = BACKEND_INITIAL_BACKOFF;
+        let gpu = task::spawn_blocking(gpu::detect).await.unwrap_or_else(|_| GpuInfo::none());
+        info!("Detected {} GPU(s) via {:?}", gpu.devices.len(), gpu.source);
+
+        'connect: loop {
+            reconnects.mark_unchanged();
+            self.set_state(&app_handle, &settings.url, BackendConnectionState::Connecting).await;
+
+            let connected = tokio::select! {
+                result = tokio::time::timeout(CONNECT_TIMEOUT, tokio_tungstenite::connect_async(settings.url.as_str())) => result,
+                _ = cancellation_token.cancelled() => break 'connect,
+            };
+            match connected {
+                Ok(Ok((socket, _))) => {
+                    let (mut sink, mut stream) = socket.split();
+
+                    let docker = self.docker_monitor.get_current_status().await;
+                    let hello = AgentMessage::Hello {
+                        agent_id: settings.agent_id.clone(),
+                        token: settings.token.clone(),
+                        capabilities: self.capabilities(&docker, &gpu).await,
+                    };
+                    if let Err(e) = send(&mut sink, &hello).await {
+                        warn!("Failed to identify with RedSys backend: {e}");
+                    } else {
+                        self.set_state(&app_handle, &settings.url, BackendConnectionState::Connected).await;
+                        backoff = BACKEND_INITIAL_BACKOFF;
+
+                        let mut heartbeat = interval(settings.heartbeat_interval);
+                        let mut docker_status = self.docker_monitor.subscribe_status();
+                        let mut profile_updates = match &self.capability_profiler {
+                            Some(profiler) => {
+                                let mut updates = profiler.subscribe();
+                                let profile = AgentMessage::CapabilityProfile { profile: profiler.profile().await };
+                                updates.mark_unchanged();
+                                if let Err(e) = send(&mut sink, &profile).await {
+                                    warn!("Failed to send capability profile to RedSys backend: {e}");
+                                }
+                                Some(updates)
+                            }
+                            None => None,
+                        };
+                        loop {
+                            tokio::select! {
+                                Ok(()) = docker_status.changed() => {
+                                    // Report status changes now rather than at the next tick
+                                    heartbeat.reset_immediately();
+                                }
+                                _ = heartbeat.tick() => {
+                                    if let Err(e) = send(&mut sink, &self.heartbeat(&gpu).await).await {
+                                        warn!("Failed to send heartbeat to RedSys backend: {e}");
+                                        break;
+                                    }
+                                }
+                                Some(ack) = pending_acks.recv() => {
+                                    if let Err(e) = send(&mut sink, &ack).await {
+                                        warn!("Failed to acknowledge backend command: {e}");
+                                        break;
+                                    }
+                                }
+                                Some(profile) = next_profile(&mut profile_updates) => {
+                                    if let Err(e) = send(&mut sink, &AgentMessage::CapabilityProfile { profile }).await {
+                                        warn!("Failed to send capability profile to RedSys backend: {e}");
+                                        break;
+                                    }
+                                }
+                                message = stream.next() => match message {
+                                    Some(Ok(Message::Text(text))) => {
+                                        let ack = info_span!("backend_command").in_scope(|| self.handle_text(&app_handle, &text));
+                                        if let Some(ack) = ack {
+                                            if let Err(e) = send(&mut sink, &ack).await {
+                                                warn!("Failed to acknowledge backend command: {e}");
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    Some(Ok(Message::Close(frame))) => {
+                                        info!("RedSys backend closed the connection: {:?}", frame);
+                                        break;
+                                    }
+                                    Some(Ok(_)) => {}
+                                    Some(Err(e)) => {
+                                        warn!("RedSys backend connection error: {e}");
+                                        break;
+                                    }
+                                    None => {
+                                        info!("RedSys backend connection ended");
+                                        break;
+                                    }
+                                },
+                                Ok(()) = reconnects.changed() => {
+                                    info!("RedSys backend reconnect requested");
+                                    let _ = sink.send(Message::Close(None)).await;
+                                    backoff = BACKEND_INITIAL_BACKOFF;
+                                    continue 'connect;
+                                }
+                                _ = cancellation_token.cancelled() => {
+                                    let _ = sink.send(Message::Close(None)).await;
+                                    break 'connect;
+                                }
+                            }
+                        }
+                    }
+                }
+                Ok(Err(e)) => debug!("Cannot connect to RedSys backend: {e}"),
+                Err(_) => debug!("Connection to RedSys backend timed out"),
+            }
+
+            self.set_state(&app_handle, &settings.url, BackendConnectionState::Disconnected).await;
+            info!("Reconnecting to RedSys backend in {}s", backoff.as_secs());
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                Ok(()) = reconnects.changed() => {
+                    info!("RedSys backend reconnect requested");
+                    backoff = BACKEND_INITIAL_BACKOFF;
+                    continue 'connect;
+                }
+                _ = cancellation_token.cancelled() => break 'connect,
+            }
+            backoff = next_backoff(backoff, BACKEND_MAX_BACKOFF);
+        }
+
+        info!("Backend connection received cancellation signal, shutting down");
+    }
+
+    /// Handles a text frame, returning the acknowledgement to send right
+    /// away.
+    ///
+    /// Commands are acknowledged by their subscriber once handled, or right
+    /// away as rejected when nothing subscribes to them.
+    fn handle_text(&self, app_handle: &tauri::AppHandle, text: &str) -> Option<AgentMessage> {
+        match parse_incoming(text) {
+            Incoming::Command(command) => {
+                info!("Received backend command {}", command.command_id());
+                if let Err(e) = telemetry::emit(app_handle, "backend-command", &command) {
+                    error!("Failed to emit backend-command event: {e}");
+                }
+                let command_id = command.command_id().to_string();
+                if self.commands.send(command).is_err() {
+                    warn!("No subsystem handles backend command {}", command_id);
+                    return Some(AgentMessage::CommandAck {
+                        command_id,
+                        accepted: false,
+                        error: Some("The agent does not run jobs".to_string()),
+                    });
+                }
+                None
+            }
+            Incoming::Rejected { command_id, error } => {
+                warn!("Rejected backend message: {}", error);
+                command_id.map(|command_id| AgentMessage::CommandAck {
+                    command_id,
+                    accepted: false,
+                    error: Some(error),
+                })
+            }
+        }
+    }
+}
+
+/// Waits for the next capability profile revision; `None` without a profiler.
+async fn next_profile(updates: &mut Option<watch::Receiver<Option<CapabilityProfile>>>) -> Option<CapabilityProfile> {
+    let updates = updates.as_mut()?;
+    updates.changed().await.ok()?;
+    updates.borrow_and_update().clone()
+}
+
+/// Serializes and sends a message.
+async fn send<S>(sink: &mut S, message: &AgentMessage) -> Result<(), String>
+where
+    S: futures::Sink<Message> + Unpin,
+    S::Error: std::fmt::Display,
+{
+    let text = serde_json::to_string(message).map_err(|e| e.to_string())?;
+    sink.send(Message::Text(text)).await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::EngineDetails;
+
+    #[test]
+    fn test_parse_job_assignment() {
+        let text = r#"{"type":"job_assignment","command_id":"c-1","job":{"image":"alpine"}}"#;
+        let Incoming::Command(command) = parse_incoming(text) else {
+            panic!("expected a command");
+        };
+        assert_eq!(command.command_id(), "c-1");
+    }
+
+    #[test]
+    fn test_parse_job_cancellation() {
+        let text = r#"{"type":"job_cancellation","command_id":"c-2","job_id":"render-1"}"#;
+        let Incoming::Command(command) = parse_incoming(text) else {
+            panic!("expected a command");
+        };
+        assert_eq!(
+            command,
+            BackendCommand::JobCancellation { command_id: "c-2".to_string(), job_id: "render-1".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_settings_fall_back_to_provider_identity() {
+        let mut config = BackendConfig {
+            url: Some("wss://platform".to_string()),
+            ..BackendConfig::default()
+        };
+        let identity = ProviderIdentity {
+            provider_id: "p-7".to_string(),
+            agent_id: "agent-42".to_string(),
+            token: "secret".to_string(),
+            registered_at: Utc::now(),
+        };
+        assert!(BackendSettings::from_config(&config, None).is_none());
+        assert_eq!(BackendSettings::from_config(&config, Some(&identity)).unwrap().agent_id, "agent-42");
+
+        config.agent_id = Some("agent-1".to_string());
+        config.token = Some("configured".to_string());
+        assert_eq!(BackendSettings::from_config(&config, Some(&identity)).unwrap().token, "configured");
+    }
+
+    #[test]
+    fn test_unknown_command_rejected_with_id() {
+        let incoming = parse_incoming(r#"{"type":"self_destruct","command_id":"c-2"}"#);
+        assert!(matches!(incoming, Incoming::Rejected { command_id: Some(ref id), .. } if id == "c-2"));
+
+        let incoming = parse_incoming("not json");
+        assert!(matches!(incoming, Incoming::Rejected { command_id: None, .. }));
+    }
+
+    #[test]
+    fn test_agent_message_serialization() {
+        let ack = AgentMessage::CommandAck {
+            command_id: "c-1".to_string(),
+            accepted: true,
+            error: None,
+        };
+        let json = serde_json::to_value(&ack).unwrap();
+        assert_eq!(json["type"], "command_ack");
+        assert_eq!(json["command_id"], "c-1");
+
+        let capabilities = Capabilities::detect(&DockerStatus::Running { version: "27.0.1".to_string(), engine: EngineDetails::default() }, &GpuInfo::none(), GpuRuntime::unavailable("No NVIDIA GPUs detected"));
+        assert_eq!(capabilities.docker_version.as_deref(), Some("27.0.1"));
+        assert!(capabilities.cpus >= 1);
+        assert!(!capabilities.degraded);
+        assert!(!capabilities.rootless);
+
+        let info: bollard::models::SystemInfo =
+            serde_json::from_value(serde_json::json!({ "SecurityOptions": ["name=seccomp,profile=builtin", "name=rootless"] })).unwrap();
+        let capabilities = capabilities.with_docker_info(&DockerInfo::from_response(&info, None));
+        assert!(capabilities.rootless);
+
+        let pressure = DiskPressure {
+            path: "/var/lib/docker".into(),
+            mount_point: "/".into(),
+            level: crate::disk_pressure::DiskPressureLevel::Warning,
+            free_percent: 10.0,
+            available_bytes: 10,
+            total_bytes: 100,
+        };
+        let capabilities = capabilities.with_disk_pressure(vec![pressure]);
+        assert!(capabilities.degraded);
+        assert_eq!(serde_json::to_value(&capabilities).unwrap()["disk_pressure"][0]["level"], "warning");
+    }
+
+    #[test]
+    fn test_clock_offset_capability() {
+        let capabilities = Capabilities::detect(&DockerStatus::Paused, &GpuInfo::none(), GpuRuntime::unavailable("No NVIDIA GPUs detected"));
+        assert_eq!(capabilities.clock_offset_ms, None);
+
+        let capabilities = capabilities.with_clock_offset(Some(TimeDelta::milliseconds(-2_500)));
+        assert_eq!(capabilities.clock_offset_ms, Some(-2_500));
+        assert!(!capabilities.degraded);
+
+        let capabilities = capabilities.with_clock_offset(Some(TimeDelta::minutes(-3)));
+        assert!(capabilities.degraded);
+    }
+
+    #[test]
+    fn test_check_url_requires_tls_off_loopback() {
+        assert!(check_url("wss://platform.redsys.tn/agent").is_ok());
+        assert!(check_url("ws://localhost:8080/agent").is_ok());
+        assert!(check_url("ws://127.0.0.1:8080").is_ok());
+        assert!(check_url("ws://[::1]:8080").is_ok());
+        assert!(check_url("ws://platform.redsys.tn/agent").is_err());
+        assert!(check_url("ws://192.168.1.10:8080").is_err());
+        assert!(check_url("https://platform.redsys.tn").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_acknowledge_queues_outcome() {
+        let client = BackendClient::new(None, Arc::new(DockerMonitor::new(CancellationToken::new())), CancellationToken::new());
+        client.acknowledge("c-1", Ok(()));
+        client.acknowledge("c-2", Err("Invalid job spec".to_string()));
+
+        let mut pending = client.pending_acks.lock().await;
+        let acks: Vec<String> = [pending.try_recv().unwrap(), pending.try_recv().unwrap()]
+            .iter()
+            .map(|ack| serde_json::to_string(ack).unwrap())
+            .collect();
+        assert_eq!(acks[0], r#"{"type":"command_ack","command_id":"c-1","accepted":true,"error":null}"#);
+        assert_eq!(acks[1], r#"{"type":"command_ack","command_id":"c-2","accepted":false,"error":"Invalid job spec"}"#);
+    }
+}
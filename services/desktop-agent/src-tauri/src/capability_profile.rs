@@ -0,0 +1,413 @@
+//! Capability profile for RedSys Desktop Agent
+//!
+//! The RedSys backend schedules jobs on providers whose host fits them.
+//! [`CapabilityProfile`] gathers what it matches on in one versioned
+//! document: CPU, memory, free disk space, GPUs and whether Docker can use
+//! them, the Docker version and the features enabled in the agent. The
+//! heartbeat [`Capabilities`](crate::backend::Capabilities) stay the liveness
+//! report; the profile is what scheduling works from.
+//!
+//! ## Caching
+//! Building a profile probes the GPU driver and the daemon, so
+//! [`CapabilityProfiler`] keeps the latest one and rebuilds it:
+//! - when the Docker status or the cached `docker info` changes
+//! - every [`PROFILE_REFRESH_INTERVAL`], which picks up hardware changes
+//!   such as an attached external GPU
+//! - when the [network probe](crate::network_probe) has new results
+//! - on request, with `get_capability_profile` and `refresh`
+//!
+//! `revision` increases whenever a rebuild changes the content, so consumers
+//! can skip profiles they already have. Free disk space is rounded down to
+//! whole GiB so that ordinary disk churn is not a change. [`PROFILE_VERSION`]
+//! is bumped when the document changes incompatibly.
+//!
+//! The backend connection sends the profile after `hello` and on every new
+//! revision, see [`backend`](crate::backend).
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{watch, Notify};
+use tokio::task;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+
+use crate::config::FeatureToggles;
+use crate::disk_pressure;
+use crate::docker::{DockerFlavor, DockerService};
+use crate::docker_monitor::DockerStatus;
+use crate::gpu::{self, GpuInfo, GpuRuntime};
+use crate::network_probe::{NetworkProbe, ProbeReport, ProbeResult};
+
+/// Version of the profile schema.
+pub const PROFILE_VERSION: u32 = 1;
+
+/// Interval between scheduled profile rebuilds.
+pub const PROFILE_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Granularity of the reported free disk space.
+const DISK_FREE_GRANULARITY: u64 = 1024 * 1024 * 1024;
+
+/// Cargo features that change what the agent can do, with whether this
+/// build has them.
+const COMPILED_FEATURES: [(&str, bool); 3] = [
+    ("grpc", cfg!(feature = "grpc")),
+    ("mqtt", cfg!(feature = "mqtt")),
+    ("otel", cfg!(feature = "otel")),
+];
+
+/// CPU of the host.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CpuProfile {
+    /// CPU brand string, e.g. `AMD Ryzen 9 7950X 16-Core Processor`
+    pub brand: String,
+
+    /// Logical CPUs available to the agent
+    pub logical_cores: usize,
+
+    /// Physical cores, if the OS reports them
+    pub physical_cores: Option<usize>,
+}
+
+/// Host capabilities the backend matches jobs against.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CapabilityProfile {
+    /// Schema version, [`PROFILE_VERSION`]
+    pub version: u32,
+
+    /// Increases whenever the content changes
+    pub revision: u64,
+
+    /// When the profile was last rebuilt
+    pub generated_at: DateTime<Utc>,
+
+    /// Agent version
+    pub agent_version: String,
+
+    /// Operating system, e.g. `linux`
+    pub os: String,
+
+    /// CPU architecture, e.g. `x86_64`
+    pub arch: String,
+
+    /// CPU
+    pub cpu: CpuProfile,
+
+    /// Installed memory
+    pub memory_total_bytes: u64,
+
+    /// Free space, rounded down to whole GiB, on the partition holding the
+    /// Docker data root, or the home directory when the daemon runs in a VM
+    pub disk_free_bytes: Option<u64>,
+
+    /// Docker Engine version, if the daemon is running
+    pub docker_version: Option<String>,
+
+    /// How Docker is installed, once `docker info` was read
+    pub docker_flavor: Option<DockerFlavor>,
+
+    /// GPUs and their driver and compute stack versions
+    pub gpu: GpuInfo,
+
+    /// Whether Docker can run GPU containers
+    pub gpu_runtime: GpuRuntime,
+
+    /// Enabled `[features]` toggles and compiled optional features
+    pub features: Vec<String>,
+
+    /// Latest latency and throughput per network probe target
+    pub network: Vec<ProbeResult>,
+}
+
+impl CapabilityProfile {
+    /// Whether `other` has the same content, ignoring revision and time.
+    pub fn same_content(&self, other: &CapabilityProfile) -> bool {
+        let other = CapabilityProfile {
+            revision: self.revision,
+            generated_at: self.generated_at,
+            ..other.clone()
+        };
+        *self == other
+    }
+}
+
+/// Names of the enabled `[features]` toggles and compiled features.
+fn enabled_features(toggles: &FeatureToggles) -> Vec<String> {
+    let mut features: Vec<String> = match serde_json::to_value(toggles) {
+        Ok(serde_json::Value::Object(toggles)) => toggles
+            .into_iter()
+            .filter(|(_, enabled)| enabled.as_bool() == Some(true))
+            .map(|(name, _)| name)
+            .collect(),
+        _ => Vec::new(),
+    };
+    features.extend(COMPILED_FEATURES.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| name.to_string()));
+    features.sort();
+    features
+}
+
+/// Reads the CPU and memory of the host. Blocking.
+fn detect_hardware() -> (CpuProfile, u64) {
+    let system = System::new_with_specifics(
+        RefreshKind::new()
+            .with_cpu(CpuRefreshKind::new())
+            .with_memory(MemoryRefreshKind::new().with_ram()),
+    );
+    let cpu = CpuProfile {
+        brand: system.cpus().first().map(|cpu| cpu.brand().trim().to_string()).unwrap_or_default(),
+        logical_cores: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        physical_cores: system.physical_core_count(),
+    };
+    (cpu, system.total_memory())
+}
+
+/// Builds, caches and refreshes the capability profile.
+#[derive(Debug)]
+pub struct CapabilityProfiler {
+    /// Source of the Docker version
+    docker_status: watch::Receiver<DockerStatus>,
+
+    /// Source of the cached `docker info`
+    docker_service: Arc<DockerService>,
+
+    /// Enabled `[features]` toggles
+    features: FeatureToggles,
+
+    /// Source of the network measurements
+    network_probe: Option<Arc<NetworkProbe>>,
+
+    /// Latest profile, `None` until the first build
+    profile: watch::Sender<Option<CapabilityProfile>>,
+
+    /// Signals the refresh loop to rebuild now
+    refresh_requested: Notify,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: Arc<CancellationToken>,
+}
+
+impl CapabilityProfiler {
+    /// Creates a profiler without a profile yet.
+    pub fn new(
+        docker_status: watch::Receiver<DockerStatus>,
+        docker_service: Arc<DockerService>,
+        features: FeatureToggles,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Self {
+            docker_status,
+            docker_service,
+            features,
+            network_probe: None,
+            profile: watch::Sender::new(None),
+            refresh_requested: Notify::new(),
+            cancellation_token: Arc::new(cancellation_token),
+        }
+    }
+
+    /// Includes the latest network probe results, rebuilding the profile
+    /// whenever there are new ones.
+    pub fn with_network_probe(self, network_probe: Arc<NetworkProbe>) -> Self {
+        Self {
+            network_probe: Some(network_probe),
+            ..self
+        }
+    }
+
+    /// Gets the cached profile, `None` until the first build.
+    pub fn cached(&self) -> Option<CapabilityProfile> {
+        self.profile.borrow().clone()
+    }
+
+    /// Subscribes to new profile revisions.
+    pub fn subscribe(&self) -> watch::Receiver<Option<CapabilityProfile>> {
+        self.profile.subscribe()
+    }
+
+    /// Gets the cached profile, building it if there is none yet.
+    pub async fn profile(&self) -> CapabilityProfile {
+        match self.cached() {
+            Some(profile) => profile,
+            None => self.refresh().await,
+        }
+    }
+
+    /// Rebuilds the profile now and caches it.
+    pub async fn refresh(&self) -> CapabilityProfile {
+        let profile = self.build().await;
+        self.publish(profile)
+    }
+
+    /// Asks the refresh loop to rebuild the profile. The resume watcher calls
+    /// this after the host resumed from sleep.
+    pub fn request_refresh(&self) {
+        self.refresh_requested.notify_one();
+    }
+
+    /// Caches `profile`, as a new revision if its content changed.
+    fn publish(&self, mut profile: CapabilityProfile) -> CapabilityProfile {
+        self.profile.send_if_modified(|current| match current {
+            Some(current) if current.same_content(&profile) => {
+                current.generated_at = profile.generated_at;
+                profile.revision = current.revision;
+                false
+            }
+            _ => {
+                profile.revision = current.as_ref().map_or(1, |current| current.revision + 1);
+                info!("Capability profile revision {}", profile.revision);
+                *current = Some(profile.clone());
+                true
+            }
+        });
+        profile
+    }
+
+    /// Detects the current capabilities of the host.
+    async fn build(&self) -> CapabilityProfile {
+        let gpu = task::spawn_blocking(gpu::detect).await.unwrap_or_else(|_| GpuInfo::none());
+        let gpu_runtime = GpuRuntime::detect(&gpu, &self.docker_service).await;
+        let docker_version = match &*self.docker_status.borrow() {
+            DockerStatus::Running { version, .. } => Some(version.clone()),
+            _ => None,
+        };
+        let info = self.docker_service.cached_info();
+        // Docker Desktop reports a data root inside its VM, whose disk image
+        // lives in the user's home directory
+        let data_root = info
+            .as_ref()
+            .and_then(|info| info.data_root.as_deref())
+            .map(PathBuf::from)
+            .filter(|path| path.exists())
+            .or_else(dirs::home_dir);
+
+        let ((cpu, memory_total_bytes), disk_free_bytes) = task::spawn_blocking(move || {
+            let disk_free = data_root.and_then(|path| disk_pressure::available_space(&path));
+            (detect_hardware(), disk_free)
+        })
+        .await
+        .unwrap_or_else(|_| {
+            let cpu = CpuProfile { brand: String::new(), logical_cores: 1, physical_cores: None };
+            ((cpu, 0), None)
+        });
+
+        CapabilityProfile {
+            version: PROFILE_VERSION,
+            revision: 0,
+            generated_at: Utc::now(),
+            agent_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpu,
+            memory_total_bytes,
+            disk_free_bytes: disk_free_bytes.map(|free| free / DISK_FREE_GRANULARITY * DISK_FREE_GRANULARITY),
+            docker_version,
+            docker_flavor: info.map(|info| info.flavor),
+            gpu,
+            gpu_runtime,
+            features: enabled_features(&self.features),
+            network: self
+                .network_probe
+                .as_ref()
+                .and_then(|network_probe| network_probe.latest())
+                .map(|report| report.results)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Keeps the profile current until cancelled.
+    pub async fn start(self: Arc<Self>) {
+        let mut docker_status = self.docker_status.clone();
+        let mut docker_info = self.docker_service.subscribe_info();
+        let mut probe_reports = self.network_probe.as_ref().map(|network_probe| network_probe.subscribe());
+        let mut ticker = interval(PROFILE_REFRESH_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                Ok(()) = docker_status.changed() => debug!("Docker status changed, rebuilding capability profile"),
+                Ok(()) = docker_info.changed() => debug!("Docker info changed, rebuilding capability profile"),
+                Some(()) = changed(&mut probe_reports) => debug!("New network probe results, rebuilding capability profile"),
+                _ = self.refresh_requested.notified() => {}
+                _ = self.cancellation_token.cancelled() => break,
+            }
+            self.refresh().await;
+        }
+
+        info!("Capability profiler received cancellation signal, shutting down");
+    }
+}
+
+/// Waits for a new network probe report; `None` without a probe.
+async fn changed(reports: &mut Option<watch::Receiver<Option<ProbeReport>>>) -> Option<()> {
+    reports.as_mut()?.changed().await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiler() -> CapabilityProfiler {
+        CapabilityProfiler::new(
+            watch::channel(DockerStatus::Paused).1,
+            Arc::new(DockerService::new(CancellationToken::new())),
+            FeatureToggles::default(),
+            CancellationToken::new(),
+        )
+    }
+
+    /// A profile as `build` would make it, without detecting anything.
+    fn sample_profile() -> CapabilityProfile {
+        CapabilityProfile {
+            version: PROFILE_VERSION,
+            revision: 0,
+            generated_at: Utc::now(),
+            agent_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            cpu: CpuProfile { brand: "Test CPU".to_string(), logical_cores: 8, physical_cores: Some(4) },
+            memory_total_bytes: 16 * 1024 * 1024 * 1024,
+            disk_free_bytes: Some(100 * DISK_FREE_GRANULARITY),
+            docker_version: None,
+            docker_flavor: None,
+            gpu: GpuInfo::none(),
+            gpu_runtime: GpuRuntime::unavailable("No GPU"),
+            features: enabled_features(&FeatureToggles::default()),
+            network: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_revision_changes_with_content() {
+        let profiler = profiler();
+        assert!(profiler.cached().is_none());
+
+        let profile = profiler.publish(sample_profile());
+        assert_eq!(profile.revision, 1);
+
+        let unchanged = profiler.publish(CapabilityProfile { generated_at: Utc::now(), ..sample_profile() });
+        assert_eq!(unchanged.revision, 1);
+
+        let changed = profiler.publish(CapabilityProfile {
+            docker_version: Some("27.3.1".to_string()),
+            ..sample_profile()
+        });
+        assert_eq!(changed.revision, 2);
+        assert_eq!(profiler.cached().unwrap().docker_version.as_deref(), Some("27.3.1"));
+    }
+
+    #[test]
+    fn test_enabled_features() {
+        let toggles = FeatureToggles {
+            prune_scheduler: false,
+            ..FeatureToggles::default()
+        };
+        let features = enabled_features(&toggles);
+        assert!(features.contains(&"orphan_gc".to_string()));
+        assert!(!features.contains(&"prune_scheduler".to_string()));
+        assert!(!features.contains(&"simulation".to_string()));
+    }
+}
@@ -0,0 +1,366 @@
+//! Command-line subcommands for RedSys Desktop Agent
+//!
+//! Operators script checks over SSH without launching the GUI:
+//! - `redsys-agent status [--json]`: Docker daemon status, exits with 1
+//!   unless the daemon is running
+//! - `redsys-agent doctor [--json]`: environment checklist with fix hints,
+//!   exits with 1 if a check fails
+//! - `redsys-agent events [--follow] [--json]`: Docker events of the last
+//!   10 minutes, then new events until interrupted with `--follow`
+//! - `redsys-agent service install|uninstall|status`: manages the
+//!   [background service](crate::service), `status` exits with 1 unless it
+//!   is installed
+//!
+//! The subcommands share the library code of the agent and read the same
+//! configuration file, so the Docker host and event filters match. Logs go to
+//! stderr at `warn` unless `RUST_LOG` says otherwise. Without a subcommand the
+//! binary starts the agent as usual.
+
+use std::process::ExitCode;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+use bollard::query_parameters::EventsOptionsBuilder;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::Serialize;
+
+use crate::config::{AgentConfig, ConfigManager};
+use crate::deep_links;
+use crate::docker::{DockerEvent, DockerService};
+use crate::docker_monitor::{self, DockerMonitor, DockerStatus};
+use crate::doctor::{self, CheckStatus};
+use crate::error::{AppError, AppResult};
+use crate::service;
+
+/// How far back `events` looks.
+const EVENTS_LOOKBACK: Duration = Duration::from_secs(10 * 60);
+
+/// Exit code for invalid arguments.
+const USAGE_EXIT_CODE: u8 = 2;
+
+const USAGE: &str = "Usage: redsys-agent [COMMAND]
+
+Without a command, starts the agent.
+
+Commands:
+  status [--json]             Print the Docker daemon status
+  doctor [--json]             Check the environment the agent needs
+  events [--follow] [--json]  Print Docker events of the last 10 minutes
+  service install|uninstall|status
+                              Manage the background service
+  help                        Print this help";
+
+/// A command-line subcommand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Print the Docker daemon status
+    Status { json: bool },
+
+    /// Check the environment the agent needs
+    Doctor { json: bool },
+
+    /// Print recent Docker events, and new ones with `follow`
+    Events { follow: bool, json: bool },
+
+    /// Manage the background service
+    Service(ServiceAction),
+
+    /// Print usage
+    Help,
+}
+
+/// What `service` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceAction {
+    /// Register and start the service
+    Install,
+
+    /// Stop and remove the service
+    Uninstall,
+
+    /// Report whether the service is registered
+    Status,
+}
+
+impl Command {
+    /// Parses the subcommand from the process arguments after the program
+    /// name.
+    ///
+    /// Returns `None` without a subcommand, including for the agent's own
+    /// flags such as `--autostart` or `--headless`.
+    pub fn parse<I, S>(args: I) -> Option<AppResult<Self>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut args = args.into_iter();
+        let first = args.next()?;
+        let name = first.as_ref();
+        // Flags and deep links the OS launched the agent with
+        if name.starts_with('-') || name.starts_with(&format!("{}:", deep_links::SCHEME)) {
+            return None;
+        }
+        let flags: Vec<S> = args.collect();
+        if name == "service" {
+            let action = match flags.iter().map(|arg| arg.as_ref()).collect::<Vec<_>>().as_slice() {
+                ["install"] => ServiceAction::Install,
+                ["uninstall"] => ServiceAction::Uninstall,
+                ["status"] => ServiceAction::Status,
+                _ => {
+                    return Some(Err(AppError::Configuration(
+                        "'service' expects one of install, uninstall or status".to_string(),
+                    )))
+                }
+            };
+            return Some(Ok(Command::Service(action)));
+        }
+        let has = |flag: &str| flags.iter().any(|arg| arg.as_ref() == flag);
+        let allowed: &[&str] = match name {
+            "status" | "doctor" => &["--json"],
+            "events" => &["--follow", "-f", "--json"],
+            "help" => &[],
+            _ => return Some(Err(AppError::Configuration(format!("Unknown command '{name}'")))),
+        };
+        if let Some(unknown) = flags.iter().find(|arg| !allowed.contains(&arg.as_ref())) {
+            return Some(Err(AppError::Configuration(format!(
+                "Unknown option '{}' for '{name}'",
+                unknown.as_ref()
+            ))));
+        }
+
+        Some(Ok(match name {
+            "status" => Command::Status { json: has("--json") },
+            "doctor" => Command::Doctor { json: has("--json") },
+            "events" => Command::Events {
+                follow: has("--follow") || has("-f"),
+                json: has("--json"),
+            },
+            _ => Command::Help,
+        }))
+    }
+}
+
+/// Runs a subcommand given on the command line, if any.
+///
+/// Returns the exit code, or `None` if the agent should start instead.
+pub fn run_from_args() -> Option<ExitCode> {
+    match Command::parse(std::env::args().skip(1))? {
+        Ok(command) => Some(run(command)),
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            Some(ExitCode::from(USAGE_EXIT_CODE))
+        }
+    }
+}
+
+/// Runs `command` to completion and returns the process exit code.
+pub fn run(command: Command) -> ExitCode {
+    if command == Command::Help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    if let Command::Service(action) = command {
+        return service_command(action).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        });
+    }
+
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .init();
+
+    let (config_manager, config_error) = ConfigManager::load_default();
+    if let Some(e) = &config_error {
+        warn!("Using default configuration, failed to load {}: {}", config_manager.path().display(), e);
+    }
+    let config = config_manager.get();
+    docker_monitor::set_docker_host_override(config.docker.host.clone());
+
+    let runtime = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start the async runtime: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = runtime.block_on(async {
+        match command {
+            Command::Status { json } => status(json).await,
+            Command::Doctor { json } => doctor(&config, json).await,
+            Command::Events { follow, json } => events(&config, follow, json).await,
+            Command::Help | Command::Service(_) => Ok(ExitCode::SUCCESS),
+        }
+    });
+    result.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        ExitCode::FAILURE
+    })
+}
+
+/// Output of `status --json`.
+#[derive(Debug, Serialize)]
+struct StatusReport {
+    /// Docker daemon status, as returned by `get_docker_status`
+    docker: DockerStatus,
+
+    /// When the daemon was checked
+    checked_at: DateTime<Utc>,
+}
+
+/// Checks the daemon once, turning check errors into an error status.
+async fn docker_status() -> DockerStatus {
+    DockerMonitor::check_once()
+        .await
+        .unwrap_or_else(|e| DockerStatus::Error {
+            message: e.to_string(),
+            retry_after: None,
+        })
+}
+
+async fn status(json: bool) -> AppResult<ExitCode> {
+    let report = StatusReport {
+        docker: docker_status().await,
+        checked_at: Utc::now(),
+    };
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+    } else {
+        println!("{}", report.docker);
+    }
+    Ok(exit_code(matches!(report.docker, DockerStatus::Running { .. })))
+}
+
+async fn doctor(config: &AgentConfig, json: bool) -> AppResult<ExitCode> {
+    let report = doctor::run_doctor(config, &Arc::new(DockerService::new(CancellationToken::new()))).await;
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+    } else {
+        for check in &report.checks {
+            let mark = match check.status {
+                CheckStatus::Pass => "ok  ",
+                CheckStatus::Warn => "warn",
+                CheckStatus::Fail => "FAIL",
+                CheckStatus::Skip => "skip",
+            };
+            println!("[{mark}] {}", check.summary);
+            if let Some(hint) = &check.fix_hint {
+                println!("       {hint}");
+            }
+        }
+    }
+    Ok(exit_code(report.passed))
+}
+
+async fn events(config: &AgentConfig, follow: bool, json: bool) -> AppResult<ExitCode> {
+    let filters = config.events.clone();
+    let cancellation_token = CancellationToken::new();
+    let service = Arc::new(DockerService::new(cancellation_token.clone()).with_event_filters(filters.clone()));
+    let client = service.docker().await?;
+    let now = Utc::now();
+    let since = (now.timestamp() - EVENTS_LOOKBACK.as_secs() as i64).to_string();
+    let until = now.timestamp().to_string();
+    let options = EventsOptionsBuilder::new()
+        .since(&since)
+        .until(&until)
+        .filters(&filters.to_docker_filters())
+        .build();
+
+    let mut recent = client.events(Some(options));
+    while let Some(message) = recent.next().await {
+        let event = DockerEvent::from(message.map_err(docker_monitor::DockerMonitorError::from)?);
+        if filters.matches(&event) {
+            print_event(&event, json)?;
+        }
+    }
+    if !follow {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // The events stream reconnects on its own while following
+    let mut events = service.subscribe_events();
+    let stream = tokio::spawn(service.clone().start_docker_events_stream());
+    loop {
+        tokio::select! {
+            received = events.recv() => match received {
+                Ok(event) => print_event(&event, json)?,
+                Err(RecvError::Lagged(missed)) => warn!("{} events dropped", missed),
+                Err(RecvError::Closed) => break,
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    cancellation_token.cancel();
+    let _ = stream.await;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn service_command(action: ServiceAction) -> AppResult<ExitCode> {
+    match action {
+        ServiceAction::Install => {
+            service::install()?;
+            println!("Background service installed and started");
+        }
+        ServiceAction::Uninstall => {
+            service::uninstall()?;
+            println!("Background service removed");
+        }
+        ServiceAction::Status => {
+            let installed = service::is_installed()?;
+            println!("Background service {}", if installed { "installed" } else { "not installed" });
+            return Ok(exit_code(installed));
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_event(event: &DockerEvent, json: bool) -> AppResult<()> {
+    if json {
+        println!("{}", serde_json::to_string(event)?);
+    } else {
+        let time = event.time.map(|time| time.to_rfc3339()).unwrap_or_default();
+        let actor = event
+            .actor_name
+            .clone()
+            .or_else(|| event.actor_id.as_deref().map(|id| id.chars().take(12).collect()))
+            .unwrap_or_default();
+        println!("{time} {} {} {actor}", event.event_type, event.action);
+    }
+    Ok(())
+}
+
+fn exit_code(success: bool) -> ExitCode {
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_subcommands() {
+        assert!(Command::parse(Vec::<String>::new()).is_none());
+        assert!(Command::parse(["--autostart"]).is_none());
+        assert!(Command::parse(["redsys://job/job-1/logs"]).is_none());
+        assert!(matches!(Command::parse(["status", "--json"]), Some(Ok(Command::Status { json: true }))));
+        assert!(matches!(
+            Command::parse(["events", "-f"]),
+            Some(Ok(Command::Events { follow: true, json: false }))
+        ));
+        assert!(matches!(Command::parse(["status", "--follow"]), Some(Err(_))));
+        assert!(matches!(Command::parse(["restart"]), Some(Err(_))));
+        assert!(matches!(Command::parse(["service", "install"]), Some(Ok(Command::Service(ServiceAction::Install)))));
+        assert!(matches!(Command::parse(["service"]), Some(Err(_))));
+    }
+}
@@ -0,0 +1,523 @@
+//! Docker client abstraction for RedSys Desktop Agent
+//!
+//! [`DockerMonitor`](crate::docker_monitor::DockerMonitor) health checks and
+//! [`DockerService`](crate::docker::DockerService) queries call the daemon
+//! through the [`DockerClient`] trait rather than Bollard directly, so their
+//! logic can run against [`MockDockerClient`] without a live daemon.
+//!
+//! ## Implementations
+//! - Bollard's [`Docker`] for real daemons, shared via [`connection`](crate::connection)
+//! - [`MockDockerClient`], an in-memory daemon that can be stopped, started
+//!   and made unresponsive on demand
+//!
+//! Operations outside the trait, such as job execution, image builds and
+//! pruning, use the Bollard client from
+//! [`DockerService::docker`](crate::docker::DockerService::docker), which
+//! refuses them while a mock is configured instead of reaching a real daemon.
+//!
+//! ## References
+//! - [Bollard Docker client](https://docs.rs/bollard/latest/bollard/struct.Docker.html)
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use tokio::sync::{broadcast, watch};
+use tokio::time::Duration;
+use bollard::errors::Error;
+use bollard::models::{
+    ContainerSummary, ContainerSummaryStateEnum, ContainerTopResponse, EventMessage, FilesystemChange,
+    SystemDataUsageResponse, SystemInfo, SystemVersion,
+};
+use bollard::query_parameters::{
+    EventsOptionsBuilder, ListContainersOptionsBuilder, RemoveContainerOptionsBuilder, StartContainerOptions,
+    StopContainerOptionsBuilder, TopOptions,
+};
+use bollard::Docker;
+use futures::future::{self, BoxFuture, FutureExt};
+use futures::stream::{self, BoxStream, StreamExt};
+
+use crate::listing::labels_match;
+
+/// Docker Engine API calls used by the monitor and the Docker service.
+pub trait DockerClient: Debug + Send + Sync {
+    /// Probes liveness via `/_ping`.
+    fn ping(&self) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Gets the daemon version.
+    fn version(&self) -> BoxFuture<'_, Result<SystemVersion, Error>>;
+
+    /// Gets `docker info`.
+    fn info(&self) -> BoxFuture<'_, Result<SystemInfo, Error>>;
+
+    /// Gets the `docker system df` report.
+    fn df(&self) -> BoxFuture<'_, Result<SystemDataUsageResponse, Error>>;
+
+    /// Lists containers matching the daemon-side `filters`, including stopped
+    /// ones if `all` is set.
+    fn list_containers(
+        &self,
+        all: bool,
+        filters: &HashMap<String, Vec<String>>,
+    ) -> BoxFuture<'_, Result<Vec<ContainerSummary>, Error>>;
+
+    /// Starts a stopped container.
+    fn start_container<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Stops a running container, killing it after `timeout`.
+    fn stop_container<'a>(&'a self, id: &'a str, timeout: Duration) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Removes a container; running containers only with `force`.
+    fn remove_container<'a>(&'a self, id: &'a str, force: bool) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Lists the files changed in a container's filesystem, as `docker diff`.
+    fn container_changes<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Vec<FilesystemChange>, Error>>;
+
+    /// Lists the processes running in a container, as `docker top`.
+    fn top_processes<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ContainerTopResponse, Error>>;
+
+    /// Subscribes to daemon events since the given timestamp.
+    fn events(&self, since: &str, filters: &HashMap<String, Vec<String>>) -> BoxStream<'_, Result<EventMessage, Error>>;
+
+    /// The Bollard client behind this one, for calls outside this trait;
+    /// `None` without a real daemon.
+    fn bollard(&self) -> Option<&Docker> {
+        None
+    }
+}
+
+impl DockerClient for Docker {
+    fn ping(&self) -> BoxFuture<'_, Result<(), Error>> {
+        Docker::ping(self).map(|result| result.map(|_| ())).boxed()
+    }
+
+    fn version(&self) -> BoxFuture<'_, Result<SystemVersion, Error>> {
+        Docker::version(self).boxed()
+    }
+
+    fn info(&self) -> BoxFuture<'_, Result<SystemInfo, Error>> {
+        Docker::info(self).boxed()
+    }
+
+    fn df(&self) -> BoxFuture<'_, Result<SystemDataUsageResponse, Error>> {
+        Docker::df(self, None).boxed()
+    }
+
+    fn list_containers(
+        &self,
+        all: bool,
+        filters: &HashMap<String, Vec<String>>,
+    ) -> BoxFuture<'_, Result<Vec<ContainerSummary>, Error>> {
+        let options = ListContainersOptionsBuilder::new().all(all).filters(filters).build();
+        Docker::list_containers(self, Some(options)).boxed()
+    }
+
+    fn start_container<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Docker::start_container(self, id, None::<StartContainerOptions>).boxed()
+    }
+
+    fn stop_container<'a>(&'a self, id: &'a str, timeout: Duration) -> BoxFuture<'a, Result<(), Error>> {
+        let options = StopContainerOptionsBuilder::new()
+            .t(i32::try_from(timeout.as_secs()).unwrap_or(i32::MAX))
+            .build();
+        Docker::stop_container(self, id, Some(options)).boxed()
+    }
+
+    fn remove_container<'a>(&'a self, id: &'a str, force: bool) -> BoxFuture<'a, Result<(), Error>> {
+        let options = RemoveContainerOptionsBuilder::new().force(force).build();
+        Docker::remove_container(self, id, Some(options)).boxed()
+    }
+
+    fn container_changes<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Vec<FilesystemChange>, Error>> {
+        // The daemon answers null for an unchanged filesystem
+        Docker::container_changes(self, id)
+            .map(|result| result.map(Option::unwrap_or_default))
+            .boxed()
+    }
+
+    fn top_processes<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ContainerTopResponse, Error>> {
+        Docker::top_processes(self, id, None::<TopOptions>).boxed()
+    }
+
+    fn events(&self, since: &str, filters: &HashMap<String, Vec<String>>) -> BoxStream<'_, Result<EventMessage, Error>> {
+        let options = EventsOptionsBuilder::new().since(since).filters(filters).build();
+        Docker::events(self, Some(options)).boxed()
+    }
+
+    fn bollard(&self) -> Option<&Docker> {
+        Some(self)
+    }
+}
+
+/// Daemon behavior simulated by [`MockDockerClient`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockDaemon {
+    /// Answers every call
+    Running,
+
+    /// Accepts connections but answers with server errors, like a booting daemon
+    Starting,
+
+    /// Refuses connections
+    Stopped,
+
+    /// Accepts connections but never answers, like a wedged daemon
+    Unresponsive,
+}
+
+/// In-memory daemon state.
+#[derive(Debug)]
+struct MockState {
+    version: String,
+    info: SystemInfo,
+    disk_usage: SystemDataUsageResponse,
+    containers: Vec<ContainerSummary>,
+}
+
+/// In-memory [`DockerClient`] for tests.
+///
+/// Every call fails the way a real daemon in the current [`MockDaemon`]
+/// state would. Container listings apply the `status` and `label` filters and
+/// ignore the others. Event streams ignore `since` and filters and deliver
+/// what is passed to [`emit_event`](Self::emit_event) after they subscribed.
+#[derive(Debug)]
+pub struct MockDockerClient {
+    daemon: watch::Sender<MockDaemon>,
+    state: RwLock<MockState>,
+    events: broadcast::Sender<EventMessage>,
+    version_calls: AtomicUsize,
+}
+
+impl MockDockerClient {
+    /// Creates a running daemon without containers.
+    pub fn new() -> Self {
+        Self {
+            daemon: watch::Sender::new(MockDaemon::Running),
+            state: RwLock::new(MockState {
+                version: "27.3.1".to_string(),
+                info: SystemInfo::default(),
+                disk_usage: SystemDataUsageResponse::default(),
+                containers: Vec::new(),
+            }),
+            events: broadcast::channel(64).0,
+            version_calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sets the daemon version.
+    pub fn with_version(self, version: &str) -> Self {
+        self.state.write().unwrap_or_else(|e| e.into_inner()).version = version.to_string();
+        self
+    }
+
+    /// Sets the `docker info` response.
+    pub fn with_info(self, info: SystemInfo) -> Self {
+        self.state.write().unwrap_or_else(|e| e.into_inner()).info = info;
+        self
+    }
+
+    /// Sets the `docker system df` response.
+    pub fn with_disk_usage(self, disk_usage: SystemDataUsageResponse) -> Self {
+        self.state.write().unwrap_or_else(|e| e.into_inner()).disk_usage = disk_usage;
+        self
+    }
+
+    /// Sets the containers.
+    pub fn with_containers(self, containers: Vec<ContainerSummary>) -> Self {
+        self.state.write().unwrap_or_else(|e| e.into_inner()).containers = containers;
+        self
+    }
+
+    /// Gets the simulated daemon behavior.
+    pub fn daemon(&self) -> MockDaemon {
+        *self.daemon.borrow()
+    }
+
+    /// Changes the simulated daemon behavior.
+    ///
+    /// Open event streams fail when the daemon stops running.
+    pub fn set_daemon(&self, daemon: MockDaemon) {
+        self.daemon.send_replace(daemon);
+    }
+
+    /// Delivers an event to the open event streams.
+    pub fn emit_event(&self, message: EventMessage) {
+        // No open stream is fine
+        let _ = self.events.send(message);
+    }
+
+    /// Number of successful version queries, to check version caching.
+    pub fn version_calls(&self) -> usize {
+        self.version_calls.load(Ordering::Relaxed)
+    }
+
+    /// The error a real daemon in state `daemon` answers with, `None` when it answers normally.
+    fn failure(daemon: MockDaemon) -> Option<Error> {
+        match daemon {
+            MockDaemon::Running | MockDaemon::Unresponsive => None,
+            MockDaemon::Starting => Some(Error::DockerResponseServerError {
+                status_code: 500,
+                message: "Docker daemon is starting".to_string(),
+            }),
+            MockDaemon::Stopped => Some(Error::IOError {
+                err: std::io::Error::from(std::io::ErrorKind::ConnectionRefused),
+            }),
+        }
+    }
+
+    /// Answers a call with `answer` unless the daemon is not running.
+    fn respond<T: Send + 'static>(
+        &self,
+        answer: impl FnOnce(&mut MockState) -> Result<T, Error>,
+    ) -> BoxFuture<'static, Result<T, Error>> {
+        let daemon = self.daemon();
+        if daemon == MockDaemon::Unresponsive {
+            return future::pending().boxed();
+        }
+        let result = match Self::failure(daemon) {
+            Some(error) => Err(error),
+            None => answer(&mut self.state.write().unwrap_or_else(|e| e.into_inner())),
+        };
+        future::ready(result).boxed()
+    }
+
+    /// Finds a container by ID or name, failing like Docker if it does not exist.
+    fn find_container(state: &MockState, id: &str) -> Result<usize, Error> {
+        state
+            .containers
+            .iter()
+            .position(|container| {
+                container.id.as_deref() == Some(id)
+                    || container.names.iter().flatten().any(|name| name.trim_start_matches('/') == id)
+            })
+            .ok_or_else(|| Error::DockerResponseServerError {
+                status_code: 404,
+                message: format!("No such container: {id}"),
+            })
+    }
+
+    /// Moves a container to `state`, failing like Docker if it does not exist.
+    fn set_container_state(state: &mut MockState, id: &str, container_state: ContainerSummaryStateEnum) -> Result<(), Error> {
+        let index = Self::find_container(state, id)?;
+        state.containers[index].state = Some(container_state);
+        Ok(())
+    }
+}
+
+impl Default for MockDockerClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DockerClient for MockDockerClient {
+    fn ping(&self) -> BoxFuture<'_, Result<(), Error>> {
+        self.respond(|_| Ok(()))
+    }
+
+    fn version(&self) -> BoxFuture<'_, Result<SystemVersion, Error>> {
+        self.respond(|state| {
+            self.version_calls.fetch_add(1, Ordering::Relaxed);
+            Ok(SystemVersion {
+                version: Some(state.version.clone()),
+                api_version: Some("1.47".to_string()),
+                os: Some("linux".to_string()),
+                arch: Some("amd64".to_string()),
+                ..Default::default()
+            })
+        })
+    }
+
+    fn info(&self) -> BoxFuture<'_, Result<SystemInfo, Error>> {
+        self.respond(|state| Ok(state.info.clone()))
+    }
+
+    fn df(&self) -> BoxFuture<'_, Result<SystemDataUsageResponse, Error>> {
+        self.respond(|state| Ok(state.disk_usage.clone()))
+    }
+
+    fn list_containers(
+        &self,
+        all: bool,
+        filters: &HashMap<String, Vec<String>>,
+    ) -> BoxFuture<'_, Result<Vec<ContainerSummary>, Error>> {
+        let statuses = filters.get("status").cloned().unwrap_or_default();
+        let selectors = filters.get("label").cloned().unwrap_or_default();
+        self.respond(move |state| {
+            Ok(state
+                .containers
+                .iter()
+                .filter(|container| {
+                    let container_state = container.state.map(|state| state.to_string()).unwrap_or_default();
+                    if statuses.is_empty() {
+                        all || container.state == Some(ContainerSummaryStateEnum::RUNNING)
+                    } else {
+                        statuses.contains(&container_state)
+                    }
+                })
+                .filter(|container| labels_match(container.labels.as_ref().unwrap_or(&HashMap::new()), &selectors))
+                .cloned()
+                .collect())
+        })
+    }
+
+    fn start_container<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.respond(|state| Self::set_container_state(state, id, ContainerSummaryStateEnum::RUNNING))
+    }
+
+    fn stop_container<'a>(&'a self, id: &'a str, _timeout: Duration) -> BoxFuture<'a, Result<(), Error>> {
+        self.respond(|state| Self::set_container_state(state, id, ContainerSummaryStateEnum::EXITED))
+    }
+
+    fn remove_container<'a>(&'a self, id: &'a str, force: bool) -> BoxFuture<'a, Result<(), Error>> {
+        self.respond(|state| {
+            let index = Self::find_container(state, id)?;
+            if !force && state.containers[index].state == Some(ContainerSummaryStateEnum::RUNNING) {
+                return Err(Error::DockerResponseServerError {
+                    status_code: 409,
+                    message: format!("You cannot remove a running container {id}"),
+                });
+            }
+            state.containers.remove(index);
+            Ok(())
+        })
+    }
+
+    fn container_changes<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Vec<FilesystemChange>, Error>> {
+        self.respond(|state| Self::find_container(state, id).map(|_| Vec::new()))
+    }
+
+    fn top_processes<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ContainerTopResponse, Error>> {
+        self.respond(|state| {
+            let container = &state.containers[Self::find_container(state, id)?];
+            if container.state != Some(ContainerSummaryStateEnum::RUNNING) {
+                return Err(Error::DockerResponseServerError {
+                    status_code: 409,
+                    message: format!("Container {id} is not running"),
+                });
+            }
+            // The main process only
+            Ok(ContainerTopResponse {
+                titles: Some(vec!["PID".to_string(), "CMD".to_string()]),
+                processes: Some(vec![vec!["1".to_string(), container.command.clone().unwrap_or_default()]]),
+            })
+        })
+    }
+
+    fn events(&self, _since: &str, _filters: &HashMap<String, Vec<String>>) -> BoxStream<'_, Result<EventMessage, Error>> {
+        let daemon = self.daemon();
+        if daemon == MockDaemon::Unresponsive {
+            return stream::pending().boxed();
+        }
+        if let Some(error) = Self::failure(daemon) {
+            return stream::once(future::ready(Err(error))).boxed();
+        }
+        let streams = (self.events.subscribe(), self.daemon.subscribe());
+        stream::unfold(Some(streams), |streams| async move {
+            let (mut events, mut daemon) = streams?;
+            loop {
+                tokio::select! {
+                    message = events.recv() => match message {
+                        Ok(message) => return Some((Ok(message), Some((events, daemon)))),
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    },
+                    changed = daemon.changed() => {
+                        if changed.is_err() {
+                            return None;
+                        }
+                        if *daemon.borrow_and_update() != MockDaemon::Running {
+                            let error = Error::IOError {
+                                err: std::io::Error::from(std::io::ErrorKind::ConnectionReset),
+                            };
+                            return Some((Err(error), None));
+                        }
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(id: &str, state: ContainerSummaryStateEnum) -> ContainerSummary {
+        ContainerSummary {
+            id: Some(id.to_string()),
+            names: Some(vec![format!("/{id}")]),
+            state: Some(state),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_daemon_failures() {
+        let client = MockDockerClient::new();
+        assert!(client.ping().await.is_ok());
+
+        client.set_daemon(MockDaemon::Starting);
+        assert!(matches!(
+            client.ping().await,
+            Err(Error::DockerResponseServerError { status_code: 500, .. })
+        ));
+
+        client.set_daemon(MockDaemon::Stopped);
+        assert!(matches!(client.version().await, Err(Error::IOError { .. })));
+        assert_eq!(client.version_calls(), 0);
+
+        client.set_daemon(MockDaemon::Unresponsive);
+        assert!(tokio::time::timeout(Duration::from_millis(10), client.ping()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mock_container_lifecycle() {
+        let client = MockDockerClient::new().with_containers(vec![
+            container("web", ContainerSummaryStateEnum::RUNNING),
+            container("worker", ContainerSummaryStateEnum::EXITED),
+        ]);
+        assert_eq!(client.list_containers(false).await.unwrap().len(), 1);
+        assert_eq!(client.list_containers(true).await.unwrap().len(), 2);
+
+        client.start_container("worker").await.unwrap();
+        client.stop_container("web", Duration::from_secs(10)).await.unwrap();
+        let running = client.list_containers(false).await.unwrap();
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].id.as_deref(), Some("worker"));
+
+        assert!(matches!(
+            client.start_container("missing").await,
+            Err(Error::DockerResponseServerError { status_code: 404, .. })
+        ));
+
+        assert!(matches!(
+            client.remove_container("worker", false).await,
+            Err(Error::DockerResponseServerError { status_code: 409, .. })
+        ));
+        client.remove_container("web", false).await.unwrap();
+        client.remove_container("worker", true).await.unwrap();
+        assert!(client.list_containers(true).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_events_stream() {
+        let client = MockDockerClient::new();
+        let mut stream = client.events("0", &HashMap::new());
+        client.emit_event(EventMessage {
+            action: Some("start".to_string()),
+            ..Default::default()
+        });
+        let message = stream.next().await.unwrap().unwrap();
+        assert_eq!(message.action.as_deref(), Some("start"));
+
+        client.set_daemon(MockDaemon::Stopped);
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+
+        let mut stream = client.events("0", &HashMap::new());
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,193 @@
+//! System clock checks for RedSys Desktop Agent
+//!
+//! Job deadlines and signed payloads break silently when the provider clock is
+//! minutes off. [`measure_offset`] compares the system clock with an NTP
+//! server using a single SNTP request.
+//!
+//! [`DriftMonitor`] repeats the measurement every `clock.check_interval_secs`
+//! against `clock.ntp_server`. The latest offset goes into the capability
+//! report, and a drift of [`DRIFT_CRITICAL`] or more marks the agent degraded
+//! so the platform stops scheduling jobs onto it.
+//!
+//! ## References
+//! - [RFC 4330: Simple Network Time Protocol](https://datatracker.ietf.org/doc/html/rfc4330)
+
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::sync::watch;
+use tokio::time::{interval, timeout, Duration, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::config::ClockConfig;
+use crate::error::{AppError, AppResult};
+
+/// NTP server queried when none is configured.
+pub const DEFAULT_NTP_SERVER: &str = "pool.ntp.org:123";
+
+/// Clock drift that may already cause problems.
+pub const DRIFT_WARNING: TimeDelta = TimeDelta::seconds(5);
+
+/// Clock drift that breaks job deadlines and signed payloads.
+pub const DRIFT_CRITICAL: TimeDelta = TimeDelta::seconds(60);
+
+/// Longest wait for the NTP server to answer.
+const NTP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Seconds from the NTP epoch (1900) to the Unix epoch (1970).
+const NTP_UNIX_OFFSET_SECS: i64 = 2_208_988_800;
+
+/// Size of an SNTP packet without extensions.
+const PACKET_LEN: usize = 48;
+
+/// Measures how far the system clock is ahead of `server` (`host:port`).
+///
+/// A negative offset means the system clock is behind.
+pub async fn measure_offset(server: &str) -> AppResult<TimeDelta> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+
+    let mut request = [0u8; PACKET_LEN];
+    // LI = 0, version 4, mode 3 (client)
+    request[0] = 0x23;
+    let sent = Utc::now();
+    socket.send(&request).await?;
+
+    let mut response = [0u8; PACKET_LEN];
+    let received = timeout(NTP_TIMEOUT, socket.recv(&mut response))
+        .await
+        .map_err(|_| AppError::Timeout {
+            operation: format!("query NTP server {server}"),
+        })??;
+    let arrived = Utc::now();
+    if received < PACKET_LEN {
+        return Err(AppError::Network(format!("Short answer from NTP server {server}")));
+    }
+    server_offset(&response, sent, arrived)
+        .map(|offset| -offset)
+        .ok_or_else(|| AppError::Network(format!("Invalid answer from NTP server {server}")))
+}
+
+/// Periodically measures the system clock offset.
+#[derive(Debug)]
+pub struct DriftMonitor {
+    /// NTP server as `host:port`
+    server: String,
+
+    /// Interval between measurements
+    check_interval: Duration,
+
+    /// Latest offset, `None` until a measurement succeeded
+    offset: watch::Sender<Option<TimeDelta>>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl DriftMonitor {
+    /// Creates a monitor from the `[clock]` config section.
+    pub fn new(config: &ClockConfig, cancellation_token: CancellationToken) -> Self {
+        Self {
+            server: config.ntp_server.clone(),
+            check_interval: Duration::from_secs(config.check_interval_secs),
+            offset: watch::Sender::new(None),
+            cancellation_token,
+        }
+    }
+
+    /// Gets the latest measured offset; positive when the system clock is
+    /// ahead.
+    pub fn offset(&self) -> Option<TimeDelta> {
+        *self.offset.borrow()
+    }
+
+    /// Measures the offset now and stores it.
+    ///
+    /// A failed measurement keeps the previous offset.
+    pub async fn check(&self) -> AppResult<TimeDelta> {
+        let offset = measure_offset(&self.server).await?;
+        self.offset.send_replace(Some(offset));
+        Ok(offset)
+    }
+
+    /// Starts periodic measurements, logging drift above [`DRIFT_WARNING`].
+    pub async fn start(self: Arc<Self>) {
+        info!(
+            "Starting clock drift checks against {} every {}s",
+            self.server,
+            self.check_interval.as_secs()
+        );
+        let mut ticker = interval(self.check_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => match self.check().await {
+                    Ok(offset) if offset.abs() >= DRIFT_WARNING => {
+                        warn!("System clock is {}ms off from {}", offset.num_milliseconds(), self.server);
+                    }
+                    Ok(offset) => debug!("System clock offset {}ms", offset.num_milliseconds()),
+                    Err(e) => warn!("Clock drift check failed: {}", e),
+                },
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Clock drift checks received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Offset of the server clock against the local clock, from an SNTP answer
+/// and the local send and arrival times.
+fn server_offset(response: &[u8; PACKET_LEN], sent: DateTime<Utc>, arrived: DateTime<Utc>) -> Option<TimeDelta> {
+    // Mode 4 (server), and a stratum of 0 is a kiss-o'-death
+    if response[0] & 0x07 != 4 || response[1] == 0 {
+        return None;
+    }
+    let server_received = timestamp(&response[32..40])?;
+    let server_sent = timestamp(&response[40..48])?;
+    Some(((server_received - sent) + (server_sent - arrived)) / 2)
+}
+
+/// Decodes a 64-bit NTP timestamp.
+fn timestamp(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let seconds = u32::from_be_bytes(bytes[..4].try_into().ok()?) as i64;
+    let fraction = u32::from_be_bytes(bytes[4..8].try_into().ok()?) as u64;
+    let nanos = (fraction * 1_000_000_000) >> 32;
+    DateTime::from_timestamp(seconds - NTP_UNIX_OFFSET_SECS, nanos as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(time: DateTime<Utc>) -> [u8; 8] {
+        let seconds = (time.timestamp() + NTP_UNIX_OFFSET_SECS) as u32;
+        let fraction = ((time.timestamp_subsec_nanos() as u64) << 32) / 1_000_000_000;
+        let mut bytes = [0u8; 8];
+        bytes[..4].copy_from_slice(&seconds.to_be_bytes());
+        bytes[4..].copy_from_slice(&(fraction as u32).to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_server_offset() {
+        let sent = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let arrived = sent + TimeDelta::milliseconds(100);
+        // Server clock 90s ahead, 50ms each way
+        let server_time = sent + TimeDelta::seconds(90) + TimeDelta::milliseconds(50);
+
+        let mut response = [0u8; PACKET_LEN];
+        response[0] = 0x24;
+        response[1] = 2;
+        response[32..40].copy_from_slice(&encode(server_time));
+        response[40..48].copy_from_slice(&encode(server_time));
+
+        let offset = server_offset(&response, sent, arrived).unwrap();
+        assert!((offset - TimeDelta::seconds(90)).abs() < TimeDelta::milliseconds(1));
+
+        response[1] = 0;
+        assert_eq!(server_offset(&response, sent, arrived), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! Compressed command responses for RedSys Desktop Agent
+//!
+//! Tauri hands command results to the webview as one JSON string, so a
+//! multi-megabyte result, e.g. the image list or job history of a big host,
+//! stalls the IPC bridge while it is copied and parsed. Commands with large
+//! results take an optional `compression` argument naming an encoding the
+//! frontend can decode. A result whose JSON exceeds
+//! [`COMPRESSION_THRESHOLD`] is then sent compressed as raw bytes, which
+//! arrive as an `ArrayBuffer`; smaller results stay plain JSON, as does a
+//! result that does not shrink.
+//!
+//! The encodings are told apart by their magic number: gzip starts with
+//! `1f 8b`, zstd with `28 b5 2f fd`. Browsers decode gzip with
+//! `DecompressionStream`; zstd compresses faster and smaller but needs a
+//! decoder in the frontend.
+//!
+//! ## References
+//! - [Tauri Calling Rust from the Frontend](https://v2.tauri.app/develop/calling-rust/#returning-array-buffers)
+//! - [RFC 1952 gzip](https://datatracker.ietf.org/doc/html/rfc1952)
+//! - [RFC 8878 Zstandard](https://datatracker.ietf.org/doc/html/rfc8878)
+
+use std::io::Write;
+use std::time::Instant;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::{InvokeResponseBody, Response};
+use tracing::debug;
+
+use crate::error::{AppError, AppResult};
+
+/// Size of the JSON above which results are compressed, if asked to.
+pub const COMPRESSION_THRESHOLD: usize = 64 * 1024;
+
+/// zstd level; low levels already shrink JSON well and stay fast.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Encodings a command result can be compressed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Compresses `data`.
+    pub fn compress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Compression::Zstd => zstd::encode_all(data, ZSTD_LEVEL),
+        }
+    }
+}
+
+/// Encodes `json` for the webview, compressed if it is large and shrinks.
+fn encode(json: String, compression: Option<Compression>) -> std::io::Result<InvokeResponseBody> {
+    let Some(compression) = compression.filter(|_| json.len() > COMPRESSION_THRESHOLD) else {
+        return Ok(InvokeResponseBody::Json(json));
+    };
+    let started = Instant::now();
+    let compressed = compression.compress(json.as_bytes())?;
+    debug!(
+        "Compressed {} byte response to {} bytes with {:?} in {:?}",
+        json.len(),
+        compressed.len(),
+        compression,
+        started.elapsed()
+    );
+    if compressed.len() >= json.len() {
+        return Ok(InvokeResponseBody::Json(json));
+    }
+    Ok(InvokeResponseBody::Raw(compressed))
+}
+
+/// Serializes a command result, compressing it with `compression` if it
+/// exceeds [`COMPRESSION_THRESHOLD`].
+///
+/// Serialization and compression run on the blocking thread pool, so large
+/// results do not hold up the async runtime.
+pub async fn respond<T: Serialize + Send + 'static>(value: T, compression: Option<Compression>) -> AppResult<Response> {
+    let body = tokio::task::spawn_blocking(move || -> AppResult<InvokeResponseBody> {
+        Ok(encode(serde_json::to_string(&value)?, compression)?)
+    })
+    .await
+    .map_err(|e| AppError::Application(format!("Response encoding failed: {e}")))??;
+    Ok(Response::new(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn large_json() -> String {
+        serde_json::to_string(&vec!["redsys/runner:latest"; COMPRESSION_THRESHOLD / 8]).unwrap()
+    }
+
+    #[test]
+    fn test_large_results_compressed() {
+        let json = large_json();
+        let InvokeResponseBody::Raw(gzip) = encode(json.clone(), Some(Compression::Gzip)).unwrap() else {
+            panic!("gzip response not compressed");
+        };
+        assert_eq!(gzip[..2], [0x1f, 0x8b]);
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(gzip.as_slice()).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, json);
+
+        let InvokeResponseBody::Raw(zstd) = encode(json.clone(), Some(Compression::Zstd)).unwrap() else {
+            panic!("zstd response not compressed");
+        };
+        assert_eq!(zstd[..4], [0x28, 0xb5, 0x2f, 0xfd]);
+        assert_eq!(zstd::decode_all(zstd.as_slice()).unwrap(), json.as_bytes());
+    }
+
+    #[test]
+    fn test_small_or_unrequested_results_stay_json() {
+        assert!(matches!(encode(large_json(), None).unwrap(), InvokeResponseBody::Json(_)));
+        assert!(matches!(
+            encode("[]".to_string(), Some(Compression::Zstd)).unwrap(),
+            InvokeResponseBody::Json(json) if json == "[]"
+        ));
+    }
+}
@@ -0,0 +1,1570 @@
+//! Configuration file subsystem for RedSys Desktop Agent
+//!
+//! This module loads the agent configuration from a TOML file in the platform
+//! configuration directory, falling back to defaults for anything not set.
+//!
+//! ## Location
+//! - **Linux**: `$XDG_CONFIG_HOME/tn.com.redsys-desktop-agent/config.toml`
+//! - **macOS**: `~/Library/Application Support/tn.com.redsys-desktop-agent/config.toml`
+//! - **Windows**: `%APPDATA%\tn.com.redsys-desktop-agent\config.toml`
+//!
+//! ## Example
+//! ```toml
+//! [monitor]
+//! polling_interval_ms = 500
+//! system_metrics_interval_secs = 5
+//!
+//! [docker]
+//! host = "tcp://192.168.1.20:2375"
+//! allow_engine_control = true
+//!
+//! [events]
+//! event_types = ["container", "image"]
+//!
+//! [logging]
+//! level = "info,desktop_agent_lib::docker_monitor=debug"
+//!
+//! [features]
+//! prune_scheduler = false
+//!
+//! [telemetry]
+//! otlp_endpoint = "http://localhost:4317"
+//!
+//! [status_server]
+//! bind = "127.0.0.1:7421"
+//!
+//! [backend]
+//! url = "wss://platform.redsys.tn/agent"
+//! agent_id = "agent-42"
+//! token = "..."
+//!
+//! [grpc]
+//! enabled = true
+//! bind = "127.0.0.1:7422"
+//! allow_container_operations = false
+//!
+//! [mqtt]
+//! enabled = true
+//! broker = "mqtt://192.168.1.10:1883"
+//! topic_prefix = "homelab/redsys-agent"
+//!
+//! [workspace]
+//! quota_bytes = 10737418240
+//! retention_hours = 24
+//!
+//! [host_paths]
+//! allowed_roots = ["/srv/redsys-data"]
+//!
+//! [notifications]
+//! docker_status = true
+//! jobs = false
+//!
+//! [[webhooks.endpoints]]
+//! url = "https://hooks.slack.com/services/T000/B000/XXXX"
+//! secret = "..."
+//! events = ["status_transition", "security_warning"]
+//!
+//! [disk_pressure]
+//! warning_free_percent = 15.0
+//! critical_free_percent = 5.0
+//! paths = ["/mnt/datasets"]
+//!
+//! [clock]
+//! ntp_server = "time.cloudflare.com:123"
+//! check_interval_secs = 3600
+//!
+//! [network_probe]
+//! check_interval_secs = 21600
+//! measure_throughput = true
+//!
+//! [[network_probe.targets]]
+//! name = "eu-west"
+//! url = "https://eu-west.redsys.tn/probe"
+//! download_url = "https://eu-west.redsys.tn/probe/download"
+//!
+//! [power]
+//! battery_saver = true
+//! battery_interval_factor = 4
+//! pause_gpu_metrics_on_battery = true
+//!
+//! [frontend]
+//! batched_events = ["docker-event", "container-health-changed"]
+//! batch_interval_ms = 250
+//!
+//! [image_updates]
+//! images = ["ghcr.io/redsys/runner:stable"]
+//! check_interval_secs = 21600
+//!
+//! [image_policy]
+//! allowed_registries = ["ghcr.io"]
+//! blocked_images = ["*:latest"]
+//!
+//! [image_signatures]
+//! enabled = true
+//! trusted_keys = ["/etc/redsys/cosign.pub"]
+//! enforce = true
+//!
+//! [job_security]
+//! seccomp_profile = "/etc/redsys/seccomp.json"
+//! apparmor_profile = "redsys-job"
+//! allowed_apparmor_profiles = ["redsys-job-strict"]
+//! ```
+//!
+//! ## Hot Reload
+//! The file is watched for changes while the agent runs. Valid edits are
+//! applied to the running monitors and announced with a `config-reloaded`
+//! event listing the changed keys; invalid edits are logged and ignored.
+//! Changes to `[features]`, `[telemetry]`, `[status_server]`, `[backend]`,
+//! `[grpc]`, `[mqtt]`, `[workspace]`, `[host_paths]`, `[disk_pressure]`,
+//! `[clock]`, `[network_probe]`, `[image_updates]`, `[image_policy]`,
+//! `[image_signatures]` and `[job_security]` only take effect after a restart.
+//!
+//! ## References
+//! - [TOML Specification](https://toml.io/en/v1.0.0)
+//! - [dirs crate](https://docs.rs/dirs/latest/dirs/)
+//! - [notify crate](https://docs.rs/notify/latest/notify/)
+//! - [EnvFilter Directives](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/filter/struct.EnvFilter.html)
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::{sync::{mpsc, watch}, time::Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, warn};
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::backend;
+use crate::clock::DEFAULT_NTP_SERVER;
+use crate::docker::EventFilters;
+use crate::docker_monitor::POLLING_INTERVAL_BOUNDS_MS;
+use crate::error::{AppError, AppResult};
+use crate::events::FRONTEND_EVENTS;
+use crate::hardening::JobSecurityConfig;
+use crate::network_probe::{DEFAULT_PROBE_DOWNLOAD_URL, DEFAULT_PROBE_URL};
+use crate::system_monitor::{DEFAULT_SYSTEM_METRICS_INTERVAL, SYSTEM_METRICS_INTERVAL_BOUNDS_SECS};
+use crate::telemetry;
+use crate::webhooks::WebhookEvent;
+use crate::APP_IDENTIFIER;
+
+/// Configuration file name.
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Settings holding credentials, as dotted keys.
+const SECRET_KEYS: [&str; 3] = ["backend.token", "grpc.token", "mqtt.password"];
+
+/// Stands in for a set secret in configurations shown to the frontend.
+pub const REDACTED_SECRET: &str = "********";
+
+/// Allowed range for the disk usage reporting interval.
+const DISK_USAGE_INTERVAL_BOUNDS_SECS: (u64, u64) = (30, 86_400);
+
+/// Sections whose changes only apply after a restart.
+const RESTART_REQUIRED_SECTIONS: [&str; 15] = [
+    "features",
+    "telemetry",
+    "status_server",
+    "backend",
+    "grpc",
+    "mqtt",
+    "workspace",
+    "host_paths",
+    "disk_pressure",
+    "clock",
+    "network_probe",
+    "image_updates",
+    "image_policy",
+    "image_signatures",
+    "job_security",
+];
+
+/// Shortest interval between clock drift checks, to stay polite to public
+/// NTP servers.
+const MIN_CLOCK_CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Shortest interval between scheduled network probes; with
+/// `network_probe.measure_throughput` each one downloads up to
+/// `network_probe.max_download_bytes` per target.
+const MIN_NETWORK_PROBE_INTERVAL_SECS: u64 = 600;
+
+/// Allowed range for the number of latency samples per target.
+const LATENCY_SAMPLES_BOUNDS: (u32, u32) = (1, 20);
+
+/// Shortest interval between image update checks, to stay within registry
+/// rate limits.
+const MIN_IMAGE_UPDATE_INTERVAL_SECS: u64 = 300;
+
+/// Allowed range for the battery polling interval factor.
+const BATTERY_INTERVAL_FACTOR_BOUNDS: (u32, u32) = (1, 20);
+
+/// Allowed range for the frontend event batch interval.
+const BATCH_INTERVAL_BOUNDS_MS: (u64, u64) = (50, 10_000);
+
+/// Allowed range for the backend heartbeat interval.
+const HEARTBEAT_INTERVAL_BOUNDS_SECS: (u64, u64) = (5, 3_600);
+
+/// Quiet period after a file system event before reloading, so the several
+/// writes of a single editor save are applied once.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Docker monitor settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// Docker daemon liveness polling interval in milliseconds
+    pub polling_interval_ms: u64,
+
+    /// Disk usage report interval in seconds
+    pub disk_usage_interval_secs: u64,
+
+    /// Host system metrics report interval in seconds
+    pub system_metrics_interval_secs: u64,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            polling_interval_ms: 500,
+            disk_usage_interval_secs: 300,
+            system_metrics_interval_secs: DEFAULT_SYSTEM_METRICS_INTERVAL.as_secs(),
+        }
+    }
+}
+
+/// Docker connection settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DockerConfig {
+    /// Overrides the `DOCKER_HOST` environment variable
+    pub host: Option<String>,
+
+    /// Allow the agent to start a stopped local Docker engine
+    pub allow_engine_control: bool,
+}
+
+/// Logging settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// `EnvFilter` directive, e.g. `info` or `info,desktop_agent_lib=debug`.
+    /// `RUST_LOG` takes precedence when set.
+    pub level: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+        }
+    }
+}
+
+/// Optional subsystems that can be switched off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeatureToggles {
+    /// Forward the Docker events stream to the frontend
+    pub events_stream: bool,
+
+    /// Emit periodic `docker-disk-usage` reports
+    pub disk_usage_reporting: bool,
+
+    /// Run scheduled prune policies
+    pub prune_scheduler: bool,
+
+    /// Remove containers and volumes of jobs the agent no longer knows
+    pub orphan_gc: bool,
+
+    /// Emit periodic `system-metrics` reports
+    pub system_metrics: bool,
+
+    /// Fabricate Docker status, containers and events instead of talking to
+    /// a daemon, for frontend development; also enabled by `--simulate`
+    pub simulation: bool,
+}
+
+impl Default for FeatureToggles {
+    fn default() -> Self {
+        Self {
+            events_stream: true,
+            disk_usage_reporting: true,
+            prune_scheduler: true,
+            orphan_gc: true,
+            system_metrics: true,
+            simulation: false,
+        }
+    }
+}
+
+/// OpenTelemetry trace export settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// OTLP/gRPC collector endpoint, e.g. `http://localhost:4317`. Export is
+    /// off when unset and requires a build with the `otel` feature.
+    pub otlp_endpoint: Option<String>,
+
+    /// `service.name` resource attribute of exported spans
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "redsys-desktop-agent".to_string(),
+        }
+    }
+}
+
+/// Local status HTTP endpoint settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusServerConfig {
+    /// Serve `/healthz` and `/status`
+    pub enabled: bool,
+
+    /// Listen address; keep it on loopback unless the network is trusted
+    pub bind: String,
+}
+
+impl Default for StatusServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bind: "127.0.0.1:7421".to_string(),
+        }
+    }
+}
+
+/// RedSys platform connection settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendConfig {
+    /// WebSocket URL of the platform, `wss://` unless it is on loopback; the
+    /// connection is off when unset
+    pub url: Option<String>,
+
+    /// Agent identifier issued by the platform; the stored provider
+    /// registration is used when unset
+    pub agent_id: Option<String>,
+
+    /// Agent token issued by the platform, set together with `agent_id`
+    pub token: Option<String>,
+
+    /// Interval between heartbeats in seconds
+    pub heartbeat_interval_secs: u64,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            agent_id: None,
+            token: None,
+            heartbeat_interval_secs: 30,
+        }
+    }
+}
+
+/// gRPC agent API settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcConfig {
+    /// Serve the gRPC API; requires a build with the `grpc` feature
+    pub enabled: bool,
+
+    /// Listen address
+    pub bind: String,
+
+    /// Allow callers to start and stop containers
+    pub allow_container_operations: bool,
+
+    /// Bearer token callers must send; required off loopback
+    pub token: Option<String>,
+
+    /// PEM certificate chain to serve TLS with, set together with `tls_key`;
+    /// required off loopback
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key of `tls_cert`
+    pub tls_key: Option<PathBuf>,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1:7422".to_string(),
+            allow_container_operations: false,
+            token: None,
+            tls_cert: None,
+            tls_key: None,
+        }
+    }
+}
+
+/// MQTT telemetry publishing settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    /// Publish to the broker; requires a build with the `mqtt` feature
+    pub enabled: bool,
+
+    /// Broker as `mqtt://host:port`, or `mqtts://host:port` for TLS
+    pub broker: String,
+
+    /// Prefix of every published topic, without a trailing `/`
+    pub topic_prefix: String,
+
+    /// Client ID; derived from the host name when unset
+    pub client_id: Option<String>,
+
+    /// User name, set together with `password`
+    pub username: Option<String>,
+
+    /// Password, set together with `username`
+    pub password: Option<String>,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            broker: "mqtt://localhost:1883".to_string(),
+            topic_prefix: "redsys/agent".to_string(),
+            client_id: None,
+            username: None,
+            password: None,
+        }
+    }
+}
+
+impl MqttConfig {
+    /// Splits `broker` into whether it uses TLS, its host and its port.
+    pub fn broker_address(&self) -> Result<(bool, &str, u16), String> {
+        let (tls, address) = if let Some(address) = self.broker.strip_prefix("mqtts://") {
+            (true, address)
+        } else if let Some(address) = self.broker.strip_prefix("mqtt://") {
+            (false, address)
+        } else {
+            return Err(format!("mqtt.broker '{}' must start with mqtt:// or mqtts://", self.broker));
+        };
+        match address.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() => port
+                .parse()
+                .map(|port| (tls, host, port))
+                .map_err(|_| format!("mqtt.broker '{}' has an invalid port", self.broker)),
+            _ => Err(format!("mqtt.broker '{}' must be scheme://host:port", self.broker)),
+        }
+    }
+}
+
+/// Job workspace settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceConfig {
+    /// Directory holding job workspaces; the platform data directory when unset
+    pub root: Option<PathBuf>,
+
+    /// Total disk space all workspaces may use
+    pub quota_bytes: u64,
+
+    /// How long finished workspaces are kept before removal
+    pub retention_hours: u64,
+}
+
+impl Default for WorkspaceConfig {
+    fn default() -> Self {
+        Self {
+            root: None,
+            quota_bytes: 10 * 1024 * 1024 * 1024,
+            retention_hours: 24,
+        }
+    }
+}
+
+/// Host directories jobs and file transfers may use.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostPathsConfig {
+    /// Absolute directories that may be bind mounted into jobs and copied
+    /// to and from; none by default
+    pub allowed_roots: Vec<PathBuf>,
+}
+
+/// Desktop notification settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Master switch for all notifications
+    pub enabled: bool,
+
+    /// Notify when the Docker daemon stops or fails
+    pub docker_status: bool,
+
+    /// Notify when a job finishes or fails
+    pub jobs: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            docker_status: true,
+            jobs: true,
+        }
+    }
+}
+
+/// A webhook receiving agent alerts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    /// URL the alerts are POSTed to
+    pub url: String,
+
+    /// Key for the `X-RedSys-Signature-256` HMAC, unsigned if unset
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    /// Alerts to send, all by default
+    #[serde(default = "all_webhook_events")]
+    pub events: Vec<WebhookEvent>,
+}
+
+fn all_webhook_events() -> Vec<WebhookEvent> {
+    WebhookEvent::ALL.to_vec()
+}
+
+/// Webhook settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    /// Master switch for all webhooks
+    pub enabled: bool,
+
+    /// Endpoints receiving alerts
+    pub endpoints: Vec<WebhookEndpoint>,
+
+    /// Attempts per delivery before giving up
+    pub max_attempts: u32,
+
+    /// Timeout of a single request in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            endpoints: Vec::new(),
+            max_attempts: 5,
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// Disk space alert settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiskPressureConfig {
+    /// Free space in percent below which the agent reports itself degraded
+    pub warning_free_percent: f64,
+
+    /// Free space in percent below which the pressure is critical
+    pub critical_free_percent: f64,
+
+    /// Watched paths in addition to the Docker data root and job workspaces
+    pub paths: Vec<PathBuf>,
+
+    /// Interval between free space checks in seconds
+    pub check_interval_secs: u64,
+}
+
+impl Default for DiskPressureConfig {
+    fn default() -> Self {
+        Self {
+            warning_free_percent: 15.0,
+            critical_free_percent: 5.0,
+            paths: Vec::new(),
+            check_interval_secs: 60,
+        }
+    }
+}
+
+/// Clock drift check settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    /// NTP server the system clock is compared with, as `host:port`
+    pub ntp_server: String,
+
+    /// Interval between drift checks in seconds
+    pub check_interval_secs: u64,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            ntp_server: DEFAULT_NTP_SERVER.to_string(),
+            check_interval_secs: 3_600,
+        }
+    }
+}
+
+/// Endpoint measured by the network probe, e.g. a RedSys region.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkProbeTarget {
+    /// Name the results are reported under, e.g. `eu-west`
+    pub name: String,
+
+    /// HTTP(S) URL whose host latency is measured against
+    pub url: String,
+
+    /// URL downloaded to measure throughput when
+    /// `network_probe.measure_throughput` is set
+    #[serde(default)]
+    pub download_url: Option<String>,
+}
+
+/// Network latency and throughput probe settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkProbeConfig {
+    /// Probe periodically; probes requested from the UI run either way
+    pub enabled: bool,
+
+    /// Endpoints to measure
+    pub targets: Vec<NetworkProbeTarget>,
+
+    /// Interval between scheduled probes in seconds
+    pub check_interval_secs: u64,
+
+    /// Connections timed per target for the latency
+    pub latency_samples: u32,
+
+    /// Download each target's `download_url` to measure throughput; off by
+    /// default since it uses the provider's bandwidth, possibly metered
+    pub measure_throughput: bool,
+
+    /// Bytes downloaded at most per target for the throughput
+    pub max_download_bytes: u64,
+
+    /// Time allowed for each target's download in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for NetworkProbeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            targets: vec![NetworkProbeTarget {
+                name: "generic".to_string(),
+                url: DEFAULT_PROBE_URL.to_string(),
+                download_url: Some(DEFAULT_PROBE_DOWNLOAD_URL.to_string()),
+            }],
+            check_interval_secs: 21_600,
+            latency_samples: 5,
+            measure_throughput: false,
+            max_download_bytes: 2_000_000,
+            timeout_secs: 20,
+        }
+    }
+}
+
+/// Battery power settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    /// Slow down monitoring while the machine runs on battery
+    pub battery_saver: bool,
+
+    /// Factor the steady Docker polling intervals are stretched by on battery
+    pub battery_interval_factor: u32,
+
+    /// Pause GPU metrics streaming on battery
+    pub pause_gpu_metrics_on_battery: bool,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            battery_saver: true,
+            battery_interval_factor: 4,
+            pause_gpu_metrics_on_battery: true,
+        }
+    }
+}
+
+/// Frontend event delivery settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrontendConfig {
+    /// Events emitted in batches as `<name>-batch` instead of one by one
+    pub batched_events: Vec<String>,
+
+    /// Interval between batches in milliseconds
+    pub batch_interval_ms: u64,
+
+    /// Events queued per batched event before the oldest are dropped
+    pub queue_capacity: usize,
+}
+
+impl Default for FrontendConfig {
+    fn default() -> Self {
+        Self {
+            batched_events: Vec::new(),
+            batch_interval_ms: 250,
+            queue_capacity: 256,
+        }
+    }
+}
+
+/// Image update check settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageUpdatesConfig {
+    /// Image references compared with their registries
+    pub images: Vec<String>,
+
+    /// Interval between checks in seconds
+    pub check_interval_secs: u64,
+}
+
+impl Default for ImageUpdatesConfig {
+    fn default() -> Self {
+        Self {
+            images: Vec::new(),
+            check_interval_secs: 21_600,
+        }
+    }
+}
+
+/// Registries and images jobs may use, see [`ImagePolicy`](crate::policy::ImagePolicy).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImagePolicyConfig {
+    /// Registries images may come from; empty allows all
+    pub allowed_registries: Vec<String>,
+
+    /// Registries images must not come from
+    pub blocked_registries: Vec<String>,
+
+    /// Patterns images must match, e.g. `ghcr.io/redsys/*`; empty allows all
+    pub allowed_images: Vec<String>,
+
+    /// Patterns images must not match, e.g. `*:latest`
+    pub blocked_images: Vec<String>,
+}
+
+/// Job image signature verification settings, see
+/// [`SignatureVerifier`](crate::signatures::SignatureVerifier).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageSignaturesConfig {
+    /// Verify job images before running them
+    pub enabled: bool,
+
+    /// Public key files or KMS URIs passed to `cosign verify --key`
+    pub trusted_keys: Vec<String>,
+
+    /// Fail jobs whose image is not verified instead of only logging it
+    pub enforce: bool,
+
+    /// Cosign executable, looked up on `PATH` unless absolute
+    pub cosign_path: String,
+}
+
+impl Default for ImageSignaturesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trusted_keys: Vec::new(),
+            enforce: true,
+            cosign_path: "cosign".to_string(),
+        }
+    }
+}
+
+/// Complete agent configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentConfig {
+    /// Docker monitor settings
+    pub monitor: MonitorConfig,
+
+    /// Docker connection settings
+    pub docker: DockerConfig,
+
+    /// Docker events stream filters
+    pub events: EventFilters,
+
+    /// Logging settings
+    pub logging: LoggingConfig,
+
+    /// Optional subsystems
+    pub features: FeatureToggles,
+
+    /// Trace export settings
+    pub telemetry: TelemetryConfig,
+
+    /// Local status HTTP endpoint settings
+    pub status_server: StatusServerConfig,
+
+    /// RedSys platform connection settings
+    pub backend: BackendConfig,
+
+    /// gRPC agent API settings
+    pub grpc: GrpcConfig,
+
+    /// MQTT telemetry publishing settings
+    pub mqtt: MqttConfig,
+
+    /// Job workspace settings
+    pub workspace: WorkspaceConfig,
+
+    /// Host directories jobs and file transfers may use
+    pub host_paths: HostPathsConfig,
+
+    /// Desktop notification settings
+    pub notifications: NotificationsConfig,
+
+    /// Webhook settings
+    pub webhooks: WebhooksConfig,
+
+    /// Disk space alert settings
+    pub disk_pressure: DiskPressureConfig,
+
+    /// Clock drift check settings
+    pub clock: ClockConfig,
+
+    /// Network latency and throughput probe settings
+    pub network_probe: NetworkProbeConfig,
+
+    /// Battery power settings
+    pub power: PowerConfig,
+
+    /// Frontend event delivery settings
+    pub frontend: FrontendConfig,
+
+    /// Image update check settings
+    pub image_updates: ImageUpdatesConfig,
+
+    /// Registries and images jobs may use
+    pub image_policy: ImagePolicyConfig,
+
+    /// Job image signature verification settings
+    pub image_signatures: ImageSignaturesConfig,
+
+    /// Default job confinement and the choices jobs may make instead
+    pub job_security: JobSecurityConfig,
+}
+
+impl AgentConfig {
+    /// Validates all settings.
+    pub fn validate(&self) -> AppResult<()> {
+        let (min, max) = POLLING_INTERVAL_BOUNDS_MS;
+        if !(min..=max).contains(&self.monitor.polling_interval_ms) {
+            return Err(AppError::Configuration(format!(
+                "monitor.polling_interval_ms must be between {min} and {max}"
+            )));
+        }
+
+        let (min, max) = DISK_USAGE_INTERVAL_BOUNDS_SECS;
+        if !(min..=max).contains(&self.monitor.disk_usage_interval_secs) {
+            return Err(AppError::Configuration(format!(
+                "monitor.disk_usage_interval_secs must be between {min} and {max}"
+            )));
+        }
+
+        let (min, max) = SYSTEM_METRICS_INTERVAL_BOUNDS_SECS;
+        if !(min..=max).contains(&self.monitor.system_metrics_interval_secs) {
+            return Err(AppError::Configuration(format!(
+                "monitor.system_metrics_interval_secs must be between {min} and {max}"
+            )));
+        }
+
+        if let Some(host) = &self.docker.host {
+            const SCHEMES: [&str; 4] = ["tcp://", "http://", "unix://", "npipe://"];
+            if !SCHEMES.iter().any(|scheme| host.starts_with(scheme)) {
+                return Err(AppError::Configuration(format!(
+                    "docker.host '{host}' must start with one of {}", SCHEMES.join(", ")
+                )));
+            }
+        }
+
+        if let Some(endpoint) = &self.telemetry.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                return Err(AppError::Configuration(format!(
+                    "telemetry.otlp_endpoint '{endpoint}' must start with http:// or https://"
+                )));
+            }
+        }
+        if self.telemetry.service_name.trim().is_empty() {
+            return Err(AppError::Configuration("telemetry.service_name must not be empty".to_string()));
+        }
+
+        self.status_server.bind.parse::<std::net::SocketAddr>().map_err(|e| {
+            AppError::Configuration(format!("Invalid status_server.bind '{}': {e}", self.status_server.bind))
+        })?;
+
+        let grpc_addr = self.grpc.bind.parse::<std::net::SocketAddr>().map_err(|e| {
+            AppError::Configuration(format!("Invalid grpc.bind '{}': {e}", self.grpc.bind))
+        })?;
+        if self.grpc.token.as_ref().is_some_and(|token| token.trim().is_empty()) {
+            return Err(AppError::Configuration("grpc.token must not be empty".to_string()));
+        }
+        if self.grpc.tls_cert.is_some() != self.grpc.tls_key.is_some() {
+            return Err(AppError::Configuration("grpc.tls_cert and grpc.tls_key must be set together".to_string()));
+        }
+        let grpc_secured = self.grpc.token.is_some() && self.grpc.tls_cert.is_some();
+        if self.grpc.enabled && !grpc_addr.ip().is_loopback() && !grpc_secured {
+            return Err(AppError::Configuration(format!(
+                "grpc.bind '{}' is not a loopback address; set grpc.token, grpc.tls_cert and grpc.tls_key",
+                self.grpc.bind
+            )));
+        }
+
+        self.mqtt.broker_address().map_err(AppError::Configuration)?;
+        let prefix = &self.mqtt.topic_prefix;
+        if prefix.is_empty() || prefix.ends_with('/') || prefix.contains(['+', '#']) {
+            return Err(AppError::Configuration(format!(
+                "mqtt.topic_prefix '{prefix}' must be non-empty without wildcards or a trailing /"
+            )));
+        }
+        if self.mqtt.username.is_some() != self.mqtt.password.is_some() {
+            return Err(AppError::Configuration(
+                "mqtt.username and mqtt.password must be set together".to_string(),
+            ));
+        }
+
+        if let Some(url) = &self.backend.url {
+            backend::check_url(url).map_err(AppError::Configuration)?;
+        }
+        // Without both, the identity from provider registration is used
+        let has_agent_id = self.backend.agent_id.as_deref().is_some_and(|id| !id.trim().is_empty());
+        let has_token = self.backend.token.as_deref().is_some_and(|token| !token.is_empty());
+        if has_agent_id != has_token {
+            return Err(AppError::Configuration(
+                "backend.agent_id and backend.token must be set together".to_string(),
+            ));
+        }
+        let (min, max) = HEARTBEAT_INTERVAL_BOUNDS_SECS;
+        if !(min..=max).contains(&self.backend.heartbeat_interval_secs) {
+            return Err(AppError::Configuration(format!(
+                "backend.heartbeat_interval_secs must be between {min} and {max}"
+            )));
+        }
+
+        if self.workspace.quota_bytes == 0 {
+            return Err(AppError::Configuration("workspace.quota_bytes must be positive".to_string()));
+        }
+        if self.workspace.retention_hours == 0 {
+            return Err(AppError::Configuration("workspace.retention_hours must be at least 1".to_string()));
+        }
+        if let Some(root) = self.host_paths.allowed_roots.iter().find(|root| !root.is_absolute()) {
+            return Err(AppError::Configuration(format!(
+                "host_paths.allowed_roots entry '{}' must be an absolute path", root.display()
+            )));
+        }
+
+        for endpoint in &self.webhooks.endpoints {
+            if !endpoint.url.starts_with("http://") && !endpoint.url.starts_with("https://") {
+                return Err(AppError::Configuration(
+                    "webhooks.endpoints urls must start with http:// or https://".to_string(),
+                ));
+            }
+            if endpoint.events.is_empty() || endpoint.secret.as_deref().is_some_and(str::is_empty) {
+                return Err(AppError::Configuration(
+                    "webhooks.endpoints events and secret must not be empty".to_string(),
+                ));
+            }
+        }
+        if self.webhooks.max_attempts == 0 || self.webhooks.timeout_secs == 0 {
+            return Err(AppError::Configuration(
+                "webhooks.max_attempts and webhooks.timeout_secs must be at least 1".to_string(),
+            ));
+        }
+
+        let DiskPressureConfig { warning_free_percent, critical_free_percent, .. } = self.disk_pressure;
+        if !(0.0..100.0).contains(&critical_free_percent)
+            || !(critical_free_percent..100.0).contains(&warning_free_percent)
+        {
+            return Err(AppError::Configuration(
+                "disk_pressure thresholds must satisfy 0 <= critical_free_percent <= warning_free_percent < 100"
+                    .to_string(),
+            ));
+        }
+        if self.disk_pressure.check_interval_secs == 0 {
+            return Err(AppError::Configuration("disk_pressure.check_interval_secs must be at least 1".to_string()));
+        }
+
+        let has_port = self
+            .clock
+            .ntp_server
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+        if !has_port {
+            return Err(AppError::Configuration(format!(
+                "clock.ntp_server '{}' must be host:port",
+                self.clock.ntp_server
+            )));
+        }
+        if self.clock.check_interval_secs < MIN_CLOCK_CHECK_INTERVAL_SECS {
+            return Err(AppError::Configuration(format!(
+                "clock.check_interval_secs must be at least {MIN_CLOCK_CHECK_INTERVAL_SECS}"
+            )));
+        }
+
+        let probe = &self.network_probe;
+        for target in &probe.targets {
+            if target.name.trim().is_empty() {
+                return Err(AppError::Configuration("network_probe.targets names must not be empty".to_string()));
+            }
+            if probe.targets.iter().filter(|other| other.name == target.name).count() > 1 {
+                return Err(AppError::Configuration(format!(
+                    "network_probe.targets name '{}' is used more than once",
+                    target.name
+                )));
+            }
+            let is_http = |url: &str| url.starts_with("http://") || url.starts_with("https://");
+            if !is_http(&target.url) || !target.download_url.as_deref().is_none_or(is_http) {
+                return Err(AppError::Configuration(format!(
+                    "network_probe.targets '{}' urls must start with http:// or https://",
+                    target.name
+                )));
+            }
+        }
+        if probe.check_interval_secs < MIN_NETWORK_PROBE_INTERVAL_SECS {
+            return Err(AppError::Configuration(format!(
+                "network_probe.check_interval_secs must be at least {MIN_NETWORK_PROBE_INTERVAL_SECS}"
+            )));
+        }
+        let (min, max) = LATENCY_SAMPLES_BOUNDS;
+        if !(min..=max).contains(&probe.latency_samples) {
+            return Err(AppError::Configuration(format!(
+                "network_probe.latency_samples must be between {min} and {max}"
+            )));
+        }
+        if probe.max_download_bytes == 0 || probe.timeout_secs == 0 {
+            return Err(AppError::Configuration(
+                "network_probe.max_download_bytes and network_probe.timeout_secs must be at least 1".to_string(),
+            ));
+        }
+
+        let (min, max) = BATTERY_INTERVAL_FACTOR_BOUNDS;
+        if !(min..=max).contains(&self.power.battery_interval_factor) {
+            return Err(AppError::Configuration(format!(
+                "power.battery_interval_factor must be between {min} and {max}"
+            )));
+        }
+
+        if let Some(name) = self.frontend.batched_events.iter().find(|name| !FRONTEND_EVENTS.contains(&name.as_str())) {
+            return Err(AppError::Configuration(format!(
+                "frontend.batched_events: unknown event '{name}', expected one of {}",
+                FRONTEND_EVENTS.join(", ")
+            )));
+        }
+        let (min, max) = BATCH_INTERVAL_BOUNDS_MS;
+        if !(min..=max).contains(&self.frontend.batch_interval_ms) {
+            return Err(AppError::Configuration(format!(
+                "frontend.batch_interval_ms must be between {min} and {max}"
+            )));
+        }
+        if self.frontend.queue_capacity == 0 {
+            return Err(AppError::Configuration("frontend.queue_capacity must be at least 1".to_string()));
+        }
+
+        if self.image_updates.images.iter().any(|image| image.trim().is_empty()) {
+            return Err(AppError::Configuration("image_updates.images must not contain empty references".to_string()));
+        }
+        if self.image_updates.check_interval_secs < MIN_IMAGE_UPDATE_INTERVAL_SECS {
+            return Err(AppError::Configuration(format!(
+                "image_updates.check_interval_secs must be at least {MIN_IMAGE_UPDATE_INTERVAL_SECS}"
+            )));
+        }
+
+        let policy = &self.image_policy;
+        let has_empty = [&policy.allowed_registries, &policy.blocked_registries, &policy.allowed_images, &policy.blocked_images]
+            .into_iter()
+            .flatten()
+            .any(|entry| entry.trim().is_empty());
+        if has_empty {
+            return Err(AppError::Configuration("image_policy lists must not contain empty entries".to_string()));
+        }
+
+        let signatures = &self.image_signatures;
+        if signatures.enabled && signatures.trusted_keys.is_empty() {
+            return Err(AppError::Configuration(
+                "image_signatures.trusted_keys must not be empty when enabled".to_string(),
+            ));
+        }
+        if signatures.trusted_keys.iter().any(|key| key.trim().is_empty()) || signatures.cosign_path.trim().is_empty() {
+            return Err(AppError::Configuration(
+                "image_signatures keys and cosign_path must not be empty".to_string(),
+            ));
+        }
+
+        self.job_security
+            .validate()
+            .map_err(|reason| AppError::Configuration(format!("Invalid job_security: {reason}")))?;
+
+        tracing_subscriber::EnvFilter::try_new(&self.logging.level).map_err(|e| {
+            AppError::Configuration(format!("Invalid logging.level '{}': {e}", self.logging.level))
+        })?;
+
+        self.events.validate()
+    }
+
+    /// Parses and validates a TOML document.
+    pub fn from_toml(contents: &str) -> AppResult<Self> {
+        let config: AgentConfig = toml::from_str(contents)
+            .map_err(|e| AppError::Configuration(format!("Invalid config file: {e}")))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Lists the settings that differ from `other`, as dotted keys.
+    pub fn diff(&self, other: &AgentConfig) -> Vec<ConfigChange> {
+        let mut changes = Vec::new();
+        match (serde_json::to_value(self), serde_json::to_value(other)) {
+            (Ok(old), Ok(new)) => diff_values("", &old, &new, &mut changes),
+            (Err(e), _) | (_, Err(e)) => error!("Failed to compare configurations: {e}"),
+        }
+        changes
+    }
+
+    /// Returns a copy with every set secret replaced by [`REDACTED_SECRET`],
+    /// for the frontend.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        for secret in [&mut config.backend.token, &mut config.grpc.token, &mut config.mqtt.password] {
+            if secret.is_some() {
+                *secret = Some(REDACTED_SECRET.to_string());
+            }
+        }
+        config
+    }
+
+    /// Puts back the secrets of `current` that are still
+    /// [`REDACTED_SECRET`], so a configuration read through
+    /// [`redacted`](Self::redacted) can be saved without losing them.
+    pub fn with_secrets_from(mut self, current: &AgentConfig) -> Self {
+        let secrets = [
+            (&mut self.backend.token, &current.backend.token),
+            (&mut self.grpc.token, &current.grpc.token),
+            (&mut self.mqtt.password, &current.mqtt.password),
+        ];
+        for (secret, current) in secrets {
+            if secret.as_deref() == Some(REDACTED_SECRET) {
+                secret.clone_from(current);
+            }
+        }
+        self
+    }
+
+    /// Serializes the configuration as pretty TOML.
+    pub fn to_toml(&self) -> AppResult<String> {
+        toml::to_string_pretty(self)
+            .map_err(|e| AppError::Configuration(format!("Failed to serialize config: {e}")))
+    }
+}
+
+/// A single changed setting.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChange {
+    /// Dotted key, e.g. `monitor.polling_interval_ms`
+    pub key: String,
+
+    /// Previous value
+    pub old: Value,
+
+    /// New value
+    pub new: Value,
+
+    /// Whether the change only applies after a restart
+    pub restart_required: bool,
+}
+
+/// Payload of the `config-reloaded` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigReloaded {
+    /// Path of the reloaded file
+    pub path: String,
+
+    /// Changed settings
+    pub changes: Vec<ConfigChange>,
+}
+
+/// Recursively collects the differences between two JSON trees.
+fn diff_values(prefix: &str, old: &Value, new: &Value, changes: &mut Vec<ConfigChange>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            let keys: BTreeSet<&String> = old_map.keys().chain(new_map.keys()).collect();
+            for key in keys {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+                let old_value = old_map.get(key).unwrap_or(&Value::Null);
+                let new_value = new_map.get(key).unwrap_or(&Value::Null);
+                diff_values(&path, old_value, new_value, changes);
+            }
+        }
+        _ if old != new => {
+            let section = prefix.split('.').next().unwrap_or_default();
+            let shown = |value: &Value| match value {
+                Value::Null => Value::Null,
+                _ if SECRET_KEYS.contains(&prefix) => Value::from(REDACTED_SECRET),
+                _ => value.clone(),
+            };
+            changes.push(ConfigChange {
+                key: prefix.to_string(),
+                old: shown(old),
+                new: shown(new),
+                restart_required: RESTART_REQUIRED_SECTIONS.contains(&section),
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Owns the configuration file and the current configuration.
+///
+/// Running services subscribe to changes through [`ConfigManager::subscribe`].
+#[derive(Debug)]
+pub struct ConfigManager {
+    /// Path of the TOML file
+    path: PathBuf,
+
+    /// Current configuration, broadcast to subscribers
+    config: watch::Sender<AgentConfig>,
+}
+
+impl ConfigManager {
+    /// Returns the default configuration file path in the platform config dir.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(APP_IDENTIFIER).join(CONFIG_FILE_NAME))
+    }
+
+    /// Loads the configuration from `path`.
+    ///
+    /// A missing file yields the defaults and writes them out so users have a
+    /// template to edit. An unreadable or invalid file is an error.
+    pub fn load(path: PathBuf) -> AppResult<Self> {
+        let config = match std::fs::read_to_string(&path) {
+            Ok(contents) => AgentConfig::from_toml(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let config = AgentConfig::default();
+                write_config_file(&path, &config)?;
+                config
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path,
+            config: watch::Sender::new(config),
+        })
+    }
+
+    /// Loads the configuration from the default location.
+    ///
+    /// Falls back to defaults if the file cannot be loaded, returning the error
+    /// so the caller can report it once logging is initialized.
+    pub fn load_default() -> (Self, Option<AppError>) {
+        let path = Self::default_path().unwrap_or_else(|| PathBuf::from(CONFIG_FILE_NAME));
+        match Self::load(path.clone()) {
+            Ok(manager) => (manager, None),
+            Err(e) => (Self::with_defaults(path), Some(e)),
+        }
+    }
+
+    /// Creates a manager holding the defaults, used when the file cannot be loaded.
+    pub fn with_defaults(path: PathBuf) -> Self {
+        Self {
+            path,
+            config: watch::Sender::new(AgentConfig::default()),
+        }
+    }
+
+    /// Path of the configuration file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the current configuration.
+    pub fn get(&self) -> AgentConfig {
+        self.config.borrow().clone()
+    }
+
+    /// Subscribes to configuration changes.
+    pub fn subscribe(&self) -> watch::Receiver<AgentConfig> {
+        self.config.subscribe()
+    }
+
+    /// Validates, persists and activates a new configuration.
+    ///
+    /// Returns the changed settings.
+    pub fn update(&self, config: AgentConfig) -> AppResult<Vec<ConfigChange>> {
+        config.validate()?;
+        write_config_file(&self.path, &config)?;
+
+        info!("Configuration saved to {}", self.path.display());
+        Ok(self.replace(config))
+    }
+
+    /// Re-reads the configuration file and activates it if it changed.
+    ///
+    /// Returns the changed settings, or an error if the file is unreadable or
+    /// invalid, in which case the current configuration stays active.
+    pub fn reload(&self) -> AppResult<Vec<ConfigChange>> {
+        let contents = std::fs::read_to_string(&self.path)?;
+        let config = AgentConfig::from_toml(&contents)?;
+        Ok(self.replace(config))
+    }
+
+    /// Activates `config` and notifies subscribers if anything changed.
+    fn replace(&self, config: AgentConfig) -> Vec<ConfigChange> {
+        let mut changes = Vec::new();
+        self.config.send_if_modified(|current| {
+            changes = current.diff(&config);
+            if changes.is_empty() {
+                return false;
+            }
+            *current = config;
+            true
+        });
+        changes
+    }
+
+    /// Watches the configuration file and reloads it on change.
+    ///
+    /// The parent directory is watched rather than the file itself so that
+    /// editors replacing the file on save are handled. Each effective reload
+    /// emits a `config-reloaded` event with the changed settings.
+    pub async fn start_watching(
+        self: Arc<Self>,
+        app_handle: tauri::AppHandle,
+        cancellation_token: CancellationToken,
+    ) {
+        let Some(watch_dir) = self.path.parent().map(Path::to_path_buf) else {
+            warn!("Cannot watch configuration file {}: no parent directory", self.path.display());
+            return;
+        };
+        let file_name = self.path.file_name().map(|name| name.to_os_string());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) => {
+                let touches_config = event.paths.iter().any(|path| path.file_name() == file_name.as_deref());
+                if touches_config && !event.kind.is_access() {
+                    let _ = tx.send(());
+                }
+            }
+            Err(e) => warn!("Configuration file watcher error: {e}"),
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                error!("Failed to create configuration file watcher: {e}");
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&watch_dir, RecursiveMode::NonRecursive) {
+            error!("Failed to watch {}: {e}", watch_dir.display());
+            return;
+        }
+
+        info!("Watching {} for configuration changes", self.path.display());
+
+        loop {
+            tokio::select! {
+                Some(()) = rx.recv() => {
+                    tokio::time::sleep(RELOAD_DEBOUNCE).await;
+                    while rx.try_recv().is_ok() {}
+
+                    info_span!("config_reload").in_scope(|| match self.reload() {
+                        Ok(changes) if changes.is_empty() => debug!("Configuration file touched without changes"),
+                        Ok(changes) => {
+                            info!("Configuration reloaded with {} change(s)", changes.len());
+                            let payload = ConfigReloaded {
+                                path: self.path.display().to_string(),
+                                changes,
+                            };
+                            if let Err(e) = telemetry::emit(&app_handle, "config-reloaded", &payload) {
+                                error!("Failed to emit config-reloaded event: {e}");
+                            }
+                        }
+                        Err(e) => warn!("Ignoring configuration file change: {e}"),
+                    });
+                }
+                _ = cancellation_token.cancelled() => {
+                    info!("Configuration watcher received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Writes the configuration atomically via a temporary file.
+fn write_config_file(path: &Path, config: &AgentConfig) -> AppResult<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("toml.tmp");
+    std::fs::write(&temp_path, config.to_toml()?)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("redsys-config-test-{}-{name}", std::process::id()))
+            .join(CONFIG_FILE_NAME)
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(AgentConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_partial_toml_uses_defaults() {
+        let config = AgentConfig::from_toml("[monitor]\npolling_interval_ms = 2000\n").unwrap();
+        assert_eq!(config.monitor.polling_interval_ms, 2000);
+        assert_eq!(config.monitor.disk_usage_interval_secs, 300);
+        assert_eq!(config.logging.level, "info");
+        assert!(config.features.events_stream);
+    }
+
+    #[test]
+    fn test_invalid_values_rejected() {
+        assert!(AgentConfig::from_toml("[monitor]\npolling_interval_ms = 1\n").is_err());
+        assert!(AgentConfig::from_toml("[monitor]\nsystem_metrics_interval_secs = 0\n").is_err());
+        assert!(AgentConfig::from_toml("[docker]\nhost = \"ftp://host\"\n").is_err());
+        assert!(AgentConfig::from_toml("[logging]\nlevel = \"info,=[\"\n").is_err());
+        assert!(AgentConfig::from_toml("[telemetry]\notlp_endpoint = \"localhost:4317\"\n").is_err());
+        assert!(AgentConfig::from_toml("[status_server]\nbind = \"localhost\"\n").is_err());
+        assert!(AgentConfig::from_toml("[backend]\nurl = \"https://platform\"\nagent_id = \"a\"\ntoken = \"t\"\n").is_err());
+        assert!(AgentConfig::from_toml("[backend]\nurl = \"ws://platform\"\nagent_id = \"a\"\ntoken = \"t\"\n").is_err());
+        assert!(AgentConfig::from_toml("[backend]\nurl = \"wss://platform\"\nagent_id = \"a\"\n").is_err());
+        assert!(AgentConfig::from_toml("[grpc]\nbind = \"7422\"\n").is_err());
+        assert!(AgentConfig::from_toml("[grpc]\nenabled = true\nbind = \"0.0.0.0:7422\"\ntoken = \"s3cret\"\n").is_err());
+        assert!(AgentConfig::from_toml("[grpc]\ntls_cert = \"/etc/redsys/grpc.pem\"\n").is_err());
+        assert!(AgentConfig::from_toml("[mqtt]\nbroker = \"tcp://broker:1883\"\n").is_err());
+        assert!(AgentConfig::from_toml("[mqtt]\ntopic_prefix = \"redsys/#\"\n").is_err());
+        assert!(AgentConfig::from_toml("[workspace]\nretention_hours = 0\n").is_err());
+        assert!(AgentConfig::from_toml("[host_paths]\nallowed_roots = [\"data\"]\n").is_err());
+        assert!(AgentConfig::from_toml("[[webhooks.endpoints]]\nurl = \"hooks.slack.com\"\n").is_err());
+        assert!(AgentConfig::from_toml("[disk_pressure]\nwarning_free_percent = 4.0\n").is_err());
+        assert!(AgentConfig::from_toml("[clock]\nntp_server = \"pool.ntp.org\"\n").is_err());
+        assert!(AgentConfig::from_toml("[clock]\ncheck_interval_secs = 10\n").is_err());
+        assert!(AgentConfig::from_toml("[network_probe]\ncheck_interval_secs = 60\n").is_err());
+        assert!(AgentConfig::from_toml("[[network_probe.targets]]\nname = \"eu-west\"\nurl = \"eu-west.redsys.tn\"\n").is_err());
+        assert!(AgentConfig::from_toml("[power]\nbattery_interval_factor = 0\n").is_err());
+        assert!(AgentConfig::from_toml("[frontend]\nbatched_events = [\"docker_event\"]\n").is_err());
+        assert!(AgentConfig::from_toml("[frontend]\nbatch_interval_ms = 0\n").is_err());
+        assert!(AgentConfig::from_toml("[image_updates]\ncheck_interval_secs = 60\n").is_err());
+        assert!(AgentConfig::from_toml("[image_policy]\nallowed_registries = [\"\"]\n").is_err());
+        assert!(AgentConfig::from_toml("[image_signatures]\nenabled = true\n").is_err());
+        assert!(AgentConfig::from_toml("[job_security]\napparmor_profile = \"unconfined\"\n").is_err());
+        assert!(AgentConfig::from_toml("[job_security]\nallowed_seccomp_profiles = [\"unconfined\"]\n").is_err());
+        assert!(AgentConfig::from_toml("not toml at all").is_err());
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let mut config = AgentConfig::default();
+        config.docker.host = Some("tcp://localhost:2375".to_string());
+        let parsed = AgentConfig::from_toml(&config.to_toml().unwrap()).unwrap();
+        assert_eq!(parsed, config);
+    }
+
+    #[test]
+    fn test_diff_lists_changed_keys() {
+        let old = AgentConfig::default();
+        let mut new = old.clone();
+        new.monitor.polling_interval_ms = 1000;
+        new.docker.host = Some("tcp://localhost:2375".to_string());
+        new.features.prune_scheduler = false;
+
+        let changes = old.diff(&new);
+        let keys: Vec<&str> = changes.iter().map(|change| change.key.as_str()).collect();
+        assert_eq!(keys, ["docker.host", "features.prune_scheduler", "monitor.polling_interval_ms"]);
+        assert_eq!(changes[0].old, Value::Null);
+        assert_eq!(changes[2].new, Value::from(1000));
+        assert!(!changes[0].restart_required);
+        assert!(changes[1].restart_required);
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_secrets_redacted_and_restored() {
+        let mut config = AgentConfig::default();
+        config.backend.token = Some("backend-secret".to_string());
+        config.mqtt.password = Some("mqtt-secret".to_string());
+
+        let redacted = config.redacted();
+        assert_eq!(redacted.backend.token.as_deref(), Some(REDACTED_SECRET));
+        assert_eq!(redacted.mqtt.password.as_deref(), Some(REDACTED_SECRET));
+        assert_eq!(redacted.grpc.token, None);
+        assert_eq!(redacted.clone().with_secrets_from(&config), config);
+
+        let mut edited = redacted;
+        edited.mqtt.password = Some("new-secret".to_string());
+        edited.backend.token = None;
+        let saved = edited.with_secrets_from(&config);
+        assert_eq!(saved.mqtt.password.as_deref(), Some("new-secret"));
+        assert_eq!(saved.backend.token, None);
+
+        let changes = config.diff(&saved);
+        assert_eq!(changes[0].key, "backend.token");
+        assert_eq!(changes[0].old, Value::from(REDACTED_SECRET));
+        assert_eq!(changes[0].new, Value::Null);
+        assert_eq!(changes[1].new, Value::from(REDACTED_SECRET));
+    }
+
+    #[test]
+    fn test_reload_keeps_config_on_invalid_file() {
+        let path = temp_config_path("reload");
+        let _ = std::fs::remove_file(&path);
+        let manager = ConfigManager::load(path.clone()).unwrap();
+        let mut receiver = manager.subscribe();
+
+        std::fs::write(&path, "[monitor]\npolling_interval_ms = 2000\n").unwrap();
+        let changes = manager.reload().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow_and_update().monitor.polling_interval_ms, 2000);
+
+        std::fs::write(&path, "[monitor]\npolling_interval_ms = 1\n").unwrap();
+        assert!(manager.reload().is_err());
+        assert!(!receiver.has_changed().unwrap());
+        assert_eq!(manager.get().monitor.polling_interval_ms, 2000);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_load_missing_file_writes_defaults() {
+        let path = temp_config_path("missing");
+        let _ = std::fs::remove_file(&path);
+
+        let manager = ConfigManager::load(path.clone()).unwrap();
+        assert_eq!(manager.get(), AgentConfig::default());
+        assert!(path.exists());
+
+        let mut config = manager.get();
+        config.monitor.polling_interval_ms = 1500;
+        assert_eq!(manager.update(config).unwrap().len(), 1);
+
+        let reloaded = ConfigManager::load(path.clone()).unwrap();
+        assert_eq!(reloaded.get().monitor.polling_interval_ms, 1500);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
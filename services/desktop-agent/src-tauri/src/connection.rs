@@ -0,0 +1,94 @@
+//! Shared Docker connection for RedSys Desktop Agent
+//!
+//! One negotiated Bollard client is shared by the
+//! [`DockerMonitor`](crate::docker_monitor::DockerMonitor) health checks, the
+//! events stream and one-off operations such as container listing, instead of
+//! each of them opening its own connection.
+//!
+//! ## Reconnection
+//! - The monitor drops the shared client when a health check over it fails,
+//!   and stores the next client that passes a check
+//! - The events stream drops it when the stream fails
+//! - Changing the Docker host drops it right away
+//!
+//! Users pass the client they got to [`invalidate`], so a failure reported
+//! late never drops a newer, working client.
+//!
+//! ## References
+//! - [Bollard Docker client](https://docs.rs/bollard/latest/bollard/struct.Docker.html)
+
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+use tracing::debug;
+use bollard::Docker;
+
+use crate::client::DockerClient;
+use crate::docker_monitor::{DockerMonitor, DockerMonitorResult};
+
+/// The shared client, if connected.
+static CLIENT: RwLock<Option<Arc<Docker>>> = RwLock::new(None);
+
+/// Held while connecting so concurrent callers share one new connection.
+static CONNECTING: Mutex<()> = Mutex::const_new(());
+
+/// Gets the shared client without connecting.
+pub(crate) fn cached() -> Option<Arc<Docker>> {
+    CLIENT.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Gets the shared client, connecting and negotiating the API version if
+/// there is none.
+pub(crate) async fn client() -> DockerMonitorResult<Arc<Docker>> {
+    if let Some(client) = cached() {
+        return Ok(client);
+    }
+    let _connecting = CONNECTING.lock().await;
+    // Another caller may have connected while this one waited
+    if let Some(client) = cached() {
+        return Ok(client);
+    }
+    let client = DockerMonitor::negotiate(DockerMonitor::get_docker_client().await?).await?;
+    Ok(store(client))
+}
+
+/// Shares a connected, negotiated client.
+pub(crate) fn store(client: Docker) -> Arc<Docker> {
+    let client = Arc::new(client);
+    *CLIENT.write().unwrap_or_else(|e| e.into_inner()) = Some(client.clone());
+    client
+}
+
+/// Drops `client` after a failure, unless it was already replaced.
+///
+/// Clients other than the shared one, e.g. mocks, are ignored.
+pub(crate) fn invalidate<C: DockerClient + ?Sized>(client: &Arc<C>) {
+    let mut shared = CLIENT.write().unwrap_or_else(|e| e.into_inner());
+    if shared.as_ref().is_some_and(|shared| std::ptr::addr_eq(Arc::as_ptr(shared), Arc::as_ptr(client))) {
+        debug!("Dropping shared Docker connection");
+        *shared = None;
+    }
+}
+
+/// Drops the shared client so the next user reconnects.
+pub(crate) fn clear() {
+    CLIENT.write().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::API_DEFAULT_VERSION;
+
+    #[test]
+    fn test_invalidate_keeps_newer_client() {
+        let connect = || Docker::connect_with_http("http://127.0.0.1:2375", 4, API_DEFAULT_VERSION).unwrap();
+        let stale = store(connect());
+        let current = store(connect());
+
+        invalidate(&stale);
+        assert!(cached().is_some_and(|client| Arc::ptr_eq(&client, &current)));
+
+        invalidate(&current);
+        assert!(cached().is_none());
+    }
+}
@@ -0,0 +1,77 @@
+//! Report de-duplication for RedSys Desktop Agent
+//!
+//! Monitors check every few seconds, and a daemon that stays down fails every
+//! check the same way. Reporting each failure would flood the frontend and
+//! the logs with the same message. A [`Deduplicator`] lets a report through
+//! when it differs from the previous one, and otherwise only as a heartbeat
+//! at most once per interval, counting what it held back in between.
+
+use std::time::Instant;
+use tokio::time::Duration;
+
+/// Lets a repeated report through once per heartbeat interval.
+#[derive(Debug)]
+pub struct Deduplicator<K> {
+    /// Minimum time between two identical reports
+    heartbeat: Duration,
+
+    /// Last report let through and when
+    last: Option<(K, Instant)>,
+
+    /// Identical reports held back since then
+    suppressed: u64,
+}
+
+impl<K: PartialEq> Deduplicator<K> {
+    /// Creates a deduplicator repeating identical reports every `heartbeat`.
+    pub const fn new(heartbeat: Duration) -> Self {
+        Self {
+            heartbeat,
+            last: None,
+            suppressed: 0,
+        }
+    }
+
+    /// Whether to send report `key` at `now`.
+    ///
+    /// Returns the number of identical reports held back since the previous
+    /// one that was sent, or `None` to hold this one back too.
+    pub fn observe(&mut self, key: K, now: Instant) -> Option<u64> {
+        if let Some((last, at)) = &self.last {
+            if *last == key && now.duration_since(*at) < self.heartbeat {
+                self.suppressed += 1;
+                return None;
+            }
+        }
+        self.last = Some((key, now));
+        Some(std::mem::take(&mut self.suppressed))
+    }
+
+    /// Forgets the last report, so the next one is sent whatever it is.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.suppressed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_reports_held_back_until_heartbeat() {
+        let mut dedup = Deduplicator::new(Duration::from_secs(60));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(dedup.observe("stopped", at(0)), Some(0));
+        assert_eq!(dedup.observe("stopped", at(5)), None);
+        assert_eq!(dedup.observe("stopped", at(10)), None);
+        assert_eq!(dedup.observe("stopped", at(60)), Some(2));
+        assert_eq!(dedup.observe("error", at(65)), Some(0));
+        assert_eq!(dedup.observe("stopped", at(70)), Some(0));
+
+        dedup.reset();
+        assert_eq!(dedup.observe("stopped", at(75)), Some(0));
+    }
+}
@@ -0,0 +1,225 @@
+//! Deep links for RedSys Desktop Agent
+//!
+//! The RedSys web dashboard hands off to the installed agent with
+//! `redsys://` URLs:
+//! - `redsys://job/<id>/logs` shows the logs of a job
+//! - `redsys://connect?token=<token>` registers this machine as a provider
+//!   with a one-time registration token
+//!
+//! Links reach the running agent through the deep link plugin. On Windows and
+//! Linux, where the OS starts a new process per link, the single instance
+//! plugin forwards the link to the running agent; a link that starts the agent
+//! is handled once it is up.
+//!
+//! Any website can open a `redsys://` URL, so a link never acts on its own: it
+//! opens the dashboard and announces the request as `deep-link-opened`. The
+//! token of a `connect` link stays in the agent as the [`PendingConnect`]
+//! and never reaches the webview; the frontend asks the user and answers
+//! with `confirm_deep_link`, which registers with the token only if the user
+//! agreed. A pending token expires after [`PENDING_CONNECT_TTL`] and is
+//! replaced by the next `connect` link. Links for unknown jobs are rejected.
+//! Tokens are never logged.
+//!
+//! ## References
+//! - [Tauri Deep Linking](https://v2.tauri.app/plugin/deep-linking/)
+//! - [Tauri Single Instance](https://v2.tauri.app/plugin/single-instance/)
+
+use std::sync::Arc;
+use tauri::{Manager, Url};
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tracing::{error, info, warn};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::jobs::{self, JobManager};
+use crate::{telemetry, tray};
+
+/// URL scheme the agent handles.
+pub const SCHEME: &str = "redsys";
+
+/// Time the user has to confirm a `connect` link.
+pub const PENDING_CONNECT_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Request carried by a `redsys://` link, and payload of the
+/// `deep-link-opened` event.
+///
+/// Uses `#[serde(tag = "type")]` like [`JobState`](crate::jobs::JobState).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(tag = "type")]
+pub enum DeepLink {
+    /// Show the logs of a job
+    JobLogs { job_id: String },
+
+    /// Register as a provider with a one-time token, once the user agrees.
+    /// The token is left out of the event.
+    Connect {
+        #[serde(skip)]
+        token: String,
+    },
+}
+
+impl DeepLink {
+    /// Parses a `redsys://` URL.
+    pub fn parse(url: &Url) -> AppResult<Self> {
+        if url.scheme() != SCHEME {
+            return Err(AppError::Configuration(format!("Unsupported deep link scheme '{}'", url.scheme())));
+        }
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+            .unwrap_or_default();
+        match (url.host_str(), segments.as_slice()) {
+            (Some("job"), [job_id, "logs"]) if jobs::is_valid_job_id(job_id) => Ok(DeepLink::JobLogs {
+                job_id: job_id.to_string(),
+            }),
+            (Some("connect"), []) => url
+                .query_pairs()
+                .find(|(key, _)| key == "token")
+                .map(|(_, token)| token.trim().to_string())
+                .filter(|token| !token.is_empty())
+                .map(|token| DeepLink::Connect { token })
+                .ok_or_else(|| AppError::Configuration("Deep link redsys://connect has no token".to_string())),
+            _ => Err(AppError::Configuration(format!("Unsupported deep link {}", redacted(url)))),
+        }
+    }
+}
+
+/// Registration token of the latest `connect` link, waiting for the user.
+#[derive(Debug, Default)]
+pub struct PendingConnect {
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl PendingConnect {
+    /// Keeps `token` until it is taken or expires, replacing any other.
+    pub async fn set(&self, token: String) {
+        *self.token.lock().await = Some((token, Instant::now()));
+    }
+
+    /// Takes the pending token, `None` if there is none or it expired.
+    pub async fn take(&self) -> Option<String> {
+        self.token
+            .lock()
+            .await
+            .take()
+            .filter(|(_, received)| received.elapsed() < PENDING_CONNECT_TTL)
+            .map(|(token, _)| token)
+    }
+}
+
+/// The URL without its query, which may hold a token.
+fn redacted(url: &Url) -> String {
+    format!("{}://{}{}", url.scheme(), url.host_str().unwrap_or_default(), url.path())
+}
+
+/// Handles a `redsys://` link: opens the dashboard and emits
+/// `deep-link-opened`.
+pub async fn open(app_handle: &tauri::AppHandle, url: &Url) -> AppResult<()> {
+    let link = DeepLink::parse(url)?;
+    if let DeepLink::JobLogs { job_id } = &link {
+        let job_manager = app_handle
+            .try_state::<Arc<JobManager>>()
+            .ok_or_else(|| AppError::InvalidState("Jobs are not available yet".to_string()))?;
+        if job_manager.job(job_id).await.is_none() {
+            return Err(AppError::NotFound { resource: format!("job '{job_id}'") });
+        }
+    }
+    if let DeepLink::Connect { token } = &link {
+        let pending = app_handle
+            .try_state::<Arc<PendingConnect>>()
+            .ok_or_else(|| AppError::InvalidState("Deep links are not available yet".to_string()))?;
+        pending.set(token.clone()).await;
+    }
+    info!("Opening deep link {}", redacted(url));
+
+    tray::open_dashboard(app_handle);
+    if let Err(e) = telemetry::emit(app_handle, "deep-link-opened", &link) {
+        error!("Failed to emit deep-link-opened event: {e}");
+    }
+    Ok(())
+}
+
+/// Handles the link that started the agent, if any, and the links opened
+/// while it runs.
+///
+/// Call once the job manager is managed, so links to jobs can be checked.
+/// Manages the [`PendingConnect`] that `confirm_deep_link` takes the token
+/// from.
+pub fn listen(app_handle: &tauri::AppHandle) {
+    app_handle.manage(Arc::new(PendingConnect::default()));
+
+    let handle = |app_handle: tauri::AppHandle, urls: Vec<Url>| {
+        tauri::async_runtime::spawn(async move {
+            for url in urls {
+                if let Err(e) = open(&app_handle, &url).await {
+                    warn!("Ignoring deep link: {}", e);
+                }
+            }
+        });
+    };
+
+    let deep_link = app_handle.deep_link();
+    // Registered at install time elsewhere; Linux needs it at runtime, as do
+    // unbundled Windows builds
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    {
+        if let Err(e) = deep_link.register_all() {
+            warn!("Failed to register the {}:// scheme: {}", SCHEME, e);
+        }
+    }
+    match deep_link.get_current() {
+        Ok(Some(urls)) => handle(app_handle.clone(), urls),
+        Ok(None) => {}
+        Err(e) => warn!("Failed to read the deep link the agent was started with: {}", e),
+    }
+    let listener_handle = app_handle.clone();
+    deep_link.on_open_url(move |event| handle(listener_handle.clone(), event.urls()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(url: &str) -> AppResult<DeepLink> {
+        DeepLink::parse(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("redsys://job/job-1/logs").unwrap(), DeepLink::JobLogs { job_id: "job-1".to_string() });
+        assert_eq!(
+            parse("redsys://connect?token=abc123").unwrap(),
+            DeepLink::Connect { token: "abc123".to_string() }
+        );
+        assert!(parse("redsys://job/../logs").is_err());
+        assert!(parse("redsys://job/job-1").is_err());
+        assert!(parse("redsys://connect?token=").is_err());
+        assert!(parse("https://job/job-1/logs").is_err());
+    }
+
+    #[test]
+    fn test_errors_do_not_leak_tokens() {
+        let error = parse("redsys://connect/now?token=secret").unwrap_err();
+        assert!(!error.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn test_event_does_not_carry_token() {
+        let payload = serde_json::to_value(parse("redsys://connect?token=secret").unwrap()).unwrap();
+        assert_eq!(payload, serde_json::json!({ "type": "Connect" }));
+    }
+
+    #[tokio::test]
+    async fn test_pending_token_taken_once() {
+        let pending = PendingConnect::default();
+        assert_eq!(pending.take().await, None);
+
+        pending.set("first".to_string()).await;
+        pending.set("second".to_string()).await;
+        assert_eq!(pending.take().await.as_deref(), Some("second"));
+        assert_eq!(pending.take().await, None);
+    }
+}
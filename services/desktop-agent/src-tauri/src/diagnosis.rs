@@ -0,0 +1,418 @@
+//! Docker availability diagnosis for RedSys Desktop Agent
+//!
+//! When the daemon cannot be reached, [`diagnose`] classifies the cause so
+//! `DockerStatus::Stopped` and `DockerStatus::Unreachable` can carry a
+//! [`DockerUnavailableReason`] and the frontend can show the matching fix:
+//! - **Not installed**: no Docker socket, named pipe or executable exists
+//! - **Service stopped**: Docker is installed but nothing listens
+//! - **Socket permission denied**: the socket exists but the user may not open
+//!   it; on Linux the socket's group is checked to tell whether the user must
+//!   be added to the `docker` group or only log in again
+//! - **WSL disabled**: Docker Desktop on Windows without the WSL feature
+//! - **TLS misconfigured**: a TLS endpoint reached over plain HTTP
+//!
+//! The diagnosis reads files such as `/etc/group` and looks for the Docker
+//! executable, so it runs on the blocking thread pool.
+//!
+//! ## References
+//! - [Docker Desktop WSL 2 backend](https://docs.docker.com/desktop/features/wsl/)
+//! - [Protect the Docker daemon socket](https://docs.docker.com/engine/security/protect-access/)
+//! - [Linux post-installation steps](https://docs.docker.com/engine/install/linux-postinstall/)
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::path::Path;
+use tokio::task;
+use serde::Serialize;
+
+/// Socket used when no Docker host is configured.
+#[cfg(unix)]
+pub(crate) const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+/// Named pipe used when no Docker host is configured.
+#[cfg(windows)]
+pub(crate) const DEFAULT_SOCKET: &str = r"\\.\pipe\docker_engine";
+
+/// Why the Docker daemon is unavailable.
+///
+/// Uses `#[serde(tag = "type")]` like [`DockerStatus`](crate::docker_monitor::DockerStatus).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(tag = "type")]
+pub enum DockerUnavailableReason {
+    /// Docker does not appear to be installed
+    NotInstalled,
+
+    /// Docker is installed but the daemon is not running
+    ServiceStopped,
+
+    /// The socket exists but the current user may not open it
+    SocketPermissionDenied(SocketPermission),
+
+    /// Docker Desktop needs the Windows Subsystem for Linux, which is off
+    WslDisabled,
+
+    /// The endpoint expects TLS, which the connection does not use
+    TlsMisconfigured { message: String },
+
+    /// The cause could not be determined
+    Unknown,
+}
+
+impl fmt::Display for DockerUnavailableReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DockerUnavailableReason::NotInstalled => write!(f, "Docker is not installed"),
+            DockerUnavailableReason::ServiceStopped => write!(f, "the Docker service is not running"),
+            DockerUnavailableReason::SocketPermissionDenied(permission) => write!(f, "permission denied: {permission}"),
+            DockerUnavailableReason::WslDisabled => write!(f, "WSL is not enabled"),
+            DockerUnavailableReason::TlsMisconfigured { message } => write!(f, "TLS misconfigured: {message}"),
+            DockerUnavailableReason::Unknown => write!(f, "cause unknown"),
+        }
+    }
+}
+
+/// Diagnosis of a Docker socket the current user may not open.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct SocketPermission {
+    /// Socket path
+    pub socket: String,
+
+    /// Group owning the socket, usually `docker`
+    pub group: Option<String>,
+
+    /// The user is not a member of `group` and must be added to it
+    pub add_to_group: bool,
+
+    /// The user was added to `group` after this session started and must log in again
+    pub relogin_required: bool,
+}
+
+impl SocketPermission {
+    /// Diagnosis without group information.
+    fn unknown(socket: &str) -> Self {
+        Self {
+            socket: socket.to_string(),
+            group: None,
+            add_to_group: false,
+            relogin_required: false,
+        }
+    }
+
+    /// Inspects the group ownership of `socket` against the user's groups.
+    /// Blocking.
+    #[cfg(target_os = "linux")]
+    pub fn inspect(socket: &str) -> Self {
+        use std::os::unix::fs::MetadataExt;
+
+        let Ok(metadata) = std::fs::metadata(socket) else {
+            return Self::unknown(socket);
+        };
+        let read = |path: &str| std::fs::read_to_string(path).unwrap_or_default();
+        Self::assess(socket, metadata.gid(), &read("/etc/group"), &read("/etc/passwd"), &read("/proc/self/status"))
+    }
+
+    /// Inspects `socket`; group ownership is only checked on Linux.
+    #[cfg(not(target_os = "linux"))]
+    pub fn inspect(socket: &str) -> Self {
+        Self::unknown(socket)
+    }
+
+    /// Compares the socket group `gid` with the configured and the session's groups.
+    ///
+    /// Group changes only apply to new login sessions, so a user listed in
+    /// `/etc/group` whose process lacks the group has to log in again.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn assess(socket: &str, gid: u32, etc_group: &str, etc_passwd: &str, proc_status: &str) -> Self {
+        let find = |database: &str, id: u32| -> Option<Vec<String>> {
+            database
+                .lines()
+                .map(|line| line.split(':').map(str::to_string).collect::<Vec<_>>())
+                .find(|fields| fields.len() >= 4 && fields[2].parse() == Ok(id))
+        };
+        let ids = |key: &str| -> Vec<u32> {
+            proc_status
+                .lines()
+                .find_map(|line| line.strip_prefix(key))
+                .map(|values| values.split_whitespace().filter_map(|id| id.parse().ok()).collect())
+                .unwrap_or_default()
+        };
+
+        let Some(group) = find(etc_group, gid) else {
+            return Self::unknown(socket);
+        };
+        let user = ids("Uid:").first().and_then(|&uid| find(etc_passwd, uid));
+        let configured = user.as_ref().is_some_and(|user| {
+            user[3].parse() == Ok(gid) || group[3].split(',').any(|member| member == user[0])
+        });
+        let in_session = ids("Gid:").first() == Some(&gid) || ids("Groups:").contains(&gid);
+
+        Self {
+            socket: socket.to_string(),
+            group: Some(group[0].clone()),
+            add_to_group: !configured && !in_session,
+            relogin_required: configured && !in_session,
+        }
+    }
+}
+
+impl fmt::Display for SocketPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.group {
+            Some(group) if self.add_to_group => write!(
+                f,
+                "{} belongs to group '{group}'; add your user with `sudo usermod -aG {group} $USER`, then log in again",
+                self.socket
+            ),
+            Some(group) if self.relogin_required => {
+                write!(f, "your user joined group '{group}' after logging in; log out and back in")
+            }
+            _ => write!(f, "{} cannot be opened by the current user", self.socket),
+        }
+    }
+}
+
+/// How a connection attempt failed, read from the error chain.
+#[derive(Debug, Clone, PartialEq)]
+enum Failure {
+    /// The socket or pipe does not exist
+    Missing,
+
+    /// Nothing accepted the connection
+    Refused,
+
+    /// Opening the socket was not permitted
+    PermissionDenied,
+
+    /// The server speaks TLS
+    Tls(String),
+}
+
+impl Failure {
+    /// Reads the failure from a bollard error, `None` when the daemon answered.
+    fn of(error: &bollard::errors::Error) -> Option<Self> {
+        use bollard::errors::Error;
+
+        match error {
+            Error::SocketNotFoundError(_) => return Some(Failure::Missing),
+            Error::DockerResponseServerError { status_code: 400, message } if message.contains("HTTPS") => {
+                return Some(Failure::Tls(message.clone()));
+            }
+            Error::DockerResponseServerError { .. } | Error::RequestTimeoutError => return None,
+            _ => {}
+        }
+
+        let mut source: Option<&(dyn StdError + 'static)> = Some(error);
+        while let Some(current) = source {
+            let message = current.to_string().to_lowercase();
+            if message.contains("certificate") || message.contains("tls") || message.contains("handshake") {
+                return Some(Failure::Tls(current.to_string()));
+            }
+            if let Some(io) = current.downcast_ref::<std::io::Error>() {
+                return match io.kind() {
+                    ErrorKind::NotFound => Some(Failure::Missing),
+                    ErrorKind::PermissionDenied => Some(Failure::PermissionDenied),
+                    _ => Some(Failure::Refused),
+                };
+            }
+            source = current.source();
+        }
+        // A connection-level error without an I/O cause, e.g. a closed connection
+        Some(Failure::Refused)
+    }
+}
+
+/// Host facts the diagnosis depends on.
+#[derive(Debug, Clone, Default)]
+struct Environment {
+    /// A Docker executable or installation directory exists
+    docker_installed: bool,
+
+    /// Docker Desktop is installed on Windows
+    docker_desktop_windows: bool,
+
+    /// The Windows Subsystem for Linux is enabled
+    wsl_enabled: bool,
+
+    /// `DOCKER_TLS_VERIFY` or `DOCKER_CERT_PATH` asks for TLS
+    tls_requested: bool,
+}
+
+impl Environment {
+    fn probe() -> Self {
+        Self {
+            docker_installed: docker_installed(),
+            docker_desktop_windows: cfg!(windows) && docker_desktop_windows(),
+            wsl_enabled: wsl_enabled(),
+            tls_requested: std::env::var_os("DOCKER_TLS_VERIFY").is_some_and(|v| !v.is_empty())
+                || std::env::var_os("DOCKER_CERT_PATH").is_some(),
+        }
+    }
+}
+
+/// Classifies why the daemon at `endpoint` (the default socket when `None`)
+/// is unavailable, `None` when `error` came from a daemon that answered.
+///
+/// The returned future does not borrow `error`.
+pub fn diagnose(
+    error: &bollard::errors::Error,
+    endpoint: Option<&str>,
+) -> impl Future<Output = Option<DockerUnavailableReason>> + Send + 'static {
+    let failure = Failure::of(error);
+    let endpoint = endpoint.map(str::to_string);
+    async move {
+        let failure = failure?;
+        task::spawn_blocking(move || classify(failure, endpoint.as_deref(), &Environment::probe()))
+            .await
+            .ok()
+    }
+}
+
+fn classify(failure: Failure, endpoint: Option<&str>, environment: &Environment) -> DockerUnavailableReason {
+    let tcp = endpoint.is_some_and(|endpoint| endpoint.starts_with("tcp://") || endpoint.starts_with("http://"));
+
+    match failure {
+        Failure::Tls(message) => DockerUnavailableReason::TlsMisconfigured { message },
+        Failure::PermissionDenied => {
+            DockerUnavailableReason::SocketPermissionDenied(SocketPermission::inspect(socket_path(endpoint)))
+        }
+        // TLS endpoints are served on port 2376 by convention; this build speaks plain HTTP
+        Failure::Refused if tcp && (environment.tls_requested || endpoint.is_some_and(|e| e.ends_with(":2376"))) => {
+            DockerUnavailableReason::TlsMisconfigured {
+                message: "The Docker host expects TLS, which this agent does not use".to_string(),
+            }
+        }
+        _ if tcp => DockerUnavailableReason::Unknown,
+        _ if environment.docker_desktop_windows && !environment.wsl_enabled => DockerUnavailableReason::WslDisabled,
+        Failure::Missing if !environment.docker_installed => DockerUnavailableReason::NotInstalled,
+        Failure::Missing | Failure::Refused => DockerUnavailableReason::ServiceStopped,
+    }
+}
+
+/// Diagnoses `error` if it is a socket permission failure.
+///
+/// The returned future does not borrow `error`.
+pub fn socket_permission(
+    error: &bollard::errors::Error,
+    endpoint: Option<&str>,
+) -> impl Future<Output = Option<SocketPermission>> + Send + 'static {
+    let denied = Failure::of(error) == Some(Failure::PermissionDenied);
+    let socket = socket_path(endpoint).to_string();
+    async move {
+        if !denied {
+            return None;
+        }
+        task::spawn_blocking(move || SocketPermission::inspect(&socket)).await.ok()
+    }
+}
+
+/// Socket path of a `unix://` or `npipe://` endpoint.
+pub(crate) fn socket_path(endpoint: Option<&str>) -> &str {
+    endpoint
+        .and_then(|endpoint| endpoint.strip_prefix("unix://").or_else(|| endpoint.strip_prefix("npipe://")))
+        .unwrap_or(DEFAULT_SOCKET)
+}
+
+/// Whether a Docker executable is on `PATH` or in a standard install location.
+fn docker_installed() -> bool {
+    let executable = if cfg!(windows) { "docker.exe" } else { "docker" };
+    let on_path = std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(executable).is_file()));
+
+    on_path
+        || Path::new("/usr/bin/dockerd").exists()
+        || Path::new("/Applications/Docker.app").exists()
+        || docker_desktop_windows()
+}
+
+/// Whether Docker Desktop is installed under `Program Files`.
+fn docker_desktop_windows() -> bool {
+    std::env::var_os("ProgramFiles")
+        .is_some_and(|dir| Path::new(&dir).join(r"Docker\Docker\Docker Desktop.exe").exists())
+}
+
+/// Whether the WSL optional feature is enabled; always `true` off Windows.
+fn wsl_enabled() -> bool {
+    if !cfg!(windows) {
+        return true;
+    }
+    // The LxssManager service only exists once the feature is enabled
+    std::env::var_os("SystemRoot")
+        .is_some_and(|root| Path::new(&root).join(r"System32\lxss\LxssManager.dll").exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn io_error(kind: ErrorKind) -> bollard::errors::Error {
+        std::io::Error::from(kind).into()
+    }
+
+    #[test]
+    fn test_failure_from_error_chain() {
+        assert_eq!(Failure::of(&io_error(ErrorKind::PermissionDenied)), Some(Failure::PermissionDenied));
+        assert_eq!(Failure::of(&io_error(ErrorKind::ConnectionRefused)), Some(Failure::Refused));
+        assert_eq!(
+            Failure::of(&bollard::errors::Error::SocketNotFoundError("/var/run/docker.sock".to_string())),
+            Some(Failure::Missing)
+        );
+        let https = bollard::errors::Error::DockerResponseServerError {
+            status_code: 400,
+            message: "Client sent an HTTP request to an HTTPS server.".to_string(),
+        };
+        assert!(matches!(Failure::of(&https), Some(Failure::Tls(_))));
+        let answered = bollard::errors::Error::DockerResponseServerError { status_code: 404, message: "page not found".to_string() };
+        assert_eq!(Failure::of(&answered), None);
+    }
+
+    #[test]
+    fn test_classify_local_failures() {
+        let installed = Environment { docker_installed: true, wsl_enabled: true, ..Environment::default() };
+        let missing = Environment { wsl_enabled: true, ..Environment::default() };
+
+        assert_eq!(classify(Failure::Missing, None, &missing), DockerUnavailableReason::NotInstalled);
+        assert_eq!(classify(Failure::Missing, None, &installed), DockerUnavailableReason::ServiceStopped);
+        assert_eq!(classify(Failure::Refused, None, &installed), DockerUnavailableReason::ServiceStopped);
+        assert_eq!(
+            classify(Failure::PermissionDenied, Some("unix:///nonexistent/docker.sock"), &installed),
+            DockerUnavailableReason::SocketPermissionDenied(SocketPermission::unknown("/nonexistent/docker.sock"))
+        );
+
+        let no_wsl = Environment { docker_installed: true, docker_desktop_windows: true, ..Environment::default() };
+        assert_eq!(classify(Failure::Missing, None, &no_wsl), DockerUnavailableReason::WslDisabled);
+    }
+
+    #[test]
+    fn test_socket_group_membership() {
+        let passwd = "root:x:0:0::/root:/bin/bash\nalice:x:1000:1000::/home/alice:/bin/bash\n";
+        let status = |groups: &str| format!("Name:\tagent\nUid:\t1000\t1000\t1000\t1000\nGid:\t1000\t1000\t1000\t1000\nGroups:\t{groups}\n");
+
+        let outsider = SocketPermission::assess("/var/run/docker.sock", 999, "docker:x:999:bob\n", passwd, &status("27"));
+        assert_eq!(outsider.group.as_deref(), Some("docker"));
+        assert!(outsider.add_to_group && !outsider.relogin_required);
+        assert!(outsider.to_string().contains("sudo usermod -aG docker $USER"));
+
+        let stale = SocketPermission::assess("/var/run/docker.sock", 999, "docker:x:999:bob,alice\n", passwd, &status("27"));
+        assert!(!stale.add_to_group && stale.relogin_required);
+
+        let member = SocketPermission::assess("/var/run/docker.sock", 999, "docker:x:999:alice\n", passwd, &status("27 999"));
+        assert!(!member.add_to_group && !member.relogin_required);
+
+        let unknown_group = SocketPermission::assess("/var/run/docker.sock", 998, "docker:x:999:\n", passwd, &status(""));
+        assert_eq!(unknown_group, SocketPermission::unknown("/var/run/docker.sock"));
+    }
+
+    #[test]
+    fn test_classify_remote_failures() {
+        let environment = Environment::default();
+        assert!(matches!(
+            classify(Failure::Refused, Some("tcp://10.0.0.5:2376"), &environment),
+            DockerUnavailableReason::TlsMisconfigured { .. }
+        ));
+        assert_eq!(classify(Failure::Refused, Some("tcp://10.0.0.5:2375"), &environment), DockerUnavailableReason::Unknown);
+    }
+}
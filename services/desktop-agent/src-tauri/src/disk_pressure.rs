@@ -0,0 +1,337 @@
+//! Disk space alerts for RedSys Desktop Agent
+//!
+//! Image pulls and job outputs fail half-way when a disk fills up, so the
+//! agent watches the free space of the partitions it writes to:
+//! - the Docker data root (`docker info` `DockerRootDir`), when it is on the
+//!   host file system rather than inside a Docker Desktop VM
+//! - the job workspace root
+//! - any `disk_pressure.paths` from the config file
+//!
+//! Free space below `disk_pressure.warning_free_percent` marks the agent
+//! degraded in its capability report so the platform stops scheduling jobs
+//! onto it; `disk_pressure.critical_free_percent` flags partitions that are
+//! about to fill up.
+//!
+//! ## Events
+//! `disk-pressure` carries a [`DiskPressure`] whenever the level of a watched
+//! partition changes, including the recovery back to `ok`.
+//!
+//! ## References
+//! - [sysinfo Disks](https://docs.rs/sysinfo/latest/sysinfo/struct.Disks.html)
+//! - [Docker data root](https://docs.docker.com/engine/daemon/#daemon-data-directory)
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::{sync::watch, task, time::{interval, Duration, MissedTickBehavior}};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use serde::Serialize;
+use sysinfo::Disks;
+
+use crate::config::DiskPressureConfig;
+use crate::docker::DockerService;
+use crate::telemetry;
+
+/// Severity of low free space on a partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskPressureLevel {
+    /// Enough free space
+    Ok,
+
+    /// Below the warning threshold, the agent reports itself degraded
+    Warning,
+
+    /// Below the critical threshold
+    Critical,
+}
+
+/// Free space thresholds in percent of the partition size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskThresholds {
+    /// Warning below this much free space
+    pub warning_free_percent: f64,
+
+    /// Critical below this much free space
+    pub critical_free_percent: f64,
+}
+
+impl DiskThresholds {
+    /// Classifies a free space percentage.
+    pub fn level(&self, free_percent: f64) -> DiskPressureLevel {
+        if free_percent < self.critical_free_percent {
+            DiskPressureLevel::Critical
+        } else if free_percent < self.warning_free_percent {
+            DiskPressureLevel::Warning
+        } else {
+            DiskPressureLevel::Ok
+        }
+    }
+}
+
+/// Free space of a watched partition, the payload of the `disk-pressure` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiskPressure {
+    /// Watched path, e.g. `/var/lib/docker`
+    pub path: PathBuf,
+
+    /// Mount point of the partition holding `path`
+    pub mount_point: PathBuf,
+
+    /// Pressure level
+    pub level: DiskPressureLevel,
+
+    /// Free space in percent
+    pub free_percent: f64,
+
+    /// Free bytes
+    pub available_bytes: u64,
+
+    /// Partition size in bytes
+    pub total_bytes: u64,
+}
+
+/// Size and free space of a mounted partition.
+#[derive(Debug, Clone, PartialEq)]
+struct MountUsage {
+    mount_point: PathBuf,
+    total_bytes: u64,
+    available_bytes: u64,
+}
+
+/// Finds the partition holding `path`, the mount point with the longest
+/// matching prefix.
+fn find_mount<'a>(path: &Path, mounts: &'a [MountUsage]) -> Option<&'a MountUsage> {
+    mounts
+        .iter()
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.components().count())
+}
+
+/// Evaluates the watched paths against the mounted partitions.
+fn evaluate(paths: &[PathBuf], mounts: &[MountUsage], thresholds: DiskThresholds) -> Vec<DiskPressure> {
+    let mut pressures: Vec<DiskPressure> = Vec::new();
+    for path in paths {
+        let Some(mount) = find_mount(path, mounts) else {
+            debug!("No mounted partition holds {}", path.display());
+            continue;
+        };
+        // Several paths on one partition share the first path's report
+        if mount.total_bytes == 0 || pressures.iter().any(|pressure| pressure.mount_point == mount.mount_point) {
+            continue;
+        }
+        let free_percent = mount.available_bytes as f64 * 100.0 / mount.total_bytes as f64;
+        pressures.push(DiskPressure {
+            path: path.clone(),
+            mount_point: mount.mount_point.clone(),
+            level: thresholds.level(free_percent),
+            free_percent,
+            available_bytes: mount.available_bytes,
+            total_bytes: mount.total_bytes,
+        });
+    }
+    pressures
+}
+
+/// Gets the free bytes on the partition holding `path`. Blocking.
+pub fn available_space(path: &Path) -> Option<u64> {
+    find_mount(path, &mounted_partitions()).map(|mount| mount.available_bytes)
+}
+
+/// Reads the mounted partitions. Blocking.
+fn mounted_partitions() -> Vec<MountUsage> {
+    Disks::new_with_refreshed_list()
+        .list()
+        .iter()
+        .map(|disk| MountUsage {
+            mount_point: disk.mount_point().to_path_buf(),
+            total_bytes: disk.total_space(),
+            available_bytes: disk.available_space(),
+        })
+        .collect()
+}
+
+/// Watches free disk space and reports pressure changes.
+#[derive(Debug)]
+pub struct DiskPressureMonitor {
+    /// Free space thresholds
+    thresholds: DiskThresholds,
+
+    /// Watched paths besides the Docker data root
+    paths: Vec<PathBuf>,
+
+    /// Interval between checks
+    check_interval: Duration,
+
+    /// Latest check result
+    pressures: watch::Sender<Vec<DiskPressure>>,
+
+    /// Service reporting the Docker data root, if it is watched
+    docker_service: Option<Arc<DockerService>>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl DiskPressureMonitor {
+    /// Creates a monitor from the `[disk_pressure]` config section.
+    pub fn new(config: &DiskPressureConfig, cancellation_token: CancellationToken) -> Self {
+        Self {
+            thresholds: DiskThresholds {
+                warning_free_percent: config.warning_free_percent,
+                critical_free_percent: config.critical_free_percent,
+            },
+            paths: config.paths.clone(),
+            check_interval: Duration::from_secs(config.check_interval_secs),
+            pressures: watch::Sender::new(Vec::new()),
+            docker_service: None,
+            cancellation_token,
+        }
+    }
+
+    /// Additionally watches `path`, e.g. the job workspace root.
+    pub fn with_path(mut self, path: PathBuf) -> Self {
+        self.paths.push(path);
+        self
+    }
+
+    /// Additionally watches the data root of the daemon behind
+    /// `docker_service`.
+    pub fn with_docker_service(self, docker_service: Arc<DockerService>) -> Self {
+        Self { docker_service: Some(docker_service), ..self }
+    }
+
+    /// Gets the result of the latest check.
+    pub fn pressures(&self) -> Vec<DiskPressure> {
+        self.pressures.borrow().clone()
+    }
+
+    /// Gets the watched partitions below the warning threshold.
+    pub fn degraded(&self) -> Vec<DiskPressure> {
+        self.pressures
+            .borrow()
+            .iter()
+            .filter(|pressure| pressure.level != DiskPressureLevel::Ok)
+            .cloned()
+            .collect()
+    }
+
+    /// Looks up the Docker data root if it is on the host file system.
+    async fn docker_root_dir(&self) -> Option<PathBuf> {
+        let info = self.docker_service.as_ref()?.get_info().await.ok()?;
+        let root = PathBuf::from(info.data_root?);
+        // Docker Desktop reports a path inside its VM
+        root.exists().then_some(root)
+    }
+
+    /// Checks the free space of all watched paths now.
+    pub async fn check(&self) -> Vec<DiskPressure> {
+        let mut paths = Vec::new();
+        paths.extend(self.docker_root_dir().await);
+        paths.extend(self.paths.iter().cloned());
+
+        let thresholds = self.thresholds;
+        let pressures = task::spawn_blocking(move || evaluate(&paths, &mounted_partitions(), thresholds))
+            .await
+            .unwrap_or_default();
+        self.pressures.send_replace(pressures.clone());
+        pressures
+    }
+
+    /// Starts periodic checks, emitting `disk-pressure` on level changes.
+    pub async fn start(self: Arc<Self>, app_handle: tauri::AppHandle) {
+        info!(
+            "Starting disk pressure checks every {}s (warning below {}% free, critical below {}%)",
+            self.check_interval.as_secs(),
+            self.thresholds.warning_free_percent,
+            self.thresholds.critical_free_percent
+        );
+
+        let mut ticker = interval(self.check_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // Levels last announced per mount point; on-demand checks do not announce
+        let mut reported: HashMap<PathBuf, DiskPressureLevel> = HashMap::new();
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    for pressure in self.check().await {
+                        let previous_level = reported
+                            .insert(pressure.mount_point.clone(), pressure.level)
+                            .unwrap_or(DiskPressureLevel::Ok);
+                        if pressure.level == previous_level {
+                            continue;
+                        }
+
+                        match pressure.level {
+                            DiskPressureLevel::Ok => info!(
+                                "Disk pressure on {} resolved, {:.1}% free", pressure.mount_point.display(), pressure.free_percent
+                            ),
+                            level => warn!(
+                                "Disk pressure {:?} on {}, {:.1}% free", level, pressure.mount_point.display(), pressure.free_percent
+                            ),
+                        }
+                        if let Err(e) = telemetry::emit(&app_handle, "disk-pressure", &pressure) {
+                            error!("Failed to emit disk-pressure event: {e}");
+                        }
+                    }
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Disk pressure checks received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: DiskThresholds = DiskThresholds {
+        warning_free_percent: 15.0,
+        critical_free_percent: 5.0,
+    };
+
+    fn mount(mount_point: &str, total_bytes: u64, available_bytes: u64) -> MountUsage {
+        MountUsage {
+            mount_point: PathBuf::from(mount_point),
+            total_bytes,
+            available_bytes,
+        }
+    }
+
+    #[test]
+    fn test_threshold_levels() {
+        assert_eq!(THRESHOLDS.level(50.0), DiskPressureLevel::Ok);
+        assert_eq!(THRESHOLDS.level(15.0), DiskPressureLevel::Ok);
+        assert_eq!(THRESHOLDS.level(14.9), DiskPressureLevel::Warning);
+        assert_eq!(THRESHOLDS.level(4.0), DiskPressureLevel::Critical);
+    }
+
+    #[test]
+    fn test_paths_map_to_longest_mount_point() {
+        let mounts = [mount("/", 1000, 500), mount("/var/lib/docker", 1000, 100), mount("/var", 1000, 30)];
+        let paths = [PathBuf::from("/var/lib/docker"), PathBuf::from("/var/log"), PathBuf::from("/home/agent")];
+
+        let pressures = evaluate(&paths, &mounts, THRESHOLDS);
+        assert_eq!(pressures.len(), 3);
+        assert_eq!(pressures[0].mount_point, PathBuf::from("/var/lib/docker"));
+        assert_eq!(pressures[0].level, DiskPressureLevel::Warning);
+        assert_eq!(pressures[1].mount_point, PathBuf::from("/var"));
+        assert_eq!(pressures[1].level, DiskPressureLevel::Critical);
+        assert_eq!(pressures[2].level, DiskPressureLevel::Ok);
+    }
+
+    #[test]
+    fn test_shared_partition_reported_once() {
+        let mounts = [mount("/", 1000, 500)];
+        let paths = [PathBuf::from("/var/lib/docker"), PathBuf::from("/home/agent/workspaces")];
+
+        let pressures = evaluate(&paths, &mounts, THRESHOLDS);
+        assert_eq!(pressures.len(), 1);
+        assert_eq!(pressures[0].path, PathBuf::from("/var/lib/docker"));
+    }
+}
@@ -0,0 +1,1913 @@
+//! Docker Engine Service for RedSys Desktop Agent
+//!
+//! This module provides on-demand Docker Engine operations that complement the
+//! liveness polling performed by [`DockerMonitor`](crate::docker_monitor::DockerMonitor).
+//!
+//! ## Features
+//! - **Disk usage reporting** equivalent to `docker system df`
+//! - **Slow-interval background reporting** via the `docker-disk-usage` event
+//! - **Daemon information** from `docker info`, cached while the daemon is up
+//! - **Install flavor detection** (Docker Desktop, standalone, rootless,
+//!   Colima, Rancher Desktop, Podman machine) for targeted troubleshooting
+//! - **Container listing** including HEALTHCHECK state, filtered, sorted and
+//!   paginated with [`ContainerQuery`]
+//! - **Container start/stop** for remote orchestration
+//! - **Container inspection** of changed files (`docker diff`) and running
+//!   processes (`docker top`) without exec'ing into the container
+//! - **Docker events stream** forwarded as `docker-event`, with dedicated
+//!   `container-health-changed` events for health transitions
+//! - **In-process event subscription** via [`DockerService::subscribe_events`]
+//! - Reports, events and health transitions are published to the
+//!   [`Bus`](crate::events::Bus), which forwards them to the frontend
+//! - **Runtime-configurable event filters** (types, labels, container name prefixes)
+//! - **Gap-free reconnection** of the events stream using exponential backoff
+//!   and `since = last_seen_timestamp`
+//! - **Events stream watchdog** that restarts a terminated stream once the
+//!   daemon is back up, counting the restarts
+//! - **Graceful shutdown** using Tokio CancellationToken
+//!
+//! ## References
+//! - [Docker System DF](https://docs.docker.com/engine/reference/commandline/system_df/)
+//! - [Bollard System API](https://docs.rs/bollard/latest/bollard/struct.Docker.html#method.df)
+//! - [Docker System Info](https://docs.docker.com/engine/api/v1.47/#tag/System/operation/SystemInfo)
+//! - [Docker Events](https://docs.docker.com/engine/reference/commandline/system_events/)
+//! - [Dockerfile HEALTHCHECK](https://docs.docker.com/engine/reference/builder/#healthcheck)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::{sync::{broadcast, watch, Notify, RwLock}, time::{interval, Duration}};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, info_span, warn, Instrument};
+use bollard::models::{
+    ContainerSummary, ContainerSummaryStateEnum, ContainerTopResponse, EventMessage, FilesystemChange,
+    SystemDataUsageResponse, SystemInfo,
+};
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::client::DockerClient;
+use crate::connection;
+use crate::docker_monitor::{DockerMonitor, DockerMonitorError, DockerMonitorResult, DockerStatus};
+use crate::error::{AppError, AppResult};
+use crate::events::{Bus, ContainerEvent, Event, SystemMetric};
+use crate::jobs::REDSYS_JOB_ID_LABEL;
+use crate::listing::{self, Page, PageRequest, SortOrder};
+use crate::pause::PauseControl;
+use crate::ports::PublishedPort;
+
+/// Label marking containers, volumes and images owned by the RedSys agent.
+pub const REDSYS_MANAGED_LABEL: &str = "redsys.managed";
+
+/// Object types accepted by the Docker events `type` filter.
+const KNOWN_EVENT_TYPES: &[&str] = &[
+    "builder", "config", "container", "daemon", "image", "network",
+    "node", "plugin", "secret", "service", "volume",
+];
+
+/// Initial delay before reconnecting a failed events stream.
+const EVENTS_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound for the events stream reconnect delay.
+const EVENTS_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Capacity of the in-process events channel.
+const EVENTS_CHANNEL_CAPACITY: usize = 256;
+
+/// Default interval between background disk usage reports.
+///
+/// `docker system df` walks every layer and volume, so it is deliberately slow.
+pub const DEFAULT_DISK_USAGE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Upper bound for a single disk usage query on hosts with large volumes.
+const DISK_USAGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Upper bound for a `docker info` query.
+const INFO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Disk usage of a single Docker object category.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DiskUsageCategory {
+    /// Number of objects in this category
+    pub total_count: u64,
+
+    /// Number of objects currently in use
+    pub active_count: u64,
+
+    /// Total size on disk in bytes
+    pub size_bytes: u64,
+
+    /// Bytes that a prune would reclaim
+    pub reclaimable_bytes: u64,
+}
+
+/// Docker disk usage report, equivalent to `docker system df`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DockerDiskUsage {
+    /// Image layer usage
+    pub images: DiskUsageCategory,
+
+    /// Container writable layer usage
+    pub containers: DiskUsageCategory,
+
+    /// Local volume usage
+    pub volumes: DiskUsageCategory,
+
+    /// BuildKit cache usage
+    pub build_cache: DiskUsageCategory,
+
+    /// Sum of all category sizes in bytes
+    pub total_bytes: u64,
+
+    /// Sum of all reclaimable bytes
+    pub reclaimable_bytes: u64,
+
+    /// When the report was collected
+    pub collected_at: DateTime<Utc>,
+}
+
+impl DockerDiskUsage {
+    /// Builds a report from the raw `/system/df` response.
+    ///
+    /// Reclaimable space follows the Docker CLI rules: unused images, stopped
+    /// containers, unreferenced volumes and build cache that is neither in use
+    /// nor shared.
+    pub fn from_response(response: &SystemDataUsageResponse) -> Self {
+        let mut images = DiskUsageCategory {
+            size_bytes: non_negative(response.layers_size.unwrap_or(0)),
+            ..Default::default()
+        };
+        let mut used_image_bytes = 0;
+        for image in response.images.iter().flatten() {
+            images.total_count += 1;
+            if image.containers > 0 {
+                images.active_count += 1;
+                used_image_bytes += non_negative(image.size - image.shared_size.max(0));
+            }
+        }
+        images.reclaimable_bytes = images.size_bytes.saturating_sub(used_image_bytes);
+
+        let mut containers = DiskUsageCategory::default();
+        for container in response.containers.iter().flatten() {
+            let size = non_negative(container.size_rw.unwrap_or(0));
+            containers.total_count += 1;
+            containers.size_bytes += size;
+            if matches!(container.state, Some(ContainerSummaryStateEnum::RUNNING)) {
+                containers.active_count += 1;
+            } else {
+                containers.reclaimable_bytes += size;
+            }
+        }
+
+        let mut volumes = DiskUsageCategory::default();
+        for volume in response.volumes.iter().flatten() {
+            let (size, ref_count) = volume
+                .usage_data
+                .as_ref()
+                .map(|usage| (non_negative(usage.size), usage.ref_count))
+                .unwrap_or((0, 0));
+            volumes.total_count += 1;
+            volumes.size_bytes += size;
+            if ref_count > 0 {
+                volumes.active_count += 1;
+            } else {
+                volumes.reclaimable_bytes += size;
+            }
+        }
+
+        let mut build_cache = DiskUsageCategory::default();
+        for record in response.build_cache.iter().flatten() {
+            let size = non_negative(record.size.unwrap_or(0));
+            let in_use = record.in_use.unwrap_or(false);
+            build_cache.total_count += 1;
+            build_cache.size_bytes += size;
+            if in_use {
+                build_cache.active_count += 1;
+            } else if !record.shared.unwrap_or(false) {
+                build_cache.reclaimable_bytes += size;
+            }
+        }
+
+        let categories = [&images, &containers, &volumes, &build_cache];
+        let total_bytes = categories.iter().map(|c| c.size_bytes).sum();
+        let reclaimable_bytes = categories.iter().map(|c| c.reclaimable_bytes).sum();
+
+        Self {
+            images,
+            containers,
+            volumes,
+            build_cache,
+            total_bytes,
+            reclaimable_bytes,
+            collected_at: Utc::now(),
+        }
+    }
+}
+
+/// Resource limits the host kernel lets Docker enforce.
+///
+/// Kernels without the cgroup controllers, cgroup v1 hosts booted without
+/// swap accounting and rootless daemons without cgroup delegation lack some
+/// of them. Limits the daemon does not report are assumed enforceable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LimitSupport {
+    /// Cgroup version, `1` or `2`, if reported
+    pub cgroup_version: Option<u8>,
+
+    /// Memory limits
+    pub memory: bool,
+
+    /// Swap limits; without them a memory-limited container may still swap
+    pub swap: bool,
+
+    /// CPU quotas, i.e. limits in cores
+    pub cpu_quota: bool,
+
+    /// CPU shares
+    pub cpu_shares: bool,
+
+    /// Process count limits
+    pub pids: bool,
+}
+
+impl Default for LimitSupport {
+    fn default() -> Self {
+        Self {
+            cgroup_version: None,
+            memory: true,
+            swap: true,
+            cpu_quota: true,
+            cpu_shares: true,
+            pids: true,
+        }
+    }
+}
+
+impl LimitSupport {
+    /// Reads the enforceable limits from the raw `/info` response.
+    pub fn from_info(info: &SystemInfo) -> Self {
+        Self {
+            cgroup_version: info.cgroup_version.as_ref().and_then(|version| version.to_string().parse().ok()),
+            memory: info.memory_limit.unwrap_or(true),
+            swap: info.swap_limit.unwrap_or(true),
+            cpu_quota: info.cpu_cfs_quota.unwrap_or(true),
+            cpu_shares: info.cpu_shares.unwrap_or(true),
+            pids: info.pids_limit.unwrap_or(true),
+        }
+    }
+}
+
+/// Docker reports unknown sizes as `-1`; treat those as zero.
+fn non_negative(value: i64) -> u64 {
+    value.max(0) as u64
+}
+
+/// Kind of Docker installation serving the API.
+///
+/// Troubleshooting differs per flavor: Docker Desktop is restarted from its
+/// app, a standalone engine through systemd, Colima with `colima start`, etc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DockerFlavor {
+    /// Docker Desktop on Windows, macOS or Linux
+    DockerDesktop,
+
+    /// System-wide `dockerd`, e.g. from distribution packages
+    Standalone,
+
+    /// `dockerd` running in rootless mode under the current user
+    Rootless,
+
+    /// Colima VM on macOS or Linux
+    Colima,
+
+    /// Rancher Desktop in `dockerd` (moby) mode
+    RancherDesktop,
+
+    /// Podman machine exposing the Docker-compatible API
+    PodmanMachine,
+}
+
+impl DockerFlavor {
+    /// Detects the flavor from `docker info` and the endpoint the agent connects to.
+    ///
+    /// Socket paths are distinctive for the VM-based installs but Docker
+    /// Desktop also links the standard `/var/run/docker.sock`, so the
+    /// reported operating system is checked first.
+    pub fn detect(info: &SystemInfo, endpoint: Option<&str>) -> Self {
+        let operating_system = info.operating_system.as_deref().unwrap_or_default().to_lowercase();
+        let name = info.name.as_deref().unwrap_or_default().to_lowercase();
+        let endpoint = endpoint.unwrap_or_default().to_lowercase();
+        let rootless = info
+            .security_options
+            .iter()
+            .flatten()
+            .any(|option| option.split(',').any(|part| part == "name=rootless"));
+
+        if operating_system.contains("docker desktop") {
+            DockerFlavor::DockerDesktop
+        } else if endpoint.contains("podman") || operating_system.contains("podman") {
+            DockerFlavor::PodmanMachine
+        } else if endpoint.contains(".colima") || name == "colima" {
+            DockerFlavor::Colima
+        } else if endpoint.contains(".rd/") || name.contains("rancher-desktop") {
+            DockerFlavor::RancherDesktop
+        } else if rootless {
+            DockerFlavor::Rootless
+        } else {
+            DockerFlavor::Standalone
+        }
+    }
+}
+
+/// Daemon information, a typed subset of `docker info`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DockerInfo {
+    /// Docker Engine version
+    pub server_version: Option<String>,
+
+    /// Storage driver, e.g. `overlay2`
+    pub storage_driver: Option<String>,
+
+    /// Cgroup version, `1` or `2`
+    pub cgroup_version: Option<String>,
+
+    /// Cgroup driver, e.g. `systemd`
+    pub cgroup_driver: Option<String>,
+
+    /// Docker data root, inside the VM for Docker Desktop
+    pub data_root: Option<String>,
+
+    /// Number of containers
+    pub containers: u64,
+
+    /// Number of running containers
+    pub containers_running: u64,
+
+    /// Number of images
+    pub images: u64,
+
+    /// Local swarm node state, e.g. `inactive` or `active`
+    pub swarm_state: Option<String>,
+
+    /// Registered container runtimes, sorted by name
+    pub runtimes: Vec<String>,
+
+    /// Runtime used when none is requested
+    pub default_runtime: Option<String>,
+
+    /// Kind of installation serving the API
+    pub flavor: DockerFlavor,
+
+    /// Resource limits the host can enforce
+    pub limit_support: LimitSupport,
+
+    /// Configuration warnings of the daemon, e.g. an API exposed without TLS
+    pub warnings: Vec<String>,
+
+    /// When the information was collected
+    pub collected_at: DateTime<Utc>,
+}
+
+impl DockerInfo {
+    /// Builds the subset from the raw `/info` response, received via `endpoint`.
+    pub fn from_response(info: &SystemInfo, endpoint: Option<&str>) -> Self {
+        let mut runtimes: Vec<String> = info.runtimes.iter().flat_map(|runtimes| runtimes.keys().cloned()).collect();
+        runtimes.sort();
+
+        Self {
+            server_version: info.server_version.clone(),
+            storage_driver: info.driver.clone(),
+            cgroup_version: info.cgroup_version.as_ref().map(ToString::to_string).filter(|v| !v.is_empty()),
+            cgroup_driver: info.cgroup_driver.as_ref().map(ToString::to_string).filter(|d| !d.is_empty()),
+            data_root: info.docker_root_dir.clone(),
+            containers: non_negative(info.containers.unwrap_or(0)),
+            containers_running: non_negative(info.containers_running.unwrap_or(0)),
+            images: non_negative(info.images.unwrap_or(0)),
+            swarm_state: info
+                .swarm
+                .as_ref()
+                .and_then(|swarm| swarm.local_node_state.as_ref())
+                .map(ToString::to_string)
+                .filter(|state| !state.is_empty()),
+            runtimes,
+            default_runtime: info.default_runtime.clone(),
+            flavor: DockerFlavor::detect(info, endpoint),
+            limit_support: LimitSupport::from_info(info),
+            warnings: info.warnings.clone().unwrap_or_default(),
+            collected_at: Utc::now(),
+        }
+    }
+}
+
+/// Docker HEALTHCHECK state of a container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerHealth {
+    /// Health check is passing
+    Healthy,
+
+    /// Health check is failing
+    Unhealthy,
+
+    /// Container is within its health check start period
+    Starting,
+
+    /// Container has no health check configured
+    None,
+}
+
+impl ContainerHealth {
+    /// Parses the health state from a container list status string,
+    /// e.g. `Up 5 minutes (healthy)` or `Up 3 seconds (health: starting)`.
+    pub fn from_status_text(status: &str) -> Self {
+        if status.contains("(healthy)") {
+            ContainerHealth::Healthy
+        } else if status.contains("(unhealthy)") {
+            ContainerHealth::Unhealthy
+        } else if status.contains("(health: starting)") {
+            ContainerHealth::Starting
+        } else {
+            ContainerHealth::None
+        }
+    }
+
+    /// Parses the health state from an event action such as `health_status: healthy`.
+    pub fn from_event_action(action: &str) -> Option<Self> {
+        match action.strip_prefix("health_status:")?.trim() {
+            "healthy" => Some(ContainerHealth::Healthy),
+            "unhealthy" => Some(ContainerHealth::Unhealthy),
+            "starting" => Some(ContainerHealth::Starting),
+            _ => None,
+        }
+    }
+}
+
+/// Container summary for the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContainerInfo {
+    /// Container ID
+    pub id: String,
+
+    /// Primary container name without the leading slash
+    pub name: String,
+
+    /// Image the container was created from
+    pub image: String,
+
+    /// Lifecycle state (`running`, `exited`, ...)
+    pub state: String,
+
+    /// Human-readable status, e.g. `Up 5 minutes (healthy)`
+    pub status: String,
+
+    /// HEALTHCHECK state
+    pub health: ContainerHealth,
+
+    /// Container labels
+    pub labels: HashMap<String, String>,
+
+    /// Creation time
+    pub created: Option<DateTime<Utc>>,
+}
+
+impl From<ContainerSummary> for ContainerInfo {
+    fn from(summary: ContainerSummary) -> Self {
+        let status = summary.status.unwrap_or_default();
+        let name = summary
+            .names
+            .and_then(|names| names.into_iter().next())
+            .map(|name| name.trim_start_matches('/').to_string())
+            .unwrap_or_default();
+
+        Self {
+            id: summary.id.unwrap_or_default(),
+            name,
+            image: summary.image.unwrap_or_default(),
+            state: summary.state.map(|state| state.to_string()).unwrap_or_default(),
+            health: ContainerHealth::from_status_text(&status),
+            status,
+            labels: summary.labels.unwrap_or_default(),
+            created: summary.created.and_then(|secs| DateTime::from_timestamp(secs, 0)),
+        }
+    }
+}
+
+impl ContainerInfo {
+    /// Whether the agent created the container.
+    pub fn is_managed(&self) -> bool {
+        self.labels.get(REDSYS_MANAGED_LABEL).is_some_and(|value| value == "true")
+    }
+
+    /// ID of the job the container runs, for agent-created containers.
+    pub fn job_id(&self) -> Option<&str> {
+        self.labels.get(REDSYS_JOB_ID_LABEL).map(String::as_str)
+    }
+}
+
+/// Lifecycle states accepted by the container `status` filter.
+const CONTAINER_STATES: &[&str] = &["created", "restarting", "running", "removing", "paused", "exited", "dead"];
+
+/// Field to sort containers by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerSort {
+    #[default]
+    Name,
+    Created,
+    State,
+    Image,
+}
+
+/// Filters and sort of a container listing.
+///
+/// The status and label filters and a first pass of the name filter are
+/// evaluated by the daemon.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ContainerQuery {
+    /// Lifecycle state, e.g. `running` or `exited`; includes stopped
+    /// containers regardless of `all`
+    pub status: Option<String>,
+
+    /// Label selectors (`key` or `key=value`) that must all match
+    pub labels: Vec<String>,
+
+    /// Case-insensitive substring of the container name
+    pub name: Option<String>,
+
+    /// Field to sort by
+    pub sort: ContainerSort,
+
+    /// Sort direction
+    pub order: SortOrder,
+}
+
+impl ContainerQuery {
+    /// Validates the status and label selectors.
+    pub fn validate(&self) -> AppResult<()> {
+        if let Some(status) = self.status.as_deref().filter(|status| !CONTAINER_STATES.contains(status)) {
+            return Err(AppError::Configuration(format!("Unknown container status '{status}'")));
+        }
+        listing::validate_labels(&self.labels)
+    }
+
+    /// Builds the daemon-side filter map for `ListContainersOptions`.
+    ///
+    /// The daemon matches `name` as a regular expression against every name
+    /// of a container, so the substring is escaped and matched again locally.
+    pub fn to_docker_filters(&self) -> HashMap<String, Vec<String>> {
+        let mut filters = HashMap::new();
+        if let Some(status) = &self.status {
+            filters.insert("status".to_string(), vec![status.clone()]);
+        }
+        if !self.labels.is_empty() {
+            filters.insert("label".to_string(), self.labels.clone());
+        }
+        if let Some(name) = self.name.as_deref().filter(|name| !name.is_empty()) {
+            let mut pattern = String::from("(?i)");
+            for c in name.chars() {
+                if r"\.+*?()|[]{}^$".contains(c) {
+                    pattern.push('\\');
+                }
+                pattern.push(c);
+            }
+            filters.insert("name".to_string(), vec![pattern]);
+        }
+        filters
+    }
+
+    /// Sorts `containers` and drops the ones whose primary name does not
+    /// match.
+    pub fn select(&self, containers: Vec<ContainerInfo>) -> Vec<ContainerInfo> {
+        let mut containers: Vec<ContainerInfo> = containers
+            .into_iter()
+            .filter(|container| listing::contains_ignore_case(&container.name, self.name.as_deref()))
+            .collect();
+        containers.sort_by(|a, b| {
+            let ordering = match self.sort {
+                ContainerSort::Name => a.name.cmp(&b.name),
+                ContainerSort::Created => a.created.cmp(&b.created),
+                ContainerSort::State => a.state.cmp(&b.state),
+                ContainerSort::Image => a.image.cmp(&b.image),
+            };
+            // Ties are broken by ID so pages stay stable
+            self.order.apply(ordering.then_with(|| a.id.cmp(&b.id)))
+        });
+        containers
+    }
+}
+
+/// How a file in a container's filesystem changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    /// Changed, or a directory with changed entries
+    Modified,
+
+    /// Created in the container
+    Added,
+
+    /// Removed from the image's files
+    Deleted,
+}
+
+/// A file changed in a container's filesystem, as listed by `docker diff`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct FileChange {
+    /// Absolute path in the container
+    pub path: String,
+
+    /// Kind of change
+    pub kind: FileChangeKind,
+}
+
+impl From<FilesystemChange> for FileChange {
+    fn from(change: FilesystemChange) -> Self {
+        // The API encodes the kind as 0 (modified), 1 (added) and 2 (deleted)
+        let kind = match change.kind as i64 {
+            1 => FileChangeKind::Added,
+            2 => FileChangeKind::Deleted,
+            _ => FileChangeKind::Modified,
+        };
+        Self { path: change.path, kind }
+    }
+}
+
+/// Processes running in a container, as listed by `docker top`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ContainerProcesses {
+    /// Column titles of the host's `ps`, e.g. `UID`, `PID`, `CMD`
+    pub titles: Vec<String>,
+
+    /// One row per process, in the order of `titles`
+    pub processes: Vec<Vec<String>>,
+}
+
+impl From<ContainerTopResponse> for ContainerProcesses {
+    fn from(response: ContainerTopResponse) -> Self {
+        Self {
+            titles: response.titles.unwrap_or_default(),
+            processes: response.processes.unwrap_or_default(),
+        }
+    }
+}
+
+/// Docker Engine event forwarded to the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct DockerEvent {
+    /// Object type (`container`, `image`, `volume`, `network`)
+    pub event_type: String,
+
+    /// Event action (`start`, `die`, `health_status: healthy`, ...)
+    pub action: String,
+
+    /// ID of the object that emitted the event
+    pub actor_id: Option<String>,
+
+    /// Actor name attribute, if present
+    pub actor_name: Option<String>,
+
+    /// Raw actor attributes (labels, image, exit code, ...)
+    pub attributes: HashMap<String, String>,
+
+    /// Health state for `health_status` container events
+    pub health: Option<ContainerHealth>,
+
+    /// When the daemon recorded the event
+    pub time: Option<DateTime<Utc>>,
+}
+
+impl From<EventMessage> for DockerEvent {
+    fn from(message: EventMessage) -> Self {
+        let action = message.action.unwrap_or_default();
+        let (actor_id, attributes) = message
+            .actor
+            .map(|actor| (actor.id, actor.attributes.unwrap_or_default()))
+            .unwrap_or_default();
+        let time = match (message.time_nano, message.time) {
+            (Some(nanos), _) => Some(DateTime::from_timestamp_nanos(nanos)),
+            (None, Some(secs)) => DateTime::from_timestamp(secs, 0),
+            (None, None) => None,
+        };
+
+        Self {
+            event_type: message.typ.map(|t| t.to_string()).unwrap_or_default(),
+            health: ContainerHealth::from_event_action(&action),
+            action,
+            actor_id,
+            actor_name: attributes.get("name").cloned(),
+            attributes,
+            time,
+        }
+    }
+}
+
+/// Payload of the `container-health-changed` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ContainerHealthChanged {
+    /// Container ID
+    pub container_id: String,
+
+    /// Container name
+    pub container_name: Option<String>,
+
+    /// New health state
+    pub health: ContainerHealth,
+
+    /// When the transition happened
+    pub changed_at: DateTime<Utc>,
+}
+
+impl ContainerHealthChanged {
+    /// Extracts a health transition from a container event, if it is one.
+    pub fn from_event(event: &DockerEvent) -> Option<Self> {
+        if event.event_type != "container" {
+            return None;
+        }
+        Some(Self {
+            container_id: event.actor_id.clone()?,
+            container_name: event.actor_name.clone(),
+            health: event.health?,
+            changed_at: event.time.unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+/// Filters applied to the Docker events stream.
+///
+/// Event types and labels are evaluated by the daemon; container name
+/// prefixes are matched locally because the daemon only supports exact names.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventFilters {
+    /// Object types to receive; empty means all types
+    #[serde(default)]
+    pub event_types: Vec<String>,
+
+    /// Label selectors (`key` or `key=value`) that must all match
+    #[serde(default)]
+    pub labels: Vec<String>,
+
+    /// Container name prefixes; empty means all containers.
+    /// Only applies to container events.
+    #[serde(default)]
+    pub container_name_prefixes: Vec<String>,
+}
+
+impl Default for EventFilters {
+    fn default() -> Self {
+        Self {
+            event_types: vec![
+                "container".to_string(),
+                "image".to_string(),
+                "volume".to_string(),
+                "network".to_string(),
+            ],
+            labels: Vec::new(),
+            container_name_prefixes: Vec::new(),
+        }
+    }
+}
+
+impl EventFilters {
+    /// Validates event types and label selectors.
+    pub fn validate(&self) -> AppResult<()> {
+        if let Some(unknown) = self
+            .event_types
+            .iter()
+            .find(|t| !KNOWN_EVENT_TYPES.contains(&t.as_str()))
+        {
+            return Err(AppError::Configuration(format!("Unknown Docker event type '{unknown}'")));
+        }
+        if self.labels.iter().any(|label| label.trim().is_empty() || label.starts_with('=')) {
+            return Err(AppError::Configuration("Label selectors must have a non-empty key".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Builds the daemon-side filter map for `EventsOptions`.
+    pub fn to_docker_filters(&self) -> HashMap<String, Vec<String>> {
+        let mut filters = HashMap::new();
+        if !self.event_types.is_empty() {
+            filters.insert("type".to_string(), self.event_types.clone());
+        }
+        if !self.labels.is_empty() {
+            filters.insert("label".to_string(), self.labels.clone());
+        }
+        filters
+    }
+
+    /// Applies the client-side container name prefix filter.
+    pub fn matches(&self, event: &DockerEvent) -> bool {
+        if self.container_name_prefixes.is_empty() || event.event_type != "container" {
+            return true;
+        }
+        event.actor_name.as_deref().is_some_and(|name| {
+            self.container_name_prefixes.iter().any(|prefix| name.starts_with(prefix.as_str()))
+        })
+    }
+
+    /// Applies every filter locally, including the ones the daemon evaluates,
+    /// for events that did not come from the daemon.
+    pub fn matches_all(&self, event: &DockerEvent) -> bool {
+        let type_matches = self.event_types.is_empty() || self.event_types.contains(&event.event_type);
+        let labels_match = self.labels.iter().all(|label| match label.split_once('=') {
+            Some((key, value)) => event.attributes.get(key).is_some_and(|v| v == value),
+            None => event.attributes.contains_key(label.as_str()),
+        });
+        type_matches && labels_match && self.matches(event)
+    }
+}
+
+/// Doubles a reconnect delay, capped at `max`.
+pub(crate) fn next_backoff(current: Duration, max: Duration) -> Duration {
+    (current * 2).min(max)
+}
+
+/// Whether the daemon answers requests.
+fn is_up(status: &DockerStatus) -> bool {
+    matches!(status, DockerStatus::Running { .. } | DockerStatus::Degraded { .. })
+}
+
+/// Formats a nanosecond timestamp as the `seconds.nanoseconds` value accepted
+/// by the events `since` parameter.
+fn format_since(nanos: i64) -> String {
+    format!("{}.{:09}", nanos.div_euclid(1_000_000_000), nanos.rem_euclid(1_000_000_000))
+}
+
+/// Docker Engine service for on-demand operations.
+///
+/// Unlike [`DockerMonitor`], which continuously tracks daemon liveness, this
+/// service performs heavier queries that are requested by commands or run on
+/// slow background intervals.
+#[derive(Debug)]
+pub struct DockerService {
+    /// Active filters for the events stream
+    event_filters: RwLock<EventFilters>,
+
+    /// Signals the events stream to resubscribe with new filters
+    event_filters_changed: Notify,
+
+    /// Interval between background disk usage reports in seconds, adjustable at runtime
+    disk_usage_interval_secs: AtomicU64,
+
+    /// Signals the events stream to reconnect, e.g. after the Docker host changed
+    reconnect_requested: Notify,
+
+    /// Times the events stream was restarted after it terminated
+    events_reconnects: AtomicU64,
+
+    /// Daemon status the events stream waits on before restarting
+    docker_status: Option<watch::Receiver<DockerStatus>>,
+
+    /// Broadcasts filtered events to in-process subscribers
+    events: broadcast::Sender<DockerEvent>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: Arc<CancellationToken>,
+
+    /// Shared monitoring pause switch
+    pause: Arc<PauseControl>,
+
+    /// Cached daemon information, cleared while the daemon is down
+    info: watch::Sender<Option<DockerInfo>>,
+
+    /// Event bus for disk usage reports and Docker events
+    bus: Arc<Bus>,
+
+    /// Client used instead of the shared connection, e.g. a mock
+    client: Option<Arc<dyn DockerClient>>,
+}
+
+impl DockerService {
+    /// Creates a new Docker service instance.
+    pub fn new(cancellation_token: CancellationToken) -> Self {
+        info!("Initializing Docker service");
+        Self {
+            event_filters: RwLock::new(EventFilters::default()),
+            event_filters_changed: Notify::new(),
+            disk_usage_interval_secs: AtomicU64::new(DEFAULT_DISK_USAGE_INTERVAL.as_secs()),
+            reconnect_requested: Notify::new(),
+            events_reconnects: AtomicU64::new(0),
+            docker_status: None,
+            events: broadcast::channel(EVENTS_CHANNEL_CAPACITY).0,
+            cancellation_token: Arc::new(cancellation_token),
+            pause: Arc::new(PauseControl::new()),
+            info: watch::Sender::new(None),
+            bus: Arc::new(Bus::new()),
+            client: None,
+        }
+    }
+
+    /// Queries `client` instead of connecting to the configured daemon.
+    pub fn with_client(self, client: Arc<dyn DockerClient>) -> Self {
+        Self {
+            client: Some(client),
+            ..self
+        }
+    }
+
+    /// Shares the event bus that reports and Docker events are published to.
+    pub fn with_event_bus(self, bus: Arc<Bus>) -> Self {
+        Self { bus, ..self }
+    }
+
+    /// Shares a monitoring pause switch.
+    ///
+    /// While paused, the events stream disconnects and disk usage reports are
+    /// skipped.
+    pub fn with_pause_control(self, pause: Arc<PauseControl>) -> Self {
+        Self { pause, ..self }
+    }
+
+    /// Follows the daemon status, so that a terminated events stream waits for
+    /// the daemon to come back up instead of retrying blindly.
+    pub fn with_docker_status(self, docker_status: watch::Receiver<DockerStatus>) -> Self {
+        Self {
+            docker_status: Some(docker_status),
+            ..self
+        }
+    }
+
+    /// Sets the initial events stream filters.
+    pub fn with_event_filters(self, filters: EventFilters) -> Self {
+        Self {
+            event_filters: RwLock::new(filters),
+            ..self
+        }
+    }
+
+    /// Sets the interval between background disk usage reports.
+    pub fn with_disk_usage_interval(self, disk_usage_interval: Duration) -> Self {
+        self.set_disk_usage_interval(disk_usage_interval);
+        self
+    }
+
+    /// Gets the interval between background disk usage reports.
+    pub fn disk_usage_interval(&self) -> Duration {
+        Duration::from_secs(self.disk_usage_interval_secs.load(Ordering::Relaxed))
+    }
+
+    /// Changes the disk usage reporting interval of a running service.
+    ///
+    /// Takes effect after the next report.
+    pub fn set_disk_usage_interval(&self, disk_usage_interval: Duration) {
+        self.disk_usage_interval_secs.store(disk_usage_interval.as_secs(), Ordering::Relaxed);
+    }
+
+    /// Makes a running events stream reconnect immediately, picking up a
+    /// changed Docker host or skipping a pending backoff. Replay via `since`
+    /// keeps the stream gap-free.
+    pub fn reconnect(&self) {
+        self.info.send_replace(None);
+        self.reconnect_requested.notify_one();
+    }
+
+    /// Subscribes to Docker events that pass the active filters.
+    ///
+    /// Slow subscribers miss events once more than 256 are buffered.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<DockerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Docker events buffered for the slowest subscriber.
+    pub fn queued_events(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Times the events stream was restarted after it terminated.
+    pub fn events_reconnects(&self) -> u64 {
+        self.events_reconnects.load(Ordering::Relaxed)
+    }
+
+    /// Gets the active events stream filters.
+    pub async fn get_event_filters(&self) -> EventFilters {
+        self.event_filters.read().await.clone()
+    }
+
+    /// Replaces the events stream filters.
+    ///
+    /// A running events stream resubscribes immediately with the new filters.
+    pub async fn configure_event_filters(&self, filters: EventFilters) -> AppResult<()> {
+        filters.validate()?;
+
+        info!("Updating Docker event filters: {:?}", filters);
+        *self.event_filters.write().await = filters;
+        self.event_filters_changed.notify_one();
+        Ok(())
+    }
+
+    /// Gets the configured client or the [shared connection](crate::connection).
+    async fn client(&self) -> DockerMonitorResult<Arc<dyn DockerClient>> {
+        match &self.client {
+            Some(client) => Ok(client.clone()),
+            None => Ok(DockerMonitor::connect().await?),
+        }
+    }
+
+    /// Gets the Bollard client of the configured daemon, for operations the
+    /// [`DockerClient`] trait does not cover.
+    ///
+    /// Fails while a client without a real daemon behind it is configured,
+    /// e.g. in simulation mode, rather than falling back to the local daemon.
+    pub async fn docker(&self) -> DockerMonitorResult<Docker> {
+        let client = self.client().await?;
+        client.bollard().cloned().ok_or_else(|| {
+            DockerMonitorError::Api("Operation not supported by the simulated Docker daemon".to_string())
+        })
+    }
+
+    /// Collects the current Docker disk usage report.
+    ///
+    /// Returns an error if the daemon is unreachable or the query times out.
+    pub async fn get_disk_usage(&self) -> DockerMonitorResult<DockerDiskUsage> {
+        let client = self.client().await?;
+
+        match tokio::time::timeout(DISK_USAGE_TIMEOUT, client.df()).await {
+            Ok(Ok(response)) => Ok(DockerDiskUsage::from_response(&response)),
+            Ok(Err(e)) => Err(DockerMonitorError::api("Disk usage query failed", e).await),
+            Err(_) => Err(DockerMonitorError::Api("Disk usage query timed out".to_string())),
+        }
+    }
+
+    /// Gets the cached daemon information without querying the daemon.
+    pub fn cached_info(&self) -> Option<DockerInfo> {
+        self.info.borrow().clone()
+    }
+
+    /// Subscribes to the cached daemon information, refreshed whenever the
+    /// daemon comes up.
+    pub fn subscribe_info(&self) -> watch::Receiver<Option<DockerInfo>> {
+        self.info.subscribe()
+    }
+
+    /// Gets the daemon information, querying the daemon if none is cached.
+    ///
+    /// Returns an error if the daemon is unreachable or the query times out.
+    pub async fn get_info(&self) -> DockerMonitorResult<DockerInfo> {
+        if let Some(info) = self.info.borrow().clone() {
+            return Ok(info);
+        }
+        let info = self.fetch_info().await?;
+        self.info.send_replace(Some(info.clone()));
+        Ok(info)
+    }
+
+    /// Queries `docker info`.
+    async fn fetch_info(&self) -> DockerMonitorResult<DockerInfo> {
+        let client = self.client().await?;
+
+        match tokio::time::timeout(INFO_TIMEOUT, client.info()).await {
+            Ok(Ok(info)) => Ok(DockerInfo::from_response(&info, DockerMonitor::docker_host().as_deref())),
+            Ok(Err(e)) => Err(DockerMonitorError::api("Docker info query failed", e).await),
+            Err(_) => Err(DockerMonitorError::Api("Docker info query timed out".to_string())),
+        }
+    }
+
+    /// Keeps the cached daemon information in sync with the daemon status.
+    ///
+    /// The cache is refreshed whenever the daemon comes up, since a restart
+    /// may change settings such as the storage driver, and cleared while it
+    /// is down.
+    pub async fn start_info_refresh(self: Arc<Self>, mut docker_status: watch::Receiver<DockerStatus>) {
+        let cancellation_token = self.cancellation_token.clone();
+        let mut was_up = false;
+
+        loop {
+            let up = is_up(&docker_status.borrow_and_update());
+            if up && !was_up {
+                match self.fetch_info().await {
+                    Ok(info) => {
+                        debug!("Docker info refreshed: {:?} storage, {} containers", info.storage_driver, info.containers);
+                        self.info.send_replace(Some(info));
+                    }
+                    Err(e) => debug!("Skipping Docker info refresh: {e}"),
+                }
+            } else if !up {
+                self.info.send_replace(None);
+            }
+            was_up = up;
+
+            tokio::select! {
+                changed = docker_status.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+                _ = cancellation_token.cancelled() => break,
+            }
+        }
+    }
+
+    /// Lists containers including their HEALTHCHECK state.
+    ///
+    /// # Arguments
+    ///
+    /// * `all` - Include stopped containers
+    pub async fn list_containers(&self, all: bool) -> DockerMonitorResult<Vec<ContainerInfo>> {
+        let client = self.client().await?;
+        let containers = match client.list_containers(all, &HashMap::new()).await {
+            Ok(containers) => containers,
+            Err(e) => return Err(DockerMonitorError::api("Container listing failed", e).await),
+        };
+
+        Ok(containers.into_iter().map(ContainerInfo::from).collect())
+    }
+
+    /// Lists a page of the containers matching `query`.
+    ///
+    /// # Arguments
+    ///
+    /// * `all` - Include stopped containers
+    /// * `query` - Filters and sort
+    /// * `page` - Window of the sorted containers to return
+    pub async fn query_containers(
+        &self,
+        all: bool,
+        query: &ContainerQuery,
+        page: PageRequest,
+    ) -> AppResult<Page<ContainerInfo>> {
+        query.validate()?;
+        page.validate()?;
+        let client = self.client().await?;
+        let containers = match client.list_containers(all || query.status.is_some(), &query.to_docker_filters()).await {
+            Ok(containers) => containers,
+            Err(e) => return Err(DockerMonitorError::api("Container listing failed", e).await.into()),
+        };
+
+        let containers = containers.into_iter().map(ContainerInfo::from).collect();
+        Ok(Page::of(query.select(containers), page))
+    }
+
+    /// Lists the ports published on the host by running containers.
+    pub async fn published_ports(&self) -> DockerMonitorResult<Vec<PublishedPort>> {
+        let client = self.client().await?;
+        let containers = match client.list_containers(false, &HashMap::new()).await {
+            Ok(containers) => containers,
+            Err(e) => return Err(DockerMonitorError::api("Container listing failed", e).await),
+        };
+
+        Ok(containers.iter().flat_map(PublishedPort::from_summary).collect())
+    }
+
+    /// Lists the containers the agent created, leaving out the user's other
+    /// workloads.
+    ///
+    /// # Arguments
+    ///
+    /// * `all` - Include stopped containers
+    /// * `job_id` - Only the container of this job
+    pub async fn list_managed_containers(&self, all: bool, job_id: Option<&str>) -> DockerMonitorResult<Vec<ContainerInfo>> {
+        let containers = self.list_containers(all).await?;
+        Ok(containers
+            .into_iter()
+            .filter(|container| container.is_managed())
+            .filter(|container| job_id.is_none() || container.job_id() == job_id)
+            .collect())
+    }
+
+    /// Removes the containers the agent created, never touching other
+    /// containers.
+    ///
+    /// Running containers are only removed with `force`, since they may
+    /// belong to a job in progress. Containers that fail to be removed are
+    /// logged and skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `job_id` - Only the container of this job
+    /// * `force` - Also remove running containers
+    ///
+    /// # Returns
+    ///
+    /// Returns the names of the removed containers
+    pub async fn remove_managed_containers(&self, job_id: Option<&str>, force: bool) -> DockerMonitorResult<Vec<String>> {
+        let client = self.client().await?;
+        let mut removed = Vec::new();
+        for container in self.list_managed_containers(true, job_id).await? {
+            if container.state == "running" && !force {
+                debug!("Keeping running container {}", container.name);
+                continue;
+            }
+            match client.remove_container(&container.id, force).await {
+                Ok(()) => {
+                    info!("Removed container {}", container.name);
+                    removed.push(container.name);
+                }
+                Err(e) => warn!("Failed to remove container {}: {}", container.name, e),
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Removes a container; running containers only with `force`.
+    pub async fn remove_container(&self, id: &str, force: bool) -> DockerMonitorResult<()> {
+        let client = self.client().await?;
+
+        info!("Removing container {}", id);
+        client
+            .remove_container(id, force)
+            .await
+            .map_err(|e| DockerMonitorError::Api(format!("Failed to remove container {id}: {e}")))
+    }
+
+    /// Starts a stopped container.
+    pub async fn start_container(&self, id: &str) -> DockerMonitorResult<()> {
+        let client = self.client().await?;
+
+        info!("Starting container {}", id);
+        client
+            .start_container(id)
+            .await
+            .map_err(|e| DockerMonitorError::Api(format!("Failed to start container {id}: {e}")))
+    }
+
+    /// Stops a running container.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Container ID or name
+    /// * `timeout` - Grace period before the container is killed
+    pub async fn stop_container(&self, id: &str, timeout: Duration) -> DockerMonitorResult<()> {
+        let client = self.client().await?;
+
+        info!("Stopping container {}", id);
+        client
+            .stop_container(id, timeout)
+            .await
+            .map_err(|e| DockerMonitorError::Api(format!("Failed to stop container {id}: {e}")))
+    }
+
+    /// Lists the files changed in a container's filesystem since it was
+    /// created from its image.
+    pub async fn container_diff(&self, id: &str) -> DockerMonitorResult<Vec<FileChange>> {
+        let client = self.client().await?;
+
+        client
+            .container_changes(id)
+            .await
+            .map(|changes| changes.into_iter().map(FileChange::from).collect())
+            .map_err(|e| DockerMonitorError::Api(format!("Failed to list changes of container {id}: {e}")))
+    }
+
+    /// Lists the processes running in a container.
+    ///
+    /// Fails for containers that are not running.
+    pub async fn container_top(&self, id: &str) -> DockerMonitorResult<ContainerProcesses> {
+        let client = self.client().await?;
+
+        client
+            .top_processes(id)
+            .await
+            .map(ContainerProcesses::from)
+            .map_err(|e| DockerMonitorError::Api(format!("Failed to list processes of container {id}: {e}")))
+    }
+
+    /// Starts periodic disk usage reporting.
+    ///
+    /// Publishes a `docker-disk-usage` report every disk usage interval while
+    /// the daemon is reachable. Failed collections are skipped silently since the
+    /// monitor already reports daemon availability.
+    pub async fn start_disk_usage_reporting(self: Arc<Self>) {
+        let cancellation_token = self.cancellation_token.clone();
+        let mut disk_usage_interval = self.disk_usage_interval();
+
+        info!("Starting Docker disk usage reporting every {}s", disk_usage_interval.as_secs());
+
+        let mut ticker = interval(disk_usage_interval);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let configured_interval = self.disk_usage_interval();
+                    if configured_interval != disk_usage_interval {
+                        info!("Docker disk usage interval changed to {}s", configured_interval.as_secs());
+                        disk_usage_interval = configured_interval;
+                        ticker = interval(disk_usage_interval);
+                        ticker.reset();
+                    }
+
+                    if self.pause.is_paused() {
+                        continue;
+                    }
+
+                    async {
+                        match self.get_disk_usage().await {
+                            Ok(usage) => {
+                                debug!(
+                                    "Docker disk usage: {} bytes total, {} bytes reclaimable",
+                                    usage.total_bytes, usage.reclaimable_bytes
+                                );
+                                self.bus.publish(Event::SystemMetric(SystemMetric::DockerDiskUsage(usage)));
+                            }
+                            Err(e) => debug!("Skipping disk usage report: {e}"),
+                        }
+                    }
+                    .instrument(info_span!("disk_usage_report"))
+                    .await;
+                }
+                _ = cancellation_token.cancelled() => {
+                    info!("Docker disk usage reporting received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Publishes `event` like an event from the events stream if it passes
+    /// the active filters, returning whether it did.
+    pub(crate) async fn dispatch_filtered(&self, event: DockerEvent) -> bool {
+        let matches = self.event_filters.read().await.matches_all(&event);
+        if matches {
+            self.dispatch(event);
+        }
+        matches
+    }
+
+    /// Publishes a filtered event to the bus and in-process subscribers.
+    fn dispatch(&self, event: DockerEvent) {
+        let span = info_span!("docker_event", event_type = %event.event_type, action = %event.action);
+        span.in_scope(|| {
+            debug!("Docker event: {} {}", event.event_type, event.action);
+
+            if let Some(change) = ContainerHealthChanged::from_event(&event) {
+                info!("Container {} health changed to {:?}", change.container_id, change.health);
+                self.bus.publish(Event::Container(ContainerEvent::HealthChanged(change)));
+            }
+            self.bus.publish(Event::Container(ContainerEvent::Docker(event.clone())));
+        });
+        // No subscribers is fine
+        let _ = self.events.send(event);
+    }
+
+    /// Starts publishing the Docker events stream to the event bus.
+    ///
+    /// Events matching the configured [`EventFilters`] are published as
+    /// `docker-event`. Container health transitions additionally publish
+    /// `container-health-changed` so orchestration can stop routing work to
+    /// unhealthy service containers.
+    ///
+    /// **Reconnection Strategy:**
+    /// - Stream errors and daemon restarts trigger a reconnect with exponential
+    ///   backoff (1s doubling up to 60s), reset once events flow again
+    /// - Resubscriptions pass `since = last_seen_timestamp` so the daemon replays
+    ///   events that happened while disconnected
+    /// - Replayed events at or before the last seen timestamp are dropped
+    /// - Pausing disconnects the stream; resuming replays the events missed
+    ///   while paused
+    /// - With a [daemon status](Self::with_docker_status), a terminated stream
+    ///   waits for the daemon to be up again and then restarts right away
+    pub async fn start_docker_events_stream(self: Arc<Self>) {
+        let cancellation_token = self.cancellation_token.clone();
+
+        let mut backoff = EVENTS_INITIAL_BACKOFF;
+        let mut last_seen_nanos: Option<i64> = None;
+        let mut docker_status = self.docker_status.clone();
+        let mut terminated = false;
+
+        'connect: loop {
+            if self.pause.is_paused() {
+                tokio::select! {
+                    _ = self.pause.resumed() => info!("Docker events stream resuming"),
+                    _ = cancellation_token.cancelled() => break 'connect,
+                }
+            }
+
+            match self.client().await {
+                Ok(client) => {
+                    // Anchor replay at the first successful connection
+                    last_seen_nanos.get_or_insert_with(|| Utc::now().timestamp_nanos_opt().unwrap_or_default());
+                    if terminated {
+                        terminated = false;
+                        let reconnects = self.events_reconnects.fetch_add(1, Ordering::Relaxed) + 1;
+                        info!("Restarting Docker events stream (reconnect #{reconnects})");
+                    }
+
+                    'subscribe: loop {
+                        let filters = self.get_event_filters().await;
+                        let since = format_since(last_seen_nanos.unwrap_or_default());
+                        let mut stream = client.events(&since, &filters.to_docker_filters());
+
+                        info!("Docker events stream subscribed since {} with filters {:?}", since, filters);
+
+                        loop {
+                            tokio::select! {
+                                item = stream.next() => match item {
+                                    Some(Ok(message)) => {
+                                        backoff = EVENTS_INITIAL_BACKOFF;
+                                        let event = DockerEvent::from(message);
+
+                                        let event_nanos = event.time.and_then(|t| t.timestamp_nanos_opt());
+                                        if let (Some(seen), Some(nanos)) = (last_seen_nanos, event_nanos) {
+                                            if nanos <= seen {
+                                                continue;
+                                            }
+                                        }
+                                        if event_nanos.is_some() {
+                                            last_seen_nanos = event_nanos;
+                                        }
+
+                                        if filters.matches(&event) {
+                                            self.dispatch(event);
+                                        }
+                                    }
+                                    Some(Err(e)) => {
+                                        warn!("Docker events stream error: {e}");
+                                        connection::invalidate(&client);
+                                        terminated = true;
+                                        break 'subscribe;
+                                    }
+                                    None => {
+                                        info!("Docker events stream ended");
+                                        terminated = true;
+                                        break 'subscribe;
+                                    }
+                                },
+                                _ = self.event_filters_changed.notified() => {
+                                    debug!("Docker event filters changed, resubscribing");
+                                    continue 'subscribe;
+                                }
+                                _ = self.pause.paused() => {
+                                    info!("Docker events stream paused");
+                                    backoff = EVENTS_INITIAL_BACKOFF;
+                                    continue 'connect;
+                                }
+                                _ = self.reconnect_requested.notified() => {
+                                    info!("Docker events stream reconnect requested");
+                                    backoff = EVENTS_INITIAL_BACKOFF;
+                                    continue 'connect;
+                                }
+                                _ = cancellation_token.cancelled() => {
+                                    break 'connect;
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(e) => debug!("Docker events stream cannot connect: {e}"),
+            }
+
+            // Watchdog: no point retrying while the monitor sees the daemon down
+            if let Some(status) = docker_status.as_mut().filter(|status| !is_up(&status.borrow())) {
+                info!("Docker events stream waiting for the daemon to come back up");
+                let monitor_alive = tokio::select! {
+                    up = status.wait_for(is_up) => up.is_ok(),
+                    _ = self.reconnect_requested.notified() => {
+                        info!("Docker events stream reconnect requested");
+                        true
+                    }
+                    _ = cancellation_token.cancelled() => break 'connect,
+                };
+                if !monitor_alive {
+                    // Fall back to blind retries
+                    docker_status = None;
+                }
+                backoff = EVENTS_INITIAL_BACKOFF;
+                continue 'connect;
+            }
+
+            info!("Reconnecting Docker events stream in {}s", backoff.as_secs());
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = self.reconnect_requested.notified() => {
+                    info!("Docker events stream reconnect requested");
+                    backoff = EVENTS_INITIAL_BACKOFF;
+                    continue 'connect;
+                }
+                _ = cancellation_token.cancelled() => break 'connect,
+            }
+            backoff = next_backoff(backoff, EVENTS_MAX_BACKOFF);
+        }
+
+        info!("Docker events stream received cancellation signal, shutting down");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::EngineDetails;
+    use bollard::models::{BuildCache, ContainerSummary, ImageSummary, Volume, VolumeUsageData};
+    use crate::client::{MockDaemon, MockDockerClient};
+
+    fn image(size: i64, shared_size: i64, containers: i64) -> ImageSummary {
+        ImageSummary { size, shared_size, containers, ..Default::default() }
+    }
+
+    #[test]
+    fn test_empty_disk_usage() {
+        let usage = DockerDiskUsage::from_response(&SystemDataUsageResponse::default());
+        assert_eq!(usage.total_bytes, 0);
+        assert_eq!(usage.reclaimable_bytes, 0);
+        assert_eq!(usage.images, DiskUsageCategory::default());
+    }
+
+    #[test]
+    fn test_disk_usage_reclaimable_space() {
+        let response = SystemDataUsageResponse {
+            layers_size: Some(1000),
+            images: Some(vec![image(600, 100, 1), image(400, 100, 0)]),
+            containers: Some(vec![
+                ContainerSummary {
+                    size_rw: Some(50),
+                    state: Some(ContainerSummaryStateEnum::RUNNING),
+                    ..Default::default()
+                },
+                ContainerSummary {
+                    size_rw: Some(30),
+                    state: Some(ContainerSummaryStateEnum::EXITED),
+                    ..Default::default()
+                },
+            ]),
+            volumes: Some(vec![
+                Volume {
+                    usage_data: Some(VolumeUsageData { size: 200, ref_count: 1 }),
+                    ..Default::default()
+                },
+                Volume {
+                    usage_data: Some(VolumeUsageData { size: -1, ref_count: 0 }),
+                    ..Default::default()
+                },
+            ]),
+            build_cache: Some(vec![
+                BuildCache { size: Some(70), in_use: Some(false), shared: Some(false), ..Default::default() },
+                BuildCache { size: Some(20), in_use: Some(true), ..Default::default() },
+            ]),
+        };
+
+        let usage = DockerDiskUsage::from_response(&response);
+        assert_eq!(usage.images.active_count, 1);
+        assert_eq!(usage.images.reclaimable_bytes, 500);
+        assert_eq!(usage.containers.reclaimable_bytes, 30);
+        assert_eq!(usage.volumes.size_bytes, 200);
+        assert_eq!(usage.volumes.reclaimable_bytes, 0);
+        assert_eq!(usage.build_cache.reclaimable_bytes, 70);
+        assert_eq!(usage.total_bytes, 1000 + 80 + 200 + 90);
+        assert_eq!(usage.reclaimable_bytes, 500 + 30 + 70);
+    }
+
+    #[test]
+    fn test_docker_info_subset() {
+        let response: SystemInfo = serde_json::from_value(serde_json::json!({
+            "ServerVersion": "27.0.1",
+            "Driver": "overlay2",
+            "CgroupVersion": "2",
+            "CgroupDriver": "systemd",
+            "DockerRootDir": "/var/lib/docker",
+            "Containers": 4,
+            "ContainersRunning": 1,
+            "Images": 12,
+            "Swarm": { "LocalNodeState": "inactive" },
+            "Runtimes": { "runc": { "path": "runc" }, "nvidia": { "path": "nvidia-container-runtime" } },
+            "DefaultRuntime": "runc",
+            "MemoryLimit": true,
+            "SwapLimit": false,
+            "CpuCfsQuota": true
+        }))
+        .unwrap();
+        let info = DockerInfo::from_response(&response, None);
+        assert_eq!(info.storage_driver.as_deref(), Some("overlay2"));
+        assert_eq!(info.cgroup_version.as_deref(), Some("2"));
+        assert_eq!(info.cgroup_driver.as_deref(), Some("systemd"));
+        assert_eq!(info.data_root.as_deref(), Some("/var/lib/docker"));
+        assert_eq!((info.containers, info.containers_running, info.images), (4, 1, 12));
+        assert_eq!(info.swarm_state.as_deref(), Some("inactive"));
+        assert_eq!(info.runtimes, ["nvidia", "runc"]);
+        assert_eq!(info.limit_support.cgroup_version, Some(2));
+        assert!(info.limit_support.memory && !info.limit_support.swap && info.limit_support.cpu_quota);
+    }
+
+    #[test]
+    fn test_empty_docker_info() {
+        let info = DockerInfo::from_response(&SystemInfo::default(), None);
+        assert_eq!(info.cgroup_version, None);
+        assert_eq!(info.swarm_state, None);
+        assert!(info.runtimes.is_empty());
+        assert_eq!(info.flavor, DockerFlavor::Standalone);
+    }
+
+    #[test]
+    fn test_docker_flavor_detection() {
+        let info = |value: serde_json::Value| -> SystemInfo { serde_json::from_value(value).unwrap() };
+
+        let desktop = info(serde_json::json!({ "OperatingSystem": "Docker Desktop", "Name": "docker-desktop" }));
+        assert_eq!(DockerFlavor::detect(&desktop, Some("unix:///var/run/docker.sock")), DockerFlavor::DockerDesktop);
+
+        let rootless = info(serde_json::json!({ "SecurityOptions": ["name=seccomp,profile=builtin", "name=rootless"] }));
+        assert_eq!(DockerFlavor::detect(&rootless, Some("unix:///run/user/1000/docker.sock")), DockerFlavor::Rootless);
+
+        let colima = info(serde_json::json!({ "OperatingSystem": "Ubuntu 24.04 LTS", "Name": "colima" }));
+        assert_eq!(DockerFlavor::detect(&colima, None), DockerFlavor::Colima);
+        assert_eq!(
+            DockerFlavor::detect(&info(serde_json::json!({})), Some("unix:///Users/me/.rd/docker.sock")),
+            DockerFlavor::RancherDesktop
+        );
+        assert_eq!(
+            DockerFlavor::detect(&info(serde_json::json!({})), Some("unix:///run/user/501/podman/podman-machine-default-api.sock")),
+            DockerFlavor::PodmanMachine
+        );
+        assert_eq!(
+            DockerFlavor::detect(&info(serde_json::json!({ "OperatingSystem": "Debian GNU/Linux 12" })), None),
+            DockerFlavor::Standalone
+        );
+    }
+
+    #[test]
+    fn test_health_from_status_text() {
+        assert_eq!(ContainerHealth::from_status_text("Up 5 minutes (healthy)"), ContainerHealth::Healthy);
+        assert_eq!(ContainerHealth::from_status_text("Up 1 hour (unhealthy)"), ContainerHealth::Unhealthy);
+        assert_eq!(ContainerHealth::from_status_text("Up 3 seconds (health: starting)"), ContainerHealth::Starting);
+        assert_eq!(ContainerHealth::from_status_text("Exited (0) 2 hours ago"), ContainerHealth::None);
+    }
+
+    #[test]
+    fn test_health_changed_from_event() {
+        let message = EventMessage {
+            typ: Some(bollard::models::EventMessageTypeEnum::CONTAINER),
+            action: Some("health_status: unhealthy".to_string()),
+            actor: Some(bollard::models::EventActor {
+                id: Some("abc123".to_string()),
+                attributes: Some(HashMap::from([("name".to_string(), "runner".to_string())])),
+            }),
+            time: Some(1_700_000_000),
+            ..Default::default()
+        };
+
+        let event = DockerEvent::from(message);
+        assert_eq!(event.health, Some(ContainerHealth::Unhealthy));
+
+        let change = ContainerHealthChanged::from_event(&event).unwrap();
+        assert_eq!(change.container_id, "abc123");
+        assert_eq!(change.container_name.as_deref(), Some("runner"));
+        assert_eq!(change.health, ContainerHealth::Unhealthy);
+    }
+
+    #[test]
+    fn test_non_health_event_ignored() {
+        let message = EventMessage {
+            typ: Some(bollard::models::EventMessageTypeEnum::CONTAINER),
+            action: Some("start".to_string()),
+            ..Default::default()
+        };
+        let event = DockerEvent::from(message);
+        assert!(ContainerHealthChanged::from_event(&event).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_containers_with_mock_client() {
+        let client = MockDockerClient::new().with_containers(vec![ContainerSummary {
+            id: Some("abc123".to_string()),
+            names: Some(vec!["/web".to_string()]),
+            state: Some(ContainerSummaryStateEnum::RUNNING),
+            status: Some("Up 5 minutes (healthy)".to_string()),
+            ..Default::default()
+        }]);
+        let service = DockerService::new(CancellationToken::new()).with_client(Arc::new(client));
+
+        let containers = service.list_containers(false).await.unwrap();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].name, "web");
+        assert_eq!(containers[0].health, ContainerHealth::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_query_containers() {
+        let container = |name: &str, state, created| ContainerSummary {
+            id: Some(format!("{name}-id")),
+            names: Some(vec![format!("/{name}")]),
+            state: Some(state),
+            created: Some(created),
+            ..Default::default()
+        };
+        let client = MockDockerClient::new().with_containers(vec![
+            container("redsys-job-1", ContainerSummaryStateEnum::EXITED, 300),
+            container("postgres", ContainerSummaryStateEnum::RUNNING, 100),
+            container("redsys-job-2", ContainerSummaryStateEnum::RUNNING, 200),
+        ]);
+        let service = DockerService::new(CancellationToken::new()).with_client(Arc::new(client));
+
+        let query = ContainerQuery {
+            name: Some("JOB".to_string()),
+            sort: ContainerSort::Created,
+            order: SortOrder::Descending,
+            ..Default::default()
+        };
+        let page = service.query_containers(true, &query, PageRequest { offset: 0, limit: 1 }).await.unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items[0].name, "redsys-job-1");
+
+        let exited = ContainerQuery { status: Some("exited".to_string()), ..Default::default() };
+        let page = service.query_containers(false, &exited, PageRequest::default()).await.unwrap();
+        assert_eq!(page.items.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["redsys-job-1"]);
+
+        let invalid = ContainerQuery { status: Some("sleeping".to_string()), ..Default::default() };
+        assert!(service.query_containers(true, &invalid, PageRequest::default()).await.is_err());
+        assert_eq!(query.to_docker_filters()["name"], ["(?i)JOB"]);
+        let dotted = ContainerQuery { name: Some("a.b".to_string()), ..Default::default() };
+        assert_eq!(dotted.to_docker_filters()["name"], [r"(?i)a\.b"]);
+    }
+
+    #[tokio::test]
+    async fn test_container_top_and_diff() {
+        let container = |id: &str, state| ContainerSummary {
+            id: Some(id.to_string()),
+            command: Some("python train.py".to_string()),
+            state: Some(state),
+            ..Default::default()
+        };
+        let client = MockDockerClient::new().with_containers(vec![
+            container("running", ContainerSummaryStateEnum::RUNNING),
+            container("exited", ContainerSummaryStateEnum::EXITED),
+        ]);
+        let service = DockerService::new(CancellationToken::new()).with_client(Arc::new(client));
+
+        let top = service.container_top("running").await.unwrap();
+        assert_eq!(top.titles, ["PID", "CMD"]);
+        assert_eq!(top.processes, [["1", "python train.py"]]);
+        assert!(service.container_top("exited").await.is_err());
+
+        assert!(service.container_diff("exited").await.unwrap().is_empty());
+        assert!(service.container_diff("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_managed_containers_leave_user_workloads_alone() {
+        let container = |name: &str, state, job_id: Option<&str>| ContainerSummary {
+            id: Some(format!("{name}-id")),
+            names: Some(vec![format!("/{name}")]),
+            state: Some(state),
+            labels: job_id.map(|job_id| {
+                HashMap::from([
+                    (REDSYS_MANAGED_LABEL.to_string(), "true".to_string()),
+                    (REDSYS_JOB_ID_LABEL.to_string(), job_id.to_string()),
+                ])
+            }),
+            ..Default::default()
+        };
+        let client = Arc::new(MockDockerClient::new().with_containers(vec![
+            container("postgres", ContainerSummaryStateEnum::EXITED, None),
+            container("redsys-job-1", ContainerSummaryStateEnum::EXITED, Some("1")),
+            container("redsys-job-2", ContainerSummaryStateEnum::RUNNING, Some("2")),
+        ]));
+        let service = DockerService::new(CancellationToken::new()).with_client(client.clone());
+
+        assert_eq!(service.list_managed_containers(true, None).await.unwrap().len(), 2);
+        let job = service.list_managed_containers(true, Some("2")).await.unwrap();
+        assert_eq!(job[0].name, "redsys-job-2");
+
+        assert_eq!(service.remove_managed_containers(None, false).await.unwrap(), ["redsys-job-1"]);
+        assert_eq!(client.list_containers(true, &HashMap::new()).await.unwrap().len(), 2);
+        assert_eq!(service.remove_managed_containers(None, true).await.unwrap(), ["redsys-job-2"]);
+        let remaining = client.list_containers(true, &HashMap::new()).await.unwrap();
+        assert_eq!(remaining[0].id.as_deref(), Some("postgres-id"));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_filtered_applies_filters() {
+        let service = DockerService::new(CancellationToken::new()).with_event_filters(EventFilters {
+            event_types: vec!["container".to_string()],
+            ..EventFilters::default()
+        });
+        let mut events = service.subscribe_events();
+        let event = |typ| DockerEvent::from(EventMessage {
+            typ: Some(typ),
+            action: Some("die".to_string()),
+            ..Default::default()
+        });
+
+        assert!(!service.dispatch_filtered(event(bollard::models::EventMessageTypeEnum::IMAGE)).await);
+        assert!(service.dispatch_filtered(event(bollard::models::EventMessageTypeEnum::CONTAINER)).await);
+        assert_eq!(events.try_recv().unwrap().action, "die");
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_info_cached_once_daemon_answers() {
+        let client = Arc::new(MockDockerClient::new());
+        client.set_daemon(MockDaemon::Stopped);
+        let service = DockerService::new(CancellationToken::new()).with_client(client.clone());
+
+        assert!(service.get_info().await.is_err());
+        assert!(service.cached_info().is_none());
+
+        client.set_daemon(MockDaemon::Running);
+        assert!(service.get_info().await.is_ok());
+        assert!(service.cached_info().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_events_stream_restarts_once_daemon_is_back() {
+        let client = Arc::new(MockDockerClient::new());
+        let running = DockerStatus::Running { version: "27.3.1".to_string(), engine: EngineDetails::default() };
+        let docker_status = watch::Sender::new(running.clone());
+        let cancellation_token = CancellationToken::new();
+        let service = Arc::new(
+            DockerService::new(cancellation_token.clone())
+                .with_client(client.clone())
+                .with_docker_status(docker_status.subscribe()),
+        );
+        let mut events = service.subscribe_events();
+        let stream = tokio::spawn(service.clone().start_docker_events_stream());
+        let message = EventMessage {
+            typ: Some(bollard::models::EventMessageTypeEnum::CONTAINER),
+            action: Some("die".to_string()),
+            ..Default::default()
+        };
+
+        // Daemon restart terminates the stream
+        docker_status.send_replace(DockerStatus::Stopped { reason: crate::diagnosis::DockerUnavailableReason::Unknown });
+        client.set_daemon(MockDaemon::Stopped);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(service.events_reconnects(), 0);
+
+        client.set_daemon(MockDaemon::Running);
+        docker_status.send_replace(running);
+        let received = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                client.emit_event(message.clone());
+                if let Ok(event) = tokio::time::timeout(Duration::from_millis(20), events.recv()).await {
+                    return event;
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(received.unwrap().unwrap().action, "die");
+        assert_eq!(service.events_reconnects(), 1);
+        cancellation_token.cancel();
+        stream.await.unwrap();
+    }
+
+    #[test]
+    fn test_default_event_filters() {
+        let filters = EventFilters::default();
+        assert!(filters.validate().is_ok());
+        let docker_filters = filters.to_docker_filters();
+        assert_eq!(docker_filters.get("type").map(Vec::len), Some(4));
+        assert!(!docker_filters.contains_key("label"));
+    }
+
+    #[test]
+    fn test_event_filters_validation() {
+        let filters = EventFilters { event_types: vec!["bogus".to_string()], ..Default::default() };
+        assert!(filters.validate().is_err());
+
+        let filters = EventFilters { labels: vec!["=value".to_string()], ..Default::default() };
+        assert!(filters.validate().is_err());
+    }
+
+    #[test]
+    fn test_container_name_prefix_filter() {
+        let filters = EventFilters {
+            container_name_prefixes: vec!["redsys-".to_string()],
+            ..Default::default()
+        };
+        let mut event = DockerEvent::from(EventMessage {
+            typ: Some(bollard::models::EventMessageTypeEnum::CONTAINER),
+            action: Some("start".to_string()),
+            ..Default::default()
+        });
+        assert!(!filters.matches(&event));
+
+        event.actor_name = Some("redsys-job-42".to_string());
+        assert!(filters.matches(&event));
+
+        event.actor_name = Some("postgres".to_string());
+        assert!(!filters.matches(&event));
+
+        event.event_type = "image".to_string();
+        assert!(filters.matches(&event));
+    }
+
+    #[test]
+    fn test_events_backoff_is_capped() {
+        let mut backoff = EVENTS_INITIAL_BACKOFF;
+        for _ in 0..10 {
+            backoff = next_backoff(backoff, EVENTS_MAX_BACKOFF);
+        }
+        assert_eq!(backoff, EVENTS_MAX_BACKOFF);
+        assert_eq!(next_backoff(Duration::from_secs(1), EVENTS_MAX_BACKOFF), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_format_since() {
+        assert_eq!(format_since(1_700_000_000_000_000_042), "1700000000.000000042");
+        assert_eq!(format_since(0), "0.000000000");
+    }
+
+    #[test]
+    fn test_disk_usage_serialization() {
+        let usage = DockerDiskUsage::from_response(&SystemDataUsageResponse::default());
+        let serialized = serde_json::to_string(&usage).unwrap();
+        assert!(serialized.contains("reclaimable_bytes"));
+        assert!(serialized.contains("build_cache"));
+    }
+}
@@ -9,14 +9,18 @@
 //! - **Professional cross-platform connection strategy** with runtime detection
 //! - **Graceful shutdown** using Tokio CancellationToken
 //! - **Comprehensive error handling** with user-friendly messages
-//! - **Real-time event emission** to frontend via Tauri events
+//! - **Real-time status events** published to the [`Bus`](crate::events::Bus)
+//!   and forwarded to the frontend as `docker-status-changed`
+//! - **De-duplicated reports**: a status that keeps failing the same way is
+//!   published and logged once, then repeated every [`STATUS_HEARTBEAT`]
 //!
 //! ## Professional Cross-Platform Support
 //! - **Runtime Platform Detection**: Dynamically determines the best connection method
 //! - **Environment Variable Priority**: `DOCKER_HOST` takes precedence (user override)
 //! - **Platform Defaults**: 
 //!   - **Windows**: Named pipe (`npipe:///./pipe/docker_engine`)
-//!   - **Linux/macOS**: Unix socket (`unix:///var/run/docker.sock`)
+//!   - **Linux/macOS**: Unix socket (`unix:///var/run/docker.sock`), or the
+//!     rootless daemon's `$XDG_RUNTIME_DIR/docker.sock` when that is missing
 //! - **HTTP Fallback**: For remote Docker hosts or custom configurations
 //!
 //! ## Enterprise Features
@@ -33,30 +37,117 @@
 //! - [Serde Enum Serialization](https://serde.rs/enum-representations.html)
 //! - [Thiserror Error Handling](https://docs.rs/thiserror/latest/thiserror/)
 
-use std::sync::Arc;
-use tokio::{sync::Mutex, time::{interval, Duration}, task};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use tokio::{sync::{broadcast, watch, Notify}, time::{interval, Duration}};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info};
-use tauri::Emitter;
-use bollard::Docker;
-use serde::Serialize;
+use tracing::{debug, debug_span, error, info, info_span, warn, Instrument};
+use bollard::models::SystemVersion;
+use bollard::{ClientVersion, Docker, API_DEFAULT_VERSION};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
 use thiserror::Error;
 
+use crate::client::DockerClient;
+use crate::connection;
+use crate::dedup::Deduplicator;
+use crate::diagnosis::{self, DockerUnavailableReason, SocketPermission};
+use crate::error::{AppError, AppResult, ErrorCode, ErrorPayload};
+use crate::events::{Bus, Event};
+use crate::faults::FaultInjector;
+use crate::network;
+use crate::notifications::Notifier;
+use crate::pause::PauseControl;
+use crate::status::{DaemonState, StatusMachine, StatusTransition};
+use crate::types::DockerStatusChangedPayload;
+
 /// Docker daemon status with discriminated union serialization.
 /// 
 /// Uses `#[serde(tag = "type")]` for TypeScript discriminated union compatibility.
 /// See [Serde Enum Representations](https://serde.rs/enum-representations.html).
 #[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
 #[serde(tag = "type")]
 pub enum DockerStatus {
     /// Docker daemon is running and responsive
-    Running { version: String },
+    Running { version: String, engine: EngineDetails },
     
     /// Docker daemon is stopped or not available
-    Stopped,
+    Stopped { reason: DockerUnavailableReason },
     
     /// Error occurred while checking daemon
-    Error { message: String },
+    /// 
+    /// `retry_after` is set while the health-check circuit is open and
+    /// checks are skipped until that time.
+    Error { message: String, retry_after: Option<DateTime<Utc>> },
+    
+    /// Monitoring is paused and the daemon is not being checked
+    Paused,
+    
+    /// Docker daemon accepts connections but answers with server errors while
+    /// it boots, as Docker Desktop does while its engine starts
+    Starting,
+    
+    /// Docker daemon went down after running and is expected back shortly
+    Restarting,
+    
+    /// Docker daemon is running but unreliable, e.g. restarting repeatedly
+    Degraded { reason: String },
+    
+    /// The configured remote Docker host (tcp, http(s), ssh) cannot be reached
+    Unreachable { endpoint: String, reason: DockerUnavailableReason },
+}
+
+/// Details of a running Docker engine, from `/version`.
+/// 
+/// The engine API reports no uptime, so `started_at` is the start time of
+/// the local `dockerd` process and `None` for remote daemons and daemons in
+/// a VM, such as Docker Desktop's.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct EngineDetails {
+    /// Engine API version, e.g. `1.47`
+    pub api_version: Option<String>,
+    
+    /// Operating system of the engine, e.g. `linux`
+    pub os: Option<String>,
+    
+    /// Architecture of the engine, e.g. `amd64`
+    pub arch: Option<String>,
+    
+    /// When the daemon started
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+/// Engine last seen running, kept while the daemon is down.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct LastKnownEngine {
+    /// Docker version
+    pub version: String,
+    
+    /// Engine details
+    pub engine: EngineDetails,
+    
+    /// When the daemon was last seen running
+    pub seen_at: DateTime<Utc>,
+}
+
+impl std::fmt::Display for DockerStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DockerStatus::Running { version, .. } => write!(f, "Docker {version} running"),
+            DockerStatus::Stopped { reason: DockerUnavailableReason::Unknown } => write!(f, "Docker stopped"),
+            DockerStatus::Stopped { reason } => write!(f, "Docker stopped: {reason}"),
+            DockerStatus::Error { message, .. } => write!(f, "Docker error: {message}"),
+            DockerStatus::Paused => write!(f, "Monitoring paused"),
+            DockerStatus::Starting => write!(f, "Docker starting"),
+            DockerStatus::Restarting => write!(f, "Docker restarting"),
+            DockerStatus::Degraded { reason } => write!(f, "Docker degraded: {reason}"),
+            DockerStatus::Unreachable { endpoint, .. } => write!(f, "Docker host {endpoint} unreachable"),
+        }
+    }
 }
 
 /// Comprehensive error types for Docker monitoring operations.
@@ -81,22 +172,314 @@ pub enum DockerMonitorError {
     /// Internal error
     #[error("Internal error: {0}")]
     Internal(String),
+    
+    /// The daemon did not answer in time
+    #[error("Docker daemon unresponsive ({0})")]
+    Timeout(String),
+    
+    /// The Docker socket exists but the current user may not open it
+    #[error("Docker socket permission denied: {0}")]
+    SocketPermission(SocketPermission),
+    
+    /// The daemon's API is older than RedSys supports
+    #[error("Docker API version {found} is not supported; RedSys requires {required} or newer")]
+    UnsupportedVersion { found: String, required: String },
+    
+    /// Requested container resources exceed what the host provides
+    #[error("Resource limit exceeded: {0}")]
+    ResourceLimit(String),
+    
+    /// The signature of an image could not be verified with a trusted key
+    #[error("Image '{image}' is not trusted: {reason}")]
+    UntrustedImage { image: String, reason: String },
+}
+
+impl From<&DockerMonitorError> for ErrorPayload {
+    fn from(error: &DockerMonitorError) -> Self {
+        Self {
+            code: error.code(),
+            message: error.to_string(),
+            details: error.details(),
+            retryable: error.retryable(),
+        }
+    }
+}
+
+impl Serialize for DockerMonitorError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorPayload::from(self).serialize(serializer)
+    }
 }
 
 /// Result type for Docker monitoring operations
 pub type DockerMonitorResult<T> = Result<T, DockerMonitorError>;
 
+impl DockerMonitorError {
+    /// Machine-readable code of the error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            DockerMonitorError::Connection(_) => ErrorCode::DockerUnavailable,
+            DockerMonitorError::Api(_) => ErrorCode::DockerApi,
+            DockerMonitorError::EventEmission(_) => ErrorCode::EventEmission,
+            DockerMonitorError::Internal(_) => ErrorCode::Internal,
+            DockerMonitorError::Timeout(_) => ErrorCode::DockerTimeout,
+            DockerMonitorError::SocketPermission(_) => ErrorCode::DockerSocketPermission,
+            DockerMonitorError::UnsupportedVersion { .. } => ErrorCode::DockerUnsupportedVersion,
+            DockerMonitorError::ResourceLimit(_) => ErrorCode::ResourceLimit,
+            DockerMonitorError::UntrustedImage { .. } => ErrorCode::PolicyViolation,
+        }
+    }
+    
+    /// Whether retrying may succeed, e.g. once the daemon is back.
+    pub fn retryable(&self) -> bool {
+        matches!(self, DockerMonitorError::Connection(_) | DockerMonitorError::Timeout(_))
+    }
+    
+    /// Structured context for the frontend, if any.
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            DockerMonitorError::SocketPermission(permission) => serde_json::to_value(permission).ok(),
+            DockerMonitorError::UnsupportedVersion { found, required } => {
+                Some(serde_json::json!({ "found": found, "required": required }))
+            }
+            DockerMonitorError::UntrustedImage { image, reason } => {
+                Some(serde_json::json!({ "image": image, "reason": reason }))
+            }
+            _ => None,
+        }
+    }
+    
+    /// Wraps a failed API call, diagnosing socket permission failures.
+    pub(crate) async fn api(context: &str, error: bollard::errors::Error) -> Self {
+        match diagnosis::socket_permission(&error, DockerMonitor::docker_host().as_deref()).await {
+            Some(permission) => DockerMonitorError::SocketPermission(permission),
+            None => DockerMonitorError::Api(format!("{context}: {error}")),
+        }
+    }
+}
+
+/// Default daemon liveness polling interval.
+pub const DEFAULT_POLLING_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Allowed range for each polling interval.
+pub(crate) const POLLING_INTERVAL_BOUNDS_MS: (u64, u64) = (100, 60_000);
+
+/// Polling intervals for the phases of the monitoring loop.
+/// 
+/// All three default to [`DEFAULT_POLLING_INTERVAL`], which keeps up and down
+/// detection symmetric. Power users can slow polling down on laptops or speed
+/// it up on CI machines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorIntervals {
+    /// Used during status transitions and suspected daemon restarts
+    pub quick_ms: u64,
+    
+    /// Used while a new status settles
+    pub fast_ms: u64,
+    
+    /// Used once the status has been stable for a few checks
+    pub normal_ms: u64,
+}
+
+impl MonitorIntervals {
+    /// Uses the same interval for every phase.
+    pub fn uniform(polling_interval: Duration) -> Self {
+        let ms = polling_interval.as_millis() as u64;
+        Self { quick_ms: ms, fast_ms: ms, normal_ms: ms }
+    }
+    
+    /// Validates the bounds and that `quick_ms <= fast_ms <= normal_ms`.
+    pub fn validate(&self) -> AppResult<()> {
+        let (min, max) = POLLING_INTERVAL_BOUNDS_MS;
+        for (name, value) in [("quick_ms", self.quick_ms), ("fast_ms", self.fast_ms), ("normal_ms", self.normal_ms)] {
+            if !(min..=max).contains(&value) {
+                return Err(AppError::Configuration(format!(
+                    "{name} must be between {min} and {max}"
+                )));
+            }
+        }
+        if self.quick_ms > self.fast_ms || self.fast_ms > self.normal_ms {
+            return Err(AppError::Configuration(
+                "Intervals must satisfy quick_ms <= fast_ms <= normal_ms".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for MonitorIntervals {
+    fn default() -> Self {
+        Self::uniform(DEFAULT_POLLING_INTERVAL)
+    }
+}
+
+/// **SYMMETRIC** timeout of each step of a daemon check, identical for all operations.
+const CHECK_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Upper bound for an on-demand check, covering all connection fallbacks.
+const FORCE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Transitions buffered per subscriber before slow subscribers start missing them.
+const TRANSITIONS_CHANNEL_CAPACITY: usize = 64;
+
+/// Consecutive timed-out checks that open the health-check circuit.
+const CIRCUIT_FAILURE_THRESHOLD: u32 = 3;
+
+/// How long an open circuit skips checks before trying the daemon again.
+const CIRCUIT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Circuit breaker for daemon health checks.
+/// 
+/// A wedged daemon accepts connections but never answers, so every check
+/// waits for its full timeout. After [`CIRCUIT_FAILURE_THRESHOLD`] timed-out
+/// checks in a row the circuit opens and checks are skipped for
+/// [`CIRCUIT_COOLDOWN`]. The first check after the cooldown decides: another
+/// timeout reopens the circuit, anything else closes it.
+#[derive(Debug)]
+struct CircuitBreaker {
+    /// Timed-out checks in a row
+    consecutive_timeouts: u32,
+    
+    /// End of the cooldown while the circuit is open
+    retry_after: Option<DateTime<Utc>>,
+}
+
+impl CircuitBreaker {
+    fn new() -> Self {
+        Self {
+            consecutive_timeouts: 0,
+            retry_after: None,
+        }
+    }
+    
+    /// End of the cooldown if the circuit is open at `now`.
+    fn open_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.retry_after.filter(|retry_after| now < *retry_after)
+    }
+    
+    /// Records a check outcome, returning the end of the cooldown if the circuit (re)opened.
+    fn record(&mut self, timed_out: bool, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if !timed_out {
+            self.reset();
+            return None;
+        }
+        self.consecutive_timeouts += 1;
+        if self.consecutive_timeouts < CIRCUIT_FAILURE_THRESHOLD {
+            return None;
+        }
+        let retry_after = now + CIRCUIT_COOLDOWN;
+        self.retry_after = Some(retry_after);
+        Some(retry_after)
+    }
+    
+    /// Closes the circuit.
+    fn reset(&mut self) {
+        self.consecutive_timeouts = 0;
+        self.retry_after = None;
+    }
+    
+    /// Status reported while the circuit is open.
+    fn open_status(retry_after: DateTime<Utc>) -> DockerStatus {
+        DockerStatus::Error {
+            message: "Docker daemon unresponsive, health checks paused".to_string(),
+            retry_after: Some(retry_after),
+        }
+    }
+}
+
+/// Oldest Docker Engine API RedSys supports (Docker Engine 20.10).
+pub const MINIMUM_API_VERSION: ClientVersion = ClientVersion { major_version: 1, minor_version: 41 };
+
+/// Read/write timeout in seconds for explicitly addressed connections (Bollard's default).
+const CLIENT_TIMEOUT_SECS: u64 = 120;
+
+/// Process-wide `DOCKER_HOST` override from the agent configuration.
+static DOCKER_HOST_OVERRIDE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Interval at which an unchanged status is published and logged again.
+pub const STATUS_HEARTBEAT: Duration = Duration::from_secs(5 * 60);
+
+/// Rate limit for the log line of failed connection attempts.
+static CONNECTION_FAILURES: Mutex<Deduplicator<()>> = Mutex::new(Deduplicator::new(STATUS_HEARTBEAT));
+
+/// What makes a status worth reporting again.
+///
+/// Errors are compared by whether the circuit is open, not by their message:
+/// a hanging daemon times out in different places on consecutive checks.
+#[derive(Debug, Clone, PartialEq)]
+enum ReportKey {
+    Status(DockerStatus),
+    Error { circuit_open: bool },
+}
+
+impl From<&DockerStatus> for ReportKey {
+    fn from(status: &DockerStatus) -> Self {
+        match status {
+            DockerStatus::Error { retry_after, .. } => Self::Error { circuit_open: retry_after.is_some() },
+            status => Self::Status(status.clone()),
+        }
+    }
+}
+
+/// Sets or clears the `DOCKER_HOST` override used by all Docker connections.
+///
+/// Takes precedence over the `DOCKER_HOST` environment variable.
+pub fn set_docker_host_override(host: Option<String>) {
+    info!("Docker host override set to {:?}", host);
+    *DOCKER_HOST_OVERRIDE.write().unwrap_or_else(|e| e.into_inner()) = host;
+    connection::clear();
+}
+
 /// Docker daemon monitor with thread-safe state management.
 /// 
 /// Provides continuous monitoring of Docker daemon status with real-time
 /// updates and comprehensive error handling.
 #[derive(Debug)]
 pub struct DockerMonitor {
-    /// Current Docker status protected by async mutex
-    status: Arc<Mutex<DockerStatus>>,
-    
     /// Cancellation token for graceful shutdown
     cancellation_token: Arc<CancellationToken>,
+    
+    /// Polling intervals, adjustable at runtime
+    intervals: RwLock<MonitorIntervals>,
+    
+    /// Factor the fast and normal intervals are stretched by, e.g. on battery
+    interval_factor: AtomicU32,
+    
+    /// Set to drop the cached connection on the next check
+    reconnect_requested: AtomicBool,
+    
+    /// Wakes the monitoring loop for an out-of-band check
+    check_requested: Notify,
+    
+    /// Signalled after every completed check
+    check_completed: Notify,
+    
+    /// How long the last daemon check took
+    last_check_duration: RwLock<Option<Duration>>,
+    
+    /// Shared monitoring pause switch
+    pause: Arc<PauseControl>,
+    
+    /// Current Docker status, broadcast to in-process subscribers
+    status: watch::Sender<DockerStatus>,
+    
+    /// Engine last seen running
+    last_known: RwLock<Option<LastKnownEngine>>,
+    
+    /// Daemon state transitions, broadcast to in-process subscribers
+    transitions: broadcast::Sender<StatusTransition>,
+    
+    /// Desktop notifications for daemon failures
+    notifier: Option<Arc<Notifier>>,
+    
+    /// Event bus for status changes
+    bus: Arc<Bus>,
+    
+    /// Client checked instead of the shared connection, e.g. a mock
+    client: Option<Arc<dyn DockerClient>>,
+    
+    /// Injected faults that override checks
+    faults: Option<Arc<FaultInjector>>,
 }
 
 impl DockerMonitor {
@@ -106,16 +489,203 @@ impl DockerMonitor {
     pub fn new(cancellation_token: CancellationToken) -> Self {
         info!("Initializing Docker monitor");
         Self {
-            status: Arc::new(Mutex::new(DockerStatus::Stopped)),
             cancellation_token: Arc::new(cancellation_token),
+            intervals: RwLock::new(MonitorIntervals::default()),
+            interval_factor: AtomicU32::new(1),
+            reconnect_requested: AtomicBool::new(false),
+            check_requested: Notify::new(),
+            check_completed: Notify::new(),
+            last_check_duration: RwLock::new(None),
+            pause: Arc::new(PauseControl::new()),
+            status: watch::Sender::new(DockerStatus::Stopped { reason: DockerUnavailableReason::Unknown }),
+            last_known: RwLock::new(None),
+            transitions: broadcast::channel(TRANSITIONS_CHANNEL_CAPACITY).0,
+            notifier: None,
+            bus: Arc::new(Bus::new()),
+            client: None,
+            faults: None,
+        }
+    }
+    
+    /// Checks `client` instead of connecting to the configured daemon.
+    /// 
+    /// Used with [`MockDockerClient`](crate::client::MockDockerClient) to
+    /// run the monitor without a daemon.
+    pub fn with_client(self, client: Arc<dyn DockerClient>) -> Self {
+        Self {
+            client: Some(client),
+            ..self
+        }
+    }
+    
+    /// Reports faults injected through `faults` instead of checking the
+    /// daemon while they are active.
+    pub fn with_fault_injector(self, faults: Arc<FaultInjector>) -> Self {
+        Self {
+            faults: Some(faults),
+            ..self
+        }
+    }
+    
+    /// Shares a monitoring pause switch.
+    /// 
+    /// While paused the monitor stops polling and reports [`DockerStatus::Paused`].
+    pub fn with_pause_control(self, pause: Arc<PauseControl>) -> Self {
+        Self { pause, ..self }
+    }
+    
+    /// Shows desktop notifications when the daemon stops or fails.
+    pub fn with_notifier(self, notifier: Arc<Notifier>) -> Self {
+        Self {
+            notifier: Some(notifier),
+            ..self
+        }
+    }
+    
+    /// Shares the event bus that status changes are published to.
+    pub fn with_event_bus(self, bus: Arc<Bus>) -> Self {
+        Self { bus, ..self }
+    }
+    
+    /// Sets the daemon liveness polling interval.
+    pub fn with_polling_interval(self, polling_interval: Duration) -> Self {
+        self.set_polling_interval(polling_interval);
+        self
+    }
+    
+    /// Gets the polling intervals.
+    pub fn intervals(&self) -> MonitorIntervals {
+        *self.intervals.read().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Changes the polling intervals of a running monitor.
+    /// 
+    /// Takes effect after the next check.
+    pub fn set_intervals(&self, intervals: MonitorIntervals) -> AppResult<()> {
+        intervals.validate()?;
+        info!("Docker monitor intervals set to {:?}", intervals);
+        *self.intervals.write().unwrap_or_else(|e| e.into_inner()) = intervals;
+        Ok(())
+    }
+    
+    /// Uses the same polling interval for every phase of a running monitor.
+    /// 
+    /// Takes effect after the next check.
+    pub fn set_polling_interval(&self, polling_interval: Duration) {
+        *self.intervals.write().unwrap_or_else(|e| e.into_inner()) = MonitorIntervals::uniform(polling_interval);
+    }
+    
+    /// Stretches the fast and normal polling intervals by `factor`, e.g. to
+    /// save battery. The quick interval is kept so transitions are still
+    /// followed closely.
+    /// 
+    /// A factor of 1 restores the configured intervals. Takes effect after the
+    /// next check.
+    pub fn set_interval_factor(&self, factor: u32) {
+        self.interval_factor.store(factor.max(1), Ordering::Relaxed);
+    }
+    
+    /// Gets the polling intervals in use, stretched by the interval factor.
+    fn effective_intervals(&self) -> MonitorIntervals {
+        let factor = self.interval_factor.load(Ordering::Relaxed) as u64;
+        let stretch = |ms: u64| (ms * factor).min(POLLING_INTERVAL_BOUNDS_MS.1);
+        let intervals = self.intervals();
+        MonitorIntervals {
+            quick_ms: intervals.quick_ms,
+            fast_ms: stretch(intervals.fast_ms),
+            normal_ms: stretch(intervals.normal_ms),
+        }
+    }
+    
+    /// Drops the cached Docker connection so the next check reconnects,
+    /// picking up a changed Docker host.
+    pub fn reconnect(&self) {
+        self.reconnect_requested.store(true, Ordering::Relaxed);
+    }
+    
+    /// Runs a check immediately instead of waiting for the next interval.
+    /// 
+    /// The check goes through the monitoring loop, so status change events and
+    /// restart detection behave exactly as for scheduled checks.
+    /// 
+    /// Returns the fresh status, [`DockerStatus::Paused`] while monitoring is
+    /// paused, or an error if the monitoring loop is not running or the check
+    /// did not complete in time.
+    pub async fn force_check(&self) -> DockerMonitorResult<DockerStatus> {
+        if self.pause.is_paused() {
+            return Ok(DockerStatus::Paused);
         }
+        
+        let completed = self.check_completed.notified();
+        tokio::pin!(completed);
+        // Register before requesting so a fast check cannot be missed
+        completed.as_mut().enable();
+        self.check_requested.notify_one();
+        
+        tokio::time::timeout(FORCE_CHECK_TIMEOUT, completed)
+            .await
+            .map_err(|_| DockerMonitorError::Internal("Docker check did not complete in time".to_string()))?;
+        Ok(self.get_current_status().await)
+    }
+    
+    /// Resolves the effective Docker host: configuration override first,
+    /// then the `DOCKER_HOST` environment variable.
+    pub(crate) fn docker_host() -> Option<String> {
+        DOCKER_HOST_OVERRIDE
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .or_else(|| std::env::var("DOCKER_HOST").ok())
+    }
+    
+    /// How long the last daemon check took, `None` before the first one.
+    /// 
+    /// Checks skipped while the circuit breaker is open don't count.
+    pub fn last_check_duration(&self) -> Option<Duration> {
+        *self.last_check_duration.read().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Subscribes to status changes, as announced by `docker-status-changed`.
+    /// 
+    /// Any number of consumers can await [`watch::Receiver::changed`] instead
+    /// of polling [`get_current_status`](Self::get_current_status).
+    pub fn subscribe_status(&self) -> watch::Receiver<DockerStatus> {
+        self.status.subscribe()
+    }
+    
+    /// Engine last seen running, `None` until the daemon was seen running.
+    pub fn last_known(&self) -> Option<LastKnownEngine> {
+        self.last_known.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    
+    /// Payload announcing `status`, with the engine last seen running unless
+    /// the daemon is running now.
+    fn status_payload(&self, status: DockerStatus) -> DockerStatusChangedPayload {
+        let last_known = match status {
+            DockerStatus::Running { .. } => None,
+            _ => self.last_known(),
+        };
+        DockerStatusChangedPayload::new(status).with_last_known(last_known)
+    }
+    
+    /// Subscribes to daemon state transitions, as announced by
+    /// `docker-status-transition`.
+    pub fn subscribe_transitions(&self) -> broadcast::Receiver<StatusTransition> {
+        self.transitions.subscribe()
+    }
+    
+    /// Announces a state transition to the frontend and in-process subscribers.
+    fn transitioned(&self, app_handle: &tauri::AppHandle, transition: StatusTransition) {
+        transition.emit(app_handle);
+        // No subscribers is fine
+        let _ = self.transitions.send(transition);
     }
     
     /// Gets the current Docker status.
     /// 
-    /// Returns a clone of the current status for thread-safe access.
+    /// Returns a clone of the latest status without waiting on the monitor.
     pub async fn get_current_status(&self) -> DockerStatus {
-        self.status.lock().await.clone()
+        self.status.borrow().clone()
     }
     
     /// Establishes connection to Docker daemon with robust cross-platform fallback strategy.
@@ -131,16 +701,17 @@ impl DockerMonitor {
     /// - [Bollard Connection Methods](https://docs.rs/bollard/latest/bollard/struct.Docker.html)
     /// - [Docker Engine API](https://docs.docker.com/engine/api/)
     /// - [Docker Host Configuration](https://docs.docker.com/engine/reference/commandline/cli/#environment-variables)
-    async fn get_docker_client() -> DockerMonitorResult<Docker> {
+    pub(crate) async fn get_docker_client() -> DockerMonitorResult<Docker> {
         // **SYMMETRIC** Consistent timeout for balanced detection
         const CONNECTION_TIMEOUT: Duration = Duration::from_millis(800); // Shorter timeout for faster detection
         
-        // 1. Try DOCKER_HOST environment variable first (user override)
-        if let Ok(docker_host) = std::env::var("DOCKER_HOST") {
+        // 1. Try configured host or DOCKER_HOST environment variable first (user override)
+        if let Some(docker_host) = Self::docker_host() {
             debug!("Attempting DOCKER_HOST connection: {}", docker_host);
             match tokio::time::timeout(CONNECTION_TIMEOUT, Self::try_docker_host_connection()).await {
                 Ok(Ok(client)) => {
                     info!("Successfully connected to Docker via DOCKER_HOST");
+                    CONNECTION_FAILURES.lock().unwrap_or_else(|e| e.into_inner()).reset();
                     return Ok(client);
                 }
                 Ok(Err(e)) => {
@@ -157,6 +728,7 @@ impl DockerMonitor {
         match tokio::time::timeout(CONNECTION_TIMEOUT, Self::try_platform_default_connection()).await {
             Ok(Ok(client)) => {
                 info!("Successfully connected to Docker via platform default");
+                CONNECTION_FAILURES.lock().unwrap_or_else(|e| e.into_inner()).reset();
                 return Ok(client);
             }
             Ok(Err(e)) => {
@@ -172,6 +744,7 @@ impl DockerMonitor {
         match tokio::time::timeout(CONNECTION_TIMEOUT, Self::try_http_connection()).await {
             Ok(Ok(client)) => {
                 info!("Successfully connected to Docker via HTTP defaults");
+                CONNECTION_FAILURES.lock().unwrap_or_else(|e| e.into_inner()).reset();
                 return Ok(client);
             }
             Ok(Err(e)) => {
@@ -182,8 +755,13 @@ impl DockerMonitor {
             }
         }
         
-        // All connection methods failed
-        error!("All Docker connection methods failed");
+        // All connection methods failed, which repeats every check while the daemon is down
+        let now = std::time::Instant::now();
+        match CONNECTION_FAILURES.lock().unwrap_or_else(|e| e.into_inner()).observe((), now) {
+            Some(0) => error!("All Docker connection methods failed"),
+            Some(suppressed) => error!("All Docker connection methods failed ({suppressed} more times since last report)"),
+            None => debug!("All Docker connection methods failed"),
+        }
         Err(DockerMonitorError::Connection(
             bollard::errors::Error::DockerResponseServerError {
                 status_code: 503,
@@ -192,39 +770,82 @@ impl DockerMonitor {
         ))
     }
     
-    /// Attempts platform-specific default connection based on runtime detection.
+    /// Gets the [shared connection](crate::connection), connecting and
+    /// negotiating the API version if there is none.
+    pub(crate) async fn connect() -> DockerMonitorResult<Arc<Docker>> {
+        connection::client().await
+    }
+    
+    /// Negotiates the API version of a new connection, downgrading the client
+    /// to the daemon's version.
+    /// 
+    /// Fails with [`DockerMonitorError::UnsupportedVersion`] if the daemon is
+    /// older than [`MINIMUM_API_VERSION`].
+    pub(crate) async fn negotiate(client: Docker) -> DockerMonitorResult<Docker> {
+        let client = client.negotiate_version().await?;
+        let found = client.client_version();
+        if found < MINIMUM_API_VERSION {
+            return Err(DockerMonitorError::UnsupportedVersion {
+                found: found.to_string(),
+                required: MINIMUM_API_VERSION.to_string(),
+            });
+        }
+        debug!("Negotiated Docker API version {}", found);
+        Ok(client)
+    }
+    
+    /// Attempts the platform-specific default connection, the
+    /// `docker_engine` named pipe on Windows.
+    #[cfg(windows)]
+    async fn try_platform_default_connection() -> Result<Docker, bollard::errors::Error> {
+        debug!("Attempting Windows named pipe connection");
+        Docker::connect_with_named_pipe_defaults()
+    }
+    
+    /// Attempts the platform-specific default connection, the system socket
+    /// on Unix.
     /// 
-    /// This method uses runtime detection to determine the best connection method
-    /// for the current platform, following Docker's standard installation patterns.
+    /// Falls back to the socket of a rootless daemon when no system daemon
+    /// is installed.
+    #[cfg(not(windows))]
     async fn try_platform_default_connection() -> Result<Docker, bollard::errors::Error> {
-        if cfg!(target_os = "windows") {
-            debug!("Attempting Windows named pipe connection");
-            Docker::connect_with_named_pipe_defaults()
-        } else {
-            debug!("Attempting Unix socket connection");
-            Docker::connect_with_socket_defaults()
+        debug!("Attempting Unix socket connection");
+        match Docker::connect_with_socket_defaults() {
+            Err(bollard::errors::Error::SocketNotFoundError(path)) => {
+                let Some(socket) = Self::rootless_socket() else {
+                    return Err(bollard::errors::Error::SocketNotFoundError(path));
+                };
+                debug!("{} not found, attempting rootless socket {}", path, socket.display());
+                Docker::connect_with_socket(&socket.to_string_lossy(), CLIENT_TIMEOUT_SECS, API_DEFAULT_VERSION)
+            }
+            result => result,
         }
     }
     
-    /// Attempts connection using DOCKER_HOST environment variable.
+    /// Socket of the current user's rootless daemon, if one is listening.
+    /// 
+    /// See [Rootless mode](https://docs.docker.com/engine/security/rootless/).
+    #[cfg(not(windows))]
+    fn rootless_socket() -> Option<std::path::PathBuf> {
+        let socket = std::path::Path::new(&std::env::var_os("XDG_RUNTIME_DIR")?).join("docker.sock");
+        socket.exists().then_some(socket)
+    }
+    
+    /// Attempts connection using the configured host or `DOCKER_HOST` environment variable.
     /// 
     /// **Supported Formats:**
-    /// - `tcp://host:port` - TCP connection
+    /// - `tcp://host:port` / `http://host:port` - TCP connection
     /// - `unix:///path/to/socket` - Unix socket
     /// - `npipe:///./pipe/name` - Windows named pipe
     async fn try_docker_host_connection() -> Result<Docker, bollard::errors::Error> {
-        if let Ok(docker_host) = std::env::var("DOCKER_HOST") {
+        if let Some(docker_host) = Self::docker_host() {
             debug!("Attempting DOCKER_HOST connection: {}", docker_host);
             
-            if docker_host.starts_with("tcp://") {
-                // Use HTTP defaults for TCP connections
-                Docker::connect_with_http_defaults()
-            } else if docker_host.starts_with("unix://") {
-                // Use socket defaults for Unix socket connections
-                Docker::connect_with_socket_defaults()
-            } else if docker_host.starts_with("npipe://") {
-                // Use named pipe defaults for Windows named pipe connections
-                Docker::connect_with_named_pipe_defaults()
+            if docker_host.starts_with("tcp://") || docker_host.starts_with("http://") {
+                Docker::connect_with_http(&docker_host, CLIENT_TIMEOUT_SECS, API_DEFAULT_VERSION)
+            } else if docker_host.starts_with("unix://") || docker_host.starts_with("npipe://") {
+                // Bollard picks Unix sockets or named pipes based on the target platform
+                Docker::connect_with_socket(&docker_host, CLIENT_TIMEOUT_SECS, API_DEFAULT_VERSION)
             } else {
                 // Invalid DOCKER_HOST format
                 Err(bollard::errors::Error::DockerResponseServerError {
@@ -258,10 +879,10 @@ impl DockerMonitor {
     
     /// Starts the main monitoring loop with resource-efficient, fast Docker daemon monitoring.
     /// 
-    /// **Smart Resource-Efficient Polling Strategy:**
-    /// - **Fast polling (1.5s)**: Standard monitoring for critical daemon status
-    /// - **Quick polling (800ms)**: During status transitions and restart detection
-    /// - **Normal polling (3s)**: When status is stable but still responsive
+    /// **Smart Resource-Efficient Polling Strategy** (see [`MonitorIntervals`]):
+    /// - **Quick polling**: During status transitions and restart detection
+    /// - **Fast polling**: While a new status settles
+    /// - **Normal polling**: When status is stable but still responsive
     /// - **Change detection**: Emits events immediately on any daemon status change
     /// - **Restart detection**: Uses intelligent pattern recognition for daemon restarts
     /// - **Resource optimization**: Minimal CPU and network usage while maintaining responsiveness
@@ -275,7 +896,7 @@ impl DockerMonitor {
     /// - Reliable response to daemon restarts for platform reliability
     /// 
     /// **Resource Efficiency Features:**
-    /// - **Fast but not aggressive**: 800ms minimum polling to avoid system overload
+    /// - **Fast but not aggressive**: 100ms minimum polling to avoid system overload
     /// - **Connection reuse**: Minimizes connection overhead
     /// - **Pattern recognition**: Detects daemon restarts without excessive polling
     /// - **Memory efficient**: Bounded history for pattern detection
@@ -290,114 +911,281 @@ impl DockerMonitor {
         self: Arc<Self>,
         app_handle: tauri::AppHandle,
     ) {
-        let status = self.status.clone();
         let cancellation_token = self.cancellation_token.clone();
+        info!("Starting Docker daemon monitoring for RedSys platform with {:?}", self.intervals());
 
-        info!("Starting perfectly symmetric Docker daemon monitoring for RedSys platform");
+        let mut machine = StatusMachine::new();
+        let mut circuit = CircuitBreaker::new();
+        let mut reports = Deduplicator::new(STATUS_HEARTBEAT);
+        let mut daemon_version: Option<(String, EngineDetails)> = None;
+        
+        // **SYMMETRIC** - Same thresholds for all states
+        const STABLE_THRESHOLD: u32 = 3; // Switch to normal after 3 checks
+        
+        let mut current_interval = Duration::from_millis(self.effective_intervals().quick_ms);
+        let mut poller = interval(current_interval);
 
-        task::spawn(async move {
-            let mut last_status: Option<DockerStatus> = None;
-            let mut consecutive_same_status = 0;
-            let mut last_change_time = std::time::Instant::now();
-            let mut status_history: Vec<(DockerStatus, std::time::Instant)> = Vec::new();
-            let mut potential_restart_detected = false;
-            let mut connection_cache: Option<Docker> = None;
-            
-            // **PERFECTLY SYMMETRIC** - Same intervals for all states
-            const POLLING_INTERVAL: Duration = Duration::from_millis(500); // Single interval for all states
-            
-            // **SYMMETRIC** - Same thresholds for all states
-            const STABLE_THRESHOLD: u32 = 3; // Switch to normal after 3 checks
-            const RESTART_DETECTION_WINDOW: Duration = Duration::from_secs(12);
-            const MAX_HISTORY_SIZE: usize = 6;
-            
-            let mut current_interval = POLLING_INTERVAL;
-            let mut poller = interval(current_interval);
-
-            loop {
-                tokio::select! {
-                    _ = poller.tick() => {
-                        let new_status = match Self::check_docker_with_cache(&mut connection_cache).await {
-                            Ok(DockerStatus::Running { version }) => DockerStatus::Running { version },
-                            Ok(other) => other,
-                            Err(e) => DockerStatus::Error { 
-                                message: format!("{e}") 
+        loop {
+            tokio::select! {
+                _ = poller.tick() => {
+                    // Apply runtime configuration changes
+                    if self.reconnect_requested.swap(false, Ordering::Relaxed) {
+                        debug!("Reconnect requested, dropping cached Docker connection");
+                        connection::clear();
+                        circuit.reset();
+                    }
+                    let intervals = self.effective_intervals();
+                    if let Some(transition) = machine.begin_check() {
+                        self.transitioned(&app_handle, transition);
+                    }
+                    
+                    let checked_status = if let Some(retry_after) = circuit.open_until(Utc::now()) {
+                        // Keep reporting the open circuit without touching the daemon
+                        CircuitBreaker::open_status(retry_after)
+                    } else {
+                        let check = self.check(&mut daemon_version);
+                        let started = std::time::Instant::now();
+                        let result = check.instrument(debug_span!("docker_check")).await;
+                        *self.last_check_duration.write().unwrap_or_else(|e| e.into_inner()) = Some(started.elapsed());
+                        let timed_out = matches!(result, Err(DockerMonitorError::Timeout(_)));
+                        match (result, circuit.record(timed_out, Utc::now())) {
+                            (_, Some(retry_after)) => {
+                                warn!(
+                                    "Docker daemon timed out {} checks in a row, pausing health checks for {}s",
+                                    CIRCUIT_FAILURE_THRESHOLD,
+                                    CIRCUIT_COOLDOWN.as_secs()
+                                );
+                                CircuitBreaker::open_status(retry_after)
+                            }
+                            (Ok(status), None) => status,
+                            (Err(e), None) => DockerStatus::Error {
+                                message: format!("{e}"),
+                                retry_after: None,
                             },
-                        };
+                        }
+                    };
 
-                        {
-                            let mut guard = status.lock().await;
-                            let status_changed = last_status.as_ref() != Some(&new_status);
+                    {
+                        let previous = machine.status().cloned();
+                        if let DockerStatus::Running { version, engine } = &checked_status {
+                            *self.last_known.write().unwrap_or_else(|e| e.into_inner()) = Some(LastKnownEngine {
+                                version: version.clone(),
+                                engine: engine.clone(),
+                                seen_at: Utc::now(),
+                            });
+                        }
+                        let observation = machine.observe(checked_status, std::time::Instant::now());
+                        let new_status = observation.status.clone();
+                        if let Some(transition) = observation.transition {
+                            self.transitioned(&app_handle, transition);
+                        }
+                        if let Some(restart) = observation.restart {
+                            restart.emit(&app_handle);
+                        }
+                        
+                        if observation.status_changed {
+                            if let (Some(notifier), Some(previous)) = (&self.notifier, &previous) {
+                                notifier.docker_status_changed(&app_handle, previous, &new_status);
+                            }
+                            self.status.send_replace(new_status.clone());
+                            
+                            // Poll quickly while the transition settles
+                            let quick_interval = Duration::from_millis(intervals.quick_ms);
+                            if current_interval != quick_interval {
+                                current_interval = quick_interval;
+                                poller = interval(current_interval);
+                                debug!("Docker daemon status changed to {:?}, switching to {}ms polling", 
+                                       new_status, intervals.quick_ms);
+                            }
                             
-                            if status_changed {
-                                // Status changed - update history efficiently
-                                let now = std::time::Instant::now();
-                                status_history.push((new_status.clone(), now));
-                                
-                                // Keep history bounded to prevent memory growth
-                                if status_history.len() > MAX_HISTORY_SIZE {
-                                    status_history.remove(0);
-                                }
-                                
-                                // Detect restart patterns efficiently
-                                potential_restart_detected = Self::detect_restart_pattern_efficient(&status_history);
-                                
-                                // Reset counters and emit event
-                                consecutive_same_status = 0;
-                                last_change_time = now;
-                                *guard = new_status.clone();
-                                last_status = Some(new_status.clone());
-                                
-                                // **SYMMETRIC** - Always use same interval on status change
-                                if current_interval != POLLING_INTERVAL {
-                                    current_interval = POLLING_INTERVAL;
-                                    poller = interval(current_interval);
-                                    debug!("Docker daemon status changed to {:?}, switching to {}ms polling", 
-                                           new_status, POLLING_INTERVAL.as_millis());
-                                }
-                                
-                                // Emit event to frontend immediately
-                                if let Err(e) = app_handle.emit("docker_status_changed", &new_status) {
-                                    error!("Failed to emit docker_status_changed event: {e}");
-                                }
-                                info!("Docker daemon status changed: {:?}", new_status);
+                            // Publish immediately, unless only the details changed
+                            if reports.observe(ReportKey::from(&new_status), std::time::Instant::now()).is_some() {
+                                info_span!("docker_status_changed").in_scope(|| {
+                                    self.bus.publish(Event::DaemonStatus(self.status_payload(new_status.clone())));
+                                    info!("Docker daemon status changed: {:?}", new_status);
+                                });
                             } else {
-                                // Same status - increment counter
-                                consecutive_same_status += 1;
-                                let time_since_last_change = last_change_time.elapsed();
-                                
-                                // **SYMMETRIC** - Same interval logic for all statuses
-                                let new_interval = if potential_restart_detected && time_since_last_change < RESTART_DETECTION_WINDOW {
-                                    POLLING_INTERVAL
-                                } else if consecutive_same_status >= STABLE_THRESHOLD {
-                                    POLLING_INTERVAL // Keep same interval even when stable
-                                } else {
-                                    POLLING_INTERVAL
-                                };
-                                
-                                // Switch interval if needed (should rarely happen now)
-                                if new_interval != current_interval {
-                                    current_interval = new_interval;
-                                    poller = interval(current_interval);
-                                    let interval_ms = current_interval.as_millis();
-                                    debug!("Daemon status stable for {} checks, switching to {}ms polling", 
-                                           consecutive_same_status, interval_ms);
-                                }
-                                
-                                // Clear restart detection flag when appropriate
-                                if time_since_last_change > RESTART_DETECTION_WINDOW && consecutive_same_status > STABLE_THRESHOLD {
-                                    potential_restart_detected = false;
-                                }
+                                debug!("Docker daemon status details changed: {:?}", new_status);
+                            }
+                        } else {
+                            // Heartbeat for a status that stays the same
+                            if let Some(suppressed) = reports.observe(ReportKey::from(&new_status), std::time::Instant::now()) {
+                                info_span!("docker_status_heartbeat").in_scope(|| {
+                                    self.bus.publish(Event::DaemonStatus(self.status_payload(new_status.clone())));
+                                    info!("Docker daemon status unchanged: {} ({} checks since last report)", new_status, suppressed);
+                                });
+                            }
+
+                            // **SYMMETRIC** - Same interval logic for all statuses
+                            let new_interval = Duration::from_millis(if machine.state() == DaemonState::Restarting {
+                                intervals.quick_ms
+                            } else if machine.stable_checks() >= STABLE_THRESHOLD {
+                                intervals.normal_ms
+                            } else {
+                                intervals.fast_ms
+                            });
+                            
+                            // Switch interval if needed (also picks up runtime interval changes)
+                            if new_interval != current_interval {
+                                current_interval = new_interval;
+                                poller = interval(current_interval);
+                                let interval_ms = current_interval.as_millis();
+                                debug!("Daemon status stable for {} checks, switching to {}ms polling", 
+                                       machine.stable_checks(), interval_ms);
                             }
                         }
                     }
-                    _ = cancellation_token.cancelled() => {
-                        info!("Docker monitor received cancellation signal, shutting down gracefully");
-                        break;
+                    self.check_completed.notify_waiters();
+                }
+                _ = self.pause.paused() => {
+                    info!("Docker monitor paused");
+                    connection::clear();
+                    // The status after resuming is news even if it is the same as before
+                    reports.reset();
+                    if let Some(transition) = machine.pause() {
+                        self.transitioned(&app_handle, transition);
+                    }
+                    self.status.send_replace(DockerStatus::Paused);
+                    info_span!("docker_status_changed").in_scope(|| {
+                        self.bus.publish(Event::DaemonStatus(self.status_payload(DockerStatus::Paused)));
+                    });
+                    
+                    tokio::select! {
+                        _ = self.pause.resumed() => {
+                            info!("Docker monitor resumed");
+                            if let Some(transition) = machine.resume() {
+                                self.transitioned(&app_handle, transition);
+                            }
+                            poller.reset_immediately();
+                        }
+                        _ = cancellation_token.cancelled() => {
+                            info!("Docker monitor received cancellation signal, shutting down gracefully");
+                            break;
+                        }
                     }
                 }
+                _ = self.check_requested.notified() => {
+                    debug!("On-demand Docker check requested");
+                    poller.reset_immediately();
+                }
+                _ = cancellation_token.cancelled() => {
+                    info!("Docker monitor received cancellation signal, shutting down gracefully");
+                    break;
+                }
+            }
+        }
+    }
+    
+    /// Performs one daemon check, over the configured client if there is one.
+    async fn check(&self, daemon_version: &mut Option<(String, EngineDetails)>) -> DockerMonitorResult<DockerStatus> {
+        if let Some(fault) = self.faults.as_ref().and_then(|faults| faults.active()) {
+            debug!("Injected fault {:?} active, skipping daemon check", fault);
+            *daemon_version = None;
+            return fault.check_result();
+        }
+        match &self.client {
+            Some(client) => Self::check_client(client.as_ref(), daemon_version).await,
+            None => Self::check_docker_with_cache(daemon_version).await,
+        }
+    }
+    
+    /// Checks the configured daemon once, outside the monitoring loop.
+    /// 
+    /// Used by the command-line subcommands, which run without a monitor.
+    pub async fn check_once() -> DockerMonitorResult<DockerStatus> {
+        Self::check_docker_with_cache(&mut None).await
+    }
+    
+    /// Checks the daemon once with the monitor's client and injected faults,
+    /// without publishing the result or waiting for the monitoring loop.
+    pub async fn check_now(&self) -> DockerMonitorResult<DockerStatus> {
+        self.check(&mut None).await
+    }
+    
+    /// Checks the daemon behind `client` without reconnecting.
+    /// 
+    /// `daemon_version` caches the version and engine details between checks
+    /// of a running daemon and is cleared when a check fails.
+    pub async fn check_client(
+        client: &dyn DockerClient,
+        daemon_version: &mut Option<(String, EngineDetails)>,
+    ) -> DockerMonitorResult<DockerStatus> {
+        match tokio::time::timeout(CHECK_TIMEOUT, Self::liveness(client, daemon_version.clone())).await {
+            Ok(Ok((version, engine))) => {
+                *daemon_version = Some((version.clone(), engine.clone()));
+                Ok(DockerStatus::Running { version, engine })
+            }
+            Ok(Err(e)) => {
+                *daemon_version = None;
+                Ok(Self::failed_check_status(e).await)
+            }
+            Err(_) => {
+                *daemon_version = None;
+                Err(DockerMonitorError::Timeout("timeout".to_string()))
             }
-        });
+        }
+    }
+    
+    /// Pings the daemon, fetching its version unless `known_version` is set.
+    async fn liveness(
+        client: &dyn DockerClient,
+        known_version: Option<(String, EngineDetails)>,
+    ) -> Result<(String, EngineDetails), bollard::errors::Error> {
+        client.ping().await?;
+        match known_version {
+            Some(known) => Ok(known),
+            None => Ok(Self::engine(client.version().await?).await),
+        }
+    }
+    
+    /// Version and engine details from a `/version` response.
+    async fn engine(version_info: SystemVersion) -> (String, EngineDetails) {
+        let started_at = tokio::task::spawn_blocking(Self::local_daemon_started_at).await.ok().flatten();
+        let engine = EngineDetails {
+            api_version: version_info.api_version,
+            os: version_info.os,
+            arch: version_info.arch,
+            started_at,
+        };
+        (version_info.version.unwrap_or_else(|| "Unknown".to_string()), engine)
+    }
+    
+    /// Start time of the local `dockerd` process, `None` if `DOCKER_HOST`
+    /// points elsewhere or no such process runs on this machine. Blocking.
+    fn local_daemon_started_at() -> Option<DateTime<Utc>> {
+        if Self::docker_host().is_some_and(|host| network::is_remote_host(&host)) {
+            return None;
+        }
+        let mut system = System::new();
+        system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::new());
+        let started = system
+            .processes()
+            .values()
+            .filter(|process| process.name() == "dockerd")
+            .map(|process| process.start_time())
+            .min()?;
+        DateTime::from_timestamp(started as i64, 0)
+    }
+    
+    /// Status for a check that the daemon failed.
+    /// 
+    /// Server errors come from a daemon that is still booting; connection
+    /// failures are diagnosed.
+    async fn failed_check_status(error: bollard::errors::Error) -> DockerStatus {
+        if let bollard::errors::Error::DockerResponseServerError { status_code, message } = &error {
+            if *status_code >= 500 {
+                debug!("Docker daemon not ready ({}): {}", status_code, message);
+                return DockerStatus::Starting;
+            }
+        }
+        let endpoint = Self::docker_host();
+        match diagnosis::diagnose(&error, endpoint.as_deref()).await {
+            Some(reason) => Self::unavailable(endpoint, reason),
+            None => DockerStatus::Error {
+                message: format!("Docker API error: {error}"),
+                retry_after: None,
+            },
+        }
     }
     
     /// **PERFECTLY SYMMETRIC** Performs Docker check with identical timeout strategy.
@@ -407,109 +1195,98 @@ impl DockerMonitor {
     /// - Identical detection speed for up and down states
     /// - Identical connection handling
     /// - Identical resource usage
-    async fn check_docker_with_cache(connection_cache: &mut Option<Docker>) -> DockerMonitorResult<DockerStatus> {
-        // **SYMMETRIC** - Identical timeout for all operations
-        const OPERATION_TIMEOUT: Duration = Duration::from_millis(800);
-        
+    /// 
+    /// Checks go over the [shared connection](crate::connection): a working
+    /// new connection is shared, a failing one dropped.
+    /// 
+    /// Liveness is probed with the cheap `/_ping` endpoint. The version is
+    /// only fetched when the daemon comes up and kept in `daemon_version`
+    /// while it stays up.
+    async fn check_docker_with_cache(daemon_version: &mut Option<(String, EngineDetails)>) -> DockerMonitorResult<DockerStatus> {
         // **SYMMETRIC** - Always test cached connections the same way
-        if let Some(client) = connection_cache {
-            match tokio::time::timeout(OPERATION_TIMEOUT, client.version()).await {
-                Ok(Ok(version_info)) => {
-                    let version = version_info.version.unwrap_or_else(|| "Unknown".to_string());
-                    return Ok(DockerStatus::Running { version });
+        if let Some(client) = connection::cached() {
+            let probe = Self::liveness(client.as_ref(), daemon_version.clone());
+            match tokio::time::timeout(CHECK_TIMEOUT, probe).await {
+                Ok(Ok((version, engine))) => {
+                    *daemon_version = Some((version.clone(), engine.clone()));
+                    return Ok(DockerStatus::Running { version, engine });
                 }
                 Ok(Err(_)) => {
                     // **SYMMETRIC** - Clear cache on any failure
                     debug!("Cached connection failed, clearing cache");
-                    *connection_cache = None;
+                    connection::invalidate(&client);
                 }
                 Err(_) => {
                     // **SYMMETRIC** - Clear cache on timeout
                     debug!("Cached connection timed out, clearing cache");
-                    *connection_cache = None;
+                    connection::invalidate(&client);
                 }
             }
+            // A daemon coming back may have been upgraded
+            *daemon_version = None;
         }
         
         // **SYMMETRIC** - Always try fresh connection the same way
-        match tokio::time::timeout(OPERATION_TIMEOUT, Self::get_docker_client()).await {
+        match tokio::time::timeout(CHECK_TIMEOUT, Self::get_docker_client()).await {
             Ok(Ok(client)) => {
-                // **SYMMETRIC** - Always test new connections the same way
-                match tokio::time::timeout(OPERATION_TIMEOUT, client.version()).await {
-                    Ok(Ok(version_info)) => {
-                        let version = version_info.version.unwrap_or_else(|| "Unknown".to_string());
+                // **SYMMETRIC** - Always test new connections the same way,
+                // negotiating the API version once for the cached connection
+                let test = async {
+                    let client = Self::negotiate(client).await?;
+                    let version_info = client.version().await?;
+                    Ok::<_, DockerMonitorError>((client, version_info))
+                };
+                match tokio::time::timeout(CHECK_TIMEOUT, test).await {
+                    Ok(Ok((client, version_info))) => {
+                        let (version, engine) = Self::engine(version_info).await;
                         // **SYMMETRIC** - Only cache if connection is fully working
-                        *connection_cache = Some(client);
-                        Ok(DockerStatus::Running { version })
+                        connection::store(client);
+                        *daemon_version = Some((version.clone(), engine.clone()));
+                        Ok(DockerStatus::Running { version, engine })
                     }
-                    Ok(Err(e)) => {
+                    Ok(Err(e @ DockerMonitorError::UnsupportedVersion { .. })) => {
+                        debug!("{}", e);
+                        Ok(DockerStatus::Error { message: e.to_string(), retry_after: None })
+                    }
+                    Ok(Err(DockerMonitorError::Connection(e))) => {
                         // **SYMMETRIC** - Don't cache failed connections
                         debug!("New connection failed API test: {}", e);
-                        Ok(DockerStatus::Error { 
-                            message: format!("Docker API error: {e}") 
-                        })
+                        Ok(Self::failed_check_status(e).await)
                     }
+                    Ok(Err(e)) => Ok(DockerStatus::Error { message: e.to_string(), retry_after: None }),
                     Err(_) => {
                         // **SYMMETRIC** - Don't cache timeout connections
                         debug!("New connection timed out on API test");
-                        Ok(DockerStatus::Error { 
-                            message: "Docker daemon unresponsive (timeout)".to_string() 
-                        })
+                        Err(DockerMonitorError::Timeout("timeout".to_string()))
                     }
                 }
             }
-            Ok(Err(_e)) => {
+            Ok(Err(e)) => {
                 debug!("All connection methods failed");
-                Ok(DockerStatus::Stopped)
+                let endpoint = Self::docker_host();
+                let reason = match &e {
+                    DockerMonitorError::Connection(e) => diagnosis::diagnose(e, endpoint.as_deref()).await,
+                    _ => None,
+                };
+                Ok(Self::unavailable(endpoint, reason.unwrap_or(DockerUnavailableReason::Unknown)))
             }
             Err(_) => {
                 debug!("Connection attempt timed out");
-                Ok(DockerStatus::Stopped)
+                Err(DockerMonitorError::Timeout("connection timeout".to_string()))
             }
         }
     }
     
-    /// Efficient restart pattern detection with bounded memory usage.
-    /// 
-    /// **Optimized Pattern Detection:**
-    /// - Uses bounded history to prevent memory growth
-    /// - Efficient pattern matching with minimal CPU usage
-    /// - Focuses on most common restart patterns
-    /// - Reduces false positives
-    fn detect_restart_pattern_efficient(status_history: &[(DockerStatus, std::time::Instant)]) -> bool {
-        if status_history.len() < 3 {
-            return false;
-        }
-        
-        let now = std::time::Instant::now();
-        let recent_history: Vec<_> = status_history
-            .iter()
-            .filter(|(_, time)| now.duration_since(*time) < Duration::from_secs(20))
-            .take(5) // Limit to last 5 entries for efficiency
-            .collect();
-            
-        if recent_history.len() < 3 {
-            return false;
+    /// Status for a daemon that cannot be reached.
+    ///
+    /// A local daemon that is not running is stopped, whether it listens on
+    /// the default socket or a configured socket or named pipe; a host
+    /// reached over the network may be running but out of reach.
+    fn unavailable(endpoint: Option<String>, reason: DockerUnavailableReason) -> DockerStatus {
+        match endpoint {
+            Some(endpoint) if network::is_remote_host(&endpoint) => DockerStatus::Unreachable { endpoint, reason },
+            _ => DockerStatus::Stopped { reason },
         }
-        
-        // Look for Running -> Stopped -> Running pattern
-        for window in recent_history.windows(3) {
-            if let [prev, curr, next] = window {
-                let time_between_prev_curr = curr.1.duration_since(prev.1);
-                let time_between_curr_next = next.1.duration_since(curr.1);
-                
-                // Check for restart pattern with reasonable timing
-                if matches!(prev.0, DockerStatus::Running { .. }) &&
-                   matches!(curr.0, DockerStatus::Stopped) &&
-                   matches!(next.0, DockerStatus::Running { .. }) &&
-                   time_between_prev_curr < Duration::from_secs(8) &&
-                   time_between_curr_next < Duration::from_secs(15) {
-                    return true;
-                }
-            }
-        }
-        
-        false
     }
     
     /// Cancels the monitoring task for graceful shutdown.
@@ -522,33 +1299,203 @@ impl DockerMonitor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::{MockDaemon, MockDockerClient};
+    use crate::status::TransitionReason;
 
     #[tokio::test]
     async fn test_docker_monitor_new() {
         let token = CancellationToken::new();
         let monitor = DockerMonitor::new(token);
         let status = monitor.get_current_status().await;
-        assert!(matches!(status, DockerStatus::Stopped));
+        assert!(matches!(status, DockerStatus::Stopped { .. }));
+    }
+
+    #[test]
+    fn test_monitor_intervals_validation() {
+        assert!(MonitorIntervals::default().validate().is_ok());
+        assert!(MonitorIntervals { quick_ms: 800, fast_ms: 1500, normal_ms: 3000 }.validate().is_ok());
+        assert!(MonitorIntervals { quick_ms: 10, fast_ms: 1500, normal_ms: 3000 }.validate().is_err());
+        assert!(MonitorIntervals { quick_ms: 800, fast_ms: 1500, normal_ms: 120_000 }.validate().is_err());
+        assert!(MonitorIntervals { quick_ms: 2000, fast_ms: 1500, normal_ms: 3000 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_set_intervals_keeps_previous_on_error() {
+        let monitor = DockerMonitor::new(CancellationToken::new());
+        let intervals = MonitorIntervals { quick_ms: 800, fast_ms: 1500, normal_ms: 3000 };
+        monitor.set_intervals(intervals).unwrap();
+        assert!(monitor.set_intervals(MonitorIntervals { quick_ms: 0, fast_ms: 0, normal_ms: 0 }).is_err());
+        assert_eq!(monitor.intervals(), intervals);
+    }
+
+    #[test]
+    fn test_interval_factor_stretches_steady_polling() {
+        let monitor = DockerMonitor::new(CancellationToken::new());
+        monitor.set_intervals(MonitorIntervals { quick_ms: 800, fast_ms: 1500, normal_ms: 30_000 }).unwrap();
+        monitor.set_interval_factor(4);
+
+        let effective = monitor.effective_intervals();
+        assert_eq!(effective, MonitorIntervals { quick_ms: 800, fast_ms: 6000, normal_ms: 60_000 });
+        assert_eq!(monitor.intervals().normal_ms, 30_000);
+
+        monitor.set_interval_factor(0);
+        assert_eq!(monitor.effective_intervals(), monitor.intervals());
     }
 
     #[tokio::test]
     async fn test_docker_status_serialization() {
         let status = DockerStatus::Running { 
-            version: "24.0.5".to_string() 
+            version: "24.0.5".to_string(),
+            engine: EngineDetails {
+                api_version: Some("1.43".to_string()),
+                ..Default::default()
+            },
         };
         let serialized = serde_json::to_string(&status).unwrap();
         assert!(serialized.contains("Running"));
         assert!(serialized.contains("24.0.5"));
+        assert!(serialized.contains(r#""api_version":"1.43""#));
     }
 
     #[tokio::test]
     async fn test_error_status_serialization() {
         let status = DockerStatus::Error { 
-            message: "Connection failed".to_string() 
+            message: "Connection failed".to_string(),
+            retry_after: None,
         };
         let serialized = serde_json::to_string(&status).unwrap();
         assert!(serialized.contains("Error"));
         assert!(serialized.contains("Connection failed"));
+        assert!(serialized.contains("\"retry_after\":null"));
+    }
+
+    #[test]
+    fn test_report_key_ignores_error_wording() {
+        let error = |message: &str| DockerStatus::Error { message: message.to_string(), retry_after: None };
+        assert_eq!(
+            ReportKey::from(&error("Docker daemon unresponsive (timeout)")),
+            ReportKey::from(&error("Docker daemon unresponsive (connection timeout)"))
+        );
+        assert_ne!(ReportKey::from(&error("timeout")), ReportKey::from(&CircuitBreaker::open_status(Utc::now())));
+        assert_ne!(
+            ReportKey::from(&DockerStatus::Stopped { reason: DockerUnavailableReason::Unknown }),
+            ReportKey::from(&DockerStatus::Starting)
+        );
+    }
+
+    #[test]
+    fn test_circuit_breaker_opens_after_repeated_timeouts() {
+        let mut circuit = CircuitBreaker::new();
+        let now = Utc::now();
+        assert_eq!(circuit.record(true, now), None);
+        assert_eq!(circuit.record(true, now), None);
+        
+        let retry_after = circuit.record(true, now).unwrap();
+        assert_eq!(retry_after, now + CIRCUIT_COOLDOWN);
+        assert_eq!(circuit.open_until(now + Duration::from_secs(1)), Some(retry_after));
+        assert_eq!(circuit.open_until(retry_after), None);
+        
+        // The trial check after the cooldown reopens on another timeout
+        let later = retry_after + Duration::from_secs(1);
+        assert_eq!(circuit.record(true, later), Some(later + CIRCUIT_COOLDOWN));
+        
+        // Any other outcome closes it
+        assert_eq!(circuit.record(false, later), None);
+        assert_eq!(circuit.open_until(later), None);
+        assert_eq!(circuit.record(true, later), None);
+    }
+    
+    #[test]
+    fn test_circuit_breaker_ignores_interleaved_failures() {
+        let mut circuit = CircuitBreaker::new();
+        let now = Utc::now();
+        for _ in 0..5 {
+            assert_eq!(circuit.record(true, now), None);
+            assert_eq!(circuit.record(false, now), None);
+        }
+        assert_eq!(circuit.open_until(now), None);
+    }
+
+    #[tokio::test]
+    async fn test_check_client_caches_version() {
+        let client = MockDockerClient::new().with_version("27.3.1");
+        let mut daemon_version = None;
+        for _ in 0..3 {
+            let status = DockerMonitor::check_client(&client, &mut daemon_version).await.unwrap();
+            assert!(matches!(
+                &status,
+                DockerStatus::Running { version, engine }
+                    if version == "27.3.1" && engine.api_version.as_deref() == Some("1.47") && engine.os.as_deref() == Some("linux")
+            ));
+        }
+        assert_eq!(client.version_calls(), 1);
+        
+        // A daemon coming back may have been upgraded
+        client.set_daemon(MockDaemon::Stopped);
+        let status = DockerMonitor::check_client(&client, &mut daemon_version).await.unwrap();
+        if DockerMonitor::docker_host().is_some_and(|host| network::is_remote_host(&host)) {
+            assert!(matches!(status, DockerStatus::Unreachable { .. }));
+        } else {
+            assert!(matches!(status, DockerStatus::Stopped { .. }));
+        }
+        client.set_daemon(MockDaemon::Running);
+        DockerMonitor::check_client(&client, &mut daemon_version).await.unwrap();
+        assert_eq!(client.version_calls(), 2);
+    }
+    
+    #[test]
+    fn test_unavailable_only_remote_hosts_unreachable() {
+        let reason = || DockerUnavailableReason::Unknown;
+        assert!(matches!(DockerMonitor::unavailable(None, reason()), DockerStatus::Stopped { .. }));
+        let local_hosts = [
+            "unix:///var/run/docker.sock",
+            "unix:///home/user/.docker/run/docker.sock",
+            "npipe:////./pipe/docker_engine",
+        ];
+        for local in local_hosts {
+            let status = DockerMonitor::unavailable(Some(local.to_string()), reason());
+            assert!(matches!(status, DockerStatus::Stopped { .. }), "{local}");
+        }
+        for remote in ["tcp://10.0.0.5:2375", "https://docker.example.com:2376", "ssh://user@build-host"] {
+            let status = DockerMonitor::unavailable(Some(remote.to_string()), reason());
+            assert!(matches!(&status, DockerStatus::Unreachable { endpoint, .. } if endpoint == remote), "{remote}");
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_check_client_starting_and_unresponsive() {
+        let client = MockDockerClient::new();
+        let mut daemon_version = None;
+        
+        client.set_daemon(MockDaemon::Starting);
+        let status = DockerMonitor::check_client(&client, &mut daemon_version).await.unwrap();
+        assert_eq!(status, DockerStatus::Starting);
+        
+        client.set_daemon(MockDaemon::Unresponsive);
+        let result = DockerMonitor::check_client(&client, &mut daemon_version).await;
+        assert!(matches!(result, Err(DockerMonitorError::Timeout(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_restart_detected_from_checks() {
+        let client = MockDockerClient::new();
+        let mut machine = StatusMachine::new();
+        let mut daemon_version = None;
+        let now = std::time::Instant::now();
+        
+        let checked = DockerMonitor::check_client(&client, &mut daemon_version).await.unwrap();
+        machine.observe(checked, now);
+        assert_eq!(machine.state(), DaemonState::Running);
+        
+        client.set_daemon(MockDaemon::Stopped);
+        let checked = DockerMonitor::check_client(&client, &mut daemon_version).await.unwrap();
+        let observation = machine.observe(checked, now + Duration::from_secs(1));
+        assert_eq!(observation.status, DockerStatus::Restarting);
+        
+        client.set_daemon(MockDaemon::Running);
+        let checked = DockerMonitor::check_client(&client, &mut daemon_version).await.unwrap();
+        let observation = machine.observe(checked, now + Duration::from_secs(2));
+        assert_eq!(observation.transition.unwrap().reason, TransitionReason::DaemonRestarted);
     }
 
     #[tokio::test]
@@ -626,6 +1573,6 @@ mod tests {
         // The actual implementation will handle this gracefully
         let monitor = DockerMonitor::new(CancellationToken::new());
         let status = monitor.get_current_status().await;
-        assert!(matches!(status, DockerStatus::Stopped));
+        assert!(matches!(status, DockerStatus::Stopped { .. }));
     }
 } 
\ No newline at end of file
@@ -0,0 +1,451 @@
+//! Environment diagnosis for RedSys Desktop Agent
+//!
+//! [`run_doctor`] checks everything a provider machine needs to run jobs and
+//! returns a checklist with a fix hint for every problem found:
+//! - **Virtualization**: VT-x / AMD-V enabled, needed by Docker Desktop
+//! - **Docker installed, running and socket permissions**, from the same
+//!   diagnosis as `DockerStatus`
+//! - **Daemon exposure**: no API listener reachable over the network without
+//!   TLS, see [`security`](crate::security)
+//! - **GPU runtime**: NVIDIA Container Toolkit, on hosts with NVIDIA GPUs
+//! - **Disk space** on the Docker data root and the job workspace partition
+//! - **Clock drift** against `clock.ntp_server`
+//! - **Backend reachability**: TCP connection to the configured platform URL
+//!
+//! The checks run concurrently. The same report backs the `run_doctor`
+//! command and `redsys-agent doctor`.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+use tokio_tungstenite::tungstenite::http::Uri;
+use tokio_util::sync::CancellationToken;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+
+use crate::clock;
+use crate::config::AgentConfig;
+use crate::diagnosis::DockerUnavailableReason;
+use crate::disk_pressure::{DiskPressureLevel, DiskPressureMonitor};
+use crate::docker::DockerService;
+use crate::docker_monitor::{DockerMonitor, DockerStatus};
+use crate::gpu::{self, GpuRuntime};
+use crate::security;
+use crate::workspace::WorkspaceManager;
+
+/// Longest wait for the backend to accept a connection.
+const BACKEND_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Result of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// Nothing to fix
+    Pass,
+
+    /// Works, but may cause problems
+    Warn,
+
+    /// Must be fixed before jobs can run
+    Fail,
+
+    /// Not applicable, or depends on a failed check
+    Skip,
+}
+
+/// What a check looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum CheckId {
+    /// CPU virtualization extensions
+    Virtualization,
+
+    /// Docker Engine or Docker Desktop present
+    DockerInstalled,
+
+    /// Daemon answers API calls
+    DockerRunning,
+
+    /// The current user may open the Docker socket
+    SocketPermission,
+
+    /// The daemon API is not reachable over the network without TLS
+    DaemonExposure,
+
+    /// GPU containers can be started
+    GpuRuntime,
+
+    /// Free space on the partitions the agent writes to
+    DiskSpace,
+
+    /// System clock against NTP
+    ClockDrift,
+
+    /// Connection to the RedSys backend
+    BackendReachable,
+}
+
+/// One entry of the checklist.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct DoctorCheck {
+    /// What was checked
+    pub id: CheckId,
+
+    /// Result
+    pub status: CheckStatus,
+
+    /// What was found
+    pub summary: String,
+
+    /// How to fix a warning or failure
+    pub fix_hint: Option<String>,
+}
+
+impl DoctorCheck {
+    fn new(id: CheckId, status: CheckStatus, summary: impl Into<String>) -> Self {
+        Self {
+            id,
+            status,
+            summary: summary.into(),
+            fix_hint: None,
+        }
+    }
+
+    fn with_hint(self, fix_hint: impl Into<String>) -> Self {
+        Self {
+            fix_hint: Some(fix_hint.into()),
+            ..self
+        }
+    }
+}
+
+/// Checklist returned by [`run_doctor`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct DoctorReport {
+    /// Checks in a fixed order
+    pub checks: Vec<DoctorCheck>,
+
+    /// No check failed
+    pub passed: bool,
+
+    /// When the checks ran
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Runs all checks against `config`.
+pub async fn run_doctor(config: &AgentConfig, docker_service: &Arc<DockerService>) -> DoctorReport {
+    let docker = async {
+        DockerMonitor::check_once()
+            .await
+            .unwrap_or_else(|e| DockerStatus::Error {
+                message: e.to_string(),
+                retry_after: None,
+            })
+    };
+    let (virtualization, docker, exposure, gpu_runtime, disk_space, clock_drift, backend) = tokio::join!(
+        check_virtualization(),
+        docker,
+        check_daemon_exposure(docker_service),
+        check_gpu_runtime(docker_service),
+        check_disk_space(config, docker_service),
+        check_clock_drift(&config.clock.ntp_server),
+        check_backend(config.backend.url.as_deref()),
+    );
+
+    let mut checks = vec![virtualization];
+    checks.extend(docker_checks(&docker));
+    checks.extend([exposure, gpu_runtime, disk_space, clock_drift, backend]);
+    DoctorReport {
+        passed: checks.iter().all(|check| check.status != CheckStatus::Fail),
+        checks,
+        checked_at: Utc::now(),
+    }
+}
+
+async fn check_virtualization() -> DoctorCheck {
+    let id = CheckId::Virtualization;
+    let hint = "Enable Intel VT-x or AMD-V in the firmware (BIOS/UEFI) settings";
+    match tokio::task::spawn_blocking(virtualization_enabled).await.ok().flatten() {
+        Some(true) => DoctorCheck::new(id, CheckStatus::Pass, "Hardware virtualization is enabled"),
+        // Docker Engine on Linux runs containers without a VM
+        Some(false) if cfg!(target_os = "linux") => DoctorCheck::new(
+            id,
+            CheckStatus::Warn,
+            "Hardware virtualization is off; only Docker Desktop and VM-based runtimes need it",
+        )
+        .with_hint(hint),
+        Some(false) => DoctorCheck::new(id, CheckStatus::Fail, "Hardware virtualization is off").with_hint(hint),
+        None => DoctorCheck::new(id, CheckStatus::Skip, "Could not determine whether virtualization is enabled"),
+    }
+}
+
+/// Whether the CPU virtualization extensions are enabled. Blocking.
+#[cfg(target_os = "linux")]
+fn virtualization_enabled() -> Option<bool> {
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+    Some(
+        cpuinfo
+            .lines()
+            .filter(|line| line.starts_with("flags"))
+            .any(|line| line.split_whitespace().any(|flag| flag == "vmx" || flag == "svm")),
+    )
+}
+
+/// Whether the CPU virtualization extensions are enabled. Blocking.
+#[cfg(target_os = "macos")]
+fn virtualization_enabled() -> Option<bool> {
+    let output = std::process::Command::new("sysctl").args(["-n", "kern.hv_support"]).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+/// Whether the CPU virtualization extensions are enabled. Blocking.
+///
+/// The firmware flag reads false while Hyper-V runs, so a present hypervisor
+/// counts as enabled.
+#[cfg(windows)]
+fn virtualization_enabled() -> Option<bool> {
+    let script = "(Get-CimInstance Win32_Processor).VirtualizationFirmwareEnabled; \
+                  (Get-CimInstance Win32_ComputerSystem).HypervisorPresent";
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).lines().any(|line| line.trim() == "True"))
+}
+
+/// Whether the CPU virtualization extensions are enabled. Blocking.
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn virtualization_enabled() -> Option<bool> {
+    None
+}
+
+/// Installed, running and socket permission checks from a daemon status.
+fn docker_checks(status: &DockerStatus) -> [DoctorCheck; 3] {
+    let installed = |status| DoctorCheck::new(CheckId::DockerInstalled, status, "Docker is installed");
+    let running = |status, summary: String| DoctorCheck::new(CheckId::DockerRunning, status, summary);
+    let socket = |status, summary: &str| DoctorCheck::new(CheckId::SocketPermission, status, summary);
+    let socket_skipped = || socket(CheckStatus::Skip, "Not checked while the daemon is unavailable");
+
+    match status {
+        DockerStatus::Running { version, .. } => [
+            installed(CheckStatus::Pass),
+            running(CheckStatus::Pass, format!("Docker {version} is running")),
+            socket(CheckStatus::Pass, "The Docker socket is accessible"),
+        ],
+        DockerStatus::Stopped { reason: DockerUnavailableReason::NotInstalled } => [
+            DoctorCheck::new(CheckId::DockerInstalled, CheckStatus::Fail, "Docker is not installed")
+                .with_hint("Install Docker Engine or Docker Desktop: https://docs.docker.com/get-docker/"),
+            running(CheckStatus::Skip, "Docker is not installed".to_string()),
+            socket(CheckStatus::Skip, "Docker is not installed"),
+        ],
+        DockerStatus::Stopped { reason: DockerUnavailableReason::SocketPermissionDenied(permission) } => [
+            installed(CheckStatus::Pass),
+            running(CheckStatus::Skip, "Cannot ask the daemon without socket access".to_string()),
+            socket(CheckStatus::Fail, "The current user may not open the Docker socket").with_hint(permission.to_string()),
+        ],
+        DockerStatus::Stopped { reason } | DockerStatus::Unreachable { reason, .. } => [
+            installed(CheckStatus::Pass),
+            running(CheckStatus::Fail, status.to_string()).with_hint(start_hint(reason)),
+            socket_skipped(),
+        ],
+        DockerStatus::Starting | DockerStatus::Restarting | DockerStatus::Degraded { .. } => [
+            installed(CheckStatus::Pass),
+            running(CheckStatus::Warn, status.to_string()).with_hint("Wait for Docker to finish starting, then run the checks again"),
+            socket_skipped(),
+        ],
+        DockerStatus::Error { .. } | DockerStatus::Paused => [
+            installed(CheckStatus::Pass),
+            running(CheckStatus::Fail, status.to_string()).with_hint("Restart Docker and check its logs"),
+            socket_skipped(),
+        ],
+    }
+}
+
+/// Fix hint for a daemon that is installed but unavailable.
+fn start_hint(reason: &DockerUnavailableReason) -> &'static str {
+    match reason {
+        DockerUnavailableReason::WslDisabled => "Enable WSL with `wsl --install` from an administrator prompt, then restart",
+        DockerUnavailableReason::TlsMisconfigured { .. } => {
+            "Use an https:// Docker host for TLS endpoints, with DOCKER_CERT_PATH pointing at the client certificates"
+        }
+        _ if cfg!(target_os = "linux") => "Start the daemon with `sudo systemctl start docker`",
+        _ => "Start Docker Desktop",
+    }
+}
+
+async fn check_daemon_exposure(docker_service: &DockerService) -> DoctorCheck {
+    let id = CheckId::DaemonExposure;
+    let info = match docker_service.docker().await {
+        Ok(client) => client.info().await,
+        Err(e) => return DoctorCheck::new(id, CheckStatus::Skip, format!("Docker is unavailable: {e}")),
+    };
+    let warnings = match info {
+        Ok(info) => info.warnings.unwrap_or_default(),
+        Err(e) => return DoctorCheck::new(id, CheckStatus::Skip, format!("Docker info query failed: {e}")),
+    };
+    match tokio::task::spawn_blocking(move || security::daemon_exposure(&warnings)).await {
+        Ok(None) => DoctorCheck::new(id, CheckStatus::Pass, "The Docker API is not exposed without TLS"),
+        Ok(Some(warning)) => DoctorCheck::new(
+            id,
+            CheckStatus::Fail,
+            format!("The Docker API is reachable without TLS on {}", warning.endpoints.join(", ")),
+        )
+        .with_hint("Remove the tcp:// hosts from the daemon configuration, or protect them with TLS: https://docs.docker.com/engine/security/protect-access/"),
+        Err(e) => DoctorCheck::new(id, CheckStatus::Skip, format!("Exposure check failed: {e}")),
+    }
+}
+
+async fn check_gpu_runtime(docker_service: &DockerService) -> DoctorCheck {
+    let id = CheckId::GpuRuntime;
+    let gpu_info = match tokio::task::spawn_blocking(gpu::detect).await {
+        Ok(gpu_info) => gpu_info,
+        Err(e) => return DoctorCheck::new(id, CheckStatus::Skip, format!("GPU detection failed: {e}")),
+    };
+    let devices = gpu_info.nvidia_device_count();
+    if devices == 0 {
+        return DoctorCheck::new(id, CheckStatus::Skip, "No NVIDIA GPUs detected");
+    }
+    let runtime = GpuRuntime::detect(&gpu_info, docker_service).await;
+    match runtime.reason {
+        None => DoctorCheck::new(id, CheckStatus::Pass, format!("{devices} NVIDIA GPUs available to containers")),
+        Some(reason) => DoctorCheck::new(id, CheckStatus::Fail, reason).with_hint(
+            "Install the NVIDIA Container Toolkit and run `sudo nvidia-ctk runtime configure --runtime=docker`",
+        ),
+    }
+}
+
+async fn check_disk_space(config: &AgentConfig, docker_service: &Arc<DockerService>) -> DoctorCheck {
+    let id = CheckId::DiskSpace;
+    let mut monitor = DiskPressureMonitor::new(&config.disk_pressure, CancellationToken::new())
+        .with_docker_service(docker_service.clone());
+    let workspace_root: Option<PathBuf> = config.workspace.root.clone().or_else(WorkspaceManager::default_root);
+    if let Some(root) = workspace_root {
+        monitor = monitor.with_path(root);
+    }
+    let pressures = monitor.check().await;
+    let Some(worst) = pressures.iter().max_by_key(|pressure| pressure.level) else {
+        return DoctorCheck::new(id, CheckStatus::Skip, "No watched partition found");
+    };
+    let summary = format!(
+        "{:.0}% free ({} GiB) on {}",
+        worst.free_percent,
+        worst.available_bytes / (1024 * 1024 * 1024),
+        worst.mount_point.display()
+    );
+    let hint = "Free up space, e.g. with `docker system prune`, or move the workspace root to a larger disk";
+    match worst.level {
+        DiskPressureLevel::Ok => DoctorCheck::new(id, CheckStatus::Pass, summary),
+        DiskPressureLevel::Warning => DoctorCheck::new(id, CheckStatus::Warn, summary).with_hint(hint),
+        DiskPressureLevel::Critical => DoctorCheck::new(id, CheckStatus::Fail, summary).with_hint(hint),
+    }
+}
+
+async fn check_clock_drift(server: &str) -> DoctorCheck {
+    match clock::measure_offset(server).await {
+        Ok(offset) => drift_check(offset),
+        Err(e) => DoctorCheck::new(CheckId::ClockDrift, CheckStatus::Skip, format!("Could not reach an NTP server: {e}")),
+    }
+}
+
+/// Classifies the offset of the system clock.
+fn drift_check(offset: TimeDelta) -> DoctorCheck {
+    let id = CheckId::ClockDrift;
+    let drift = offset.abs();
+    let direction = if offset < TimeDelta::zero() { "behind" } else { "ahead" };
+    let summary = format!("System clock is {:.1}s {direction}", drift.num_milliseconds() as f64 / 1000.0);
+    let hint = "Enable automatic time synchronization in the system settings";
+    if drift >= clock::DRIFT_CRITICAL {
+        DoctorCheck::new(id, CheckStatus::Fail, summary).with_hint(hint)
+    } else if drift >= clock::DRIFT_WARNING {
+        DoctorCheck::new(id, CheckStatus::Warn, summary).with_hint(hint)
+    } else {
+        DoctorCheck::new(id, CheckStatus::Pass, summary)
+    }
+}
+
+async fn check_backend(url: Option<&str>) -> DoctorCheck {
+    let id = CheckId::BackendReachable;
+    let Some(url) = url else {
+        return DoctorCheck::new(id, CheckStatus::Skip, "No backend URL configured");
+    };
+    let Some((host, port)) = backend_address(url) else {
+        return DoctorCheck::new(id, CheckStatus::Fail, format!("Invalid backend URL '{url}'"))
+            .with_hint("Set backend.url to a ws:// or wss:// URL");
+    };
+    let hint = format!("Check that the network, proxy and firewall allow outbound connections to {host}:{port}");
+    match timeout(BACKEND_CONNECT_TIMEOUT, TcpStream::connect((host.as_str(), port))).await {
+        Ok(Ok(_)) => DoctorCheck::new(id, CheckStatus::Pass, format!("Connected to {host}:{port}")),
+        Ok(Err(e)) => DoctorCheck::new(id, CheckStatus::Fail, format!("Cannot connect to {host}:{port}: {e}")).with_hint(hint),
+        Err(_) => DoctorCheck::new(id, CheckStatus::Fail, format!("Connecting to {host}:{port} timed out")).with_hint(hint),
+    }
+}
+
+/// Host and port of a `ws://` or `wss://` URL.
+fn backend_address(url: &str) -> Option<(String, u16)> {
+    let uri: Uri = url.parse().ok()?;
+    let default_port = match uri.scheme_str()? {
+        "ws" => 80,
+        "wss" => 443,
+        _ => return None,
+    };
+    Some((uri.host()?.to_string(), uri.port_u16().unwrap_or(default_port)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::EngineDetails;
+    use crate::diagnosis::SocketPermission;
+
+    fn statuses(checks: &[DoctorCheck]) -> Vec<CheckStatus> {
+        checks.iter().map(|check| check.status).collect()
+    }
+
+    #[test]
+    fn test_docker_checks() {
+        let running = docker_checks(&DockerStatus::Running { version: "27.3.1".to_string(), engine: EngineDetails::default() });
+        assert_eq!(statuses(&running), [CheckStatus::Pass; 3]);
+
+        let missing = docker_checks(&DockerStatus::Stopped { reason: DockerUnavailableReason::NotInstalled });
+        assert_eq!(statuses(&missing), [CheckStatus::Fail, CheckStatus::Skip, CheckStatus::Skip]);
+        assert!(missing[0].fix_hint.is_some());
+
+        let denied = docker_checks(&DockerStatus::Stopped {
+            reason: DockerUnavailableReason::SocketPermissionDenied(SocketPermission {
+                socket: "/var/run/docker.sock".to_string(),
+                group: Some("docker".to_string()),
+                add_to_group: true,
+                relogin_required: false,
+            }),
+        });
+        assert_eq!(denied[2].status, CheckStatus::Fail);
+        assert!(denied[2].fix_hint.as_deref().unwrap().contains("usermod -aG docker"));
+
+        let stopped = docker_checks(&DockerStatus::Stopped { reason: DockerUnavailableReason::ServiceStopped });
+        assert_eq!(statuses(&stopped), [CheckStatus::Pass, CheckStatus::Fail, CheckStatus::Skip]);
+    }
+
+    #[test]
+    fn test_drift_check() {
+        assert_eq!(drift_check(TimeDelta::milliseconds(300)).status, CheckStatus::Pass);
+        assert_eq!(drift_check(TimeDelta::seconds(-12)).status, CheckStatus::Warn);
+        let failed = drift_check(TimeDelta::minutes(3));
+        assert_eq!(failed.status, CheckStatus::Fail);
+        assert_eq!(failed.summary, "System clock is 180.0s ahead");
+    }
+
+    #[test]
+    fn test_backend_address() {
+        assert_eq!(backend_address("wss://agents.redsys.tn/ws"), Some(("agents.redsys.tn".to_string(), 443)));
+        assert_eq!(backend_address("ws://localhost:8080"), Some(("localhost".to_string(), 8080)));
+        assert_eq!(backend_address("https://agents.redsys.tn"), None);
+    }
+}
@@ -0,0 +1,166 @@
+//! Docker engine start-up for RedSys Desktop Agent
+//!
+//! Lets providers start a stopped local Docker engine from the agent instead
+//! of hunting for the right app or service:
+//! - **Windows**: launches `Docker Desktop.exe`
+//! - **macOS**: opens the Docker Desktop app
+//! - **Linux**: runs `systemctl start docker` through `pkexec`, which shows the
+//!   polkit password prompt, falling back to non-interactive `sudo`
+//!
+//! After launching, [`start`] waits until the
+//! [`DockerMonitor`](crate::docker_monitor::DockerMonitor) reports the daemon
+//! up. Starting the engine is off unless `docker.allow_engine_control` is set.
+//!
+//! ## References
+//! - [Start Docker Desktop](https://docs.docker.com/desktop/setup/install/windows-install/#start-docker-desktop)
+//! - [Configure Docker to start on boot with systemd](https://docs.docker.com/engine/install/linux-postinstall/#configure-docker-to-start-on-boot-with-systemd)
+//! - [pkexec](https://www.freedesktop.org/software/polkit/docs/latest/pkexec.1.html)
+
+use tokio::{process::Command, sync::watch, time::Duration};
+use tracing::{debug, info};
+
+use crate::docker_monitor::{DockerMonitor, DockerStatus};
+use crate::error::{AppError, AppResult};
+
+/// How long to wait for the daemon after launching it; Docker Desktop boots a VM.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Whether the daemon is up and able to run jobs.
+fn is_up(status: &DockerStatus) -> bool {
+    matches!(status, DockerStatus::Running { .. } | DockerStatus::Degraded { .. })
+}
+
+/// Starts the local Docker engine and waits until the monitor reports it up.
+///
+/// Returns immediately if the daemon is already up. Fails for remote
+/// `docker.host` endpoints, since only a local engine can be started.
+pub async fn start(monitor: &DockerMonitor) -> AppResult<DockerStatus> {
+    let status = monitor.subscribe_status();
+    if is_up(&status.borrow()) {
+        debug!("Docker engine already running");
+        return Ok(status.borrow().clone());
+    }
+    if let Some(host) = DockerMonitor::docker_host().filter(|host| host.starts_with("tcp://") || host.starts_with("http://")) {
+        return Err(AppError::InvalidState(format!("Cannot start the remote Docker engine at {host}")));
+    }
+
+    platform::launch().await?;
+    info!("Docker engine launched, waiting for the daemon");
+
+    // Probe right away rather than waiting for the next poll
+    let _ = monitor.force_check().await;
+    wait_until_up(status, STARTUP_TIMEOUT).await
+}
+
+/// Waits for `status` to report the daemon up.
+async fn wait_until_up(mut status: watch::Receiver<DockerStatus>, timeout: Duration) -> AppResult<DockerStatus> {
+    match tokio::time::timeout(timeout, status.wait_for(is_up)).await {
+        Ok(Ok(status)) => Ok(status.clone()),
+        Ok(Err(_)) => Err(AppError::InvalidState("Docker monitor stopped".to_string())),
+        Err(_) => Err(AppError::Timeout {
+            operation: "waiting for the Docker engine to start".to_string(),
+        }),
+    }
+}
+
+/// Runs `program` to completion, mapping a failed exit to an error.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+async fn run(program: &str, args: &[&str]) -> AppResult<()> {
+    let output = Command::new(program).args(args).output().await?;
+    match output.status.code() {
+        Some(0) => Ok(()),
+        // pkexec: the authentication dialog was dismissed or authorization failed
+        Some(126 | 127) if program == "pkexec" => {
+            Err(AppError::Permission("Authorization to start Docker was not granted".to_string()))
+        }
+        _ => Err(AppError::Application(format!(
+            "{program} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+
+    pub(super) async fn launch() -> AppResult<()> {
+        match run("pkexec", &["systemctl", "start", "docker"]).await {
+            Err(AppError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("pkexec not available, trying sudo");
+                run("sudo", &["-n", "systemctl", "start", "docker"]).await
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    pub(super) async fn launch() -> AppResult<()> {
+        let status = Command::new("open").args(["-a", "Docker"]).status().await?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(AppError::NotFound { resource: "Docker Desktop application".to_string() })
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    pub(super) async fn launch() -> AppResult<()> {
+        let program_files = std::env::var_os("ProgramFiles").unwrap_or_else(|| r"C:\Program Files".into());
+        let executable = PathBuf::from(program_files).join(r"Docker\Docker\Docker Desktop.exe");
+        if !executable.exists() {
+            return Err(AppError::NotFound { resource: executable.display().to_string() });
+        }
+        // Docker Desktop keeps running in the tray, so it is not awaited
+        Command::new(executable).spawn()?;
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::*;
+
+    pub(super) async fn launch() -> AppResult<()> {
+        Err(AppError::Configuration(format!("Starting Docker is not supported on {}", std::env::consts::OS)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::EngineDetails;
+    use crate::diagnosis::DockerUnavailableReason;
+
+    fn stopped() -> DockerStatus {
+        DockerStatus::Stopped { reason: DockerUnavailableReason::ServiceStopped }
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_running_status() {
+        let (sender, receiver) = watch::channel(stopped());
+        let waiter = tokio::spawn(wait_until_up(receiver, Duration::from_secs(5)));
+
+        sender.send_replace(DockerStatus::Starting);
+        sender.send_replace(DockerStatus::Running { version: "27.0.1".to_string(), engine: EngineDetails::default() });
+        assert_eq!(waiter.await.unwrap().unwrap(), DockerStatus::Running { version: "27.0.1".to_string(), engine: EngineDetails::default() });
+    }
+
+    #[tokio::test]
+    async fn test_times_out_while_stopped() {
+        let (_sender, receiver) = watch::channel(stopped());
+        let result = wait_until_up(receiver, Duration::from_millis(10)).await;
+        assert!(matches!(result, Err(AppError::Timeout { .. })));
+    }
+}
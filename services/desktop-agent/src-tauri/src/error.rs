@@ -2,9 +2,19 @@
 //!
 //! This module provides comprehensive error handling for the application,
 //! including general application errors.
-
+//!
+//! ## Frontend Errors
+//! Tauri commands return [`AppError`], which serializes as an
+//! [`ErrorPayload`]: a stable [`ErrorCode`] to branch on and localize, the
+//! English message, optional structured details and whether retrying may
+//! succeed. Codes are part of the frontend contract and are never renamed.
+
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
 use thiserror::Error;
 
+use crate::docker_monitor::DockerMonitorError;
+
 /// Application result type
 pub type AppResult<T> = Result<T, AppError>;
 
@@ -39,6 +49,14 @@ pub enum AppError {
     #[error("Invalid state: {0}")]
     InvalidState(String),
 
+    /// An update was based on an outdated revision
+    #[error("Revision conflict: expected {expected}, found {found}")]
+    Conflict { expected: u64, found: u64 },
+
+    /// Image rejected by the image policy
+    #[error("Image '{image}' is not allowed: {reason}")]
+    PolicyViolation { image: String, reason: String },
+
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -46,6 +64,112 @@ pub enum AppError {
     /// IO error
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    /// Docker operation error
+    #[error(transparent)]
+    Docker(#[from] DockerMonitorError),
+}
+
+/// Stable machine-readable error codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Application,
+    Configuration,
+    Network,
+    Timeout,
+    PermissionDenied,
+    NotFound,
+    InvalidState,
+    Conflict,
+    PolicyViolation,
+    Serialization,
+    Io,
+    DockerUnavailable,
+    DockerApi,
+    DockerTimeout,
+    DockerSocketPermission,
+    DockerUnsupportedVersion,
+    ResourceLimit,
+    EventEmission,
+    Internal,
+}
+
+/// Serialized form of [`AppError`] and [`DockerMonitorError`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ErrorPayload {
+    /// Error kind
+    pub code: ErrorCode,
+
+    /// Human-readable message in English
+    pub message: String,
+
+    /// Structured context, e.g. the missing resource
+    #[cfg_attr(feature = "ts-bindings", ts(type = "Record<string, unknown> | null"))]
+    pub details: Option<Value>,
+
+    /// Whether the same call may succeed when retried later
+    pub retryable: bool,
+}
+
+impl AppError {
+    /// Machine-readable code of the error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Application(_) => ErrorCode::Application,
+            AppError::Configuration(_) => ErrorCode::Configuration,
+            AppError::Network(_) => ErrorCode::Network,
+            AppError::Timeout { .. } => ErrorCode::Timeout,
+            AppError::Permission(_) => ErrorCode::PermissionDenied,
+            AppError::NotFound { .. } => ErrorCode::NotFound,
+            AppError::InvalidState(_) => ErrorCode::InvalidState,
+            AppError::Conflict { .. } => ErrorCode::Conflict,
+            AppError::PolicyViolation { .. } => ErrorCode::PolicyViolation,
+            AppError::Serialization(_) => ErrorCode::Serialization,
+            AppError::Io(_) => ErrorCode::Io,
+            AppError::Docker(e) => e.code(),
+        }
+    }
+
+    /// Whether retrying may succeed without the user changing anything.
+    pub fn retryable(&self) -> bool {
+        match self {
+            AppError::Network(_) | AppError::Timeout { .. } | AppError::Conflict { .. } => true,
+            AppError::Docker(e) => e.retryable(),
+            _ => false,
+        }
+    }
+
+    /// Structured context for the frontend, if any.
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            AppError::Timeout { operation } => Some(json!({ "operation": operation })),
+            AppError::NotFound { resource } => Some(json!({ "resource": resource })),
+            AppError::Conflict { expected, found } => Some(json!({ "expected": expected, "found": found })),
+            AppError::PolicyViolation { image, reason } => Some(json!({ "image": image, "reason": reason })),
+            AppError::Docker(e) => e.details(),
+            _ => None,
+        }
+    }
+}
+
+impl From<&AppError> for ErrorPayload {
+    fn from(error: &AppError) -> Self {
+        Self {
+            code: error.code(),
+            message: error.to_string(),
+            details: error.details(),
+            retryable: error.retryable(),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorPayload::from(self).serialize(serializer)
+    }
 }
 
 impl From<String> for AppError {
@@ -70,6 +194,22 @@ mod tests {
         assert_eq!(error.to_string(), "Application error: test error");
     }
 
+    #[test]
+    fn test_error_payload() {
+        let error = AppError::NotFound {
+            resource: "job render-1".to_string(),
+        };
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["code"], "not_found");
+        assert_eq!(value["message"], "Resource not found: job render-1");
+        assert_eq!(value["details"]["resource"], "job render-1");
+        assert_eq!(value["retryable"], false);
+
+        let error = AppError::from(DockerMonitorError::Timeout("ping".to_string()));
+        assert_eq!(error.code(), ErrorCode::DockerTimeout);
+        assert!(error.retryable());
+    }
+
     #[test]
     fn test_timeout_error() {
         let error = AppError::Timeout {
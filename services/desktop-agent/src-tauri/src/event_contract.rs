@@ -0,0 +1,84 @@
+//! Frontend event contract for RedSys Desktop Agent
+//!
+//! Every Tauri event sent to the frontend goes through
+//! [`telemetry::emit`](crate::telemetry::emit), which applies this contract
+//! and [routes](crate::routing) the event to the windows subscribed to it:
+//! - Event names are kebab-case, e.g. `docker-status-changed`
+//! - Object payloads carry `contract_version` (currently [`VERSION`]) and,
+//!   when known, `correlation_id`
+//! - Events renamed to fit the contract are also emitted under their old
+//!   names from [`LEGACY_NAMES`], so existing listeners keep working until
+//!   they move to the new name
+//!
+//! [`VERSION`] is bumped when a payload changes incompatibly; adding fields
+//! does not bump it.
+//!
+//! ## Events
+//! | Name | Payload |
+//! | --- | --- |
+//! | `docker-status-changed` | [`DockerStatusChangedPayload`](crate::types::DockerStatusChangedPayload) |
+//! | `docker-status-transition` | [`StatusTransition`](crate::status::StatusTransition) |
+//! | `docker-daemon-restarted` | [`DaemonRestarted`](crate::status::DaemonRestarted) |
+//! | `docker-event` | [`DockerEvent`](crate::docker::DockerEvent) |
+//! | `container-health-changed` | [`ContainerHealthChanged`](crate::docker::ContainerHealthChanged) |
+//! | `docker-disk-usage` | [`DockerDiskUsage`](crate::docker::DockerDiskUsage) |
+//! | `job-state-changed` | [`JobStateChanged`](crate::jobs::JobStateChanged) |
+//! | `system-metrics` | [`SystemMetrics`](crate::system_monitor::SystemMetrics) |
+//! | `app-state-changed` | [`AppStateChanged`](crate::types::AppStateChanged) |
+//! | `requirements-unmet` | [`RequirementsReport`](crate::requirements::RequirementsReport) |
+//! | `agent-connectivity-changed` | [`ConnectivityChanged`](crate::network::ConnectivityChanged) |
+//! | `network-probe-completed` | [`ProbeReport`](crate::network_probe::ProbeReport) |
+//! | `gc-report` | [`GcReport`](crate::orphans::GcReport) |
+//! | `port-conflict` | [`PortConflict`](crate::ports::PortConflict) |
+//! | `image-update-available` | [`ImageUpdate`](crate::image_updates::ImageUpdate) |
+//! | `security-findings` | [`SecurityReport`](crate::security::SecurityReport) |
+//! | `security-warning` | [`SecurityWarning`](crate::security::SecurityWarning) |
+//! | `container-failed` | [`ContainerFailed`](crate::failures::ContainerFailed) |
+//! | `service-flapping` | [`ServiceFlapping`](crate::restarts::ServiceFlapping) |
+//! | `deep-link-opened` | [`DeepLink`](crate::deep_links::DeepLink) |
+//! | `<name>-batch` | [`EventBatch`](crate::events::EventBatch), for the events in `frontend.batched_events` |
+//!
+//! ## Channels
+//! High-volume streams for a single consumer are not events: the command that
+//! starts them takes a Tauri channel and sends the payloads to it alone, so
+//! other windows never receive them and a dropped channel ends the stream.
+//! Channel payloads are sent as they are, without `contract_version`.
+//!
+//! | Command | Payload |
+//! | --- | --- |
+//! | `copy_to_container`, `copy_from_container` | [`CopyProgress`](crate::transfer::CopyProgress) |
+//! | `build_image` | [`BuildProgress`](crate::images::BuildProgress) |
+//! | `export_image`, `import_image` | [`ImageTransferProgress`](crate::images::ImageTransferProgress) |
+//! | `subscribe_gpu_metrics` | [`GpuMetrics`](crate::gpu::GpuMetrics) |
+
+/// Version of the event payload schema.
+pub const VERSION: u32 = 1;
+
+/// Current event names with the old names they are also emitted under.
+pub const LEGACY_NAMES: &[(&str, &str)] = &[("docker-status-changed", "docker_status_changed")];
+
+/// Old names that `event` is also emitted under.
+pub fn legacy_names(event: &str) -> impl Iterator<Item = &'static str> + '_ {
+    LEGACY_NAMES
+        .iter()
+        .filter(move |(name, _)| *name == event)
+        .map(|(_, legacy)| *legacy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_names() {
+        assert_eq!(legacy_names("docker-status-changed").collect::<Vec<_>>(), ["docker_status_changed"]);
+        assert_eq!(legacy_names("docker-event").count(), 0);
+    }
+
+    #[test]
+    fn test_names_are_kebab_case() {
+        for (name, _) in LEGACY_NAMES {
+            assert!(name.chars().all(|c| c.is_ascii_lowercase() || c == '-'), "{name}");
+        }
+    }
+}
@@ -0,0 +1,416 @@
+//! Internal event bus for RedSys Desktop Agent
+//!
+//! Subsystems publish typed [`Event`]s to a shared [`Bus`] instead of emitting
+//! Tauri events themselves. The frontend is one subscriber among others:
+//! [`forward_to_frontend`] turns every event back into the Tauri event it
+//! used to be, so the webview sees the same names and payloads as before.
+//!
+//! ## Topics
+//! - [`Event::DaemonStatus`] → `docker-status-changed`
+//! - [`Event::Container`] → `docker-event` and `container-health-changed`
+//! - [`Event::Job`] → `job-state-changed`
+//! - [`Event::SystemMetric`] → `system-metrics` and `docker-disk-usage`
+//! - [`Event::AppState`] → `app-state-changed`
+//! - [`Event::RequirementsUnmet`] → `requirements-unmet`
+//! - [`Event::Connectivity`] → `agent-connectivity-changed`
+//! - [`Event::GarbageCollected`] → `gc-report`
+//!
+//! Events carry the correlation ID of the span they were published in, so the
+//! forwarded Tauri event still matches the logs of the code that raised it.
+//!
+//! ## Backpressure
+//! The bus is bounded: a subscriber that falls more than 1024 events behind
+//! loses the oldest ones rather than holding up publishers. Under heavy
+//! container churn, per-event emits can still flood the webview, so events
+//! listed in `frontend.batched_events` are queued instead and emitted every
+//! `frontend.batch_interval_ms` as a single `<name>-batch` event carrying an
+//! [`EventBatch`]. Each queue holds `frontend.queue_capacity` events and drops
+//! the oldest when full, counting them in the batch. Batched payloads keep
+//! the correlation ID of their own event.
+//!
+//! ## References
+//! - [Tokio broadcast channel](https://docs.rs/tokio/latest/tokio/sync/broadcast/index.html)
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::{broadcast::{self, error::RecvError}, watch};
+use tokio::time::{interval, Duration, Interval, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{AgentConfig, FrontendConfig};
+use crate::docker::{ContainerHealthChanged, DockerDiskUsage, DockerEvent};
+use crate::jobs::JobStateChanged;
+use crate::network::ConnectivityChanged;
+use crate::orphans::GcReport;
+use crate::requirements::RequirementsReport;
+use crate::system_monitor::SystemMetrics;
+use crate::telemetry;
+use crate::types::{AppStateChanged, DockerStatusChangedPayload};
+
+/// Events buffered per subscriber before slow subscribers start missing them.
+const BUS_CAPACITY: usize = 1024;
+
+/// Container activity reported by the Docker events stream.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerEvent {
+    /// An event that passed the active filters
+    Docker(DockerEvent),
+
+    /// A HEALTHCHECK transition
+    HealthChanged(ContainerHealthChanged),
+}
+
+/// Job lifecycle updates.
+#[derive(Debug, Clone)]
+pub enum JobEvent {
+    /// A job moved to a new state
+    StateChanged(JobStateChanged),
+}
+
+/// Periodic resource samples.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SystemMetric {
+    /// Host CPU, memory, disk and network usage
+    Host(SystemMetrics),
+
+    /// Docker disk usage, as in `docker system df`
+    DockerDiskUsage(DockerDiskUsage),
+}
+
+/// An event on the bus, grouped by topic.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The Docker daemon status changed
+    DaemonStatus(DockerStatusChangedPayload),
+
+    /// Container activity
+    Container(ContainerEvent),
+
+    /// Job lifecycle updates
+    Job(JobEvent),
+
+    /// Resource samples
+    SystemMetric(SystemMetric),
+
+    /// The application state was updated
+    AppState(AppStateChanged),
+
+    /// The host does not meet the minimum requirements
+    RequirementsUnmet(RequirementsReport),
+
+    /// The network interfaces changed
+    Connectivity(ConnectivityChanged),
+
+    /// Orphaned containers and volumes were collected
+    GarbageCollected(GcReport),
+}
+
+/// Declares [`Event::tauri_name`] and [`FRONTEND_EVENTS`] from one mapping, so
+/// the list of names cannot miss an event.
+macro_rules! tauri_names {
+    ($($pattern:pat => $name:literal,)+) => {
+        /// Names of the Tauri events forwarded from the bus.
+        pub const FRONTEND_EVENTS: &[&str] = &[$($name),+];
+
+        impl Event {
+            /// Name of the Tauri event the frontend listens for.
+            pub fn tauri_name(&self) -> &'static str {
+                match self {
+                    $($pattern => $name,)+
+                }
+            }
+        }
+    };
+}
+
+tauri_names! {
+    Event::DaemonStatus(_) => "docker-status-changed",
+    Event::Container(ContainerEvent::Docker(_)) => "docker-event",
+    Event::Container(ContainerEvent::HealthChanged(_)) => "container-health-changed",
+    Event::Job(JobEvent::StateChanged(_)) => "job-state-changed",
+    Event::SystemMetric(SystemMetric::Host(_)) => "system-metrics",
+    Event::SystemMetric(SystemMetric::DockerDiskUsage(_)) => "docker-disk-usage",
+    Event::AppState(_) => "app-state-changed",
+    Event::RequirementsUnmet(_) => "requirements-unmet",
+    Event::Connectivity(_) => "agent-connectivity-changed",
+    Event::GarbageCollected(_) => "gc-report",
+}
+
+impl Event {
+    /// Serializes the payload of the Tauri event.
+    pub fn payload(&self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            Self::DaemonStatus(change) => serde_json::to_value(change),
+            Self::Container(ContainerEvent::Docker(event)) => serde_json::to_value(event),
+            Self::Container(ContainerEvent::HealthChanged(change)) => serde_json::to_value(change),
+            Self::Job(JobEvent::StateChanged(change)) => serde_json::to_value(change),
+            Self::SystemMetric(SystemMetric::Host(metrics)) => serde_json::to_value(metrics),
+            Self::SystemMetric(SystemMetric::DockerDiskUsage(usage)) => serde_json::to_value(usage),
+            Self::AppState(change) => serde_json::to_value(change),
+            Self::RequirementsUnmet(report) => serde_json::to_value(report),
+            Self::Connectivity(change) => serde_json::to_value(change),
+            Self::GarbageCollected(report) => serde_json::to_value(report),
+        }
+    }
+
+    /// Emits the event to the frontend with its original Tauri payload.
+    fn emit(&self, app_handle: &tauri::AppHandle, correlation_id: Option<String>) -> tauri::Result<()> {
+        let name = self.tauri_name();
+        match self {
+            Self::DaemonStatus(change) => telemetry::emit_correlated(app_handle, name, change, correlation_id),
+            Self::Container(ContainerEvent::Docker(event)) => telemetry::emit_correlated(app_handle, name, event, correlation_id),
+            Self::Container(ContainerEvent::HealthChanged(change)) => {
+                telemetry::emit_correlated(app_handle, name, change, correlation_id)
+            }
+            Self::Job(JobEvent::StateChanged(change)) => telemetry::emit_correlated(app_handle, name, change, correlation_id),
+            Self::SystemMetric(SystemMetric::Host(metrics)) => telemetry::emit_correlated(app_handle, name, metrics, correlation_id),
+            Self::SystemMetric(SystemMetric::DockerDiskUsage(usage)) => {
+                telemetry::emit_correlated(app_handle, name, usage, correlation_id)
+            }
+            Self::AppState(change) => telemetry::emit_correlated(app_handle, name, change, correlation_id),
+            Self::RequirementsUnmet(report) => telemetry::emit_correlated(app_handle, name, report, correlation_id),
+            Self::Connectivity(change) => telemetry::emit_correlated(app_handle, name, change, correlation_id),
+            Self::GarbageCollected(report) => telemetry::emit_correlated(app_handle, name, report, correlation_id),
+        }
+    }
+}
+
+/// An event together with the correlation ID of the span that published it.
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    /// The published event
+    pub event: Event,
+
+    /// Correlation ID of the publishing span, if any
+    pub correlation_id: Option<String>,
+}
+
+/// Payload of the `<name>-batch` events.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct EventBatch {
+    /// Payloads of the batched events, oldest first, each with the
+    /// `correlation_id` of its publisher if it had one
+    #[cfg_attr(feature = "ts-bindings", ts(type = "Array<unknown>"))]
+    pub events: Vec<Value>,
+
+    /// Events dropped since the previous batch because the queue was full
+    pub dropped: u64,
+}
+
+/// Bounded per-event queues for batched frontend events.
+#[derive(Debug, Default)]
+struct Batches {
+    pending: BTreeMap<&'static str, EventBatch>,
+}
+
+impl Batches {
+    /// Queues `payload` for `name`, dropping the oldest queued event if there
+    /// are already `capacity`.
+    fn push(&mut self, name: &'static str, payload: Value, capacity: usize) {
+        let batch = self.pending.entry(name).or_insert_with(|| EventBatch { events: Vec::new(), dropped: 0 });
+        if batch.events.len() >= capacity {
+            let excess = batch.events.len() + 1 - capacity;
+            batch.events.drain(..excess);
+            batch.dropped += excess as u64;
+        }
+        batch.events.push(payload);
+    }
+
+    /// Takes the queued batches.
+    fn drain(&mut self) -> impl Iterator<Item = (&'static str, EventBatch)> {
+        std::mem::take(&mut self.pending).into_iter()
+    }
+}
+
+/// Broadcasts [`Event`]s to the frontend and in-process subscribers.
+#[derive(Debug)]
+pub struct Bus {
+    sender: broadcast::Sender<Envelope>,
+}
+
+impl Bus {
+    /// Creates a bus without subscribers.
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(BUS_CAPACITY).0,
+        }
+    }
+
+    /// Publishes an event to all current subscribers.
+    pub fn publish(&self, event: Event) {
+        // No subscribers is fine
+        let _ = self.sender.send(Envelope {
+            event,
+            correlation_id: telemetry::current_correlation_id(),
+        });
+    }
+
+    /// Subscribes to events published from now on.
+    ///
+    /// Slow subscribers miss events once more than 1024 are buffered.
+    pub fn subscribe(&self) -> broadcast::Receiver<Envelope> {
+        self.sender.subscribe()
+    }
+
+    /// Events buffered for the slowest subscriber.
+    pub fn queued(&self) -> usize {
+        self.sender.len()
+    }
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Forwards every bus event to the frontend as its Tauri event until cancelled.
+///
+/// Events listed in the `[frontend]` section of `config` are batched, see
+/// [Backpressure](self#backpressure). The section applies without a restart.
+pub async fn forward_to_frontend(
+    bus: Arc<Bus>,
+    app_handle: tauri::AppHandle,
+    mut config: watch::Receiver<AgentConfig>,
+    cancellation_token: CancellationToken,
+) {
+    info!("Forwarding bus events to the frontend");
+    let mut events = bus.subscribe();
+    let mut settings = config.borrow_and_update().frontend.clone();
+    let mut config_open = true;
+    let mut batches = Batches::default();
+    let mut flush = flush_interval(&settings);
+    loop {
+        tokio::select! {
+            received = events.recv() => match received {
+                Ok(envelope) => {
+                    let name = envelope.event.tauri_name();
+                    if !settings.batched_events.iter().any(|batched| batched == name) {
+                        if let Err(e) = envelope.event.emit(&app_handle, envelope.correlation_id) {
+                            error!("Failed to emit {name} event: {e}");
+                        }
+                        continue;
+                    }
+                    match envelope.event.payload() {
+                        Ok(payload) => {
+                            let payload = with_correlation_id(payload, envelope.correlation_id);
+                            batches.push(name, payload, settings.queue_capacity);
+                        }
+                        Err(e) => error!("Failed to serialize {name} event: {e}"),
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => warn!("Frontend event forwarding lagged, {} events dropped", missed),
+                Err(RecvError::Closed) => break,
+            },
+            _ = flush.tick() => emit_batches(&app_handle, &mut batches),
+            changed = config.changed(), if config_open => {
+                if changed.is_err() {
+                    config_open = false;
+                    continue;
+                }
+                let frontend = config.borrow_and_update().frontend.clone();
+                if frontend != settings {
+                    debug!("Frontend event delivery settings changed: {:?}", frontend);
+                    emit_batches(&app_handle, &mut batches);
+                    flush = flush_interval(&frontend);
+                    settings = frontend;
+                }
+            }
+            _ = cancellation_token.cancelled() => {
+                info!("Frontend event forwarding received cancellation signal, shutting down");
+                break;
+            }
+        }
+    }
+}
+
+/// Ticks every `frontend.batch_interval_ms`.
+fn flush_interval(settings: &FrontendConfig) -> Interval {
+    let mut flush = interval(Duration::from_millis(settings.batch_interval_ms));
+    flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    flush
+}
+
+/// Adds the publisher's correlation ID to a batched payload, as
+/// [`telemetry::emit_correlated`] does for single events.
+fn with_correlation_id(mut payload: Value, correlation_id: Option<String>) -> Value {
+    if let (Some(object), Some(correlation_id)) = (payload.as_object_mut(), correlation_id) {
+        object.insert("correlation_id".to_string(), Value::String(correlation_id));
+    }
+    payload
+}
+
+/// Emits the queued batches as `<name>-batch` events.
+///
+/// A batch mixes events of several publishers, so it carries no correlation
+/// ID of its own; each payload has its publisher's.
+fn emit_batches(app_handle: &tauri::AppHandle, batches: &mut Batches) {
+    for (name, batch) in batches.drain() {
+        if batch.dropped > 0 {
+            warn!("Frontend {name} queue full, {} events dropped", batch.dropped);
+        }
+        if let Err(e) = telemetry::emit_correlated(app_handle, &format!("{name}-batch"), &batch, None) {
+            error!("Failed to emit {name}-batch event: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::DockerStatus;
+
+    #[tokio::test]
+    async fn test_subscribers_receive_published_events() {
+        let bus = Bus::new();
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+
+        bus.publish(Event::DaemonStatus(DockerStatusChangedPayload::new(DockerStatus::Paused)));
+
+        for subscriber in [&mut first, &mut second] {
+            let envelope = subscriber.recv().await.unwrap();
+            assert!(matches!(envelope.event, Event::DaemonStatus(DockerStatusChangedPayload { status: DockerStatus::Paused, .. })));
+            assert_eq!(envelope.event.tauri_name(), "docker-status-changed");
+        }
+    }
+
+    #[test]
+    fn test_batches_drop_oldest() {
+        let mut batches = Batches::default();
+        for i in 0..5 {
+            batches.push("docker-event", Value::from(i), 3);
+        }
+        batches.push("system-metrics", Value::from(0), 3);
+
+        let drained: Vec<_> = batches.drain().collect();
+        assert_eq!(
+            drained,
+            [
+                ("docker-event", EventBatch { events: vec![Value::from(2), Value::from(3), Value::from(4)], dropped: 2 }),
+                ("system-metrics", EventBatch { events: vec![Value::from(0)], dropped: 0 }),
+            ]
+        );
+        assert_eq!(batches.drain().count(), 0);
+    }
+
+    #[test]
+    fn test_batched_payloads_keep_correlation_id() {
+        let payload = serde_json::json!({ "id": "abc" });
+        assert_eq!(
+            with_correlation_id(payload.clone(), Some("req-1".to_string())),
+            serde_json::json!({ "id": "abc", "correlation_id": "req-1" })
+        );
+        assert_eq!(with_correlation_id(payload.clone(), None), payload);
+        assert_eq!(with_correlation_id(Value::from(3), Some("req-1".to_string())), Value::from(3));
+    }
+
+    #[test]
+    fn test_publishing_without_subscribers() {
+        Bus::new().publish(Event::DaemonStatus(DockerStatusChangedPayload::new(DockerStatus::Starting)));
+    }
+}
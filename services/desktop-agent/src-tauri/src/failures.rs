@@ -0,0 +1,157 @@
+//! Container failure classification for RedSys Desktop Agent
+//!
+//! An exit code alone does not tell a provider why a job failed: `137` is
+//! both a container killed for exceeding its memory limit and one killed by
+//! `docker kill`. When a RedSys container dies, its exit code and the
+//! `OOMKilled` flag from inspecting it are classified as an
+//! [`ExitFailure`]:
+//! - `OomKilled` when it exited non-zero after the kernel killed a process
+//!   in it for exceeding its memory limit
+//! - `Signal` for exit codes above 128, i.e. `128 + signal number`
+//! - `NonZeroExit` for any other non-zero code
+//!
+//! The classification is recorded in the job result and the failed job's
+//! error message.
+//!
+//! ## Events
+//! `container-failed` carries a [`ContainerFailed`] for every RedSys
+//! container that did not exit cleanly.
+//!
+//! ## References
+//! - [Docker Inspect Container API](https://docs.docker.com/engine/api/v1.47/#tag/Container/operation/ContainerInspect)
+//! - [Exit status of signalled processes](https://tldp.org/LDP/abs/html/exitcodes.html)
+
+use std::fmt;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Exit codes above this are `128 + signal number`.
+const SIGNAL_EXIT_BASE: i64 = 128;
+
+/// Highest signal number on Linux, including real-time signals.
+const MAX_SIGNAL: i64 = 64;
+
+/// Why a container did not exit cleanly.
+///
+/// Uses `#[serde(tag = "type")]` like [`JobState`](crate::jobs::JobState).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(tag = "type")]
+pub enum ExitFailure {
+    /// Killed by the kernel for exceeding its memory limit
+    OomKilled,
+
+    /// Terminated by a signal, e.g. `SIGKILL` from `docker kill`
+    Signal { signal: i64, name: Option<String> },
+
+    /// Exited with a non-zero code
+    NonZeroExit,
+}
+
+impl ExitFailure {
+    /// Classifies how a container exited; `None` for a clean exit.
+    ///
+    /// `OOMKilled` is also set when a process other than the main one was
+    /// killed, so it only counts if the container did not exit cleanly.
+    pub fn classify(exit_code: i64, oom_killed: bool) -> Option<Self> {
+        match exit_code {
+            0 => None,
+            _ if oom_killed => Some(ExitFailure::OomKilled),
+            code if code > SIGNAL_EXIT_BASE && code <= SIGNAL_EXIT_BASE + MAX_SIGNAL => {
+                let signal = code - SIGNAL_EXIT_BASE;
+                Some(ExitFailure::Signal {
+                    signal,
+                    name: signal_name(signal).map(str::to_string),
+                })
+            }
+            _ => Some(ExitFailure::NonZeroExit),
+        }
+    }
+
+    /// Human-readable description including the exit code.
+    pub fn describe(&self, exit_code: i64) -> String {
+        format!("{self} (exit code {exit_code})")
+    }
+}
+
+impl fmt::Display for ExitFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitFailure::OomKilled => write!(f, "Container was killed for exceeding its memory limit"),
+            ExitFailure::Signal { name: Some(name), .. } => write!(f, "Container was terminated by {name}"),
+            ExitFailure::Signal { signal, name: None } => write!(f, "Container was terminated by signal {signal}"),
+            ExitFailure::NonZeroExit => write!(f, "Container exited with an error"),
+        }
+    }
+}
+
+/// Name of a standard POSIX signal.
+fn signal_name(signal: i64) -> Option<&'static str> {
+    Some(match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        _ => return None,
+    })
+}
+
+/// Payload of the `container-failed` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ContainerFailed {
+    /// Container ID
+    pub container_id: String,
+
+    /// Container name
+    pub container_name: String,
+
+    /// Job the container ran, for job containers
+    pub job_id: Option<String>,
+
+    /// Exit code
+    pub exit_code: i64,
+
+    /// Classification of the failure
+    pub failure: ExitFailure,
+
+    /// When the failure was observed
+    pub failed_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(ExitFailure::classify(0, false), None);
+        assert_eq!(ExitFailure::classify(137, true), Some(ExitFailure::OomKilled));
+        assert_eq!(ExitFailure::classify(1, true), Some(ExitFailure::OomKilled));
+        assert_eq!(ExitFailure::classify(0, true), None);
+        assert_eq!(
+            ExitFailure::classify(137, false),
+            Some(ExitFailure::Signal { signal: 9, name: Some("SIGKILL".to_string()) })
+        );
+        assert_eq!(ExitFailure::classify(1, false), Some(ExitFailure::NonZeroExit));
+        assert_eq!(ExitFailure::classify(255, false), Some(ExitFailure::NonZeroExit));
+    }
+
+    #[test]
+    fn test_describe() {
+        let failure = ExitFailure::classify(143, false).unwrap();
+        assert_eq!(failure.describe(143), "Container was terminated by SIGTERM (exit code 143)");
+        assert_eq!(
+            ExitFailure::OomKilled.describe(137),
+            "Container was killed for exceeding its memory limit (exit code 137)"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Fault injection for RedSys Desktop Agent
+//!
+//! Lets QA script outage, flapping and restart scenarios against the real
+//! UI. An injected [`Fault`] overrides the
+//! [`DockerMonitor`](crate::docker_monitor::DockerMonitor) checks for a while,
+//! so status transitions, restart detection and notifications behave as for a
+//! real daemon failure. Injected events go through the same path as events
+//! from the Docker events stream.
+//!
+//! Injection is only available in builds with the `fault-injection` feature.
+//! Other builds reject the `inject_fault` and `inject_event` commands with a
+//! permission error.
+//!
+//! ## Scenarios
+//! - Restart: `daemon_down` for a few seconds, shorter than the restart window
+//! - Outage: `daemon_down` for a minute or more
+//! - Flapping: repeated short `daemon_down` injections
+//! - Wedged daemon: `daemon_unresponsive` until the health-check circuit opens
+
+use std::sync::RwLock;
+use std::time::Instant;
+use tokio::time::Duration;
+use tracing::{info, warn};
+use bollard::models::EventMessage;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::diagnosis::DockerUnavailableReason;
+use crate::docker::{DockerEvent, DockerService};
+use crate::docker_monitor::{DockerMonitorError, DockerMonitorResult, DockerStatus};
+use crate::error::{AppError, AppResult};
+
+/// Whether this build accepts injected faults and events.
+pub const ENABLED: bool = cfg!(feature = "fault-injection");
+
+/// Longest fault that can be injected.
+const MAX_FAULT_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// Daemon failure reported by the monitor while a fault is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// The daemon is stopped
+    DaemonDown,
+
+    /// The daemon is booting and not answering API calls yet
+    DaemonStarting,
+
+    /// Checks time out, like a wedged daemon
+    DaemonUnresponsive,
+}
+
+impl Fault {
+    /// Result of a monitor check while the fault is active.
+    pub fn check_result(self) -> DockerMonitorResult<DockerStatus> {
+        match self {
+            Fault::DaemonDown => Ok(DockerStatus::Stopped { reason: DockerUnavailableReason::ServiceStopped }),
+            Fault::DaemonStarting => Ok(DockerStatus::Starting),
+            Fault::DaemonUnresponsive => Err(DockerMonitorError::Timeout("injected fault".to_string())),
+        }
+    }
+}
+
+/// Holds the active injected fault.
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    /// Injected fault and when it ends
+    active: RwLock<Option<(Fault, Instant)>>,
+}
+
+impl FaultInjector {
+    /// Creates an injector without an active fault.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes monitor checks report `fault` for `duration`, replacing any
+    /// active fault.
+    pub fn inject(&self, fault: Fault, duration: Duration) -> AppResult<()> {
+        ensure_enabled()?;
+        if duration.is_zero() || duration > MAX_FAULT_DURATION {
+            return Err(AppError::Configuration(format!(
+                "Fault duration must be between 1s and {}s",
+                MAX_FAULT_DURATION.as_secs()
+            )));
+        }
+        warn!("Injecting fault {:?} for {}s", fault, duration.as_secs());
+        self.set(fault, duration);
+        Ok(())
+    }
+
+    /// Ends the active fault, if any.
+    pub fn clear(&self) {
+        if self.active.write().unwrap_or_else(|e| e.into_inner()).take().is_some() {
+            info!("Injected fault cleared");
+        }
+    }
+
+    /// Gets the fault that is active now.
+    pub fn active(&self) -> Option<Fault> {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .filter(|(_, until)| Instant::now() < *until)
+            .map(|(fault, _)| fault)
+    }
+
+    /// Publishes a Docker event given as Docker Engine JSON, e.g. a line of
+    /// `docker events --format '{{json .}}'`, as if it came from the events
+    /// stream.
+    ///
+    /// Events without a timestamp are stamped with the current time. Returns
+    /// whether the event passed the active filters and was published.
+    pub async fn inject_event(&self, service: &DockerService, event_json: &str) -> AppResult<bool> {
+        ensure_enabled()?;
+        let mut message: EventMessage = serde_json::from_str(event_json)?;
+        if message.time.is_none() {
+            let now = Utc::now();
+            message.time = Some(now.timestamp());
+            message.time_nano = now.timestamp_nanos_opt();
+        }
+        let event = DockerEvent::from(message);
+        warn!("Injecting Docker event {} {}", event.event_type, event.action);
+        Ok(service.dispatch_filtered(event).await)
+    }
+
+    fn set(&self, fault: Fault, duration: Duration) {
+        *self.active.write().unwrap_or_else(|e| e.into_inner()) = Some((fault, Instant::now() + duration));
+    }
+}
+
+fn ensure_enabled() -> AppResult<()> {
+    if ENABLED {
+        Ok(())
+    } else {
+        Err(AppError::Permission(
+            "Fault injection is not included in this build; enable the fault-injection feature".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_expires() {
+        let injector = FaultInjector::new();
+        assert_eq!(injector.active(), None);
+
+        injector.set(Fault::DaemonDown, Duration::from_secs(60));
+        assert_eq!(injector.active(), Some(Fault::DaemonDown));
+        injector.clear();
+        assert_eq!(injector.active(), None);
+
+        injector.set(Fault::DaemonStarting, Duration::ZERO);
+        assert_eq!(injector.active(), None);
+    }
+
+    #[test]
+    fn test_inject_requires_feature() {
+        let injector = FaultInjector::new();
+        let result = injector.inject(Fault::DaemonUnresponsive, Duration::from_secs(5));
+        assert_eq!(result.is_ok(), ENABLED);
+        assert!(injector.inject(Fault::DaemonDown, Duration::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_fault_check_result() {
+        assert!(matches!(
+            Fault::DaemonDown.check_result(),
+            Ok(DockerStatus::Stopped { reason: DockerUnavailableReason::ServiceStopped })
+        ));
+        assert!(matches!(Fault::DaemonUnresponsive.check_result(), Err(DockerMonitorError::Timeout(_))));
+    }
+}
@@ -0,0 +1,891 @@
+//! GPU detection for RedSys Desktop Agent
+//!
+//! RedSys jobs are largely GPU workloads, so the agent reports the GPUs of
+//! the host as part of its capability profile. NVIDIA, AMD and Apple GPUs are
+//! normalized into the same [`GpuInfo`] schema so the backend can match jobs
+//! to heterogeneous providers.
+//!
+//! ## Detection
+//! 1. **NVML** through `nvml-wrapper`, which loads the driver library at
+//!    runtime so the agent still starts on hosts without NVIDIA drivers
+//! 2. **`nvidia-smi`** output parsing when NVML cannot be loaded, e.g. with
+//!    mismatched library versions
+//! 3. **`rocm-smi`** on Linux for AMD GPUs
+//! 4. **`system_profiler`** on macOS for Apple Silicon (Metal, unified
+//!    memory) and discrete GPUs of Intel Macs
+//!
+//! The first method that finds devices wins; hosts without any report an
+//! empty device list.
+//!
+//! ## Container Runtime
+//! A driver alone does not make GPUs usable by jobs. [`GpuRuntime`] checks
+//! that Docker can start GPU containers: either the `nvidia` runtime is
+//! registered with the daemon (`docker info` runtimes) or the NVIDIA Container
+//! Toolkit hook that backs `--gpus` is installed.
+//!
+//! ## Live Metrics
+//! [`GpuMetricsStreamer`] samples utilization, VRAM usage, temperature and
+//! power draw every [`GPU_METRICS_INTERVAL`] and sends one [`GpuMetrics`]
+//! per device to every subscribed Tauri channel. It only samples while at
+//! least one channel (a dashboard panel) is subscribed, so idle agents do not
+//! poll the driver. A channel the frontend dropped, e.g. with its window, is
+//! unsubscribed by the first sample it fails to receive. Metrics are
+//! NVIDIA-only, sampled through NVML or `nvidia-smi`.
+//!
+//! ## References
+//! - [NVML API Reference](https://docs.nvidia.com/deploy/nvml-api/)
+//! - [nvml-wrapper](https://docs.rs/nvml-wrapper/latest/nvml_wrapper/)
+//! - [nvidia-smi](https://docs.nvidia.com/deploy/nvidia-smi/)
+//! - [Tauri Channels](https://v2.tauri.app/develop/calling-frontend/#channels)
+//! - [NVIDIA Container Toolkit](https://docs.nvidia.com/datacenter/cloud-native/container-toolkit/latest/)
+//! - [rocm-smi](https://rocm.docs.amd.com/projects/rocm_smi_lib/en/latest/)
+
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::{sync::watch, task, time::{interval, Duration, MissedTickBehavior}};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use chrono::{DateTime, Utc};
+use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+use nvml_wrapper::Nvml;
+use serde::Serialize;
+use tauri::ipc::Channel;
+
+use crate::docker::DockerService;
+
+/// Fields queried from `nvidia-smi`, in output column order.
+const NVIDIA_SMI_QUERY: &str = "index,name,uuid,memory.total,driver_version";
+
+/// Metric fields queried from `nvidia-smi`, in output column order.
+const NVIDIA_SMI_METRICS_QUERY: &str = "index,utilization.gpu,memory.used,memory.total,temperature.gpu,power.draw";
+
+/// Executables of the NVIDIA Container Toolkit that enable `docker run --gpus`.
+const CONTAINER_TOOLKIT_BINARIES: [&str; 2] = ["nvidia-container-runtime-hook", "nvidia-container-cli"];
+
+/// Interval between GPU metric samples while subscribed.
+pub const GPU_METRICS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// GPU manufacturer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuVendor {
+    Nvidia,
+    Amd,
+    Apple,
+    Intel,
+}
+
+/// A GPU of the host.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GpuDevice {
+    /// Device index as used by `CUDA_VISIBLE_DEVICES`, `HIP_VISIBLE_DEVICES`
+    /// and Docker device requests
+    pub index: u32,
+
+    /// Manufacturer
+    pub vendor: GpuVendor,
+
+    /// Model name, e.g. `NVIDIA GeForce RTX 4090`
+    pub name: String,
+
+    /// Device UUID or unique ID, empty if the platform reports none
+    pub uuid: String,
+
+    /// Total VRAM in bytes; for unified memory the system memory shared
+    /// with the CPU
+    pub memory_total_bytes: u64,
+
+    /// Memory is shared with the CPU (Apple Silicon)
+    pub unified_memory: bool,
+}
+
+/// Where the GPU information came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuInfoSource {
+    /// NVIDIA Management Library
+    Nvml,
+
+    /// `nvidia-smi` output
+    NvidiaSmi,
+
+    /// `rocm-smi` output
+    RocmSmi,
+
+    /// macOS `system_profiler` output
+    SystemProfiler,
+
+    /// No GPU tooling found
+    None,
+}
+
+/// GPUs, driver and CUDA version of the host.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GpuInfo {
+    /// Detection method
+    pub source: GpuInfoSource,
+
+    /// GPU driver version, e.g. `550.54.14`
+    pub driver_version: Option<String>,
+
+    /// Highest CUDA version supported by the NVIDIA driver, e.g. `12.4`
+    pub cuda_version: Option<String>,
+
+    /// Installed ROCm version, e.g. `6.1.2`
+    pub rocm_version: Option<String>,
+
+    /// Supported Metal family on macOS, e.g. `Metal 3`
+    pub metal_version: Option<String>,
+
+    /// Detected devices
+    pub devices: Vec<GpuDevice>,
+}
+
+impl GpuInfo {
+    /// Information for a host without detectable GPUs.
+    pub fn none() -> Self {
+        Self {
+            source: GpuInfoSource::None,
+            driver_version: None,
+            cuda_version: None,
+            rocm_version: None,
+            metal_version: None,
+            devices: Vec::new(),
+        }
+    }
+
+    /// Number of NVIDIA devices, the ones Docker `--gpus` requests can use.
+    pub fn nvidia_device_count(&self) -> usize {
+        self.devices.iter().filter(|device| device.vendor == GpuVendor::Nvidia).count()
+    }
+}
+
+/// Whether Docker can run GPU containers on this host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GpuRuntime {
+    /// GPU containers can be started
+    pub available: bool,
+
+    /// Why GPU containers cannot be started
+    pub reason: Option<String>,
+}
+
+impl GpuRuntime {
+    /// A runtime that cannot start GPU containers.
+    pub fn unavailable(reason: impl Into<String>) -> Self {
+        Self {
+            available: false,
+            reason: Some(reason.into()),
+        }
+    }
+
+    /// Evaluates GPU container support.
+    ///
+    /// # Arguments
+    ///
+    /// * `gpu` - Detected GPUs
+    /// * `runtimes` - Runtime names reported by `docker info`
+    /// * `toolkit_installed` - Whether the NVIDIA Container Toolkit hook is on the `PATH`
+    pub fn evaluate<S: AsRef<str>>(gpu: &GpuInfo, runtimes: &[S], toolkit_installed: bool) -> Self {
+        if gpu.nvidia_device_count() == 0 {
+            return Self::unavailable("No NVIDIA GPUs detected");
+        }
+        if runtimes.iter().any(|runtime| runtime.as_ref() == "nvidia") || toolkit_installed {
+            return Self {
+                available: true,
+                reason: None,
+            };
+        }
+        Self::unavailable("NVIDIA Container Toolkit is not installed or not registered with Docker")
+    }
+
+    /// Checks GPU container support against the daemon of `docker_service`.
+    pub async fn detect(gpu: &GpuInfo, docker_service: &DockerService) -> Self {
+        if gpu.nvidia_device_count() == 0 {
+            return Self::evaluate::<&str>(gpu, &[], false);
+        }
+        let runtimes = match docker_service.get_info().await {
+            Ok(info) => info.runtimes,
+            Err(e) => return Self::unavailable(format!("Failed to read Docker runtimes: {e}")),
+        };
+        let toolkit_installed = task::spawn_blocking(container_toolkit_installed).await.unwrap_or(false);
+        Self::evaluate(gpu, &runtimes, toolkit_installed)
+    }
+}
+
+/// Whether an NVIDIA Container Toolkit executable is on the `PATH`.
+pub(crate) fn container_toolkit_installed() -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|directory| {
+        CONTAINER_TOOLKIT_BINARIES
+            .iter()
+            .any(|binary| directory.join(binary).is_file())
+    })
+}
+
+/// Detects the GPUs of the host.
+///
+/// Blocking: loads NVML or runs vendor tools, so call it from
+/// `spawn_blocking` in async code.
+pub fn detect() -> GpuInfo {
+    match detect_nvml() {
+        Ok(info) if !info.devices.is_empty() => return info,
+        Ok(_) => debug!("NVML found no devices"),
+        Err(e) => debug!("NVML unavailable, falling back to nvidia-smi: {e}"),
+    }
+    match detect_nvidia_smi() {
+        Ok(info) if !info.devices.is_empty() => return info,
+        Ok(_) => debug!("nvidia-smi found no devices"),
+        Err(e) => debug!("nvidia-smi unavailable: {e}"),
+    }
+
+    if cfg!(target_os = "linux") {
+        match detect_rocm_smi() {
+            Ok(info) if !info.devices.is_empty() => return info,
+            Ok(_) => debug!("rocm-smi found no devices"),
+            Err(e) => debug!("rocm-smi unavailable: {e}"),
+        }
+    }
+    if cfg!(target_os = "macos") {
+        match detect_system_profiler() {
+            Ok(info) if !info.devices.is_empty() => return info,
+            Ok(_) => debug!("system_profiler found no GPUs"),
+            Err(e) => debug!("system_profiler unavailable: {e}"),
+        }
+    }
+
+    debug!("No GPUs detected");
+    GpuInfo::none()
+}
+
+/// Reads GPU information through NVML.
+fn detect_nvml() -> Result<GpuInfo, nvml_wrapper::error::NvmlError> {
+    let nvml = Nvml::init()?;
+
+    let mut devices = Vec::new();
+    for index in 0..nvml.device_count()? {
+        let device = nvml.device_by_index(index)?;
+        devices.push(GpuDevice {
+            index,
+            vendor: GpuVendor::Nvidia,
+            name: device.name()?,
+            uuid: device.uuid()?,
+            memory_total_bytes: device.memory_info()?.total,
+            unified_memory: false,
+        });
+    }
+
+    let cuda_version = nvml.sys_cuda_driver_version().ok().map(|version| {
+        format!(
+            "{}.{}",
+            nvml_wrapper::cuda_driver_version_major(version),
+            nvml_wrapper::cuda_driver_version_minor(version)
+        )
+    });
+
+    Ok(GpuInfo {
+        source: GpuInfoSource::Nvml,
+        driver_version: nvml.sys_driver_version().ok(),
+        cuda_version,
+        devices,
+        ..GpuInfo::none()
+    })
+}
+
+/// Runs a vendor tool and returns its standard output.
+fn run_tool(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {program}: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{program} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Runs `nvidia-smi` with `args` and returns its standard output.
+fn run_nvidia_smi(args: &[&str]) -> Result<String, String> {
+    run_tool("nvidia-smi", args)
+}
+
+/// Reads GPU information from `nvidia-smi`.
+fn detect_nvidia_smi() -> Result<GpuInfo, String> {
+    let query = format!("--query-gpu={NVIDIA_SMI_QUERY}");
+    let csv = run_nvidia_smi(&[&query, "--format=csv,noheader,nounits"])?;
+    let (devices, driver_version) = parse_query_output(&csv)?;
+
+    // The CUDA version is only printed in the summary header
+    let cuda_version = run_nvidia_smi(&[]).ok().and_then(|summary| parse_cuda_version(&summary));
+
+    Ok(GpuInfo {
+        source: GpuInfoSource::NvidiaSmi,
+        driver_version,
+        cuda_version,
+        devices,
+        ..GpuInfo::none()
+    })
+}
+
+/// Parses `nvidia-smi --query-gpu` CSV output into devices and the driver version.
+fn parse_query_output(csv: &str) -> Result<(Vec<GpuDevice>, Option<String>), String> {
+    let mut devices = Vec::new();
+    let mut driver_version = None;
+
+    for line in csv.lines().filter(|line| !line.trim().is_empty()) {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [index, name, uuid, memory_mib, driver] = fields[..] else {
+            return Err(format!("Unexpected nvidia-smi output line: {line}"));
+        };
+        devices.push(GpuDevice {
+            index: index.parse().map_err(|_| format!("Invalid GPU index '{index}'"))?,
+            vendor: GpuVendor::Nvidia,
+            name: name.to_string(),
+            uuid: uuid.to_string(),
+            memory_total_bytes: memory_mib.parse::<u64>().map(|mib| mib * 1024 * 1024).unwrap_or_default(),
+            unified_memory: false,
+        });
+        driver_version.get_or_insert_with(|| driver.to_string());
+    }
+    Ok((devices, driver_version))
+}
+
+/// Extracts `CUDA Version: 12.4` from the `nvidia-smi` summary header.
+fn parse_cuda_version(summary: &str) -> Option<String> {
+    let (_, rest) = summary.split_once("CUDA Version:")?;
+    let version = rest.split_whitespace().next()?;
+    Some(version.trim_end_matches('|').to_string())
+}
+
+/// Reads AMD GPU information from `rocm-smi`.
+fn detect_rocm_smi() -> Result<GpuInfo, String> {
+    let json = run_tool(
+        "rocm-smi",
+        &["--showproductname", "--showmeminfo", "vram", "--showuniqueid", "--showdriverversion", "--json"],
+    )?;
+    let (devices, driver_version) = parse_rocm_smi_output(&json)?;
+
+    let rocm_version = std::fs::read_to_string("/opt/rocm/.info/version")
+        .ok()
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty());
+
+    Ok(GpuInfo {
+        source: GpuInfoSource::RocmSmi,
+        driver_version,
+        rocm_version,
+        devices,
+        ..GpuInfo::none()
+    })
+}
+
+/// Parses `rocm-smi --json` output, keyed by `card0`, `card1`, ... with a
+/// `system` entry holding the driver version.
+fn parse_rocm_smi_output(json: &str) -> Result<(Vec<GpuDevice>, Option<String>), String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Invalid rocm-smi output: {e}"))?;
+    let Some(entries) = value.as_object() else {
+        return Err("Unexpected rocm-smi output".to_string());
+    };
+    let field = |entry: &serde_json::Value, keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| entry.get(*key)?.as_str())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut devices = Vec::new();
+    for (key, entry) in entries {
+        let Some(index) = key.strip_prefix("card").and_then(|index| index.parse().ok()) else {
+            continue;
+        };
+        devices.push(GpuDevice {
+            index,
+            vendor: GpuVendor::Amd,
+            name: field(entry, &["Card Series", "Card series", "Device Name", "Card model"]).unwrap_or_default(),
+            uuid: field(entry, &["Unique ID"]).unwrap_or_default(),
+            memory_total_bytes: field(entry, &["VRAM Total Memory (B)"])
+                .and_then(|bytes| bytes.parse().ok())
+                .unwrap_or_default(),
+            unified_memory: false,
+        });
+    }
+    devices.sort_by_key(|device| device.index);
+
+    let driver_version = entries.get("system").and_then(|system| field(system, &["Driver version"]));
+    Ok((devices, driver_version))
+}
+
+/// Reads Apple Silicon and Intel Mac GPU information from `system_profiler`.
+fn detect_system_profiler() -> Result<GpuInfo, String> {
+    let json = run_tool("system_profiler", &["SPDisplaysDataType", "-json"])?;
+    // Apple Silicon GPUs share system memory
+    let memory_bytes = run_tool("sysctl", &["-n", "hw.memsize"])
+        .ok()
+        .and_then(|memsize| memsize.trim().parse().ok())
+        .unwrap_or_default();
+    parse_system_profiler_output(&json, memory_bytes)
+}
+
+/// Parses `system_profiler SPDisplaysDataType -json` output.
+fn parse_system_profiler_output(json: &str, system_memory_bytes: u64) -> Result<GpuInfo, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Invalid system_profiler output: {e}"))?;
+    let displays = value
+        .get("SPDisplaysDataType")
+        .and_then(|displays| displays.as_array())
+        .ok_or_else(|| "Unexpected system_profiler output".to_string())?;
+
+    let mut devices = Vec::new();
+    let mut metal_version = None;
+    for (index, display) in displays.iter().enumerate() {
+        let text = |key: &str| display.get(key).and_then(|value| value.as_str()).unwrap_or_default();
+
+        let vendor_text = text("spdisplays_vendor").to_ascii_lowercase() + &text("sppci_vendor").to_ascii_lowercase();
+        let vendor = if vendor_text.contains("apple") {
+            GpuVendor::Apple
+        } else if vendor_text.contains("intel") {
+            GpuVendor::Intel
+        } else if vendor_text.contains("amd") || vendor_text.contains("ati") {
+            GpuVendor::Amd
+        } else if vendor_text.contains("nvidia") {
+            GpuVendor::Nvidia
+        } else {
+            continue;
+        };
+        let unified_memory = vendor == GpuVendor::Apple;
+
+        // Discrete GPUs report VRAM like "8 GB"; Apple Silicon shares system memory
+        let memory_total_bytes = if unified_memory {
+            system_memory_bytes
+        } else {
+            parse_memory_size(text("spdisplays_vram"))
+                .or_else(|| parse_memory_size(text("spdisplays_vram_shared")))
+                .unwrap_or_default()
+        };
+
+        if let Some(family) = text("spdisplays_mtlgpufamilysupport").strip_prefix("spdisplays_metal") {
+            metal_version.get_or_insert_with(|| format!("Metal {family}"));
+        }
+
+        devices.push(GpuDevice {
+            index: index as u32,
+            vendor,
+            name: text("sppci_model").to_string(),
+            uuid: String::new(),
+            memory_total_bytes,
+            unified_memory,
+        });
+    }
+
+    Ok(GpuInfo {
+        source: GpuInfoSource::SystemProfiler,
+        metal_version,
+        devices,
+        ..GpuInfo::none()
+    })
+}
+
+/// Parses sizes like `8 GB` or `1536 MB` into bytes.
+fn parse_memory_size(text: &str) -> Option<u64> {
+    let mut parts = text.split_whitespace();
+    let amount: u64 = parts.next()?.parse().ok()?;
+    let multiplier = match parts.next()?.to_ascii_uppercase().as_str() {
+        "GB" => 1024 * 1024 * 1024,
+        "MB" => 1024 * 1024,
+        _ => return None,
+    };
+    Some(amount * multiplier)
+}
+
+/// Metrics sample of one GPU, sent to `subscribe_gpu_metrics` channels.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GpuMetrics {
+    /// Device index
+    pub index: u32,
+
+    /// GPU utilization in percent
+    pub utilization_percent: u32,
+
+    /// Used VRAM in bytes
+    pub memory_used_bytes: u64,
+
+    /// Total VRAM in bytes
+    pub memory_total_bytes: u64,
+
+    /// Core temperature in degrees Celsius
+    pub temperature_celsius: u32,
+
+    /// Power draw in watts, if the device reports it
+    pub power_draw_watts: Option<f64>,
+
+    /// When the sample was taken
+    pub sampled_at: DateTime<Utc>,
+}
+
+/// Samples all GPUs through NVML.
+fn sample_nvml(nvml: &Nvml) -> Result<Vec<GpuMetrics>, nvml_wrapper::error::NvmlError> {
+    let sampled_at = Utc::now();
+    let mut metrics = Vec::new();
+    for index in 0..nvml.device_count()? {
+        let device = nvml.device_by_index(index)?;
+        let memory = device.memory_info()?;
+        metrics.push(GpuMetrics {
+            index,
+            utilization_percent: device.utilization_rates()?.gpu,
+            memory_used_bytes: memory.used,
+            memory_total_bytes: memory.total,
+            temperature_celsius: device.temperature(TemperatureSensor::Gpu)?,
+            // Reported in milliwatts; unsupported on some consumer boards
+            power_draw_watts: device.power_usage().ok().map(|milliwatts| f64::from(milliwatts) / 1000.0),
+            sampled_at,
+        });
+    }
+    Ok(metrics)
+}
+
+/// Samples all GPUs through `nvidia-smi`.
+fn sample_nvidia_smi() -> Result<Vec<GpuMetrics>, String> {
+    let query = format!("--query-gpu={NVIDIA_SMI_METRICS_QUERY}");
+    let csv = run_nvidia_smi(&[&query, "--format=csv,noheader,nounits"])?;
+    parse_metrics_output(&csv, Utc::now())
+}
+
+/// Parses `nvidia-smi --query-gpu` metrics CSV output.
+fn parse_metrics_output(csv: &str, sampled_at: DateTime<Utc>) -> Result<Vec<GpuMetrics>, String> {
+    const MIB: u64 = 1024 * 1024;
+
+    csv.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [index, utilization, memory_used, memory_total, temperature, power] = fields[..] else {
+                return Err(format!("Unexpected nvidia-smi output line: {line}"));
+            };
+            Ok(GpuMetrics {
+                index: index.parse().map_err(|_| format!("Invalid GPU index '{index}'"))?,
+                utilization_percent: utilization.parse().unwrap_or_default(),
+                memory_used_bytes: memory_used.parse::<u64>().map(|mib| mib * MIB).unwrap_or_default(),
+                memory_total_bytes: memory_total.parse::<u64>().map(|mib| mib * MIB).unwrap_or_default(),
+                temperature_celsius: temperature.parse().unwrap_or_default(),
+                // `[N/A]` when the board does not report power
+                power_draw_watts: power.parse().ok(),
+                sampled_at,
+            })
+        })
+        .collect()
+}
+
+/// Streams GPU metrics to the channels of subscribed dashboard panels.
+pub struct GpuMetricsStreamer {
+    /// Subscribed channels by channel ID
+    channels: Mutex<HashMap<u32, Channel<GpuMetrics>>>,
+
+    /// Number of subscribed channels
+    subscribers: watch::Sender<usize>,
+
+    /// Set to stop sampling regardless of subscribers, e.g. on battery
+    paused: watch::Sender<bool>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: Arc<CancellationToken>,
+}
+
+impl std::fmt::Debug for GpuMetricsStreamer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuMetricsStreamer")
+            .field("subscribers", &self.subscriber_count())
+            .field("paused", &self.is_paused())
+            .finish()
+    }
+}
+
+impl GpuMetricsStreamer {
+    /// Creates a streamer without subscribers.
+    pub fn new(cancellation_token: CancellationToken) -> Self {
+        Self {
+            channels: Mutex::new(HashMap::new()),
+            subscribers: watch::Sender::new(0),
+            paused: watch::Sender::new(false),
+            cancellation_token: Arc::new(cancellation_token),
+        }
+    }
+
+    /// Locks the subscribed channels.
+    fn channels(&self) -> MutexGuard<'_, HashMap<u32, Channel<GpuMetrics>>> {
+        self.channels.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Publishes the number of channels, waking the sampling loop if it
+    /// changed.
+    fn update_count(&self, count: usize) -> usize {
+        self.subscribers.send_if_modified(|current| std::mem::replace(current, count) != count);
+        count
+    }
+
+    /// Sends samples to `channel`, starting sampling if it is the first.
+    ///
+    /// Returns the number of subscribers.
+    pub fn subscribe(&self, channel: Channel<GpuMetrics>) -> usize {
+        let mut channels = self.channels();
+        channels.insert(channel.id(), channel);
+        self.update_count(channels.len())
+    }
+
+    /// Stops sending samples to the channel with ID `channel_id`, stopping
+    /// sampling after the last one.
+    ///
+    /// Returns the number of remaining subscribers.
+    pub fn unsubscribe(&self, channel_id: u32) -> usize {
+        let mut channels = self.channels();
+        channels.remove(&channel_id);
+        self.update_count(channels.len())
+    }
+
+    /// Gets the number of subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        *self.subscribers.borrow()
+    }
+
+    /// Sends `metrics` to every channel, unsubscribing the channels that fail
+    /// to receive them.
+    fn publish(&self, metrics: &[GpuMetrics]) {
+        let mut channels = self.channels();
+        channels.retain(|id, channel| {
+            let sent = metrics.iter().try_for_each(|device| channel.send(device.clone()));
+            if let Err(e) = &sent {
+                debug!("Unsubscribing GPU metrics channel {}: {}", id, e);
+            }
+            sent.is_ok()
+        });
+        self.update_count(channels.len());
+    }
+
+    /// Stops or resumes sampling while subscribers stay registered.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.send_if_modified(|current| std::mem::replace(current, paused) != paused);
+    }
+
+    /// Whether sampling is paused.
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Completes once samples are wanted: there is a subscriber and sampling
+    /// is not paused. Completes immediately if that is already the case.
+    async fn subscribed(&self) {
+        let mut subscribers = self.subscribers.subscribe();
+        let mut paused = self.paused.subscribe();
+        // The senders live in `self`, so the channels cannot close here
+        while *subscribers.borrow_and_update() == 0 || *paused.borrow_and_update() {
+            tokio::select! {
+                _ = subscribers.changed() => {}
+                _ = paused.changed() => {}
+            }
+        }
+    }
+
+    /// Completes once the last subscriber is gone or sampling is paused.
+    async fn unsubscribed(&self) {
+        let mut subscribers = self.subscribers.subscribe();
+        let mut paused = self.paused.subscribe();
+        tokio::select! {
+            _ = subscribers.wait_for(|count| *count == 0) => {}
+            _ = paused.wait_for(|paused| *paused) => {}
+        }
+    }
+
+    /// Completes on the next subscribe or unsubscribe.
+    async fn subscribers_changed(&self) {
+        let mut receiver = self.subscribers.subscribe();
+        let _ = receiver.changed().await;
+    }
+
+    /// Starts the sampling loop.
+    pub async fn start(self: Arc<Self>) {
+        let cancellation_token = self.cancellation_token.clone();
+
+        'idle: loop {
+            tokio::select! {
+                _ = self.subscribed() => {}
+                _ = cancellation_token.cancelled() => break 'idle,
+            }
+            info!("GPU metrics streaming started");
+
+            // Keep NVML loaded for the whole streaming session
+            let nvml = task::spawn_blocking(Nvml::init)
+                .await
+                .ok()
+                .and_then(|result| result.map_err(|e| debug!("NVML unavailable for metrics: {e}")).ok())
+                .map(Arc::new);
+
+            let mut ticker = interval(GPU_METRICS_INTERVAL);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        let nvml = nvml.clone();
+                        let sample = task::spawn_blocking(move || match nvml {
+                            Some(nvml) => sample_nvml(&nvml).map_err(|e| e.to_string()),
+                            None => sample_nvidia_smi(),
+                        });
+                        match sample.await {
+                            Ok(Ok(metrics)) => self.publish(&metrics),
+                            Ok(Err(e)) => {
+                                warn!("Failed to sample GPU metrics, pausing until subscribers change: {e}");
+                                tokio::select! {
+                                    _ = self.subscribers_changed() => continue 'idle,
+                                    _ = cancellation_token.cancelled() => break 'idle,
+                                }
+                            }
+                            Err(e) => error!("GPU metrics sampling panicked: {e}"),
+                        }
+                    }
+                    _ = self.unsubscribed() => {
+                        info!("GPU metrics streaming stopped, no subscribers or paused");
+                        continue 'idle;
+                    }
+                    _ = cancellation_token.cancelled() => break 'idle,
+                }
+            }
+        }
+
+        info!("GPU metrics streamer received cancellation signal, shutting down");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_output() {
+        let csv = "0, NVIDIA GeForce RTX 4090, GPU-1a2b, 24564, 550.54.14\n1, NVIDIA A100-SXM4-80GB, GPU-3c4d, 81920, 550.54.14\n";
+        let (devices, driver_version) = parse_query_output(csv).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].name, "NVIDIA GeForce RTX 4090");
+        assert_eq!(devices[1].index, 1);
+        assert_eq!(devices[1].memory_total_bytes, 81920 * 1024 * 1024);
+        assert_eq!(driver_version.as_deref(), Some("550.54.14"));
+
+        assert!(parse_query_output("garbage").is_err());
+        assert_eq!(parse_query_output("").unwrap().0, Vec::new());
+    }
+
+    #[test]
+    fn test_parse_metrics_output() {
+        let csv = "0, 87, 20480, 24564, 71, 312.45\n1, 0, 0, 16384, 34, [N/A]\n";
+        let metrics = parse_metrics_output(csv, Utc::now()).unwrap();
+        assert_eq!(metrics[0].utilization_percent, 87);
+        assert_eq!(metrics[0].memory_used_bytes, 20480 * 1024 * 1024);
+        assert_eq!(metrics[0].power_draw_watts, Some(312.45));
+        assert_eq!(metrics[1].temperature_celsius, 34);
+        assert_eq!(metrics[1].power_draw_watts, None);
+    }
+
+    #[test]
+    fn test_dropped_channels_unsubscribed() {
+        let streamer = GpuMetricsStreamer::new(CancellationToken::new());
+        let open = Channel::new(|_| Ok(()));
+        let dropped = Channel::new(|_| Err(tauri::Error::WebviewNotFound));
+        assert_eq!(streamer.subscribe(open.clone()), 1);
+        assert_eq!(streamer.subscribe(open.clone()), 1);
+        assert_eq!(streamer.subscribe(dropped), 2);
+
+        streamer.publish(&parse_metrics_output("0, 87, 20480, 24564, 71, 312.45\n", Utc::now()).unwrap());
+        assert_eq!(streamer.subscriber_count(), 1);
+        assert_eq!(streamer.unsubscribe(open.id()), 0);
+        assert_eq!(streamer.unsubscribe(open.id()), 0);
+    }
+
+    #[tokio::test]
+    async fn test_pause_overrides_subscribers() {
+        let streamer = GpuMetricsStreamer::new(CancellationToken::new());
+        streamer.subscribe(Channel::new(|_| Ok(())));
+        streamer.set_paused(true);
+        assert!(tokio::time::timeout(Duration::from_millis(50), streamer.subscribed()).await.is_err());
+        streamer.unsubscribed().await;
+
+        streamer.set_paused(false);
+        streamer.subscribed().await;
+    }
+
+    #[test]
+    fn test_gpu_runtime_evaluation() {
+        let mut gpu = GpuInfo::none();
+        assert!(!GpuRuntime::evaluate(&gpu, &["nvidia"], true).available);
+
+        gpu.devices.push(GpuDevice {
+            index: 0,
+            vendor: GpuVendor::Amd,
+            name: "AMD Radeon RX 7900 XTX".to_string(),
+            uuid: String::new(),
+            memory_total_bytes: 24 << 30,
+            unified_memory: false,
+        });
+        assert!(!GpuRuntime::evaluate(&gpu, &["nvidia"], true).available);
+
+        gpu.devices.push(GpuDevice {
+            index: 0,
+            vendor: GpuVendor::Nvidia,
+            name: "NVIDIA GeForce RTX 4090".to_string(),
+            uuid: "GPU-1a2b".to_string(),
+            memory_total_bytes: 24 << 30,
+            unified_memory: false,
+        });
+        assert!(GpuRuntime::evaluate(&gpu, &["runc", "nvidia"], false).available);
+        assert!(GpuRuntime::evaluate(&gpu, &["runc"], true).available);
+
+        let runtime = GpuRuntime::evaluate(&gpu, &["runc"], false);
+        assert!(!runtime.available);
+        assert!(runtime.reason.unwrap().contains("Container Toolkit"));
+    }
+
+    #[test]
+    fn test_parse_rocm_smi_output() {
+        let json = r#"{
+            "card1": {"Card Series": "Navi 31 [Radeon RX 7900 XTX]", "Unique ID": "0x2f1e", "VRAM Total Memory (B)": "25753026560"},
+            "card0": {"Card Series": "Instinct MI210", "Unique ID": "0x9a4b", "VRAM Total Memory (B)": "68702699520"},
+            "system": {"Driver version": "6.7.0"}
+        }"#;
+        let (devices, driver_version) = parse_rocm_smi_output(json).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].name, "Instinct MI210");
+        assert_eq!(devices[1].memory_total_bytes, 25_753_026_560);
+        assert_eq!(devices[1].vendor, GpuVendor::Amd);
+        assert_eq!(driver_version.as_deref(), Some("6.7.0"));
+    }
+
+    #[test]
+    fn test_parse_system_profiler_output() {
+        let json = r#"{"SPDisplaysDataType": [{
+            "sppci_model": "Apple M2 Pro",
+            "sppci_vendor": "sppci_vendor_Apple",
+            "spdisplays_mtlgpufamilysupport": "spdisplays_metal3"
+        }]}"#;
+        let info = parse_system_profiler_output(json, 32 << 30).unwrap();
+        assert_eq!(info.devices[0].vendor, GpuVendor::Apple);
+        assert!(info.devices[0].unified_memory);
+        assert_eq!(info.devices[0].memory_total_bytes, 32 << 30);
+        assert_eq!(info.metal_version.as_deref(), Some("Metal 3"));
+
+        let json = r#"{"SPDisplaysDataType": [{"sppci_model": "AMD Radeon Pro 5500M", "spdisplays_vendor": "sppci_vendor_amd", "spdisplays_vram": "8 GB"}]}"#;
+        let info = parse_system_profiler_output(json, 32 << 30).unwrap();
+        assert_eq!(info.devices[0].memory_total_bytes, 8 << 30);
+        assert!(!info.devices[0].unified_memory);
+    }
+
+    #[test]
+    fn test_parse_cuda_version() {
+        let summary = "| NVIDIA-SMI 550.54.14    Driver Version: 550.54.14    CUDA Version: 12.4     |";
+        assert_eq!(parse_cuda_version(summary).as_deref(), Some("12.4"));
+        assert_eq!(parse_cuda_version("no header"), None);
+    }
+}
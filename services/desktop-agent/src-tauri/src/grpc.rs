@@ -0,0 +1,394 @@
+//! gRPC agent API for RedSys Desktop Agent
+//!
+//! Serves the `redsys.agent.v1.AgentService` contract from `proto/agent.proto`
+//! so the RedSys orchestrator can query status, stream events and operate
+//! containers with typed messages instead of ad-hoc JSON.
+//!
+//! Available with the `grpc` cargo feature (which needs `protoc` at build
+//! time) and enabled via `[grpc]` in the config file. Container operations
+//! answer `PERMISSION_DENIED` unless `grpc.allow_container_operations` is set.
+//!
+//! ## Authentication
+//! With `grpc.token` set, every call must carry `authorization: Bearer
+//! <token>` metadata or is answered with `UNAUTHENTICATED`. With
+//! `grpc.tls_cert` and `grpc.tls_key` set, the API is served over TLS. The
+//! API refuses to listen on a non-loopback address without both, since it
+//! would hand the Docker daemon to anyone on the network.
+//!
+//! ## References
+//! - [tonic](https://docs.rs/tonic/latest/tonic/)
+//! - [prost](https://docs.rs/prost/latest/prost/)
+//! - [gRPC Status Codes](https://grpc.github.io/grpc/core/md_doc_statuscodes.html)
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tonic::transport::{Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+use futures::Stream;
+
+use crate::app_state::AppStateStore;
+use crate::docker::{ContainerHealth, ContainerInfo, DockerEvent, DockerService};
+use crate::docker_monitor::{DockerMonitor, DockerMonitorError, DockerStatus};
+use crate::error::{AppError, AppResult};
+
+/// Types and service traits generated from `proto/agent.proto`.
+pub mod proto {
+    tonic::include_proto!("redsys.agent.v1");
+}
+
+use proto::agent_service_server::{AgentService, AgentServiceServer};
+
+/// Grace period for `StopContainer` calls without a timeout.
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl From<&DockerStatus> for proto::DockerStatus {
+    fn from(status: &DockerStatus) -> Self {
+        use proto::docker_status::State;
+
+        let (state, version, message) = match status {
+            DockerStatus::Running { version, .. } => (State::Running, version.clone(), String::new()),
+            DockerStatus::Stopped { reason } => (State::Stopped, String::new(), reason.to_string()),
+            DockerStatus::Error { message, .. } => (State::Error, String::new(), message.clone()),
+            DockerStatus::Paused => (State::Paused, String::new(), String::new()),
+            DockerStatus::Starting => (State::Starting, String::new(), String::new()),
+            DockerStatus::Restarting => (State::Restarting, String::new(), String::new()),
+            DockerStatus::Degraded { reason } => (State::Degraded, String::new(), reason.clone()),
+            DockerStatus::Unreachable { endpoint, reason } => {
+                (State::Unreachable, String::new(), format!("{endpoint}: {reason}"))
+            }
+        };
+        Self {
+            state: state as i32,
+            version,
+            message,
+        }
+    }
+}
+
+impl From<DockerEvent> for proto::DockerEvent {
+    fn from(event: DockerEvent) -> Self {
+        Self {
+            event_type: event.event_type,
+            action: event.action,
+            actor_id: event.actor_id.unwrap_or_default(),
+            actor_name: event.actor_name.unwrap_or_default(),
+            attributes: event.attributes,
+            time_unix_nanos: event.time.and_then(|t| t.timestamp_nanos_opt()).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ContainerInfo> for proto::Container {
+    fn from(container: ContainerInfo) -> Self {
+        let health = match container.health {
+            ContainerHealth::Healthy => "healthy",
+            ContainerHealth::Unhealthy => "unhealthy",
+            ContainerHealth::Starting => "starting",
+            ContainerHealth::None => "none",
+        };
+        Self {
+            id: container.id,
+            name: container.name,
+            image: container.image,
+            state: container.state,
+            status: container.status,
+            health: health.to_string(),
+            labels: container.labels,
+        }
+    }
+}
+
+/// Maps Docker errors to gRPC status codes.
+fn to_status(error: DockerMonitorError) -> Status {
+    match error {
+        DockerMonitorError::Connection(e) => Status::unavailable(e.to_string()),
+        DockerMonitorError::ResourceLimit(message) => Status::resource_exhausted(message),
+        error @ (DockerMonitorError::SocketPermission(_) | DockerMonitorError::UntrustedImage { .. }) => {
+            Status::permission_denied(error.to_string())
+        }
+        error @ DockerMonitorError::UnsupportedVersion { .. } => Status::failed_precondition(error.to_string()),
+        other => Status::internal(other.to_string()),
+    }
+}
+
+/// `AgentService` implementation backed by the running monitors.
+#[derive(Debug)]
+pub struct AgentApi {
+    /// Source of the Docker status
+    docker_monitor: Arc<DockerMonitor>,
+
+    /// Source of events and container operations
+    docker_service: Arc<DockerService>,
+
+    /// Source of the agent name and version
+    app_state: Arc<AppStateStore>,
+
+    /// Whether start/stop calls are allowed
+    allow_container_operations: bool,
+
+    /// Bearer token callers must present, if any
+    token: Option<String>,
+
+    /// PEM certificate and key paths to serve TLS with, if any
+    tls: Option<(PathBuf, PathBuf)>,
+}
+
+/// Rejects requests without the bearer `token`, if one is required.
+fn check_token(token: Option<&str>, request: Request<()>) -> Result<Request<()>, Status> {
+    let Some(token) = token else {
+        return Ok(request);
+    };
+    let provided = request
+        .metadata()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    // Compare every byte so the time taken does not reveal the matching prefix
+    let matches = provided.is_some_and(|provided| {
+        provided.len() == token.len()
+            && provided.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    });
+    if matches {
+        Ok(request)
+    } else {
+        Err(Status::unauthenticated("Missing or invalid bearer token"))
+    }
+}
+
+impl AgentApi {
+    /// Creates the service; container operations are denied by default.
+    pub fn new(docker_monitor: Arc<DockerMonitor>, docker_service: Arc<DockerService>) -> Self {
+        Self {
+            docker_monitor,
+            docker_service,
+            app_state: Arc::new(AppStateStore::new()),
+            allow_container_operations: false,
+            token: None,
+            tls: None,
+        }
+    }
+
+    /// Requires callers to present `token` as a bearer token.
+    pub fn with_token(self, token: Option<String>) -> Self {
+        Self { token, ..self }
+    }
+
+    /// Serves TLS with the PEM certificate chain at `cert` and the private
+    /// key at `key`.
+    pub fn with_tls(self, cert: PathBuf, key: PathBuf) -> Self {
+        Self {
+            tls: Some((cert, key)),
+            ..self
+        }
+    }
+
+    /// Reports the agent from `app_state` instead of a default state.
+    pub fn with_app_state(self, app_state: Arc<AppStateStore>) -> Self {
+        Self { app_state, ..self }
+    }
+
+    /// Allows callers to start and stop containers.
+    pub fn with_container_operations(self, allow_container_operations: bool) -> Self {
+        Self {
+            allow_container_operations,
+            ..self
+        }
+    }
+
+    /// Rejects container operations unless they are enabled.
+    fn check_container_operations(&self) -> Result<(), Status> {
+        if self.allow_container_operations {
+            Ok(())
+        } else {
+            Err(Status::permission_denied(
+                "Container operations are disabled; set grpc.allow_container_operations",
+            ))
+        }
+    }
+
+    /// Serves the API on `addr` until `cancellation_token` is cancelled.
+    ///
+    /// Fails without serving if `addr` is not a loopback address and no token
+    /// or no TLS certificate is set.
+    pub async fn serve(self, addr: SocketAddr, cancellation_token: CancellationToken) -> AppResult<()> {
+        if !addr.ip().is_loopback() && (self.token.is_none() || self.tls.is_none()) {
+            return Err(AppError::Configuration(format!(
+                "Refusing to serve the gRPC API on non-loopback address {addr} without grpc.token, grpc.tls_cert and grpc.tls_key"
+            )));
+        }
+
+        let mut server = Server::builder();
+        if let Some((cert, key)) = &self.tls {
+            let identity = Identity::from_pem(tokio::fs::read(cert).await?, tokio::fs::read(key).await?);
+            server = server
+                .tls_config(ServerTlsConfig::new().identity(identity))
+                .map_err(|e| AppError::Configuration(format!("Invalid gRPC TLS certificate or key: {e}")))?;
+        }
+        if self.token.is_none() {
+            warn!("gRPC API on {} accepts calls without a token", addr);
+        }
+        info!("gRPC API listening on {}", addr);
+
+        let token = self.token.clone();
+        server
+            .add_service(AgentServiceServer::with_interceptor(self, move |request| {
+                check_token(token.as_deref(), request)
+            }))
+            .serve_with_shutdown(addr, async move { cancellation_token.cancelled().await })
+            .await
+            .map_err(|e| AppError::Network(format!("gRPC API on {addr} failed: {e}")))?;
+
+        info!("gRPC API received cancellation signal, shutting down");
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl AgentService for AgentApi {
+    async fn get_status(
+        &self,
+        _request: Request<proto::GetStatusRequest>,
+    ) -> Result<Response<proto::GetStatusResponse>, Status> {
+        let status = self.docker_monitor.get_current_status().await;
+        let app = self.app_state.get().await;
+        Ok(Response::new(proto::GetStatusResponse {
+            docker: Some(proto::DockerStatus::from(&status)),
+            agent_name: app.app_metadata.name,
+            agent_version: app.app_metadata.version,
+        }))
+    }
+
+    type WatchEventsStream = Pin<Box<dyn Stream<Item = Result<proto::DockerEvent, Status>> + Send + 'static>>;
+
+    async fn watch_events(
+        &self,
+        request: Request<proto::WatchEventsRequest>,
+    ) -> Result<Response<Self::WatchEventsStream>, Status> {
+        let event_types = request.into_inner().event_types;
+        let receiver = self.docker_service.subscribe_events();
+
+        let stream = futures::stream::unfold(receiver, move |mut receiver| {
+            let event_types = event_types.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) if event_types.is_empty() || event_types.contains(&event.event_type) => {
+                            return Some((Ok(proto::DockerEvent::from(event)), receiver));
+                        }
+                        Ok(_) => continue,
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!("gRPC event watcher lagged, {} events dropped", missed);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn list_containers(
+        &self,
+        request: Request<proto::ListContainersRequest>,
+    ) -> Result<Response<proto::ListContainersResponse>, Status> {
+        let containers = self
+            .docker_service
+            .list_containers(request.into_inner().all)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(proto::ListContainersResponse {
+            containers: containers.into_iter().map(proto::Container::from).collect(),
+        }))
+    }
+
+    async fn start_container(
+        &self,
+        request: Request<proto::StartContainerRequest>,
+    ) -> Result<Response<proto::ContainerOperationResponse>, Status> {
+        self.check_container_operations()?;
+        let id = request.into_inner().id;
+        self.docker_service.start_container(&id).await.map_err(to_status)?;
+        Ok(Response::new(proto::ContainerOperationResponse {}))
+    }
+
+    async fn stop_container(
+        &self,
+        request: Request<proto::StopContainerRequest>,
+    ) -> Result<Response<proto::ContainerOperationResponse>, Status> {
+        self.check_container_operations()?;
+        let request = request.into_inner();
+        let timeout = request
+            .timeout_secs
+            .map(|secs| Duration::from_secs(u64::from(secs)))
+            .unwrap_or(DEFAULT_STOP_TIMEOUT);
+        self.docker_service.stop_container(&request.id, timeout).await.map_err(to_status)?;
+        Ok(Response::new(proto::ContainerOperationResponse {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::EngineDetails;
+
+    #[test]
+    fn test_docker_status_conversion() {
+        let status = proto::DockerStatus::from(&DockerStatus::Running { version: "27.0.1".to_string(), engine: EngineDetails::default() });
+        assert_eq!(status.state, proto::docker_status::State::Running as i32);
+        assert_eq!(status.version, "27.0.1");
+
+        let status = proto::DockerStatus::from(&DockerStatus::Error { message: "boom".to_string(), retry_after: None });
+        assert_eq!(status.state, proto::docker_status::State::Error as i32);
+        assert_eq!(status.message, "boom");
+    }
+
+    #[tokio::test]
+    async fn test_container_operations_denied_by_default() {
+        let token = CancellationToken::new();
+        let api = AgentApi::new(
+            Arc::new(DockerMonitor::new(token.clone())),
+            Arc::new(DockerService::new(token)),
+        );
+        let result = api
+            .start_container(Request::new(proto::StartContainerRequest { id: "web".to_string() }))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[test]
+    fn test_check_token() {
+        let request = |authorization: Option<&str>| {
+            let mut request = Request::new(());
+            if let Some(authorization) = authorization {
+                request.metadata_mut().insert("authorization", authorization.parse().unwrap());
+            }
+            request
+        };
+
+        assert!(check_token(None, request(None)).is_ok());
+        assert!(check_token(Some("s3cret"), request(Some("Bearer s3cret"))).is_ok());
+        for authorization in [None, Some("Bearer wrong"), Some("Bearer s3cre"), Some("s3cret")] {
+            let status = check_token(Some("s3cret"), request(authorization)).unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_loopback_requires_token_and_tls() {
+        let token = CancellationToken::new();
+        let api = AgentApi::new(
+            Arc::new(DockerMonitor::new(token.clone())),
+            Arc::new(DockerService::new(token.clone())),
+        )
+        .with_token(Some("s3cret".to_string()));
+        let result = api.serve("0.0.0.0:0".parse().unwrap(), token).await;
+        assert!(matches!(result, Err(AppError::Configuration(_))));
+    }
+}
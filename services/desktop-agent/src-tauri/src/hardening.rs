@@ -0,0 +1,296 @@
+//! Job container hardening for RedSys Desktop Agent
+//!
+//! Jobs run code the provider did not write. Besides the defaults Docker
+//! applies to every container, a [`JobSecurity`] can confine a job container
+//! further with
+//! - a custom seccomp profile, read from a JSON file on the host
+//! - an AppArmor profile already loaded on the host
+//! - SELinux labels, e.g. `type:container_t` or `level:s0:c100,c200`
+//!
+//! The `[job_security]` config section is authoritative: its settings are
+//! the defaults for every job, and a job spec's `security` may only replace
+//! them with seccomp profiles, AppArmor profiles and SELinux labels the
+//! section allow-lists. Jobs asking for anything else are rejected when they
+//! are submitted. Whether the daemon
+//! supports seccomp, AppArmor and SELinux is read from the security options
+//! of `docker info`. A job that asks for a feature the host lacks fails
+//! before its container is created, while config defaults the host lacks are
+//! skipped with a warning, so one config can serve hosts with and without
+//! AppArmor or SELinux.
+//!
+//! Settings that remove confinement, such as `unconfined` profiles or
+//! `label=disable`, are rejected.
+//!
+//! ## References
+//! - [Docker seccomp profiles](https://docs.docker.com/engine/security/seccomp/)
+//! - [Docker AppArmor profiles](https://docs.docker.com/engine/security/apparmor/)
+//! - [Docker security options](https://docs.docker.com/reference/cli/docker/container/run/#security-opt)
+
+use tracing::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::docker_monitor::{DockerMonitorError, DockerMonitorResult};
+
+/// Profile name that turns seccomp or AppArmor off.
+const UNCONFINED: &str = "unconfined";
+
+/// Confinement settings of a job container.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobSecurity {
+    /// Host path of a JSON seccomp profile
+    pub seccomp_profile: Option<String>,
+
+    /// Name of an AppArmor profile loaded on the host
+    pub apparmor_profile: Option<String>,
+
+    /// SELinux labels in `<user|role|type|level>:<value>` form
+    pub selinux_labels: Vec<String>,
+}
+
+impl JobSecurity {
+    /// Rejects settings that remove confinement or are malformed.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(profile) = &self.seccomp_profile {
+            if profile.trim().is_empty() || profile == UNCONFINED {
+                return Err("seccomp_profile must be the path of a JSON profile".to_string());
+            }
+        }
+        if let Some(profile) = &self.apparmor_profile {
+            if profile.trim().is_empty() || profile == UNCONFINED {
+                return Err("apparmor_profile must name a loaded profile other than unconfined".to_string());
+            }
+        }
+        for label in &self.selinux_labels {
+            let valid = label
+                .split_once(':')
+                .is_some_and(|(key, value)| matches!(key, "user" | "role" | "type" | "level") && !value.is_empty());
+            if !valid {
+                return Err(format!("SELinux label '{label}' must be user:, role:, type: or level: followed by a value"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `[job_security]` config section: default confinement and what jobs
+/// may choose instead.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobSecurityConfig {
+    /// Confinement of jobs that do not set their own
+    #[serde(flatten)]
+    pub defaults: JobSecurity,
+
+    /// Seccomp profile paths jobs may choose
+    pub allowed_seccomp_profiles: Vec<String>,
+
+    /// AppArmor profiles jobs may choose
+    pub allowed_apparmor_profiles: Vec<String>,
+
+    /// SELinux labels jobs may choose
+    pub allowed_selinux_labels: Vec<String>,
+}
+
+impl JobSecurityConfig {
+    /// Rejects defaults and allow-list entries that remove confinement or are
+    /// malformed.
+    pub fn validate(&self) -> Result<(), String> {
+        self.defaults.validate()?;
+        for profile in &self.allowed_seccomp_profiles {
+            JobSecurity { seccomp_profile: Some(profile.clone()), ..Default::default() }.validate()?;
+        }
+        for profile in &self.allowed_apparmor_profiles {
+            JobSecurity { apparmor_profile: Some(profile.clone()), ..Default::default() }.validate()?;
+        }
+        JobSecurity { selinux_labels: self.allowed_selinux_labels.clone(), ..Default::default() }.validate()
+    }
+
+    /// Rejects job settings other than the defaults and the allow-listed
+    /// choices.
+    pub fn check(&self, job: &JobSecurity) -> Result<(), String> {
+        let permitted = |value: &String, default: Option<&String>, allowed: &[String]| {
+            default == Some(value) || allowed.contains(value)
+        };
+        if let Some(profile) = &job.seccomp_profile {
+            if !permitted(profile, self.defaults.seccomp_profile.as_ref(), &self.allowed_seccomp_profiles) {
+                return Err(format!("seccomp profile '{profile}' is not allowed by the provider"));
+            }
+        }
+        if let Some(profile) = &job.apparmor_profile {
+            if !permitted(profile, self.defaults.apparmor_profile.as_ref(), &self.allowed_apparmor_profiles) {
+                return Err(format!("AppArmor profile '{profile}' is not allowed by the provider"));
+            }
+        }
+        for label in &job.selinux_labels {
+            if !self.defaults.selinux_labels.contains(label) && !self.allowed_selinux_labels.contains(label) {
+                return Err(format!("SELinux label '{label}' is not allowed by the provider"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Confinement features the daemon supports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct HostSecurity {
+    /// Custom seccomp profiles
+    pub seccomp: bool,
+
+    /// AppArmor profiles
+    pub apparmor: bool,
+
+    /// SELinux labels
+    pub selinux: bool,
+}
+
+impl HostSecurity {
+    /// Reads the features from the `docker info` security options, e.g.
+    /// `name=seccomp,profile=builtin`.
+    pub fn from_security_options(options: &[String]) -> Self {
+        let has = |name: &str| {
+            options
+                .iter()
+                .any(|option| option.split(',').any(|part| part.strip_prefix("name=") == Some(name)))
+        };
+        Self {
+            seccomp: has("seccomp"),
+            apparmor: has("apparmor"),
+            selinux: has("selinux"),
+        }
+    }
+}
+
+/// Docker `SecurityOpt` entries for a job, from the job's own settings and
+/// the config `defaults`.
+///
+/// Fails with [`DockerMonitorError::ResourceLimit`] if the job asks for a
+/// feature the host does not support, and with
+/// [`DockerMonitorError::Internal`] if the seccomp profile cannot be read.
+///
+/// `job` must have passed [`JobSecurityConfig::check`].
+pub async fn security_options(
+    job: &JobSecurity,
+    defaults: &JobSecurity,
+    host: &HostSecurity,
+) -> DockerMonitorResult<Vec<String>> {
+    // Whether to apply a setting, failing for the job's own settings only
+    let applies = |from_job: bool, supported: bool, feature: &str| -> DockerMonitorResult<bool> {
+        match (supported, from_job) {
+            (true, _) => Ok(true),
+            (false, true) => Err(DockerMonitorError::ResourceLimit(format!(
+                "{feature} requested, the Docker daemon does not support {feature}"
+            ))),
+            (false, false) => {
+                warn!("Skipping the default {} settings, the Docker daemon does not support {}", feature, feature);
+                Ok(false)
+            }
+        }
+    };
+    let mut options = Vec::new();
+
+    let seccomp = job.seccomp_profile.as_ref().map(|path| (path, true));
+    if let Some((path, from_job)) = seccomp.or(defaults.seccomp_profile.as_ref().map(|path| (path, false))) {
+        if applies(from_job, host.seccomp, "seccomp")? {
+            let profile = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| DockerMonitorError::Internal(format!("Failed to read seccomp profile {path}: {e}")))?;
+            serde_json::from_str::<serde_json::Value>(&profile)
+                .map_err(|e| DockerMonitorError::Internal(format!("Seccomp profile {path} is not valid JSON: {e}")))?;
+            options.push(format!("seccomp={profile}"));
+        }
+    }
+
+    let apparmor = job.apparmor_profile.as_ref().map(|profile| (profile, true));
+    if let Some((profile, from_job)) = apparmor.or(defaults.apparmor_profile.as_ref().map(|profile| (profile, false))) {
+        if applies(from_job, host.apparmor, "AppArmor")? {
+            options.push(format!("apparmor={profile}"));
+        }
+    }
+
+    let (labels, from_job) = if job.selinux_labels.is_empty() {
+        (&defaults.selinux_labels, false)
+    } else {
+        (&job.selinux_labels, true)
+    };
+    if !labels.is_empty() && applies(from_job, host.selinux, "SELinux")? {
+        options.extend(labels.iter().map(|label| format!("label={label}")));
+    }
+
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_rejects_unconfined() {
+        let security = |seccomp: Option<&str>, apparmor: Option<&str>, label: &str| JobSecurity {
+            seccomp_profile: seccomp.map(str::to_string),
+            apparmor_profile: apparmor.map(str::to_string),
+            selinux_labels: vec![label.to_string()],
+        };
+        assert!(security(Some("/etc/redsys/seccomp.json"), Some("redsys-job"), "type:container_t").validate().is_ok());
+        assert!(security(Some("unconfined"), None, "type:container_t").validate().is_err());
+        assert!(security(None, Some("unconfined"), "type:container_t").validate().is_err());
+        assert!(security(None, None, "disable").validate().is_err());
+    }
+
+    #[test]
+    fn test_check_allows_defaults_and_allow_lists_only() {
+        let config = JobSecurityConfig {
+            defaults: JobSecurity {
+                seccomp_profile: Some("/etc/redsys/seccomp.json".to_string()),
+                apparmor_profile: Some("redsys-default".to_string()),
+                selinux_labels: vec!["type:container_t".to_string()],
+            },
+            allowed_apparmor_profiles: vec!["redsys-strict".to_string()],
+            ..Default::default()
+        };
+        let job = |seccomp: Option<&str>, apparmor: Option<&str>, labels: &[&str]| JobSecurity {
+            seccomp_profile: seccomp.map(str::to_string),
+            apparmor_profile: apparmor.map(str::to_string),
+            selinux_labels: labels.iter().map(|label| label.to_string()).collect(),
+        };
+
+        assert!(config.check(&JobSecurity::default()).is_ok());
+        assert!(config.check(&job(Some("/etc/redsys/seccomp.json"), Some("redsys-strict"), &["type:container_t"])).is_ok());
+        assert!(config.check(&job(Some("/home/user/seccomp.json"), None, &[])).is_err());
+        assert!(config.check(&job(None, Some("docker-default"), &[])).is_err());
+        assert!(config.check(&job(None, None, &["type:spc_t"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_security_options() {
+        let host = HostSecurity::from_security_options(&["name=apparmor".to_string(), "name=seccomp,profile=builtin".to_string()]);
+        assert_eq!(host, HostSecurity { seccomp: true, apparmor: true, selinux: false });
+
+        let defaults = JobSecurity {
+            apparmor_profile: Some("redsys-default".to_string()),
+            selinux_labels: vec!["type:container_t".to_string()],
+            ..Default::default()
+        };
+        // Unsupported defaults are skipped
+        assert_eq!(
+            security_options(&JobSecurity::default(), &defaults, &host).await.unwrap(),
+            vec!["apparmor=redsys-default".to_string()]
+        );
+
+        let job = JobSecurity {
+            apparmor_profile: Some("redsys-strict".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(security_options(&job, &defaults, &host).await.unwrap(), vec!["apparmor=redsys-strict".to_string()]);
+
+        // Unsupported job settings fail
+        let job = JobSecurity {
+            selinux_labels: vec!["level:s0:c100,c200".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            security_options(&job, &defaults, &host).await,
+            Err(DockerMonitorError::ResourceLimit(_))
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+//! Headless mode for RedSys Desktop Agent
+//!
+//! Providers running farm machines don't want a window per host. Started
+//! with `--headless`, the agent creates neither the main window nor the tray
+//! icon, while the Docker monitor, the backend connection, the job subsystem
+//! and the other background services run as usual. The Tauri event loop still
+//! runs, so Linux hosts without a display need a virtual one, e.g. `xvfb-run`.
+//!
+//! ## Output
+//! Status goes to stdout as JSON lines, one per bus event, and console logs
+//! move to stderr so the two don't mix:
+//!
+//! ```text
+//! {"event":"docker-status-changed","timestamp":"2025-01-01T12:00:00Z","payload":{...}}
+//! ```
+//!
+//! `event` and `payload` are the Tauri event name and payload the frontend
+//! receives; `correlation_id` is added when the event was published in a
+//! correlated span.
+
+use std::io::Write;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::events::{Bus, Envelope};
+
+/// Command-line flag that enables headless mode.
+pub const HEADLESS_ARG: &str = "--headless";
+
+/// Whether headless mode was requested on the command line.
+pub fn requested() -> bool {
+    std::env::args().any(|arg| arg == HEADLESS_ARG)
+}
+
+/// A bus event printed to stdout.
+#[derive(Debug, Serialize)]
+struct StatusLine {
+    /// Tauri event name
+    event: &'static str,
+
+    /// When the line was printed
+    timestamp: DateTime<Utc>,
+
+    /// Correlation ID of the publishing span
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<String>,
+
+    /// Tauri event payload
+    payload: serde_json::Value,
+}
+
+impl StatusLine {
+    fn from_envelope(envelope: Envelope) -> serde_json::Result<Self> {
+        Ok(Self {
+            event: envelope.event.tauri_name(),
+            timestamp: Utc::now(),
+            payload: envelope.event.payload()?,
+            correlation_id: envelope.correlation_id,
+        })
+    }
+}
+
+/// Prints every bus event to stdout as a JSON line until cancelled.
+pub async fn print_status_lines(bus: Arc<Bus>, cancellation_token: CancellationToken) {
+    info!("Printing status as JSON lines to stdout");
+    let mut events = bus.subscribe();
+    loop {
+        tokio::select! {
+            received = events.recv() => match received {
+                Ok(envelope) => {
+                    let name = envelope.event.tauri_name();
+                    let printed = StatusLine::from_envelope(envelope)
+                        .and_then(|line| serde_json::to_string(&line))
+                        .map_err(std::io::Error::from)
+                        .and_then(|line| {
+                            let mut stdout = std::io::stdout().lock();
+                            writeln!(stdout, "{line}")?;
+                            stdout.flush()
+                        });
+                    if let Err(e) = printed {
+                        error!("Failed to print {} status line: {e}", name);
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => warn!("Status output lagged, {} events dropped", missed),
+                Err(RecvError::Closed) => break,
+            },
+            _ = cancellation_token.cancelled() => {
+                info!("Status output received cancellation signal, shutting down");
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::DockerStatus;
+    use crate::events::Event;
+    use crate::types::DockerStatusChangedPayload;
+
+    #[test]
+    fn test_status_line_carries_tauri_event() {
+        let line = StatusLine::from_envelope(Envelope {
+            event: Event::DaemonStatus(DockerStatusChangedPayload::new(DockerStatus::Paused)),
+            correlation_id: None,
+        })
+        .unwrap();
+        let json = serde_json::to_value(&line).unwrap();
+
+        assert_eq!(json["event"], "docker-status-changed");
+        assert_eq!(json["payload"]["type"], "Paused");
+        assert!(json.get("correlation_id").is_none());
+    }
+}
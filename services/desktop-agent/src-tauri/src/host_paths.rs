@@ -0,0 +1,163 @@
+//! Host path access for RedSys Desktop Agent
+//!
+//! Bind mounts give a job container a directory of the provider's machine,
+//! and file transfers read and write host files. [`HostPaths`] confines both
+//! to the directories listed in `host_paths.allowed_roots`; with none listed,
+//! jobs get no bind mounts besides their workspace.
+//!
+//! Paths are canonicalized before they are checked, so `..` components and
+//! symlinks cannot lead out of a root. The Docker socket and every directory
+//! containing it are refused even below an allowed root, since access to the
+//! socket amounts to root access to the host.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::config::HostPathsConfig;
+use crate::diagnosis;
+use crate::docker_monitor::DockerMonitor;
+
+/// Socket paths of the Docker daemon on the host: the configured endpoint and
+/// the standard locations, canonicalized where they exist.
+pub(crate) fn docker_socket_paths() -> Vec<PathBuf> {
+    let configured = diagnosis::socket_path(DockerMonitor::docker_host().as_deref()).to_string();
+    let mut paths = Vec::new();
+    for path in [configured.as_str(), diagnosis::DEFAULT_SOCKET, "/run/docker.sock"] {
+        let path = PathBuf::from(path);
+        if let Ok(canonical) = path.canonicalize() {
+            paths.push(canonical);
+        }
+        paths.push(path);
+    }
+    paths.dedup();
+    paths
+}
+
+/// Whether `path` is one of the `sockets` or a directory containing one.
+pub(crate) fn exposes_socket(path: &Path, sockets: &[PathBuf]) -> bool {
+    sockets.iter().any(|socket| socket.starts_with(path))
+}
+
+/// Directories host paths must be inside of.
+#[derive(Debug, Clone, Default)]
+pub struct HostPaths {
+    /// Allowed root directories
+    roots: Vec<PathBuf>,
+}
+
+impl HostPaths {
+    /// Creates the allow list from the `[host_paths]` config section.
+    pub fn new(config: &HostPathsConfig) -> Self {
+        Self {
+            roots: config.allowed_roots.clone(),
+        }
+    }
+
+    /// Additionally allows paths below `root`, e.g. the job workspace root.
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.roots.push(root);
+        self
+    }
+
+    /// Resolves `path` to its canonical form if it lies inside an allowed
+    /// root. Blocking.
+    ///
+    /// A path that does not exist yet resolves through its parent, so a
+    /// copy can name its destination.
+    pub fn resolve(&self, path: &Path) -> Result<PathBuf, String> {
+        self.resolve_with(path, &docker_socket_paths())
+    }
+
+    fn resolve_with(&self, path: &Path, sockets: &[PathBuf]) -> Result<PathBuf, String> {
+        if !path.is_absolute() {
+            return Err(format!("Host path '{}' must be absolute", path.display()));
+        }
+        let unresolvable = |e: std::io::Error| format!("Cannot resolve host path '{}': {e}", path.display());
+        let resolved = match path.symlink_metadata() {
+            // Fails for dangling symlinks, which could be swapped for anything
+            Ok(_) => path.canonicalize().map_err(unresolvable)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => match (path.parent(), path.file_name()) {
+                (Some(parent), Some(name)) => parent.canonicalize().map_err(unresolvable)?.join(name),
+                _ => return Err(unresolvable(e)),
+            },
+            Err(e) => return Err(unresolvable(e)),
+        };
+
+        if exposes_socket(&resolved, sockets) {
+            return Err(format!("Host path '{}' exposes the Docker socket", path.display()));
+        }
+        let allowed = self
+            .roots
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .any(|root| resolved.starts_with(root));
+        if !allowed {
+            return Err(format!(
+                "Host path '{}' is outside the directories allowed by host_paths.allowed_roots",
+                path.display()
+            ));
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("redsys-host-paths-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("allowed").join("data")).unwrap();
+        std::fs::create_dir_all(root.join("other")).unwrap();
+        root.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_no_roots_allow_nothing() {
+        let root = temp_root("none");
+        assert!(HostPaths::default().resolve_with(&root.join("allowed"), &[]).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_stays_inside_roots() {
+        let root = temp_root("resolve");
+        let paths = HostPaths::default().with_root(root.join("allowed"));
+
+        assert_eq!(paths.resolve_with(&root.join("allowed/data"), &[]).unwrap(), root.join("allowed/data"));
+        // Destinations that do not exist yet
+        assert_eq!(paths.resolve_with(&root.join("allowed/new"), &[]).unwrap(), root.join("allowed/new"));
+        assert!(paths.resolve_with(&root.join("allowed/../other"), &[]).is_err());
+        assert!(paths.resolve_with(&root.join("allowed/data/../../other/new"), &[]).is_err());
+        assert!(paths.resolve_with(Path::new("allowed/data"), &[]).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_out_of_roots_rejected() {
+        let root = temp_root("symlink");
+        std::os::unix::fs::symlink(root.join("other"), root.join("allowed/escape")).unwrap();
+        std::os::unix::fs::symlink(root.join("missing"), root.join("allowed/dangling")).unwrap();
+        let paths = HostPaths::default().with_root(root.join("allowed"));
+
+        assert!(paths.resolve_with(&root.join("allowed/escape"), &[]).is_err());
+        assert!(paths.resolve_with(&root.join("allowed/dangling"), &[]).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_docker_socket_and_parents_rejected() {
+        let root = temp_root("socket");
+        let socket = root.join("allowed/data/docker.sock");
+        std::fs::write(&socket, "").unwrap();
+        let paths = HostPaths::default().with_root(root.clone());
+        let sockets = [socket.clone()];
+
+        assert!(paths.resolve_with(&socket, &sockets).is_err());
+        assert!(paths.resolve_with(&root.join("allowed/data"), &sockets).is_err());
+        assert!(paths.resolve_with(&root, &sockets).is_err());
+        assert!(paths.resolve_with(&root.join("other"), &sockets).is_ok());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
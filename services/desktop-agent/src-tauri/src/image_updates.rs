@@ -0,0 +1,230 @@
+//! Image update checks for RedSys Desktop Agent
+//!
+//! A provider that keeps running an old RedSys runner image finds out when a
+//! job fails on a version mismatch. [`ImageUpdateChecker`] compares the
+//! images listed in `image_updates.images` with their registries every
+//! `image_updates.check_interval_secs`, like `docker pull` would without
+//! downloading anything.
+//!
+//! An image is stale when the digest its tag points to in the registry is
+//! none of the digests the local image was pulled by. Images that are not
+//! present locally are skipped; the next job pulls them anyway. Registries
+//! are queried without credentials, so only public images can be checked.
+//!
+//! ## Events
+//! `image-update-available` carries an [`ImageUpdate`] once per new
+//! registry digest of a stale image.
+//!
+//! ## References
+//! - [Docker Distribution API](https://docs.docker.com/engine/api/v1.47/#tag/Distribution)
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::ImageUpdatesConfig;
+use crate::docker::DockerService;
+use crate::docker_monitor::{DockerMonitorError, DockerMonitorResult};
+use crate::pause::PauseControl;
+use crate::telemetry;
+
+/// Payload of the `image-update-available` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ImageUpdate {
+    /// Image reference from the configuration
+    pub image: String,
+
+    /// Digests the local image was pulled by
+    pub local_digests: Vec<String>,
+
+    /// Digest the reference points to in the registry
+    pub remote_digest: String,
+
+    /// When the update was found
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Digest part of `repository@digest` references.
+fn digests(repo_digests: &[String]) -> Vec<String> {
+    repo_digests
+        .iter()
+        .filter_map(|reference| reference.split_once('@'))
+        .map(|(_, digest)| digest.to_string())
+        .collect()
+}
+
+/// Periodically checks configured images for newer registry versions.
+#[derive(Debug)]
+pub struct ImageUpdateChecker {
+    /// Image references to check
+    images: Vec<String>,
+
+    /// Interval between checks
+    check_interval: Duration,
+
+    /// Available updates by image reference
+    updates: Mutex<HashMap<String, ImageUpdate>>,
+
+    /// Service whose daemon holds the local images
+    docker_service: Arc<DockerService>,
+
+    /// Shared monitoring pause switch
+    pause: Arc<PauseControl>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl ImageUpdateChecker {
+    /// Creates a checker from the `[image_updates]` config section.
+    pub fn new(
+        config: &ImageUpdatesConfig,
+        docker_service: Arc<DockerService>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Self {
+            images: config.images.clone(),
+            check_interval: Duration::from_secs(config.check_interval_secs),
+            updates: Mutex::new(HashMap::new()),
+            docker_service,
+            pause: Arc::new(PauseControl::new()),
+            cancellation_token,
+        }
+    }
+
+    /// Shares a monitoring pause switch; nothing is checked while it is
+    /// paused.
+    pub fn with_pause_control(self, pause: Arc<PauseControl>) -> Self {
+        Self { pause, ..self }
+    }
+
+    /// Gets the updates found by the latest checks.
+    pub fn updates(&self) -> Vec<ImageUpdate> {
+        let mut updates: Vec<ImageUpdate> = self.updates.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+        updates.sort_by(|a, b| a.image.cmp(&b.image));
+        updates
+    }
+
+    /// Compares one image with its registry.
+    ///
+    /// Returns the update, or `None` if the image is current or not present
+    /// locally.
+    async fn check_image(client: &Docker, image: &str) -> DockerMonitorResult<Option<ImageUpdate>> {
+        let local = match client.inspect_image(image).await {
+            Ok(inspect) => digests(&inspect.repo_digests.unwrap_or_default()),
+            Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => return Ok(None),
+            Err(e) => return Err(DockerMonitorError::api("Image inspection failed", e).await),
+        };
+        let remote = client
+            .inspect_registry_image(image, None)
+            .await
+            .map_err(|e| DockerMonitorError::Api(format!("Registry lookup of {image} failed: {e}")))?
+            .descriptor
+            .digest
+            .ok_or_else(|| DockerMonitorError::Api(format!("Registry returned no digest for {image}")))?;
+
+        // Images built or loaded locally have no digest to compare
+        if local.is_empty() || local.contains(&remote) {
+            return Ok(None);
+        }
+        Ok(Some(ImageUpdate {
+            image: image.to_string(),
+            local_digests: local,
+            remote_digest: remote,
+            checked_at: Utc::now(),
+        }))
+    }
+
+    /// Checks all configured images once.
+    ///
+    /// Returns the updates found for the first time; images that fail to be
+    /// checked are logged and keep their previous result.
+    pub async fn check(&self) -> DockerMonitorResult<Vec<ImageUpdate>> {
+        let client = self.docker_service.docker().await?;
+        let mut found = Vec::new();
+        for image in &self.images {
+            let update = match Self::check_image(&client, image).await {
+                Ok(update) => update,
+                Err(e) => {
+                    warn!("Update check of image {} failed: {}", image, e);
+                    continue;
+                }
+            };
+            let mut updates = self.updates.lock().unwrap_or_else(|e| e.into_inner());
+            match update {
+                Some(update) => {
+                    let known = updates.get(image).is_some_and(|known| known.remote_digest == update.remote_digest);
+                    if !known {
+                        found.push(update.clone());
+                    }
+                    updates.insert(image.clone(), update);
+                }
+                None => {
+                    updates.remove(image);
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Checks at startup and then every check interval until cancelled,
+    /// emitting an `image-update-available` event per new update.
+    pub async fn start(self: Arc<Self>, app_handle: tauri::AppHandle) {
+        info!(
+            "Starting update checks of {} image(s) every {}s",
+            self.images.len(),
+            self.check_interval.as_secs()
+        );
+        let mut ticker = interval(self.check_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if self.pause.is_paused() {
+                        debug!("Monitoring paused, skipping image update checks");
+                        continue;
+                    }
+                    match self.check().await {
+                        Ok(found) => {
+                            for update in found {
+                                info!("Update available for image {}: {}", update.image, update.remote_digest);
+                                if let Err(e) = telemetry::emit(&app_handle, "image-update-available", &update) {
+                                    error!("Failed to emit image-update-available event: {e}");
+                                }
+                            }
+                        }
+                        Err(DockerMonitorError::Connection(e)) => {
+                            debug!("Skipping image update checks, Docker unavailable: {e}");
+                        }
+                        Err(e) => warn!("Image update checks failed: {e}"),
+                    }
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Image update checks received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digests() {
+        let repo_digests = vec![
+            "ghcr.io/redsys/runner@sha256:aaa".to_string(),
+            "redsys/runner@sha256:bbb".to_string(),
+            "malformed".to_string(),
+        ];
+        assert_eq!(digests(&repo_digests), vec!["sha256:aaa".to_string(), "sha256:bbb".to_string()]);
+    }
+}
@@ -0,0 +1,882 @@
+//! Image management for RedSys Desktop Agent
+//!
+//! Providers whose network blocks registry pulls can build the RedSys runner
+//! image locally with [`build_image`], like `docker build`. The build context
+//! directory is archived into a temporary file and streamed to the daemon.
+//! `.dockerignore` files are not applied, so the context should only hold
+//! what the build needs. The daemon may be remote, so the context must lie
+//! inside the job workspace root or `host_paths.allowed_roots`.
+//!
+//! Builds run on the daemon's classic builder, since the BuildKit API needs
+//! a client session the agent does not provide. The image gets the first tag
+//! at build time and the others afterwards.
+//!
+//! Air-gapped providers sideload job images instead: [`export_image`] saves
+//! an image to a tar file like `docker save`, and [`import_image`] loads one
+//! like `docker load`. Loaded images are ordinary daemon images: jobs fall
+//! back to them when the pull fails, recording the pull error as the job's
+//! `pull_error`, and prune policies and disk usage reports cover them. Tar
+//! files must lie inside the job workspace root or `host_paths.allowed_roots`
+//! like [`transfer`] paths, and exports never overwrite an existing file.
+//!
+//! [`list_images`] lists the local images a page at a time, filtered and
+//! sorted with an [`ImageQuery`], see [`listing`](crate::listing).
+//!
+//! [`analyze_image`] breaks an image down into its layers, like `dive`. It
+//! reports the size of each layer, which layers other local images share, and
+//! the space wasted on files that a later layer overwrites or deletes. The
+//! image is saved to a temporary tar file to read its layers, which needs as
+//! much free disk space as the image takes.
+//!
+//! ## Progress
+//! The Tauri commands send progress to a channel passed by the caller, so
+//! only the window that started the operation receives it:
+//! - `build_image` sends a [`BuildProgress`] for every line of build output
+//! - `export_image` and `import_image` send an [`ImageTransferProgress`] for
+//!   every mebibyte saved or loaded and once more when the transfer is done
+//!
+//! ## References
+//! - [Docker Build API](https://docs.docker.com/engine/api/v1.47/#tag/Image/operation/ImageBuild)
+//! - [Docker Export Image API](https://docs.docker.com/engine/api/v1.47/#tag/Image/operation/ImageGet)
+//! - [OCI layer whiteouts](https://github.com/opencontainers/image-spec/blob/main/layer.md#whiteouts)
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::task;
+use tracing::{info, warn};
+use bollard::models::ImageSummary;
+use bollard::Docker;
+use bollard::query_parameters::{
+    BuildImageOptionsBuilder, ImportImageOptions, ListImagesOptions, ListImagesOptionsBuilder, TagImageOptionsBuilder,
+};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::docker_monitor::DockerMonitorError;
+use crate::error::{AppError, AppResult};
+use crate::host_paths::HostPaths;
+use crate::listing::{self, Page, PageRequest, SortOrder};
+use crate::transfer::{self, CHUNK_SIZE};
+
+/// Build output sent to the `build_image` progress channel.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct BuildProgress {
+    /// Build context directory on the host
+    pub context_path: String,
+
+    /// Line of build output, e.g. `Step 2/5 : RUN apk add curl`
+    pub message: String,
+}
+
+/// Direction of an image transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum ImageTransferDirection {
+    /// Saving an image to a tar file
+    Export,
+
+    /// Loading images from a tar file
+    Import,
+}
+
+/// Progress sent to the `export_image` and `import_image` progress channels.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ImageTransferProgress {
+    /// Tar file on the host
+    pub path: String,
+
+    /// Direction of the transfer
+    pub direction: ImageTransferDirection,
+
+    /// Bytes of the tar file transferred so far
+    pub transferred_bytes: u64,
+
+    /// Size of the tar file, unknown when exporting
+    pub total_bytes: Option<u64>,
+
+    /// Whether the transfer completed
+    pub done: bool,
+}
+
+/// Layer of an analyzed image.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct LayerInfo {
+    /// Digest of the uncompressed layer
+    pub diff_id: String,
+
+    /// Instruction that created the layer, e.g. `RUN apk add curl`
+    pub created_by: String,
+
+    /// Size of the files in the layer
+    pub size_bytes: u64,
+
+    /// Whether another local image uses the layer too
+    pub shared: bool,
+}
+
+/// Layer breakdown of an image.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ImageAnalysis {
+    /// Analyzed image reference
+    pub reference: String,
+
+    /// Image ID
+    pub image_id: String,
+
+    /// Layers from the base image up
+    pub layers: Vec<LayerInfo>,
+
+    /// Size of all layers
+    pub total_bytes: u64,
+
+    /// Size of the layers other local images share
+    pub shared_bytes: u64,
+
+    /// Size of files that a later layer overwrites or deletes
+    pub wasted_bytes: u64,
+
+    /// When the analysis completed
+    pub analyzed_at: DateTime<Utc>,
+}
+
+/// Local image summary for the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageInfo {
+    /// Image ID
+    pub id: String,
+
+    /// References the image is tagged with; empty for dangling images
+    pub tags: Vec<String>,
+
+    /// Size of the image including its base layers
+    pub size_bytes: u64,
+
+    /// Number of containers using the image, unknown unless the daemon
+    /// counted them
+    pub containers: Option<u64>,
+
+    /// Image labels
+    pub labels: HashMap<String, String>,
+
+    /// Creation time
+    pub created: Option<DateTime<Utc>>,
+}
+
+impl From<ImageSummary> for ImageInfo {
+    fn from(summary: ImageSummary) -> Self {
+        Self {
+            id: summary.id,
+            // Older daemons report dangling images as `<none>:<none>`
+            tags: summary.repo_tags.into_iter().filter(|tag| tag != "<none>:<none>").collect(),
+            size_bytes: u64::try_from(summary.size).unwrap_or_default(),
+            // -1 when not counted
+            containers: u64::try_from(summary.containers).ok(),
+            labels: summary.labels,
+            created: DateTime::from_timestamp(summary.created, 0),
+        }
+    }
+}
+
+/// Field to sort images by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageSort {
+    /// First tag, untagged images last
+    #[default]
+    Name,
+    Created,
+    Size,
+}
+
+/// Filters and sort of an image listing.
+///
+/// The dangling and label filters are evaluated by the daemon.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ImageQuery {
+    /// Only untagged images if `true`, only tagged ones if `false`
+    pub dangling: Option<bool>,
+
+    /// Label selectors (`key` or `key=value`) that must all match
+    pub labels: Vec<String>,
+
+    /// Case-insensitive substring of a tag or the image ID
+    pub name: Option<String>,
+
+    /// Field to sort by
+    pub sort: ImageSort,
+
+    /// Sort direction
+    pub order: SortOrder,
+}
+
+impl ImageQuery {
+    /// Builds the daemon-side filter map for `ListImagesOptions`.
+    pub fn to_docker_filters(&self) -> HashMap<String, Vec<String>> {
+        let mut filters = HashMap::new();
+        if let Some(dangling) = self.dangling {
+            filters.insert("dangling".to_string(), vec![dangling.to_string()]);
+        }
+        if !self.labels.is_empty() {
+            filters.insert("label".to_string(), self.labels.clone());
+        }
+        filters
+    }
+
+    /// Sorts `images` and drops the ones whose tags and ID do not match the
+    /// name filter.
+    pub fn select(&self, images: Vec<ImageInfo>) -> Vec<ImageInfo> {
+        let name = self.name.as_deref();
+        let mut images: Vec<ImageInfo> = images
+            .into_iter()
+            .filter(|image| {
+                listing::contains_ignore_case(&image.id, name)
+                    || image.tags.iter().any(|tag| listing::contains_ignore_case(tag, name))
+            })
+            .collect();
+        images.sort_by(|a, b| {
+            let ordering = match self.sort {
+                ImageSort::Name => (a.tags.is_empty(), a.tags.first()).cmp(&(b.tags.is_empty(), b.tags.first())),
+                ImageSort::Created => a.created.cmp(&b.created),
+                ImageSort::Size => a.size_bytes.cmp(&b.size_bytes),
+            };
+            // Ties are broken by ID so pages stay stable
+            self.order.apply(ordering.then_with(|| a.id.cmp(&b.id)))
+        });
+        images
+    }
+}
+
+/// Image entry of `manifest.json` in a saved image.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SavedManifest {
+    /// Path of the image config in the archive
+    config: String,
+
+    /// Paths of the layer tar files, base layer first
+    layers: Vec<String>,
+}
+
+/// Image config in a saved image, reduced to what the analysis needs.
+#[derive(Debug, Deserialize)]
+struct SavedConfig {
+    rootfs: SavedRootFs,
+
+    #[serde(default)]
+    history: Vec<SavedHistory>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SavedRootFs {
+    diff_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SavedHistory {
+    #[serde(default)]
+    created_by: String,
+
+    /// Set for instructions that did not create a layer, e.g. `ENV`
+    #[serde(default)]
+    empty_layer: bool,
+}
+
+/// Entry of a layer tar that affects the image filesystem.
+#[derive(Debug, Clone, PartialEq)]
+enum LayerEntry {
+    /// Regular file and its size
+    File(String, u64),
+
+    /// Whiteout deleting a path of the layers below
+    Whiteout(String),
+
+    /// Opaque whiteout hiding the contents of a directory in the layers below
+    Opaque(String),
+}
+
+/// Magic bytes starting a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Magic bytes starting a zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Reads the entries of a layer tar, which may be gzip or zstd compressed.
+/// Blocking.
+fn read_layer(reader: impl Read) -> std::io::Result<Vec<LayerEntry>> {
+    // Daemons using the containerd image store save layers as pulled, compressed
+    let mut reader = BufReader::new(reader);
+    let head = reader.fill_buf()?;
+    let (is_gzip, is_zstd) = (head.starts_with(&GZIP_MAGIC), head.starts_with(&ZSTD_MAGIC));
+    if is_gzip {
+        read_layer_entries(flate2::read::GzDecoder::new(reader))
+    } else if is_zstd {
+        read_layer_entries(zstd::stream::read::Decoder::with_buffer(reader)?)
+    } else {
+        read_layer_entries(reader)
+    }
+}
+
+/// Reads the entries of an uncompressed layer tar. Blocking.
+fn read_layer_entries(reader: impl Read) -> std::io::Result<Vec<LayerEntry>> {
+    let mut entries = Vec::new();
+    for entry in tar::Archive::new(reader).entries()? {
+        let entry = entry?;
+        let path = entry.path()?.to_string_lossy().trim_start_matches("./").trim_end_matches('/').to_string();
+        let (dir, name) = match path.rsplit_once('/') {
+            Some((dir, name)) => (format!("{dir}/"), name),
+            None => (String::new(), path.as_str()),
+        };
+        if name == ".wh..wh..opq" {
+            entries.push(LayerEntry::Opaque(dir));
+        } else if let Some(deleted) = name.strip_prefix(".wh.") {
+            entries.push(LayerEntry::Whiteout(format!("{dir}{deleted}")));
+        } else if entry.header().entry_type().is_file() {
+            entries.push(LayerEntry::File(path.clone(), entry.header().size()?));
+        }
+    }
+    Ok(entries)
+}
+
+/// Bytes of files in `layers` that a later layer overwrites or deletes.
+fn wasted_bytes(layers: &[Vec<LayerEntry>]) -> u64 {
+    // Removes the files under `prefix`, returning their size
+    fn remove_under(present: &mut BTreeMap<String, u64>, prefix: &str) -> u64 {
+        let removed: Vec<String> = present
+            .range(prefix.to_string()..)
+            .take_while(|(path, _)| path.starts_with(prefix))
+            .map(|(path, _)| path.clone())
+            .collect();
+        removed.iter().filter_map(|path| present.remove(path)).sum()
+    }
+
+    let mut present = BTreeMap::new();
+    let mut wasted = 0;
+    for layer in layers {
+        // Whiteouts only hide the layers below, whatever their position in the tar
+        let (files, whiteouts): (Vec<_>, Vec<_>) = layer.iter().partition(|entry| matches!(entry, LayerEntry::File(..)));
+        for entry in whiteouts.into_iter().chain(files) {
+            match entry {
+                LayerEntry::File(path, size) => wasted += present.insert(path.clone(), *size).unwrap_or(0),
+                LayerEntry::Whiteout(path) => {
+                    wasted += present.remove(path).unwrap_or(0);
+                    wasted += remove_under(&mut present, &format!("{path}/"));
+                }
+                LayerEntry::Opaque(dir) => wasted += remove_under(&mut present, dir),
+            }
+        }
+    }
+    wasted
+}
+
+/// Layer of an image saved with [`export_image`].
+#[derive(Debug)]
+struct SavedLayer {
+    diff_id: String,
+    created_by: String,
+    entries: Vec<LayerEntry>,
+}
+
+/// Layers of an image saved with [`export_image`], base layer first.
+/// Blocking.
+fn read_saved_image(archive: &Path) -> std::io::Result<Vec<SavedLayer>> {
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+    // The manifest naming the config and layer files may come last
+    let mut manifest = None;
+    for entry in tar::Archive::new(File::open(archive)?).entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_ref() == Path::new("manifest.json") {
+            let manifests: Vec<SavedManifest> = serde_json::from_reader(&mut entry)?;
+            manifest = manifests.into_iter().next();
+            break;
+        }
+    }
+    let manifest = manifest.ok_or_else(|| invalid("Saved image has no manifest".to_string()))?;
+
+    let mut config = None;
+    let mut layer_entries: HashMap<String, Vec<LayerEntry>> = HashMap::new();
+    for entry in tar::Archive::new(File::open(archive)?).entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        if path == manifest.config {
+            config = Some(serde_json::from_reader::<_, SavedConfig>(&mut entry)?);
+        } else if manifest.layers.contains(&path) {
+            layer_entries.insert(path, read_layer(&mut entry)?);
+        }
+    }
+    let config = config.ok_or_else(|| invalid(format!("Saved image has no config {}", manifest.config)))?;
+
+    let mut created_by = config.history.into_iter().filter(|step| !step.empty_layer).map(|step| step.created_by);
+    Ok(manifest
+        .layers
+        .iter()
+        .zip(config.rootfs.diff_ids)
+        .map(|(path, diff_id)| SavedLayer {
+            diff_id,
+            created_by: created_by.next().unwrap_or_default(),
+            entries: layer_entries.get(path).cloned().unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Splits an image reference into repository and tag; the registry port in
+/// `host:5000/image` is not a tag.
+fn split_reference(reference: &str) -> (&str, Option<&str>) {
+    match reference.rsplit_once(':') {
+        Some((repo, tag)) if !tag.contains('/') => (repo, Some(tag)),
+        _ => (reference, None),
+    }
+}
+
+/// Builds an image from a build context directory.
+///
+/// # Arguments
+///
+/// * `client` - Docker client, from [`DockerService::docker`](crate::docker::DockerService::docker)
+/// * `host_paths` - Directories the build context may be in
+/// * `context_path` - Build context directory
+/// * `dockerfile` - Dockerfile path relative to the context, `Dockerfile` if `None`
+/// * `tags` - References to tag the image with, e.g. `redsys/runner:local`
+/// * `progress` - Called with every line of build output
+///
+/// Returns the ID of the built image.
+pub async fn build_image(
+    client: &Docker,
+    host_paths: &HostPaths,
+    context_path: &Path,
+    dockerfile: Option<&str>,
+    tags: &[String],
+    progress: impl Fn(BuildProgress),
+) -> AppResult<String> {
+    let context_path = &transfer::resolve_host_path(host_paths, context_path).await?;
+    if !context_path.is_dir() {
+        return Err(AppError::NotFound { resource: context_path.display().to_string() });
+    }
+    if tags.iter().any(|tag| tag.trim().is_empty()) {
+        return Err(AppError::Configuration("Image tags must not be empty".to_string()));
+    }
+    info!("Building image from {}", context_path.display());
+
+    let archive = transfer::staging_path();
+    let written = task::spawn_blocking({
+        let (context_path, archive) = (context_path.to_path_buf(), archive.clone());
+        move || transfer::write_archive(&context_path, ".", &archive)
+    })
+    .await
+    .map_err(|e| AppError::Application(format!("Archiving task failed: {e}")));
+    let result = match written {
+        Ok(Ok(_)) => build(client, context_path, &archive, dockerfile, tags, progress).await,
+        Ok(Err(e)) => Err(e.into()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = tokio::fs::remove_file(&archive).await {
+        warn!("Failed to remove staged build context {}: {}", archive.display(), e);
+    }
+    result
+}
+
+/// Sends the staged build context `archive` to the daemon and follows the
+/// build.
+async fn build(
+    client: &Docker,
+    context_path: &Path,
+    archive: &Path,
+    dockerfile: Option<&str>,
+    tags: &[String],
+    progress: impl Fn(BuildProgress),
+) -> AppResult<String> {
+    let context = transfer::file_chunks(tokio::fs::File::open(archive).await?, |_| {});
+
+    let mut options = BuildImageOptionsBuilder::new()
+        .dockerfile(dockerfile.unwrap_or("Dockerfile"))
+        .rm(true);
+    if let Some(tag) = tags.first() {
+        options = options.t(tag);
+    }
+    let mut build = client.build_image(options.build(), None, Some(bollard::body_stream(context))).boxed();
+
+    let mut image_id = None;
+    while let Some(info) = build.next().await {
+        let info = info.map_err(|e| DockerMonitorError::Api(format!("Image build failed: {e}")))?;
+        if let Some(message) = info.error_detail.and_then(|detail| detail.message) {
+            return Err(DockerMonitorError::Api(format!("Image build failed: {message}")).into());
+        }
+        let message = info.stream.or(info.status).unwrap_or_default();
+        if !message.trim().is_empty() {
+            progress(BuildProgress {
+                context_path: context_path.display().to_string(),
+                message: message.trim_end().to_string(),
+            });
+        }
+        if let Some(id) = info.aux.and_then(|aux| aux.id) {
+            image_id = Some(id);
+        }
+    }
+    let image_id = image_id.ok_or_else(|| DockerMonitorError::Api("Image build finished without an image ID".to_string()))?;
+
+    for tag in tags.iter().skip(1) {
+        let (repo, tag) = split_reference(tag);
+        let options = TagImageOptionsBuilder::new().repo(repo).tag(tag.unwrap_or("latest")).build();
+        client
+            .tag_image(&image_id, Some(options))
+            .await
+            .map_err(|e| DockerMonitorError::Api(format!("Failed to tag image {image_id} as {repo}: {e}")))?;
+    }
+    info!("Built image {}", image_id);
+    Ok(image_id)
+}
+
+/// Saves an image to a tar file that [`import_image`] can load.
+///
+/// # Arguments
+///
+/// * `client` - Docker client, from [`DockerService::docker`](crate::docker::DockerService::docker)
+/// * `host_paths` - Directories the tar file may be written to
+/// * `reference` - Image name, tag or ID
+/// * `path` - Tar file to write, which must not exist yet
+/// * `progress` - Called with the progress of the transfer
+///
+/// Returns the size of the tar file.
+pub async fn export_image(
+    client: &Docker,
+    host_paths: &HostPaths,
+    reference: &str,
+    path: &Path,
+    progress: impl Fn(ImageTransferProgress),
+) -> AppResult<u64> {
+    let path = transfer::resolve_host_path(host_paths, path).await?;
+    save_image(client, reference, &path, progress).await
+}
+
+/// Saves an image to the tar file `path`, which must not exist yet.
+async fn save_image(
+    client: &Docker,
+    reference: &str,
+    path: &Path,
+    progress: impl Fn(ImageTransferProgress),
+) -> AppResult<u64> {
+    info!("Exporting image {} to {}", reference, path.display());
+    let report = |transferred_bytes, done| {
+        progress(ImageTransferProgress {
+            path: path.display().to_string(),
+            direction: ImageTransferDirection::Export,
+            transferred_bytes,
+            total_bytes: None,
+            done,
+        })
+    };
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => AppError::InvalidState(format!("{} already exists", path.display())),
+            _ => e.into(),
+        })?;
+    let mut chunks = client.export_image(reference).boxed();
+    let mut written = 0u64;
+    let result: AppResult<()> = async {
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.map_err(|e| DockerMonitorError::Api(format!("Failed to export image {reference}: {e}")))?;
+            file.write_all(&chunk).await?;
+            let previous = written;
+            written += chunk.len() as u64;
+            if previous / CHUNK_SIZE as u64 != written / CHUNK_SIZE as u64 {
+                report(written, false);
+            }
+        }
+        Ok(file.flush().await?)
+    }
+    .await;
+
+    if let Err(e) = result {
+        drop(file);
+        if let Err(e) = tokio::fs::remove_file(path).await {
+            warn!("Failed to remove partial image export {}: {}", path.display(), e);
+        }
+        return Err(e);
+    }
+    report(written, true);
+    Ok(written)
+}
+
+/// Loads the images in a tar file written by [`export_image`] or
+/// `docker save`.
+///
+/// # Arguments
+///
+/// * `client` - Docker client, from [`DockerService::docker`](crate::docker::DockerService::docker)
+/// * `host_paths` - Directories the tar file may be read from
+/// * `path` - Tar file to load
+/// * `progress` - Called with the progress of the transfer
+///
+/// Returns the references of the loaded images, or their IDs for untagged
+/// ones.
+pub async fn import_image(
+    client: &Docker,
+    host_paths: &HostPaths,
+    path: &Path,
+    progress: impl Fn(ImageTransferProgress) + Send + Sync + 'static,
+) -> AppResult<Vec<String>> {
+    let path = &transfer::resolve_host_path(host_paths, path).await?;
+    let file = tokio::fs::File::open(path).await.map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::NotFound { resource: path.display().to_string() },
+        _ => e.into(),
+    })?;
+    let total = file.metadata().await?.len();
+    info!("Importing images from {}", path.display());
+
+    let report = {
+        let path = path.display().to_string();
+        Arc::new(move |transferred_bytes, done| {
+            progress(ImageTransferProgress {
+                path: path.clone(),
+                direction: ImageTransferDirection::Import,
+                transferred_bytes,
+                total_bytes: Some(total),
+                done,
+            })
+        })
+    };
+    let chunk_report = report.clone();
+    let archive = transfer::file_chunks(file, move |sent| chunk_report(sent, false));
+
+    let mut responses = client.import_image(ImportImageOptions::default(), bollard::body_stream(archive), None).boxed();
+    let mut loaded = Vec::new();
+    while let Some(info) = responses.next().await {
+        let info = info.map_err(|e| DockerMonitorError::Api(format!("Failed to import images: {e}")))?;
+        if let Some(message) = info.error_detail.and_then(|detail| detail.message) {
+            return Err(DockerMonitorError::Api(format!("Failed to import images: {message}")).into());
+        }
+        if let Some(reference) = info.stream.as_deref().and_then(loaded_reference) {
+            loaded.push(reference.to_string());
+        }
+    }
+    info!("Imported {} image(s): {}", loaded.len(), loaded.join(", "));
+    report(total, true);
+    Ok(loaded)
+}
+
+/// Image named by a `docker load` output line, e.g.
+/// `Loaded image: alpine:3.20` or `Loaded image ID: sha256:...`.
+fn loaded_reference(line: &str) -> Option<&str> {
+    let line = line.trim();
+    line.strip_prefix("Loaded image ID: ")
+        .or_else(|| line.strip_prefix("Loaded image: "))
+        .map(str::trim)
+}
+
+/// Lists a page of the local images matching `query`.
+///
+/// # Arguments
+///
+/// * `client` - Docker client, from [`DockerService::docker`](crate::docker::DockerService::docker)
+/// * `query` - Filters and sort
+/// * `page` - Window of the sorted images to return
+pub async fn list_images(client: &Docker, query: &ImageQuery, page: PageRequest) -> AppResult<Page<ImageInfo>> {
+    listing::validate_labels(&query.labels)?;
+    page.validate()?;
+    let options = ListImagesOptionsBuilder::new().filters(&query.to_docker_filters()).build();
+    let images = match client.list_images(Some(options)).await {
+        Ok(images) => images,
+        Err(e) => return Err(DockerMonitorError::api("Image listing failed", e).await.into()),
+    };
+
+    let images = images.into_iter().map(ImageInfo::from).collect();
+    Ok(Page::of(query.select(images), page))
+}
+
+/// Analyzes the layers of a local image.
+///
+/// # Arguments
+///
+/// * `client` - Docker client, from [`DockerService::docker`](crate::docker::DockerService::docker)
+/// * `reference` - Image name, tag or ID
+///
+/// Returns the size of each layer, the layers shared with other local images
+/// and the space wasted on overwritten or deleted files.
+pub async fn analyze_image(client: &Docker, reference: &str) -> AppResult<ImageAnalysis> {
+    let image_id = match client.inspect_image(reference).await {
+        Ok(inspect) => inspect.id.unwrap_or_default(),
+        Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => {
+            return Err(AppError::NotFound { resource: format!("image '{reference}'") });
+        }
+        Err(e) => return Err(DockerMonitorError::api("Image inspection failed", e).await.into()),
+    };
+
+    // Layers of the other local images
+    let mut other_layers = HashSet::new();
+    let images = match client.list_images(None::<ListImagesOptions>).await {
+        Ok(images) => images,
+        Err(e) => return Err(DockerMonitorError::api("Image listing failed", e).await.into()),
+    };
+    for image in images.iter().filter(|image| image.id != image_id) {
+        match client.inspect_image(&image.id).await {
+            Ok(inspect) => other_layers.extend(inspect.root_fs.and_then(|root_fs| root_fs.layers).unwrap_or_default()),
+            Err(e) => warn!("Failed to inspect image {}: {}", image.id, e),
+        }
+    }
+
+    let archive = transfer::staging_path();
+    let saved = match save_image(client, &image_id, &archive, |_| {}).await {
+        Ok(_) => task::spawn_blocking({
+            let archive = archive.clone();
+            move || read_saved_image(&archive)
+        })
+        .await
+        .map_err(|e| AppError::Application(format!("Image analysis task failed: {e}"))),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = tokio::fs::remove_file(&archive).await {
+        warn!("Failed to remove staged image {}: {}", archive.display(), e);
+    }
+    let saved = saved??;
+
+    let wasted_bytes = wasted_bytes(&saved.iter().map(|layer| layer.entries.clone()).collect::<Vec<_>>());
+    let layers: Vec<LayerInfo> = saved
+        .into_iter()
+        .map(|layer| LayerInfo {
+            size_bytes: layer
+                .entries
+                .iter()
+                .map(|entry| match entry {
+                    LayerEntry::File(_, size) => *size,
+                    _ => 0,
+                })
+                .sum(),
+            shared: other_layers.contains(&layer.diff_id),
+            diff_id: layer.diff_id,
+            created_by: layer.created_by,
+        })
+        .collect();
+    Ok(ImageAnalysis {
+        reference: reference.to_string(),
+        image_id,
+        total_bytes: layers.iter().map(|layer| layer.size_bytes).sum(),
+        shared_bytes: layers.iter().filter(|layer| layer.shared).map(|layer| layer.size_bytes).sum(),
+        wasted_bytes,
+        layers,
+        analyzed_at: Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_reference() {
+        assert_eq!(split_reference("redsys/runner:local"), ("redsys/runner", Some("local")));
+        assert_eq!(split_reference("redsys/runner"), ("redsys/runner", None));
+        assert_eq!(split_reference("localhost:5000/runner"), ("localhost:5000/runner", None));
+        assert_eq!(split_reference("localhost:5000/runner:1.0"), ("localhost:5000/runner", Some("1.0")));
+    }
+
+    #[test]
+    fn test_loaded_reference() {
+        assert_eq!(loaded_reference("Loaded image: alpine:3.20\n"), Some("alpine:3.20"));
+        assert_eq!(loaded_reference("Loaded image ID: sha256:1234\n"), Some("sha256:1234"));
+        assert_eq!(loaded_reference("Loading layer 1.2MB/3.4MB"), None);
+    }
+
+    #[test]
+    fn test_image_query_select() {
+        let image = |id: &str, tags: &[&str], size| {
+            ImageInfo::from(ImageSummary {
+                id: id.to_string(),
+                repo_tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                size,
+                containers: -1,
+                ..Default::default()
+            })
+        };
+        let images = vec![
+            image("sha256:c", &["<none>:<none>"], 10),
+            image("sha256:b", &["redsys/runner:latest"], 300),
+            image("sha256:a", &["alpine:3.20"], 8),
+        ];
+        assert!(images[0].tags.is_empty());
+        assert_eq!(images[0].containers, None);
+
+        let by_name: Vec<String> = ImageQuery::default().select(images.clone()).into_iter().map(|image| image.id).collect();
+        assert_eq!(by_name, ["sha256:a", "sha256:b", "sha256:c"]);
+
+        let query = ImageQuery {
+            name: Some("Runner".to_string()),
+            sort: ImageSort::Size,
+            order: SortOrder::Descending,
+            ..Default::default()
+        };
+        assert_eq!(query.select(images)[0].id, "sha256:b");
+        assert_eq!(ImageQuery { dangling: Some(true), ..Default::default() }.to_docker_filters()["dangling"], ["true"]);
+    }
+
+    #[test]
+    fn test_wasted_bytes_counts_overwritten_and_deleted_files() {
+        let file = |path: &str, size| LayerEntry::File(path.to_string(), size);
+        let layers = vec![
+            vec![file("etc/config", 10), file("var/cache/apk/index", 100), file("var/cache/apk/pkg", 50)],
+            vec![file("etc/config", 12), LayerEntry::Whiteout("var/cache/apk".to_string()), file("tmp/build/out", 7)],
+            vec![file("tmp/kept", 1), LayerEntry::Opaque("tmp/".to_string())],
+        ];
+        assert_eq!(wasted_bytes(&layers), 10 + 100 + 50 + 7);
+    }
+
+    #[test]
+    fn test_read_compressed_layers() {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in [("etc/config", "0123456789"), ("var/cache/.wh.apk", "")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, contents.as_bytes()).unwrap();
+        }
+        let layer = builder.into_inner().unwrap();
+        let expected = vec![
+            LayerEntry::File("etc/config".to_string(), 10),
+            LayerEntry::Whiteout("var/cache/apk".to_string()),
+        ];
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        std::io::Write::write_all(&mut gzip, &layer).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let zstd = zstd::encode_all(layer.as_slice(), 0).unwrap();
+
+        assert_eq!(read_layer(layer.as_slice()).unwrap(), expected);
+        assert_eq!(read_layer(gzip.as_slice()).unwrap(), expected);
+        assert_eq!(read_layer(zstd.as_slice()).unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_export_confined_and_never_overwrites() {
+        let root = std::env::temp_dir().join(format!("redsys-image-export-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let root = root.canonicalize().unwrap();
+        let existing = root.join("existing.tar");
+        std::fs::write(&existing, "keep").unwrap();
+        // Never reached: both exports fail before talking to the daemon
+        let client = Docker::connect_with_http("http://127.0.0.1:9", 1, bollard::API_DEFAULT_VERSION).unwrap();
+
+        let outside = export_image(&client, &HostPaths::default(), "alpine", &root.join("new.tar"), |_| {}).await;
+        assert!(matches!(outside, Err(AppError::Permission(_))));
+        let paths = HostPaths::default().with_root(root.clone());
+        let replaced = export_image(&client, &paths, "alpine", &existing, |_| {}).await;
+        assert!(matches!(replaced, Err(AppError::InvalidState(_))));
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "keep");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
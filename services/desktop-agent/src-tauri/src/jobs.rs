@@ -0,0 +1,1444 @@
+//! Job execution for RedSys Desktop Agent
+//!
+//! Runs RedSys jobs as Docker containers on the provider's machine. A job
+//! spec names an image, command, environment, bind mounts and resource
+//! limits; the agent pulls the image, creates a labelled container, starts
+//! it and follows it to completion.
+//!
+//! ## Lifecycle
+//! `Queued` → `Pulling` → `Starting` → `Running` → `Succeeded` | `Failed`
+//!
+//! - Completion is detected from the container `die` event of the Docker
+//!   events stream, with a periodic inspect as a fallback for missed events
+//! - The exit code and the last [`MAX_LOG_LINES`] lines of output are
+//!   captured, then the container is removed
+//! - Failed exits are classified as OOM kills, signals or non-zero exits,
+//!   recorded in the job and reported as `container-failed`, see
+//!   [`failures`](crate::failures)
+//! - Every transition publishes `job-state-changed` to the
+//!   [`Bus`](crate::events::Bus)
+//! - With a [`WorkspaceManager`], each job gets a workspace mounted at
+//!   `/workspace` that is kept for the retention window after it finishes
+//! - Bind mount sources must lie inside the [`HostPaths`] allowed by the
+//!   provider, and are canonicalized when the job is accepted
+//! - Requested host ports that are already taken are reported as
+//!   `port-conflict` warnings before the image is pulled, see
+//!   [`ports`](crate::ports)
+//!
+//! ## Image Policy
+//! Submitted jobs whose image the [`ImagePolicy`] rejects fail with
+//! [`AppError::PolicyViolation`] before they are accepted. With a
+//! [`SignatureVerifier`], pulled images are verified before their container
+//! is created from the verified digest, and the result is recorded in the
+//! [`JobRecord`], see [`signatures`](crate::signatures).
+//!
+//! ## Hardening
+//! Seccomp profiles, AppArmor profiles and SELinux labels from the
+//! `[job_security]` defaults, or those the spec's `security` picks from its
+//! allow-lists, are applied as container security options, see
+//! [`hardening`](crate::hardening).
+//!
+//! ## Services
+//! A spec with a `service` policy runs a long-running service instead of a
+//! one-shot job: its container is started again with exponential backoff
+//! whenever it exits with a non-zero code, until the restart budget is
+//! spent, and `service-flapping` is emitted when it keeps dying, see
+//! [`restarts`](crate::restarts).
+//!
+//! ## Cancellation
+//! [`JobManager::cancel`] stops the container of a job with a grace period,
+//! or keeps it from starting, and the job ends [`JobState::Cancelled`]. A job
+//! container stopped or killed by the user or the Docker daemon, seen as a
+//! `kill` event, ends the job the same way instead of being restarted.
+//! [`JobManager::enforce_workspace_quota`] stops jobs the same way when their
+//! workspaces exceed the quota, but fails them.
+//!
+//! ## Resource Limits
+//! CPU quota and shares, memory, PID and GPU limits from the spec are applied
+//! to the container. Specs asking for more CPUs, memory or GPUs than the host
+//! has fail with [`DockerMonitorError::ResourceLimit`] before anything is
+//! pulled.
+//!
+//! Job containers carry `redsys.managed=true`, `redsys.job-id=<id>` and
+//! `redsys.agent-version` labels, plus a `redsys.job.<key>` label per entry
+//! of the spec's `metadata`, so prune policies, cleanup and operators can
+//! tell them apart from the user's other containers.
+//!
+//! ## References
+//! - [Docker Create Container](https://docs.docker.com/engine/api/v1.47/#tag/Container/operation/ContainerCreate)
+//! - [Bollard Container API](https://docs.rs/bollard/latest/bollard/struct.Docker.html#method.create_container)
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{broadcast::{self, error::RecvError}, RwLock};
+use tokio::{task, time::{interval, sleep, Duration, Instant}};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, info_span, warn, Instrument};
+use bollard::models::{ContainerCreateBody, DeviceRequest, HostConfig, Mount, MountTypeEnum, PortBinding};
+use bollard::query_parameters::{
+    CreateContainerOptionsBuilder, CreateImageOptionsBuilder, InspectContainerOptions, LogsOptionsBuilder,
+    RemoveContainerOptionsBuilder, StartContainerOptions, StopContainerOptionsBuilder,
+};
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::docker::{DockerEvent, DockerService, LimitSupport, REDSYS_MANAGED_LABEL};
+use crate::docker_monitor::{DockerMonitorError, DockerMonitorResult};
+use crate::error::{AppError, AppResult};
+use crate::events::{Bus, Event, JobEvent};
+use crate::failures::{ContainerFailed, ExitFailure};
+use crate::gpu::{self, GpuInfo, GpuRuntime};
+use crate::hardening::{self, HostSecurity, JobSecurity, JobSecurityConfig};
+use crate::host_paths::HostPaths;
+use crate::notifications::Notifier;
+use crate::policy::ImagePolicy;
+use crate::ports::{self, JobPort};
+use crate::restarts::{RestartDecision, RestartTracker, ServiceFlapping, ServicePolicy};
+use crate::signatures::{SignatureVerification, SignatureVerifier};
+use crate::telemetry;
+use crate::workspace::{WorkspaceManager, WORKSPACE_MOUNT_TARGET};
+
+/// Label carrying the job ID on job containers.
+pub const REDSYS_JOB_ID_LABEL: &str = "redsys.job-id";
+
+/// Label carrying the version of the agent that created a job container.
+pub const REDSYS_AGENT_VERSION_LABEL: &str = "redsys.agent-version";
+
+/// Prefix of the labels carrying the job metadata.
+pub const REDSYS_JOB_LABEL_PREFIX: &str = "redsys.job.";
+
+/// Number of trailing log lines captured when a job finishes.
+pub const MAX_LOG_LINES: usize = 1000;
+
+/// Finished jobs kept for inspection before the oldest are dropped.
+const MAX_FINISHED_JOBS: usize = 100;
+
+/// Interval of the inspect fallback while waiting for a job to exit.
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Seconds a cancelled job's container gets to exit before Docker kills it.
+const CANCEL_GRACE_SECS: i32 = 10;
+
+/// Interval between workspace quota checks of running jobs.
+const QUOTA_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Smallest memory limit accepted by the Docker Engine.
+const MIN_MEMORY_BYTES: i64 = 6 * 1024 * 1024;
+
+/// Smallest CPU shares value accepted by the Docker Engine.
+const MIN_CPU_SHARES: i64 = 2;
+
+/// Bind mount of a host path into the job container.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobMount {
+    /// Absolute host path inside `host_paths.allowed_roots`
+    pub source: String,
+
+    /// Absolute path inside the container
+    pub target: String,
+
+    /// Mount read-only
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// GPUs requested for a job container.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpuRequest {
+    /// Number of GPUs, `-1` for all; ignored when `device_ids` is set
+    pub count: Option<i64>,
+
+    /// Specific GPU indices or UUIDs
+    pub device_ids: Vec<String>,
+}
+
+impl GpuRequest {
+    /// Number of GPUs requested, `None` for all of them.
+    fn requested(&self) -> Option<i64> {
+        if !self.device_ids.is_empty() {
+            return Some(self.device_ids.len() as i64);
+        }
+        match self.count {
+            Some(-1) => None,
+            Some(count) => Some(count),
+            None => Some(1),
+        }
+    }
+
+    /// Builds the NVIDIA device request.
+    fn to_device_request(&self) -> DeviceRequest {
+        let (count, device_ids) = if self.device_ids.is_empty() {
+            (Some(self.count.unwrap_or(1)), None)
+        } else {
+            (None, Some(self.device_ids.clone()))
+        };
+        DeviceRequest {
+            driver: Some("nvidia".to_string()),
+            count,
+            device_ids,
+            capabilities: Some(vec![vec!["gpu".to_string()]]),
+            ..Default::default()
+        }
+    }
+}
+
+/// Resource limits of a job container.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// CPU quota in cores, e.g. `1.5`
+    pub cpus: Option<f64>,
+
+    /// Relative CPU weight under contention (Docker default 1024)
+    pub cpu_shares: Option<i64>,
+
+    /// Memory limit in bytes
+    pub memory_bytes: Option<i64>,
+
+    /// Maximum number of processes
+    pub pids_limit: Option<i64>,
+
+    /// GPU devices
+    pub gpus: Option<GpuRequest>,
+}
+
+/// Resources the host can offer to jobs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct HostCapacity {
+    /// Logical CPUs
+    pub cpus: i64,
+
+    /// Total memory in bytes
+    pub memory_bytes: i64,
+
+    /// GPUs, `None` if unknown
+    pub gpus: Option<i64>,
+
+    /// Confinement features the daemon supports
+    pub security: HostSecurity,
+
+    /// Resource limits the host can enforce
+    pub limit_support: LimitSupport,
+}
+
+impl HostCapacity {
+    /// Reads the CPU, memory, limit enforcement and confinement capabilities
+    /// reported by the Docker daemon and the locally detected GPUs.
+    pub async fn detect(client: &Docker) -> DockerMonitorResult<Self> {
+        let info = client
+            .info()
+            .await
+            .map_err(|e| DockerMonitorError::Api(format!("Failed to read host capacity: {e}")))?;
+        let gpu = task::spawn_blocking(gpu::detect).await.unwrap_or_else(|_| GpuInfo::none());
+        let runtimes: Vec<String> = info.runtimes.unwrap_or_default().into_keys().collect();
+        let toolkit_installed = task::spawn_blocking(gpu::container_toolkit_installed).await.unwrap_or(false);
+        let gpu_runtime = GpuRuntime::evaluate(&gpu, &runtimes, toolkit_installed);
+        Ok(Self {
+            cpus: info.ncpu.unwrap_or_default(),
+            memory_bytes: info.mem_total.unwrap_or_default(),
+            // GPUs only count when Docker can hand them to containers
+            gpus: Some(if gpu_runtime.available { gpu.nvidia_device_count() as i64 } else { 0 }),
+            limit_support: LimitSupport::from_info(&info),
+            security: HostSecurity::from_security_options(&info.security_options.unwrap_or_default()),
+        })
+    }
+}
+
+impl ResourceLimits {
+    /// Rejects limits that ask for more than the host provides or that the
+    /// host cannot enforce.
+    pub fn check_capacity(&self, host: &HostCapacity) -> DockerMonitorResult<()> {
+        self.check_enforceable(&host.limit_support)?;
+        if let Some(cpus) = self.cpus {
+            if host.cpus > 0 && cpus > host.cpus as f64 {
+                return Err(DockerMonitorError::ResourceLimit(format!(
+                    "{cpus} CPUs requested, host has {}", host.cpus
+                )));
+            }
+        }
+        if let Some(memory) = self.memory_bytes {
+            if host.memory_bytes > 0 && memory > host.memory_bytes {
+                return Err(DockerMonitorError::ResourceLimit(format!(
+                    "{memory} bytes of memory requested, host has {}", host.memory_bytes
+                )));
+            }
+        }
+        if let (Some(gpus), Some(available)) = (&self.gpus, host.gpus) {
+            let requested = gpus.requested().unwrap_or(available.max(1));
+            if requested > available {
+                return Err(DockerMonitorError::ResourceLimit(format!(
+                    "{requested} GPUs requested, host has {available}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects limits the host cannot enforce, which Docker would otherwise
+    /// refuse when creating the container or silently ignore.
+    fn check_enforceable(&self, support: &LimitSupport) -> DockerMonitorResult<()> {
+        let cgroups = support.cgroup_version.map_or("cgroups".to_string(), |version| format!("cgroup v{version}"));
+        let unsupported = [
+            ("CPU", self.cpus.is_some() && !support.cpu_quota),
+            ("CPU share", self.cpu_shares.is_some() && !support.cpu_shares),
+            ("memory", self.memory_bytes.is_some() && !support.memory),
+            ("process", self.pids_limit.is_some() && !support.pids),
+        ];
+        if let Some((limit, _)) = unsupported.iter().find(|(_, unsupported)| *unsupported) {
+            return Err(DockerMonitorError::ResourceLimit(format!(
+                "{limit} limit requested, {cgroups} on this host cannot enforce it"
+            )));
+        }
+        if self.memory_bytes.is_some() && !support.swap {
+            warn!("Swap limits are not enforceable on this host, the memory-limited job may swap");
+        }
+        Ok(())
+    }
+}
+
+/// Whether `id` can identify a job: non-empty, not starting with `.` and
+/// made of letters, digits, `-`, `_` and `.` only, so it is safe in container
+/// and directory names.
+pub fn is_valid_job_id(id: &str) -> bool {
+    !id.is_empty() && !id.starts_with('.') && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Job submitted by the RedSys platform.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobSpec {
+    /// Job identifier, unique per agent
+    pub id: String,
+
+    /// Image reference, e.g. `alpine:3.20`
+    pub image: String,
+
+    /// Command overriding the image default
+    #[serde(default)]
+    pub command: Vec<String>,
+
+    /// Environment variables
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Bind mounts
+    #[serde(default)]
+    pub mounts: Vec<JobMount>,
+
+    /// Container ports published on the host
+    #[serde(default)]
+    pub ports: Vec<JobPort>,
+
+    /// Resource limits
+    #[serde(default)]
+    pub limits: ResourceLimits,
+
+    /// Platform metadata, e.g. the workload or tenant, added to the
+    /// container as `redsys.job.<key>` labels
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+
+    /// Seccomp, AppArmor and SELinux confinement, chosen from what
+    /// `[job_security]` allows
+    #[serde(default)]
+    pub security: JobSecurity,
+
+    /// Restart policy that makes the job a long-running service
+    #[serde(default)]
+    pub service: Option<ServicePolicy>,
+}
+
+impl JobSpec {
+    /// Validates the spec before any container is created.
+    pub fn validate(&self) -> AppResult<()> {
+        if !is_valid_job_id(&self.id) {
+            return Err(AppError::Configuration(format!(
+                "Job ID '{}' must be non-empty, not start with '.' and contain only letters, digits, '-', '_' or '.'", self.id
+            )));
+        }
+        if self.image.trim().is_empty() {
+            return Err(AppError::Configuration(format!("Job '{}' has no image", self.id)));
+        }
+        let invalid_key = self.metadata.keys().find(|key| {
+            key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+        if let Some(key) = invalid_key {
+            return Err(AppError::Configuration(format!(
+                "Job '{}': metadata key '{key}' must be non-empty and contain only letters, digits, '-', '_' or '.'", self.id
+            )));
+        }
+        for mount in &self.mounts {
+            if mount.target == WORKSPACE_MOUNT_TARGET {
+                return Err(AppError::Configuration(format!(
+                    "Job '{}': {WORKSPACE_MOUNT_TARGET} is reserved for the job workspace", self.id
+                )));
+            }
+            if !mount.target.starts_with('/') || mount.source.is_empty() {
+                return Err(AppError::Configuration(format!(
+                    "Job '{}': mount '{}' -> '{}' needs a host path and an absolute target",
+                    self.id, mount.source, mount.target
+                )));
+            }
+        }
+        let mut host_ports = HashSet::new();
+        for port in &self.ports {
+            if port.container_port == 0 || port.host_port == 0 {
+                return Err(AppError::Configuration(format!(
+                    "Job '{}': port {} -> {} must be non-zero", self.id, port.host_port, port.container_port
+                )));
+            }
+            if !host_ports.insert((port.host_port, port.protocol)) {
+                return Err(AppError::Configuration(format!(
+                    "Job '{}': host port {}/{} is published twice", self.id, port.host_port, port.protocol
+                )));
+            }
+        }
+        if self.limits.cpus.is_some_and(|cpus| cpus.is_nan() || cpus <= 0.0) {
+            return Err(AppError::Configuration(format!("Job '{}': cpus must be positive", self.id)));
+        }
+        if self.limits.memory_bytes.is_some_and(|memory| memory < MIN_MEMORY_BYTES) {
+            return Err(AppError::Configuration(format!(
+                "Job '{}': memory_bytes must be at least {MIN_MEMORY_BYTES}", self.id
+            )));
+        }
+        if self.limits.cpu_shares.is_some_and(|shares| shares < MIN_CPU_SHARES) {
+            return Err(AppError::Configuration(format!(
+                "Job '{}': cpu_shares must be at least {MIN_CPU_SHARES}", self.id
+            )));
+        }
+        if self.limits.pids_limit.is_some_and(|pids| pids <= 0) {
+            return Err(AppError::Configuration(format!("Job '{}': pids_limit must be positive", self.id)));
+        }
+        if let Some(gpus) = &self.limits.gpus {
+            if gpus.device_ids.is_empty() && gpus.count.is_some_and(|count| count == 0 || count < -1) {
+                return Err(AppError::Configuration(format!(
+                    "Job '{}': gpus.count must be positive or -1 for all GPUs", self.id
+                )));
+            }
+        }
+        self.security
+            .validate()
+            .map_err(|reason| AppError::Configuration(format!("Job '{}': {reason}", self.id)))?;
+        if let Some(service) = &self.service {
+            service
+                .validate()
+                .map_err(|reason| AppError::Configuration(format!("Job '{}': {reason}", self.id)))?;
+        }
+        Ok(())
+    }
+
+    /// Name of the job container.
+    pub fn container_name(&self) -> String {
+        format!("redsys-job-{}", self.id)
+    }
+
+    /// Builds the container configuration for `image`, the spec's image or
+    /// its verified digest reference, mounting `workspace` at
+    /// [`WORKSPACE_MOUNT_TARGET`] when given and applying `security_opt`.
+    fn to_container_body(&self, image: &str, workspace: Option<&Path>, security_opt: Vec<String>) -> ContainerCreateBody {
+        let mut labels: HashMap<String, String> = self
+            .metadata
+            .iter()
+            .map(|(key, value)| (format!("{REDSYS_JOB_LABEL_PREFIX}{key}"), value.clone()))
+            .collect();
+        labels.extend([
+            (REDSYS_MANAGED_LABEL.to_string(), "true".to_string()),
+            (REDSYS_JOB_ID_LABEL.to_string(), self.id.clone()),
+            (REDSYS_AGENT_VERSION_LABEL.to_string(), env!("CARGO_PKG_VERSION").to_string()),
+        ]);
+        let mounts = self
+            .mounts
+            .iter()
+            .map(|mount| Mount {
+                source: Some(mount.source.clone()),
+                target: Some(mount.target.clone()),
+                typ: Some(MountTypeEnum::BIND),
+                read_only: Some(mount.read_only),
+                ..Default::default()
+            })
+            .chain(workspace.map(|workspace| Mount {
+                source: Some(workspace.display().to_string()),
+                target: Some(WORKSPACE_MOUNT_TARGET.to_string()),
+                typ: Some(MountTypeEnum::BIND),
+                read_only: Some(false),
+                ..Default::default()
+            }))
+            .collect();
+        let mut port_bindings: HashMap<String, Option<Vec<PortBinding>>> = HashMap::new();
+        for port in &self.ports {
+            port_bindings.entry(port.container_key()).or_default().get_or_insert_with(Vec::new).push(PortBinding {
+                host_ip: None,
+                host_port: Some(port.host_port.to_string()),
+            });
+        }
+
+        ContainerCreateBody {
+            image: Some(image.to_string()),
+            cmd: (!self.command.is_empty()).then(|| self.command.clone()),
+            env: Some(self.env.iter().map(|(key, value)| format!("{key}={value}")).collect()),
+            labels: Some(labels),
+            host_config: Some(HostConfig {
+                mounts: Some(mounts),
+                port_bindings: (!port_bindings.is_empty()).then_some(port_bindings),
+                nano_cpus: self.limits.cpus.map(|cpus| (cpus * 1e9) as i64),
+                cpu_shares: self.limits.cpu_shares,
+                memory: self.limits.memory_bytes,
+                pids_limit: self.limits.pids_limit,
+                device_requests: self.limits.gpus.as_ref().map(|gpus| vec![gpus.to_device_request()]),
+                security_opt: (!security_opt.is_empty()).then_some(security_opt),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+/// Lifecycle state of a job.
+///
+/// Uses `#[serde(tag = "type")]` like [`DockerStatus`](crate::docker_monitor::DockerStatus).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(tag = "type")]
+pub enum JobState {
+    /// Accepted and waiting to run
+    Queued,
+
+    /// Pulling the image
+    Pulling,
+
+    /// Creating and starting the container
+    Starting,
+
+    /// Container is running
+    Running,
+
+    /// Container exited with code 0
+    Succeeded,
+
+    /// Job could not run or exited with a non-zero code
+    Failed { error: String },
+
+    /// Cancelled, or its container was stopped by the user or the Docker
+    /// daemon
+    Cancelled,
+}
+
+impl JobState {
+    /// Whether the job has finished.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Succeeded | JobState::Failed { .. } | JobState::Cancelled)
+    }
+}
+
+/// Cancellation of a job that has not finished.
+#[derive(Debug, Default)]
+struct Cancellation {
+    /// Cancels the run
+    token: CancellationToken,
+
+    /// Why the agent stopped the job, `None` when cancelled on request
+    failure: Option<String>,
+}
+
+/// How a job run ended.
+#[derive(Debug, Clone, PartialEq)]
+enum RunOutcome {
+    /// The container exited on its own with this code and, unless it exited
+    /// cleanly, why
+    Exited(i64, Option<ExitFailure>),
+
+    /// The job was cancelled or its container stopped from outside, with the
+    /// exit code if the container ran
+    Stopped(Option<i64>),
+}
+
+/// Tracked job and its results.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobRecord {
+    /// Submitted spec
+    pub spec: JobSpec,
+
+    /// Current state
+    pub state: JobState,
+
+    /// Container ID once created
+    pub container_id: Option<String>,
+
+    /// Container exit code once finished
+    pub exit_code: Option<i64>,
+
+    /// Why the container did not exit cleanly
+    pub failure: Option<ExitFailure>,
+
+    /// Trailing container output once finished
+    pub logs: Option<String>,
+
+    /// When the job was submitted
+    pub submitted_at: DateTime<Utc>,
+
+    /// When the job finished
+    pub finished_at: Option<DateTime<Utc>>,
+
+    /// Host directory mounted at [`WORKSPACE_MOUNT_TARGET`], if any
+    pub workspace: Option<String>,
+
+    /// Image signature verification result, if verification is enabled
+    pub signature: Option<SignatureVerification>,
+
+    /// Why pulling the image failed, when the job ran the local image instead
+    pub pull_error: Option<String>,
+
+    /// Times a service container was restarted
+    pub restarts: u32,
+}
+
+/// Payload of the `job-state-changed` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct JobStateChanged {
+    /// Job identifier
+    pub job_id: String,
+
+    /// New state
+    pub state: JobState,
+
+    /// Container ID once created
+    pub container_id: Option<String>,
+
+    /// Container exit code once finished
+    pub exit_code: Option<i64>,
+}
+
+/// Runs and tracks jobs.
+#[derive(Debug)]
+pub struct JobManager {
+    /// Source of container lifecycle events
+    docker_service: Arc<DockerService>,
+
+    /// Jobs by ID
+    jobs: RwLock<HashMap<String, JobRecord>>,
+
+    /// Per-job workspaces, if enabled
+    workspaces: Option<Arc<WorkspaceManager>>,
+
+    /// Desktop notifications for finished jobs
+    notifier: Option<Arc<Notifier>>,
+
+    /// Registries and images jobs may use
+    image_policy: ImagePolicy,
+
+    /// Default confinement and the choices jobs may make instead
+    security: JobSecurityConfig,
+
+    /// Host directories jobs may bind mount
+    host_paths: HostPaths,
+
+    /// Verifies job images before they run
+    signature_verifier: Option<Arc<SignatureVerifier>>,
+
+    /// Event bus for job state changes
+    bus: Arc<Bus>,
+
+    /// Cancellations of the jobs that have not finished
+    cancellations: RwLock<HashMap<String, Cancellation>>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: Arc<CancellationToken>,
+}
+
+impl JobManager {
+    /// Creates a job manager.
+    pub fn new(docker_service: Arc<DockerService>, cancellation_token: CancellationToken) -> Self {
+        Self {
+            docker_service,
+            jobs: RwLock::new(HashMap::new()),
+            workspaces: None,
+            notifier: None,
+            image_policy: ImagePolicy::default(),
+            security: JobSecurityConfig::default(),
+            host_paths: HostPaths::default(),
+            signature_verifier: None,
+            bus: Arc::new(Bus::new()),
+            cancellations: RwLock::new(HashMap::new()),
+            cancellation_token: Arc::new(cancellation_token),
+        }
+    }
+
+    /// Gives every job a workspace from `workspaces`.
+    pub fn with_workspaces(self, workspaces: Arc<WorkspaceManager>) -> Self {
+        Self {
+            workspaces: Some(workspaces),
+            ..self
+        }
+    }
+
+    /// Shows desktop notifications when jobs finish or fail.
+    pub fn with_notifier(self, notifier: Arc<Notifier>) -> Self {
+        Self {
+            notifier: Some(notifier),
+            ..self
+        }
+    }
+
+    /// Rejects jobs whose image `image_policy` does not allow.
+    pub fn with_image_policy(self, image_policy: ImagePolicy) -> Self {
+        Self { image_policy, ..self }
+    }
+
+    /// Confines jobs with the defaults of `security` where their spec sets
+    /// nothing, and rejects jobs choosing what it does not allow.
+    pub fn with_security_config(self, security: JobSecurityConfig) -> Self {
+        Self { security, ..self }
+    }
+
+    /// Lets jobs bind mount directories inside `host_paths`; without it,
+    /// jobs with bind mounts are rejected.
+    pub fn with_host_paths(self, host_paths: HostPaths) -> Self {
+        Self { host_paths, ..self }
+    }
+
+    /// Verifies the signature of every job image before it runs.
+    pub fn with_signature_verifier(self, verifier: Arc<SignatureVerifier>) -> Self {
+        Self {
+            signature_verifier: Some(verifier),
+            ..self
+        }
+    }
+
+    /// Shares the event bus that job state changes are published to.
+    pub fn with_event_bus(self, bus: Arc<Bus>) -> Self {
+        Self { bus, ..self }
+    }
+
+    /// Lists all tracked jobs, newest first.
+    pub async fn jobs(&self) -> Vec<JobRecord> {
+        let mut jobs: Vec<JobRecord> = self.jobs.read().await.values().cloned().collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.submitted_at));
+        jobs
+    }
+
+    /// Gets a job by ID.
+    pub async fn job(&self, id: &str) -> Option<JobRecord> {
+        self.jobs.read().await.get(id).cloned()
+    }
+
+    /// IDs of the jobs that have not finished yet.
+    pub async fn active_job_ids(&self) -> HashSet<String> {
+        self.jobs
+            .read()
+            .await
+            .values()
+            .filter(|job| !job.state.is_finished())
+            .map(|job| job.spec.id.clone())
+            .collect()
+    }
+
+    /// Cancels a job that has not finished: its container is stopped with a
+    /// grace period, or never started if it does not exist yet.
+    ///
+    /// Returns once the cancellation is requested; the job reaches
+    /// [`JobState::Cancelled`] when its container is gone. Fails if the job
+    /// is not tracked or has already finished.
+    pub async fn cancel(&self, id: &str) -> AppResult<()> {
+        let job = self
+            .job(id)
+            .await
+            .ok_or_else(|| AppError::NotFound { resource: format!("job '{id}'") })?;
+        if job.state.is_finished() {
+            return Err(AppError::InvalidState(format!("Job '{id}' has already finished")));
+        }
+        info!("Cancelling job {}", id);
+        self.stop(id, None).await;
+        Ok(())
+    }
+
+    /// Cancels the run of a job, failing it with `failure` if given.
+    async fn stop(&self, id: &str, failure: Option<String>) {
+        if let Some(cancellation) = self.cancellations.write().await.get_mut(id) {
+            if cancellation.failure.is_none() {
+                cancellation.failure = failure;
+            }
+            cancellation.token.cancel();
+        }
+    }
+
+    /// Checks the workspace quota every minute until cancelled, failing the
+    /// running jobs whose workspaces exceed it, see
+    /// [`WorkspaceManager::over_quota`].
+    pub async fn enforce_workspace_quota(self: Arc<Self>) {
+        let Some(workspaces) = self.workspaces.clone() else {
+            return;
+        };
+        let mut check = interval(QUOTA_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = check.tick() => {
+                    let workspaces = workspaces.clone();
+                    match task::spawn_blocking(move || workspaces.over_quota()).await {
+                        Ok(Ok(jobs)) => {
+                            for job_id in jobs {
+                                warn!("Stopping job {}, its workspace exceeds the quota", job_id);
+                                self.stop(&job_id, Some("Workspace quota exceeded".to_string())).await;
+                            }
+                        }
+                        Ok(Err(e)) => warn!("Workspace quota check failed: {e}"),
+                        Err(e) => error!("Workspace quota check panicked: {e}"),
+                    }
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Workspace quota enforcement received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Accepts a job and starts running it in the background.
+    ///
+    /// Returns an error if the spec is invalid, the image policy or the
+    /// `[job_security]` allow-lists reject it, a bind mount is outside the allowed host paths, a job with the
+    /// same ID is already tracked or no workspace can be created.
+    pub async fn submit(self: &Arc<Self>, app_handle: tauri::AppHandle, spec: JobSpec) -> AppResult<JobRecord> {
+        spec.validate()?;
+        self.image_policy.check(&spec.image)?;
+        self.security
+            .check(&spec.security)
+            .map_err(|reason| AppError::Configuration(format!("Job '{}': {reason}", spec.id)))?;
+        let spec = self.resolve_mounts(spec).await?;
+
+        let mut record = JobRecord {
+            spec: spec.clone(),
+            state: JobState::Queued,
+            container_id: None,
+            exit_code: None,
+            failure: None,
+            logs: None,
+            submitted_at: Utc::now(),
+            finished_at: None,
+            workspace: None,
+            signature: None,
+            pull_error: None,
+            restarts: 0,
+        };
+        {
+            let mut jobs = self.jobs.write().await;
+            if jobs.contains_key(&spec.id) {
+                return Err(AppError::InvalidState(format!("Job '{}' already exists", spec.id)));
+            }
+            jobs.insert(spec.id.clone(), record.clone());
+            evict_finished(&mut jobs);
+        }
+        // Measuring the quota walks the workspaces, so not under the lock
+        if let Some(workspaces) = self.workspaces.clone() {
+            let job_id = spec.id.clone();
+            let created = task::spawn_blocking(move || workspaces.create(&job_id))
+                .await
+                .map_err(|e| AppError::Application(format!("Workspace creation task failed: {e}")))
+                .and_then(|created| created);
+            match created {
+                Ok(path) => {
+                    record.workspace = Some(path.display().to_string());
+                    self.update(&spec.id, |job| job.workspace = record.workspace.clone()).await;
+                }
+                Err(e) => {
+                    self.jobs.write().await.remove(&spec.id);
+                    return Err(e);
+                }
+            }
+        }
+        let cancel = CancellationToken::new();
+        let cancellation = Cancellation {
+            token: cancel.clone(),
+            failure: None,
+        };
+        self.cancellations.write().await.insert(spec.id.clone(), cancellation);
+        info!("Accepted job {} ({})", spec.id, spec.image);
+        self.publish_state(&spec.id).await;
+
+        let manager = self.clone();
+        let span = info_span!("job", job_id = %spec.id);
+        task::spawn(
+            async move {
+                let job_id = spec.id.clone();
+                manager.warn_port_conflicts(&app_handle, &spec).await;
+                let result = manager.run(&app_handle, &spec, &cancel).await;
+                let stopped_for = manager
+                    .cancellations
+                    .write()
+                    .await
+                    .remove(&job_id)
+                    .and_then(|cancellation| cancellation.failure);
+                let state = match &result {
+                    Ok(RunOutcome::Exited(_, None)) => JobState::Succeeded,
+                    Ok(RunOutcome::Exited(code, Some(failure))) => JobState::Failed {
+                        error: failure.describe(*code),
+                    },
+                    Ok(RunOutcome::Stopped(_)) => match stopped_for {
+                        Some(error) => JobState::Failed { error },
+                        None => JobState::Cancelled,
+                    },
+                    Err(e) => JobState::Failed { error: e.to_string() },
+                };
+                if let Ok(RunOutcome::Exited(exit_code, Some(failure))) = result {
+                    manager.report_failure(&app_handle, &spec, exit_code, failure).await;
+                }
+                match &state {
+                    JobState::Succeeded => info!("Job {} succeeded", job_id),
+                    JobState::Failed { error } => warn!("Job {} failed: {}", job_id, error),
+                    JobState::Cancelled => info!("Job {} was cancelled", job_id),
+                    _ => {}
+                }
+                manager
+                    .update(&job_id, |job| {
+                        job.state = state;
+                        job.finished_at = Some(Utc::now());
+                    })
+                    .await;
+                if let Some(workspaces) = &manager.workspaces {
+                    if let Err(e) = workspaces.finish(&job_id) {
+                        warn!("Failed to mark workspace of job {} finished: {}", job_id, e);
+                    }
+                }
+                manager.publish_state(&job_id).await;
+                if let (Some(notifier), Some(job)) = (&manager.notifier, manager.job(&job_id).await) {
+                    notifier.job_finished(&app_handle, &job);
+                }
+            }
+            .instrument(span),
+        );
+
+        Ok(record)
+    }
+
+    /// Replaces the bind mount sources of `spec` with their canonical paths.
+    ///
+    /// Fails if a source is outside the allowed host paths or exposes the
+    /// Docker socket.
+    async fn resolve_mounts(&self, mut spec: JobSpec) -> AppResult<JobSpec> {
+        if spec.mounts.is_empty() {
+            return Ok(spec);
+        }
+        let host_paths = self.host_paths.clone();
+        let sources: Vec<PathBuf> = spec.mounts.iter().map(|mount| PathBuf::from(&mount.source)).collect();
+        let resolved = task::spawn_blocking(move || {
+            sources.iter().map(|source| host_paths.resolve(source)).collect::<Result<Vec<_>, _>>()
+        })
+        .await
+        .map_err(|e| AppError::Application(format!("Mount resolution task failed: {e}")))?
+        .map_err(|reason| AppError::Configuration(format!("Job '{}': {reason}", spec.id)))?;
+        for (mount, source) in spec.mounts.iter_mut().zip(resolved) {
+            mount.source = source.display().to_string();
+        }
+        Ok(spec)
+    }
+
+    /// Emits a `container-failed` event for a job container that did not
+    /// exit cleanly.
+    async fn report_failure(&self, app_handle: &tauri::AppHandle, spec: &JobSpec, exit_code: i64, failure: ExitFailure) {
+        let container_id = self.job(&spec.id).await.and_then(|job| job.container_id).unwrap_or_default();
+        let payload = ContainerFailed {
+            container_id,
+            container_name: spec.container_name(),
+            job_id: Some(spec.id.clone()),
+            exit_code,
+            failure,
+            failed_at: Utc::now(),
+        };
+        if let Err(e) = telemetry::emit(app_handle, "container-failed", &payload) {
+            error!("Failed to emit container-failed event: {e}");
+        }
+    }
+
+    /// Emits a `port-conflict` event for every host port of the job that is
+    /// already taken. The job still runs; Docker refuses to start it if the
+    /// port is still taken then.
+    async fn warn_port_conflicts(&self, app_handle: &tauri::AppHandle, spec: &JobSpec) {
+        if spec.ports.is_empty() {
+            return;
+        }
+        let published = self.docker_service.published_ports().await.unwrap_or_else(|e| {
+            warn!("Checking job ports against host listeners only, containers unavailable: {}", e);
+            Vec::new()
+        });
+        for conflict in ports::conflicts(&spec.id, &spec.ports, &published, ports::host_port_in_use) {
+            warn!(
+                "Host port {}/{} requested by job {} is already taken",
+                conflict.host_port, conflict.protocol, spec.id
+            );
+            if let Err(e) = telemetry::emit(app_handle, "port-conflict", &conflict) {
+                error!("Failed to emit port-conflict event: {e}");
+            }
+        }
+    }
+
+    /// Pulls, creates, starts and waits for the job container until it exits
+    /// or `cancel` is cancelled.
+    async fn run(&self, app_handle: &tauri::AppHandle, spec: &JobSpec, cancel: &CancellationToken) -> DockerMonitorResult<RunOutcome> {
+        let client = self.docker_service.docker().await?;
+        let host = HostCapacity::detect(&client).await?;
+        spec.limits.check_capacity(&host)?;
+        let security_opt = hardening::security_options(&spec.security, &self.security.defaults, &host.security).await?;
+
+        self.transition(&spec.id, JobState::Pulling).await;
+        let pull_error = tokio::select! {
+            pulled = pull_image(&client, &spec.image) => pulled?,
+            _ = cancel.cancelled() => return Ok(RunOutcome::Stopped(None)),
+        };
+        if let Some(pull_error) = pull_error {
+            self.update(&spec.id, |job| job.pull_error = Some(pull_error)).await;
+        }
+        let verified = self.verify_signature(&client, spec).await?;
+        if cancel.is_cancelled() {
+            return Ok(RunOutcome::Stopped(None));
+        }
+
+        self.transition(&spec.id, JobState::Starting).await;
+        let workspace = self.workspaces.as_ref().map(|workspaces| workspaces.path(&spec.id));
+        let image = verified.unwrap_or_else(|| spec.image.clone());
+        let options = CreateContainerOptionsBuilder::new().name(&spec.container_name()).build();
+        let container_id = client
+            .create_container(Some(options), spec.to_container_body(&image, workspace.as_deref(), security_opt))
+            .await
+            .map_err(|e| DockerMonitorError::Api(format!("Failed to create container: {e}")))?
+            .id;
+        self.update(&spec.id, |job| job.container_id = Some(container_id.clone())).await;
+
+        // Subscribe before starting so a fast exit is not missed
+        let mut events = self.docker_service.subscribe_events();
+        let mut restarts = spec.service.as_ref().map(RestartTracker::new);
+        let result = async {
+            loop {
+                client
+                    .start_container(&container_id, None::<StartContainerOptions>)
+                    .await
+                    .map_err(|e| DockerMonitorError::Api(format!("Failed to start container: {e}")))?;
+                self.transition(&spec.id, JobState::Running).await;
+
+                let started = Instant::now();
+                let (exit_code, stopped) = self.wait_for_exit(&client, &container_id, &mut events, cancel).await?;
+                if stopped {
+                    return Ok(RunOutcome::Stopped(Some(exit_code)));
+                }
+                let failure = ExitFailure::classify(exit_code, oom_killed(&client, &container_id).await);
+                let Some(tracker) = restarts.as_mut() else {
+                    return Ok(RunOutcome::Exited(exit_code, failure));
+                };
+                let (delay, flapping) = match tracker.on_exit(started.elapsed(), exit_code) {
+                    RestartDecision::Restart { delay, flapping } => (delay, flapping),
+                    RestartDecision::GiveUp => {
+                        warn!("Service job {} exhausted its restart budget", spec.id);
+                        return Ok(RunOutcome::Exited(exit_code, failure));
+                    }
+                    RestartDecision::Done => {
+                        info!("Service job {} exited cleanly, not restarting", spec.id);
+                        return Ok(RunOutcome::Exited(exit_code, failure));
+                    }
+                };
+                warn!(
+                    "Service job {} exited with code {}, restarting in {:?} ({} in a row)",
+                    spec.id, exit_code, delay, tracker.consecutive()
+                );
+                let total = tracker.total();
+                self.update(&spec.id, |job| job.restarts = total).await;
+                if flapping {
+                    let payload = ServiceFlapping {
+                        job_id: spec.id.clone(),
+                        container_id: container_id.clone(),
+                        consecutive_restarts: tracker.consecutive(),
+                        max_restarts: spec.service.as_ref().map_or(0, |service| service.max_restarts),
+                        exit_code,
+                        failure,
+                        detected_at: Utc::now(),
+                    };
+                    if let Err(e) = telemetry::emit(app_handle, "service-flapping", &payload) {
+                        error!("Failed to emit service-flapping event: {e}");
+                    }
+                }
+                tokio::select! {
+                    _ = sleep(delay) => {}
+                    _ = cancel.cancelled() => return Ok(RunOutcome::Stopped(Some(exit_code))),
+                    _ = self.cancellation_token.cancelled() => {
+                        return Err(DockerMonitorError::Internal("Agent shut down while the service was restarting".to_string()));
+                    }
+                }
+            }
+        }
+        .await;
+
+        let logs = collect_logs(&client, &container_id).await;
+        self.update(&spec.id, |job| {
+            match &result {
+                Ok(RunOutcome::Exited(exit_code, failure)) => {
+                    job.exit_code = Some(*exit_code);
+                    job.failure = failure.clone();
+                }
+                Ok(RunOutcome::Stopped(exit_code)) => job.exit_code = *exit_code,
+                Err(_) => {}
+            }
+            job.logs = Some(logs);
+        })
+        .await;
+
+        let options = RemoveContainerOptionsBuilder::new().force(true).build();
+        if let Err(e) = client.remove_container(&container_id, Some(options)).await {
+            warn!("Failed to remove job container {}: {}", container_id, e);
+        }
+        result
+    }
+
+    /// Verifies the job image with the signature verifier, if any, and
+    /// records the result.
+    ///
+    /// Returns the verified digest reference, which the container must be
+    /// created from so a tag moved since the check is not used. Fails with
+    /// [`DockerMonitorError::UntrustedImage`] if verification fails and is
+    /// enforced.
+    async fn verify_signature(&self, client: &Docker, spec: &JobSpec) -> DockerMonitorResult<Option<String>> {
+        let Some(verifier) = &self.signature_verifier else {
+            return Ok(None);
+        };
+        let verification = verifier.verify(client, &spec.image).await;
+        self.update(&spec.id, |job| job.signature = Some(verification.clone())).await;
+
+        let Some(reason) = verification.error else {
+            info!("Verified signature of {} with {}", spec.image, verification.key.unwrap_or_default());
+            return Ok(verification.reference);
+        };
+        if verifier.enforced() {
+            return Err(DockerMonitorError::UntrustedImage {
+                image: spec.image.clone(),
+                reason,
+            });
+        }
+        warn!("Running job {} with unverified image {}: {}", spec.id, spec.image, reason);
+        Ok(None)
+    }
+
+    /// Waits for the container `die` event, inspecting periodically in case
+    /// events are missed or the events stream is disabled. Stops the
+    /// container when `cancel` is cancelled.
+    ///
+    /// Returns the exit code and whether the container was stopped rather
+    /// than exiting on its own: cancelled, or stopped or killed by the user
+    /// or the Docker daemon. Outside stops are only noticed through their
+    /// `kill` event.
+    async fn wait_for_exit(
+        &self,
+        client: &Docker,
+        container_id: &str,
+        events: &mut broadcast::Receiver<DockerEvent>,
+        cancel: &CancellationToken,
+    ) -> DockerMonitorResult<(i64, bool)> {
+        let mut reconcile = interval(RECONCILE_INTERVAL);
+        let mut events_open = true;
+        let mut stopped = false;
+
+        loop {
+            tokio::select! {
+                event = events.recv(), if events_open => match event {
+                    Ok(event) if is_exit_of(&event, container_id) => {
+                        if let Some(code) = event.attributes.get("exitCode").and_then(|code| code.parse().ok()) {
+                            return Ok((code, stopped));
+                        }
+                        reconcile.reset_immediately();
+                    }
+                    Ok(event) if is_kill_of(&event, container_id) => stopped = true,
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(_)) => reconcile.reset_immediately(),
+                    Err(RecvError::Closed) => events_open = false,
+                },
+                _ = reconcile.tick() => {
+                    let inspect = client
+                        .inspect_container(container_id, None::<InspectContainerOptions>)
+                        .await
+                        .map_err(|e| DockerMonitorError::Api(format!("Failed to inspect container: {e}")))?;
+                    if let Some(state) = inspect.state.filter(|state| state.running == Some(false)) {
+                        return Ok((state.exit_code.unwrap_or(-1), stopped));
+                    }
+                }
+                _ = cancel.cancelled(), if !stopped => {
+                    stopped = true;
+                    info!("Stopping container {} of cancelled job", container_id);
+                    let options = StopContainerOptionsBuilder::new().t(CANCEL_GRACE_SECS).build();
+                    if let Err(e) = client.stop_container(container_id, Some(options)).await {
+                        warn!("Failed to stop container {}: {}", container_id, e);
+                    }
+                    reconcile.reset_immediately();
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    return Err(DockerMonitorError::Internal("Agent shut down while the job was running".to_string()));
+                }
+            }
+        }
+    }
+
+    /// Applies a change to a tracked job.
+    async fn update(&self, id: &str, change: impl FnOnce(&mut JobRecord)) {
+        if let Some(job) = self.jobs.write().await.get_mut(id) {
+            change(job);
+        }
+    }
+
+    /// Moves a job to a new state and publishes it.
+    async fn transition(&self, id: &str, state: JobState) {
+        self.update(id, |job| job.state = state).await;
+        self.publish_state(id).await;
+    }
+
+    /// Publishes `job-state-changed` with the current state of a job.
+    async fn publish_state(&self, id: &str) {
+        let Some(job) = self.job(id).await else {
+            return;
+        };
+        let payload = JobStateChanged {
+            job_id: job.spec.id,
+            state: job.state,
+            container_id: job.container_id,
+            exit_code: job.exit_code,
+        };
+        self.bus.publish(Event::Job(JobEvent::StateChanged(payload)));
+    }
+}
+
+/// Whether an event reports that the container exited.
+fn is_exit_of(event: &DockerEvent, container_id: &str) -> bool {
+    event.event_type == "container" && event.action == "die" && event.actor_id.as_deref() == Some(container_id)
+}
+
+/// Whether an event reports a signal sent to the container by `docker stop`,
+/// `docker kill` or the daemon shutting down.
+fn is_kill_of(event: &DockerEvent, container_id: &str) -> bool {
+    event.event_type == "container" && event.action == "kill" && event.actor_id.as_deref() == Some(container_id)
+}
+
+/// Whether the kernel killed the container for exceeding its memory limit,
+/// from the `OOMKilled` flag of its state.
+async fn oom_killed(client: &Docker, container_id: &str) -> bool {
+    match client.inspect_container(container_id, None::<InspectContainerOptions>).await {
+        Ok(inspect) => inspect.state.and_then(|state| state.oom_killed).unwrap_or(false),
+        Err(e) => {
+            warn!("Failed to inspect exited container {}: {}", container_id, e);
+            false
+        }
+    }
+}
+
+/// Pulls an image, failing on the first error reported by the daemon unless
+/// the image is already present, e.g. sideloaded on an air-gapped host.
+///
+/// Returns the pull error when the local image is used instead, since it may
+/// be stale.
+async fn pull_image(client: &Docker, image: &str) -> DockerMonitorResult<Option<String>> {
+    let options = CreateImageOptionsBuilder::new().from_image(image).build();
+    let mut progress = client.create_image(Some(options), None, None);
+    while let Some(item) = progress.next().await {
+        if let Err(e) = item {
+            if client.inspect_image(image).await.is_ok() {
+                warn!("Failed to pull {}, using the local image: {}", image, e);
+                return Ok(Some(e.to_string()));
+            }
+            return Err(DockerMonitorError::Api(format!("Failed to pull {image}: {e}")));
+        }
+    }
+    Ok(None)
+}
+
+/// Collects the trailing stdout and stderr of a container.
+async fn collect_logs(client: &Docker, container_id: &str) -> String {
+    let options = LogsOptionsBuilder::new()
+        .stdout(true)
+        .stderr(true)
+        .tail(&MAX_LOG_LINES.to_string())
+        .build();
+    let mut stream = client.logs(container_id, Some(options));
+    let mut logs = String::new();
+    while let Some(item) = stream.next().await {
+        match item {
+            Ok(output) => logs.push_str(&output.to_string()),
+            Err(e) => {
+                warn!("Failed to read logs of job container {}: {}", container_id, e);
+                break;
+            }
+        }
+    }
+    logs
+}
+
+/// Drops the oldest finished jobs beyond [`MAX_FINISHED_JOBS`].
+fn evict_finished(jobs: &mut HashMap<String, JobRecord>) {
+    let mut finished: Vec<(DateTime<Utc>, String)> = jobs
+        .values()
+        .filter(|job| job.state.is_finished())
+        .map(|job| (job.submitted_at, job.spec.id.clone()))
+        .collect();
+    if finished.len() <= MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort();
+    let excess = finished.len() - MAX_FINISHED_JOBS;
+    for (_, id) in finished.into_iter().take(excess) {
+        jobs.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> JobSpec {
+        serde_json::from_value(serde_json::json!({
+            "id": "job-1",
+            "image": "alpine:3.20",
+            "command": ["echo", "hello"],
+            "env": { "MODE": "test" },
+            "mounts": [{ "source": "/data", "target": "/mnt/data", "read_only": true }],
+            "ports": [{ "container_port": 80, "host_port": 8080 }],
+            "limits": { "cpus": 1.5, "memory_bytes": 268435456 },
+            "metadata": { "workload": "render" }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_spec() {
+        assert!(spec().validate().is_ok());
+
+        let mut invalid = spec();
+        invalid.id = "../etc".to_string();
+        assert!(invalid.validate().is_err());
+        invalid.id = "..".to_string();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = spec();
+        invalid.mounts[0].target = "relative".to_string();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = spec();
+        invalid.ports.push(invalid.ports[0].clone());
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = spec();
+        invalid.limits.memory_bytes = Some(1024);
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = spec();
+        invalid.metadata.insert("tenant id".to_string(), "42".to_string());
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = spec();
+        invalid.service = Some(ServicePolicy { max_backoff_secs: 0, ..Default::default() });
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_container_body_carries_labels_and_limits() {
+        let security_opt = vec!["apparmor=redsys-job".to_string()];
+        let image = "docker.io/library/alpine@sha256:0123456789abcdef";
+        let body = spec().to_container_body(image, Some(Path::new("/var/lib/redsys/workspaces/job-1")), security_opt.clone());
+        assert_eq!(body.image.as_deref(), Some(image));
+        let labels = body.labels.unwrap();
+        assert_eq!(labels.get(REDSYS_MANAGED_LABEL).map(String::as_str), Some("true"));
+        assert_eq!(labels.get(REDSYS_JOB_ID_LABEL).map(String::as_str), Some("job-1"));
+        assert_eq!(labels.get("redsys.job.workload").map(String::as_str), Some("render"));
+        assert!(labels.contains_key(REDSYS_AGENT_VERSION_LABEL));
+        assert_eq!(body.env, Some(vec!["MODE=test".to_string()]));
+
+        let host_config = body.host_config.unwrap();
+        assert_eq!(host_config.nano_cpus, Some(1_500_000_000));
+        assert_eq!(host_config.memory, Some(268_435_456));
+        let mounts = host_config.mounts.unwrap();
+        assert_eq!(mounts[0].read_only, Some(true));
+        assert_eq!(mounts[1].target.as_deref(), Some(WORKSPACE_MOUNT_TARGET));
+        let bindings = host_config.port_bindings.unwrap();
+        assert_eq!(bindings["80/tcp"].as_ref().unwrap()[0].host_port.as_deref(), Some("8080"));
+        assert_eq!(host_config.security_opt, Some(security_opt));
+    }
+
+    #[test]
+    fn test_gpu_and_pids_limits_applied() {
+        let mut spec = spec();
+        spec.limits.pids_limit = Some(256);
+        spec.limits.gpus = Some(GpuRequest { count: None, device_ids: vec!["0".to_string(), "1".to_string()] });
+        let host_config = spec.to_container_body(&spec.image, None, Vec::new()).host_config.unwrap();
+        assert_eq!(host_config.pids_limit, Some(256));
+
+        let request = &host_config.device_requests.unwrap()[0];
+        assert_eq!(request.driver.as_deref(), Some("nvidia"));
+        assert_eq!(request.device_ids.as_ref().map(Vec::len), Some(2));
+        assert_eq!(request.count, None);
+    }
+
+    #[test]
+    fn test_check_capacity() {
+        let host = HostCapacity { cpus: 4, memory_bytes: 8 << 30, gpus: Some(1), ..Default::default() };
+        assert!(spec().limits.check_capacity(&host).is_ok());
+
+        let mut limits = spec().limits;
+        limits.cpus = Some(8.0);
+        assert!(matches!(limits.check_capacity(&host), Err(DockerMonitorError::ResourceLimit(_))));
+
+        let mut limits = spec().limits;
+        limits.memory_bytes = Some(16 << 30);
+        assert!(matches!(limits.check_capacity(&host), Err(DockerMonitorError::ResourceLimit(_))));
+
+        let mut limits = spec().limits;
+        limits.gpus = Some(GpuRequest { count: Some(2), device_ids: Vec::new() });
+        assert!(matches!(limits.check_capacity(&host), Err(DockerMonitorError::ResourceLimit(_))));
+        limits.gpus = Some(GpuRequest { count: Some(-1), device_ids: Vec::new() });
+        assert!(limits.check_capacity(&host).is_ok());
+    }
+
+    #[test]
+    fn test_unenforceable_limits_rejected() {
+        let support = LimitSupport { cgroup_version: Some(1), cpu_quota: false, swap: false, ..Default::default() };
+        let host = HostCapacity { cpus: 4, memory_bytes: 8 << 30, limit_support: support, ..Default::default() };
+
+        let error = spec().limits.check_capacity(&host).unwrap_err();
+        assert_eq!(error.to_string(), "Resource limit exceeded: CPU limit requested, cgroup v1 on this host cannot enforce it");
+
+        // Memory limits still apply without swap accounting
+        let mut limits = spec().limits;
+        limits.cpus = None;
+        assert!(limits.check_capacity(&host).is_ok());
+    }
+
+    #[test]
+    fn test_is_exit_of() {
+        let event = DockerEvent {
+            event_type: "container".to_string(),
+            action: "die".to_string(),
+            actor_id: Some("abc".to_string()),
+            actor_name: None,
+            attributes: HashMap::from([("exitCode".to_string(), "3".to_string())]),
+            health: None,
+            time: None,
+        };
+        assert!(is_exit_of(&event, "abc"));
+        assert!(!is_exit_of(&event, "def"));
+    }
+
+    #[test]
+    fn test_is_kill_of() {
+        let event = DockerEvent {
+            event_type: "container".to_string(),
+            action: "kill".to_string(),
+            actor_id: Some("abc".to_string()),
+            actor_name: None,
+            attributes: HashMap::from([("signal".to_string(), "15".to_string())]),
+            health: None,
+            time: None,
+        };
+        assert!(is_kill_of(&event, "abc"));
+        assert!(!is_kill_of(&event, "def"));
+        assert!(!is_exit_of(&event, "abc"));
+    }
+
+    #[test]
+    fn test_cancelled_is_finished() {
+        assert!(JobState::Cancelled.is_finished());
+        assert!(!JobState::Running.is_finished());
+    }
+}
@@ -8,83 +8,132 @@
 //! - Professional error handling and logging
 //! - Cross-platform support
 
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::info;
-use once_cell::sync::Lazy;
+use tracing::{info, warn};
 
+pub mod agent_metrics;
+pub mod app_state;
+pub mod autostart;
+pub mod availability;
+pub mod backend;
+pub mod capability_profile;
+pub mod cli;
+pub mod client;
+pub mod clock;
+pub mod compression;
+pub mod config;
+pub mod connection;
+pub mod dedup;
+pub mod deep_links;
+pub mod diagnosis;
+pub mod disk_pressure;
+pub mod docker;
 pub mod docker_monitor;
+pub mod doctor;
+pub mod engine;
 pub mod error;
+pub mod event_contract;
+pub mod events;
+pub mod failures;
+pub mod faults;
+pub mod gpu;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hardening;
+pub mod headless;
+pub mod host_paths;
+pub mod image_updates;
+pub mod images;
+pub mod jobs;
+pub mod listing;
+pub mod logging;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod network;
+pub mod network_probe;
+pub mod notifications;
+pub mod orphans;
+pub mod pause;
+pub mod policy;
+pub mod ports;
+pub mod power;
+pub mod provider;
+pub mod prune;
+pub mod requirements;
+pub mod restarts;
+pub mod routing;
+pub mod security;
+pub mod service;
+pub mod signatures;
+pub mod simulation;
+pub mod status;
+pub mod status_server;
+pub mod supervisor;
+pub mod system_monitor;
+pub mod telemetry;
+pub mod transfer;
+pub mod tray;
 pub mod types;
+pub mod webhooks;
+pub mod workspace;
 
+use app_state::AppStateStore;
+use config::AgentConfig;
+use docker_monitor::{DockerMonitor, DockerStatus};
 use error::AppResult;
 use types::AppState;
 
-/// Global application state using thread-safe lazy initialization
-static APP_STATE: Lazy<Arc<RwLock<AppState>>> = Lazy::new(|| {
-    Arc::new(RwLock::new(AppState::default()))
-});
-
-
+/// Application identifier, matching `identifier` in `tauri.conf.json`.
+/// Used as the directory name for configuration and log files.
+pub const APP_IDENTIFIER: &str = "tn.com.redsys-desktop-agent";
 
 /// Initialize the application
 /// 
-/// This function initializes all services and sets up the global application state.
+/// This function initializes all services, refreshes the application
+/// metadata, keeping the sections the subsystems have already reported, and
+/// checks the host against the minimum requirements.
 /// 
 /// # Arguments
 /// 
-/// * `app_handle` - Optional Tauri app handle for emitting events
+/// * `app_state` - The application state store shared with the commands
+/// * `config` - The agent configuration
+/// * `docker_monitor` - The managed monitor, whose client the requirements
+///   check uses so that simulation mode is honoured
 /// 
 /// # Returns
 /// 
 /// Returns success or an error
-pub async fn initialize_app(_app_handle: Option<tauri::AppHandle>) -> AppResult<()> {
+pub async fn initialize_app(
+    app_state: &AppStateStore,
+    config: &AgentConfig,
+    docker_monitor: &DockerMonitor,
+) -> AppResult<()> {
     info!("Initializing RedSys Desktop Agent...");
     
-    // Create application state
-    let app_state = AppState {
-        app_metadata: types::AppMetadata::default(),
-        last_updated: chrono::Utc::now(),
-    };
+    // Refresh application metadata
+    let current = app_state.get().await;
+    app_state
+        .replace(AppState {
+            app_metadata: types::AppMetadata::default(),
+            last_updated: chrono::Utc::now(),
+            ..current
+        })
+        .await;
     
-    // Update global state
-    {
-        let mut state = APP_STATE.write().await;
-        *state = app_state;
+    // Let onboarding block on hosts below the minimum requirements
+    let docker_status = docker_monitor.check_now().await.unwrap_or_else(|e| DockerStatus::Error {
+        message: e.to_string(),
+        retry_after: None,
+    });
+    let report = requirements::check_requirements(config, &docker_status).await;
+    for unmet in &report.unmet {
+        warn!("Minimum requirement not met: {}", unmet.message);
     }
+    app_state.record_requirements(report).await;
     
     info!("RedSys Desktop Agent initialized successfully");
     Ok(())
 }
 
-/// Get the current application state
-/// 
-/// Returns a clone of the current application state.
-/// 
-/// # Returns
-/// 
-/// Returns the current application state
-pub async fn get_app_state() -> AppState {
-    APP_STATE.read().await.clone()
-}
-
-/// Update the application state
-/// 
-/// Updates the global application state with new information.
-/// 
-/// # Arguments
-/// 
-/// * `new_state` - The new application state
-/// 
-/// # Returns
-/// 
-/// Returns success or an error
-pub async fn update_app_state(new_state: AppState) -> AppResult<()> {
-    let mut state = APP_STATE.write().await;
-    *state = new_state;
-    Ok(())
-}
-
 
 
 /// Cleanup the application
@@ -104,22 +153,21 @@ pub async fn cleanup_app() -> AppResult<()> {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
+    use tokio_util::sync::CancellationToken;
+
     use super::*;
+    use crate::client::MockDockerClient;
 
     #[tokio::test]
     async fn test_initialize_app() {
-        // This test would require proper mocking in a real environment
-        let result = initialize_app(None).await;
-        // We don't assert here because the app might not be available in test environment
-        if let Err(_e) = result {
-            // App initialization test failed as expected
-        }
-    }
-
-    #[tokio::test]
-    async fn test_update_app_state() {
-        let test_state = AppState::default();
-        let result = update_app_state(test_state).await;
-        assert!(result.is_ok());
+        let app_state = AppStateStore::new();
+        let docker_monitor = DockerMonitor::new(CancellationToken::new())
+            .with_client(Arc::new(MockDockerClient::new()));
+        initialize_app(&app_state, &AgentConfig::default(), &docker_monitor).await.unwrap();
+        let state = app_state.get().await;
+        assert_eq!(state.app_metadata.name, "RedSys Desktop Agent");
+        assert!(state.requirements.is_some());
     }
 }
@@ -0,0 +1,147 @@
+//! Paginated listings for RedSys Desktop Agent
+//!
+//! Hosts can run hundreds of containers and keep as many images, so the
+//! `list_containers` and `list_images` commands filter, sort and page the
+//! listing in the agent and only send the requested [`Page`] to the webview.
+//! Filters the daemon understands are passed to it as Docker API filters, the
+//! rest are evaluated by the agent. The Docker API has no paging, so every
+//! page is cut from a fresh listing: entries created or removed in between
+//! shift the following pages.
+//!
+//! ## References
+//! - [Docker List Containers API](https://docs.docker.com/engine/api/v1.47/#tag/Container/operation/ContainerList)
+//! - [Docker List Images API](https://docs.docker.com/engine/api/v1.47/#tag/Image/operation/ImageList)
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// Page size used when the caller does not ask for one.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page a caller can ask for.
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Direction of a sort.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    /// Applies the direction to an ascending comparison.
+    pub fn apply(self, ordering: Ordering) -> Ordering {
+        match self {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+}
+
+/// Window of a listing to return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PageRequest {
+    /// Entries to skip
+    pub offset: usize,
+
+    /// Entries to return, at most [`MAX_PAGE_SIZE`]
+    pub limit: usize,
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+impl PageRequest {
+    /// Validates the page size.
+    pub fn validate(&self) -> AppResult<()> {
+        if !(1..=MAX_PAGE_SIZE).contains(&self.limit) {
+            return Err(AppError::Configuration(format!("Page size must be between 1 and {MAX_PAGE_SIZE}")));
+        }
+        Ok(())
+    }
+}
+
+/// A page of a filtered and sorted listing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Page<T> {
+    /// Entries of the page
+    pub items: Vec<T>,
+
+    /// Number of entries matching the filters across all pages
+    pub total: usize,
+
+    /// Index of the first entry of the page
+    pub offset: usize,
+}
+
+impl<T> Page<T> {
+    /// Cuts the page `request` out of the matching entries `items`.
+    pub fn of(items: Vec<T>, request: PageRequest) -> Self {
+        let total = items.len();
+        Self {
+            items: items.into_iter().skip(request.offset).take(request.limit).collect(),
+            total,
+            offset: request.offset,
+        }
+    }
+}
+
+/// Validates label selectors (`key` or `key=value`).
+pub fn validate_labels(selectors: &[String]) -> AppResult<()> {
+    if selectors.iter().any(|selector| selector.trim().is_empty() || selector.starts_with('=')) {
+        return Err(AppError::Configuration("Label selectors must have a non-empty key".to_string()));
+    }
+    Ok(())
+}
+
+/// Whether `labels` match every selector.
+pub fn labels_match(labels: &HashMap<String, String>, selectors: &[String]) -> bool {
+    selectors.iter().all(|selector| match selector.split_once('=') {
+        Some((key, value)) => labels.get(key).is_some_and(|v| v == value),
+        None => labels.contains_key(selector.as_str()),
+    })
+}
+
+/// Case-insensitive substring match; no `pattern` matches everything.
+pub fn contains_ignore_case(text: &str, pattern: Option<&str>) -> bool {
+    pattern.is_none_or(|pattern| text.to_lowercase().contains(&pattern.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_of() {
+        let page = Page::of((0..120).collect(), PageRequest { offset: 100, limit: 50 });
+        assert_eq!(page.items, (100..120).collect::<Vec<_>>());
+        assert_eq!(page.total, 120);
+        assert!(Page::of(vec![1, 2], PageRequest { offset: 5, limit: 1 }).items.is_empty());
+
+        assert!(PageRequest::default().validate().is_ok());
+        assert!(PageRequest { offset: 0, limit: 0 }.validate().is_err());
+        assert!(PageRequest { offset: 0, limit: MAX_PAGE_SIZE + 1 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_label_selectors() {
+        let labels = HashMap::from([("redsys.managed".to_string(), "true".to_string())]);
+        assert!(labels_match(&labels, &["redsys.managed".to_string()]));
+        assert!(labels_match(&labels, &["redsys.managed=true".to_string()]));
+        assert!(!labels_match(&labels, &["redsys.managed=false".to_string()]));
+        assert!(validate_labels(&["=true".to_string()]).is_err());
+        assert!(contains_ignore_case("redsys-job-1", Some("JOB")));
+    }
+}
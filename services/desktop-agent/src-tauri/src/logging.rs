@@ -0,0 +1,364 @@
+//! Logging subsystem for RedSys Desktop Agent
+//!
+//! Tracing output goes to stdout as before, or to stderr when stdout carries
+//! headless status output, and additionally to JSON log files in the platform
+//! log directory so that logs survive when the agent is not launched from a
+//! terminal.
+//!
+//! ## Location
+//! - **Linux**: `$XDG_DATA_HOME/tn.com.redsys-desktop-agent/logs`
+//! - **macOS**: `~/Library/Logs/tn.com.redsys-desktop-agent`
+//! - **Windows**: `%LOCALAPPDATA%\tn.com.redsys-desktop-agent\logs`
+//!
+//! ## Runtime Level Control
+//! The filter is installed behind a [`reload`] layer, so support can raise
+//! the verbosity of a single target, e.g. `desktop_agent_lib::docker_monitor`
+//! to `debug`, on a running agent. Per-target overrides are layered on top of
+//! the base directives from `RUST_LOG` or `logging.level`.
+//!
+//! ## Retention
+//! - Files rotate daily (`redsys-agent.YYYY-MM-DD.log`)
+//! - At most [`MAX_LOG_FILES`] files are kept
+//! - Oldest files are deleted at startup while the directory exceeds
+//!   [`MAX_TOTAL_LOG_BYTES`]
+//!
+//! ## References
+//! - [tracing-appender](https://docs.rs/tracing-appender/latest/tracing_appender/)
+//! - [Reload Layer](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/reload/index.html)
+//! - [JSON formatter](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/fmt/format/struct.Json.html)
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::info;
+use tracing::level_filters::LevelFilter;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, fmt::writer::BoxMakeWriter, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+use crate::config::TelemetryConfig;
+use crate::error::{AppError, AppResult};
+use crate::telemetry::CorrelationLayer;
+use crate::APP_IDENTIFIER;
+
+/// Log file name prefix.
+pub const LOG_FILE_PREFIX: &str = "redsys-agent";
+
+/// Log file name suffix.
+pub const LOG_FILE_SUFFIX: &str = "log";
+
+/// Maximum number of rotated log files to keep.
+pub const MAX_LOG_FILES: usize = 7;
+
+/// Maximum total size of the log directory.
+pub const MAX_TOTAL_LOG_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Stream that console log output goes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Console {
+    /// Standard output
+    Stdout,
+
+    /// Standard error, e.g. when stdout carries headless status output
+    Stderr,
+}
+
+/// Active logging configuration.
+///
+/// Holds the background writer guard; buffered file output is flushed when
+/// this is dropped.
+#[derive(Debug)]
+pub struct Logging {
+    /// Directory of the log files, if file logging is active
+    directory: Option<PathBuf>,
+
+    /// Handle to swap the active filter
+    filter_handle: reload::Handle<EnvFilter, Registry>,
+
+    /// Base directives and per-target level overrides
+    directives: Mutex<FilterDirectives>,
+
+    /// Keeps the non-blocking file writer alive
+    _guard: Option<WorkerGuard>,
+
+    /// OTLP span exporter, if trace export is active
+    #[cfg(feature = "otel")]
+    tracer_provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Logging {
+    /// Returns the platform log directory.
+    pub fn default_directory() -> Option<PathBuf> {
+        if cfg!(target_os = "macos") {
+            dirs::home_dir().map(|home| home.join("Library").join("Logs").join(APP_IDENTIFIER))
+        } else {
+            dirs::data_local_dir().map(|dir| dir.join(APP_IDENTIFIER).join("logs"))
+        }
+    }
+
+    /// Initializes the global tracing subscriber.
+    ///
+    /// Console output goes to `console`. `RUST_LOG` takes precedence over
+    /// `level`. If the log directory or the trace exporter cannot be prepared,
+    /// the agent keeps running without them and the errors are returned
+    /// alongside so the caller can report them.
+    pub fn init(level: &str, telemetry: &TelemetryConfig, console: Console) -> (Self, Vec<AppError>) {
+        let mut errors = Vec::new();
+        let base = std::env::var(EnvFilter::DEFAULT_ENV)
+            .ok()
+            .filter(|directives| EnvFilter::try_new(directives).is_ok())
+            .unwrap_or_else(|| level.to_string());
+        let (filter_layer, filter_handle) = reload::Layer::new(EnvFilter::new(&base));
+
+        let file_writer = match Self::default_directory() {
+            Some(directory) => Self::file_appender(&directory).map(|appender| (directory, appender)),
+            None => Err(AppError::Configuration("No platform log directory available".to_string())),
+        };
+        let (directory, file_layer, guard) = match file_writer {
+            Ok((directory, appender)) => {
+                let (writer, guard) = tracing_appender::non_blocking(appender);
+                let layer = fmt::layer().json().with_writer(writer);
+                (Some(directory), Some(layer), Some(guard))
+            }
+            Err(e) => {
+                errors.push(e);
+                (None, None, None)
+            }
+        };
+
+        #[cfg(feature = "otel")]
+        let (otel_layer, tracer_provider) = {
+            use opentelemetry::trace::TracerProvider as _;
+
+            let provider = telemetry.otlp_endpoint.as_deref().and_then(|endpoint| {
+                crate::telemetry::otlp::tracer_provider(endpoint, &telemetry.service_name)
+                    .map_err(|e| errors.push(e))
+                    .ok()
+            });
+            let layer = provider.as_ref().map(|provider| {
+                tracing_opentelemetry::layer().with_tracer(provider.tracer(telemetry.service_name.clone()))
+            });
+            (layer, provider)
+        };
+        #[cfg(not(feature = "otel"))]
+        if let Some(endpoint) = &telemetry.otlp_endpoint {
+            errors.push(AppError::Configuration(format!(
+                "Trace export to {endpoint} requires building the agent with the `otel` feature"
+            )));
+        }
+
+        let subscriber = tracing_subscriber::registry()
+            .with(filter_layer)
+            .with(CorrelationLayer)
+            .with(fmt::layer().with_writer(match console {
+                Console::Stdout => BoxMakeWriter::new(std::io::stdout),
+                Console::Stderr => BoxMakeWriter::new(std::io::stderr),
+            }))
+            .with(file_layer);
+        #[cfg(feature = "otel")]
+        let subscriber = subscriber.with(otel_layer);
+        subscriber.init();
+
+        let logging = Self {
+            directory,
+            filter_handle,
+            directives: Mutex::new(FilterDirectives {
+                base,
+                overrides: BTreeMap::new(),
+            }),
+            _guard: guard,
+            #[cfg(feature = "otel")]
+            tracer_provider,
+        };
+        (logging, errors)
+    }
+
+    /// Flushes and stops trace export, if active.
+    pub fn shutdown(&self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = &self.tracer_provider {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!("Failed to shut down trace export: {e}");
+            }
+        }
+    }
+
+    /// Creates the rotating file appender, enforcing the size cap first.
+    fn file_appender(directory: &Path) -> AppResult<RollingFileAppender> {
+        std::fs::create_dir_all(directory)?;
+        enforce_size_cap(directory, MAX_TOTAL_LOG_BYTES)?;
+
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix(LOG_FILE_SUFFIX)
+            .max_log_files(MAX_LOG_FILES)
+            .build(directory)
+            .map_err(|e| AppError::Application(format!("Failed to create log file appender: {e}")))
+    }
+
+    /// Sets the level of a single target, or of all targets without an
+    /// override when `target` is `None`.
+    ///
+    /// Returns the resulting filter directives.
+    pub fn set_log_level(&self, target: Option<&str>, level: &str) -> AppResult<String> {
+        let level: LevelFilter = level
+            .parse()
+            .map_err(|_| AppError::Configuration(format!("Invalid log level '{level}'")))?;
+
+        let mut directives = self.directives.lock().unwrap_or_else(|e| e.into_inner());
+        let mut updated = directives.clone();
+        match target.map(str::trim).filter(|target| !target.is_empty()) {
+            Some(target) => {
+                updated.overrides.insert(target.to_string(), level.to_string().to_lowercase());
+            }
+            None => updated.base = level.to_string().to_lowercase(),
+        }
+        self.apply(&updated)?;
+        *directives = updated;
+
+        Ok(directives.to_string())
+    }
+
+    /// Replaces the base directives, e.g. after `logging.level` changed.
+    /// Per-target overrides are kept.
+    pub fn set_base_directives(&self, base: &str) -> AppResult<()> {
+        let mut directives = self.directives.lock().unwrap_or_else(|e| e.into_inner());
+        let updated = FilterDirectives {
+            base: base.to_string(),
+            overrides: directives.overrides.clone(),
+        };
+        self.apply(&updated)?;
+        *directives = updated;
+        Ok(())
+    }
+
+    /// Gets the active filter directives.
+    pub fn directives(&self) -> String {
+        self.directives.lock().unwrap_or_else(|e| e.into_inner()).to_string()
+    }
+
+    /// Installs a filter built from `directives`.
+    fn apply(&self, directives: &FilterDirectives) -> AppResult<()> {
+        let directives = directives.to_string();
+        let filter = EnvFilter::try_new(&directives)
+            .map_err(|e| AppError::Configuration(format!("Invalid log filter '{directives}': {e}")))?;
+        self.filter_handle
+            .reload(filter)
+            .map_err(|e| AppError::Application(format!("Failed to reload log filter: {e}")))?;
+
+        info!("Log filter set to '{}'", directives);
+        Ok(())
+    }
+
+    /// Directory of the log files, if file logging is active.
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    /// Path of the log file currently being written, if any.
+    pub fn current_log_file(&self) -> Option<PathBuf> {
+        let directory = self.directory.as_deref()?;
+        log_files(directory).ok()?.into_iter().next().map(|(path, _, _)| path)
+    }
+}
+
+/// Base directives plus per-target level overrides.
+#[derive(Debug, Clone, PartialEq)]
+struct FilterDirectives {
+    /// Directives from `RUST_LOG` or `logging.level`
+    base: String,
+
+    /// Level per target, applied after the base directives
+    overrides: BTreeMap<String, String>,
+}
+
+impl std::fmt::Display for FilterDirectives {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.base)?;
+        for (target, level) in &self.overrides {
+            write!(f, ",{target}={level}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists log files as `(path, modified, size)`, newest first.
+fn log_files(directory: &Path) -> AppResult<Vec<(PathBuf, SystemTime, u64)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let is_log_file = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX) && name.ends_with(LOG_FILE_SUFFIX));
+        let metadata = entry.metadata()?;
+        if is_log_file && metadata.is_file() {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((entry.path(), modified, metadata.len()));
+        }
+    }
+    files.sort_by_key(|(_, modified, _)| std::cmp::Reverse(*modified));
+    Ok(files)
+}
+
+/// Deletes the oldest log files until the total size fits `max_total_bytes`.
+///
+/// The newest file is always kept. Returns the number of deleted files.
+fn enforce_size_cap(directory: &Path, max_total_bytes: u64) -> AppResult<usize> {
+    let mut total = 0;
+    let mut deleted = 0;
+    for (index, (path, _, size)) in log_files(directory)?.into_iter().enumerate() {
+        total += size;
+        if index > 0 && total > max_total_bytes {
+            std::fs::remove_file(&path)?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_directives_display() {
+        let mut directives = FilterDirectives {
+            base: "info".to_string(),
+            overrides: BTreeMap::new(),
+        };
+        assert_eq!(directives.to_string(), "info");
+
+        directives.overrides.insert("desktop_agent_lib::docker_monitor".to_string(), "debug".to_string());
+        directives.overrides.insert("bollard".to_string(), "warn".to_string());
+        assert_eq!(
+            directives.to_string(),
+            "info,bollard=warn,desktop_agent_lib::docker_monitor=debug"
+        );
+        assert!(EnvFilter::try_new(directives.to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_enforce_size_cap_deletes_oldest() {
+        let directory = std::env::temp_dir().join(format!("redsys-logging-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        for day in 1..=3 {
+            let path = directory.join(format!("{LOG_FILE_PREFIX}.2025-01-0{day}.{LOG_FILE_SUFFIX}"));
+            std::fs::write(&path, vec![b'x'; 100]).unwrap();
+            let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(day * 86_400);
+            std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        }
+        std::fs::write(directory.join("unrelated.txt"), vec![b'x'; 1000]).unwrap();
+
+        assert_eq!(enforce_size_cap(&directory, 250).unwrap(), 1);
+        assert!(!directory.join(format!("{LOG_FILE_PREFIX}.2025-01-01.{LOG_FILE_SUFFIX}")).exists());
+        assert!(directory.join("unrelated.txt").exists());
+        assert_eq!(enforce_size_cap(&directory, 10).unwrap(), 1);
+        assert_eq!(log_files(&directory).unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
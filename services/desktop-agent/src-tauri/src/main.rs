@@ -5,50 +5,1516 @@
 //! monitoring Docker daemon status and system resources.
 
 use desktop_agent_lib::{
-    initialize_app, get_app_state, cleanup_app,
-    types::AppState,
+    initialize_app, cleanup_app,
+    types::{AppState, AppStatePatch},
     error::AppError,
 };
-use desktop_agent_lib::docker_monitor::{DockerMonitor, DockerStatus};
+use desktop_agent_lib::agent_metrics::{self, AgentMetrics};
+use desktop_agent_lib::app_state::AppStateStore;
+use desktop_agent_lib::autostart;
+use desktop_agent_lib::availability::{AvailabilityStats, AvailabilityTracker};
+use desktop_agent_lib::cli;
+use desktop_agent_lib::clock::DriftMonitor;
+use desktop_agent_lib::compression::{self, Compression};
+use desktop_agent_lib::backend::{BackendClient, BackendCommand, BackendConnectionState, BackendSettings};
+use desktop_agent_lib::capability_profile::{CapabilityProfile, CapabilityProfiler};
+use desktop_agent_lib::config::{AgentConfig, ConfigChange, ConfigManager};
+use desktop_agent_lib::deep_links::{self, PendingConnect};
+use desktop_agent_lib::disk_pressure::{DiskPressure, DiskPressureMonitor};
+use desktop_agent_lib::docker::{
+    ContainerInfo, ContainerProcesses, ContainerQuery, DockerDiskUsage, DockerInfo, DockerService, EventFilters,
+    FileChange,
+};
+use desktop_agent_lib::docker_monitor::{self, DockerMonitor, DockerStatus, MonitorIntervals};
+use desktop_agent_lib::doctor::{self, DoctorReport};
+use desktop_agent_lib::engine;
+use desktop_agent_lib::events::{self, Bus};
+use desktop_agent_lib::faults::{self, Fault, FaultInjector};
+use desktop_agent_lib::gpu::{self, GpuInfo, GpuMetrics, GpuMetricsStreamer};
+use desktop_agent_lib::headless;
+use desktop_agent_lib::host_paths::HostPaths;
+use desktop_agent_lib::image_updates::{ImageUpdate, ImageUpdateChecker};
+use desktop_agent_lib::images::{self, BuildProgress, ImageAnalysis, ImageQuery, ImageTransferProgress};
+use desktop_agent_lib::jobs::{JobManager, JobRecord, JobSpec};
+use desktop_agent_lib::listing::PageRequest;
+use desktop_agent_lib::logging::{Console, Logging};
+use desktop_agent_lib::network::NetworkMonitor;
+use desktop_agent_lib::network_probe::{NetworkProbe, ProbeReport};
+use desktop_agent_lib::notifications::Notifier;
+use desktop_agent_lib::orphans::OrphanCollector;
+use desktop_agent_lib::pause::PauseControl;
+use desktop_agent_lib::policy::ImagePolicy;
+use desktop_agent_lib::ports::{self, PortOverview};
+use desktop_agent_lib::power::{BatteryThrottle, ResumeWatcher};
+use desktop_agent_lib::provider::{self, HardwareFingerprint, ProviderRegistration, ProviderStore};
+use desktop_agent_lib::prune::{PrunePolicy, PruneScheduler};
+use desktop_agent_lib::routing::EventRouter;
+use desktop_agent_lib::security::{self, SecurityReport};
+use desktop_agent_lib::service;
+use desktop_agent_lib::signatures::SignatureVerifier;
+use desktop_agent_lib::simulation::{self, Simulator};
+use desktop_agent_lib::status_server::StatusServer;
+use desktop_agent_lib::supervisor::{RestartPolicy, Supervisor};
+use desktop_agent_lib::telemetry;
+use desktop_agent_lib::transfer::{self, CopyProgress};
+use desktop_agent_lib::tray;
+use desktop_agent_lib::webhooks::WebhookNotifier;
+use desktop_agent_lib::system_monitor::{SystemMetrics, SystemMonitor};
+use desktop_agent_lib::workspace::{WorkspaceManager, WorkspaceUsage};
+use std::process::ExitCode;
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 use tokio::time::{sleep, Duration};
-use tracing::{error, info};
+use tracing::{debug, error, info, instrument, warn};
+use tauri::ipc::{Channel, IpcResponse, Response};
 use tauri::Manager;
-use tauri::Listener;
 
-/// Tauri command to get application state
+/// Tauri command to get application state
+/// 
+/// Returns the current application state.
+/// 
+/// # Returns
+/// 
+/// Returns the current application state
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_application_state(state: tauri::State<'_, Arc<AppStateStore>>) -> Result<AppState, AppError> {
+    info!("Getting application state");
+    
+    let state = state.get().await;
+    Ok(state)
+}
+
+/// Tauri command to update single fields of the application state
+/// 
+/// Fields missing from the patch keep their value. A patch with
+/// `base_revision` fails if the state was updated after that revision, so
+/// the caller can refetch and retry instead of overwriting the other change.
+/// 
+/// # Arguments
+/// 
+/// * `patch` - Fields to change and, optionally, the revision they are based on
+/// 
+/// # Returns
+/// 
+/// Returns the updated application state or a conflict error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn patch_app_state(
+    state: tauri::State<'_, Arc<AppStateStore>>,
+    patch: AppStatePatch,
+) -> Result<AppState, AppError> {
+    info!("Patching application state");
+    
+    state.patch(patch).await.inspect_err(|e| {
+        error!("Failed to patch application state: {}", e);
+    })
+}
+
+/// Tauri command to get Docker daemon status
+/// 
+/// Returns the current Docker daemon status without performing a new check.
+/// 
+/// # Returns
+/// 
+/// Returns Docker status information or an error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_docker_status(state: tauri::State<'_, Arc<DockerMonitor>>) -> Result<DockerStatus, AppError> {
+    info!("Getting Docker daemon status");
+    
+    match state.get_current_status().await {
+        status => {
+            info!("Docker status retrieved successfully: {:?}", status);
+            Ok(status)
+        }
+    }
+}
+
+/// Tauri command to check the Docker daemon immediately
+/// 
+/// Triggers the monitor's health probe out-of-band from the polling interval,
+/// e.g. for a refresh button.
+/// 
+/// # Returns
+/// 
+/// Returns the fresh Docker status or an error if the check did not complete
+#[tauri::command]
+#[instrument(skip_all)]
+async fn force_docker_check(state: tauri::State<'_, Arc<DockerMonitor>>) -> Result<DockerStatus, AppError> {
+    info!("Forcing Docker daemon check");
+    
+    state.force_check().await.map_err(|e| {
+        error!("Failed to force Docker check: {}", e);
+        e.into()
+    })
+}
+
+/// Tauri command to start the local Docker engine
+/// 
+/// Launches Docker Desktop on Windows and macOS, or starts the `docker`
+/// systemd unit on Linux behind a polkit prompt, then waits until the
+/// monitor reports the daemon running. Requires `docker.allow_engine_control`.
+/// 
+/// # Returns
+/// 
+/// Returns the Docker status once the daemon is up or an error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn start_docker_engine(
+    config: tauri::State<'_, Arc<ConfigManager>>,
+    monitor: tauri::State<'_, Arc<DockerMonitor>>,
+) -> Result<DockerStatus, AppError> {
+    info!("Starting Docker engine");
+    
+    if !config.get().docker.allow_engine_control {
+        return Err(AppError::Permission(
+            "Starting Docker is disabled; set docker.allow_engine_control".to_string(),
+        ));
+    }
+    engine::start(&monitor).await.inspect_err(|e| {
+        error!("Failed to start Docker engine: {}", e);
+    })
+}
+
+/// Tauri command to pause Docker monitoring
+/// 
+/// Suspends daemon polling, the events stream, disk usage reports and
+/// scheduled prunes until `resume_monitoring` is called. The monitor reports
+/// `DockerStatus::Paused` meanwhile.
+/// 
+/// # Returns
+/// 
+/// Returns `true` if monitoring was running and is now paused
+#[tauri::command]
+#[instrument(skip_all)]
+async fn pause_monitoring(state: tauri::State<'_, Arc<PauseControl>>) -> Result<bool, AppError> {
+    info!("Pausing Docker monitoring");
+    
+    Ok(state.pause())
+}
+
+/// Tauri command to resume Docker monitoring
+/// 
+/// # Returns
+/// 
+/// Returns `true` if monitoring was paused and is now running again
+#[tauri::command]
+#[instrument(skip_all)]
+async fn resume_monitoring(state: tauri::State<'_, Arc<PauseControl>>) -> Result<bool, AppError> {
+    info!("Resuming Docker monitoring");
+    
+    Ok(state.resume())
+}
+
+/// Tauri command to make the Docker monitor report a daemon failure
+/// 
+/// QA only: needs a build with the `fault-injection` feature. Monitor checks
+/// report `fault` for `duration_secs`, then look at the daemon again. The
+/// monitor checks right away, so the UI reacts without waiting for the next
+/// poll.
+/// 
+/// # Returns
+/// 
+/// Returns the Docker status with the fault applied or an error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn inject_fault(
+    faults: tauri::State<'_, Arc<FaultInjector>>,
+    monitor: tauri::State<'_, Arc<DockerMonitor>>,
+    fault: Fault,
+    duration_secs: u64,
+) -> Result<DockerStatus, AppError> {
+    faults.inject(fault, Duration::from_secs(duration_secs)).inspect_err(|e| {
+        error!("Failed to inject fault: {}", e);
+    })?;
+    monitor.force_check().await.map_err(|e| {
+        error!("Failed to check Docker after injecting a fault: {}", e);
+        e.into()
+    })
+}
+
+/// Tauri command to end an injected fault early
+#[tauri::command]
+#[instrument(skip_all)]
+async fn clear_fault(faults: tauri::State<'_, Arc<FaultInjector>>) -> Result<(), AppError> {
+    faults.clear();
+    Ok(())
+}
+
+/// Tauri command to publish a Docker event as if the daemon sent it
+/// 
+/// QA only: needs a build with the `fault-injection` feature. `event_json`
+/// is Docker Engine event JSON, e.g. a line of
+/// `docker events --format '{{json .}}'`.
+/// 
+/// # Returns
+/// 
+/// Returns `true` if the event passed the active filters and was published
+#[tauri::command]
+#[instrument(skip_all)]
+async fn inject_event(
+    faults: tauri::State<'_, Arc<FaultInjector>>,
+    docker_service: tauri::State<'_, Arc<DockerService>>,
+    event_json: String,
+) -> Result<bool, AppError> {
+    faults.inject_event(&docker_service, &event_json).await.inspect_err(|e| {
+        error!("Failed to inject Docker event: {}", e);
+    })
+}
+
+/// Tauri command to get the Docker monitor polling intervals
+/// 
+/// # Returns
+/// 
+/// Returns the active quick, fast and normal polling intervals
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_monitor_intervals(state: tauri::State<'_, Arc<DockerMonitor>>) -> Result<MonitorIntervals, AppError> {
+    info!("Getting Docker monitor intervals");
+    
+    Ok(state.intervals())
+}
+
+/// Tauri command to adjust the Docker monitor polling intervals
+/// 
+/// Applies to the running monitor after its next check. The change is not
+/// persisted; a later `monitor.polling_interval_ms` configuration change
+/// resets all three intervals to that value.
+/// 
+/// # Arguments
+/// 
+/// * `quick_ms` - Interval during status transitions and suspected restarts
+/// * `fast_ms` - Interval while a new status settles
+/// * `normal_ms` - Interval once the status is stable
+/// 
+/// # Returns
+/// 
+/// Returns success or a validation error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn set_monitor_intervals(
+    state: tauri::State<'_, Arc<DockerMonitor>>,
+    quick_ms: u64,
+    fast_ms: u64,
+    normal_ms: u64,
+) -> Result<(), AppError> {
+    info!("Setting Docker monitor intervals to {}/{}/{}ms", quick_ms, fast_ms, normal_ms);
+    
+    state.set_intervals(MonitorIntervals { quick_ms, fast_ms, normal_ms }).inspect_err(|e| {
+        error!("Failed to set monitor intervals: {}", e);
+    })
+}
+
+/// Tauri command to get Docker disk usage
+/// 
+/// Performs a fresh `docker system df` query covering images, containers,
+/// volumes and build cache.
+/// 
+/// # Returns
+/// 
+/// Returns the disk usage report or an error if the daemon is unreachable
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_docker_disk_usage(state: tauri::State<'_, Arc<DockerService>>) -> Result<DockerDiskUsage, AppError> {
+    info!("Getting Docker disk usage");
+    
+    state.get_disk_usage().await.map_err(|e| {
+        error!("Failed to get Docker disk usage: {}", e);
+        e.into()
+    })
+}
+
+/// Tauri command to get Docker daemon information
+/// 
+/// Returns a subset of `docker info`, cached while the daemon is up and
+/// refreshed whenever it comes back.
+/// 
+/// # Returns
+/// 
+/// Returns the daemon information or an error if the daemon is unreachable
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_docker_info(state: tauri::State<'_, Arc<DockerService>>) -> Result<DockerInfo, AppError> {
+    info!("Getting Docker info");
+    
+    state.get_info().await.map_err(|e| {
+        error!("Failed to get Docker info: {}", e);
+        e.into()
+    })
+}
+
+/// Tauri command to list Docker containers a page at a time
+/// 
+/// Each entry includes the container's HEALTHCHECK state.
+/// 
+/// # Arguments
+/// 
+/// * `all` - Include stopped containers (defaults to `false`)
+/// * `query` - Status, label and name filters and the sort (defaults to
+///   all containers by name)
+/// * `page` - Offset and size of the page (defaults to the first 50)
+/// * `compression` - Encoding to compress a large page with
+/// 
+/// # Returns
+/// 
+/// Returns the page of containers or an error if the filters are invalid or
+/// the daemon is unreachable
+#[tauri::command]
+#[instrument(skip_all)]
+async fn list_containers(
+    state: tauri::State<'_, Arc<DockerService>>,
+    all: Option<bool>,
+    query: Option<ContainerQuery>,
+    page: Option<PageRequest>,
+    compression: Option<Compression>,
+) -> Result<Response, AppError> {
+    info!("Listing Docker containers");
+    
+    let page = state
+        .query_containers(all.unwrap_or(false), &query.unwrap_or_default(), page.unwrap_or_default())
+        .await
+        .inspect_err(|e| {
+            error!("Failed to list containers: {}", e);
+        })?;
+    compression::respond(page, compression).await
+}
+
+/// Tauri command to list local images a page at a time
+/// 
+/// # Arguments
+/// 
+/// * `query` - Dangling, label and name filters and the sort (defaults to
+///   all images by name)
+/// * `page` - Offset and size of the page (defaults to the first 50)
+/// * `compression` - Encoding to compress a large page with
+/// 
+/// # Returns
+/// 
+/// Returns the page of images or an error if the filters are invalid or the
+/// daemon is unreachable
+#[tauri::command]
+#[instrument(skip_all)]
+async fn list_images(
+    state: tauri::State<'_, Arc<DockerService>>,
+    query: Option<ImageQuery>,
+    page: Option<PageRequest>,
+    compression: Option<Compression>,
+) -> Result<Response, AppError> {
+    info!("Listing images");
+    
+    let client = state.docker().await?;
+    let page = images::list_images(&client, &query.unwrap_or_default(), page.unwrap_or_default())
+        .await
+        .inspect_err(|e| {
+            error!("Failed to list images: {}", e);
+        })?;
+    compression::respond(page, compression).await
+}
+
+/// Tauri command to list the containers the agent created
+/// 
+/// Containers are recognized by their `redsys.managed=true` label, so the
+/// user's other workloads are left out.
+/// 
+/// # Arguments
+/// 
+/// * `all` - Include stopped containers (defaults to `false`)
+/// * `job_id` - Only the container of this job
+/// 
+/// # Returns
+/// 
+/// Returns the container list or an error if the daemon is unreachable
+#[tauri::command]
+#[instrument(skip_all)]
+async fn list_managed_containers(
+    state: tauri::State<'_, Arc<DockerService>>,
+    all: Option<bool>,
+    job_id: Option<String>,
+) -> Result<Vec<ContainerInfo>, AppError> {
+    info!("Listing RedSys-managed containers");
+    
+    state.list_managed_containers(all.unwrap_or(false), job_id.as_deref()).await.map_err(|e| {
+        error!("Failed to list managed containers: {}", e);
+        e.into()
+    })
+}
+
+/// Tauri command to remove the containers the agent created
+/// 
+/// Only containers labelled `redsys.managed=true` are touched.
+/// 
+/// # Arguments
+/// 
+/// * `job_id` - Only the container of this job
+/// * `force` - Also remove running containers (defaults to `false`)
+/// 
+/// # Returns
+/// 
+/// Returns the names of the removed containers or an error if the daemon is
+/// unreachable
+#[tauri::command]
+#[instrument(skip_all)]
+async fn cleanup_managed_containers(
+    state: tauri::State<'_, Arc<DockerService>>,
+    job_id: Option<String>,
+    force: Option<bool>,
+) -> Result<Vec<String>, AppError> {
+    info!("Cleaning up RedSys-managed containers");
+    
+    state.remove_managed_containers(job_id.as_deref(), force.unwrap_or(false)).await.map_err(|e| {
+        error!("Failed to clean up managed containers: {}", e);
+        e.into()
+    })
+}
+
+/// Tauri command to list the files changed in a container, as `docker diff`
+/// 
+/// # Arguments
+/// 
+/// * `id` - Container ID or name
+/// 
+/// # Returns
+/// 
+/// Returns the changed files or an error if the container does not exist
+#[tauri::command]
+#[instrument(skip_all)]
+async fn container_diff(state: tauri::State<'_, Arc<DockerService>>, id: String) -> Result<Vec<FileChange>, AppError> {
+    info!("Listing changed files of container {}", id);
+    
+    state.container_diff(&id).await.map_err(|e| {
+        error!("Failed to list changed files of container {}: {}", id, e);
+        e.into()
+    })
+}
+
+/// Tauri command to list the processes running in a container, as `docker top`
+/// 
+/// # Arguments
+/// 
+/// * `id` - Container ID or name
+/// 
+/// # Returns
+/// 
+/// Returns the processes or an error if the container is not running
+#[tauri::command]
+#[instrument(skip_all)]
+async fn container_top(state: tauri::State<'_, Arc<DockerService>>, id: String) -> Result<ContainerProcesses, AppError> {
+    info!("Listing processes of container {}", id);
+    
+    state.container_top(&id).await.map_err(|e| {
+        error!("Failed to list processes of container {}: {}", id, e);
+        e.into()
+    })
+}
+
+/// Sends command progress to the frontend; the command carries on if the
+/// frontend dropped the channel
+fn send_progress<T: IpcResponse>(channel: &Channel<T>, progress: T) {
+    if let Err(e) = channel.send(progress) {
+        debug!("Dropped progress for channel {}: {}", channel.id(), e);
+    }
+}
+
+/// Tauri command to copy a file or directory from the host into a container,
+/// sending progress to a channel
+/// 
+/// # Arguments
+/// 
+/// * `host_path` - File or directory to copy, inside the workspace root or
+///   `host_paths.allowed_roots`
+/// * `id` - Container ID or name
+/// * `container_path` - Absolute path of the copy in the container
+/// * `on_progress` - Channel receiving the progress
+/// 
+/// # Returns
+/// 
+/// Returns the number of bytes transferred or an error if the copy failed
+#[tauri::command]
+#[instrument(skip_all)]
+async fn copy_to_container(
+    state: tauri::State<'_, Arc<DockerService>>,
+    host_paths: tauri::State<'_, Arc<HostPaths>>,
+    id: String,
+    host_path: String,
+    container_path: String,
+    on_progress: Channel<CopyProgress>,
+) -> Result<u64, AppError> {
+    info!("Copying {} to container {}", host_path, id);
+    
+    let client = state.docker().await?;
+    transfer::copy_to_container(&client, &host_paths, &id, host_path.as_ref(), &container_path, move |progress| {
+        send_progress(&on_progress, progress)
+    })
+    .await
+    .map_err(|e| {
+        error!("Failed to copy {} to container {}: {}", host_path, id, e);
+        e
+    })
+}
+
+/// Tauri command to copy a file or directory from a container to the host,
+/// sending progress to a channel
+/// 
+/// # Arguments
+/// 
+/// * `id` - Container ID or name
+/// * `container_path` - Absolute path of the file or directory to copy
+/// * `host_path` - Path of the copy on the host, inside the workspace root
+///   or `host_paths.allowed_roots`; it must not exist yet
+/// * `on_progress` - Channel receiving the progress
+/// 
+/// # Returns
+/// 
+/// Returns the number of bytes transferred or an error if the copy failed
+#[tauri::command]
+#[instrument(skip_all)]
+async fn copy_from_container(
+    state: tauri::State<'_, Arc<DockerService>>,
+    host_paths: tauri::State<'_, Arc<HostPaths>>,
+    id: String,
+    container_path: String,
+    host_path: String,
+    on_progress: Channel<CopyProgress>,
+) -> Result<u64, AppError> {
+    info!("Copying {} from container {}", container_path, id);
+    
+    let client = state.docker().await?;
+    transfer::copy_from_container(&client, &host_paths, &id, &container_path, host_path.as_ref(), move |progress| {
+        send_progress(&on_progress, progress)
+    })
+    .await
+    .map_err(|e| {
+        error!("Failed to copy {} from container {}: {}", container_path, id, e);
+        e
+    })
+}
+
+/// Tauri command to list the ports published by containers and the host
+/// ports requested by unfinished jobs that are already taken
+/// 
+/// # Returns
+/// 
+/// Returns the published ports and port conflicts, or an error if Docker is
+/// unavailable
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_port_overview(
+    docker_service: tauri::State<'_, Arc<DockerService>>,
+    job_manager: tauri::State<'_, Arc<JobManager>>,
+) -> Result<PortOverview, AppError> {
+    info!("Getting port overview");
+    
+    ports::overview(&docker_service, &job_manager).await.map_err(|e| {
+        error!("Failed to get port overview: {}", e);
+        e.into()
+    })
+}
+
+/// Tauri command to build an image from a local build context, sending
+/// progress to a channel
+/// 
+/// # Arguments
+/// 
+/// * `context_path` - Build context directory, inside the workspace root or
+///   `host_paths.allowed_roots`
+/// * `dockerfile` - Dockerfile path relative to the context, `Dockerfile` by default
+/// * `tags` - References to tag the image with
+/// * `on_progress` - Channel receiving the build output
+/// 
+/// # Returns
+/// 
+/// Returns the ID of the built image or an error if the build failed
+#[tauri::command]
+#[instrument(skip_all)]
+async fn build_image(
+    state: tauri::State<'_, Arc<DockerService>>,
+    host_paths: tauri::State<'_, Arc<HostPaths>>,
+    context_path: String,
+    dockerfile: Option<String>,
+    tags: Vec<String>,
+    on_progress: Channel<BuildProgress>,
+) -> Result<String, AppError> {
+    info!("Building image from {}", context_path);
+    
+    let client = state.docker().await?;
+    images::build_image(&client, &host_paths, context_path.as_ref(), dockerfile.as_deref(), &tags, |progress| {
+        send_progress(&on_progress, progress)
+    })
+    .await
+    .inspect_err(|e| {
+        error!("Failed to build image from {}: {}", context_path, e);
+    })
+}
+
+/// Tauri command to save an image to a tar file, sending progress to a
+/// channel
+/// 
+/// # Arguments
+/// 
+/// * `reference` - Image name, tag or ID
+/// * `path` - Tar file to write, inside the workspace root or
+///   `host_paths.allowed_roots`; it must not exist yet
+/// * `on_progress` - Channel receiving the progress
+/// 
+/// # Returns
+/// 
+/// Returns the size of the tar file or an error if the export failed
+#[tauri::command]
+#[instrument(skip_all)]
+async fn export_image(
+    state: tauri::State<'_, Arc<DockerService>>,
+    host_paths: tauri::State<'_, Arc<HostPaths>>,
+    reference: String,
+    path: String,
+    on_progress: Channel<ImageTransferProgress>,
+) -> Result<u64, AppError> {
+    info!("Exporting image {} to {}", reference, path);
+    
+    let client = state.docker().await?;
+    images::export_image(&client, &host_paths, &reference, path.as_ref(), |progress| {
+        send_progress(&on_progress, progress)
+    })
+    .await
+    .inspect_err(|e| {
+        error!("Failed to export image {}: {}", reference, e);
+    })
+}
+
+/// Tauri command to load the images in a tar file, sending progress to a
+/// channel
+/// 
+/// # Arguments
+/// 
+/// * `path` - Tar file written by `export_image` or `docker save`, inside
+///   the workspace root or `host_paths.allowed_roots`
+/// * `on_progress` - Channel receiving the progress
+/// 
+/// # Returns
+/// 
+/// Returns the loaded image references or an error if the import failed
+#[tauri::command]
+#[instrument(skip_all)]
+async fn import_image(
+    state: tauri::State<'_, Arc<DockerService>>,
+    host_paths: tauri::State<'_, Arc<HostPaths>>,
+    path: String,
+    on_progress: Channel<ImageTransferProgress>,
+) -> Result<Vec<String>, AppError> {
+    info!("Importing images from {}", path);
+    
+    let client = state.docker().await?;
+    images::import_image(&client, &host_paths, path.as_ref(), move |progress| send_progress(&on_progress, progress))
+    .await
+    .inspect_err(|e| {
+        error!("Failed to import images from {}: {}", path, e);
+    })
+}
+
+/// Tauri command to break a local image down into its layers
+/// 
+/// # Arguments
+/// 
+/// * `reference` - Image name, tag or ID
+/// 
+/// # Returns
+/// 
+/// Returns per-layer sizes, the total, shared and wasted sizes, or an error
+/// if the image does not exist
+#[tauri::command]
+#[instrument(skip_all)]
+async fn analyze_image(state: tauri::State<'_, Arc<DockerService>>, reference: String) -> Result<ImageAnalysis, AppError> {
+    info!("Analyzing image {}", reference);
+    
+    let client = state.docker().await?;
+    images::analyze_image(&client, &reference).await.inspect_err(|e| {
+        error!("Failed to analyze image {}: {}", reference, e);
+    })
+}
+
+/// Tauri command to get the updates found for the images in
+/// `image_updates.images`
+/// 
+/// # Returns
+/// 
+/// Returns the stale images with their registry digests
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_image_updates(state: tauri::State<'_, Arc<ImageUpdateChecker>>) -> Result<Vec<ImageUpdate>, AppError> {
+    info!("Getting image updates");
+    
+    Ok(state.updates())
+}
+
+/// Tauri command to audit the running containers for insecure settings,
+/// emitting the report as a `security-findings` event
+/// 
+/// # Returns
+/// 
+/// Returns the findings or an error if Docker is unavailable
+#[tauri::command]
+#[instrument(skip_all)]
+async fn audit_security(
+    app_handle: tauri::AppHandle,
+    docker_service: tauri::State<'_, Arc<DockerService>>,
+) -> Result<SecurityReport, AppError> {
+    info!("Auditing container security");
+    
+    let report = security::audit(&docker_service).await.map_err(|e| {
+        error!("Failed to audit container security: {}", e);
+        AppError::from(e)
+    })?;
+    if !report.findings.is_empty() {
+        warn!("Security audit found {} insecure container setting(s)", report.findings.len());
+    }
+    if let Err(e) = telemetry::emit(&app_handle, "security-findings", &report) {
+        error!("Failed to emit security-findings event: {e}");
+    }
+    Ok(report)
+}
+
+/// Tauri command to get the Docker events stream filters
+/// 
+/// # Returns
+/// 
+/// Returns the active event filters
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_event_filters(state: tauri::State<'_, Arc<DockerService>>) -> Result<EventFilters, AppError> {
+    info!("Getting Docker event filters");
+    
+    Ok(state.get_event_filters().await)
+}
+
+/// Tauri command to configure the Docker events stream filters
+/// 
+/// The running events stream resubscribes immediately, so the frontend only
+/// receives matching events from then on.
+/// 
+/// # Arguments
+/// 
+/// * `filters` - Event types, label selectors and container name prefixes
+/// 
+/// # Returns
+/// 
+/// Returns success or a validation error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn configure_event_filters(
+    state: tauri::State<'_, Arc<DockerService>>,
+    filters: EventFilters,
+) -> Result<(), AppError> {
+    info!("Configuring Docker event filters");
+    
+    state.configure_event_filters(filters).await.inspect_err(|e| {
+        error!("Failed to configure event filters: {}", e);
+    })
+}
+
+/// Tauri command to diagnose the environment
+/// 
+/// Checks virtualization, the Docker installation and daemon, socket
+/// permissions, the GPU runtime, disk space, clock drift and backend
+/// reachability. Takes a few seconds.
+/// 
+/// # Returns
+/// 
+/// Returns the checklist with fix hints for the problems found
+#[tauri::command]
+#[instrument(skip_all)]
+async fn run_doctor(
+    config: tauri::State<'_, Arc<ConfigManager>>,
+    docker_service: tauri::State<'_, Arc<DockerService>>,
+) -> Result<DoctorReport, AppError> {
+    info!("Running environment diagnosis");
+    
+    Ok(doctor::run_doctor(&config.get(), docker_service.inner()).await)
+}
+
+/// Tauri command to get the agent configuration
+/// 
+/// Tokens and passwords are replaced by a placeholder, which
+/// `update_config` keeps as the current secret.
+/// 
+/// # Returns
+/// 
+/// Returns the current configuration without its secrets
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_config(state: tauri::State<'_, Arc<ConfigManager>>) -> Result<AgentConfig, AppError> {
+    info!("Getting agent configuration");
+    
+    Ok(state.get().redacted())
+}
+
+/// Tauri command to update the agent configuration
+/// 
+/// Validates and persists the configuration to the config file, then applies
+/// it to the running monitors and the log filter. Feature toggle changes take
+/// effect the next time the agent starts.
+/// 
+/// # Arguments
+/// 
+/// * `config` - The complete new configuration; secrets left at the
+///   `get_config` placeholder keep their current value
+/// 
+/// # Returns
+/// 
+/// Returns the changed settings or a validation error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn update_config(
+    state: tauri::State<'_, Arc<ConfigManager>>,
+    config: AgentConfig,
+) -> Result<Vec<ConfigChange>, AppError> {
+    info!("Updating agent configuration");
+    
+    state.update(config.with_secrets_from(&state.get())).inspect_err(|e| {
+        error!("Failed to update configuration: {}", e);
+    })
+}
+
+/// Tauri command to get the RedSys backend connection state
+/// 
+/// # Returns
+/// 
+/// Returns `disabled` when no backend is configured, otherwise
+/// `disconnected`, `connecting` or `connected`
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_backend_status(state: tauri::State<'_, Arc<BackendClient>>) -> Result<BackendConnectionState, AppError> {
+    info!("Getting backend connection state");
+    
+    Ok(state.connection_state().await)
+}
+
+/// Tauri command to run a job
+/// 
+/// # Arguments
+/// 
+/// * `spec` - Image, command, environment, mounts and resource limits
+/// 
+/// # Returns
+/// 
+/// Returns the queued job, or an error if the spec is invalid or the job ID
+/// is already in use
+#[tauri::command]
+#[instrument(skip_all)]
+async fn submit_job(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, Arc<JobManager>>,
+    spec: JobSpec,
+) -> Result<JobRecord, AppError> {
+    info!("Submitting job {}", spec.id);
+    
+    state.submit(app_handle, spec).await.inspect_err(|e| {
+        error!("Failed to submit job: {}", e);
+    })
+}
+
+/// Tauri command to list tracked jobs
+/// 
+/// # Arguments
+/// 
+/// * `compression` - Encoding to compress a large list with
+/// 
+/// # Returns
+/// 
+/// Returns running and recently finished jobs, newest first
+#[tauri::command]
+#[instrument(skip_all)]
+async fn list_jobs(
+    state: tauri::State<'_, Arc<JobManager>>,
+    compression: Option<Compression>,
+) -> Result<Response, AppError> {
+    info!("Listing jobs");
+    
+    compression::respond(state.jobs().await, compression).await
+}
+
+/// Tauri command to cancel a job that has not finished
+/// 
+/// # Arguments
+/// 
+/// * `id` - Job identifier
+/// 
+/// # Returns
+/// 
+/// Returns once its container is being stopped, or an error if the job is
+/// not tracked or has already finished
+#[tauri::command]
+#[instrument(skip_all)]
+async fn cancel_job(state: tauri::State<'_, Arc<JobManager>>, id: String) -> Result<(), AppError> {
+    info!("Cancelling job {}", id);
+    
+    state.cancel(&id).await.inspect_err(|e| {
+        error!("Failed to cancel job: {}", e);
+    })
+}
+
+/// Tauri command to get a job including its exit code and logs
+/// 
+/// # Arguments
+/// 
+/// * `id` - Job identifier
+/// 
+/// # Returns
+/// 
+/// Returns the job, or an error if it is not tracked
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_job(state: tauri::State<'_, Arc<JobManager>>, id: String) -> Result<JobRecord, AppError> {
+    info!("Getting job {}", id);
+    
+    state
+        .job(&id)
+        .await
+        .ok_or_else(|| AppError::NotFound { resource: format!("job '{id}'") })
+}
+
+/// Tauri command to get the host's GPUs
+/// 
+/// # Returns
+/// 
+/// Returns GPU models, VRAM, driver and CUDA version; the device list is
+/// empty on hosts without NVIDIA GPUs
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_gpu_info() -> Result<GpuInfo, AppError> {
+    info!("Getting GPU info");
+    
+    tokio::task::spawn_blocking(gpu::detect)
+        .await
+        .map_err(|e| AppError::Application(format!("GPU detection failed: {e}")))
+}
+
+/// Tauri command to get the capability profile used for job matching
+/// 
+/// # Arguments
+/// 
+/// * `refresh` - Rebuild the profile instead of returning the cached one
+/// 
+/// # Returns
+/// 
+/// Returns the versioned profile of CPU, memory, free disk space, GPUs,
+/// Docker version and enabled features
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_capability_profile(
+    state: tauri::State<'_, Arc<CapabilityProfiler>>,
+    refresh: Option<bool>,
+) -> Result<CapabilityProfile, AppError> {
+    info!("Getting capability profile");
+    
+    if refresh.unwrap_or(false) {
+        return Ok(state.refresh().await);
+    }
+    Ok(state.profile().await)
+}
+
+/// Tauri command to measure latency and throughput to the targets in
+/// `network_probe.targets` now
+/// 
+/// Waits for a probe already in progress; the report is also emitted as a
+/// `network-probe-completed` event.
+/// 
+/// # Returns
+/// 
+/// Returns the measurements per target; failed measurements carry an error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn run_network_probe(state: tauri::State<'_, Arc<NetworkProbe>>) -> Result<ProbeReport, AppError> {
+    info!("Running network probe");
+    
+    Ok(state.run().await)
+}
+
+/// Tauri command to get the recent network probe reports
+/// 
+/// # Returns
+/// 
+/// Returns the reports of the latest probes, oldest first
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_network_probe_results(state: tauri::State<'_, Arc<NetworkProbe>>) -> Result<Vec<ProbeReport>, AppError> {
+    info!("Getting network probe results");
+    
+    Ok(state.history())
+}
+
+/// Tauri command to start receiving GPU metrics on a channel
+/// 
+/// Call when a GPU dashboard panel opens and pair it with
+/// `unsubscribe_gpu_metrics` when it closes; sampling runs while at least
+/// one panel is subscribed. Channels the frontend dropped are unsubscribed
+/// automatically.
+/// 
+/// # Arguments
+/// 
+/// * `on_metrics` - Channel receiving one sample per device
+/// 
+/// # Returns
+/// 
+/// Returns the number of subscribers
+#[tauri::command]
+#[instrument(skip_all)]
+async fn subscribe_gpu_metrics(
+    state: tauri::State<'_, Arc<GpuMetricsStreamer>>,
+    on_metrics: Channel<GpuMetrics>,
+) -> Result<usize, AppError> {
+    info!("Subscribing to GPU metrics");
+    
+    Ok(state.subscribe(on_metrics))
+}
+
+/// Tauri command to stop receiving GPU metrics on a channel
+/// 
+/// # Arguments
+/// 
+/// * `channel_id` - ID of the channel passed to `subscribe_gpu_metrics`
+/// 
+/// # Returns
+/// 
+/// Returns the number of remaining subscribers
+#[tauri::command]
+#[instrument(skip_all)]
+async fn unsubscribe_gpu_metrics(
+    state: tauri::State<'_, Arc<GpuMetricsStreamer>>,
+    channel_id: u32,
+) -> Result<usize, AppError> {
+    info!("Unsubscribing from GPU metrics");
+    
+    Ok(state.unsubscribe(channel_id))
+}
+
+/// Tauri command to get host system metrics
+/// 
+/// Takes a fresh sample of CPU, memory, swap, disk and network usage. CPU
+/// usage and network throughput cover the time since the previous sample.
+/// 
+/// # Returns
+/// 
+/// Returns the host resource sample or an error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_system_metrics(state: tauri::State<'_, Arc<SystemMonitor>>) -> Result<SystemMetrics, AppError> {
+    info!("Getting system metrics");
+    
+    state.sample().await.inspect_err(|e| {
+        error!("Failed to get system metrics: {}", e);
+    })
+}
+
+/// Tauri command to get disk pressure
+/// 
+/// Checks the free space of the Docker data root, the job workspace root and
+/// the configured paths now.
+/// 
+/// # Returns
+/// 
+/// Returns the free space and pressure level of each watched partition
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_disk_pressure(state: tauri::State<'_, Arc<DiskPressureMonitor>>) -> Result<Vec<DiskPressure>, AppError> {
+    info!("Getting disk pressure");
+    
+    Ok(state.check().await)
+}
+
+/// Tauri command to get the Docker daemon availability
+/// 
+/// Summarizes the recorded daemon state timeline over the last `window_secs`
+/// seconds, up to 30 days.
+/// 
+/// # Returns
+/// 
+/// Returns the uptime percentage, outage count and mean outage length, or an
+/// error if the window is out of range
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_availability_stats(
+    state: tauri::State<'_, Arc<AvailabilityTracker>>,
+    window_secs: u64,
+) -> Result<AvailabilityStats, AppError> {
+    info!("Getting availability stats for the last {}s", window_secs);
+    
+    state.stats(Duration::from_secs(window_secs))
+}
+
+/// Tauri command to get the agent's own resource usage
+/// 
+/// Reports memory, uptime, task counts, channel depths and the latency of the
+/// last Docker check, to spot leaks and stuck streams in the field.
+/// 
+/// # Returns
+/// 
+/// Returns the agent metrics
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_agent_metrics(
+    supervisor: tauri::State<'_, Arc<Supervisor>>,
+    bus: tauri::State<'_, Arc<Bus>>,
+    docker_monitor: tauri::State<'_, Arc<DockerMonitor>>,
+    docker_service: tauri::State<'_, Arc<DockerService>>,
+    backend_client: tauri::State<'_, Arc<BackendClient>>,
+) -> Result<AgentMetrics, AppError> {
+    info!("Getting agent metrics");
+    
+    Ok(agent_metrics::collect(&supervisor, &bus, &docker_monitor, &docker_service, &backend_client).await)
+}
+
+/// Tauri command to enable starting the agent at login
+/// 
+/// Registers the current executable as a login item. Login launches start
+/// hidden in the tray.
+/// 
+/// # Returns
+/// 
+/// Returns success or an error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn enable_autostart() -> Result<(), AppError> {
+    info!("Enabling autostart");
+    
+    autostart::enable().inspect_err(|e| {
+        error!("Failed to enable autostart: {}", e);
+    })
+}
+
+/// Tauri command to disable starting the agent at login
+/// 
+/// # Returns
+/// 
+/// Returns success or an error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn disable_autostart() -> Result<(), AppError> {
+    info!("Disabling autostart");
+    
+    autostart::disable().inspect_err(|e| {
+        error!("Failed to disable autostart: {}", e);
+    })
+}
+
+/// Tauri command to check whether the agent starts at login
+/// 
+/// # Returns
+/// 
+/// Returns true if the agent is registered as a login item
+#[tauri::command]
+#[instrument(skip_all)]
+async fn is_autostart_enabled() -> Result<bool, AppError> {
+    info!("Checking autostart");
+    
+    autostart::is_enabled().inspect_err(|e| {
+        error!("Failed to check autostart: {}", e);
+    })
+}
+
+/// Tauri command to install the agent as a background service
+/// 
+/// Registers the current executable with the user's service manager, which
+/// starts it at login and restarts it when it fails. Replaces the login item.
+/// 
+/// # Returns
+/// 
+/// Returns success or an error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn install_service() -> Result<(), AppError> {
+    info!("Installing background service");
+    
+    service::install().inspect_err(|e| {
+        error!("Failed to install background service: {}", e);
+    })
+}
+
+/// Tauri command to remove the background service
+/// 
+/// # Returns
+/// 
+/// Returns success or an error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn uninstall_service() -> Result<(), AppError> {
+    info!("Uninstalling background service");
+    
+    service::uninstall().inspect_err(|e| {
+        error!("Failed to uninstall background service: {}", e);
+    })
+}
+
+/// Tauri command to check whether the agent is installed as a background
+/// service
+/// 
+/// # Returns
+/// 
+/// Returns true if the service is registered
+#[tauri::command]
+#[instrument(skip_all)]
+async fn is_service_installed() -> Result<bool, AppError> {
+    info!("Checking background service");
+    
+    service::is_installed().inspect_err(|e| {
+        error!("Failed to check background service: {}", e);
+    })
+}
+
+/// Tauri command to get the hardware fingerprint
+/// 
+/// # Returns
+/// 
+/// Returns the fingerprint submitted on provider registration
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_hardware_fingerprint(state: tauri::State<'_, Arc<ProviderStore>>) -> Result<HardwareFingerprint, AppError> {
+    info!("Getting hardware fingerprint");
+    
+    let agent_id = state.installation_id().inspect_err(|e| {
+        error!("Failed to get installation ID: {}", e);
+    })?;
+    tokio::task::spawn_blocking(move || HardwareFingerprint::collect(&agent_id))
+        .await
+        .map_err(|e| AppError::Application(format!("Fingerprint collection failed: {e}")))
+}
+
+/// Tauri command to register this machine as a compute provider
 /// 
-/// Returns the current application state.
+/// Submits the hardware fingerprint to the backend at `backend.url` and
+/// stores the returned identity. The backend connection uses it after the
+/// next restart.
+/// 
+/// # Arguments
+/// 
+/// * `token` - One-time registration token from the RedSys dashboard
 /// 
 /// # Returns
 /// 
-/// Returns the current application state
+/// Returns the provider identity without its token, or an error
 #[tauri::command]
-async fn get_application_state() -> Result<AppState, String> {
-    info!("Getting application state");
+#[instrument(skip_all)]
+async fn register_provider(
+    store: tauri::State<'_, Arc<ProviderStore>>,
+    config_manager: tauri::State<'_, Arc<ConfigManager>>,
+    token: String,
+) -> Result<ProviderRegistration, AppError> {
+    info!("Registering as provider");
     
-    let state = get_app_state().await;
-    Ok(state)
+    register_as_provider(&store, &config_manager, &token).await.inspect_err(|e| {
+        error!("Failed to register as provider: {}", e);
+    })
 }
 
-/// Tauri command to get Docker daemon status
+/// Registers with `token` at `backend.url` and stores the identity.
+async fn register_as_provider(
+    store: &ProviderStore,
+    config_manager: &ConfigManager,
+    token: &str,
+) -> Result<ProviderRegistration, AppError> {
+    let url = config_manager.get().backend.url.ok_or_else(|| {
+        AppError::Configuration("backend.url must be set to register as a provider".to_string())
+    })?;
+    let agent_id = store.installation_id()?;
+    let fingerprint = tokio::task::spawn_blocking(move || HardwareFingerprint::collect(&agent_id))
+        .await
+        .map_err(|e| AppError::Application(format!("Fingerprint collection failed: {e}")))?;
+    let identity = provider::register(&url, token, &fingerprint).await?;
+    store.save(&identity)?;
+    Ok(ProviderRegistration::from(&identity))
+}
+
+/// Tauri command to answer a `redsys://connect` deep link
 /// 
-/// Returns the current Docker daemon status without performing a new check.
+/// The link's registration token stays in the agent; this registers with it
+/// only if the user approved, and discards it either way.
+/// 
+/// # Arguments
+/// 
+/// * `approved` - Whether the user agreed to register this machine
 /// 
 /// # Returns
 /// 
-/// Returns Docker status information or an error
+/// Returns the provider identity without its token if approved, `None` if
+/// declined, or an error if no link is pending or registration failed
 #[tauri::command]
-async fn get_docker_status(state: tauri::State<'_, Arc<DockerMonitor>>) -> Result<DockerStatus, String> {
-    info!("Getting Docker daemon status");
-    
-    match state.get_current_status().await {
-        status => {
-            info!("Docker status retrieved successfully: {:?}", status);
-            Ok(status)
-        }
+#[instrument(skip_all)]
+async fn confirm_deep_link(
+    pending: tauri::State<'_, Arc<PendingConnect>>,
+    store: tauri::State<'_, Arc<ProviderStore>>,
+    config_manager: tauri::State<'_, Arc<ConfigManager>>,
+    approved: bool,
+) -> Result<Option<ProviderRegistration>, AppError> {
+    let token = pending.take().await.ok_or_else(|| AppError::NotFound {
+        resource: "pending deep link".to_string(),
+    })?;
+    if !approved {
+        info!("Provider registration from deep link declined");
+        return Ok(None);
     }
+    info!("Registering as provider from deep link");
+    
+    register_as_provider(&store, &config_manager, &token)
+        .await
+        .map(Some)
+        .inspect_err(|e| {
+            error!("Failed to register as provider: {}", e);
+        })
+}
+
+/// Tauri command to get job workspace disk usage
+/// 
+/// # Returns
+/// 
+/// Returns the quota, total usage and per-job workspace sizes
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_workspace_usage(state: tauri::State<'_, Arc<WorkspaceManager>>) -> Result<WorkspaceUsage, AppError> {
+    info!("Getting workspace usage");
+    
+    let workspaces = state.inner().clone();
+    tokio::task::spawn_blocking(move || workspaces.usage())
+        .await
+        .map_err(|e| AppError::Application(format!("Workspace measurement failed: {e}")))?
+        .inspect_err(|e| {
+            error!("Failed to measure workspaces: {}", e);
+        })
+}
+
+/// Tauri command to limit the events the calling window receives
+/// 
+/// Detached windows, e.g. logs or metrics, subscribe to the events they show
+/// so they do not receive the traffic of the others. Windows without a
+/// subscription receive every event.
+/// 
+/// # Arguments
+/// 
+/// * `events` - Event names, e.g. `docker-event`; batches and legacy names of
+///   these are included
+/// 
+/// # Returns
+/// 
+/// Returns success or a validation error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn subscribe_window_events(
+    window: tauri::WebviewWindow,
+    router: tauri::State<'_, Arc<EventRouter>>,
+    events: Vec<String>,
+) -> Result<(), AppError> {
+    info!("Routing {} event(s) to window {}", events.len(), window.label());
+    
+    router.subscribe(window.label(), events).inspect_err(|e| {
+        error!("Failed to subscribe window {} to events: {}", window.label(), e);
+    })
+}
+
+/// Tauri command to let the calling window receive every event again
+/// 
+/// # Returns
+/// 
+/// Returns whether the window had a subscription
+#[tauri::command]
+#[instrument(skip_all)]
+async fn unsubscribe_window_events(
+    window: tauri::WebviewWindow,
+    router: tauri::State<'_, Arc<EventRouter>>,
+) -> Result<bool, AppError> {
+    info!("Routing every event to window {}", window.label());
+    
+    Ok(router.unsubscribe(window.label()))
+}
+
+/// Tauri command to get the current log file path
+/// 
+/// # Returns
+/// 
+/// Returns the path of the JSON log file being written, or an error if file
+/// logging is disabled
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_log_file_path(state: tauri::State<'_, Arc<Logging>>) -> Result<String, AppError> {
+    info!("Getting log file path");
+    
+    state
+        .current_log_file()
+        .or_else(|| state.directory().map(|directory| directory.to_path_buf()))
+        .map(|path| path.display().to_string())
+        .ok_or_else(|| AppError::Configuration("File logging is disabled".to_string()))
+}
+
+/// Tauri command to quit the agent
+/// 
+/// Closing the window only hides it; this stops the monitors, jobs and the
+/// backend connection and exits, like Quit in the tray menu.
+#[tauri::command]
+#[instrument(skip_all)]
+async fn shutdown_agent(app_handle: tauri::AppHandle) {
+    info!("Shutdown requested");
+    
+    app_handle.exit(0);
+}
+
+/// Tauri command to change the log level at runtime
+/// 
+/// Lets support raise the verbosity of one module on a user's machine without
+/// rebuilding or restarting the agent. The change is not persisted.
+/// 
+/// # Arguments
+/// 
+/// * `target` - Target to override, e.g. `desktop_agent_lib::docker_monitor`;
+///   omit to change the base level
+/// * `level` - One of `trace`, `debug`, `info`, `warn`, `error` or `off`
+/// 
+/// # Returns
+/// 
+/// Returns the active filter directives or a validation error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn set_log_level(
+    state: tauri::State<'_, Arc<Logging>>,
+    target: Option<String>,
+    level: String,
+) -> Result<String, AppError> {
+    info!("Setting log level of {} to {}", target.as_deref().unwrap_or("all targets"), level);
+    
+    state.set_log_level(target.as_deref(), &level).inspect_err(|e| {
+        error!("Failed to set log level: {}", e);
+    })
+}
+
+/// Tauri command to get the scheduled prune policies
+/// 
+/// # Returns
+/// 
+/// Returns the currently active retention rules
+#[tauri::command]
+#[instrument(skip_all)]
+async fn get_prune_policies(state: tauri::State<'_, Arc<PruneScheduler>>) -> Result<Vec<PrunePolicy>, AppError> {
+    info!("Getting prune policies");
+    
+    Ok(state.get_policies().await)
+}
+
+/// Tauri command to replace the scheduled prune policies
+/// 
+/// # Arguments
+/// 
+/// * `policies` - The new set of retention rules
+/// 
+/// # Returns
+/// 
+/// Returns success or a validation error
+#[tauri::command]
+#[instrument(skip_all)]
+async fn set_prune_policies(
+    state: tauri::State<'_, Arc<PruneScheduler>>,
+    policies: Vec<PrunePolicy>,
+) -> Result<(), AppError> {
+    info!("Setting {} prune policies", policies.len());
+    
+    state.set_policies(policies).await.inspect_err(|e| {
+        error!("Failed to set prune policies: {}", e);
+    })
 }
 
 
@@ -68,44 +1534,687 @@ async fn get_docker_status(state: tauri::State<'_, Arc<DockerMonitor>>) -> Resul
 async fn setup_app(app_handle: &tauri::AppHandle) -> Result<(), AppError> {
     info!("Setting up RedSys Desktop Agent with Docker monitoring...");
     
-    // Initialize the application with app handle for event emission
-    initialize_app(Some(app_handle.clone())).await?;
+    // Reset the managed application state
+    let app_state = app_handle.state::<Arc<AppStateStore>>().inner().clone();
+    let config = app_handle.state::<Arc<ConfigManager>>().get();
+    let docker_monitor = app_handle.state::<Arc<DockerMonitor>>().inner().clone();
+    initialize_app(&app_state, &config, &docker_monitor).await?;
     
     info!("RedSys Desktop Agent setup completed successfully");
     Ok(())
 }
 
+/// Applies a changed configuration to the running services
+/// 
+/// Called for every configuration change, whether it came from the
+/// `update_config` command or from an edit of the config file.
+/// 
+/// # Arguments
+/// 
+/// * `previous` - The configuration currently applied
+/// * `config` - The new configuration
+/// * `docker_monitor` - The running Docker monitor
+/// * `docker_service` - The running Docker service
+/// * `logging` - The active logging subsystem
+async fn apply_config(
+    previous: &AgentConfig,
+    config: &AgentConfig,
+    docker_monitor: &DockerMonitor,
+    docker_service: &DockerService,
+    system_monitor: &SystemMonitor,
+    notifier: &Notifier,
+    logging: &Logging,
+) {
+    if previous.monitor.polling_interval_ms != config.monitor.polling_interval_ms {
+        docker_monitor.set_polling_interval(Duration::from_millis(config.monitor.polling_interval_ms));
+    }
+    if previous.monitor.disk_usage_interval_secs != config.monitor.disk_usage_interval_secs {
+        docker_service.set_disk_usage_interval(Duration::from_secs(config.monitor.disk_usage_interval_secs));
+    }
+    if previous.monitor.system_metrics_interval_secs != config.monitor.system_metrics_interval_secs {
+        system_monitor.set_interval(Duration::from_secs(config.monitor.system_metrics_interval_secs));
+    }
+    if previous.docker.host != config.docker.host {
+        docker_monitor::set_docker_host_override(config.docker.host.clone());
+        docker_monitor.reconnect();
+        docker_service.reconnect();
+    }
+    if previous.events != config.events {
+        if let Err(e) = docker_service.configure_event_filters(config.events.clone()).await {
+            error!("Failed to apply event filters from configuration: {}", e);
+        }
+    }
+    if previous.notifications != config.notifications {
+        notifier.configure(&config.notifications);
+    }
+    if previous.logging != config.logging {
+        if let Err(e) = logging.set_base_directives(&config.logging.level) {
+            error!("Failed to apply log level from configuration: {}", e);
+        }
+    }
+    if previous.features != config.features {
+        warn!("Feature toggle changes take effect after restarting the agent");
+    }
+}
+
 /// Main application entry point
 /// 
 /// This function initializes the Tauri application with all necessary
 /// services, commands, and event handlers.
-fn main() {
+fn main() -> ExitCode {
+    // Subcommands such as `status` run without the GUI
+    if let Some(exit_code) = cli::run_from_args() {
+        return exit_code;
+    }
+    
+    // Load configuration before logging so the configured level applies from the start
+    let (config_manager, config_error) = ConfigManager::load_default();
+    let config = config_manager.get();
+    // Headless status lines own stdout
+    let headless = headless::requested();
+    // Background services outlive their window
+    let daemon = service::requested();
+    let console = if headless { Console::Stderr } else { Console::Stdout };
+    
+    // `RUST_LOG` takes precedence over the configured level
+    let (logging, logging_errors) = Logging::init(&config.logging.level, &config.telemetry, console);
+    if let Some(directory) = logging.directory() {
+        info!("Writing logs to {}", directory.display());
+    }
+    for e in logging_errors {
+        warn!("Logging partially disabled: {}", e);
+    }
+    let logging = Arc::new(logging);
+    
+    match config_error {
+        Some(e) => warn!("Using default configuration, failed to load {}: {}", config_manager.path().display(), e),
+        None => info!("Loaded configuration from {}", config_manager.path().display()),
+    }
+    docker_monitor::set_docker_host_override(config.docker.host.clone());
+    let config_manager = Arc::new(config_manager);
+    
     // Initialize the Tauri application
     tauri::Builder::default()
-        // Add plugins
+        // Add plugins; a second instance, e.g. started by the OS for a
+        // `redsys://` link, hands its link over and exits
+        .plugin(tauri_plugin_single_instance::init(move |app_handle, _args, _cwd| {
+            if headless {
+                info!("Another instance was started, ignoring it while headless");
+                return;
+            }
+            info!("Another instance was started, focusing this one");
+            tray::open_dashboard(app_handle);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
         
         // Setup function
-        .setup(|app| {
-            // Show the window immediately when app is ready
-            let window = app.get_webview_window("main").unwrap();
-            window.show().unwrap();
+        .setup(move |app| {
+            // Show the window immediately when app is ready, unless launched
+            // at login, which starts in the tray. Headless agents get no window,
+            // background services get one when the agent is launched again.
+            let launched_at_login = autostart::launched_at_login();
+            let window = if headless {
+                info!("Running headless, no window or tray icon");
+                None
+            } else if daemon {
+                info!("Running as a background service, launch the agent again to open its window");
+                None
+            } else {
+                let window = tauri::WebviewWindowBuilder::from_config(app.handle(), &app.config().app.windows[0])?.build()?;
+                if !launched_at_login {
+                    window.show().unwrap();
+                }
+                Some(window)
+            };
+            
+            // Deliver events only to the windows subscribed to them
+            app.manage(Arc::new(EventRouter::new()));
             
             // Initialize Docker monitor
             let cancellation_token = CancellationToken::new();
-            let docker_monitor = Arc::new(DockerMonitor::new(cancellation_token.clone()));
+            // Restarts background tasks that panic or stop unexpectedly
+            let supervisor = Arc::new(
+                Supervisor::new(cancellation_token.clone()).with_app_handle(app.handle().clone()),
+            );
+            app.manage(supervisor.clone());
+            let pause_control = Arc::new(PauseControl::new());
+            let notifier = Arc::new(Notifier::new(&config.notifications));
+            let webhooks = Arc::new(WebhookNotifier::new(&config.webhooks, cancellation_token.clone()));
+            
+            // Forward events published by the subsystems to the frontend
+            let event_bus = Arc::new(Bus::new());
+            let app_state = Arc::new(AppStateStore::new().with_event_bus(event_bus.clone()));
+            let event_bus_clone = event_bus.clone();
+            let app_handle = app.handle().clone();
+            let config_manager_clone = config_manager.clone();
+            let forwarding_token = cancellation_token.clone();
+            supervisor.spawn("event-forwarding", RestartPolicy::Always, move || {
+                events::forward_to_frontend(
+                    event_bus_clone.clone(),
+                    app_handle.clone(),
+                    config_manager_clone.subscribe(),
+                    forwarding_token.clone(),
+                )
+            });
+            if headless {
+                let event_bus_clone = event_bus.clone();
+                let status_token = cancellation_token.clone();
+                supervisor.spawn("status-output", RestartPolicy::Always, move || {
+                    headless::print_status_lines(event_bus_clone.clone(), status_token.clone())
+                });
+            }
+            
+            // Fabricate the Docker side for frontend development
+            let simulator = simulation::requested(&config).then(|| Arc::new(Simulator::new(cancellation_token.clone())));
+            if let Some(simulator) = &simulator {
+                warn!("Simulation mode enabled, not connecting to Docker");
+                let simulator = simulator.clone();
+                supervisor.spawn("simulation", RestartPolicy::Always, move || simulator.clone().start());
+            }
+            
+            // Injected faults override the monitor's checks in QA builds
+            if faults::ENABLED {
+                warn!("Fault injection enabled, Docker status may be fabricated");
+            }
+            let fault_injector = Arc::new(FaultInjector::new());
+            
+            let mut docker_monitor = DockerMonitor::new(cancellation_token.clone())
+                .with_pause_control(pause_control.clone())
+                .with_notifier(notifier.clone())
+                .with_event_bus(event_bus.clone())
+                .with_fault_injector(fault_injector.clone())
+                .with_polling_interval(Duration::from_millis(config.monitor.polling_interval_ms));
+            if let Some(simulator) = &simulator {
+                docker_monitor = docker_monitor.with_client(simulator.client());
+            }
+            let docker_monitor = Arc::new(docker_monitor);
             
             // Start Docker monitoring in background
             let docker_monitor_clone = docker_monitor.clone();
             let app_handle = app.handle().clone();
-            // Use Tauri's async runtime for background tasks (official best practice)
-            tauri::async_runtime::spawn(async move {
-                docker_monitor_clone.start_monitoring(app_handle).await;
+            supervisor.spawn("docker-monitor", RestartPolicy::Always, move || {
+                docker_monitor_clone.clone().start_monitoring(app_handle.clone())
+            });
+            
+            // Keep the Docker, system and jobs sections of the app state current
+            let app_state_clone = app_state.clone();
+            let docker_monitor_clone = docker_monitor.clone();
+            let sync_token = cancellation_token.clone();
+            supervisor.spawn("app-state-sync", RestartPolicy::Always, move || {
+                app_state_clone.clone().start_sync(docker_monitor_clone.subscribe_status(), sync_token.clone())
+            });
+            
+            // Record the daemon availability timeline
+            let availability = Arc::new(AvailabilityTracker::new(
+                AvailabilityTracker::default_path(),
+                cancellation_token.clone(),
+            ));
+            let availability_clone = availability.clone();
+            let docker_monitor_clone = docker_monitor.clone();
+            supervisor.spawn("availability", RestartPolicy::Always, move || {
+                availability_clone.clone().start(docker_monitor_clone.subscribe_transitions())
+            });
+            app.manage(availability);
+            
+            // Send daemon transitions and finished jobs to the configured webhooks
+            let webhooks_clone = webhooks.clone();
+            let docker_monitor_clone = docker_monitor.clone();
+            let event_bus_clone = event_bus.clone();
+            let config_manager_clone = config_manager.clone();
+            supervisor.spawn("webhooks", RestartPolicy::Always, move || {
+                webhooks_clone.clone().start(
+                    config_manager_clone.subscribe(),
+                    docker_monitor_clone.subscribe_transitions(),
+                    event_bus_clone.subscribe(),
+                )
+            });
+            
+            // Serve /healthz and /status for local orchestration scripts
+            if config.status_server.enabled {
+                match config.status_server.bind.parse() {
+                    Ok(addr) => {
+                        let status_server = Arc::new(
+                            StatusServer::new(addr, docker_monitor.clone(), cancellation_token.clone())
+                                .with_app_state(app_state.clone()),
+                        );
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = status_server.start().await {
+                                error!("Failed to start status server: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => error!("Invalid status server address {}: {}", config.status_server.bind, e),
+                }
+            }
+            
+            // Compare the system clock with NTP for the capability report
+            let drift_monitor = Arc::new(DriftMonitor::new(&config.clock, cancellation_token.clone()));
+            let drift_monitor_clone = drift_monitor.clone();
+            supervisor.spawn("clock-drift", RestartPolicy::Always, move || drift_monitor_clone.clone().start());
+            
+            // Credentials from provider registration, unless the config sets its own
+            let provider_store = Arc::new(ProviderStore::new(
+                ProviderStore::default_directory().unwrap_or_else(|| std::path::PathBuf::from(".")),
+            ));
+            let provider_identity = provider_store.load().unwrap_or_else(|e| {
+                error!("Failed to load provider registration: {}", e);
+                None
+            });
+            if let Some(identity) = &provider_identity {
+                info!("Registered as provider {}", identity.provider_id);
+            }
+            app.manage(provider_store);
+            
+            // Initialize Docker service
+            let mut docker_service = DockerService::new(cancellation_token.clone())
+                .with_pause_control(pause_control.clone())
+                .with_event_bus(event_bus.clone())
+                .with_event_filters(config.events.clone())
+                .with_docker_status(docker_monitor.subscribe_status())
+                .with_disk_usage_interval(Duration::from_secs(config.monitor.disk_usage_interval_secs));
+            if let Some(simulator) = &simulator {
+                docker_service = docker_service.with_client(simulator.client());
+            }
+            let docker_service = Arc::new(docker_service);
+            
+            // Watch free space where images and job outputs are written
+            let mut disk_pressure = DiskPressureMonitor::new(&config.disk_pressure, cancellation_token.clone())
+                .with_docker_service(docker_service.clone());
+            if let Some(workspace_root) = config.workspace.root.clone().or_else(WorkspaceManager::default_root) {
+                disk_pressure = disk_pressure.with_path(workspace_root);
+            }
+            let disk_pressure = Arc::new(disk_pressure);
+            let disk_pressure_clone = disk_pressure.clone();
+            let app_handle = app.handle().clone();
+            supervisor.spawn("disk-pressure", RestartPolicy::Always, move || {
+                disk_pressure_clone.clone().start(app_handle.clone())
+            });
+            
+            // Measure latency and throughput for the scheduler and the UI
+            let network_probe = Arc::new(
+                NetworkProbe::new(&config.network_probe, cancellation_token.clone())
+                    .with_pause_control(pause_control.clone()),
+            );
+            let network_probe_clone = network_probe.clone();
+            let app_handle = app.handle().clone();
+            // Only forwards on-demand reports when periodic probes are disabled
+            supervisor.spawn("network-probe", RestartPolicy::Always, move || {
+                network_probe_clone.clone().start(app_handle.clone())
+            });
+            
+            // Keep the capability profile for job matching current
+            let capability_profiler = Arc::new(
+                CapabilityProfiler::new(
+                    docker_monitor.subscribe_status(),
+                    docker_service.clone(),
+                    config.features.clone(),
+                    cancellation_token.clone(),
+                )
+                .with_network_probe(network_probe.clone()),
+            );
+            let capability_profiler_clone = capability_profiler.clone();
+            supervisor.spawn("capability-profile", RestartPolicy::Always, move || {
+                capability_profiler_clone.clone().start()
+            });
+            
+            // Connect to the RedSys platform and report heartbeats, unless the
+            // daemon is simulated and jobs could not run
+            let backend_settings = if simulator.is_some() {
+                warn!("Simulation mode enabled, not connecting to the RedSys platform");
+                None
+            } else {
+                BackendSettings::from_config(&config.backend, provider_identity.as_ref())
+            };
+            let backend_client = Arc::new(
+                BackendClient::new(
+                    backend_settings,
+                    docker_monitor.clone(),
+                    cancellation_token.clone(),
+                )
+                .with_disk_pressure(disk_pressure.clone())
+                .with_docker_service(docker_service.clone())
+                .with_clock_monitor(drift_monitor.clone())
+                .with_capability_profiler(capability_profiler.clone()),
+            );
+            let backend_client_clone = backend_client.clone();
+            let app_handle = app.handle().clone();
+            // Returns right away when no backend is configured
+            supervisor.spawn("backend", RestartPolicy::OnPanic, move || {
+                backend_client_clone.clone().start(app_handle.clone())
             });
             
+            // Reconnect the backend and a remote Docker host when the network changes
+            let network_monitor = Arc::new(
+                NetworkMonitor::new(
+                    docker_monitor.clone(),
+                    docker_service.clone(),
+                    backend_client.clone(),
+                    cancellation_token.clone(),
+                )
+                .with_event_bus(event_bus.clone()),
+            );
+            supervisor.spawn("network-monitor", RestartPolicy::Always, move || network_monitor.clone().start());
+            app.manage(backend_client);
+            app.manage(capability_profiler);
+            app.manage(network_probe);
+            app.manage(disk_pressure);
+            
             // Store Docker monitor in app state
             app.manage(docker_monitor);
+            app.manage(fault_injector);
+            
+            // Start slow-interval disk usage reporting
+            if config.features.disk_usage_reporting {
+                let docker_service_clone = docker_service.clone();
+                supervisor.spawn("disk-usage-reporting", RestartPolicy::Always, move || {
+                    docker_service_clone.clone().start_disk_usage_reporting()
+                });
+            }
+            
+            // Forward Docker events (including container health transitions)
+            if config.features.events_stream {
+                let docker_service_clone = docker_service.clone();
+                supervisor.spawn("docker-events", RestartPolicy::Always, move || {
+                    docker_service_clone.clone().start_docker_events_stream()
+                });
+            }
+            
+            // Keep `docker info` cached while the daemon is up
+            let docker_service_clone = docker_service.clone();
+            let docker_monitor = app.state::<Arc<DockerMonitor>>().inner().clone();
+            supervisor.spawn("docker-info", RestartPolicy::Always, move || {
+                docker_service_clone.clone().start_info_refresh(docker_monitor.subscribe_status())
+            });
+            
+            // Warn about a daemon API exposed without TLS whenever Docker comes up
+            let docker_service_clone = docker_service.clone();
+            let app_handle = app.handle().clone();
+            let exposure_token = cancellation_token.clone();
+            supervisor.spawn("daemon-exposure", RestartPolicy::Always, move || {
+                security::watch_daemon_exposure(
+                    app_handle.clone(),
+                    docker_service_clone.subscribe_info(),
+                    webhooks.clone(),
+                    exposure_token.clone(),
+                )
+            });
+            
+            // Re-check Docker, replay missed events and rebuild the capability
+            // profile after the machine sleeps
+            let resume_watcher = Arc::new(
+                ResumeWatcher::new(
+                    app.state::<Arc<DockerMonitor>>().inner().clone(),
+                    docker_service.clone(),
+                    cancellation_token.clone(),
+                )
+                .with_capability_profiler(app.state::<Arc<CapabilityProfiler>>().inner().clone()),
+            );
+            supervisor.spawn("resume-watcher", RestartPolicy::Always, move || resume_watcher.clone().start());
+            
+            // Serve the typed orchestrator API
+            if config.grpc.enabled {
+                #[cfg(feature = "grpc")]
+                match config.grpc.bind.parse() {
+                    Ok(addr) => {
+                        let api = desktop_agent_lib::grpc::AgentApi::new(
+                            app.state::<Arc<DockerMonitor>>().inner().clone(),
+                            docker_service.clone(),
+                        )
+                        .with_app_state(app_state.clone())
+                        .with_container_operations(config.grpc.allow_container_operations)
+                        .with_token(config.grpc.token.clone());
+                        let api = match (&config.grpc.tls_cert, &config.grpc.tls_key) {
+                            (Some(cert), Some(key)) => api.with_tls(cert.clone(), key.clone()),
+                            _ => api,
+                        };
+                        let cancellation_token = cancellation_token.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = api.serve(addr, cancellation_token).await {
+                                error!("Failed to serve gRPC API: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => error!("Invalid gRPC address {}: {}", config.grpc.bind, e),
+                }
+                #[cfg(not(feature = "grpc"))]
+                warn!("grpc.enabled is set but this build does not include the grpc feature");
+            }
+            
+            // Publish status and metrics for home-lab monitoring
+            if config.mqtt.enabled {
+                #[cfg(feature = "mqtt")]
+                match desktop_agent_lib::mqtt::MqttPublisher::new(&config.mqtt) {
+                    Ok(publisher) => {
+                        info!("Publishing telemetry to MQTT broker {}", config.mqtt.broker);
+                        let publisher = Arc::new(publisher);
+                        let event_bus_clone = event_bus.clone();
+                        let mqtt_token = cancellation_token.clone();
+                        supervisor.spawn("mqtt", RestartPolicy::Always, move || {
+                            publisher.clone().start(event_bus_clone.subscribe(), mqtt_token.clone())
+                        });
+                    }
+                    Err(e) => error!("Failed to set up MQTT publishing: {}", e),
+                }
+                #[cfg(not(feature = "mqtt"))]
+                warn!("mqtt.enabled is set but this build does not include the mqtt feature");
+            }
+            
+            // Run jobs assigned by the RedSys platform
+            let mut job_manager = JobManager::new(docker_service.clone(), cancellation_token.clone())
+                .with_notifier(notifier.clone())
+                .with_image_policy(ImagePolicy::new(&config.image_policy))
+                .with_security_config(config.job_security.clone())
+                .with_host_paths(HostPaths::new(&config.host_paths))
+                .with_event_bus(event_bus.clone());
+            if config.image_signatures.enabled {
+                info!("Verifying job image signatures with {} trusted key(s)", config.image_signatures.trusted_keys.len());
+                job_manager = job_manager.with_signature_verifier(Arc::new(SignatureVerifier::new(&config.image_signatures)));
+            }
+            // File copies, image tar files and build contexts may use the
+            // workspaces besides the allowed roots
+            let mut transfer_paths = HostPaths::new(&config.host_paths);
+            match WorkspaceManager::new(&config.workspace, cancellation_token.clone()) {
+                Ok(workspace_manager) => {
+                    transfer_paths = transfer_paths.with_root(workspace_manager.root().to_path_buf());
+                    let workspace_manager = Arc::new(workspace_manager);
+                    info!("Job workspaces in {}", workspace_manager.root().display());
+                    let workspace_manager_clone = workspace_manager.clone();
+                    supervisor.spawn("workspace-gc", RestartPolicy::Always, move || {
+                        workspace_manager_clone.clone().start_gc()
+                    });
+                    job_manager = job_manager.with_workspaces(workspace_manager.clone());
+                    app.manage(workspace_manager);
+                }
+                Err(e) => warn!("Job workspaces disabled: {}", e),
+            }
+            app.manage(Arc::new(transfer_paths));
+            let job_manager = Arc::new(job_manager);
+            // Returns right away when workspaces are disabled
+            let job_manager_clone = job_manager.clone();
+            supervisor.spawn("workspace-quota", RestartPolicy::OnPanic, move || {
+                job_manager_clone.clone().enforce_workspace_quota()
+            });
+            // Simulated daemons cannot run jobs, and no backend assigns any
+            if simulator.is_none() {
+                let job_manager_clone = job_manager.clone();
+                let backend_client = app.state::<Arc<BackendClient>>().inner().clone();
+                let app_handle = app.handle().clone();
+                supervisor.spawn("job-runner", RestartPolicy::Always, move || {
+                    let job_manager_clone = job_manager_clone.clone();
+                    let mut backend_commands = backend_client.subscribe_commands();
+                    let backend_client = backend_client.clone();
+                    let app_handle = app_handle.clone();
+                    async move {
+                        loop {
+                            let command = match backend_commands.recv().await {
+                                Ok(command) => command,
+                                Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                                    warn!("Job runner lagged, {} backend commands dropped", missed);
+                                    continue;
+                                }
+                                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                            };
+                            let (command_id, result) = match command {
+                                BackendCommand::JobAssignment { command_id, job } => {
+                                    let result = match serde_json::from_value::<JobSpec>(job) {
+                                        Ok(spec) => job_manager_clone.submit(app_handle.clone(), spec).await.map(|_| ()).map_err(|e| e.to_string()),
+                                        Err(e) => Err(format!("Invalid job spec: {e}")),
+                                    };
+                                    (command_id, result)
+                                }
+                                BackendCommand::JobCancellation { command_id, job_id } => {
+                                    (command_id, job_manager_clone.cancel(&job_id).await.map_err(|e| e.to_string()))
+                                }
+                            };
+                            if let Err(e) = &result {
+                                error!("Failed to handle backend command {}: {}", command_id, e);
+                            }
+                            backend_client.acknowledge(&command_id, result);
+                        }
+                    }
+                });
+            }
+            
+            // Reap containers and volumes of jobs lost in a crash
+            if config.features.orphan_gc && simulator.is_none() {
+                let orphan_collector = Arc::new(
+                    OrphanCollector::new(job_manager.clone(), docker_service.clone(), cancellation_token.clone())
+                        .with_pause_control(pause_control.clone())
+                        .with_event_bus(event_bus.clone()),
+                );
+                supervisor.spawn("orphan-gc", RestartPolicy::Always, move || {
+                    orphan_collector.clone().start()
+                });
+            }
+            app.manage(job_manager);
+            app.manage(docker_service.clone());
+            deep_links::listen(app.handle());
+            
+            // Start scheduled prune policies
+            let prune_scheduler = Arc::new(
+                PruneScheduler::new(PrunePolicy::defaults(), docker_service.clone(), cancellation_token.clone())
+                    .with_pause_control(pause_control.clone()),
+            );
+            if config.features.prune_scheduler {
+                let prune_scheduler_clone = prune_scheduler.clone();
+                let app_handle = app.handle().clone();
+                supervisor.spawn("prune-scheduler", RestartPolicy::Always, move || {
+                    prune_scheduler_clone.clone().start(app_handle.clone())
+                });
+            }
+            app.manage(prune_scheduler);
+            
+            // Check configured images for newer registry versions
+            let image_update_checker = Arc::new(
+                ImageUpdateChecker::new(&config.image_updates, docker_service.clone(), cancellation_token.clone())
+                    .with_pause_control(pause_control.clone()),
+            );
+            if !config.image_updates.images.is_empty() {
+                let image_update_checker_clone = image_update_checker.clone();
+                let app_handle = app.handle().clone();
+                supervisor.spawn("image-updates", RestartPolicy::Always, move || {
+                    image_update_checker_clone.clone().start(app_handle.clone())
+                });
+            }
+            app.manage(image_update_checker);
+            
+            // Stream GPU metrics while dashboard panels are subscribed
+            let gpu_metrics = Arc::new(GpuMetricsStreamer::new(cancellation_token.clone()));
+            let gpu_metrics_clone = gpu_metrics.clone();
+            supervisor.spawn("gpu-metrics", RestartPolicy::Always, move || gpu_metrics_clone.clone().start());
+            
+            // Poll less and pause GPU metrics on battery power
+            let battery_throttle = Arc::new(BatteryThrottle::new(
+                app.state::<Arc<DockerMonitor>>().inner().clone(),
+                gpu_metrics.clone(),
+                cancellation_token.clone(),
+            ));
+            let config_manager_clone = config_manager.clone();
+            supervisor.spawn("battery-throttle", RestartPolicy::Always, move || {
+                battery_throttle.clone().start(config_manager_clone.subscribe())
+            });
+            app.manage(gpu_metrics);
+            
+            // Keep the agent reachable from the tray while the window is closed
+            if let Some(window) = &window {
+                if let Err(e) = tray::create(
+                    app.handle(),
+                    app.state::<Arc<DockerMonitor>>().inner().clone(),
+                    pause_control.clone(),
+                    cancellation_token.clone(),
+                ) {
+                    warn!("System tray unavailable, launch the agent again to reopen a closed window: {}", e);
+                    if launched_at_login {
+                        window.show().unwrap();
+                    }
+                }
+            }
+            app.manage(pause_control);
+            
+            // Report host CPU, memory, disk and network usage
+            let system_monitor = Arc::new(
+                SystemMonitor::new(cancellation_token.clone())
+                    .with_event_bus(event_bus.clone())
+                    .with_interval(Duration::from_secs(config.monitor.system_metrics_interval_secs)),
+            );
+            if config.features.system_metrics {
+                let system_monitor_clone = system_monitor.clone();
+                supervisor.spawn("system-metrics", RestartPolicy::Always, move || {
+                    system_monitor_clone.clone().start()
+                });
+            }
+            app.manage(system_monitor);
+            app.manage(event_bus);
+            app.manage(app_state);
+            
+            // Apply configuration changes to the running services
+            let config_manager_clone = config_manager.clone();
+            let docker_monitor = app.state::<Arc<DockerMonitor>>().inner().clone();
+            let docker_service = app.state::<Arc<DockerService>>().inner().clone();
+            let system_monitor = app.state::<Arc<SystemMonitor>>().inner().clone();
+            let logging_clone = logging.clone();
+            let cancellation_token_clone = cancellation_token.clone();
+            supervisor.spawn("config-apply", RestartPolicy::Always, move || {
+                let mut config_receiver = config_manager_clone.subscribe();
+                let docker_monitor = docker_monitor.clone();
+                let docker_service = docker_service.clone();
+                let system_monitor = system_monitor.clone();
+                let notifier = notifier.clone();
+                let logging_clone = logging_clone.clone();
+                let cancellation_token_clone = cancellation_token_clone.clone();
+                async move {
+                    let mut applied = config_receiver.borrow_and_update().clone();
+                    loop {
+                        tokio::select! {
+                            changed = config_receiver.changed() => {
+                                if changed.is_err() {
+                                    break;
+                                }
+                                let config = config_receiver.borrow_and_update().clone();
+                                apply_config(&applied, &config, &docker_monitor, &docker_service, &system_monitor, &notifier, &logging_clone).await;
+                                applied = config;
+                            }
+                            _ = cancellation_token_clone.cancelled() => break,
+                        }
+                    }
+                }
+            });
+            
+            // Hot-reload the configuration file
+            let config_manager_clone = config_manager.clone();
+            let app_handle = app.handle().clone();
+            let cancellation_token_clone = cancellation_token.clone();
+            // Returns right away when the file cannot be watched
+            supervisor.spawn("config-watcher", RestartPolicy::OnPanic, move || {
+                config_manager_clone.clone().start_watching(app_handle.clone(), cancellation_token_clone.clone())
+            });
+            
+            // Store configuration and logging in app state
+            app.manage(config_manager);
+            app.manage(logging);
             
             // Initialize app in background with minimal delay
             let app_handle = app.handle().clone();
@@ -119,53 +2228,138 @@ fn main() {
                 }
             });
             
-            // Setup graceful shutdown
-            let cancellation_token_clone = cancellation_token.clone();
-            app.listen("tauri://close-requested", move |_| {
-                info!("Application closing, cancelling Docker monitor");
-                cancellation_token_clone.cancel();
-            });
+            // Cancelled on exit to stop the background tasks
+            app.manage(cancellation_token);
             
             Ok(())
         })
         
-        // Cleanup function
-        .on_window_event(|_window, event| {
-            if let tauri::WindowEvent::CloseRequested { .. } = event {
-                // Perform cleanup before closing
-                tauri::async_runtime::block_on(async {
-                    info!("Application closing, performing cleanup...");
-                    if let Err(e) = cleanup_app().await {
-                        error!("Failed to cleanup application: {}", e);
-                    } else {
-                        info!("Application cleanup completed successfully");
-                    }
-                });
-                // Allow the window to close after cleanup
-                // The window will close automatically after this event handler
+        // Closing the window hides it; monitors, jobs and the backend
+        // connection keep running until an explicit quit. Without a tray
+        // icon nothing could reopen the window or quit, so closing quits.
+        .on_window_event(|window, event| match event {
+            // Detached windows close for good
+            tauri::WindowEvent::CloseRequested { api, .. } if window.label() == "main" => {
+                api.prevent_close();
+                if window.app_handle().tray_by_id(tray::TRAY_ID).is_none() {
+                    info!("Window closed without a tray icon, quitting");
+                    window.app_handle().exit(0);
+                    return;
+                }
+                if let Err(e) = window.hide() {
+                    error!("Failed to hide window: {}", e);
+                }
             }
+            // Event subscriptions end with their window
+            tauri::WindowEvent::Destroyed => {
+                if let Some(router) = window.app_handle().try_state::<Arc<EventRouter>>() {
+                    router.unsubscribe(window.label());
+                }
+            }
+            _ => {}
         })
         
         // Register commands
         .invoke_handler(tauri::generate_handler![
             get_application_state,
+            patch_app_state,
             get_docker_status,
+            force_docker_check,
+            start_docker_engine,
+            pause_monitoring,
+            resume_monitoring,
+            inject_fault,
+            clear_fault,
+            inject_event,
+            get_monitor_intervals,
+            set_monitor_intervals,
+            get_docker_disk_usage,
+            get_docker_info,
+            list_containers,
+            list_images,
+            list_managed_containers,
+            cleanup_managed_containers,
+            container_diff,
+            container_top,
+            copy_to_container,
+            copy_from_container,
+            get_port_overview,
+            build_image,
+            export_image,
+            import_image,
+            analyze_image,
+            get_image_updates,
+            audit_security,
+            get_event_filters,
+            configure_event_filters,
+            get_prune_policies,
+            set_prune_policies,
+            run_doctor,
+            get_config,
+            update_config,
+            get_backend_status,
+            submit_job,
+            list_jobs,
+            cancel_job,
+            get_job,
+            get_workspace_usage,
+            get_gpu_info,
+            get_capability_profile,
+            run_network_probe,
+            get_network_probe_results,
+            subscribe_gpu_metrics,
+            unsubscribe_gpu_metrics,
+            get_system_metrics,
+            get_disk_pressure,
+            get_agent_metrics,
+            get_availability_stats,
+            enable_autostart,
+            disable_autostart,
+            is_autostart_enabled,
+            install_service,
+            uninstall_service,
+            is_service_installed,
+            get_hardware_fingerprint,
+            register_provider,
+            confirm_deep_link,
+            get_log_file_path,
+            set_log_level,
+            shutdown_agent,
+            subscribe_window_events,
+            unsubscribe_window_events,
         ])
         
         // Run the application
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[tokio::test]
-    async fn test_get_application_state() {
-        // This test would require the application to be properly initialized
-        // In a real test environment, you'd mock the dependencies
-        let result = get_application_state().await;
-        assert!(result.is_ok());
-    }
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Only an explicit quit, which passes an exit code, stops the
+            // agent; losing the last window does not
+            if let tauri::RunEvent::ExitRequested { code: None, api, .. } = &event {
+                info!("Last window closed, the agent keeps running");
+                api.prevent_exit();
+            }
+            if let tauri::RunEvent::Exit = event {
+                info!("Application closing, cancelling background tasks");
+                if let Some(cancellation_token) = app_handle.try_state::<CancellationToken>() {
+                    cancellation_token.cancel();
+                }
+                
+                // Perform cleanup before exiting
+                tauri::async_runtime::block_on(async {
+                    info!("Application closing, performing cleanup...");
+                    if let Err(e) = cleanup_app().await {
+                        error!("Failed to cleanup application: {}", e);
+                    } else {
+                        info!("Application cleanup completed successfully");
+                    }
+                });
+                // Flush pending spans before the process exits
+                if let Some(logging) = app_handle.try_state::<Arc<Logging>>() {
+                    logging.shutdown();
+                }
+            }
+        });
+    
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,207 @@
+//! MQTT telemetry publisher for RedSys Desktop Agent
+//!
+//! Publishes the daemon status and resource samples from the event
+//! [`Bus`](crate::events::Bus)
+//! to an MQTT broker, so providers can watch the agent from existing home-lab
+//! or IoT monitoring. Enabled by the `[mqtt]` config section in builds with
+//! the `mqtt` feature.
+//!
+//! ## Topics
+//! Payloads are the JSON of the matching Tauri event, on topics under
+//! `topic_prefix`:
+//! - `<prefix>/docker-status-changed`, retained
+//! - `<prefix>/system-metrics`
+//! - `<prefix>/docker-disk-usage`
+//! - `<prefix>/availability`: `online` once connected, `offline` on shutdown
+//!   and as the last will when the agent dies, retained
+//!
+//! The broker connection is re-established after errors, and the latest
+//! status is published again on every reconnect. Samples are not queued
+//! while the broker is unreachable, so a reconnect does not flush stale
+//! ones.
+//!
+//! ## References
+//! - [MQTT Version 3.1.1](https://docs.oasis-open.org/mqtt/mqtt/v3.1.1/mqtt-v3.1.1.html)
+//! - [rumqttc](https://docs.rs/rumqttc/latest/rumqttc/)
+
+use std::sync::Arc;
+use rumqttc::{AsyncClient, LastWill, MqttOptions, Packet, QoS, Transport};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::{sleep, timeout, Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::config::MqttConfig;
+use crate::docker::next_backoff;
+use crate::error::{AppError, AppResult};
+use crate::events::{Envelope, Event, SystemMetric};
+
+/// Requests queued for the broker connection.
+const CLIENT_CAPACITY: usize = 64;
+
+/// Keep-alive interval of the broker connection.
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// Delay before the first reconnect attempt.
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound for the delay between reconnect attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Time allowed for the goodbye message on shutdown.
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Topic, under the prefix, and whether the broker retains it, for the
+/// events published over MQTT.
+fn topic(event: &Event) -> Option<(&'static str, bool)> {
+    match event {
+        Event::DaemonStatus(_) => Some((event.tauri_name(), true)),
+        Event::SystemMetric(SystemMetric::Host(_) | SystemMetric::DockerDiskUsage(_)) => {
+            Some((event.tauri_name(), false))
+        }
+        _ => None,
+    }
+}
+
+/// Publishes agent telemetry to an MQTT broker.
+#[derive(Debug)]
+pub struct MqttPublisher {
+    /// Broker connection options
+    options: MqttOptions,
+
+    /// Prefix of every published topic
+    topic_prefix: String,
+}
+
+impl MqttPublisher {
+    /// Creates a publisher from the `[mqtt]` config section.
+    pub fn new(config: &MqttConfig) -> AppResult<Self> {
+        let (tls, host, port) = config.broker_address().map_err(AppError::Configuration)?;
+        let client_id = config.client_id.clone().unwrap_or_else(|| {
+            format!("redsys-agent-{}", sysinfo::System::host_name().unwrap_or_else(|| "unknown".to_string()))
+        });
+
+        let mut options = MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(KEEP_ALIVE).set_last_will(LastWill::new(
+            format!("{}/availability", config.topic_prefix),
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            options.set_credentials(username, password);
+        }
+        if tls {
+            options.set_transport(Transport::tls_with_default_config());
+        }
+        Ok(Self {
+            options,
+            topic_prefix: config.topic_prefix.clone(),
+        })
+    }
+
+    /// Publishes events from `events` until `cancellation_token` is cancelled.
+    pub async fn start(
+        self: Arc<Self>,
+        mut events: broadcast::Receiver<Envelope>,
+        cancellation_token: CancellationToken,
+    ) {
+        let (client, mut event_loop) = AsyncClient::new(self.options.clone(), CLIENT_CAPACITY);
+        let (host, port) = self.options.broker_address();
+        let mut last_status: Option<Vec<u8>> = None;
+        let mut connected = false;
+        let mut reconnect_delay = INITIAL_RECONNECT_DELAY;
+
+        loop {
+            tokio::select! {
+                polled = event_loop.poll() => match polled {
+                    Ok(rumqttc::Event::Incoming(Packet::ConnAck(_))) => {
+                        info!("Connected to MQTT broker {}:{}", host, port);
+                        connected = true;
+                        reconnect_delay = INITIAL_RECONNECT_DELAY;
+                        self.publish(&client, "availability", true, "online");
+                        if let Some(status) = &last_status {
+                            self.publish(&client, "docker-status-changed", true, status.clone());
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        warn!("MQTT broker {}:{} connection failed: {}", host, port, e);
+                        connected = false;
+                        tokio::select! {
+                            _ = sleep(reconnect_delay) => {}
+                            _ = cancellation_token.cancelled() => break,
+                        }
+                        reconnect_delay = next_backoff(reconnect_delay, MAX_RECONNECT_DELAY);
+                    }
+                },
+                received = events.recv() => match received {
+                    Ok(envelope) => {
+                        let Some((name, retain)) = topic(&envelope.event) else {
+                            continue;
+                        };
+                        let payload = match envelope.event.payload().and_then(|payload| serde_json::to_vec(&payload)) {
+                            Ok(payload) => payload,
+                            Err(e) => {
+                                warn!("Failed to serialize {} for MQTT: {}", name, e);
+                                continue;
+                            }
+                        };
+                        if retain {
+                            last_status = Some(payload.clone());
+                        }
+                        // The status goes out again on connect, samples would be stale by then
+                        if connected {
+                            self.publish(&client, name, retain, payload);
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => warn!("MQTT publisher lagged, {} events dropped", missed),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = cancellation_token.cancelled() => break,
+            }
+        }
+
+        // Say goodbye ourselves; the last will only covers crashes
+        self.publish(&client, "availability", true, "offline");
+        match client.try_disconnect() {
+            Ok(()) => {
+                let flush = async { while event_loop.poll().await.is_ok() {} };
+                if timeout(DISCONNECT_TIMEOUT, flush).await.is_err() {
+                    debug!("Timed out disconnecting from MQTT broker {}:{}", host, port);
+                }
+            }
+            Err(e) => debug!("Failed to disconnect from MQTT broker: {}", e),
+        }
+    }
+
+    /// Queues a publish to `<prefix>/<name>`, dropping it when the queue is
+    /// full so a slow broker never holds up the event loop.
+    fn publish(&self, client: &AsyncClient, name: &str, retain: bool, payload: impl Into<Vec<u8>>) {
+        let qos = if retain { QoS::AtLeastOnce } else { QoS::AtMostOnce };
+        if let Err(e) = client.try_publish(format!("{}/{}", self.topic_prefix, name), qos, retain, payload) {
+            debug!("Dropped MQTT message for {}: {}", name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::DockerStatus;
+    use crate::types::DockerStatusChangedPayload;
+
+    #[test]
+    fn test_topics() {
+        let status = Event::DaemonStatus(DockerStatusChangedPayload::new(DockerStatus::Starting));
+        assert_eq!(topic(&status), Some(("docker-status-changed", true)));
+
+        let publisher = MqttPublisher::new(&MqttConfig {
+            broker: "mqtts://broker.lan:8883".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(publisher.options.broker_address(), ("broker.lan".to_string(), 8883));
+        assert_eq!(publisher.topic_prefix, "redsys/agent");
+    }
+}
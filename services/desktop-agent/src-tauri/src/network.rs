@@ -0,0 +1,245 @@
+//! Network change handling for RedSys Desktop Agent
+//!
+//! Switching Wi-Fi networks, plugging in a cable or connecting a VPN can leave
+//! connections bound to an address that no longer exists. They look alive
+//! until a write times out, which takes up to a heartbeat interval for the
+//! backend and a full check timeout for a remote Docker host.
+//!
+//! [`NetworkMonitor`] watches the addresses of the network interfaces. When
+//! they change it immediately:
+//! - reconnects the backend WebSocket
+//! - for a remote Docker host (any scheme besides `unix://` and `npipe://`,
+//!   e.g. `tcp://`, `http://`, `https://` or `ssh://`), drops the cached
+//!   connection, re-checks the daemon and reconnects the events stream
+//!
+//! Loopback and link-local addresses are ignored, as they don't affect
+//! reachability, and so are the bridges Docker creates for container
+//! networks.
+//!
+//! ## Events
+//! `agent-connectivity-changed` carries a [`ConnectivityChanged`] with the
+//! interfaces after the change.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::{task, time::{interval, Duration, MissedTickBehavior}};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sysinfo::Networks;
+
+use crate::backend::BackendClient;
+use crate::docker::DockerService;
+use crate::docker_monitor::DockerMonitor;
+use crate::events::{Bus, Event};
+
+/// Interval between interface checks.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Name prefixes of the interfaces Docker creates.
+const DOCKER_INTERFACE_PREFIXES: [&str; 3] = ["docker", "br-", "veth"];
+
+/// A network interface with its routable addresses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct NetworkInterface {
+    /// Interface name, e.g. `eth0` or `en0`
+    pub name: String,
+
+    /// Addresses, sorted
+    pub addresses: Vec<String>,
+}
+
+/// Payload of the `agent-connectivity-changed` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ConnectivityChanged {
+    /// Whether any interface has a routable address
+    pub online: bool,
+
+    /// Interfaces with routable addresses, sorted by name
+    pub interfaces: Vec<NetworkInterface>,
+
+    /// When the change was noticed
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Whether `address` can reach anything beyond this host or link.
+fn routable(address: &IpAddr) -> bool {
+    match address {
+        IpAddr::V4(v4) => !v4.is_loopback() && !v4.is_link_local() && !v4.is_unspecified(),
+        // fe80::/10
+        IpAddr::V6(v6) => !v6.is_loopback() && !v6.is_unspecified() && (v6.segments()[0] & 0xffc0) != 0xfe80,
+    }
+}
+
+/// Keeps the interfaces with routable addresses, sorted, leaving out Docker's
+/// own.
+fn routable_interfaces<I>(interfaces: I) -> Vec<NetworkInterface>
+where
+    I: IntoIterator<Item = (String, Vec<IpAddr>)>,
+{
+    let mut routable_interfaces = BTreeMap::new();
+    for (name, addresses) in interfaces {
+        if DOCKER_INTERFACE_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+            continue;
+        }
+        let mut addresses: Vec<String> = addresses.iter().filter(|address| routable(address)).map(IpAddr::to_string).collect();
+        if addresses.is_empty() {
+            continue;
+        }
+        addresses.sort();
+        routable_interfaces.insert(name.clone(), NetworkInterface { name, addresses });
+    }
+    routable_interfaces.into_values().collect()
+}
+
+/// Whether the Docker host `host` is reached over the network rather than a
+/// local socket or named pipe.
+pub(crate) fn is_remote_host(host: &str) -> bool {
+    host.split_once("://")
+        .is_some_and(|(scheme, _)| !matches!(scheme.to_ascii_lowercase().as_str(), "unix" | "npipe"))
+}
+
+/// Reads the network interfaces. Blocking.
+fn current_interfaces() -> Vec<NetworkInterface> {
+    let networks = Networks::new_with_refreshed_list();
+    routable_interfaces(networks.iter().map(|(name, data)| {
+        (name.clone(), data.ip_networks().iter().map(|network| network.addr).collect())
+    }))
+}
+
+/// Re-establishes connections when the network changes.
+#[derive(Debug)]
+pub struct NetworkMonitor {
+    /// Monitor to re-check when Docker is remote
+    docker_monitor: Arc<DockerMonitor>,
+
+    /// Service whose events stream to reconnect when Docker is remote
+    docker_service: Arc<DockerService>,
+
+    /// Backend connection to replace
+    backend_client: Arc<BackendClient>,
+
+    /// Event bus for `agent-connectivity-changed`
+    bus: Arc<Bus>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl NetworkMonitor {
+    /// Creates a monitor for the given connections.
+    pub fn new(
+        docker_monitor: Arc<DockerMonitor>,
+        docker_service: Arc<DockerService>,
+        backend_client: Arc<BackendClient>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Self {
+            docker_monitor,
+            docker_service,
+            backend_client,
+            bus: Arc::new(Bus::new()),
+            cancellation_token,
+        }
+    }
+
+    /// Shares the event bus that connectivity changes are published to.
+    pub fn with_event_bus(self, bus: Arc<Bus>) -> Self {
+        Self { bus, ..self }
+    }
+
+    /// Watches the network interfaces until cancelled.
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting network change detection");
+        let mut interfaces = task::spawn_blocking(current_interfaces).await.unwrap_or_default();
+        debug!("Network interfaces: {:?}", interfaces);
+
+        let mut ticker = interval(CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let Ok(current) = task::spawn_blocking(current_interfaces).await else {
+                        continue;
+                    };
+                    if current != interfaces {
+                        interfaces = current;
+                        self.changed(interfaces.clone()).await;
+                    }
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Network change detection received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Reconnects and announces a network change.
+    async fn changed(&self, interfaces: Vec<NetworkInterface>) {
+        let change = ConnectivityChanged {
+            online: !interfaces.is_empty(),
+            interfaces,
+            changed_at: Utc::now(),
+        };
+        info!(
+            "Network changed, {} ({} interfaces)",
+            if change.online { "online" } else { "offline" },
+            change.interfaces.len()
+        );
+        self.bus.publish(Event::Connectivity(change));
+
+        self.backend_client.reconnect();
+        if DockerMonitor::docker_host().is_some_and(|host| is_remote_host(&host)) {
+            self.docker_monitor.reconnect();
+            self.docker_service.reconnect();
+            if let Err(e) = self.docker_monitor.force_check().await {
+                warn!("Docker re-check after network change failed: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_hosts() {
+        for host in ["tcp://10.0.0.5:2375", "http://docker.lan:2375", "https://docker.lan:2376", "SSH://user@docker.lan"] {
+            assert!(is_remote_host(host), "{host}");
+        }
+        for host in ["unix:///var/run/docker.sock", "npipe:////./pipe/docker_engine", "/var/run/docker.sock"] {
+            assert!(!is_remote_host(host), "{host}");
+        }
+    }
+
+    #[test]
+    fn test_routable_interfaces() {
+        let interfaces = routable_interfaces([
+            ("wlan0".to_string(), vec!["192.168.1.20".parse().unwrap(), "fe80::1".parse().unwrap()]),
+            ("lo".to_string(), vec!["127.0.0.1".parse().unwrap(), "::1".parse().unwrap()]),
+            ("eth0".to_string(), vec!["2001:db8::2".parse().unwrap(), "10.0.0.5".parse().unwrap()]),
+            ("docker0".to_string(), vec!["172.17.0.1".parse().unwrap()]),
+            ("tun0".to_string(), vec![]),
+        ]);
+
+        assert_eq!(
+            interfaces,
+            [
+                NetworkInterface {
+                    name: "eth0".to_string(),
+                    addresses: vec!["10.0.0.5".to_string(), "2001:db8::2".to_string()],
+                },
+                NetworkInterface {
+                    name: "wlan0".to_string(),
+                    addresses: vec!["192.168.1.20".to_string()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,416 @@
+//! Network latency and throughput probe for RedSys Desktop Agent
+//!
+//! Jobs pull images and datasets and upload results, so the scheduler
+//! prefers providers close to the job's region with enough bandwidth.
+//! [`NetworkProbe`] measures both against the targets in
+//! `network_probe.targets`, by default a generic public endpoint:
+//! - Latency is the median time to open a TCP connection to the target's
+//!   host, over `network_probe.latency_samples` connections; DNS is resolved
+//!   once beforehand and not counted. Jitter is the mean difference between
+//!   consecutive samples.
+//! - Throughput is measured only with `network_probe.measure_throughput`,
+//!   since it spends the provider's bandwidth: `download_url` is downloaded,
+//!   stopping after `network_probe.max_download_bytes`, and counted from the
+//!   first response byte so the latency is not included. Shutdown stops a
+//!   download in progress.
+//!
+//! Targets are probed one after another, so downloads do not compete for
+//! bandwidth. Probes run every `network_probe.check_interval_secs` unless
+//! disabled or monitoring is paused, and on demand with `run_network_probe`.
+//! The latest [`ProbeReport`] goes into the
+//! [capability profile](crate::capability_profile) for the scheduler; the
+//! last [`HISTORY_LEN`] reports are kept for the UI.
+//!
+//! ## Events
+//! `network-probe-completed` carries every new [`ProbeReport`].
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tokio::time::{interval, timeout, Duration, Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::{NetworkProbeConfig, NetworkProbeTarget};
+use crate::error::{AppError, AppResult};
+use crate::pause::PauseControl;
+use crate::telemetry;
+
+/// Endpoint probed by default.
+pub const DEFAULT_PROBE_URL: &str = "https://speed.cloudflare.com";
+
+/// Download used for the throughput of the default endpoint.
+pub const DEFAULT_PROBE_DOWNLOAD_URL: &str = "https://speed.cloudflare.com/__down?bytes=2000000";
+
+/// Number of reports kept for the UI.
+pub const HISTORY_LEN: usize = 24;
+
+/// Longest wait for a single TCP connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Measurements of one target.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ProbeResult {
+    /// Target name from the configuration
+    pub target: String,
+
+    /// Median TCP connect time in milliseconds
+    pub latency_ms: Option<f64>,
+
+    /// Mean difference between consecutive connect times in milliseconds
+    pub jitter_ms: Option<f64>,
+
+    /// Download throughput in bits per second
+    #[cfg_attr(feature = "ts-bindings", ts(type = "number | null"))]
+    pub download_bps: Option<u64>,
+
+    /// Why a measurement is missing
+    pub error: Option<String>,
+}
+
+/// Results of one probe of all targets, the `network-probe-completed`
+/// payload.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ProbeReport {
+    /// When the probe started
+    pub measured_at: DateTime<Utc>,
+
+    /// Results per target, in configuration order
+    pub results: Vec<ProbeResult>,
+}
+
+/// Median and jitter of connect times, in milliseconds.
+fn latency_stats(samples: &[Duration]) -> Option<(f64, f64)> {
+    let millis: Vec<f64> = samples.iter().map(|sample| sample.as_secs_f64() * 1000.0).collect();
+    let mut sorted = millis.clone();
+    sorted.sort_by(f64::total_cmp);
+    let median = match sorted.len() {
+        0 => return None,
+        n if n % 2 == 0 => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+        n => sorted[n / 2],
+    };
+    let jitter = if millis.len() > 1 {
+        millis.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum::<f64>() / (millis.len() - 1) as f64
+    } else {
+        0.0
+    };
+    Some((median, jitter))
+}
+
+/// Throughput of `bytes` received in `elapsed`, in bits per second.
+fn bits_per_second(bytes: u64, elapsed: Duration) -> Option<u64> {
+    let seconds = elapsed.as_secs_f64();
+    (bytes > 0 && seconds > 0.0).then(|| (bytes as f64 * 8.0 / seconds) as u64)
+}
+
+/// Resolves the host and port of an HTTP(S) URL.
+async fn resolve(url: &str) -> AppResult<SocketAddr> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| AppError::Configuration(format!("Invalid probe URL '{url}': {e}")))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| AppError::Configuration(format!("Probe URL '{url}' has no host")))?;
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    tokio::net::lookup_host((host, port))
+        .await?
+        .next()
+        .ok_or_else(|| AppError::Network(format!("{host} did not resolve")))
+}
+
+/// Periodically measures latency and throughput to the configured targets.
+#[derive(Debug)]
+pub struct NetworkProbe {
+    /// Endpoints to measure
+    targets: Vec<NetworkProbeTarget>,
+
+    /// Whether probes run periodically
+    enabled: bool,
+
+    /// Interval between scheduled probes
+    check_interval: Duration,
+
+    /// Connections timed per target
+    latency_samples: u32,
+
+    /// Whether targets' downloads are measured
+    measure_throughput: bool,
+
+    /// Bytes downloaded at most per target
+    max_download_bytes: u64,
+
+    /// HTTP client for the downloads, with the download timeout
+    client: reqwest::Client,
+
+    /// Latest report, `None` until the first probe
+    latest: watch::Sender<Option<ProbeReport>>,
+
+    /// Recent reports, oldest first
+    history: Mutex<VecDeque<ProbeReport>>,
+
+    /// Serializes probes, so on-demand and scheduled ones do not overlap
+    running: tokio::sync::Mutex<()>,
+
+    /// Skips scheduled probes while monitoring is paused
+    pause: Arc<PauseControl>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl NetworkProbe {
+    /// Creates a probe from the `[network_probe]` config section.
+    pub fn new(config: &NetworkProbeConfig, cancellation_token: CancellationToken) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .user_agent(concat!("redsys-desktop-agent/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self {
+            targets: config.targets.clone(),
+            enabled: config.enabled,
+            check_interval: Duration::from_secs(config.check_interval_secs),
+            latency_samples: config.latency_samples,
+            measure_throughput: config.measure_throughput,
+            max_download_bytes: config.max_download_bytes,
+            client,
+            latest: watch::Sender::new(None),
+            history: Mutex::new(VecDeque::with_capacity(HISTORY_LEN)),
+            running: tokio::sync::Mutex::new(()),
+            pause: Arc::new(PauseControl::new()),
+            cancellation_token,
+        }
+    }
+
+    /// Shares a monitoring pause switch; scheduled probes are skipped while
+    /// it is paused.
+    pub fn with_pause_control(self, pause: Arc<PauseControl>) -> Self {
+        Self { pause, ..self }
+    }
+
+    /// Gets the latest report.
+    pub fn latest(&self) -> Option<ProbeReport> {
+        self.latest.borrow().clone()
+    }
+
+    /// Gets the recent reports, oldest first.
+    pub fn history(&self) -> Vec<ProbeReport> {
+        self.history.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+
+    /// Subscribes to new reports.
+    pub fn subscribe(&self) -> watch::Receiver<Option<ProbeReport>> {
+        self.latest.subscribe()
+    }
+
+    /// Probes all targets now and stores the report.
+    ///
+    /// Waits for a probe already in progress to finish first.
+    pub async fn run(&self) -> ProbeReport {
+        let _running = self.running.lock().await;
+        let measured_at = Utc::now();
+        let mut results = Vec::with_capacity(self.targets.len());
+        for target in &self.targets {
+            let result = self.probe(target).await;
+            debug!(
+                "Probed {}: latency {:?}ms, throughput {:?}bps",
+                result.target, result.latency_ms, result.download_bps
+            );
+            results.push(result);
+        }
+        let report = ProbeReport { measured_at, results };
+        self.store(report.clone());
+        report
+    }
+
+    /// Adds a report to the history and publishes it.
+    fn store(&self, report: ProbeReport) {
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        if history.len() == HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(report.clone());
+        drop(history);
+        self.latest.send_replace(Some(report));
+    }
+
+    /// Measures one target; failed measurements are reported in `error`.
+    async fn probe(&self, target: &NetworkProbeTarget) -> ProbeResult {
+        let mut errors = Vec::new();
+        let latency = match self.measure_latency(&target.url).await {
+            Ok(latency) => latency,
+            Err(e) => {
+                errors.push(format!("latency: {e}"));
+                None
+            }
+        };
+        let download_bps = match target.download_url.as_ref().filter(|_| self.measure_throughput) {
+            Some(url) => match self.measure_throughput(url).await {
+                Ok(bps) => bps,
+                Err(e) => {
+                    errors.push(format!("throughput: {e}"));
+                    None
+                }
+            },
+            None => None,
+        };
+        ProbeResult {
+            target: target.name.clone(),
+            latency_ms: latency.map(|(median, _)| median),
+            jitter_ms: latency.map(|(_, jitter)| jitter),
+            download_bps,
+            error: (!errors.is_empty()).then(|| errors.join("; ")),
+        }
+    }
+
+    /// Times TCP connections to the host of `url`.
+    async fn measure_latency(&self, url: &str) -> AppResult<Option<(f64, f64)>> {
+        let address = resolve(url).await?;
+        let mut samples = Vec::with_capacity(self.latency_samples as usize);
+        for _ in 0..self.latency_samples {
+            let started = Instant::now();
+            match timeout(CONNECT_TIMEOUT, TcpStream::connect(address)).await {
+                Ok(Ok(_stream)) => samples.push(started.elapsed()),
+                Ok(Err(e)) => debug!("Probe connection to {address} failed: {e}"),
+                Err(_) => debug!("Probe connection to {address} timed out"),
+            }
+        }
+        if samples.is_empty() {
+            return Err(AppError::Network(format!("No connection to {address} succeeded")));
+        }
+        Ok(latency_stats(&samples))
+    }
+
+    /// Downloads up to the byte limit from `url` and computes the throughput.
+    ///
+    /// Stops early on cancellation.
+    async fn measure_throughput(&self, url: &str) -> AppResult<Option<u64>> {
+        let cancelled = || AppError::Network("Download cancelled".to_string());
+        let mut response = tokio::select! {
+            response = self.client.get(url).send() => response
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|e| AppError::Network(e.to_string()))?,
+            _ = self.cancellation_token.cancelled() => return Err(cancelled()),
+        };
+        let mut received = 0u64;
+        let mut started = None;
+        while received < self.max_download_bytes {
+            let chunk = tokio::select! {
+                chunk = response.chunk() => chunk,
+                _ = self.cancellation_token.cancelled() => return Err(cancelled()),
+            };
+            let chunk = match chunk {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                // Timing out after some data still gives a throughput
+                Err(e) if e.is_timeout() && received > 0 => break,
+                Err(e) => return Err(AppError::Network(e.to_string())),
+            };
+            started.get_or_insert_with(Instant::now);
+            received += chunk.len() as u64;
+        }
+        Ok(started.and_then(|started| bits_per_second(received, started.elapsed())))
+    }
+
+    /// Probes every check interval while enabled, until cancelled, and emits
+    /// a `network-probe-completed` event for every report, including those of
+    /// on-demand probes.
+    pub async fn start(self: Arc<Self>, app_handle: tauri::AppHandle) {
+        if self.enabled {
+            info!(
+                "Starting network probes of {} target(s) every {}s",
+                self.targets.len(),
+                self.check_interval.as_secs()
+            );
+        }
+        let mut reports = self.subscribe();
+        let mut ticker = interval(self.check_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick(), if self.enabled => {
+                    if self.pause.is_paused() {
+                        debug!("Monitoring paused, skipping network probe");
+                        continue;
+                    }
+                    let report = self.run().await;
+                    for result in report.results.iter().filter(|result| result.error.is_some()) {
+                        warn!("Network probe of {} incomplete: {}", result.target, result.error.as_deref().unwrap_or_default());
+                    }
+                }
+                Ok(()) = reports.changed() => {
+                    let report = reports.borrow_and_update().clone();
+                    if let Some(report) = report {
+                        if let Err(e) = telemetry::emit(&app_handle, "network-probe-completed", &report) {
+                            error!("Failed to emit network-probe-completed event: {e}");
+                        }
+                    }
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Network probe received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_stats() {
+        let samples = [30, 10, 20, 40].map(Duration::from_millis);
+        let (median, jitter) = latency_stats(&samples).unwrap();
+        assert!((median - 25.0).abs() < 1e-9);
+        // |10-30| + |20-10| + |40-20| over 3 pairs
+        assert!((jitter - 50.0 / 3.0).abs() < 1e-9);
+        assert_eq!(latency_stats(&[Duration::from_millis(12)]), Some((12.0, 0.0)));
+        assert_eq!(latency_stats(&[]), None);
+
+        assert_eq!(bits_per_second(1_250_000, Duration::from_secs(1)), Some(10_000_000));
+        assert_eq!(bits_per_second(0, Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn test_history_bounded() {
+        let probe = NetworkProbe::new(&NetworkProbeConfig::default(), CancellationToken::new());
+        for _ in 0..HISTORY_LEN + 3 {
+            probe.store(ProbeReport { measured_at: Utc::now(), results: Vec::new() });
+        }
+        assert_eq!(probe.history().len(), HISTORY_LEN);
+        assert!(probe.latest().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_download_stops_on_cancellation() {
+        use tokio::io::AsyncWriteExt;
+
+        // Sends the start of a large body, then stalls
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 1000000\r\n\r\n0123456789")
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+
+        let token = CancellationToken::new();
+        let probe = NetworkProbe::new(&NetworkProbeConfig::default(), token.clone());
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            canceller.cancel();
+        });
+        let result = timeout(Duration::from_secs(5), probe.measure_throughput(&url)).await;
+        assert!(matches!(result, Ok(Err(AppError::Network(_)))));
+    }
+}
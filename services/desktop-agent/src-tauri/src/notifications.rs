@@ -0,0 +1,197 @@
+//! Native desktop notifications for RedSys Desktop Agent
+//!
+//! Raised from the Rust side, so they reach the user while the window is
+//! minimized or hidden in the tray:
+//! - the Docker daemon going from running to stopped, unreachable or
+//!   failing, raised by the
+//!   [`DockerMonitor`](crate::docker_monitor::DockerMonitor) change-detection
+//!   branch
+//! - a job succeeding or failing, raised by the
+//!   [`JobManager`](crate::jobs::JobManager)
+//!
+//! Each kind can be switched off in the `[notifications]` config section;
+//! changes apply without a restart.
+//!
+//! ## References
+//! - [Tauri Notification Plugin](https://v2.tauri.app/plugin/notification/)
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, warn};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::config::NotificationsConfig;
+use crate::docker_monitor::DockerStatus;
+use crate::jobs::{JobRecord, JobState};
+
+/// Title and body of a notification.
+#[derive(Debug, Clone, PartialEq)]
+struct NotificationContent {
+    title: String,
+    body: String,
+}
+
+/// Describes a daemon transition worth notifying about, if any.
+///
+/// A restart is not worth a notification, a daemon that stays down after one is.
+fn docker_transition(previous: &DockerStatus, status: &DockerStatus) -> Option<NotificationContent> {
+    if !matches!(
+        previous,
+        DockerStatus::Running { .. } | DockerStatus::Degraded { .. } | DockerStatus::Restarting
+    ) {
+        return None;
+    }
+    match status {
+        DockerStatus::Stopped { .. } => Some(NotificationContent {
+            title: "Docker stopped".to_string(),
+            body: "The Docker daemon is no longer running; jobs cannot start until it is back.".to_string(),
+        }),
+        DockerStatus::Error { message, .. } => Some(NotificationContent {
+            title: "Docker error".to_string(),
+            body: message.clone(),
+        }),
+        DockerStatus::Unreachable { endpoint, .. } => Some(NotificationContent {
+            title: "Docker unreachable".to_string(),
+            body: format!("The Docker host {endpoint} cannot be reached; jobs cannot start until it is back."),
+        }),
+        _ => None,
+    }
+}
+
+/// Describes a finished job, `None` while it is still running.
+fn job_finished(job: &JobRecord) -> Option<NotificationContent> {
+    match &job.state {
+        JobState::Succeeded => Some(NotificationContent {
+            title: format!("Job {} finished", job.spec.id),
+            body: format!("{} completed successfully.", job.spec.image),
+        }),
+        JobState::Failed { error } => Some(NotificationContent {
+            title: format!("Job {} failed", job.spec.id),
+            body: error.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// Shows desktop notifications according to the `[notifications]` settings.
+#[derive(Debug)]
+pub struct Notifier {
+    /// Notify on Running → Stopped/Error transitions
+    docker_status: AtomicBool,
+
+    /// Notify on finished and failed jobs
+    jobs: AtomicBool,
+}
+
+impl Notifier {
+    /// Creates a notifier from the `[notifications]` config section.
+    pub fn new(config: &NotificationsConfig) -> Self {
+        let notifier = Self {
+            docker_status: AtomicBool::new(false),
+            jobs: AtomicBool::new(false),
+        };
+        notifier.configure(config);
+        notifier
+    }
+
+    /// Applies changed `[notifications]` settings.
+    pub fn configure(&self, config: &NotificationsConfig) {
+        self.docker_status.store(config.enabled && config.docker_status, Ordering::Relaxed);
+        self.jobs.store(config.enabled && config.jobs, Ordering::Relaxed);
+    }
+
+    /// Notifies about a daemon status change.
+    pub fn docker_status_changed(&self, app_handle: &tauri::AppHandle, previous: &DockerStatus, status: &DockerStatus) {
+        if !self.docker_status.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Some(content) = docker_transition(previous, status) {
+            show(app_handle, content);
+        }
+    }
+
+    /// Notifies about a job that reached a final state.
+    pub fn job_finished(&self, app_handle: &tauri::AppHandle, job: &JobRecord) {
+        if !self.jobs.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Some(content) = job_finished(job) {
+            show(app_handle, content);
+        }
+    }
+}
+
+/// Shows a notification, logging failures.
+fn show(app_handle: &tauri::AppHandle, content: NotificationContent) {
+    debug!("Showing notification '{}'", content.title);
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(content.title)
+        .body(content.body)
+        .show()
+    {
+        warn!("Failed to show desktop notification: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::EngineDetails;
+    use crate::diagnosis::DockerUnavailableReason;
+
+    fn stopped() -> DockerStatus {
+        DockerStatus::Stopped { reason: DockerUnavailableReason::ServiceStopped }
+    }
+    use crate::jobs::JobSpec;
+
+    #[test]
+    fn test_only_running_to_down_transitions_notify() {
+        let running = DockerStatus::Running { version: "27.0.1".to_string(), engine: EngineDetails::default() };
+        assert_eq!(docker_transition(&running, &stopped()).unwrap().title, "Docker stopped");
+        assert_eq!(
+            docker_transition(&running, &DockerStatus::Error { message: "boom".to_string(), retry_after: None }).unwrap().body,
+            "boom"
+        );
+        assert!(docker_transition(&running, &DockerStatus::Paused).is_none());
+        assert!(docker_transition(&running, &DockerStatus::Restarting).is_none());
+        assert_eq!(docker_transition(&DockerStatus::Restarting, &stopped()).unwrap().title, "Docker stopped");
+        assert!(docker_transition(&stopped(), &DockerStatus::Error { message: "boom".to_string(), retry_after: None }).is_none());
+        assert!(docker_transition(&stopped(), &running).is_none());
+    }
+
+    #[test]
+    fn test_job_notifications() {
+        let spec: JobSpec = serde_json::from_value(serde_json::json!({"id": "render-1", "image": "alpine"})).unwrap();
+        let mut job = JobRecord {
+            spec,
+            state: JobState::Running,
+            container_id: None,
+            exit_code: None,
+            failure: None,
+            logs: None,
+            submitted_at: chrono::Utc::now(),
+            finished_at: None,
+            workspace: None,
+            signature: None,
+            pull_error: None,
+            restarts: 0,
+        };
+        assert!(job_finished(&job).is_none());
+
+        job.state = JobState::Failed { error: "Container exited with code 1".to_string() };
+        let content = job_finished(&job).unwrap();
+        assert_eq!(content.title, "Job render-1 failed");
+        assert_eq!(content.body, "Container exited with code 1");
+    }
+
+    #[test]
+    fn test_master_switch_disables_all() {
+        let notifier = Notifier::new(&NotificationsConfig { enabled: false, ..NotificationsConfig::default() });
+        assert!(!notifier.docker_status.load(Ordering::Relaxed));
+        assert!(!notifier.jobs.load(Ordering::Relaxed));
+
+        notifier.configure(&NotificationsConfig::default());
+        assert!(notifier.docker_status.load(Ordering::Relaxed));
+    }
+}
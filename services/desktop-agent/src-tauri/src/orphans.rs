@@ -0,0 +1,233 @@
+//! Orphan garbage collection for RedSys Desktop Agent
+//!
+//! A job removes its container when it finishes, but not if the agent
+//! crashes or is killed while the job runs. The container, and any volume
+//! labelled with the job, then stays behind with nobody watching it, running
+//! or taking up disk space.
+//!
+//! [`OrphanCollector`] runs at startup and then hourly. It reaps containers
+//! and volumes labelled `redsys.managed=true` whose `redsys.job-id` is not a
+//! job the [`JobManager`] is running. Each container is checked against the
+//! job manager again right before it goes, so a job submitted during the
+//! run keeps its container. Running orphan containers are stopped with a
+//! grace period first rather than force-removed. Volumes still in use and
+//! objects without a job ID are left alone.
+//!
+//! ## Events
+//! [`Event::GarbageCollected`] carries a [`GcReport`] with what each run
+//! removed, forwarded to the frontend as `gc-report`.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::time::{interval, Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use bollard::query_parameters::{ListVolumesOptionsBuilder, RemoveVolumeOptions};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::docker::{DockerService, REDSYS_MANAGED_LABEL};
+use crate::docker_monitor::{DockerMonitorError, DockerMonitorResult};
+use crate::events::{Bus, Event};
+use crate::jobs::{JobManager, REDSYS_JOB_ID_LABEL};
+use crate::pause::PauseControl;
+
+/// Interval between collections after the one at startup.
+const GC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Grace period for running orphan containers to exit before Docker kills them.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Payload of the `gc-report` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct GcReport {
+    /// Names of the removed containers
+    pub containers: Vec<String>,
+
+    /// Names of the removed volumes
+    pub volumes: Vec<String>,
+
+    /// When the collection completed
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Whether an object with `labels` belongs to a job that is not active.
+fn is_orphan(labels: &HashMap<String, String>, active_jobs: &HashSet<String>) -> bool {
+    let managed = labels.get(REDSYS_MANAGED_LABEL).is_some_and(|value| value == "true");
+    managed && labels.get(REDSYS_JOB_ID_LABEL).is_some_and(|job_id| !active_jobs.contains(job_id))
+}
+
+/// Reaps containers and volumes of jobs the agent no longer runs.
+#[derive(Debug)]
+pub struct OrphanCollector {
+    /// Jobs whose containers and volumes are kept
+    job_manager: Arc<JobManager>,
+
+    /// Service the containers are listed and removed through
+    docker_service: Arc<DockerService>,
+
+    /// Shared monitoring pause switch
+    pause: Arc<PauseControl>,
+
+    /// Event bus the reports are published to
+    bus: Arc<Bus>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl OrphanCollector {
+    /// Creates a collector keeping the containers and volumes of the jobs in
+    /// `job_manager`.
+    pub fn new(
+        job_manager: Arc<JobManager>,
+        docker_service: Arc<DockerService>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Self {
+            job_manager,
+            docker_service,
+            pause: Arc::new(PauseControl::new()),
+            bus: Arc::new(Bus::new()),
+            cancellation_token,
+        }
+    }
+
+    /// Shares a monitoring pause switch; nothing is collected while it is
+    /// paused.
+    pub fn with_pause_control(self, pause: Arc<PauseControl>) -> Self {
+        Self { pause, ..self }
+    }
+
+    /// Shares the event bus that reports are published to.
+    pub fn with_event_bus(self, bus: Arc<Bus>) -> Self {
+        Self { bus, ..self }
+    }
+
+    /// Whether the job of a container labelled `labels` is still orphaned,
+    /// asking the job manager again rather than trusting an older snapshot.
+    async fn still_orphaned(&self, labels: &HashMap<String, String>) -> bool {
+        match labels.get(REDSYS_JOB_ID_LABEL) {
+            Some(job_id) => self.job_manager.job(job_id).await.is_none_or(|job| job.state.is_finished()),
+            None => false,
+        }
+    }
+
+    /// Removes the orphaned containers and volumes once.
+    ///
+    /// Objects that fail to be removed are logged and skipped.
+    pub async fn collect(&self) -> DockerMonitorResult<GcReport> {
+        let active_jobs = self.job_manager.active_job_ids().await;
+
+        let mut containers = Vec::new();
+        for container in self.docker_service.list_managed_containers(true, None).await? {
+            if !is_orphan(&container.labels, &active_jobs) {
+                continue;
+            }
+            if container.state == "running" {
+                if !self.still_orphaned(&container.labels).await {
+                    debug!("Keeping container {}, its job became active", container.name);
+                    continue;
+                }
+                if let Err(e) = self.docker_service.stop_container(&container.id, STOP_TIMEOUT).await {
+                    warn!("Failed to stop orphaned container {}: {}", container.name, e);
+                    continue;
+                }
+            }
+            if !self.still_orphaned(&container.labels).await {
+                debug!("Keeping container {}, its job became active", container.name);
+                continue;
+            }
+            // Not forced: a container restarted in the meantime stays
+            match self.docker_service.remove_container(&container.id, false).await {
+                Ok(()) => containers.push(container.name),
+                Err(e) => warn!("Failed to remove orphaned container {}: {}", container.name, e),
+            }
+        }
+
+        // Volumes go after the containers that may use them
+        let client = self.docker_service.docker().await?;
+        let filters = HashMap::from([("label".to_string(), vec![format!("{REDSYS_MANAGED_LABEL}=true")])]);
+        let options = ListVolumesOptionsBuilder::new().filters(&filters).build();
+        let response = client
+            .list_volumes(Some(options))
+            .await
+            .map_err(|e| DockerMonitorError::Api(format!("Volume listing failed: {e}")))?;
+        let mut volumes = Vec::new();
+        for volume in response.volumes.unwrap_or_default() {
+            if !is_orphan(&volume.labels, &active_jobs) {
+                continue;
+            }
+            match client.remove_volume(&volume.name, None::<RemoveVolumeOptions>).await {
+                Ok(()) => volumes.push(volume.name),
+                Err(e) => warn!("Failed to remove orphaned volume {}: {}", volume.name, e),
+            }
+        }
+
+        Ok(GcReport {
+            containers,
+            volumes,
+            completed_at: Utc::now(),
+        })
+    }
+
+    /// Collects at startup and then hourly until cancelled, publishing an
+    /// [`Event::GarbageCollected`] after every collection.
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting orphan garbage collection");
+        let mut gc_interval = interval(GC_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = gc_interval.tick() => {
+                    if self.pause.is_paused() {
+                        debug!("Monitoring paused, skipping orphan garbage collection");
+                        continue;
+                    }
+                    match self.collect().await {
+                        Ok(report) => {
+                            if report.containers.is_empty() && report.volumes.is_empty() {
+                                debug!("Orphan garbage collection found nothing to remove");
+                            } else {
+                                info!(
+                                    "Removed {} orphaned container(s) and {} volume(s)",
+                                    report.containers.len(),
+                                    report.volumes.len()
+                                );
+                            }
+                            self.bus.publish(Event::GarbageCollected(report));
+                        }
+                        Err(DockerMonitorError::Connection(e)) => {
+                            debug!("Skipping orphan garbage collection, Docker unavailable: {e}");
+                        }
+                        Err(e) => warn!("Orphan garbage collection failed: {e}"),
+                    }
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Orphan garbage collection received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_orphan() {
+        let active_jobs = HashSet::from(["running".to_string()]);
+        let labels = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+        };
+
+        assert!(is_orphan(&labels(&[(REDSYS_MANAGED_LABEL, "true"), (REDSYS_JOB_ID_LABEL, "lost")]), &active_jobs));
+        assert!(!is_orphan(&labels(&[(REDSYS_MANAGED_LABEL, "true"), (REDSYS_JOB_ID_LABEL, "running")]), &active_jobs));
+        // Not job-scoped
+        assert!(!is_orphan(&labels(&[(REDSYS_MANAGED_LABEL, "true")]), &active_jobs));
+        // The user's own
+        assert!(!is_orphan(&labels(&[(REDSYS_JOB_ID_LABEL, "lost")]), &active_jobs));
+    }
+}
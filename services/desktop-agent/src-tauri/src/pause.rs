@@ -0,0 +1,109 @@
+//! Monitoring pause control for RedSys Desktop Agent
+//!
+//! A single [`PauseControl`] is shared by every background task that talks to
+//! the Docker daemon. While paused, the liveness monitor reports
+//! [`DockerStatus::Paused`](crate::docker_monitor::DockerStatus::Paused) and
+//! stops polling, the events stream disconnects, and the disk usage reporter
+//! and prune scheduler skip their runs. This keeps the agent quiet during
+//! benchmarks or when the user enables a quiet mode.
+//!
+//! ## References
+//! - [Tokio watch channel](https://docs.rs/tokio/latest/tokio/sync/watch/index.html)
+
+use tokio::sync::watch;
+use tracing::info;
+
+/// Shared pause switch for Docker polling and event streams.
+#[derive(Debug)]
+pub struct PauseControl {
+    /// Whether monitoring is paused
+    paused: watch::Sender<bool>,
+}
+
+impl PauseControl {
+    /// Creates a pause control in the running state.
+    pub fn new() -> Self {
+        Self {
+            paused: watch::Sender::new(false),
+        }
+    }
+
+    /// Pauses monitoring.
+    ///
+    /// Returns `false` if monitoring was already paused.
+    pub fn pause(&self) -> bool {
+        let changed = self.paused.send_if_modified(|paused| !std::mem::replace(paused, true));
+        if changed {
+            info!("Docker monitoring paused");
+        }
+        changed
+    }
+
+    /// Resumes monitoring.
+    ///
+    /// Returns `false` if monitoring was not paused.
+    pub fn resume(&self) -> bool {
+        let changed = self.paused.send_if_modified(|paused| std::mem::replace(paused, false));
+        if changed {
+            info!("Docker monitoring resumed");
+        }
+        changed
+    }
+
+    /// Whether monitoring is currently paused.
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Completes once monitoring is paused, immediately if it already is.
+    pub async fn paused(&self) {
+        let mut receiver = self.paused.subscribe();
+        // The sender lives in `self`, so the channel cannot close here
+        let _ = receiver.wait_for(|paused| *paused).await;
+    }
+
+    /// Completes once monitoring is resumed, immediately if it is not paused.
+    pub async fn resumed(&self) {
+        let mut receiver = self.paused.subscribe();
+        let _ = receiver.wait_for(|paused| !*paused).await;
+    }
+}
+
+impl Default for PauseControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_and_resume_report_changes() {
+        let control = PauseControl::new();
+        assert!(!control.is_paused());
+        assert!(!control.resume());
+
+        assert!(control.pause());
+        assert!(!control.pause());
+        assert!(control.is_paused());
+
+        assert!(control.resume());
+        assert!(!control.is_paused());
+    }
+
+    #[tokio::test]
+    async fn test_waiters_complete_on_transition() {
+        let control = std::sync::Arc::new(PauseControl::new());
+        control.resumed().await;
+
+        let waiter = tokio::spawn({
+            let control = control.clone();
+            async move { control.paused().await }
+        });
+        control.pause();
+        waiter.await.unwrap();
+        control.paused().await;
+    }
+}
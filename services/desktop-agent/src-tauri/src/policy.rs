@@ -0,0 +1,203 @@
+//! Image policy for RedSys Desktop Agent
+//!
+//! Enterprises deploying the agent restrict which images jobs may run, e.g.
+//! only their own registry mirror. [`ImagePolicy`] applies the allow- and
+//! blocklists of the `[image_policy]` config section. Jobs are checked when
+//! they are submitted, before anything is pulled, and rejected images fail
+//! with [`AppError::PolicyViolation`].
+//!
+//! References are normalized the way Docker resolves them before matching:
+//! `alpine` becomes `docker.io/library/alpine:latest`. Registry hosts are
+//! lowercased, and `index.docker.io` and `registry-1.docker.io` count as
+//! `docker.io`, both in references and in the registry lists, so an alias
+//! cannot get around a rule for Docker Hub. Image patterns match
+//! the normalized reference, where `*` stands for any characters, e.g.
+//! `ghcr.io/redsys/*` or `*:latest`.
+//!
+//! An image is allowed when
+//! 1. neither its registry nor the reference is blocked,
+//! 2. its registry is allowed, or no registries are listed as allowed, and
+//! 3. the reference matches an allowed pattern, or none are listed.
+//!
+//! ## References
+//! - [Docker image references](https://docs.docker.com/reference/cli/docker/image/tag/)
+
+use crate::config::ImagePolicyConfig;
+use crate::error::{AppError, AppResult};
+
+/// Registry of references without one.
+const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// Other hostnames Docker resolves to Docker Hub.
+const DOCKER_HUB_ALIASES: [&str; 2] = ["index.docker.io", "registry-1.docker.io"];
+
+/// Registry host as Docker resolves it: lowercased, with the Docker Hub
+/// aliases mapped to `docker.io`.
+fn canonical_registry(host: &str) -> String {
+    let host = host.to_ascii_lowercase();
+    if DOCKER_HUB_ALIASES.contains(&host.as_str()) {
+        DEFAULT_REGISTRY.to_string()
+    } else {
+        host
+    }
+}
+
+/// Registry and full form of an image reference, e.g. `docker.io` and
+/// `docker.io/library/alpine:latest` for `alpine`.
+pub fn normalize(reference: &str) -> (String, String) {
+    let (registry, path) = match reference.split_once('/') {
+        Some((host, path)) if host.contains('.') || host.contains(':') || host.eq_ignore_ascii_case("localhost") => {
+            (canonical_registry(host), path.to_string())
+        }
+        _ => (DEFAULT_REGISTRY.to_string(), reference.to_string()),
+    };
+    // Official images, however the registry was spelled
+    let path = if registry == DEFAULT_REGISTRY && !path.contains('/') {
+        format!("library/{path}")
+    } else {
+        path
+    };
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let suffix = if path.contains('@') || name.contains(':') { "" } else { ":latest" };
+    let full = format!("{registry}/{path}{suffix}");
+    (registry, full)
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Allow- and blocklists of registries and image patterns.
+#[derive(Debug, Clone, Default)]
+pub struct ImagePolicy {
+    config: ImagePolicyConfig,
+}
+
+impl ImagePolicy {
+    /// Creates a policy from the `[image_policy]` config section.
+    pub fn new(config: &ImagePolicyConfig) -> Self {
+        Self { config: config.clone() }
+    }
+
+    /// Checks whether `image` may be pulled and run.
+    ///
+    /// Fails with [`AppError::PolicyViolation`] naming the rule that rejected
+    /// the image.
+    pub fn check(&self, image: &str) -> AppResult<()> {
+        let (registry, full) = normalize(image);
+        let violation = |reason: String| AppError::PolicyViolation {
+            image: image.to_string(),
+            reason,
+        };
+
+        let listed = |registries: &[String]| registries.iter().any(|listed| canonical_registry(listed) == registry);
+        if listed(&self.config.blocked_registries) {
+            return Err(violation(format!("registry {registry} is blocked")));
+        }
+        if let Some(pattern) = self.config.blocked_images.iter().find(|pattern| glob_match(pattern, &full)) {
+            return Err(violation(format!("{full} matches blocked pattern {pattern}")));
+        }
+        if !self.config.allowed_registries.is_empty() && !listed(&self.config.allowed_registries) {
+            return Err(violation(format!("registry {registry} is not in the allowed registries")));
+        }
+        if !self.config.allowed_images.is_empty()
+            && !self.config.allowed_images.iter().any(|pattern| glob_match(pattern, &full))
+        {
+            return Err(violation(format!("{full} matches none of the allowed image patterns")));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("alpine").1, "docker.io/library/alpine:latest");
+        assert_eq!(normalize("redsys/runner:1.2").1, "docker.io/redsys/runner:1.2");
+        assert_eq!(normalize("localhost:5000/runner").0, "localhost:5000");
+        assert_eq!(normalize("localhost:5000/runner").1, "localhost:5000/runner:latest");
+        assert_eq!(normalize("ghcr.io/redsys/runner@sha256:abc").1, "ghcr.io/redsys/runner@sha256:abc");
+        assert_eq!(normalize("GHCR.io/redsys/runner").1, "ghcr.io/redsys/runner:latest");
+    }
+
+    #[test]
+    fn test_normalize_docker_io_prefix() {
+        assert_eq!(normalize("docker.io/alpine").1, "docker.io/library/alpine:latest");
+        assert_eq!(normalize("Docker.IO/alpine:3.20").1, "docker.io/library/alpine:3.20");
+        assert_eq!(normalize("docker.io/alpine@sha256:abc").1, "docker.io/library/alpine@sha256:abc");
+    }
+
+    #[test]
+    fn test_normalize_index_docker_io() {
+        assert_eq!(normalize("index.docker.io/alpine").0, "docker.io");
+        assert_eq!(normalize("index.docker.io/alpine").1, "docker.io/library/alpine:latest");
+        assert_eq!(normalize("index.docker.io/redsys/runner:1.2").1, "docker.io/redsys/runner:1.2");
+    }
+
+    #[test]
+    fn test_normalize_registry_1_docker_io() {
+        assert_eq!(normalize("registry-1.docker.io/alpine").0, "docker.io");
+        assert_eq!(normalize("registry-1.docker.io/alpine").1, "docker.io/library/alpine:latest");
+        assert_eq!(normalize("registry-1.docker.io/library/alpine:3.20").1, "docker.io/library/alpine:3.20");
+    }
+
+    #[test]
+    fn test_aliases_do_not_bypass_rules() {
+        let policy = ImagePolicy::new(&ImagePolicyConfig {
+            allowed_registries: Vec::new(),
+            blocked_registries: vec!["index.docker.io".to_string()],
+            allowed_images: Vec::new(),
+            blocked_images: Vec::new(),
+        });
+        assert!(policy.check("alpine").is_err());
+        assert!(policy.check("registry-1.docker.io/alpine").is_err());
+        assert!(policy.check("ghcr.io/redsys/runner").is_ok());
+
+        let policy = ImagePolicy::new(&ImagePolicyConfig {
+            allowed_registries: Vec::new(),
+            blocked_registries: Vec::new(),
+            allowed_images: Vec::new(),
+            blocked_images: vec!["docker.io/library/alpine:*".to_string()],
+        });
+        assert!(policy.check("index.docker.io/alpine:3.20").is_err());
+        assert!(policy.check("Docker.io/alpine").is_err());
+    }
+
+    #[test]
+    fn test_policy_rules() {
+        let policy = ImagePolicy::new(&ImagePolicyConfig {
+            allowed_registries: vec!["ghcr.io".to_string(), "docker.io".to_string()],
+            blocked_registries: Vec::new(),
+            allowed_images: vec!["ghcr.io/redsys/*".to_string(), "docker.io/library/*".to_string()],
+            blocked_images: vec!["*:latest".to_string()],
+        });
+
+        assert!(policy.check("ghcr.io/redsys/runner:1.2").is_ok());
+        assert!(policy.check("python:3.12").is_ok());
+        // Untagged means latest
+        assert!(matches!(policy.check("python"), Err(AppError::PolicyViolation { .. })));
+        assert!(policy.check("quay.io/redsys/runner:1.2").is_err());
+        assert!(policy.check("ghcr.io/someone/miner:1.0").is_err());
+        assert!(ImagePolicy::default().check("anything:latest").is_ok());
+    }
+}
@@ -0,0 +1,270 @@
+//! Port mappings for RedSys Desktop Agent
+//!
+//! Jobs can publish container ports on the host with [`JobPort`]s. A host
+//! port that another process or container already holds makes the job
+//! container fail to start, so the ports are checked when a job is submitted
+//! and every conflict is reported as a `port-conflict` warning before the job
+//! runs.
+//!
+//! [`overview`] lists the ports published by all containers, together with
+//! the conflicts of the jobs that have not finished.
+//!
+//! Host listeners are found by binding the port on all IPv4 interfaces, which
+//! fails while any process listens on it. Ports below 1024 can only be probed
+//! with the privilege to bind them and otherwise count as free.
+//!
+//! ## Events
+//! `port-conflict` carries a [`PortConflict`] per requested port that is
+//! already taken.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, TcpListener, UdpSocket};
+use bollard::models::{ContainerSummary, PortTypeEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::docker::DockerService;
+use crate::docker_monitor::DockerMonitorResult;
+use crate::jobs::{JobManager, JobState, REDSYS_JOB_ID_LABEL};
+
+/// Transport protocol of a port.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "lowercase")]
+pub enum PortProtocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+impl fmt::Display for PortProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortProtocol::Tcp => write!(f, "tcp"),
+            PortProtocol::Udp => write!(f, "udp"),
+        }
+    }
+}
+
+/// Container port a job publishes on the host.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobPort {
+    /// Port inside the container
+    pub container_port: u16,
+
+    /// Port on all host interfaces
+    pub host_port: u16,
+
+    /// Transport protocol, TCP by default
+    #[serde(default)]
+    pub protocol: PortProtocol,
+}
+
+impl JobPort {
+    /// Container port in the `<port>/<protocol>` form of the Docker API.
+    pub fn container_key(&self) -> String {
+        format!("{}/{}", self.container_port, self.protocol)
+    }
+}
+
+/// Container port published on the host.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct PublishedPort {
+    /// Container ID
+    pub container_id: String,
+
+    /// Container name
+    pub container_name: String,
+
+    /// Job the container runs, for agent-created containers
+    pub job_id: Option<String>,
+
+    /// Host address the port is bound to, e.g. `0.0.0.0` or `::`
+    pub host_ip: Option<String>,
+
+    /// Port on the host
+    pub host_port: u16,
+
+    /// Port inside the container
+    pub container_port: u16,
+
+    /// Transport protocol
+    pub protocol: PortProtocol,
+}
+
+impl PublishedPort {
+    /// Published ports of a container; unpublished and SCTP ports are left
+    /// out.
+    pub fn from_summary(summary: &ContainerSummary) -> Vec<Self> {
+        let container_name = summary
+            .names
+            .as_ref()
+            .and_then(|names| names.first())
+            .map(|name| name.trim_start_matches('/').to_string())
+            .unwrap_or_default();
+        let job_id = summary.labels.as_ref().and_then(|labels| labels.get(REDSYS_JOB_ID_LABEL)).cloned();
+
+        summary
+            .ports
+            .iter()
+            .flatten()
+            .filter_map(|port| {
+                let protocol = match port.typ {
+                    Some(PortTypeEnum::UDP) => PortProtocol::Udp,
+                    Some(PortTypeEnum::TCP) | None => PortProtocol::Tcp,
+                    Some(_) => return None,
+                };
+                Some(Self {
+                    container_id: summary.id.clone().unwrap_or_default(),
+                    container_name: container_name.clone(),
+                    job_id: job_id.clone(),
+                    host_ip: port.ip.clone(),
+                    host_port: port.public_port?,
+                    container_port: port.private_port,
+                    protocol,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Payload of the `port-conflict` event: a host port requested by a job that
+/// is already taken.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct PortConflict {
+    /// Job requesting the port
+    pub job_id: String,
+
+    /// Requested host port
+    pub host_port: u16,
+
+    /// Transport protocol
+    pub protocol: PortProtocol,
+
+    /// Names of the other containers publishing the port
+    pub containers: Vec<String>,
+
+    /// Whether a process outside the containers listens on the port
+    pub host_listener: bool,
+}
+
+/// Published ports and the conflicts of the unfinished jobs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct PortOverview {
+    /// Ports published by all containers
+    pub published: Vec<PublishedPort>,
+
+    /// Requested job ports that are already taken
+    pub conflicts: Vec<PortConflict>,
+}
+
+/// Whether a process on the host listens on `port`.
+pub fn host_port_in_use(port: u16, protocol: PortProtocol) -> bool {
+    let address = (Ipv4Addr::UNSPECIFIED, port);
+    let bound = match protocol {
+        PortProtocol::Tcp => TcpListener::bind(address).map(drop),
+        PortProtocol::Udp => UdpSocket::bind(address).map(drop),
+    };
+    matches!(bound, Err(e) if e.kind() == ErrorKind::AddrInUse)
+}
+
+/// Finds the `ports` of job `job_id` that other containers publish or, when
+/// `probe_host` says so, a host process listens on.
+///
+/// Host listeners are only probed for ports no container publishes, since
+/// Docker holds published ports on the host itself.
+pub fn conflicts(
+    job_id: &str,
+    ports: &[JobPort],
+    published: &[PublishedPort],
+    probe_host: impl Fn(u16, PortProtocol) -> bool,
+) -> Vec<PortConflict> {
+    ports
+        .iter()
+        .filter_map(|port| {
+            let containers: BTreeSet<&str> = published
+                .iter()
+                .filter(|other| other.host_port == port.host_port && other.protocol == port.protocol)
+                .filter(|other| other.job_id.as_deref() != Some(job_id))
+                .map(|other| other.container_name.as_str())
+                .collect();
+            let host_listener = containers.is_empty() && probe_host(port.host_port, port.protocol);
+            (!containers.is_empty() || host_listener).then(|| PortConflict {
+                job_id: job_id.to_string(),
+                host_port: port.host_port,
+                protocol: port.protocol,
+                containers: containers.into_iter().map(str::to_string).collect(),
+                host_listener,
+            })
+        })
+        .collect()
+}
+
+/// Lists the published ports of all containers and the port conflicts of the
+/// jobs that have not finished.
+pub async fn overview(docker_service: &DockerService, job_manager: &JobManager) -> DockerMonitorResult<PortOverview> {
+    let published = docker_service.published_ports().await?;
+    let conflicts = job_manager
+        .jobs()
+        .await
+        .into_iter()
+        .filter(|job| !job.state.is_finished())
+        .flat_map(|job| {
+            // The container of a running job holds its own ports
+            let running = job.state == JobState::Running;
+            conflicts(&job.spec.id, &job.spec.ports, &published, |port, protocol| {
+                !running && host_port_in_use(port, protocol)
+            })
+        })
+        .collect();
+    Ok(PortOverview { published, conflicts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn published(container_name: &str, job_id: Option<&str>, host_port: u16) -> PublishedPort {
+        PublishedPort {
+            container_id: container_name.to_string(),
+            container_name: container_name.to_string(),
+            job_id: job_id.map(str::to_string),
+            host_ip: Some("0.0.0.0".to_string()),
+            host_port,
+            container_port: 80,
+            protocol: PortProtocol::Tcp,
+        }
+    }
+
+    #[test]
+    fn test_conflicts_with_containers_and_host_listeners() {
+        let port = |host_port| JobPort { container_port: 80, host_port, protocol: PortProtocol::Tcp };
+        let ports = [port(8080), port(8081), port(9000), port(9001)];
+        let published = [
+            published("web", None, 8080),
+            // Docker lists a port once per address family
+            published("web", None, 8080),
+            published("redsys-job-job-1", Some("job-1"), 8081),
+        ];
+
+        let found = conflicts("job-1", &ports, &published, |port, _| port == 9000);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].host_port, found[0].containers.clone()), (8080, vec!["web".to_string()]));
+        assert!(!found[0].host_listener);
+        assert_eq!((found[1].host_port, found[1].host_listener), (9000, true));
+    }
+
+    #[test]
+    fn test_host_port_in_use() {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(host_port_in_use(port, PortProtocol::Tcp));
+
+        drop(listener);
+        assert!(!host_port_in_use(port, PortProtocol::Tcp));
+    }
+}
@@ -0,0 +1,297 @@
+//! Power state handling for RedSys Desktop Agent
+//!
+//! ## Sleep and resume
+//!
+//! Timers stand still while the machine sleeps. Without help, the agent would
+//! report the Docker status from before the sleep until the next poll, and
+//! the events stream could hang on a connection that died during the sleep.
+//! On resume, [`ResumeWatcher`] immediately:
+//! - forces a Docker re-check over a fresh connection
+//! - reconnects the events stream, which replays the events since the last
+//!   one seen, so nothing that happened around the sleep is lost
+//! - rebuilds the capability profile, since disks, GPUs or the network may
+//!   have changed while the machine was asleep
+//!
+//! ## Detection
+//! Suspend notifications are platform specific, so the watcher detects resumes
+//! from the clocks instead: it ticks every few seconds and compares the wall
+//! clock time between ticks with the tick interval. The wall clock keeps
+//! running during sleep while the tick does not, so a gap well beyond the
+//! interval means the machine was asleep. A large wall clock correction, e.g.
+//! from NTP, looks the same and costs one extra check.
+//!
+//! ## Battery
+//! On laptops running on battery, [`BatteryThrottle`] stretches the steady
+//! Docker polling intervals by `power.battery_interval_factor` and pauses GPU
+//! metrics streaming, and restores both once the machine is plugged in. The
+//! `[power]` config section sets the policy and applies without a restart.
+
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::capability_profile::CapabilityProfiler;
+use crate::config::{AgentConfig, PowerConfig};
+use crate::docker::DockerService;
+use crate::docker_monitor::DockerMonitor;
+use crate::gpu::GpuMetricsStreamer;
+
+/// Interval between clock comparisons.
+const TICK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Wall clock gap beyond the tick interval that counts as a sleep.
+const SLEEP_THRESHOLD: TimeDelta = TimeDelta::seconds(30);
+
+/// Interval between power source checks.
+const POWER_SOURCE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Length of the sleep between two ticks, if the wall clock gap says there
+/// was one.
+fn slept(previous: DateTime<Utc>, now: DateTime<Utc>, tick: Duration) -> Option<TimeDelta> {
+    let tick = TimeDelta::from_std(tick).ok()?;
+    let gap = now - previous - tick;
+    (gap > SLEEP_THRESHOLD).then_some(gap)
+}
+
+/// Re-checks Docker and reconnects the events stream after a sleep.
+#[derive(Debug)]
+pub struct ResumeWatcher {
+    /// Monitor to re-check
+    docker_monitor: Arc<DockerMonitor>,
+
+    /// Service whose events stream to reconnect
+    docker_service: Arc<DockerService>,
+
+    /// Profiler to rebuild the capability profile with
+    capability_profiler: Option<Arc<CapabilityProfiler>>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl ResumeWatcher {
+    /// Creates a watcher for `docker_monitor` and `docker_service`.
+    pub fn new(
+        docker_monitor: Arc<DockerMonitor>,
+        docker_service: Arc<DockerService>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Self {
+            docker_monitor,
+            docker_service,
+            capability_profiler: None,
+            cancellation_token,
+        }
+    }
+
+    /// Rebuilds the capability profile of `capability_profiler` on resume.
+    pub fn with_capability_profiler(self, capability_profiler: Arc<CapabilityProfiler>) -> Self {
+        Self {
+            capability_profiler: Some(capability_profiler),
+            ..self
+        }
+    }
+
+    /// Watches for resumes until cancelled.
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting sleep/resume detection");
+        let mut ticker = interval(TICK_INTERVAL);
+        // A late tick is the signal, so don't catch up with a burst of ticks
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker.tick().await;
+        let mut previous = Utc::now();
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let now = Utc::now();
+                    if let Some(duration) = slept(previous, now, TICK_INTERVAL) {
+                        info!("System resumed after about {}s asleep", duration.num_seconds());
+                        self.resumed().await;
+                    }
+                    previous = now;
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Sleep/resume detection received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Refreshes the Docker state and the capability profile after a resume.
+    async fn resumed(&self) {
+        if let Some(capability_profiler) = &self.capability_profiler {
+            capability_profiler.request_refresh();
+        }
+        // Connections may have died during the sleep, especially to remote hosts
+        self.docker_monitor.reconnect();
+        self.docker_service.reconnect();
+        match self.docker_monitor.force_check().await {
+            Ok(status) => debug!("Docker status after resume: {}", status),
+            Err(e) => warn!("Docker re-check after resume failed: {}", e),
+        }
+    }
+}
+
+/// Whether the machine runs on battery. Blocking.
+///
+/// Peripheral batteries, e.g. of a wireless mouse, don't count.
+#[cfg(target_os = "linux")]
+pub fn on_battery() -> Option<bool> {
+    let mut has_battery = false;
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let path = entry.path();
+        let read = |name: &str| {
+            std::fs::read_to_string(path.join(name))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+        match read("type").as_str() {
+            "Mains" | "USB" if read("online") == "1" => return Some(false),
+            "Battery" if read("scope") != "Device" => has_battery = true,
+            _ => {}
+        }
+    }
+    Some(has_battery)
+}
+
+/// Whether the machine runs on battery. Blocking.
+#[cfg(target_os = "macos")]
+pub fn on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).contains("'Battery Power'"))
+}
+
+/// Whether the machine runs on battery. Blocking.
+///
+/// `BatteryStatus` 1 means discharging; machines without a battery print
+/// nothing.
+#[cfg(target_os = "windows")]
+pub fn on_battery() -> Option<bool> {
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", "(Get-CimInstance Win32_Battery).BatteryStatus"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).lines().any(|line| line.trim() == "1"))
+}
+
+/// Whether the machine runs on battery. Blocking.
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn on_battery() -> Option<bool> {
+    None
+}
+
+/// Slows down monitoring while the machine runs on battery.
+#[derive(Debug)]
+pub struct BatteryThrottle {
+    /// Monitor whose polling is stretched
+    docker_monitor: Arc<DockerMonitor>,
+
+    /// Streamer paused on battery
+    gpu_metrics: Arc<GpuMetricsStreamer>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl BatteryThrottle {
+    /// Creates a throttle for `docker_monitor` and `gpu_metrics`.
+    pub fn new(
+        docker_monitor: Arc<DockerMonitor>,
+        gpu_metrics: Arc<GpuMetricsStreamer>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Self {
+            docker_monitor,
+            gpu_metrics,
+            cancellation_token,
+        }
+    }
+
+    /// Follows the power source and the `[power]` section of `config` until
+    /// cancelled.
+    pub async fn start(self: Arc<Self>, mut config: watch::Receiver<AgentConfig>) {
+        info!("Starting battery power detection");
+        let mut policy = config.borrow_and_update().power.clone();
+        let mut config_open = true;
+        let mut battery = false;
+        let mut ticker = interval(POWER_SOURCE_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let Some(on_battery) = task::spawn_blocking(on_battery).await.ok().flatten() else {
+                        continue;
+                    };
+                    if on_battery != battery {
+                        info!("Running on {}", if on_battery { "battery" } else { "external power" });
+                        battery = on_battery;
+                    }
+                }
+                changed = config.changed(), if config_open => {
+                    if changed.is_err() {
+                        config_open = false;
+                        continue;
+                    }
+                    policy = config.borrow_and_update().power.clone();
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    info!("Battery power detection received cancellation signal, shutting down");
+                    break;
+                }
+            }
+            self.apply(&policy, battery);
+        }
+    }
+
+    /// Throttles or restores monitoring according to `policy`.
+    fn apply(&self, policy: &PowerConfig, on_battery: bool) {
+        let throttled = policy.battery_saver && on_battery;
+        self.docker_monitor
+            .set_interval_factor(if throttled { policy.battery_interval_factor } else { 1 });
+        self.gpu_metrics.set_paused(throttled && policy.pause_gpu_metrics_on_battery);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slept() {
+        let previous = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let late = |secs| previous + TimeDelta::seconds(secs);
+
+        assert_eq!(slept(previous, late(5), TICK_INTERVAL), None);
+        assert_eq!(slept(previous, late(20), TICK_INTERVAL), None);
+        assert_eq!(slept(previous, late(605), TICK_INTERVAL), Some(TimeDelta::seconds(600)));
+        // Wall clock stepped back
+        assert_eq!(slept(previous, late(-60), TICK_INTERVAL), None);
+    }
+
+    #[test]
+    fn test_battery_throttle_follows_policy() {
+        let throttle = BatteryThrottle::new(
+            Arc::new(DockerMonitor::new(CancellationToken::new())),
+            Arc::new(GpuMetricsStreamer::new(CancellationToken::new())),
+            CancellationToken::new(),
+        );
+        let mut policy = PowerConfig::default();
+
+        throttle.apply(&policy, true);
+        assert!(throttle.gpu_metrics.is_paused());
+        throttle.apply(&policy, false);
+        assert!(!throttle.gpu_metrics.is_paused());
+
+        policy.battery_saver = false;
+        throttle.apply(&policy, true);
+        assert!(!throttle.gpu_metrics.is_paused());
+    }
+}
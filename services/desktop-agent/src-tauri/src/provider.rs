@@ -0,0 +1,417 @@
+//! Provider registration for RedSys Desktop Agent
+//!
+//! A machine joins the RedSys platform as a compute provider once: the user
+//! pastes a one-time registration token, the agent submits a
+//! [`HardwareFingerprint`] and the platform answers with a provider identity
+//! (provider ID, agent ID and agent token). The identity is stored in
+//! `provider.json` next to the config file, readable only by the current
+//! user, and used for the backend connection whenever `[backend]` sets no
+//! credentials of its own.
+//!
+//! ## Fingerprint
+//! The fingerprint covers the installation ID (generated on first launch),
+//! CPU model, logical cores, memory, GPUs, OS and architecture. Its `hash`
+//! is the SHA-256 of these fields, so it stays the same across restarts
+//! and changes when the hardware does.
+//!
+//! ## Protocol
+//! Registration uses a short-lived connection to the backend URL:
+//! `{"type":"register","registration_token":..,"fingerprint":{..}}` is
+//! answered with `{"type":"registered","provider_id":..,"agent_id":..,"token":..}`
+//! or `{"type":"registration_rejected","reason":..}`.
+//!
+//! ## References
+//! - [sysinfo crate](https://docs.rs/sysinfo/latest/sysinfo/)
+//! - [sha2 crate](https://docs.rs/sha2/latest/sha2/)
+
+use std::path::{Path, PathBuf};
+use tokio::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info};
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sysinfo::System;
+
+use crate::error::{AppError, AppResult};
+use crate::gpu;
+use crate::APP_IDENTIFIER;
+
+/// Provider identity file name.
+const PROVIDER_FILE_NAME: &str = "provider.json";
+
+/// Installation ID file name.
+const INSTALLATION_ID_FILE_NAME: &str = "installation-id";
+
+/// Time allowed for the whole registration exchange.
+const REGISTRATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Hardware summary identifying this machine to the platform.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HardwareFingerprint {
+    /// Installation ID of this agent
+    pub agent_id: String,
+
+    /// CPU brand string
+    pub cpu_model: String,
+
+    /// Logical CPU cores
+    pub cpu_cores: usize,
+
+    /// Physical memory in bytes, rounded down to whole MiB
+    pub memory_bytes: u64,
+
+    /// GPU models, e.g. `NVIDIA GeForce RTX 4090`
+    pub gpus: Vec<String>,
+
+    /// Operating system name and version, e.g. `Ubuntu 24.04`
+    pub os: String,
+
+    /// CPU architecture, e.g. `x86_64`
+    pub arch: String,
+
+    /// SHA-256 over the fields above, hex encoded
+    pub hash: String,
+}
+
+impl HardwareFingerprint {
+    /// Collects the fingerprint of this machine.
+    ///
+    /// Blocking: reads CPU information and runs GPU detection, so call it
+    /// from `spawn_blocking` in async code.
+    pub fn collect(agent_id: &str) -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_all();
+        system.refresh_memory();
+
+        let os = match (System::name(), System::os_version()) {
+            (Some(name), Some(version)) => format!("{name} {version}"),
+            (Some(name), None) => name,
+            _ => std::env::consts::OS.to_string(),
+        };
+        let gpus = gpu::detect().devices.into_iter().map(|device| device.name).collect();
+
+        Self::new(
+            agent_id,
+            system.cpus().first().map(|cpu| cpu.brand().trim().to_string()).unwrap_or_default(),
+            system.cpus().len(),
+            system.total_memory(),
+            gpus,
+            os,
+        )
+    }
+
+    /// Builds a fingerprint and computes its hash.
+    fn new(agent_id: &str, cpu_model: String, cpu_cores: usize, memory_bytes: u64, gpus: Vec<String>, os: String) -> Self {
+        const MIB: u64 = 1024 * 1024;
+
+        let mut fingerprint = Self {
+            agent_id: agent_id.to_string(),
+            cpu_model,
+            cpu_cores,
+            // Reported memory varies by a few KiB between boots on some systems
+            memory_bytes: memory_bytes / MIB * MIB,
+            gpus,
+            os,
+            arch: std::env::consts::ARCH.to_string(),
+            hash: String::new(),
+        };
+
+        let mut hasher = Sha256::new();
+        for part in [
+            fingerprint.agent_id.as_str(),
+            fingerprint.cpu_model.as_str(),
+            &fingerprint.cpu_cores.to_string(),
+            &fingerprint.memory_bytes.to_string(),
+            &fingerprint.gpus.join(","),
+            fingerprint.os.as_str(),
+            fingerprint.arch.as_str(),
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        fingerprint.hash = hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect();
+        fingerprint
+    }
+}
+
+/// Identity issued by the platform on registration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderIdentity {
+    /// Provider ID on the platform
+    pub provider_id: String,
+
+    /// Agent ID for the backend connection
+    pub agent_id: String,
+
+    /// Agent token for the backend connection
+    pub token: String,
+
+    /// Registration time
+    pub registered_at: DateTime<Utc>,
+}
+
+/// Provider identity without the token, safe to show in the UI.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderRegistration {
+    /// Provider ID on the platform
+    pub provider_id: String,
+
+    /// Agent ID for the backend connection
+    pub agent_id: String,
+
+    /// Registration time
+    pub registered_at: DateTime<Utc>,
+}
+
+impl From<&ProviderIdentity> for ProviderRegistration {
+    fn from(identity: &ProviderIdentity) -> Self {
+        Self {
+            provider_id: identity.provider_id.clone(),
+            agent_id: identity.agent_id.clone(),
+            registered_at: identity.registered_at,
+        }
+    }
+}
+
+/// Stores the installation ID and provider identity.
+#[derive(Debug, Clone)]
+pub struct ProviderStore {
+    /// Directory holding the files
+    directory: PathBuf,
+}
+
+impl ProviderStore {
+    /// Creates a store in `directory`.
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    /// Returns the default directory, the platform config dir.
+    pub fn default_directory() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(APP_IDENTIFIER))
+    }
+
+    /// Gets the installation ID, generating it on first use.
+    pub fn installation_id(&self) -> AppResult<String> {
+        let path = self.directory.join(INSTALLATION_ID_FILE_NAME);
+        match std::fs::read_to_string(&path) {
+            Ok(id) if !id.trim().is_empty() => return Ok(id.trim().to_string()),
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        write_private_file(&path, id.as_bytes())?;
+        info!("Generated installation ID {}", id);
+        Ok(id)
+    }
+
+    /// Loads the stored provider identity, `None` if not registered.
+    pub fn load(&self) -> AppResult<Option<ProviderIdentity>> {
+        match std::fs::read_to_string(self.directory.join(PROVIDER_FILE_NAME)) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Stores the provider identity, readable only by the current user.
+    pub fn save(&self, identity: &ProviderIdentity) -> AppResult<()> {
+        write_private_file(
+            &self.directory.join(PROVIDER_FILE_NAME),
+            serde_json::to_string_pretty(identity)?.as_bytes(),
+        )
+    }
+}
+
+/// Writes a file atomically with owner-only permissions.
+fn write_private_file(path: &Path, contents: &[u8]) -> AppResult<()> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("tmp");
+    // The mode only applies to new files, so a leftover from an interrupted
+    // write must not be reused with whatever permissions it has
+    match std::fs::remove_file(&temp_path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // Windows keeps files under the user profile private through its ACLs
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&temp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Registration request sent to the backend.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RegistrationRequest<'a> {
+    Register {
+        registration_token: &'a str,
+        fingerprint: &'a HardwareFingerprint,
+    },
+}
+
+/// Registration answer from the backend.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RegistrationResponse {
+    Registered {
+        provider_id: String,
+        agent_id: String,
+        token: String,
+    },
+    RegistrationRejected {
+        reason: String,
+    },
+}
+
+/// Parses the backend's answer to a registration request.
+fn parse_registration_response(text: &str) -> AppResult<ProviderIdentity> {
+    let response: RegistrationResponse = serde_json::from_str(text)
+        .map_err(|e| AppError::Network(format!("Unexpected registration response: {e}")))?;
+    match response {
+        RegistrationResponse::Registered { provider_id, agent_id, token } => Ok(ProviderIdentity {
+            provider_id,
+            agent_id,
+            token,
+            registered_at: Utc::now(),
+        }),
+        RegistrationResponse::RegistrationRejected { reason } => {
+            Err(AppError::Permission(format!("Registration rejected: {reason}")))
+        }
+    }
+}
+
+/// Registers this machine as a provider.
+///
+/// # Arguments
+///
+/// * `url` - Backend WebSocket URL
+/// * `registration_token` - One-time token from the RedSys dashboard
+/// * `fingerprint` - Fingerprint of this machine
+pub async fn register(url: &str, registration_token: &str, fingerprint: &HardwareFingerprint) -> AppResult<ProviderIdentity> {
+    let exchange = async {
+        let (socket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to connect to {url}: {e}")))?;
+        let (mut sink, mut stream) = socket.split();
+
+        let request = RegistrationRequest::Register { registration_token, fingerprint };
+        sink.send(Message::Text(serde_json::to_string(&request)?))
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to send registration: {e}")))?;
+
+        while let Some(message) = stream.next().await {
+            match message.map_err(|e| AppError::Network(format!("Registration connection failed: {e}")))? {
+                Message::Text(text) => {
+                    let identity = parse_registration_response(&text)?;
+                    let _ = sink.send(Message::Close(None)).await;
+                    return Ok(identity);
+                }
+                Message::Close(_) => break,
+                other => debug!("Ignoring registration message {:?}", other),
+            }
+        }
+        Err(AppError::Network("Backend closed the connection during registration".to_string()))
+    };
+
+    let identity = tokio::time::timeout(REGISTRATION_TIMEOUT, exchange)
+        .await
+        .map_err(|_| AppError::Timeout { operation: "provider registration".to_string() })??;
+    info!("Registered as provider {} (agent {})", identity.provider_id, identity.agent_id);
+    Ok(identity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(memory_bytes: u64) -> HardwareFingerprint {
+        HardwareFingerprint::new(
+            "a1b2",
+            "AMD Ryzen 9 7950X".to_string(),
+            32,
+            memory_bytes,
+            vec!["NVIDIA GeForce RTX 4090".to_string()],
+            "Ubuntu 24.04".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_fingerprint_hash_is_stable() {
+        let mib = 1024 * 1024;
+        assert_eq!(fingerprint(64 * 1024 * mib + 12).hash, fingerprint(64 * 1024 * mib + 4096).hash);
+        assert_ne!(fingerprint(64 * 1024 * mib).hash, fingerprint(32 * 1024 * mib).hash);
+        assert_eq!(fingerprint(0).hash.len(), 64);
+    }
+
+    #[test]
+    fn test_parse_registration_response() {
+        let identity = parse_registration_response(
+            r#"{"type":"registered","provider_id":"p-7","agent_id":"agent-42","token":"secret"}"#,
+        )
+        .unwrap();
+        assert_eq!(identity.provider_id, "p-7");
+        assert_eq!(identity.token, "secret");
+
+        let rejected = parse_registration_response(r#"{"type":"registration_rejected","reason":"token expired"}"#);
+        assert!(matches!(rejected, Err(AppError::Permission(_))));
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let directory = std::env::temp_dir().join(format!("redsys-provider-test-{}", std::process::id()));
+        let store = ProviderStore::new(directory.clone());
+        assert!(store.load().unwrap().is_none());
+
+        let id = store.installation_id().unwrap();
+        assert_eq!(store.installation_id().unwrap(), id);
+
+        let identity = ProviderIdentity {
+            provider_id: "p-7".to_string(),
+            agent_id: "agent-42".to_string(),
+            token: "secret".to_string(),
+            registered_at: Utc::now(),
+        };
+        store.save(&identity).unwrap();
+        assert_eq!(store.load().unwrap(), Some(identity));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(directory.join(PROVIDER_FILE_NAME)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_temp_file_not_reused() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = std::env::temp_dir().join(format!("redsys-provider-stale-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(PROVIDER_FILE_NAME);
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, "leftover").unwrap();
+        std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private_file(&path, b"{}").unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(std::fs::read(&path).unwrap(), b"{}");
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,428 @@
+//! Scheduled Docker Prune Policies for RedSys Desktop Agent
+//!
+//! This module runs retention rules that reclaim disk space used by finished
+//! RedSys workloads, so providers do not have to clean up manually.
+//!
+//! ## Features
+//! - **Retention rules** per object category (containers, images, volumes, build cache)
+//! - **Label and age filters** mapped directly onto Docker prune filters
+//! - **Tokio interval scheduling** with per-policy run frequency
+//! - **Reclaimed space reporting** via the `docker-prune-completed` Tauri event
+//!
+//! ## References
+//! - [Docker Container Prune](https://docs.docker.com/engine/reference/commandline/container_prune/)
+//! - [Docker Image Prune](https://docs.docker.com/engine/reference/commandline/image_prune/)
+//! - [Docker Engine API Prune Filters](https://docs.docker.com/engine/api/v1.43/#tag/Container/operation/ContainerPrune)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::{sync::{Mutex, RwLock}, time::{interval, Duration}};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+use bollard::query_parameters::{
+    PruneBuildOptionsBuilder, PruneContainersOptionsBuilder, PruneImagesOptionsBuilder,
+    PruneVolumesOptionsBuilder,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::docker::{DockerService, REDSYS_MANAGED_LABEL};
+use crate::docker_monitor::{DockerMonitorError, DockerMonitorResult};
+use crate::error::{AppError, AppResult};
+use crate::pause::PauseControl;
+use crate::telemetry;
+
+/// How often the scheduler checks whether a policy is due.
+const SCHEDULER_TICK: Duration = Duration::from_secs(600);
+
+/// Longest interval a policy may run at, one year.
+const MAX_INTERVAL_HOURS: u64 = 24 * 365;
+
+/// Docker object category targeted by a prune policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PruneTarget {
+    /// Stopped containers
+    Containers,
+
+    /// Unused or dangling images
+    Images,
+
+    /// Unreferenced volumes
+    Volumes,
+
+    /// BuildKit build cache
+    BuildCache,
+}
+
+/// A single retention rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrunePolicy {
+    /// Unique, human-readable policy name
+    pub name: String,
+
+    /// Object category to prune
+    pub target: PruneTarget,
+
+    /// How often the policy runs, in hours, at most a year
+    pub interval_hours: u64,
+
+    /// Only prune objects older than this many hours
+    #[serde(default)]
+    pub older_than_hours: Option<u64>,
+
+    /// Label selectors (`key` or `key=value`) objects must match
+    #[serde(default)]
+    pub labels: Vec<String>,
+
+    /// Restrict image pruning to dangling (untagged) images
+    #[serde(default)]
+    pub dangling_only: bool,
+
+    /// Whether the policy is active
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl PrunePolicy {
+    /// Default RedSys retention rules.
+    ///
+    /// - Stopped RedSys job containers older than 24 hours, checked hourly
+    /// - Dangling images, weekly
+    pub fn defaults() -> Vec<PrunePolicy> {
+        vec![
+            PrunePolicy {
+                name: "redsys-stopped-containers".to_string(),
+                target: PruneTarget::Containers,
+                interval_hours: 1,
+                older_than_hours: Some(24),
+                labels: vec![format!("{REDSYS_MANAGED_LABEL}=true")],
+                dangling_only: false,
+                enabled: true,
+            },
+            PrunePolicy {
+                name: "dangling-images".to_string(),
+                target: PruneTarget::Images,
+                interval_hours: 24 * 7,
+                older_than_hours: None,
+                labels: Vec::new(),
+                dangling_only: true,
+                enabled: true,
+            },
+        ]
+    }
+
+    /// Validates the policy against the filters Docker supports per target.
+    pub fn validate(&self) -> AppResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(AppError::Configuration("Prune policy name must not be empty".to_string()));
+        }
+        if self.interval_hours == 0 {
+            return Err(AppError::Configuration(format!(
+                "Prune policy '{}' must run at least every hour", self.name
+            )));
+        }
+        if self.interval_hours > MAX_INTERVAL_HOURS {
+            return Err(AppError::Configuration(format!(
+                "Prune policy '{}' must run at least once a year", self.name
+            )));
+        }
+        if self.target == PruneTarget::Volumes && self.older_than_hours.is_some() {
+            return Err(AppError::Configuration(format!(
+                "Prune policy '{}': volumes do not support an age filter", self.name
+            )));
+        }
+        if self.target == PruneTarget::BuildCache && !self.labels.is_empty() {
+            return Err(AppError::Configuration(format!(
+                "Prune policy '{}': build cache does not support label filters", self.name
+            )));
+        }
+        if self.dangling_only && self.target != PruneTarget::Images {
+            return Err(AppError::Configuration(format!(
+                "Prune policy '{}': dangling_only only applies to images", self.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Builds the Docker prune filter map for this policy.
+    pub fn filters(&self) -> HashMap<String, Vec<String>> {
+        let mut filters = HashMap::new();
+        if let Some(hours) = self.older_than_hours {
+            filters.insert("until".to_string(), vec![format!("{hours}h")]);
+        }
+        if !self.labels.is_empty() {
+            filters.insert("label".to_string(), self.labels.clone());
+        }
+        match self.target {
+            PruneTarget::Images => {
+                let dangling = if self.dangling_only { "true" } else { "false" };
+                filters.insert("dangling".to_string(), vec![dangling.to_string()]);
+            }
+            // Since API 1.42 only anonymous volumes are pruned unless `all` is set
+            PruneTarget::Volumes => {
+                filters.insert("all".to_string(), vec!["true".to_string()]);
+            }
+            PruneTarget::Containers | PruneTarget::BuildCache => {}
+        }
+        filters
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_hours.saturating_mul(3600))
+    }
+}
+
+/// Result of a single prune policy run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PruneReport {
+    /// Name of the policy that ran
+    pub policy: String,
+
+    /// Object category that was pruned
+    pub target: PruneTarget,
+
+    /// Number of removed objects
+    pub objects_deleted: u64,
+
+    /// Disk space reclaimed in bytes
+    pub reclaimed_bytes: u64,
+
+    /// When the run completed
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Prune scheduler running retention policies on a tokio interval.
+#[derive(Debug)]
+pub struct PruneScheduler {
+    /// Active retention policies
+    policies: RwLock<Vec<PrunePolicy>>,
+
+    /// Last run time per policy name
+    last_runs: Mutex<HashMap<String, Instant>>,
+
+    /// Service whose daemon is pruned
+    docker_service: Arc<DockerService>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: Arc<CancellationToken>,
+
+    /// Shared monitoring pause switch
+    pause: Arc<PauseControl>,
+}
+
+impl PruneScheduler {
+    /// Creates a new prune scheduler with the given policies.
+    pub fn new(
+        policies: Vec<PrunePolicy>,
+        docker_service: Arc<DockerService>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        info!("Initializing prune scheduler with {} policies", policies.len());
+        Self {
+            policies: RwLock::new(policies),
+            last_runs: Mutex::new(HashMap::new()),
+            docker_service,
+            cancellation_token: Arc::new(cancellation_token),
+            pause: Arc::new(PauseControl::new()),
+        }
+    }
+
+    /// Shares a monitoring pause switch; no policies run while it is paused.
+    pub fn with_pause_control(self, pause: Arc<PauseControl>) -> Self {
+        Self { pause, ..self }
+    }
+
+    /// Gets the current prune policies.
+    pub async fn get_policies(&self) -> Vec<PrunePolicy> {
+        self.policies.read().await.clone()
+    }
+
+    /// Replaces all prune policies after validating them.
+    pub async fn set_policies(&self, policies: Vec<PrunePolicy>) -> AppResult<()> {
+        for (index, policy) in policies.iter().enumerate() {
+            policy.validate()?;
+            if policies[..index].iter().any(|other| other.name == policy.name) {
+                return Err(AppError::Configuration(format!(
+                    "Duplicate prune policy name '{}'", policy.name
+                )));
+            }
+        }
+
+        info!("Updating prune policies ({} rules)", policies.len());
+        *self.policies.write().await = policies;
+        Ok(())
+    }
+
+    /// Starts the scheduling loop.
+    ///
+    /// Policies that have never run are due immediately; afterwards each policy
+    /// runs once per its configured interval. Every completed run emits a
+    /// `docker-prune-completed` event.
+    pub async fn start(self: Arc<Self>, app_handle: tauri::AppHandle) {
+        let cancellation_token = self.cancellation_token.clone();
+
+        info!("Starting prune scheduler");
+
+        let mut ticker = interval(SCHEDULER_TICK);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if self.pause.is_paused() {
+                        debug!("Monitoring paused, skipping prune policies");
+                        continue;
+                    }
+                    for policy in self.due_policies().await {
+                        let span = info_span!("prune_policy", policy = %policy.name);
+                        match self.run_policy(&policy).instrument(span.clone()).await {
+                            Ok(report) => {
+                                info!(
+                                    "Prune policy '{}' removed {} objects, reclaimed {} bytes",
+                                    report.policy, report.objects_deleted, report.reclaimed_bytes
+                                );
+                                self.last_runs.lock().await.insert(policy.name.clone(), Instant::now());
+                                span.in_scope(|| {
+                                    if let Err(e) = telemetry::emit(&app_handle, "docker-prune-completed", &report) {
+                                        error!("Failed to emit docker-prune-completed event: {e}");
+                                    }
+                                });
+                            }
+                            Err(DockerMonitorError::Connection(e)) => {
+                                debug!("Skipping prune policy '{}', Docker unavailable: {e}", policy.name);
+                            }
+                            Err(e) => {
+                                warn!("Prune policy '{}' failed: {e}", policy.name);
+                                self.last_runs.lock().await.insert(policy.name.clone(), Instant::now());
+                            }
+                        }
+                    }
+                }
+                _ = cancellation_token.cancelled() => {
+                    info!("Prune scheduler received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Returns enabled policies whose interval has elapsed since their last run.
+    async fn due_policies(&self) -> Vec<PrunePolicy> {
+        let last_runs = self.last_runs.lock().await;
+        self.policies
+            .read()
+            .await
+            .iter()
+            .filter(|policy| policy.enabled)
+            .filter(|policy| {
+                last_runs
+                    .get(&policy.name)
+                    .is_none_or(|last| last.elapsed() >= policy.interval())
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Executes a single policy against the Docker daemon.
+    pub async fn run_policy(&self, policy: &PrunePolicy) -> DockerMonitorResult<PruneReport> {
+        let client = self.docker_service.docker().await?;
+        let filters = policy.filters();
+
+        let (objects_deleted, space_reclaimed) = match policy.target {
+            PruneTarget::Containers => {
+                let options = PruneContainersOptionsBuilder::new().filters(&filters).build();
+                let response = client.prune_containers(Some(options)).await
+                    .map_err(|e| DockerMonitorError::Api(format!("Container prune failed: {e}")))?;
+                (response.containers_deleted.map_or(0, |c| c.len()), response.space_reclaimed)
+            }
+            PruneTarget::Images => {
+                let options = PruneImagesOptionsBuilder::new().filters(&filters).build();
+                let response = client.prune_images(Some(options)).await
+                    .map_err(|e| DockerMonitorError::Api(format!("Image prune failed: {e}")))?;
+                (response.images_deleted.map_or(0, |i| i.len()), response.space_reclaimed)
+            }
+            PruneTarget::Volumes => {
+                let options = PruneVolumesOptionsBuilder::new().filters(&filters).build();
+                let response = client.prune_volumes(Some(options)).await
+                    .map_err(|e| DockerMonitorError::Api(format!("Volume prune failed: {e}")))?;
+                (response.volumes_deleted.map_or(0, |v| v.len()), response.space_reclaimed)
+            }
+            PruneTarget::BuildCache => {
+                let options = PruneBuildOptionsBuilder::new().filters(&filters).build();
+                let response = client.prune_build(Some(options)).await
+                    .map_err(|e| DockerMonitorError::Api(format!("Build cache prune failed: {e}")))?;
+                (response.caches_deleted.map_or(0, |c| c.len()), response.space_reclaimed)
+            }
+        };
+
+        Ok(PruneReport {
+            policy: policy.name.clone(),
+            target: policy.target,
+            objects_deleted: objects_deleted as u64,
+            reclaimed_bytes: space_reclaimed.unwrap_or(0).max(0) as u64,
+            completed_at: Utc::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policies_are_valid() {
+        for policy in PrunePolicy::defaults() {
+            assert!(policy.validate().is_ok(), "invalid default policy {}", policy.name);
+        }
+    }
+
+    #[test]
+    fn test_container_policy_filters() {
+        let policy = &PrunePolicy::defaults()[0];
+        let filters = policy.filters();
+        assert_eq!(filters.get("until"), Some(&vec!["24h".to_string()]));
+        assert_eq!(filters.get("label"), Some(&vec!["redsys.managed=true".to_string()]));
+        assert!(!filters.contains_key("dangling"));
+    }
+
+    #[test]
+    fn test_invalid_policies_rejected() {
+        let mut policy = PrunePolicy::defaults()[0].clone();
+        policy.interval_hours = 0;
+        assert!(policy.validate().is_err());
+        policy.interval_hours = u64::MAX;
+        assert!(policy.validate().is_err());
+        assert_eq!(policy.interval(), Duration::from_secs(u64::MAX));
+        policy.interval_hours = MAX_INTERVAL_HOURS;
+        assert!(policy.validate().is_ok());
+
+        let mut policy = PrunePolicy::defaults()[0].clone();
+        policy.target = PruneTarget::Volumes;
+        assert!(policy.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_policy_names_rejected() {
+        let scheduler = PruneScheduler::new(Vec::new(), Arc::new(DockerService::new(CancellationToken::new())), CancellationToken::new());
+        let policy = PrunePolicy::defaults()[0].clone();
+        let result = scheduler.set_policies(vec![policy.clone(), policy]).await;
+        assert!(result.is_err());
+        assert!(scheduler.get_policies().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_never_run_policies_are_due() {
+        let scheduler = PruneScheduler::new(PrunePolicy::defaults(), Arc::new(DockerService::new(CancellationToken::new())), CancellationToken::new());
+        assert_eq!(scheduler.due_policies().await.len(), 2);
+
+        scheduler.last_runs.lock().await.insert("dangling-images".to_string(), Instant::now());
+        let due = scheduler.due_policies().await;
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].name, "redsys-stopped-containers");
+    }
+}
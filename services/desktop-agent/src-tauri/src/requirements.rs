@@ -0,0 +1,280 @@
+//! Minimum system requirements for RedSys Desktop Agent
+//!
+//! Jobs are sized for a baseline provider machine. [`check_requirements`]
+//! compares this host with that baseline when the agent starts:
+//! - **CPU cores**: at least 2 logical CPUs
+//! - **Memory**: at least 4 GiB
+//! - **Disk**: at least 20 GiB free on the job workspace partition
+//! - **OS version**: Linux kernel 4.18, macOS 12 or Windows 10 build 19041,
+//!   the oldest releases Docker and the GPU stack support
+//! - **Docker version**: Docker Engine 20.10, if the daemon runs at startup
+//!
+//! Values that cannot be read are not held against the host.
+//!
+//! ## Events
+//! The report is stored in the application state. If a requirement is unmet,
+//! `requirements-unmet` carries the same [`RequirementsReport`], so onboarding
+//! can explain what to upgrade instead of failing on the first job.
+
+use std::path::PathBuf;
+use tokio::task;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sysinfo::System;
+
+use crate::config::AgentConfig;
+use crate::disk_pressure;
+use crate::docker_monitor::DockerStatus;
+use crate::workspace::WorkspaceManager;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Minimum logical CPUs.
+pub const MIN_CPU_CORES: usize = 2;
+
+/// Minimum installed memory.
+pub const MIN_MEMORY_BYTES: u64 = 4 * GIB;
+
+/// Minimum free space on the workspace partition.
+pub const MIN_FREE_DISK_BYTES: u64 = 20 * GIB;
+
+/// Minimum Docker Engine version.
+pub const MIN_DOCKER_VERSION: &str = "20.10";
+
+/// Minimum OS version, compared with [`os_version`].
+#[cfg(target_os = "linux")]
+pub const MIN_OS_VERSION: &str = "4.18";
+
+/// Minimum OS version, compared with [`os_version`].
+#[cfg(target_os = "macos")]
+pub const MIN_OS_VERSION: &str = "12";
+
+/// Minimum OS version, compared with [`os_version`].
+#[cfg(target_os = "windows")]
+pub const MIN_OS_VERSION: &str = "19041";
+
+/// Minimum OS version, compared with [`os_version`].
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub const MIN_OS_VERSION: &str = "0";
+
+/// A requirement the host is checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum Requirement {
+    /// Logical CPUs
+    CpuCores,
+
+    /// Installed memory
+    Memory,
+
+    /// Free space on the workspace partition
+    DiskSpace,
+
+    /// Kernel version on Linux, OS version on macOS, build number on Windows
+    OsVersion,
+
+    /// Docker Engine version
+    DockerVersion,
+}
+
+/// A requirement this host does not meet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct UnmetRequirement {
+    /// Requirement that is not met
+    pub requirement: Requirement,
+
+    /// Minimum value, e.g. `4 GiB`
+    pub required: String,
+
+    /// Value found on this host, e.g. `2 GiB`
+    pub actual: String,
+
+    /// What to upgrade, for display
+    pub message: String,
+}
+
+/// Result of a requirements check.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct RequirementsReport {
+    /// Whether every requirement is met
+    pub met: bool,
+
+    /// Requirements this host does not meet
+    pub unmet: Vec<UnmetRequirement>,
+
+    /// When the check ran
+    pub checked_at: DateTime<Utc>,
+}
+
+impl RequirementsReport {
+    fn new(unmet: Vec<UnmetRequirement>) -> Self {
+        Self {
+            met: unmet.is_empty(),
+            unmet,
+            checked_at: Utc::now(),
+        }
+    }
+}
+
+/// What the requirements are checked against; `None` where unknown.
+#[derive(Debug, Default)]
+struct HostSpec {
+    cpu_cores: Option<usize>,
+    memory_bytes: Option<u64>,
+    free_disk_bytes: Option<u64>,
+    os_version: Option<String>,
+    docker_version: Option<String>,
+}
+
+impl HostSpec {
+    /// Reads the host values. Blocking.
+    fn collect(workspace_root: Option<PathBuf>, docker_version: Option<String>) -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_all();
+        system.refresh_memory();
+        Self {
+            cpu_cores: Some(system.cpus().len()).filter(|&cores| cores > 0),
+            memory_bytes: Some(system.total_memory()).filter(|&bytes| bytes > 0),
+            free_disk_bytes: workspace_root.and_then(|root| disk_pressure::available_space(&root)),
+            os_version: os_version(),
+            docker_version,
+        }
+    }
+
+    fn unmet(&self) -> Vec<UnmetRequirement> {
+        let mut unmet = Vec::new();
+        if let Some(cores) = self.cpu_cores.filter(|&cores| cores < MIN_CPU_CORES) {
+            unmet.push(UnmetRequirement {
+                requirement: Requirement::CpuCores,
+                required: MIN_CPU_CORES.to_string(),
+                actual: cores.to_string(),
+                message: format!("At least {MIN_CPU_CORES} CPU cores are needed, this machine has {cores}"),
+            });
+        }
+        // Firmware and integrated GPUs reserve part of the installed memory
+        if let Some(memory) = self.memory_bytes.filter(|&bytes| bytes < MIN_MEMORY_BYTES / 10 * 9) {
+            unmet.push(UnmetRequirement {
+                requirement: Requirement::Memory,
+                required: format_gib(MIN_MEMORY_BYTES),
+                actual: format_gib(memory),
+                message: format!("At least {} of memory is needed", format_gib(MIN_MEMORY_BYTES)),
+            });
+        }
+        if let Some(free) = self.free_disk_bytes.filter(|&bytes| bytes < MIN_FREE_DISK_BYTES) {
+            unmet.push(UnmetRequirement {
+                requirement: Requirement::DiskSpace,
+                required: format_gib(MIN_FREE_DISK_BYTES),
+                actual: format_gib(free),
+                message: format!(
+                    "At least {} must be free for job workspaces, free up space or move the workspace root",
+                    format_gib(MIN_FREE_DISK_BYTES)
+                ),
+            });
+        }
+        if let Some(version) = self.os_version.as_deref().filter(|version| older_than(version, MIN_OS_VERSION)) {
+            unmet.push(UnmetRequirement {
+                requirement: Requirement::OsVersion,
+                required: MIN_OS_VERSION.to_string(),
+                actual: version.to_string(),
+                message: format!("Update the operating system to version {MIN_OS_VERSION} or later"),
+            });
+        }
+        if let Some(version) = self.docker_version.as_deref().filter(|version| older_than(version, MIN_DOCKER_VERSION)) {
+            unmet.push(UnmetRequirement {
+                requirement: Requirement::DockerVersion,
+                required: MIN_DOCKER_VERSION.to_string(),
+                actual: version.to_string(),
+                message: format!("Update Docker to version {MIN_DOCKER_VERSION} or later"),
+            });
+        }
+        unmet
+    }
+}
+
+/// Checks this host against the minimum requirements.
+///
+/// The Docker version comes from `docker_status` and is only checked while
+/// the daemon runs.
+pub async fn check_requirements(config: &AgentConfig, docker_status: &DockerStatus) -> RequirementsReport {
+    let workspace_root = config.workspace.root.clone().or_else(WorkspaceManager::default_root);
+    let docker_version = match docker_status {
+        DockerStatus::Running { version, .. } => Some(version.clone()),
+        _ => None,
+    };
+    let host = task::spawn_blocking(move || HostSpec::collect(workspace_root, docker_version))
+        .await
+        .unwrap_or_default();
+    RequirementsReport::new(host.unmet())
+}
+
+/// Version of the OS compared with [`MIN_OS_VERSION`].
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    System::os_version()
+}
+
+/// Version of the OS compared with [`MIN_OS_VERSION`].
+#[cfg(not(target_os = "macos"))]
+fn os_version() -> Option<String> {
+    System::kernel_version()
+}
+
+/// Whether `version` is older than `minimum`, comparing the leading numeric
+/// components, e.g. `6.8.0-45-generic` as `6.8.0`.
+///
+/// Versions without a leading number are not considered older.
+fn older_than(version: &str, minimum: &str) -> bool {
+    let numbers = |version: &str| -> Vec<u64> {
+        version
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    let version = numbers(version);
+    !version.is_empty() && version < numbers(minimum)
+}
+
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / GIB as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_older_than() {
+        assert!(older_than("19.03.12", "20.10"));
+        assert!(older_than("4.15.0-213-generic", "4.18"));
+        assert!(older_than("20", "20.10"));
+        assert!(!older_than("20.10", "20.10"));
+        assert!(!older_than("27.3.1", "20.10"));
+        assert!(!older_than("6.18.44-fc-v130", "4.18"));
+        assert!(!older_than("unknown", "20.10"));
+    }
+
+    #[test]
+    fn test_unmet_requirements() {
+        let host = HostSpec {
+            cpu_cores: Some(1),
+            memory_bytes: Some(8 * GIB),
+            free_disk_bytes: Some(5 * GIB),
+            os_version: None,
+            docker_version: Some("19.03.12".to_string()),
+        };
+        let report = RequirementsReport::new(host.unmet());
+
+        assert!(!report.met);
+        let unmet: Vec<Requirement> = report.unmet.iter().map(|unmet| unmet.requirement).collect();
+        assert_eq!(unmet, [Requirement::CpuCores, Requirement::DiskSpace, Requirement::DockerVersion]);
+        assert_eq!(report.unmet[1].actual, "5.0 GiB");
+
+        assert!(RequirementsReport::new(HostSpec::default().unmet()).met);
+    }
+}
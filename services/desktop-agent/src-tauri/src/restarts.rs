@@ -0,0 +1,213 @@
+//! Service container restarts for RedSys Desktop Agent
+//!
+//! Most jobs run once, but some RedSys workloads are long-running services,
+//! e.g. an inference server. A job spec with a [`ServicePolicy`] makes its
+//! container a service: whenever it dies with a non-zero exit code, the
+//! agent starts it again after an exponential backoff (`initial_backoff_secs`
+//! doubling up to `max_backoff_secs`). A service that exits with code 0 is
+//! done, and one stopped by the user, the Docker daemon or a cancellation is
+//! not restarted either.
+//!
+//! A run that lasts [`STABLE_RUNTIME`] counts as healthy and resets the
+//! backoff and the restart budget. A service that dies `max_restarts` times
+//! in a row without a healthy run is given up and the job fails with its
+//! last exit. A service that dies [`FLAPPING_THRESHOLD`] times in a row
+//! within the stable runtime is reported as flapping, once per episode.
+//!
+//! ## Events
+//! `service-flapping` carries a [`ServiceFlapping`] when a service starts
+//! flapping.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::time::Duration;
+
+use crate::failures::ExitFailure;
+
+/// Runtime after which a service counts as healthy again.
+pub const STABLE_RUNTIME: Duration = Duration::from_secs(60);
+
+/// Consecutive short runs after which a service is reported as flapping.
+pub const FLAPPING_THRESHOLD: u32 = 3;
+
+/// Restart policy of a long-running service job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServicePolicy {
+    /// Restarts in a row without a healthy run before giving up
+    pub max_restarts: u32,
+
+    /// Delay before the first restart in seconds
+    pub initial_backoff_secs: u64,
+
+    /// Upper bound for the restart delay in seconds
+    pub max_backoff_secs: u64,
+}
+
+impl Default for ServicePolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 10,
+            initial_backoff_secs: 1,
+            max_backoff_secs: 60,
+        }
+    }
+}
+
+impl ServicePolicy {
+    /// Rejects budgets and backoffs that cannot work.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_restarts == 0 {
+            return Err("service.max_restarts must be positive".to_string());
+        }
+        if self.initial_backoff_secs == 0 || self.max_backoff_secs < self.initial_backoff_secs {
+            return Err("service backoff must be positive with max_backoff_secs >= initial_backoff_secs".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// What to do after a service container died.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartDecision {
+    /// Start it again after `delay`; `flapping` is set when the service just
+    /// started flapping
+    Restart { delay: Duration, flapping: bool },
+
+    /// The restart budget is spent
+    GiveUp,
+
+    /// The service exited cleanly and is done
+    Done,
+}
+
+/// Restart state of one service container.
+#[derive(Debug, Clone)]
+pub struct RestartTracker {
+    /// Restart policy of the service
+    policy: ServicePolicy,
+
+    /// Restarts since the last healthy run
+    consecutive: u32,
+
+    /// Restarts over the lifetime of the job
+    total: u32,
+
+    /// Delay before the next restart
+    backoff: Duration,
+}
+
+impl RestartTracker {
+    /// Starts tracking a service with `policy`.
+    pub fn new(policy: &ServicePolicy) -> Self {
+        Self {
+            policy: policy.clone(),
+            consecutive: 0,
+            total: 0,
+            backoff: Duration::from_secs(policy.initial_backoff_secs),
+        }
+    }
+
+    /// Restarts over the lifetime of the job.
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// Restarts since the last healthy run.
+    pub fn consecutive(&self) -> u32 {
+        self.consecutive
+    }
+
+    /// Decides on a restart after the container exited with `exit_code`
+    /// having run for `ran_for`.
+    pub fn on_exit(&mut self, ran_for: Duration, exit_code: i64) -> RestartDecision {
+        if exit_code == 0 {
+            return RestartDecision::Done;
+        }
+        if ran_for >= STABLE_RUNTIME {
+            self.consecutive = 0;
+            self.backoff = Duration::from_secs(self.policy.initial_backoff_secs);
+        }
+        if self.consecutive >= self.policy.max_restarts {
+            return RestartDecision::GiveUp;
+        }
+        let delay = self.backoff;
+        self.backoff = (self.backoff * 2).min(Duration::from_secs(self.policy.max_backoff_secs));
+        self.consecutive += 1;
+        self.total += 1;
+        RestartDecision::Restart {
+            delay,
+            flapping: self.consecutive == FLAPPING_THRESHOLD,
+        }
+    }
+}
+
+/// Payload of the `service-flapping` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct ServiceFlapping {
+    /// Service job
+    pub job_id: String,
+
+    /// Service container ID
+    pub container_id: String,
+
+    /// Restarts since the last healthy run
+    pub consecutive_restarts: u32,
+
+    /// Restarts the policy allows before giving up
+    pub max_restarts: u32,
+
+    /// Exit code of the latest run
+    pub exit_code: i64,
+
+    /// Why the latest run ended, unless it exited cleanly
+    pub failure: Option<ExitFailure>,
+
+    /// When flapping was detected
+    pub detected_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_budget_and_flapping() {
+        let policy = ServicePolicy { max_restarts: 4, initial_backoff_secs: 1, max_backoff_secs: 4 };
+        let mut tracker = RestartTracker::new(&policy);
+        let short = Duration::from_secs(5);
+
+        let delays: Vec<RestartDecision> = (0..5).map(|_| tracker.on_exit(short, 1)).collect();
+        let restart = |secs, flapping| RestartDecision::Restart { delay: Duration::from_secs(secs), flapping };
+        assert_eq!(
+            delays,
+            vec![restart(1, false), restart(2, false), restart(4, true), restart(4, false), RestartDecision::GiveUp]
+        );
+        assert_eq!(tracker.total(), 4);
+    }
+
+    #[test]
+    fn test_healthy_run_resets_budget() {
+        let policy = ServicePolicy { max_restarts: 2, ..Default::default() };
+        let mut tracker = RestartTracker::new(&policy);
+        tracker.on_exit(Duration::from_secs(1), 1);
+        tracker.on_exit(Duration::from_secs(1), 137);
+
+        assert_eq!(
+            tracker.on_exit(STABLE_RUNTIME, 1),
+            RestartDecision::Restart { delay: Duration::from_secs(1), flapping: false }
+        );
+        assert_eq!((tracker.consecutive(), tracker.total()), (1, 3));
+        assert!(ServicePolicy { max_restarts: 0, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_clean_exit_is_not_restarted() {
+        let mut tracker = RestartTracker::new(&ServicePolicy::default());
+        tracker.on_exit(Duration::from_secs(1), 1);
+
+        assert_eq!(tracker.on_exit(Duration::from_secs(1), 0), RestartDecision::Done);
+        assert_eq!((tracker.consecutive(), tracker.total()), (1, 1));
+    }
+}
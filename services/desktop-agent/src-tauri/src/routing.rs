@@ -0,0 +1,119 @@
+//! Window-targeted event routing for RedSys Desktop Agent
+//!
+//! Besides the dashboard, parts of the UI can live in detached webview
+//! windows, e.g. `logs` or `metrics`. Broadcasting every event to every
+//! window would make a logs window receive the full metrics traffic and the
+//! other way round, so [`telemetry::emit`](crate::telemetry::emit) sends
+//! events through the [`EventRouter`]:
+//! - a window without a subscription receives every event, as before
+//! - a window that called `subscribe_window_events` receives only the listed
+//!   events, including their `<name>-batch` form and legacy names
+//!
+//! A subscription ends with `unsubscribe_window_events` or when its window is
+//! destroyed. While no window has one, events are broadcast with a single
+//! emit.
+//!
+//! ## References
+//! - [Tauri Calling the Frontend from Rust](https://v2.tauri.app/develop/calling-frontend/)
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::RwLock;
+use tracing::debug;
+
+use crate::error::{AppError, AppResult};
+
+/// Suffix of batched event names, see [`EventBatch`](crate::events::EventBatch).
+const BATCH_SUFFIX: &str = "-batch";
+
+/// The event a subscription has to list for `event` to be delivered.
+fn subscribed_name(event: &str) -> &str {
+    event.strip_suffix(BATCH_SUFFIX).unwrap_or(event)
+}
+
+/// Per-window event subscriptions.
+#[derive(Debug, Default)]
+pub struct EventRouter {
+    /// Subscribed event names by window label
+    subscriptions: RwLock<HashMap<String, BTreeSet<String>>>,
+}
+
+impl EventRouter {
+    /// Creates a router without subscriptions, which broadcasts every event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the window `label` to `events`; replaces its previous
+    /// subscription.
+    pub fn subscribe(&self, label: &str, events: Vec<String>) -> AppResult<()> {
+        let is_kebab_case = |name: &String| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        };
+        if let Some(name) = events.iter().find(|name| !is_kebab_case(name)) {
+            return Err(AppError::Configuration(format!("Invalid event name '{name}'")));
+        }
+        debug!("Window {} subscribed to {:?}", label, events);
+        self.subscriptions
+            .write()
+            .map_err(|_| AppError::InvalidState("Event subscriptions are poisoned".to_string()))?
+            .insert(label.to_string(), events.into_iter().collect());
+        Ok(())
+    }
+
+    /// Lets the window `label` receive every event again; returns whether it
+    /// had a subscription.
+    pub fn unsubscribe(&self, label: &str) -> bool {
+        self.subscriptions
+            .write()
+            .is_ok_and(|mut subscriptions| subscriptions.remove(label).is_some())
+    }
+
+    /// Whether any window has a subscription.
+    pub fn is_routing(&self) -> bool {
+        self.subscriptions.read().is_ok_and(|subscriptions| !subscriptions.is_empty())
+    }
+
+    /// The windows among `labels` that receive `event`.
+    pub fn targets(&self, event: &str, labels: impl IntoIterator<Item = String>) -> Vec<String> {
+        let Ok(subscriptions) = self.subscriptions.read() else {
+            return labels.into_iter().collect();
+        };
+        let name = subscribed_name(event);
+        labels
+            .into_iter()
+            .filter(|label| subscriptions.get(label).is_none_or(|events| events.contains(name)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels() -> Vec<String> {
+        vec!["main".to_string(), "logs".to_string(), "metrics".to_string()]
+    }
+
+    #[test]
+    fn test_subscribed_windows_only_receive_their_events() {
+        let router = EventRouter::new();
+        assert!(!router.is_routing());
+        router.subscribe("logs", vec!["docker-event".to_string()]).unwrap();
+        router.subscribe("metrics", vec!["system-metrics".to_string()]).unwrap();
+
+        assert_eq!(router.targets("system-metrics", labels()), ["main", "metrics"]);
+        assert_eq!(router.targets("docker-event-batch", labels()), ["main", "logs"]);
+
+        assert!(router.unsubscribe("logs"));
+        assert_eq!(router.targets("system-metrics", labels()), ["main", "logs", "metrics"]);
+        assert!(router.is_routing());
+    }
+
+    #[test]
+    fn test_invalid_event_names_rejected() {
+        let router = EventRouter::new();
+        assert!(router.subscribe("logs", vec!["docker_event".to_string()]).is_err());
+        assert!(router.subscribe("logs", vec![String::new()]).is_err());
+        assert!(!router.is_routing());
+    }
+}
@@ -0,0 +1,436 @@
+//! Container security audit for RedSys Desktop Agent
+//!
+//! Jobs share the provider's Docker daemon with whatever else the provider
+//! runs. A container that can reach the host, e.g. a privileged container or
+//! one with the Docker socket mounted, can reach every job on it too.
+//! [`audit`] inspects the running containers and reports such settings as
+//! [`SecurityFinding`]s:
+//!
+//! - privileged containers
+//! - containers on the host network
+//! - bind mounts of the Docker socket or a directory containing it
+//! - added capabilities from [`DANGEROUS_CAPABILITIES`]
+//!
+//! Findings describe configuration only; the audit changes nothing.
+//!
+//! A daemon listening on TCP without TLS, e.g. `tcp://0.0.0.0:2375`, gives
+//! root on the host to anyone who can reach the port. [`watch_daemon_exposure`]
+//! checks for such listeners whenever the daemon comes up, from the warnings
+//! `docker info` reports and, for a daemon on this machine, the `hosts` of
+//! the daemon configuration file.
+//! Listeners on loopback addresses are only reachable from the host and are
+//! not reported. The `daemon_exposure` doctor check reports the same.
+//!
+//! ## Events
+//! - `security-findings` carries the [`SecurityReport`] of every audit
+//! - `security-warning` carries a [`SecurityWarning`] when the daemon API is
+//!   exposed
+//!
+//! ## References
+//! - [Docker runtime privilege and Linux capabilities](https://docs.docker.com/engine/containers/run/#runtime-privilege-and-linux-capabilities)
+//! - [Docker daemon attack surface](https://docs.docker.com/engine/security/#docker-daemon-attack-surface)
+//! - [Protect the Docker daemon socket](https://docs.docker.com/engine/security/protect-access/)
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, warn};
+use bollard::models::ContainerInspectResponse;
+use bollard::query_parameters::{InspectContainerOptions, ListContainersOptions};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::docker::{DockerInfo, DockerService};
+use crate::docker_monitor::{DockerMonitor, DockerMonitorError, DockerMonitorResult};
+use crate::host_paths;
+use crate::network;
+use crate::telemetry;
+use crate::webhooks::WebhookNotifier;
+
+/// Added capabilities that let a container escape to or disrupt the host.
+pub const DANGEROUS_CAPABILITIES: [&str; 7] = [
+    "ALL",
+    "SYS_ADMIN",
+    "SYS_MODULE",
+    "SYS_PTRACE",
+    "SYS_RAWIO",
+    "DAC_READ_SEARCH",
+    "NET_ADMIN",
+];
+
+/// Kind of insecure container setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// Runs with all capabilities and host devices
+    Privileged,
+
+    /// Shares the host network stack
+    HostNetwork,
+
+    /// Mounts the Docker socket, i.e. controls the daemon
+    DockerSocketMount,
+
+    /// Adds a capability from [`DANGEROUS_CAPABILITIES`]
+    DangerousCapability,
+}
+
+/// How much a finding exposes the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum FindingSeverity {
+    /// Weakens isolation
+    Warning,
+
+    /// Amounts to root access on the host
+    Critical,
+}
+
+/// Insecure setting of a running container.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct SecurityFinding {
+    /// Container ID
+    pub container_id: String,
+
+    /// Container name
+    pub container_name: String,
+
+    /// Kind of setting
+    pub kind: FindingKind,
+
+    /// How much the setting exposes the host
+    pub severity: FindingSeverity,
+
+    /// The setting, e.g. the mounted socket path or the capability
+    pub detail: String,
+}
+
+/// Payload of the `security-findings` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct SecurityReport {
+    /// Findings, most severe first
+    pub findings: Vec<SecurityFinding>,
+
+    /// Number of running containers inspected
+    pub containers_audited: usize,
+
+    /// When the audit ran
+    pub audited_at: DateTime<Utc>,
+}
+
+/// Finds the insecure settings of an inspected container, given the
+/// [`host_paths::docker_socket_paths`].
+pub fn audit_container(inspect: &ContainerInspectResponse, sockets: &[PathBuf]) -> Vec<SecurityFinding> {
+    let finding = |kind, severity, detail: String| SecurityFinding {
+        container_id: inspect.id.clone().unwrap_or_default(),
+        container_name: inspect.name.as_deref().unwrap_or_default().trim_start_matches('/').to_string(),
+        kind,
+        severity,
+        detail,
+    };
+    let mut findings = Vec::new();
+
+    if let Some(host_config) = &inspect.host_config {
+        if host_config.privileged == Some(true) {
+            findings.push(finding(FindingKind::Privileged, FindingSeverity::Critical, "--privileged".to_string()));
+        }
+        if host_config.network_mode.as_deref() == Some("host") {
+            findings.push(finding(FindingKind::HostNetwork, FindingSeverity::Warning, "--network host".to_string()));
+        }
+        for capability in host_config.cap_add.iter().flatten() {
+            let name = capability.to_uppercase();
+            let name = name.trim_start_matches("CAP_");
+            if DANGEROUS_CAPABILITIES.contains(&name) {
+                let severity = if matches!(name, "ALL" | "SYS_ADMIN" | "SYS_MODULE") {
+                    FindingSeverity::Critical
+                } else {
+                    FindingSeverity::Warning
+                };
+                findings.push(finding(FindingKind::DangerousCapability, severity, name.to_string()));
+            }
+        }
+    }
+    for mount in inspect.mounts.iter().flatten() {
+        let Some(source) = mount.source.as_deref() else {
+            continue;
+        };
+        if source.ends_with("docker.sock")
+            || source.ends_with("docker_engine")
+            || host_paths::exposes_socket(Path::new(source), sockets)
+        {
+            findings.push(finding(FindingKind::DockerSocketMount, FindingSeverity::Critical, source.to_string()));
+        }
+    }
+    findings
+}
+
+/// Audits the running containers of the daemon behind `docker_service`.
+pub async fn audit(docker_service: &DockerService) -> DockerMonitorResult<SecurityReport> {
+    let client = docker_service.docker().await?;
+    let sockets = task::spawn_blocking(host_paths::docker_socket_paths).await.unwrap_or_default();
+    let containers = match client.list_containers(None::<ListContainersOptions>).await {
+        Ok(containers) => containers,
+        Err(e) => return Err(DockerMonitorError::api("Container listing failed", e).await),
+    };
+
+    let mut findings = Vec::new();
+    let mut containers_audited = 0;
+    for id in containers.into_iter().filter_map(|container| container.id) {
+        match client.inspect_container(&id, None::<InspectContainerOptions>).await {
+            Ok(inspect) => {
+                containers_audited += 1;
+                findings.extend(audit_container(&inspect, &sockets));
+            }
+            // Removed since it was listed
+            Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => {
+                debug!("Container {} disappeared during the security audit", id);
+            }
+            Err(e) => return Err(DockerMonitorError::api("Container inspection failed", e).await),
+        }
+    }
+    findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+
+    Ok(SecurityReport {
+        findings,
+        containers_audited,
+        audited_at: Utc::now(),
+    })
+}
+
+/// Kind of daemon-wide security problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityWarningKind {
+    /// The API is reachable over the network without TLS
+    ExposedDaemon,
+}
+
+/// Payload of the `security-warning` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct SecurityWarning {
+    /// Kind of problem
+    pub kind: SecurityWarningKind,
+
+    /// What was found and how to fix it
+    pub message: String,
+
+    /// Unprotected listeners, e.g. `tcp://0.0.0.0:2375`
+    pub endpoints: Vec<String>,
+
+    /// When the problem was detected
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Whether a `tcp://host:port` listener only accepts local connections.
+fn is_loopback(endpoint: &str) -> bool {
+    let address = endpoint.split_once("://").map_or(endpoint, |(_, address)| address);
+    let host = address.rsplit_once(':').map_or(address, |(host, _)| host);
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host == "localhost" || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Network-reachable TCP listeners without TLS, from the `docker info`
+/// warnings and the daemon configuration file contents, if any.
+pub fn exposed_endpoints(info_warnings: &[String], daemon_config: Option<&str>) -> Vec<String> {
+    // "WARNING: API is accessible on http://0.0.0.0:2375 without encryption."
+    let from_warnings = info_warnings.iter().filter_map(|warning| {
+        let (_, rest) = warning.split_once("API is accessible on ")?;
+        let url = rest.split_whitespace().next()?;
+        Some(format!("tcp://{}", url.split_once("://").map_or(url, |(_, address)| address)))
+    });
+
+    let config: Option<serde_json::Value> = daemon_config.and_then(|contents| serde_json::from_str(contents).ok());
+    let tls_verified = config.as_ref().and_then(|config| config["tlsverify"].as_bool()).unwrap_or(false);
+    let from_config = config
+        .as_ref()
+        .and_then(|config| config["hosts"].as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|host| host.as_str())
+        .filter(|host| host.starts_with("tcp://") && !tls_verified)
+        .map(str::to_string);
+
+    let mut endpoints: Vec<String> = from_warnings
+        .chain(from_config)
+        .filter(|endpoint| !is_loopback(endpoint))
+        .collect();
+    endpoints.sort();
+    endpoints.dedup();
+    endpoints
+}
+
+/// Locations of the daemon configuration file.
+fn daemon_config_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if cfg!(windows) {
+        paths.push(PathBuf::from(r"C:\ProgramData\docker\config\daemon.json"));
+    } else {
+        paths.push(PathBuf::from("/etc/docker/daemon.json"));
+    }
+    // Rootless Docker and Docker Desktop
+    paths.extend(dirs::config_dir().map(|dir| dir.join("docker").join("daemon.json")));
+    paths.extend(dirs::home_dir().map(|home| home.join(".docker").join("daemon.json")));
+    paths
+}
+
+/// Checks the daemon for network-reachable listeners without TLS, given the
+/// `docker info` warnings. Blocking.
+///
+/// The daemon configuration files on this machine are only read when the
+/// daemon runs here; those of a remote daemon are out of reach.
+pub fn daemon_exposure(info_warnings: &[String]) -> Option<SecurityWarning> {
+    let mut endpoints = exposed_endpoints(info_warnings, None);
+    if !DockerMonitor::docker_host().is_some_and(|host| network::is_remote_host(&host)) {
+        for path in daemon_config_paths() {
+            if let Ok(contents) = std::fs::read_to_string(&path) {
+                endpoints.extend(exposed_endpoints(&[], Some(&contents)));
+            }
+        }
+    }
+    endpoints.sort();
+    endpoints.dedup();
+    if endpoints.is_empty() {
+        return None;
+    }
+    Some(SecurityWarning {
+        kind: SecurityWarningKind::ExposedDaemon,
+        message: format!(
+            "The Docker API is reachable without TLS on {}, giving root access to anyone on the network. \
+             Remove the tcp:// hosts from the daemon configuration or enable TLS verification.",
+            endpoints.join(", ")
+        ),
+        endpoints,
+        detected_at: Utc::now(),
+    })
+}
+
+/// Checks for an exposed daemon whenever `docker_info` is refreshed, i.e.
+/// the daemon came up, until cancelled.
+///
+/// Emits a `security-warning` event, and sends it to the webhooks, per new
+/// set of exposed endpoints.
+pub async fn watch_daemon_exposure(
+    app_handle: tauri::AppHandle,
+    mut docker_info: watch::Receiver<Option<DockerInfo>>,
+    webhooks: Arc<WebhookNotifier>,
+    cancellation_token: CancellationToken,
+) {
+    let mut reported: Vec<String> = Vec::new();
+    loop {
+        let info = docker_info.borrow_and_update().clone();
+        if let Some(info) = info {
+            let exposure = task::spawn_blocking(move || daemon_exposure(&info.warnings)).await.unwrap_or(None);
+            match exposure {
+                Some(warning) if warning.endpoints != reported => {
+                    warn!("{}", warning.message);
+                    if let Err(e) = telemetry::emit(&app_handle, "security-warning", &warning) {
+                        error!("Failed to emit security-warning event: {e}");
+                    }
+                    webhooks.security_warning(&warning);
+                    reported = warning.endpoints;
+                }
+                Some(_) => {}
+                None => reported.clear(),
+            }
+        }
+
+        tokio::select! {
+            changed = docker_info.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+            _ = cancellation_token.cancelled() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::models::{HostConfig, MountPoint};
+
+    #[test]
+    fn test_audit_container() {
+        let inspect = ContainerInspectResponse {
+            id: Some("abc".to_string()),
+            name: Some("/ci-runner".to_string()),
+            host_config: Some(HostConfig {
+                privileged: Some(true),
+                network_mode: Some("host".to_string()),
+                cap_add: Some(vec!["CAP_SYS_PTRACE".to_string(), "CHOWN".to_string()]),
+                ..Default::default()
+            }),
+            mounts: Some(vec![
+                MountPoint {
+                    source: Some("/var/run/docker.sock".to_string()),
+                    ..Default::default()
+                },
+                MountPoint {
+                    source: Some("/home/provider/data".to_string()),
+                    ..Default::default()
+                },
+                // Contains the socket under its own name
+                MountPoint {
+                    source: Some("/run/user/1000".to_string()),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let findings = audit_container(&inspect, &[PathBuf::from("/run/user/1000/docker.sock")]);
+        let kinds: Vec<FindingKind> = findings.iter().map(|finding| finding.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                FindingKind::Privileged,
+                FindingKind::HostNetwork,
+                FindingKind::DangerousCapability,
+                FindingKind::DockerSocketMount,
+                FindingKind::DockerSocketMount,
+            ]
+        );
+        assert_eq!(findings[0].container_name, "ci-runner");
+        assert_eq!(findings[2].detail, "SYS_PTRACE");
+        assert_eq!(findings[4].detail, "/run/user/1000");
+    }
+
+    #[test]
+    fn test_default_container_has_no_findings() {
+        let inspect = ContainerInspectResponse {
+            host_config: Some(HostConfig {
+                network_mode: Some("bridge".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(audit_container(&inspect, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_exposed_endpoints() {
+        let warnings = vec![
+            "WARNING: API is accessible on http://0.0.0.0:2375 without encryption.\n         Access to the remote API is equivalent to root access on the host.".to_string(),
+            "WARNING: API is accessible on http://127.0.0.1:2376 without encryption.".to_string(),
+            "WARNING: No swap limit support".to_string(),
+        ];
+        let config = r#"{"hosts": ["unix:///var/run/docker.sock", "tcp://0.0.0.0:2375", "tcp://192.168.1.10:2375"]}"#;
+        assert_eq!(
+            exposed_endpoints(&warnings, Some(config)),
+            vec!["tcp://0.0.0.0:2375".to_string(), "tcp://192.168.1.10:2375".to_string()]
+        );
+
+        let tls = r#"{"hosts": ["tcp://0.0.0.0:2376"], "tlsverify": true}"#;
+        assert!(exposed_endpoints(&[], Some(tls)).is_empty());
+        assert!(exposed_endpoints(&[], Some(r#"{"hosts": ["tcp://[::1]:2375"]}"#)).is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+//! Background service mode for RedSys Desktop Agent
+//!
+//! Providers want the monitoring to keep running while nobody looks at it.
+//! Started with [`DAEMON_ARG`], the agent runs as a background service: the
+//! Docker monitor, the backend connection, the job subsystem and the other
+//! background services start without a window or tray icon, and the process
+//! outlives its window.
+//!
+//! ## Attaching the GUI
+//! Launching the agent again, e.g. from the applications menu, reaches the
+//! running service over the single instance plugin's local IPC (D-Bus on
+//! Linux, a named pipe on Windows, a local socket on macOS). The service then
+//! opens its dashboard window on the runtime that is already running, and
+//! closing the window only hides it again.
+//!
+//! ## Registration
+//! `redsys-agent service install` registers the service with the user's
+//! service manager, started at login and restarted when it fails:
+//! - **Linux**: systemd user unit `~/.config/systemd/user/<name>.service`,
+//!   bound to the graphical session the window needs
+//! - **macOS**: launch agent `~/Library/LaunchAgents/<identifier>.daemon.plist`
+//!   with `KeepAlive`
+//! - **Windows**: scheduled task run at logon. A Windows service would run in
+//!   session 0, which has no desktop to show the window on and no access to
+//!   the user's Docker Desktop.
+//!
+//! Installing the service removes the [`autostart`](crate::autostart) login
+//! item, which would otherwise race it for the single instance at login. While
+//! the agent already runs with its window, the service hands over to it and
+//! takes over at the next login.
+//!
+//! ## References
+//! - [systemd.service](https://www.freedesktop.org/software/systemd/man/latest/systemd.service.html)
+//! - [launchd.plist](https://keith.github.io/xcode-man-pages/launchd.plist.5.html)
+//! - [schtasks create](https://learn.microsoft.com/en-us/windows-server/administration/windows-commands/schtasks-create)
+
+use std::path::PathBuf;
+use std::process::Command;
+use tracing::info;
+
+use crate::autostart;
+use crate::error::{AppError, AppResult};
+#[cfg(target_os = "macos")]
+use crate::APP_IDENTIFIER;
+
+/// Argument the service manager starts the agent with.
+pub const DAEMON_ARG: &str = "--daemon";
+
+/// Name of the systemd unit and the Windows scheduled task.
+pub const SERVICE_NAME: &str = "redsys-desktop-agent";
+
+/// Whether background service mode was requested on the command line.
+pub fn requested() -> bool {
+    std::env::args().any(|arg| arg == DAEMON_ARG)
+}
+
+/// Path of the executable to register.
+///
+/// AppImages run from a temporary mount, so the image itself is registered.
+fn executable() -> AppResult<PathBuf> {
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    Ok(std::env::current_exe()?)
+}
+
+/// Runs a service manager command, failing unless it succeeds.
+#[cfg_attr(not(any(target_os = "linux", target_os = "macos", target_os = "windows")), allow(dead_code))]
+fn run(program: &str, args: &[&str]) -> AppResult<()> {
+    let output = Command::new(program).args(args).output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(AppError::Permission(format!(
+        "{program} {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    )))
+}
+
+/// Registers and starts the background service.
+pub fn install() -> AppResult<()> {
+    let executable = executable()?;
+    autostart::disable()?;
+    platform::install(&executable)?;
+    info!("Background service installed for {}", executable.display());
+    Ok(())
+}
+
+/// Stops and removes the background service; does nothing if none exists.
+pub fn uninstall() -> AppResult<()> {
+    platform::uninstall()?;
+    info!("Background service uninstalled");
+    Ok(())
+}
+
+/// Whether the background service is registered.
+pub fn is_installed() -> AppResult<bool> {
+    platform::is_installed()
+}
+
+/// Builds the systemd user unit.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn systemd_unit(executable: &std::path::Path) -> String {
+    // ExecStart arguments are quoted with `"`, escaping `"` and `\`; `%` and
+    // `$` would be expanded by systemd
+    let mut quoted = String::from('"');
+    for c in executable.display().to_string().chars() {
+        match c {
+            '"' | '\\' => quoted.extend(['\\', c]),
+            '%' => quoted.push_str("%%"),
+            '$' => quoted.push_str("$$"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    format!(
+        "[Unit]\n\
+         Description=RedSys Desktop Agent\n\
+         PartOf=graphical-session.target\n\
+         After=graphical-session.target\n\
+         \n\
+         [Service]\n\
+         ExecStart={quoted} {DAEMON_ARG}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=graphical-session.target\n"
+    )
+}
+
+/// Builds the launch agent property list.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn launch_agent_plist(label: &str, executable: &std::path::Path) -> String {
+    let escape = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{executable}</string>
+        <string>{DAEMON_ARG}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ProcessType</key>
+    <string>Background</string>
+</dict>
+</plist>
+"#,
+        label = escape(label),
+        executable = escape(&executable.display().to_string()),
+    )
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    /// Location of the unit file.
+    fn location() -> AppResult<PathBuf> {
+        dirs::config_dir()
+            .map(|dir| dir.join("systemd").join("user").join(format!("{SERVICE_NAME}.service")))
+            .ok_or_else(|| AppError::Configuration("Cannot determine the systemd user unit directory".to_string()))
+    }
+
+    pub(super) fn install(executable: &Path) -> AppResult<()> {
+        let location = location()?;
+        if let Some(parent) = location.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&location, systemd_unit(executable))?;
+        run("systemctl", &["--user", "daemon-reload"])?;
+        run("systemctl", &["--user", "enable", "--now", &format!("{SERVICE_NAME}.service")])
+    }
+
+    pub(super) fn uninstall() -> AppResult<()> {
+        let location = location()?;
+        if !location.exists() {
+            return Ok(());
+        }
+        run("systemctl", &["--user", "disable", "--now", &format!("{SERVICE_NAME}.service")])?;
+        std::fs::remove_file(location)?;
+        run("systemctl", &["--user", "daemon-reload"])
+    }
+
+    pub(super) fn is_installed() -> AppResult<bool> {
+        Ok(location()?.exists())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    /// Label of the launch agent.
+    fn label() -> String {
+        format!("{APP_IDENTIFIER}.daemon")
+    }
+
+    /// Location of the launch agent property list.
+    fn location() -> AppResult<PathBuf> {
+        dirs::home_dir()
+            .map(|dir| dir.join("Library").join("LaunchAgents").join(format!("{}.plist", label())))
+            .ok_or_else(|| AppError::Configuration("Cannot determine the launch agent directory".to_string()))
+    }
+
+    pub(super) fn install(executable: &Path) -> AppResult<()> {
+        let location = location()?;
+        if let Some(parent) = location.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&location, launch_agent_plist(&label(), executable))?;
+        run("launchctl", &["load", "-w", &location.display().to_string()])
+    }
+
+    pub(super) fn uninstall() -> AppResult<()> {
+        let location = location()?;
+        if !location.exists() {
+            return Ok(());
+        }
+        run("launchctl", &["unload", "-w", &location.display().to_string()])?;
+        std::fs::remove_file(location)?;
+        Ok(())
+    }
+
+    pub(super) fn is_installed() -> AppResult<bool> {
+        Ok(location()?.exists())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::path::Path;
+
+    use super::*;
+
+    pub(super) fn install(executable: &Path) -> AppResult<()> {
+        let command = format!("\"{}\" {DAEMON_ARG}", executable.display());
+        run(
+            "schtasks",
+            &["/Create", "/TN", SERVICE_NAME, "/TR", &command, "/SC", "ONLOGON", "/RL", "LIMITED", "/F"],
+        )?;
+        run("schtasks", &["/Run", "/TN", SERVICE_NAME])
+    }
+
+    pub(super) fn uninstall() -> AppResult<()> {
+        if !is_installed()? {
+            return Ok(());
+        }
+        // Ending fails when the task is not running, which is the desired state
+        let _ = run("schtasks", &["/End", "/TN", SERVICE_NAME]);
+        run("schtasks", &["/Delete", "/TN", SERVICE_NAME, "/F"])
+    }
+
+    pub(super) fn is_installed() -> AppResult<bool> {
+        Ok(Command::new("schtasks").args(["/Query", "/TN", SERVICE_NAME]).output()?.status.success())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::path::Path;
+
+    use super::*;
+
+    fn unsupported<T>() -> AppResult<T> {
+        Err(AppError::Configuration(format!(
+            "The background service is not supported on {}",
+            std::env::consts::OS
+        )))
+    }
+
+    pub(super) fn install(_executable: &Path) -> AppResult<()> {
+        unsupported()
+    }
+
+    pub(super) fn uninstall() -> AppResult<()> {
+        unsupported()
+    }
+
+    pub(super) fn is_installed() -> AppResult<bool> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_systemd_unit_escapes_executable() {
+        let unit = systemd_unit(Path::new("/opt/Red Sys/agent$1%"));
+        assert!(unit.contains("ExecStart=\"/opt/Red Sys/agent$$1%%\" --daemon\n"));
+        assert!(unit.contains("Restart=on-failure\n"));
+    }
+
+    #[test]
+    fn test_launch_agent_plist_keeps_alive() {
+        let plist = launch_agent_plist("tn.com.redsys-desktop-agent.daemon", Path::new("/Applications/R&D.app/agent"));
+        assert!(plist.contains("<string>/Applications/R&amp;D.app/agent</string>\n        <string>--daemon</string>"));
+        assert!(plist.contains("<key>SuccessfulExit</key>\n        <false/>"));
+    }
+}
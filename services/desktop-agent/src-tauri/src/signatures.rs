@@ -0,0 +1,206 @@
+//! Image signature verification for RedSys Desktop Agent
+//!
+//! Providers that only want to run images published by RedSys, or by their
+//! own build pipeline, enable `image_signatures` with the public keys they
+//! trust. [`SignatureVerifier`] then checks every job image with
+//! `cosign verify` after it is pulled and before its container is created.
+//!
+//! The digest the local image was pulled by is verified rather than its tag,
+//! and the job container is then created from that digest reference, so a
+//! tag moved after the pull cannot slip an unsigned image through. Images
+//! without a registry digest, e.g. built or loaded locally, cannot be
+//! verified and fail.
+//!
+//! The result is recorded in the job record. With `enforce` the job fails
+//! with [`DockerMonitorError::UntrustedImage`]; without it failures are only
+//! logged, which lets a provider audit their jobs before turning enforcement
+//! on.
+//!
+//! ## References
+//! - [Cosign verification](https://docs.sigstore.dev/cosign/verifying/verify/)
+
+use tokio::process::Command;
+use tokio::time::{timeout, Duration};
+use tracing::debug;
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::ImageSignaturesConfig;
+use crate::policy;
+
+/// Longest a single `cosign verify` run may take, including registry lookups.
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Signature verification result of a job image.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct SignatureVerification {
+    /// Digest reference that was verified, e.g. `ghcr.io/redsys/runner@sha256:…`
+    pub reference: Option<String>,
+
+    /// Whether a trusted key signed the image
+    pub verified: bool,
+
+    /// Trusted key the signature was verified with
+    pub key: Option<String>,
+
+    /// Why verification failed
+    pub error: Option<String>,
+
+    /// When the image was verified
+    pub verified_at: DateTime<Utc>,
+}
+
+impl SignatureVerification {
+    /// Failed verification of `reference`.
+    fn failed(reference: Option<String>, error: String) -> Self {
+        Self {
+            reference,
+            verified: false,
+            key: None,
+            error: Some(error),
+            verified_at: Utc::now(),
+        }
+    }
+}
+
+/// Repository of an image reference without tag or digest, e.g.
+/// `docker.io/library/alpine` for `alpine:3.20`.
+fn repository(reference: &str) -> String {
+    let (_, full) = policy::normalize(reference);
+    let full = full.split('@').next().unwrap_or_default();
+    match full.rsplit_once(':') {
+        // A colon followed by a slash separates a registry port
+        Some((repository, tag)) if !tag.contains('/') => repository.to_string(),
+        _ => full.to_string(),
+    }
+}
+
+/// Digest reference of `image` among the repo digests of the local image.
+fn pinned_reference(image: &str, repo_digests: &[String]) -> Option<String> {
+    if image.contains('@') {
+        return Some(image.to_string());
+    }
+    let wanted = repository(image);
+    repo_digests
+        .iter()
+        .find(|digest| digest.split_once('@').is_some_and(|(name, _)| repository(name) == wanted))
+        .cloned()
+}
+
+/// Verifies job images against the trusted keys with `cosign`.
+#[derive(Debug)]
+pub struct SignatureVerifier {
+    /// Cosign executable
+    cosign: String,
+
+    /// Public keys or KMS URIs passed to `cosign verify --key`
+    keys: Vec<String>,
+
+    /// Whether images that fail verification must not run
+    enforce: bool,
+}
+
+impl SignatureVerifier {
+    /// Creates a verifier from the `[image_signatures]` config section.
+    pub fn new(config: &ImageSignaturesConfig) -> Self {
+        Self {
+            cosign: config.cosign_path.clone(),
+            keys: config.trusted_keys.clone(),
+            enforce: config.enforce,
+        }
+    }
+
+    /// Whether images that fail verification must not run.
+    pub fn enforced(&self) -> bool {
+        self.enforce
+    }
+
+    /// Verifies the local image `image` with each trusted key until one
+    /// succeeds.
+    pub async fn verify(&self, client: &Docker, image: &str) -> SignatureVerification {
+        let repo_digests = match client.inspect_image(image).await {
+            Ok(inspect) => inspect.repo_digests.unwrap_or_default(),
+            Err(e) => return SignatureVerification::failed(None, format!("Image inspection failed: {e}")),
+        };
+        let Some(reference) = pinned_reference(image, &repo_digests) else {
+            return SignatureVerification::failed(None, "Image has no registry digest to verify".to_string());
+        };
+
+        let mut errors = Vec::new();
+        for key in &self.keys {
+            match self.verify_with(&reference, key).await {
+                Ok(()) => {
+                    return SignatureVerification {
+                        reference: Some(reference),
+                        verified: true,
+                        key: Some(key.clone()),
+                        error: None,
+                        verified_at: Utc::now(),
+                    };
+                }
+                Err(e) => {
+                    debug!("Signature of {} not verified with {}: {}", reference, key, e);
+                    errors.push(format!("{key}: {e}"));
+                }
+            }
+        }
+        SignatureVerification::failed(Some(reference), errors.join("; "))
+    }
+
+    /// Runs `cosign verify` for one key.
+    async fn verify_with(&self, reference: &str, key: &str) -> Result<(), String> {
+        let command = Command::new(&self.cosign)
+            .args(["verify", "--key", key, reference])
+            .kill_on_drop(true)
+            .output();
+        let output = match timeout(VERIFY_TIMEOUT, command).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => return Err(format!("Failed to run {}: {e}", self.cosign)),
+            Err(_) => return Err(format!("Timed out after {}s", VERIFY_TIMEOUT.as_secs())),
+        };
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map_or_else(|| format!("cosign exited with {}", output.status), str::to_string))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repository() {
+        assert_eq!(repository("alpine:3.20"), "docker.io/library/alpine");
+        assert_eq!(repository("localhost:5000/runner"), "localhost:5000/runner");
+        assert_eq!(repository("localhost:5000/runner@sha256:abc"), "localhost:5000/runner");
+    }
+
+    #[test]
+    fn test_pinned_reference() {
+        let repo_digests = vec![
+            "ghcr.io/redsys/runner@sha256:aaa".to_string(),
+            "redsys/runner@sha256:bbb".to_string(),
+        ];
+        assert_eq!(
+            pinned_reference("redsys/runner:1.2", &repo_digests).as_deref(),
+            Some("redsys/runner@sha256:bbb")
+        );
+        assert_eq!(
+            pinned_reference("ghcr.io/redsys/runner", &repo_digests).as_deref(),
+            Some("ghcr.io/redsys/runner@sha256:aaa")
+        );
+        assert_eq!(pinned_reference("quay.io/redsys/runner:1.2", &repo_digests), None);
+        assert_eq!(
+            pinned_reference("ghcr.io/redsys/runner@sha256:ccc", &[]).as_deref(),
+            Some("ghcr.io/redsys/runner@sha256:ccc")
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! Simulation mode for RedSys Desktop Agent
+//!
+//! Lets frontend developers work on dashboards on machines without Docker.
+//! With `--simulate` on the command line or `features.simulation` in the
+//! config file, the [`DockerMonitor`](crate::docker_monitor::DockerMonitor)
+//! and [`DockerService`](crate::docker::DockerService) talk to a
+//! [`MockDockerClient`] instead of a daemon, and the [`Simulator`] drives it
+//! on timers.
+//!
+//! ## Scenario
+//! The simulated daemon loops through a fixed scenario, so every status the
+//! frontend renders shows up within a few minutes:
+//! - Running, with container start/stop and health events every few seconds
+//! - A quick restart (stopped, then booting)
+//! - An outage long enough to leave the restart window
+//! - A wedged daemon that opens the health-check circuit
+//!
+//! `docker info`, `docker system df` and the container list return fixed,
+//! realistic data. Host system metrics stay real. The agent does not
+//! connect to the RedSys platform or run its job assignments, and does not
+//! collect orphans. Jobs submitted locally, pruning and GPU runtime checks
+//! still need a real daemon and fail as without Docker.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{interval, sleep, Duration, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+use bollard::models::{
+    ContainerSummary, ContainerSummaryStateEnum, EventActor, EventMessage, EventMessageTypeEnum, ImageSummary,
+    SystemDataUsageResponse, SystemInfo, Volume, VolumeUsageData,
+};
+use chrono::Utc;
+
+use crate::client::{DockerClient, MockDaemon, MockDockerClient};
+use crate::config::AgentConfig;
+
+/// Command-line flag that enables simulation mode.
+pub const SIMULATE_ARG: &str = "--simulate";
+
+/// Interval between simulated container events while the daemon runs.
+const EVENT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Daemon behavior and how long it lasts, in seconds.
+const SCENARIO: [(MockDaemon, u64); 8] = [
+    (MockDaemon::Running, 90),
+    // Restart within the restart window
+    (MockDaemon::Stopped, 4),
+    (MockDaemon::Starting, 3),
+    (MockDaemon::Running, 120),
+    // Outage
+    (MockDaemon::Stopped, 45),
+    (MockDaemon::Running, 90),
+    // Wedged daemon, opens the health-check circuit
+    (MockDaemon::Unresponsive, 40),
+    (MockDaemon::Starting, 3),
+];
+
+/// Container actions cycled through while the daemon runs.
+const CONTAINER_ACTIONS: [(&str, &str); 8] = [
+    ("redsys-worker", "start"),
+    ("redsys-worker", "health_status: starting"),
+    ("redsys-worker", "health_status: healthy"),
+    ("postgres", "health_status: unhealthy"),
+    ("redsys-worker", "die"),
+    ("redsys-worker", "stop"),
+    ("postgres", "health_status: healthy"),
+    ("jupyter", "exec_start: bash"),
+];
+
+/// Whether simulation mode was requested on the command line or in `config`.
+pub fn requested(config: &AgentConfig) -> bool {
+    config.features.simulation || std::env::args().any(|arg| arg == SIMULATE_ARG)
+}
+
+/// Drives a [`MockDockerClient`] through the simulation scenario.
+#[derive(Debug)]
+pub struct Simulator {
+    /// Simulated daemon
+    client: Arc<MockDockerClient>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl Simulator {
+    /// Creates a simulator with a running daemon and sample containers.
+    pub fn new(cancellation_token: CancellationToken) -> Self {
+        let client = MockDockerClient::new()
+            .with_version("27.3.1")
+            .with_info(sample_info())
+            .with_disk_usage(sample_disk_usage())
+            .with_containers(CONTAINERS.iter().map(|(name, image, state)| container(name, image, *state)).collect());
+        Self {
+            client: Arc::new(client),
+            cancellation_token,
+        }
+    }
+
+    /// Gets the simulated daemon to hand to the monitor and services.
+    pub fn client(&self) -> Arc<MockDockerClient> {
+        self.client.clone()
+    }
+
+    /// Runs the scenario until cancelled.
+    pub async fn start(self: Arc<Self>) {
+        info!("Simulation mode: Docker status, containers and events are fabricated");
+        let mut actions = CONTAINER_ACTIONS.iter().cycle();
+
+        loop {
+            for (daemon, secs) in SCENARIO {
+                debug!("Simulated Docker daemon {:?} for {}s", daemon, secs);
+                self.client.set_daemon(daemon);
+                let phase = sleep(Duration::from_secs(secs));
+                tokio::pin!(phase);
+                let mut events = interval(EVENT_INTERVAL);
+                events.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                events.reset();
+
+                loop {
+                    tokio::select! {
+                        _ = &mut phase => break,
+                        _ = events.tick(), if daemon == MockDaemon::Running => {
+                            if let Some((name, action)) = actions.next() {
+                                self.container_event(name, action).await;
+                            }
+                        }
+                        _ = self.cancellation_token.cancelled() => {
+                            info!("Simulation received cancellation signal, shutting down");
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies a container action to the simulated daemon and emits its event.
+    async fn container_event(&self, name: &str, action: &str) {
+        // The container list follows the lifecycle events
+        let result = match action {
+            "start" => self.client.start_container(name).await,
+            "die" | "stop" => self.client.stop_container(name, Duration::ZERO).await,
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            debug!("Simulated {} of {} failed: {}", action, name, e);
+        }
+
+        let image = CONTAINERS
+            .iter()
+            .find(|(container, _, _)| *container == name)
+            .map(|(_, image, _)| *image)
+            .unwrap_or_default();
+        let now = Utc::now();
+        self.client.emit_event(EventMessage {
+            typ: Some(EventMessageTypeEnum::CONTAINER),
+            action: Some(action.to_string()),
+            actor: Some(EventActor {
+                id: Some(container_id(name)),
+                attributes: Some(HashMap::from([
+                    ("name".to_string(), name.to_string()),
+                    ("image".to_string(), image.to_string()),
+                ])),
+            }),
+            time: Some(now.timestamp()),
+            time_nano: now.timestamp_nanos_opt(),
+            ..Default::default()
+        });
+    }
+}
+
+/// Sample containers: name, image and initial state.
+const CONTAINERS: [(&str, &str, ContainerSummaryStateEnum); 3] = [
+    ("redsys-worker", "ghcr.io/redsys/worker:1.4.0", ContainerSummaryStateEnum::EXITED),
+    ("postgres", "postgres:16", ContainerSummaryStateEnum::RUNNING),
+    ("jupyter", "jupyter/scipy-notebook:latest", ContainerSummaryStateEnum::RUNNING),
+];
+
+/// Stable fake container ID derived from the name.
+fn container_id(name: &str) -> String {
+    let hex: String = name.bytes().map(|b| format!("{b:02x}")).collect();
+    format!("{hex:0<64.64}")
+}
+
+fn container(name: &str, image: &str, state: ContainerSummaryStateEnum) -> ContainerSummary {
+    let status = match state {
+        ContainerSummaryStateEnum::RUNNING => "Up 2 hours (healthy)",
+        _ => "Exited (0) 10 minutes ago",
+    };
+    ContainerSummary {
+        id: Some(container_id(name)),
+        names: Some(vec![format!("/{name}")]),
+        image: Some(image.to_string()),
+        state: Some(state),
+        status: Some(status.to_string()),
+        labels: Some(HashMap::from([("tn.redsys.simulated".to_string(), "true".to_string())])),
+        created: Some(Utc::now().timestamp() - 7_200),
+        size_rw: Some(48 * 1024 * 1024),
+        ..Default::default()
+    }
+}
+
+fn sample_info() -> SystemInfo {
+    SystemInfo {
+        server_version: Some("27.3.1".to_string()),
+        driver: Some("overlay2".to_string()),
+        docker_root_dir: Some("/var/lib/docker".to_string()),
+        operating_system: Some("Ubuntu 24.04.1 LTS".to_string()),
+        containers: Some(CONTAINERS.len() as i64),
+        containers_running: Some(2),
+        images: Some(3),
+        ncpu: Some(16),
+        mem_total: Some(64 * 1024 * 1024 * 1024),
+        default_runtime: Some("runc".to_string()),
+        ..Default::default()
+    }
+}
+
+fn sample_disk_usage() -> SystemDataUsageResponse {
+    const GIB: i64 = 1024 * 1024 * 1024;
+    let image = |id: &str, size: i64, containers: i64| ImageSummary {
+        id: id.to_string(),
+        size,
+        shared_size: 0,
+        containers,
+        ..Default::default()
+    };
+    let volume = |name: &str, size: i64, ref_count: i64| Volume {
+        name: name.to_string(),
+        usage_data: Some(VolumeUsageData { size, ref_count }),
+        ..Default::default()
+    };
+    SystemDataUsageResponse {
+        layers_size: Some(9 * GIB),
+        images: Some(vec![
+            image("sha256:worker", 2 * GIB, 1),
+            image("sha256:postgres", GIB / 2, 1),
+            image("sha256:scipy-notebook", 6 * GIB, 1),
+        ]),
+        containers: Some(CONTAINERS.iter().map(|(name, image, state)| container(name, image, *state)).collect()),
+        volumes: Some(vec![volume("pgdata", 3 * GIB, 1), volume("datasets-cache", 12 * GIB, 0)]),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::DockerDiskUsage;
+
+    #[tokio::test]
+    async fn test_container_event_updates_container_list() {
+        let simulator = Simulator::new(CancellationToken::new());
+        let client = simulator.client();
+        simulator.container_event("redsys-worker", "start").await;
+        let running = client.list_containers(false, &HashMap::new()).await.unwrap();
+        assert_eq!(running.len(), 3);
+
+        simulator.container_event("postgres", "die").await;
+        assert_eq!(client.list_containers(false, &HashMap::new()).await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_sample_disk_usage_has_reclaimable_space() {
+        let usage = DockerDiskUsage::from_response(&sample_disk_usage());
+        assert!(usage.total_bytes > 0);
+        assert_eq!(usage.volumes.reclaimable_bytes, 12 * 1024 * 1024 * 1024);
+    }
+}
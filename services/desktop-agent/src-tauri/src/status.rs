@@ -0,0 +1,481 @@
+//! Docker daemon status state machine for RedSys Desktop Agent
+//!
+//! The [`DockerMonitor`](crate::docker_monitor::DockerMonitor) feeds every
+//! check result into a [`StatusMachine`], which tracks the daemon through
+//! explicit states:
+//!
+//! ```text
+//! Unknown ──> Checking ──> Running / Stopped / Error
+//!                            │   ^
+//!                 went down  v   │ back within RESTART_WINDOW
+//!                          Restarting ──> Stopped / Error (window passed)
+//! ```
+//!
+//! Pausing moves any state to `Paused`; resuming goes back to `Checking`.
+//! A restart is assumed whenever a running daemon goes down, so the monitor
+//! polls quickly until it is back or [`RESTART_WINDOW`] has passed.
+//!
+//! The machine also decides the reported [`DockerStatus`]: a daemon that is
+//! down while restarting is reported as `Restarting` rather than `Stopped`,
+//! and one that restarted [`DEGRADED_RESTARTS`] times within
+//! [`DEGRADED_WINDOW`] as `Degraded` rather than `Running`.
+//!
+//! ## Events
+//! `docker-status-transition` carries a [`StatusTransition`] with `from`,
+//! `to` and `reason` on every state change. `docker-status-changed` keeps
+//! reporting the detailed [`DockerStatus`]. `docker-daemon-restarted`
+//! carries a [`DaemonRestarted`] with the downtime whenever the daemon comes
+//! back within the restart window.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::time::Duration;
+
+use tracing::{error, info};
+
+use crate::docker_monitor::DockerStatus;
+use crate::telemetry;
+
+/// How long a daemon that went down counts as restarting.
+pub const RESTART_WINDOW: Duration = Duration::from_secs(15);
+
+/// Restarts within [`DEGRADED_WINDOW`] that mark a running daemon degraded.
+pub const DEGRADED_RESTARTS: usize = 3;
+
+/// Period over which restarts are counted.
+pub const DEGRADED_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// State of the Docker daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub enum DaemonState {
+    /// Not checked yet
+    Unknown,
+
+    /// First check after start or resume in progress
+    Checking,
+
+    /// Daemon answers API calls
+    Running,
+
+    /// Daemon is not reachable
+    Stopped,
+
+    /// Daemon is reachable but failing, or unresponsive
+    Error,
+
+    /// Daemon went down after running and is expected back
+    Restarting,
+
+    /// Monitoring is paused
+    Paused,
+}
+
+impl DaemonState {
+    /// State a check result maps to outside of restarts.
+    fn of(status: &DockerStatus) -> Self {
+        match status {
+            DockerStatus::Running { .. } | DockerStatus::Degraded { .. } => Self::Running,
+            DockerStatus::Stopped { .. } | DockerStatus::Starting | DockerStatus::Unreachable { .. } => Self::Stopped,
+            DockerStatus::Error { .. } => Self::Error,
+            DockerStatus::Restarting => Self::Restarting,
+            DockerStatus::Paused => Self::Paused,
+        }
+    }
+
+    /// Whether the daemon is down in this state.
+    fn is_down(self) -> bool {
+        matches!(self, Self::Stopped | Self::Error)
+    }
+}
+
+/// Why the state changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionReason {
+    /// The first check started
+    CheckStarted,
+
+    /// A check found the daemon running
+    DaemonUp,
+
+    /// A check found the daemon stopped or failing
+    DaemonDown,
+
+    /// A running daemon went down
+    DaemonWentDown,
+
+    /// The daemon came back within the restart window
+    DaemonRestarted,
+
+    /// The daemon stayed down for the whole restart window
+    RestartTimedOut,
+
+    /// Monitoring was paused
+    Paused,
+
+    /// Monitoring was resumed
+    Resumed,
+}
+
+/// Payload of the `docker-status-transition` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct StatusTransition {
+    /// State before the transition
+    pub from: DaemonState,
+
+    /// State after the transition
+    pub to: DaemonState,
+
+    /// Why the state changed
+    pub reason: TransitionReason,
+
+    /// Detailed status behind the new state
+    pub status: Option<DockerStatus>,
+
+    /// When the transition happened
+    pub at: DateTime<Utc>,
+}
+
+impl StatusTransition {
+    /// Emits the transition as a `docker-status-transition` event.
+    pub fn emit(&self, app_handle: &tauri::AppHandle) {
+        info!("Docker daemon {:?} -> {:?} ({:?})", self.from, self.to, self.reason);
+        if let Err(e) = telemetry::emit(app_handle, "docker-status-transition", self) {
+            error!("Failed to emit docker-status-transition event: {e}");
+        }
+    }
+}
+
+/// Payload of the `docker-daemon-restarted` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct DaemonRestarted {
+    /// How long the daemon was down in milliseconds
+    pub downtime_ms: u64,
+
+    /// Restarts within [`DEGRADED_WINDOW`], including this one
+    pub recent_restarts: u32,
+
+    /// Daemon version after the restart
+    pub version: Option<String>,
+
+    /// When the daemon was back
+    pub at: DateTime<Utc>,
+}
+
+impl DaemonRestarted {
+    /// Emits the restart as a `docker-daemon-restarted` event.
+    pub fn emit(&self, app_handle: &tauri::AppHandle) {
+        info!("Docker daemon restarted after {}ms down", self.downtime_ms);
+        if let Err(e) = telemetry::emit(app_handle, "docker-daemon-restarted", self) {
+            error!("Failed to emit docker-daemon-restarted event: {e}");
+        }
+    }
+}
+
+/// Result of feeding a check result into the machine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    /// Status to report for the check
+    pub status: DockerStatus,
+
+    /// Whether the reported status differs from the previous check
+    pub status_changed: bool,
+
+    /// State change caused by the check, if any
+    pub transition: Option<StatusTransition>,
+
+    /// Daemon restart completed by the check, if any
+    pub restart: Option<DaemonRestarted>,
+}
+
+/// Tracks the daemon state across checks.
+#[derive(Debug)]
+pub struct StatusMachine {
+    /// Current state
+    state: DaemonState,
+
+    /// Status reported for the latest check
+    status: Option<DockerStatus>,
+
+    /// When the daemon went down while restarting
+    down_since: Option<Instant>,
+
+    /// Checks in a row with an unchanged status
+    stable_checks: u32,
+
+    /// When the daemon came back from recent restarts
+    restarts: VecDeque<Instant>,
+}
+
+impl StatusMachine {
+    /// Creates a machine in the [`DaemonState::Unknown`] state.
+    pub fn new() -> Self {
+        Self {
+            state: DaemonState::Unknown,
+            status: None,
+            down_since: None,
+            stable_checks: 0,
+            restarts: VecDeque::new(),
+        }
+    }
+
+    /// Current state.
+    pub fn state(&self) -> DaemonState {
+        self.state
+    }
+
+    /// Status reported for the latest check.
+    pub fn status(&self) -> Option<&DockerStatus> {
+        self.status.as_ref()
+    }
+
+    /// Checks in a row that did not change the status.
+    pub fn stable_checks(&self) -> u32 {
+        self.stable_checks
+    }
+
+    /// Marks the start of a check; only the first check is a transition.
+    pub fn begin_check(&mut self) -> Option<StatusTransition> {
+        (self.state == DaemonState::Unknown).then(|| self.transition(DaemonState::Checking, TransitionReason::CheckStarted))
+    }
+
+    /// Feeds in a check result.
+    pub fn observe(&mut self, checked: DockerStatus, now: Instant) -> Observation {
+        let observed = DaemonState::of(&checked);
+        let next = match (self.state, observed) {
+            (DaemonState::Running, down) if down.is_down() => {
+                self.down_since = Some(now);
+                Some((DaemonState::Restarting, TransitionReason::DaemonWentDown))
+            }
+            (DaemonState::Restarting, DaemonState::Running) => Some((DaemonState::Running, TransitionReason::DaemonRestarted)),
+            (DaemonState::Restarting, down) => self
+                .down_since
+                .filter(|since| now.duration_since(*since) >= RESTART_WINDOW)
+                .map(|_| (down, TransitionReason::RestartTimedOut)),
+            (current, observed) if current != observed => Some((
+                observed,
+                if observed == DaemonState::Running { TransitionReason::DaemonUp } else { TransitionReason::DaemonDown },
+            )),
+            _ => None,
+        };
+
+        while self.restarts.front().is_some_and(|at| now.duration_since(*at) > DEGRADED_WINDOW) {
+            self.restarts.pop_front();
+        }
+        let from = self.state;
+        let mut restart = None;
+        if let Some((to, reason)) = next {
+            if reason == TransitionReason::DaemonRestarted {
+                self.restarts.push_back(now);
+                let version = match &checked {
+                    DockerStatus::Running { version, .. } => Some(version.clone()),
+                    _ => None,
+                };
+                restart = Some(DaemonRestarted {
+                    downtime_ms: self.down_since.map_or(0, |since| now.duration_since(since).as_millis() as u64),
+                    recent_restarts: self.restarts.len() as u32,
+                    version,
+                    at: Utc::now(),
+                });
+            }
+            if to != DaemonState::Restarting {
+                self.down_since = None;
+            }
+            self.state = to;
+        }
+
+        let status = self.reported(checked);
+        let status_changed = self.status.as_ref() != Some(&status);
+        self.stable_checks = if status_changed { 0 } else { self.stable_checks + 1 };
+        self.status = Some(status.clone());
+
+        let transition = next.map(|(to, reason)| StatusTransition {
+            from,
+            to,
+            reason,
+            status: Some(status.clone()),
+            at: Utc::now(),
+        });
+        Observation { status, status_changed, transition, restart }
+    }
+
+    /// Status to report for a check result in the current state.
+    fn reported(&self, checked: DockerStatus) -> DockerStatus {
+        match (self.state, checked) {
+            (DaemonState::Restarting, DockerStatus::Stopped { .. } | DockerStatus::Starting | DockerStatus::Unreachable { .. }) => {
+                DockerStatus::Restarting
+            }
+            (DaemonState::Running, DockerStatus::Running { .. }) if self.restarts.len() >= DEGRADED_RESTARTS => {
+                DockerStatus::Degraded {
+                    reason: format!(
+                        "Docker daemon restarted {} times in the last {} minutes",
+                        self.restarts.len(),
+                        DEGRADED_WINDOW.as_secs() / 60
+                    ),
+                }
+            }
+            (_, checked) => checked,
+        }
+    }
+
+    /// Moves to [`DaemonState::Paused`].
+    pub fn pause(&mut self) -> Option<StatusTransition> {
+        if self.state == DaemonState::Paused {
+            return None;
+        }
+        self.status = Some(DockerStatus::Paused);
+        self.down_since = None;
+        Some(self.transition(DaemonState::Paused, TransitionReason::Paused))
+    }
+
+    /// Moves from [`DaemonState::Paused`] back to [`DaemonState::Checking`].
+    pub fn resume(&mut self) -> Option<StatusTransition> {
+        (self.state == DaemonState::Paused).then(|| self.transition(DaemonState::Checking, TransitionReason::Resumed))
+    }
+
+    fn transition(&mut self, to: DaemonState, reason: TransitionReason) -> StatusTransition {
+        let from = std::mem::replace(&mut self.state, to);
+        StatusTransition {
+            from,
+            to,
+            reason,
+            status: self.status.clone(),
+            at: Utc::now(),
+        }
+    }
+}
+
+impl Default for StatusMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::EngineDetails;
+    use crate::diagnosis::DockerUnavailableReason;
+
+    fn stopped() -> DockerStatus {
+        DockerStatus::Stopped { reason: DockerUnavailableReason::ServiceStopped }
+    }
+
+    fn running() -> DockerStatus {
+        DockerStatus::Running { version: "27.0.1".to_string(), engine: EngineDetails::default() }
+    }
+
+    fn transition_of(observation: Observation) -> Option<(DaemonState, DaemonState, TransitionReason)> {
+        observation.transition.map(|t| (t.from, t.to, t.reason))
+    }
+
+    #[test]
+    fn test_startup_transitions() {
+        let mut machine = StatusMachine::new();
+        let now = Instant::now();
+        let checking = machine.begin_check().unwrap();
+        assert_eq!((checking.from, checking.to), (DaemonState::Unknown, DaemonState::Checking));
+        assert!(machine.begin_check().is_none());
+
+        let observation = machine.observe(running(), now);
+        assert!(observation.status_changed);
+        assert_eq!(
+            transition_of(observation),
+            Some((DaemonState::Checking, DaemonState::Running, TransitionReason::DaemonUp))
+        );
+
+        let observation = machine.observe(running(), now);
+        assert!(!observation.status_changed);
+        assert!(observation.transition.is_none());
+        assert_eq!(machine.stable_checks(), 1);
+    }
+
+    #[test]
+    fn test_restart_within_window() {
+        let mut machine = StatusMachine::new();
+        let now = Instant::now();
+        machine.observe(running(), now);
+
+        assert_eq!(
+            transition_of(machine.observe(stopped(), now)),
+            Some((DaemonState::Running, DaemonState::Restarting, TransitionReason::DaemonWentDown))
+        );
+        assert_eq!(machine.status(), Some(&DockerStatus::Restarting));
+        assert!(machine.observe(stopped(), now + Duration::from_secs(5)).transition.is_none());
+        let observation = machine.observe(running(), now + Duration::from_secs(6));
+        let restart = observation.restart.clone().unwrap();
+        assert_eq!(
+            transition_of(observation),
+            Some((DaemonState::Restarting, DaemonState::Running, TransitionReason::DaemonRestarted))
+        );
+        assert_eq!((restart.downtime_ms, restart.recent_restarts), (6000, 1));
+        assert_eq!(restart.version.as_deref(), Some("27.0.1"));
+    }
+
+    #[test]
+    fn test_restart_times_out() {
+        let mut machine = StatusMachine::new();
+        let now = Instant::now();
+        machine.observe(running(), now);
+        machine.observe(stopped(), now);
+
+        assert_eq!(
+            transition_of(machine.observe(stopped(), now + RESTART_WINDOW)),
+            Some((DaemonState::Restarting, DaemonState::Stopped, TransitionReason::RestartTimedOut))
+        );
+        assert_eq!(
+            transition_of(machine.observe(running(), now + RESTART_WINDOW)),
+            Some((DaemonState::Stopped, DaemonState::Running, TransitionReason::DaemonUp))
+        );
+    }
+
+    #[test]
+    fn test_repeated_restarts_degrade() {
+        let mut machine = StatusMachine::new();
+        let mut now = Instant::now();
+        machine.observe(running(), now);
+        for _ in 0..DEGRADED_RESTARTS {
+            now += Duration::from_secs(30);
+            machine.observe(DockerStatus::Unreachable { endpoint: "tcp://10.0.0.5:2375".to_string(), reason: DockerUnavailableReason::Unknown }, now);
+            assert_eq!(machine.status(), Some(&DockerStatus::Restarting));
+            machine.observe(running(), now + Duration::from_secs(2));
+        }
+        assert!(matches!(machine.status(), Some(DockerStatus::Degraded { .. })));
+        assert_eq!(machine.state(), DaemonState::Running);
+
+        // Old restarts age out
+        let observation = machine.observe(running(), now + DEGRADED_WINDOW);
+        assert!(observation.status_changed);
+        assert_eq!(observation.status, running());
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let mut machine = StatusMachine::new();
+        machine.observe(stopped(), Instant::now());
+
+        let paused = machine.pause().unwrap();
+        assert_eq!((paused.from, paused.reason), (DaemonState::Stopped, TransitionReason::Paused));
+        assert_eq!(machine.status(), Some(&DockerStatus::Paused));
+        assert!(machine.pause().is_none());
+
+        let resumed = machine.resume().unwrap();
+        assert_eq!((resumed.from, resumed.to), (DaemonState::Paused, DaemonState::Checking));
+        assert!(machine.resume().is_none());
+    }
+
+    #[test]
+    fn test_transition_serialization() {
+        let mut machine = StatusMachine::new();
+        let value = serde_json::to_value(machine.begin_check().unwrap()).unwrap();
+        assert_eq!(value["from"], "Unknown");
+        assert_eq!(value["to"], "Checking");
+        assert_eq!(value["reason"], "check_started");
+    }
+}
@@ -0,0 +1,324 @@
+//! Local status HTTP endpoint for RedSys Desktop Agent
+//!
+//! Serves agent health and Docker status over plain HTTP so orchestration
+//! scripts and the RedSys backend sidecar can query the agent without going
+//! through the Tauri IPC.
+//!
+//! ## Endpoints
+//! - `GET /healthz` - `200 {"status":"ok"}` while the agent is running
+//! - `GET /status` - current [`DockerStatus`] and [`AppState`] as JSON
+//!
+//! The server binds to `127.0.0.1:7421` by default (see `[status_server]` in
+//! the config file) and only implements what these endpoints need: one
+//! request per connection, `GET`/`HEAD`, no request bodies.
+//!
+//! Requests must name the server as `localhost`, `127.0.0.1` or `[::1]` with
+//! the bound port in their `Host` header, and are refused with `403`
+//! otherwise. A web page the user visits cannot read the status through DNS
+//! rebinding, since the browser sends the attacker's hostname.
+//!
+//! ## References
+//! - [HTTP/1.1 Message Syntax](https://www.rfc-editor.org/rfc/rfc9112)
+//! - [Tokio TcpListener](https://docs.rs/tokio/latest/tokio/net/struct.TcpListener.html)
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::{task, time::Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use serde::Serialize;
+
+use crate::app_state::AppStateStore;
+use crate::docker_monitor::{DockerMonitor, DockerStatus};
+use crate::error::{AppError, AppResult};
+use crate::types::AppState;
+
+/// Maximum accepted size of the request head.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Time a client gets to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Response body of `/status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentStatus {
+    /// Current Docker daemon status
+    pub docker: DockerStatus,
+
+    /// Application state
+    pub app: AppState,
+}
+
+/// HTTP response produced by the router.
+#[derive(Debug, Clone, PartialEq)]
+struct Response {
+    /// Status code
+    status: u16,
+
+    /// JSON body
+    body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(status: u16, body: &T) -> Self {
+        let body = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+        Self { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message }))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+
+    /// Serializes the response, omitting the body for `HEAD` requests.
+    fn to_bytes(&self, include_body: bool) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.reason(),
+            self.body.len()
+        );
+        if include_body {
+            response.push_str(&self.body);
+        }
+        response.into_bytes()
+    }
+}
+
+/// Local HTTP server exposing agent health and status.
+#[derive(Debug)]
+pub struct StatusServer {
+    /// Address to listen on
+    addr: SocketAddr,
+
+    /// Source of the Docker status
+    docker_monitor: Arc<DockerMonitor>,
+
+    /// Source of the application state
+    app_state: Arc<AppStateStore>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: Arc<CancellationToken>,
+}
+
+impl StatusServer {
+    /// Creates a new status server.
+    pub fn new(addr: SocketAddr, docker_monitor: Arc<DockerMonitor>, cancellation_token: CancellationToken) -> Self {
+        Self {
+            addr,
+            docker_monitor,
+            app_state: Arc::new(AppStateStore::new()),
+            cancellation_token: Arc::new(cancellation_token),
+        }
+    }
+
+    /// Serves `app_state` in `/status` instead of a default state.
+    pub fn with_app_state(self, app_state: Arc<AppStateStore>) -> Self {
+        Self { app_state, ..self }
+    }
+
+    /// Binds the listener and starts serving in the background.
+    ///
+    /// Returns the bound address, which differs from the configured one when
+    /// binding to port 0.
+    pub async fn start(self: Arc<Self>) -> AppResult<SocketAddr> {
+        let listener = TcpListener::bind(self.addr).await.map_err(|e| {
+            AppError::Network(format!("Failed to bind status server to {}: {e}", self.addr))
+        })?;
+        let local_addr = listener.local_addr()?;
+
+        if !local_addr.ip().is_loopback() {
+            warn!("Status server listening on non-loopback address {}", local_addr);
+        }
+        info!("Status server listening on http://{}", local_addr);
+
+        let cancellation_token = self.cancellation_token.clone();
+        task::spawn(async move {
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, peer)) => {
+                            let server = self.clone();
+                            task::spawn(async move {
+                                if let Err(e) = server.handle_connection(stream, local_addr.port()).await {
+                                    debug!("Status server connection from {} failed: {}", peer, e);
+                                }
+                            });
+                        }
+                        Err(e) => warn!("Status server failed to accept connection: {e}"),
+                    },
+                    _ = cancellation_token.cancelled() => {
+                        info!("Status server received cancellation signal, shutting down");
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(local_addr)
+    }
+
+    /// Reads one request, routes it and writes the response.
+    ///
+    /// `port` is the bound port the `Host` header must name.
+    async fn handle_connection(&self, mut stream: TcpStream, port: u16) -> AppResult<()> {
+        let mut buffer = Vec::with_capacity(1024);
+        let read = tokio::time::timeout(REQUEST_TIMEOUT, async {
+            let mut chunk = [0u8; 1024];
+            while !buffer.windows(4).any(|window| window == b"\r\n\r\n") && buffer.len() < MAX_REQUEST_BYTES {
+                let n = stream.read(&mut chunk).await?;
+                if n == 0 {
+                    break;
+                }
+                buffer.extend_from_slice(&chunk[..n]);
+            }
+            Ok::<_, std::io::Error>(())
+        })
+        .await;
+        match read {
+            Ok(result) => result?,
+            Err(_) => {
+                return Err(AppError::Timeout {
+                    operation: "status server request".to_string(),
+                })
+            }
+        }
+
+        let head = String::from_utf8_lossy(&buffer);
+        let (response, include_body) = match parse_request_line(&head) {
+            Some((method, _)) if !host_allowed(&head, port) => {
+                (Response::error(403, "Host must be localhost, 127.0.0.1 or [::1]"), method != "HEAD")
+            }
+            Some((method, path)) => (self.route(method, path).await, method != "HEAD"),
+            None => (Response::error(400, "Malformed request"), true),
+        };
+
+        stream.write_all(&response.to_bytes(include_body)).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+
+    /// Maps a request to its response.
+    async fn route(&self, method: &str, path: &str) -> Response {
+        if method != "GET" && method != "HEAD" {
+            return Response::error(405, "Only GET and HEAD are supported");
+        }
+
+        match path {
+            "/healthz" => Response::json(200, &serde_json::json!({ "status": "ok" })),
+            "/status" => {
+                let status = AgentStatus {
+                    docker: self.docker_monitor.get_current_status().await,
+                    app: self.app_state.get().await,
+                };
+                Response::json(200, &status)
+            }
+            _ => Response::error(404, "Not found"),
+        }
+    }
+}
+
+/// Parses the request line into method and path, dropping any query string.
+fn parse_request_line(head: &str) -> Option<(&str, &str)> {
+    let mut parts = head.lines().next()?.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    let version = parts.next()?;
+    if !version.starts_with("HTTP/1.") || !target.starts_with('/') {
+        return None;
+    }
+    let path = target.split('?').next().unwrap_or(target);
+    Some((method, path))
+}
+
+/// Whether the `Host` header of `head` names a loopback host with `port`.
+fn host_allowed(head: &str, port: u16) -> bool {
+    let Some(host) = head
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("host"))
+        .map(|(_, value)| value.trim().to_ascii_lowercase())
+    else {
+        return false;
+    };
+    ["localhost", "127.0.0.1", "[::1]"]
+        .iter()
+        .any(|allowed| host == format!("{allowed}:{port}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_line() {
+        assert_eq!(parse_request_line("GET /status HTTP/1.1\r\nHost: x\r\n\r\n"), Some(("GET", "/status")));
+        assert_eq!(parse_request_line("HEAD /healthz?probe=1 HTTP/1.0\r\n\r\n"), Some(("HEAD", "/healthz")));
+        assert_eq!(parse_request_line("GET status HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(parse_request_line("garbage"), None);
+        assert_eq!(parse_request_line(""), None);
+    }
+
+    #[test]
+    fn test_host_allowed() {
+        assert!(host_allowed("GET / HTTP/1.1\r\nHost: localhost:7421\r\n\r\n", 7421));
+        assert!(host_allowed("GET / HTTP/1.1\r\nhost: 127.0.0.1:7421\r\n\r\n", 7421));
+        assert!(host_allowed("GET / HTTP/1.1\r\nHOST: [::1]:7421\r\n\r\n", 7421));
+        // DNS rebinding sends the attacker's hostname
+        assert!(!host_allowed("GET / HTTP/1.1\r\nHost: evil.example:7421\r\n\r\n", 7421));
+        assert!(!host_allowed("GET / HTTP/1.1\r\nHost: localhost:8080\r\n\r\n", 7421));
+        assert!(!host_allowed("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n", 7421));
+        assert!(!host_allowed("GET / HTTP/1.1\r\n\r\n", 7421));
+    }
+
+    #[test]
+    fn test_head_response_omits_body() {
+        let response = Response::json(200, &serde_json::json!({ "status": "ok" }));
+        let head = String::from_utf8(response.to_bytes(false)).unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains(&format!("Content-Length: {}", response.body.len())));
+        assert!(head.ends_with("\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_serves_healthz_and_status() {
+        let token = CancellationToken::new();
+        let monitor = Arc::new(DockerMonitor::new(token.clone()));
+        let server = Arc::new(StatusServer::new("127.0.0.1:0".parse().unwrap(), monitor, token.clone()));
+        let addr = server.start().await.unwrap();
+
+        let localhost = format!("localhost:{}", addr.port());
+        let requests = [
+            ("/healthz", localhost.as_str(), "\"ok\""),
+            ("/status", localhost.as_str(), "\"Stopped\""),
+            ("/missing", localhost.as_str(), "404"),
+            ("/status", "evil.example", "403"),
+        ];
+        for (path, host, expected) in requests {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(format!("GET {path} HTTP/1.1\r\nHost: {host}\r\n\r\n").as_bytes())
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(response.contains(expected), "{path}: {response}");
+        }
+
+        token.cancel();
+    }
+}
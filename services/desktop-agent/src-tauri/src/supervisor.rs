@@ -0,0 +1,305 @@
+//! Background task supervision for RedSys Desktop Agent
+//!
+//! Every long-running subsystem (Docker monitor, events stream, backend
+//! connection, schedulers, ...) is spawned through the [`Supervisor`]. A
+//! subsystem that panics, or returns while the agent is still running, is
+//! restarted with exponential backoff (1s doubling up to 60s), reset once it
+//! has run for a minute. Subsystems that may legitimately finish early, such
+//! as the backend connection when no backend is configured, are spawned with
+//! [`RestartPolicy::OnPanic`].
+//!
+//! ## Events
+//! `subsystem-restarted` carries a [`SubsystemRestarted`] each time a
+//! subsystem is restarted.
+//!
+//! ## References
+//! - [Tokio JoinError](https://docs.rs/tokio/latest/tokio/task/struct.JoinError.html)
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::{task, time::Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, warn};
+use serde::Serialize;
+
+use crate::docker::next_backoff;
+use crate::telemetry;
+
+/// Delay before the first restart.
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound for the restart delay.
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Runtime after which a subsystem counts as healthy again and the backoff resets.
+const STABLE_RUNTIME: Duration = Duration::from_secs(60);
+
+/// When to restart a subsystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Restart on panics and on any return before shutdown
+    Always,
+
+    /// Restart on panics only; returning means the subsystem is done
+    OnPanic,
+}
+
+/// Why a subsystem stopped.
+///
+/// Uses `#[serde(tag = "type")]` like [`DockerStatus`](crate::docker_monitor::DockerStatus).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum ExitReason {
+    /// The task panicked
+    Panicked { message: String },
+
+    /// The task returned while the agent was still running
+    Exited,
+}
+
+/// Payload of the `subsystem-restarted` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemRestarted {
+    /// Name the subsystem was spawned with
+    pub subsystem: String,
+
+    /// Why the previous run stopped
+    pub reason: ExitReason,
+
+    /// Restarts since the agent started
+    pub restarts: u32,
+
+    /// Delay before this restart
+    pub backoff_ms: u64,
+}
+
+/// Owns the agent's background tasks and restarts them when they fail.
+#[derive(Debug)]
+pub struct Supervisor {
+    /// Cancelled on exit; tasks stopping afterwards are not restarted
+    cancellation_token: CancellationToken,
+
+    /// Target of `subsystem-restarted` events
+    app_handle: Option<tauri::AppHandle>,
+
+    /// Delay before the first restart
+    initial_backoff: Duration,
+
+    /// Upper bound for the restart delay
+    max_backoff: Duration,
+
+    /// Restart count per subsystem
+    restarts: Mutex<HashMap<&'static str, u32>>,
+
+    /// Subsystem runs currently in progress
+    running: AtomicUsize,
+}
+
+impl Supervisor {
+    /// Creates a supervisor that stops restarting once `cancellation_token` is cancelled.
+    pub fn new(cancellation_token: CancellationToken) -> Self {
+        Self {
+            cancellation_token,
+            app_handle: None,
+            initial_backoff: INITIAL_RESTART_BACKOFF,
+            max_backoff: MAX_RESTART_BACKOFF,
+            restarts: Mutex::new(HashMap::new()),
+            running: AtomicUsize::new(0),
+        }
+    }
+
+    /// Emits `subsystem-restarted` events to the frontend.
+    pub fn with_app_handle(self, app_handle: tauri::AppHandle) -> Self {
+        Self {
+            app_handle: Some(app_handle),
+            ..self
+        }
+    }
+
+    /// Overrides the restart backoff bounds.
+    pub fn with_backoff(self, initial: Duration, max: Duration) -> Self {
+        Self {
+            initial_backoff: initial,
+            max_backoff: max,
+            ..self
+        }
+    }
+
+    /// How often a subsystem has been restarted.
+    pub fn restarts(&self, subsystem: &str) -> u32 {
+        self.restarts.lock().unwrap().get(subsystem).copied().unwrap_or(0)
+    }
+
+    /// Restarts of all subsystems since the agent started.
+    pub fn total_restarts(&self) -> u32 {
+        self.restarts.lock().unwrap().values().sum()
+    }
+
+    /// How many subsystems are running, not counting those waiting for a
+    /// restart.
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Spawns a subsystem, calling `factory` again for every restart.
+    pub fn spawn<F, Fut>(self: &Arc<Self>, subsystem: &'static str, policy: RestartPolicy, factory: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let supervisor = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut backoff = supervisor.initial_backoff;
+            loop {
+                let started = Instant::now();
+                supervisor.running.fetch_add(1, Ordering::Relaxed);
+                let result = task::spawn(factory()).await;
+                supervisor.running.fetch_sub(1, Ordering::Relaxed);
+                if supervisor.cancellation_token.is_cancelled() {
+                    break;
+                }
+
+                let reason = match result {
+                    Ok(()) if policy == RestartPolicy::OnPanic => {
+                        debug!("Subsystem {} finished", subsystem);
+                        break;
+                    }
+                    Ok(()) => ExitReason::Exited,
+                    Err(e) if e.is_panic() => ExitReason::Panicked {
+                        message: panic_message(e.into_panic()),
+                    },
+                    // Aborted by the runtime shutting down
+                    Err(_) => break,
+                };
+                if started.elapsed() >= STABLE_RUNTIME {
+                    backoff = supervisor.initial_backoff;
+                }
+                warn!("Subsystem {} stopped ({:?}), restarting in {}ms", subsystem, reason, backoff.as_millis());
+
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = supervisor.cancellation_token.cancelled() => break,
+                }
+                supervisor.restarted(subsystem, reason, backoff);
+                backoff = next_backoff(backoff, supervisor.max_backoff);
+            }
+        });
+    }
+
+    /// Counts a restart and reports it.
+    fn restarted(&self, subsystem: &'static str, reason: ExitReason, backoff: Duration) {
+        let restarts = {
+            let mut restarts = self.restarts.lock().unwrap();
+            let count = restarts.entry(subsystem).or_default();
+            *count += 1;
+            *count
+        };
+        let Some(app_handle) = &self.app_handle else {
+            return;
+        };
+        let payload = SubsystemRestarted {
+            subsystem: subsystem.to_string(),
+            reason,
+            restarts,
+            backoff_ms: backoff.as_millis() as u64,
+        };
+        if let Err(e) = telemetry::emit(app_handle, "subsystem-restarted", &payload) {
+            error!("Failed to emit subsystem-restarted event: {e}");
+        }
+    }
+}
+
+/// Extracts the message of a panic payload.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn test_supervisor(cancellation_token: CancellationToken) -> Arc<Supervisor> {
+        Arc::new(
+            Supervisor::new(cancellation_token).with_backoff(Duration::from_millis(1), Duration::from_millis(5)),
+        )
+    }
+
+    /// Waits until `runs` reaches `expected`.
+    async fn wait_for_runs(runs: &AtomicU32, expected: u32) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while runs.load(Ordering::SeqCst) < expected {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("subsystem was not restarted");
+    }
+
+    #[tokio::test]
+    async fn test_restarts_panicked_and_exited_tasks() {
+        let cancellation_token = CancellationToken::new();
+        let supervisor = test_supervisor(cancellation_token.clone());
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let runs_clone = runs.clone();
+        let token = cancellation_token.clone();
+        supervisor.spawn("flaky", RestartPolicy::Always, move || {
+            let runs = runs_clone.clone();
+            let token = token.clone();
+            async move {
+                match runs.fetch_add(1, Ordering::SeqCst) {
+                    0 => panic!("first run fails"),
+                    1 => {}
+                    _ => token.cancelled().await,
+                }
+            }
+        });
+
+        wait_for_runs(&runs, 3).await;
+        assert_eq!(supervisor.restarts("flaky"), 2);
+        assert_eq!(supervisor.total_restarts(), 2);
+        assert_eq!(supervisor.running(), 1);
+
+        cancellation_token.cancel();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(supervisor.running(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_on_panic_policy_lets_tasks_finish() {
+        let supervisor = test_supervisor(CancellationToken::new());
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let runs_clone = runs.clone();
+        supervisor.spawn("one-shot", RestartPolicy::OnPanic, move || {
+            let runs = runs_clone.clone();
+            async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        wait_for_runs(&runs, 1).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(supervisor.restarts("one-shot"), 0);
+    }
+
+    #[test]
+    fn test_panic_message() {
+        assert_eq!(panic_message(Box::new("static")), "static");
+        assert_eq!(panic_message(Box::new(String::from("formatted"))), "formatted");
+        assert_eq!(panic_message(Box::new(42)), "unknown panic");
+    }
+}
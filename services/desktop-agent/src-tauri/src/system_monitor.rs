@@ -0,0 +1,359 @@
+//! Host system resource monitoring for RedSys Desktop Agent
+//!
+//! Complements the Docker-only view with the resources of the host itself:
+//! CPU, memory, swap, mounted disks and per-interface network traffic, read
+//! through `sysinfo`.
+//!
+//! [`SystemMonitor`] keeps one sampler alive between samples because CPU
+//! usage and network throughput are measured as the difference to the
+//! previous refresh. The first sample after startup therefore reports zero
+//! CPU usage and throughput.
+//!
+//! ## Events
+//! `system-metrics` carries a [`SystemMetrics`] sample every
+//! `monitor.system_metrics_interval_secs` seconds. Samples are published to
+//! the [`Bus`](crate::events::Bus), which forwards them to the frontend.
+//!
+//! ## References
+//! - [sysinfo crate](https://docs.rs/sysinfo/latest/sysinfo/)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::{task, time::{interval, Duration, MissedTickBehavior}};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sysinfo::{Disks, Networks, System};
+
+use crate::error::{AppError, AppResult};
+use crate::events::{Bus, Event, SystemMetric};
+
+/// Default interval between `system-metrics` events.
+pub const DEFAULT_SYSTEM_METRICS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Allowed range for the system metrics interval in seconds.
+pub(crate) const SYSTEM_METRICS_INTERVAL_BOUNDS_SECS: (u64, u64) = (1, 3_600);
+
+/// CPU usage sample.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct CpuMetrics {
+    /// Average usage over all cores in percent
+    pub usage_percent: f32,
+
+    /// Usage of each logical core in percent
+    pub per_core_percent: Vec<f32>,
+
+    /// CPU brand string, e.g. `AMD Ryzen 9 7950X 16-Core Processor`
+    pub brand: String,
+}
+
+/// Memory or swap usage sample.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct MemoryMetrics {
+    /// Total size in bytes
+    pub total_bytes: u64,
+
+    /// Used bytes
+    pub used_bytes: u64,
+
+    /// Bytes available for new allocations without swapping
+    pub available_bytes: u64,
+}
+
+/// Usage of one mounted disk.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct DiskMetrics {
+    /// Device name, e.g. `/dev/nvme0n1p2`
+    pub name: String,
+
+    /// Mount point, e.g. `/`
+    pub mount_point: String,
+
+    /// File system, e.g. `ext4`
+    pub file_system: String,
+
+    /// Total size in bytes
+    pub total_bytes: u64,
+
+    /// Free bytes available to the agent's user
+    pub available_bytes: u64,
+
+    /// Removable media such as USB drives
+    pub removable: bool,
+}
+
+/// Traffic of one network interface.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct NetworkMetrics {
+    /// Interface name, e.g. `eth0`
+    pub interface: String,
+
+    /// Receive throughput since the previous sample in bytes per second
+    pub received_bytes_per_sec: f64,
+
+    /// Transmit throughput since the previous sample in bytes per second
+    pub transmitted_bytes_per_sec: f64,
+
+    /// Bytes received since the interface came up
+    pub total_received_bytes: u64,
+
+    /// Bytes transmitted since the interface came up
+    pub total_transmitted_bytes: u64,
+}
+
+/// Host resource sample, the payload of the `system-metrics` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct SystemMetrics {
+    /// CPU usage
+    pub cpu: CpuMetrics,
+
+    /// Physical memory usage
+    pub memory: MemoryMetrics,
+
+    /// Swap usage
+    pub swap: MemoryMetrics,
+
+    /// Mounted disks
+    pub disks: Vec<DiskMetrics>,
+
+    /// Network interfaces, sorted by name
+    pub networks: Vec<NetworkMetrics>,
+
+    /// Sample time
+    pub sampled_at: DateTime<Utc>,
+}
+
+/// `sysinfo` state kept between samples.
+struct Sampler {
+    system: System,
+    disks: Disks,
+    networks: Networks,
+    refreshed_at: Instant,
+}
+
+impl Sampler {
+    fn new() -> Self {
+        let mut system = System::new();
+        // Baseline for the usage of the first sample
+        system.refresh_cpu_usage();
+        system.refresh_memory();
+        Self {
+            system,
+            disks: Disks::new_with_refreshed_list(),
+            networks: Networks::new_with_refreshed_list(),
+            refreshed_at: Instant::now(),
+        }
+    }
+
+    /// Refreshes all counters and builds a sample.
+    fn sample(&mut self) -> SystemMetrics {
+        self.system.refresh_cpu_usage();
+        self.system.refresh_memory();
+        // Re-list so mounted or ejected disks show up
+        self.disks.refresh_list();
+        self.networks.refresh();
+
+        let now = Instant::now();
+        let elapsed_secs = now.duration_since(self.refreshed_at).as_secs_f64();
+        self.refreshed_at = now;
+        let rate = |bytes: u64| if elapsed_secs > 0.0 { bytes as f64 / elapsed_secs } else { 0.0 };
+
+        let cpus = self.system.cpus();
+        let cpu = CpuMetrics {
+            usage_percent: self.system.global_cpu_usage(),
+            per_core_percent: cpus.iter().map(|cpu| cpu.cpu_usage()).collect(),
+            brand: cpus.first().map(|cpu| cpu.brand().trim().to_string()).unwrap_or_default(),
+        };
+
+        let total_swap = self.system.total_swap();
+        let used_swap = self.system.used_swap();
+
+        let disks = self
+            .disks
+            .list()
+            .iter()
+            .map(|disk| DiskMetrics {
+                name: disk.name().to_string_lossy().into_owned(),
+                mount_point: disk.mount_point().display().to_string(),
+                file_system: disk.file_system().to_string_lossy().into_owned(),
+                total_bytes: disk.total_space(),
+                available_bytes: disk.available_space(),
+                removable: disk.is_removable(),
+            })
+            .collect();
+
+        let mut networks: Vec<NetworkMetrics> = self
+            .networks
+            .list()
+            .iter()
+            .map(|(interface, data)| NetworkMetrics {
+                interface: interface.clone(),
+                received_bytes_per_sec: rate(data.received()),
+                transmitted_bytes_per_sec: rate(data.transmitted()),
+                total_received_bytes: data.total_received(),
+                total_transmitted_bytes: data.total_transmitted(),
+            })
+            .collect();
+        networks.sort_by(|a, b| a.interface.cmp(&b.interface));
+
+        SystemMetrics {
+            cpu,
+            memory: MemoryMetrics {
+                total_bytes: self.system.total_memory(),
+                used_bytes: self.system.used_memory(),
+                available_bytes: self.system.available_memory(),
+            },
+            swap: MemoryMetrics {
+                total_bytes: total_swap,
+                used_bytes: used_swap,
+                available_bytes: total_swap.saturating_sub(used_swap),
+            },
+            disks,
+            networks,
+            sampled_at: Utc::now(),
+        }
+    }
+}
+
+/// Samples host resources on demand and on an interval.
+pub struct SystemMonitor {
+    /// Sampler shared with blocking sample tasks
+    sampler: Arc<Mutex<Sampler>>,
+
+    /// Interval between `system-metrics` events in seconds, adjustable at runtime
+    interval_secs: AtomicU64,
+
+    /// Event bus for metrics samples
+    bus: Arc<Bus>,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: CancellationToken,
+}
+
+impl std::fmt::Debug for SystemMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SystemMonitor")
+            .field("interval_secs", &self.interval_secs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SystemMonitor {
+    /// Creates a monitor sampling every [`DEFAULT_SYSTEM_METRICS_INTERVAL`].
+    pub fn new(cancellation_token: CancellationToken) -> Self {
+        info!("Initializing system monitor");
+        Self {
+            sampler: Arc::new(Mutex::new(Sampler::new())),
+            interval_secs: AtomicU64::new(DEFAULT_SYSTEM_METRICS_INTERVAL.as_secs()),
+            bus: Arc::new(Bus::new()),
+            cancellation_token,
+        }
+    }
+
+    /// Shares the event bus that samples are published to.
+    pub fn with_event_bus(self, bus: Arc<Bus>) -> Self {
+        Self { bus, ..self }
+    }
+
+    /// Sets the interval between `system-metrics` events.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        self.set_interval(interval);
+        self
+    }
+
+    /// Gets the interval between `system-metrics` events.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.load(Ordering::Relaxed))
+    }
+
+    /// Changes the interval of a running monitor.
+    ///
+    /// Takes effect after the next sample.
+    pub fn set_interval(&self, interval: Duration) {
+        self.interval_secs.store(interval.as_secs(), Ordering::Relaxed);
+    }
+
+    /// Takes a fresh sample.
+    ///
+    /// CPU usage and network throughput cover the time since the previous
+    /// sample, whether taken here or by the background reporter.
+    pub async fn sample(&self) -> AppResult<SystemMetrics> {
+        let sampler = self.sampler.clone();
+        task::spawn_blocking(move || {
+            let mut sampler = sampler.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            sampler.sample()
+        })
+        .await
+        .map_err(|e| AppError::Application(format!("System metrics sampling failed: {e}")))
+    }
+
+    /// Starts publishing `system-metrics` samples every interval.
+    pub async fn start(self: Arc<Self>) {
+        let mut metrics_interval = self.interval();
+
+        info!("Starting system metrics reporting every {}s", metrics_interval.as_secs());
+
+        let mut ticker = interval(metrics_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let configured_interval = self.interval();
+                    if configured_interval != metrics_interval {
+                        info!("System metrics interval changed to {}s", configured_interval.as_secs());
+                        metrics_interval = configured_interval;
+                        ticker = interval(metrics_interval);
+                        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                        ticker.reset();
+                    }
+
+                    match self.sample().await {
+                        Ok(metrics) => {
+                            debug!(
+                                "System metrics: {:.1}% CPU, {} of {} bytes memory used",
+                                metrics.cpu.usage_percent, metrics.memory.used_bytes, metrics.memory.total_bytes
+                            );
+                            self.bus.publish(Event::SystemMetric(SystemMetric::Host(metrics)));
+                        }
+                        Err(e) => error!("Skipping system metrics report: {e}"),
+                    }
+                }
+                _ = self.cancellation_token.cancelled() => {
+                    info!("System metrics reporting received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_is_adjustable() {
+        let monitor = SystemMonitor::new(CancellationToken::new()).with_interval(Duration::from_secs(10));
+        assert_eq!(monitor.interval(), Duration::from_secs(10));
+
+        monitor.set_interval(Duration::from_secs(2));
+        assert_eq!(monitor.interval(), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_sample_reports_memory() {
+        let monitor = SystemMonitor::new(CancellationToken::new());
+        let metrics = monitor.sample().await.unwrap();
+        assert!(metrics.memory.used_bytes <= metrics.memory.total_bytes);
+        assert!(metrics.swap.available_bytes <= metrics.swap.total_bytes);
+        assert!(metrics.networks.windows(2).all(|pair| pair[0].interface <= pair[1].interface));
+    }
+}
@@ -0,0 +1,193 @@
+//! Telemetry for RedSys Desktop Agent
+//!
+//! ## Correlation IDs
+//! Every root span gets a correlation ID that its child spans inherit. Events
+//! emitted to the frontend through [`emit`] carry the ID of the span they were
+//! emitted in as a `correlation_id` field, so a UI report can be matched with
+//! the agent logs and traces. When spans are exported via OTLP, the
+//! OpenTelemetry trace ID is used as the correlation ID.
+//!
+//! ## OTLP Export
+//! With the `otel` cargo feature enabled and `telemetry.otlp_endpoint` set in
+//! the config file, spans from Tauri commands and the monitor loops are
+//! exported over OTLP/gRPC to a collector.
+//!
+//! ## References
+//! - [tracing-subscriber Layers](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/layer/index.html)
+//! - [tracing-opentelemetry](https://docs.rs/tracing-opentelemetry/latest/tracing_opentelemetry/)
+//! - [OTLP Exporter](https://docs.rs/opentelemetry-otlp/latest/opentelemetry_otlp/)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{Emitter, Manager};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Registry;
+
+use crate::event_contract;
+use crate::routing::EventRouter;
+
+/// Per-process seed so correlation IDs differ between agent runs.
+static CORRELATION_SEED: Lazy<u64> = Lazy::new(|| {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default();
+    nanos ^ (u64::from(std::process::id()) << 32)
+});
+
+/// Counter mixed into the seed for each new correlation ID.
+static CORRELATION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Correlation ID stored in span extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CorrelationId(String);
+
+impl CorrelationId {
+    /// Generates a new 16-digit hexadecimal ID.
+    fn generate() -> Self {
+        let count = CORRELATION_COUNTER.fetch_add(1, Ordering::Relaxed);
+        // Multiplying by an odd constant spreads consecutive counts over the whole range
+        let id = CORRELATION_SEED.wrapping_add(count).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        Self(format!("{id:016x}"))
+    }
+}
+
+/// Layer assigning correlation IDs to spans.
+///
+/// Root spans get a new ID; child spans inherit the ID of their parent.
+#[derive(Debug, Default)]
+pub struct CorrelationLayer;
+
+impl<S> Layer<S> for CorrelationLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let correlation_id = span
+            .parent()
+            .and_then(|parent| parent.extensions().get::<CorrelationId>().cloned())
+            .unwrap_or_else(CorrelationId::generate);
+        span.extensions_mut().insert(correlation_id);
+    }
+}
+
+/// Gets the correlation ID of the current span, if any.
+pub fn current_correlation_id() -> Option<String> {
+    #[cfg(feature = "otel")]
+    if let Some(trace_id) = otlp::current_trace_id() {
+        return Some(trace_id);
+    }
+
+    tracing::Span::current()
+        .with_subscriber(|(id, dispatch)| {
+            let span = dispatch.downcast_ref::<Registry>()?.span(id)?;
+            let extensions = span.extensions();
+            extensions.get::<CorrelationId>().map(|correlation_id| correlation_id.0.clone())
+        })
+        .flatten()
+}
+
+/// Emits a Tauri event, adding the current correlation ID to object payloads.
+///
+/// Follows the [event contract](crate::event_contract), and only reaches the
+/// windows the [`EventRouter`] delivers the event to.
+pub fn emit<S: Serialize>(app_handle: &tauri::AppHandle, event: &str, payload: &S) -> tauri::Result<()> {
+    emit_correlated(app_handle, event, payload, current_correlation_id())
+}
+
+/// Emits a Tauri event on behalf of a span that has already ended, e.g. one
+/// that published to the [`Bus`](crate::events::Bus).
+pub fn emit_correlated<S: Serialize>(
+    app_handle: &tauri::AppHandle,
+    event: &str,
+    payload: &S,
+    correlation_id: Option<String>,
+) -> tauri::Result<()> {
+    let mut value = serde_json::to_value(payload)?;
+    if let Some(object) = value.as_object_mut() {
+        object.insert("contract_version".to_string(), Value::from(event_contract::VERSION));
+        if let Some(correlation_id) = correlation_id {
+            object.insert("correlation_id".to_string(), Value::String(correlation_id));
+        }
+    }
+    let targets = app_handle
+        .try_state::<Arc<EventRouter>>()
+        .filter(|router| router.is_routing())
+        .map(|router| router.targets(event, app_handle.webview_windows().into_keys()));
+    for name in event_contract::legacy_names(event).chain([event]) {
+        match &targets {
+            Some(labels) => {
+                for label in labels {
+                    app_handle.emit_to(label.as_str(), name, value.clone())?;
+                }
+            }
+            None => app_handle.emit(name, value.clone())?,
+        }
+    }
+    Ok(())
+}
+
+/// OTLP span export, available with the `otel` feature.
+#[cfg(feature = "otel")]
+pub(crate) mod otlp {
+    use opentelemetry::trace::TraceContextExt;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    use crate::error::{AppError, AppResult};
+
+    /// Creates a tracer provider exporting batches of spans to `endpoint`.
+    pub(crate) fn tracer_provider(endpoint: &str, service_name: &str) -> AppResult<TracerProvider> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| AppError::Configuration(format!("Failed to create OTLP exporter for {endpoint}: {e}")))?;
+
+        Ok(TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new(vec![KeyValue::new("service.name", service_name.to_string())]))
+            .build())
+    }
+
+    /// Gets the OpenTelemetry trace ID of the current span, if it is sampled.
+    pub(crate) fn current_trace_id() -> Option<String> {
+        let context = tracing::Span::current().context();
+        let span_context = context.span().span_context().clone();
+        span_context.is_valid().then(|| span_context.trace_id().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_child_spans_inherit_correlation_id() {
+        let subscriber = Registry::default().with(CorrelationLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            assert_eq!(current_correlation_id(), None);
+
+            let root = tracing::info_span!("root");
+            let root_id = root.in_scope(current_correlation_id).unwrap();
+            let child_id = root.in_scope(|| tracing::info_span!("child").in_scope(current_correlation_id));
+            assert_eq!(child_id.as_deref(), Some(root_id.as_str()));
+
+            let other_id = tracing::info_span!("other").in_scope(current_correlation_id).unwrap();
+            assert_ne!(other_id, root_id);
+            assert_eq!(root_id.len(), 16);
+        });
+    }
+}
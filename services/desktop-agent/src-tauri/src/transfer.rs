@@ -0,0 +1,519 @@
+//! File transfer between the host and containers for RedSys Desktop Agent
+//!
+//! Jobs that don't use volumes need their inputs copied into the container
+//! and their results copied out. [`copy_to_container`] and
+//! [`copy_from_container`] move a file or directory through the Docker
+//! archive API, like `docker cp`. The archive is staged in a temporary file,
+//! so large datasets don't have to fit in memory.
+//!
+//! The destination path names the copy itself, not the directory it goes
+//! into: copying `./inputs` to `/data/job-inputs` creates `/data/job-inputs`
+//! with the contents of `./inputs`. Parent directories in the container must
+//! exist.
+//!
+//! ## Host Paths
+//! Host paths must lie inside the job workspace root or a directory listed
+//! in `host_paths.allowed_roots`, see [`host_paths`](crate::host_paths).
+//! Copies from a container never overwrite an existing host path, and
+//! archive entries and links that would leave the copy are rejected.
+//!
+//! ## Progress
+//! The Tauri commands send a [`CopyProgress`] to a channel passed by the
+//! caller for every mebibyte transferred and once more when the copy is done.
+//!
+//! ## References
+//! - [Docker archive API](https://docs.docker.com/engine/api/v1.47/#tag/Container/operation/PutContainerArchive)
+//! - [tar crate](https://docs.rs/tar/latest/tar/)
+
+use std::fs::File;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::task;
+use tracing::{info, warn};
+use bollard::query_parameters::{DownloadFromContainerOptionsBuilder, UploadToContainerOptionsBuilder};
+use bollard::Docker;
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::Serialize;
+
+use crate::docker_monitor::DockerMonitorError;
+use crate::error::{AppError, AppResult};
+use crate::host_paths::HostPaths;
+
+/// Bytes read per upload chunk and transferred between progress reports.
+pub(crate) const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Direction of a copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum CopyDirection {
+    /// From the host into the container
+    ToContainer,
+
+    /// From the container to the host
+    FromContainer,
+}
+
+/// Progress sent to the copy commands' progress channels.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct CopyProgress {
+    /// Container ID or name
+    pub container: String,
+
+    /// Path in the container
+    pub container_path: String,
+
+    /// Direction of the copy
+    pub direction: CopyDirection,
+
+    /// Archive bytes transferred so far
+    pub transferred_bytes: u64,
+
+    /// Archive size, unknown when copying from the container
+    pub total_bytes: Option<u64>,
+
+    /// Whether the copy completed
+    pub done: bool,
+}
+
+/// Temporary file for staging an archive.
+pub(crate) fn staging_path() -> PathBuf {
+    std::env::temp_dir().join(format!("redsys-copy-{}.tar", uuid::Uuid::new_v4()))
+}
+
+/// Splits an absolute container path into its parent directory and name.
+fn split_container_path(container_path: &str) -> AppResult<(String, String)> {
+    let path = Path::new(container_path);
+    match (path.is_absolute(), path.parent(), path.file_name()) {
+        (true, Some(parent), Some(name)) => Ok((parent.to_string_lossy().into_owned(), name.to_string_lossy().into_owned())),
+        _ => Err(AppError::Configuration(format!(
+            "Container path '{container_path}' must be absolute and name a file or directory"
+        ))),
+    }
+}
+
+/// Archives `host_path` under `name` into `archive`, returning the archive
+/// size. Blocking.
+pub(crate) fn write_archive(host_path: &Path, name: &str, archive: &Path) -> std::io::Result<u64> {
+    let mut builder = tar::Builder::new(File::create(archive)?);
+    builder.follow_symlinks(false);
+    if host_path.is_dir() {
+        builder.append_dir_all(name, host_path)?;
+    } else {
+        builder.append_path_with_name(host_path, name)?;
+    }
+    Ok(builder.into_inner()?.metadata()?.len())
+}
+
+/// Whether link `target`, relative to directory `base` of the archive, stays
+/// below the archive's top level entry `top`.
+fn link_stays_inside(top: &Path, base: &Path, target: &Path) -> bool {
+    let mut resolved = Vec::new();
+    for component in base.components().chain(target.components()) {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if resolved.pop().is_none() {
+                    return false;
+                }
+            }
+            // Absolute targets
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    resolved.first().is_some_and(|first| Path::new(first) == top)
+}
+
+/// Extracts an archive from the archive API to `host_path`, renaming its top
+/// level entry to `host_path`, which must not exist yet. Blocking.
+///
+/// The archive is unpacked with [`tar::Entry::unpack_in`] into a staging
+/// directory next to `host_path`, which refuses entries that would land
+/// outside it. Links whose targets are absolute or leave the top level
+/// entry, and entries besides the top level one, are rejected up front.
+fn extract_archive(archive: &Path, host_path: &Path) -> std::io::Result<()> {
+    if host_path.symlink_metadata().is_ok() {
+        return Err(IoError::new(ErrorKind::AlreadyExists, format!("{} already exists", host_path.display())));
+    }
+    let (Some(parent), Some(name)) = (host_path.parent(), host_path.file_name()) else {
+        return Err(IoError::new(ErrorKind::InvalidInput, format!("Invalid host path {}", host_path.display())));
+    };
+    let staging = parent.join(format!(".redsys-copy-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir(&staging)?;
+    let result = unpack_staged(archive, &staging).and_then(|top| {
+        std::fs::rename(staging.join(top), parent.join(name))
+    });
+    if let Err(e) = std::fs::remove_dir_all(&staging) {
+        warn!("Failed to remove staging directory {}: {}", staging.display(), e);
+    }
+    result
+}
+
+/// Unpacks `archive` into `staging`, returning the name of its top level
+/// entry.
+fn unpack_staged(archive: &Path, staging: &Path) -> std::io::Result<PathBuf> {
+    let mut archive = tar::Archive::new(File::open(archive)?);
+    let mut top: Option<PathBuf> = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let unsafe_entry = |what: &str| IoError::new(ErrorKind::InvalidData, format!("Unsafe {what} in archive: {}", path.display()));
+
+        let Some(Component::Normal(first)) = path.components().find(|component| *component != Component::CurDir) else {
+            return Err(unsafe_entry("path"));
+        };
+        let top: &Path = top.get_or_insert_with(|| PathBuf::from(first));
+        if top.as_os_str() != first {
+            return Err(unsafe_entry("path"));
+        }
+
+        let kind = entry.header().entry_type();
+        if kind.is_symlink() || kind.is_hard_link() {
+            let target = entry.link_name()?.ok_or_else(|| unsafe_entry("link"))?.into_owned();
+            // Symlinks resolve from their directory, hard links from the archive root
+            let base = if kind.is_symlink() { path.parent().unwrap_or(Path::new("")) } else { Path::new("") };
+            if !link_stays_inside(top, base, &target) {
+                return Err(unsafe_entry("link"));
+            }
+        }
+        if !entry.unpack_in(staging)? {
+            return Err(unsafe_entry("path"));
+        }
+    }
+    top.ok_or_else(|| IoError::new(ErrorKind::InvalidData, "Empty archive"))
+}
+
+/// Resolves `host_path` to its canonical form if `host_paths` allows it.
+pub(crate) async fn resolve_host_path(host_paths: &HostPaths, host_path: &Path) -> AppResult<PathBuf> {
+    let (host_paths, path) = (host_paths.clone(), host_path.to_path_buf());
+    task::spawn_blocking(move || host_paths.resolve(&path))
+        .await
+        .map_err(|e| AppError::Application(format!("Path resolution task failed: {e}")))?
+        .map_err(AppError::Permission)
+}
+
+/// Copies a file or directory from the host into a container.
+///
+/// # Arguments
+///
+/// * `client` - Docker client, from [`DockerService::docker`](crate::docker::DockerService::docker)
+/// * `host_paths` - Directories the copy may be read from
+/// * `id` - Container ID or name
+/// * `host_path` - File or directory to copy
+/// * `container_path` - Absolute path of the copy in the container
+/// * `progress` - Called with the progress of the transfer
+///
+/// Returns the size of the transferred archive.
+pub async fn copy_to_container(
+    client: &Docker,
+    host_paths: &HostPaths,
+    id: &str,
+    host_path: &Path,
+    container_path: &str,
+    progress: impl Fn(CopyProgress) + Send + Sync + 'static,
+) -> AppResult<u64> {
+    let (parent, name) = split_container_path(container_path)?;
+    let host_path = &resolve_host_path(host_paths, host_path).await?;
+    if !host_path.exists() {
+        return Err(AppError::NotFound { resource: host_path.display().to_string() });
+    }
+    info!("Copying {} to {}:{}", host_path.display(), id, container_path);
+
+    let archive = staging_path();
+    let written = task::spawn_blocking({
+        let (host_path, archive) = (host_path.to_path_buf(), archive.clone());
+        move || write_archive(&host_path, &name, &archive)
+    })
+    .await
+    .map_err(|e| AppError::Application(format!("Archiving task failed: {e}")));
+    let result = match written {
+        Ok(Ok(total)) => upload(client, id, &parent, container_path, &archive, total, progress).await.map(|()| total),
+        Ok(Err(e)) => Err(e.into()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = tokio::fs::remove_file(&archive).await {
+        warn!("Failed to remove staged archive {}: {}", archive.display(), e);
+    }
+    result
+}
+
+/// Reads a staged archive in chunks for a request body, calling `on_chunk`
+/// with the bytes read so far.
+pub(crate) fn file_chunks(
+    file: tokio::fs::File,
+    on_chunk: impl Fn(u64) + Send + Sync + 'static,
+) -> BoxStream<'static, Bytes> {
+    let on_chunk = Arc::new(on_chunk);
+    stream::unfold((file, 0u64), move |(mut file, sent)| {
+        let on_chunk = on_chunk.clone();
+        async move {
+            let mut buffer = vec![0; CHUNK_SIZE];
+            match file.read(&mut buffer).await {
+                Ok(0) => None,
+                Ok(read) => {
+                    buffer.truncate(read);
+                    let sent = sent + read as u64;
+                    on_chunk(sent);
+                    Some((Bytes::from(buffer), (file, sent)))
+                }
+                Err(e) => {
+                    // The daemon rejects the truncated archive
+                    warn!("Failed to read staged archive: {}", e);
+                    None
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+/// Streams the staged `archive` into directory `parent` of a container.
+async fn upload(
+    client: &Docker,
+    id: &str,
+    parent: &str,
+    container_path: &str,
+    archive: &Path,
+    total: u64,
+    progress: impl Fn(CopyProgress) + Send + Sync + 'static,
+) -> AppResult<()> {
+    let file = tokio::fs::File::open(archive).await?;
+    let report = {
+        let (container, container_path) = (id.to_string(), container_path.to_string());
+        Arc::new(move |transferred_bytes, done| {
+            progress(CopyProgress {
+                container: container.clone(),
+                container_path: container_path.clone(),
+                direction: CopyDirection::ToContainer,
+                transferred_bytes,
+                total_bytes: Some(total),
+                done,
+            })
+        })
+    };
+
+    let chunk_report = report.clone();
+    let chunks = file_chunks(file, move |sent| chunk_report(sent, false));
+
+    let options = UploadToContainerOptionsBuilder::new().path(parent).build();
+    client
+        .upload_to_container(id, Some(options), bollard::body_stream(chunks))
+        .await
+        .map_err(|e| DockerMonitorError::Api(format!("Failed to copy to container {id}: {e}")))?;
+    report(total, true);
+    Ok(())
+}
+
+/// Copies a file or directory from a container to the host.
+///
+/// # Arguments
+///
+/// * `client` - Docker client, from [`DockerService::docker`](crate::docker::DockerService::docker)
+/// * `host_paths` - Directories the copy may be written to
+/// * `id` - Container ID or name
+/// * `container_path` - Absolute path of the file or directory to copy
+/// * `host_path` - Path of the copy on the host, which must not exist yet;
+///   its parent must
+/// * `progress` - Called with the progress of the transfer
+///
+/// Returns the size of the transferred archive.
+pub async fn copy_from_container(
+    client: &Docker,
+    host_paths: &HostPaths,
+    id: &str,
+    container_path: &str,
+    host_path: &Path,
+    progress: impl Fn(CopyProgress) + Send + Sync + 'static,
+) -> AppResult<u64> {
+    split_container_path(container_path)?;
+    let host_path = &resolve_host_path(host_paths, host_path).await?;
+    info!("Copying {}:{} to {}", id, container_path, host_path.display());
+
+    let archive = staging_path();
+    let result = download(client, id, container_path, host_path, &archive, progress).await;
+    if let Err(e) = tokio::fs::remove_file(&archive).await {
+        if e.kind() != ErrorKind::NotFound {
+            warn!("Failed to remove staged archive {}: {}", archive.display(), e);
+        }
+    }
+    result
+}
+
+/// Downloads the archive of `container_path` to `archive` and extracts it to
+/// `host_path`.
+async fn download(
+    client: &Docker,
+    id: &str,
+    container_path: &str,
+    host_path: &Path,
+    archive: &Path,
+    progress: impl Fn(CopyProgress) + Send + Sync + 'static,
+) -> AppResult<u64> {
+    let report = |transferred_bytes, done| {
+        progress(CopyProgress {
+            container: id.to_string(),
+            container_path: container_path.to_string(),
+            direction: CopyDirection::FromContainer,
+            transferred_bytes,
+            total_bytes: None,
+            done,
+        })
+    };
+
+    let options = DownloadFromContainerOptionsBuilder::new().path(container_path).build();
+    let mut chunks = client.download_from_container(id, Some(options)).boxed();
+    let mut file = tokio::fs::File::create(archive).await?;
+    let mut received = 0u64;
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|e| DockerMonitorError::Api(format!("Failed to copy from container {id}: {e}")))?;
+        file.write_all(&chunk).await?;
+        let previous = received;
+        received += chunk.len() as u64;
+        if previous / CHUNK_SIZE as u64 != received / CHUNK_SIZE as u64 {
+            report(received, false);
+        }
+    }
+    file.flush().await?;
+    drop(file);
+
+    task::spawn_blocking({
+        let (archive, host_path) = (archive.to_path_buf(), host_path.to_path_buf());
+        move || extract_archive(&archive, &host_path)
+    })
+    .await
+    .map_err(|e| AppError::Application(format!("Extraction task failed: {e}")))??;
+    report(received, true);
+    Ok(received)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_container_path() {
+        assert_eq!(
+            split_container_path("/data/inputs").unwrap(),
+            ("/data".to_string(), "inputs".to_string())
+        );
+        assert!(split_container_path("data/inputs").is_err());
+        assert!(split_container_path("/").is_err());
+    }
+
+    #[test]
+    fn test_archive_round_trip_renames_top_level_entry() {
+        let root = std::env::temp_dir().join(format!("redsys-transfer-test-{}", std::process::id()));
+        let source = root.join("results");
+        std::fs::create_dir_all(source.join("nested")).unwrap();
+        std::fs::write(source.join("nested").join("metrics.json"), "{}").unwrap();
+        let archive = root.join("results.tar");
+
+        assert!(write_archive(&source, "output", &archive).unwrap() > 0);
+        let copy = root.join("copy");
+        extract_archive(&archive, &copy).unwrap();
+
+        assert_eq!(std::fs::read_to_string(copy.join("nested").join("metrics.json")).unwrap(), "{}");
+        // Existing host paths are not overwritten
+        assert_eq!(extract_archive(&archive, &copy).unwrap_err().kind(), ErrorKind::AlreadyExists);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Writes an archive of `(path, type, link target)` entries, bypassing
+    /// the path checks of `tar::Builder`.
+    fn raw_archive(archive: &Path, entries: &[(&str, tar::EntryType, &str)]) {
+        let mut builder = tar::Builder::new(File::create(archive).unwrap());
+        for (path, kind, link) in entries {
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.as_old_mut().linkname[..link.len()].copy_from_slice(link.as_bytes());
+            header.set_entry_type(*kind);
+            header.set_mode(if kind.is_dir() { 0o755 } else { 0o644 });
+            let data: &[u8] = if *kind == tar::EntryType::Regular { b"data" } else { b"" };
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+        builder.finish().unwrap();
+    }
+
+    /// Extracts an archive of `entries` below `root`, leaving no staging
+    /// directory behind.
+    fn extract_raw(root: &Path, entries: &[(&str, tar::EntryType, &str)]) -> std::io::Result<()> {
+        let archive = root.join("raw.tar");
+        raw_archive(&archive, entries);
+        let copy = root.join("copy");
+        let result = extract_archive(&archive, &copy);
+        let staged = std::fs::read_dir(root)
+            .unwrap()
+            .any(|entry| entry.unwrap().file_name().to_string_lossy().starts_with(".redsys-copy-"));
+        assert!(!staged);
+        if result.is_err() {
+            assert!(!copy.exists());
+        } else {
+            std::fs::remove_dir_all(&copy).unwrap();
+        }
+        result
+    }
+
+    fn test_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("redsys-transfer-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_extract_rejects_path_traversal() {
+        use tar::EntryType::{Directory, Regular};
+        let root = test_root("traversal");
+        for path in ["output/../../escaped", "output/../escaped", "/tmp/escaped", "other/escaped"] {
+            let result = extract_raw(&root, &[("output/", Directory, ""), (path, Regular, "")]);
+            assert!(result.is_err(), "{path} was extracted");
+        }
+        assert!(!root.join("escaped").exists());
+        assert!(!root.parent().unwrap().join("escaped").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_extract_rejects_links_out_of_the_copy() {
+        use tar::EntryType::{Directory, Link, Symlink};
+        let root = test_root("links");
+        for (kind, target) in [
+            (Link, "/etc/passwd"),
+            (Link, "../outside"),
+            (Link, "output/../../outside"),
+            (Symlink, "/etc"),
+            (Symlink, "../outside"),
+            (Symlink, "nested/../../../outside"),
+        ] {
+            let result = extract_raw(&root, &[("output/", Directory, ""), ("output/link", kind, target)]);
+            assert!(result.is_err(), "{kind:?} to {target} was extracted");
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_keeps_links_inside_the_copy() {
+        use tar::EntryType::{Directory, Link, Regular, Symlink};
+        let root = test_root("inside-links");
+        let archive = root.join("raw.tar");
+        raw_archive(&archive, &[
+            ("output/", Directory, ""),
+            ("output/data", Regular, ""),
+            ("output/hard", Link, "output/data"),
+            ("output/soft", Symlink, "data"),
+        ]);
+        let copy = root.join("copy");
+        extract_archive(&archive, &copy).unwrap();
+
+        assert_eq!(std::fs::read_to_string(copy.join("hard")).unwrap(), "data");
+        assert_eq!(std::fs::read_to_string(copy.join("soft")).unwrap(), "data");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
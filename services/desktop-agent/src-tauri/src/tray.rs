@@ -0,0 +1,186 @@
+//! System tray for RedSys Desktop Agent
+//!
+//! Keeps the agent reachable while its window is closed. The tray icon is a
+//! status dot that follows [`DockerStatus`] in real time, with the status in
+//! its tooltip, and its menu offers:
+//! - **Open dashboard**: shows and focuses the main window
+//! - **Pause monitoring** / **Resume monitoring**: toggles the shared
+//!   [`PauseControl`]
+//! - **Quit**: exits the agent
+//!
+//! Closing the window hides it; the agent keeps running until **Quit** or the
+//! `shutdown_agent` command.
+//!
+//! ## References
+//! - [Tauri System Tray](https://v2.tauri.app/learn/system-tray/)
+
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::Manager;
+
+use crate::docker_monitor::{DockerMonitor, DockerStatus};
+use crate::pause::PauseControl;
+
+/// ID of the agent's tray icon.
+pub const TRAY_ID: &str = "main";
+
+/// Side length of the generated status icons in pixels.
+const ICON_SIZE: u32 = 32;
+
+/// Menu item IDs.
+const OPEN_DASHBOARD_ID: &str = "open_dashboard";
+const TOGGLE_PAUSE_ID: &str = "toggle_pause";
+const QUIT_ID: &str = "quit";
+
+/// Color of the status dot as RGB.
+fn status_color(status: &DockerStatus) -> [u8; 3] {
+    match status {
+        DockerStatus::Running { .. } => [0x22, 0xc5, 0x5e],
+        DockerStatus::Stopped { .. } => [0x9c, 0xa3, 0xaf],
+        DockerStatus::Error { .. } => [0xef, 0x44, 0x44],
+        DockerStatus::Paused => [0xf5, 0x9e, 0x0b],
+        DockerStatus::Starting | DockerStatus::Restarting => [0x3b, 0x82, 0xf6],
+        DockerStatus::Degraded { .. } => [0xea, 0xb3, 0x08],
+        DockerStatus::Unreachable { .. } => [0xef, 0x44, 0x44],
+    }
+}
+
+/// Renders the status dot as RGBA pixels, anti-aliased at the edge.
+fn status_icon_rgba(status: &DockerStatus) -> Vec<u8> {
+    let [r, g, b] = status_color(status);
+    let center = ICON_SIZE as f32 / 2.0;
+    let radius = center - 2.0;
+
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let distance = ((x as f32 + 0.5 - center).powi(2) + (y as f32 + 0.5 - center).powi(2)).sqrt();
+            let coverage = (radius + 0.5 - distance).clamp(0.0, 1.0);
+            rgba.extend_from_slice(&[r, g, b, (coverage * 255.0) as u8]);
+        }
+    }
+    rgba
+}
+
+/// Tooltip text for a status.
+fn tooltip(status: &DockerStatus) -> String {
+    format!("RedSys Desktop Agent - {status}")
+}
+
+/// Shows and focuses the main window, creating it when the agent runs
+/// without one, e.g. as a background service.
+pub fn open_dashboard(app_handle: &tauri::AppHandle) {
+    let window = match app_handle.get_webview_window("main") {
+        Some(window) => window,
+        None => {
+            let Some(config) = app_handle.config().app.windows.first() else {
+                warn!("Main window not configured");
+                return;
+            };
+            match tauri::WebviewWindowBuilder::from_config(app_handle, config).and_then(|builder| builder.build()) {
+                Ok(window) => window,
+                Err(e) => {
+                    warn!("Failed to create dashboard window: {e}");
+                    return;
+                }
+            }
+        }
+    };
+    if let Err(e) = window.show().and_then(|_| window.unminimize()).and_then(|_| window.set_focus()) {
+        warn!("Failed to open dashboard: {e}");
+    }
+}
+
+/// Creates the tray icon and keeps it in sync with the Docker status until
+/// `cancellation_token` is cancelled.
+pub fn create(
+    app_handle: &tauri::AppHandle,
+    docker_monitor: Arc<DockerMonitor>,
+    pause: Arc<PauseControl>,
+    cancellation_token: CancellationToken,
+) -> tauri::Result<()> {
+    let mut status_changes = docker_monitor.subscribe_status();
+    let status = status_changes.borrow_and_update().clone();
+
+    let pause_item = MenuItem::with_id(app_handle, TOGGLE_PAUSE_ID, "Pause monitoring", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app_handle,
+        &[
+            &MenuItem::with_id(app_handle, OPEN_DASHBOARD_ID, "Open dashboard", true, None::<&str>)?,
+            &pause_item,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(app_handle, QUIT_ID, "Quit", true, None::<&str>)?,
+        ],
+    )?;
+
+    let tray = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(Image::new_owned(status_icon_rgba(&status), ICON_SIZE, ICON_SIZE))
+        .tooltip(tooltip(&status))
+        .menu(&menu)
+        .on_menu_event(move |app_handle, event| match event.id().as_ref() {
+            OPEN_DASHBOARD_ID => open_dashboard(app_handle),
+            TOGGLE_PAUSE_ID => {
+                if !pause.pause() {
+                    pause.resume();
+                }
+            }
+            QUIT_ID => {
+                info!("Quit requested from the tray");
+                app_handle.exit(0);
+            }
+            other => debug!("Ignoring tray menu event {other}"),
+        })
+        .build(app_handle)?;
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                changed = status_changes.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let status = status_changes.borrow_and_update().clone();
+                    let label = if status == DockerStatus::Paused { "Resume monitoring" } else { "Pause monitoring" };
+
+                    let result = tray
+                        .set_icon(Some(Image::new_owned(status_icon_rgba(&status), ICON_SIZE, ICON_SIZE)))
+                        .and_then(|_| tray.set_tooltip(Some(tooltip(&status))))
+                        .and_then(|_| pause_item.set_text(label));
+                    if let Err(e) = result {
+                        warn!("Failed to update tray icon: {e}");
+                    }
+                }
+                _ = cancellation_token.cancelled() => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_monitor::EngineDetails;
+
+    #[test]
+    fn test_status_icon_is_a_dot() {
+        let rgba = status_icon_rgba(&DockerStatus::Error { message: "boom".to_string(), retry_after: None });
+        assert_eq!(rgba.len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+
+        let pixel = |x: u32, y: u32| &rgba[((y * ICON_SIZE + x) * 4) as usize..][..4];
+        assert_eq!(pixel(0, 0)[3], 0);
+        assert_eq!(pixel(ICON_SIZE / 2, ICON_SIZE / 2), [0xef, 0x44, 0x44, 0xff]);
+    }
+
+    #[test]
+    fn test_tooltip_includes_status() {
+        assert_eq!(
+            tooltip(&DockerStatus::Running { version: "27.0.1".to_string(), engine: EngineDetails::default() }),
+            "RedSys Desktop Agent - Docker 27.0.1 running"
+        );
+    }
+}
@@ -1,35 +1,222 @@
 //! Type definitions for RedSys Desktop Agent
 //!
 //! This module contains all the type definitions used throughout the application,
-//! including application state.
+//! including application state and the payloads of Tauri events.
 
+use std::collections::BTreeMap;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::diagnosis::DockerUnavailableReason;
+use crate::docker_monitor::{DockerStatus, LastKnownEngine};
+use crate::jobs::{JobState, JobStateChanged};
+use crate::requirements::RequirementsReport;
+use crate::system_monitor::SystemMetrics;
 
 /// Application state
 ///
 /// This struct holds the global state of the application, including
-/// runtime information.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// runtime information from the Docker monitor, the system monitor and the
+/// job manager, so one snapshot describes the whole agent.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
 pub struct AppState {
     /// Application metadata
     pub app_metadata: AppMetadata,
 
+    /// Docker daemon status
+    pub docker: DockerSection,
+
+    /// Host resource usage
+    pub system: SystemSection,
+
+    /// Job activity
+    pub jobs: JobsSection,
+
+    /// Minimum requirements check, `None` until it ran at startup
+    pub requirements: Option<RequirementsReport>,
+
     /// Last update timestamp
     pub last_updated: DateTime<Utc>,
+
+    /// Incremented on every update
+    pub revision: u64,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
             app_metadata: AppMetadata::default(),
+            docker: DockerSection::default(),
+            system: SystemSection::default(),
+            jobs: JobsSection::default(),
+            requirements: None,
             last_updated: Utc::now(),
+            revision: 0,
+        }
+    }
+}
+
+/// Docker part of the [`AppState`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct DockerSection {
+    /// Latest daemon status
+    pub status: DockerStatus,
+
+    /// When the status last changed, `None` before the first check
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for DockerSection {
+    fn default() -> Self {
+        Self {
+            status: DockerStatus::Stopped { reason: DockerUnavailableReason::Unknown },
+            updated_at: None,
+        }
+    }
+}
+
+/// Host resource part of the [`AppState`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct SystemSection {
+    /// Latest sample, `None` until the system monitor reported one
+    pub metrics: Option<SystemMetrics>,
+
+    /// When the sample was taken
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Job part of the [`AppState`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct JobsSection {
+    /// Jobs that have not finished, in the order they were accepted
+    pub active: Vec<JobStateChanged>,
+
+    /// Jobs that succeeded since the agent started
+    pub succeeded: u64,
+
+    /// Jobs that failed since the agent started
+    pub failed: u64,
+
+    /// Jobs cancelled since the agent started
+    pub cancelled: u64,
+
+    /// When a job last changed state
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl JobsSection {
+    /// Records a job state change.
+    pub fn record(&mut self, change: JobStateChanged) {
+        let position = self.active.iter().position(|job| job.job_id == change.job_id);
+        match (&change.state, position) {
+            (JobState::Succeeded | JobState::Failed { .. } | JobState::Cancelled, _) => {
+                if let Some(position) = position {
+                    self.active.remove(position);
+                }
+                match change.state {
+                    JobState::Succeeded => self.succeeded += 1,
+                    JobState::Cancelled => self.cancelled += 1,
+                    _ => self.failed += 1,
+                }
+            }
+            (_, Some(position)) => self.active[position] = change,
+            (_, None) => self.active.push(change),
+        }
+        self.updated_at = Some(Utc::now());
+    }
+}
+
+impl AppState {
+    /// Applies the fields set in `patch`.
+    pub fn apply(&mut self, patch: AppStatePatch) {
+        if let Some(metadata) = patch.app_metadata {
+            self.app_metadata.apply(metadata);
+        }
+    }
+}
+
+/// Payload of the `app-state-changed` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct AppStateChanged {
+    /// Revision after the update
+    pub revision: u64,
+
+    /// New values of the changed fields by dotted path, e.g. `app_metadata.name`
+    #[cfg_attr(feature = "ts-bindings", ts(type = "Record<string, unknown>"))]
+    pub changes: BTreeMap<String, Value>,
+
+    /// When the update happened
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AppStateChanged {
+    /// Describes the update from `previous` to `current`.
+    ///
+    /// `revision` and `last_updated` change with every update and are left
+    /// out of `changes`.
+    pub fn between(previous: &AppState, current: &AppState) -> Self {
+        let mut changes = BTreeMap::new();
+        if let (Ok(Value::Object(mut previous)), Ok(Value::Object(mut current))) =
+            (serde_json::to_value(previous), serde_json::to_value(current))
+        {
+            for field in ["revision", "last_updated"] {
+                previous.remove(field);
+                current.remove(field);
+            }
+            diff("", &Value::Object(previous), &Value::Object(current), &mut changes);
+        }
+        Self {
+            revision: current.revision,
+            changes,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// Collects the values in `current` that differ from `previous`.
+///
+/// Objects are compared field by field; any other value is reported whole.
+/// Fields removed from an object are reported as `null`.
+fn diff(path: &str, previous: &Value, current: &Value, changes: &mut BTreeMap<String, Value>) {
+    match (previous, current) {
+        (Value::Object(previous), Value::Object(current)) => {
+            for (key, value) in current {
+                let path = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                diff(&path, previous.get(key).unwrap_or(&Value::Null), value, changes);
+            }
+            for key in previous.keys().filter(|key| !current.contains_key(*key)) {
+                let path = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                changes.insert(path, Value::Null);
+            }
         }
+        _ if previous != current => {
+            changes.insert(path.to_string(), current.clone());
+        }
+        _ => {}
     }
 }
 
+/// Field-level update of the [`AppState`]; unset fields are left unchanged.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AppStatePatch {
+    /// Revision the patch was based on; the patch is rejected if the state
+    /// has been updated since
+    pub base_revision: Option<u64>,
+
+    /// Application metadata changes
+    pub app_metadata: Option<AppMetadataPatch>,
+}
+
 /// Application metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
 pub struct AppMetadata {
     /// Application name
     pub name: String,
@@ -44,6 +231,35 @@ pub struct AppMetadata {
     pub build_timestamp: DateTime<Utc>,
 }
 
+impl AppMetadata {
+    /// Applies the fields set in `patch`.
+    pub fn apply(&mut self, patch: AppMetadataPatch) {
+        if let Some(name) = patch.name {
+            self.name = name;
+        }
+        if let Some(version) = patch.version {
+            self.version = version;
+        }
+        if let Some(description) = patch.description {
+            self.description = description;
+        }
+    }
+}
+
+/// Field-level update of the [`AppMetadata`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AppMetadataPatch {
+    /// New application name
+    pub name: Option<String>,
+
+    /// New application version
+    pub version: Option<String>,
+
+    /// New application description
+    pub description: Option<String>,
+}
+
 impl Default for AppMetadata {
     fn default() -> Self {
         Self {
@@ -55,13 +271,112 @@ impl Default for AppMetadata {
     }
 }
 
+/// Payload of the `docker-status-changed` event.
+///
+/// The status fields are inlined, so listeners see the same `type`-tagged
+/// object as `get_docker_status` returns, plus when the change was observed
+/// and, while the daemon is not running, the engine last seen running.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS), ts(export))]
+pub struct DockerStatusChangedPayload {
+    /// New daemon status
+    #[serde(flatten)]
+    pub status: DockerStatus,
+
+    /// When the monitor observed the change
+    pub changed_at: DateTime<Utc>,
+
+    /// Engine last seen running, while the daemon is not running
+    pub last_known: Option<LastKnownEngine>,
+}
+
+impl DockerStatusChangedPayload {
+    /// Creates a payload for a change observed now.
+    pub fn new(status: DockerStatus) -> Self {
+        Self {
+            status,
+            changed_at: Utc::now(),
+            last_known: None,
+        }
+    }
+
+    /// Adds the engine last seen running.
+    pub fn with_last_known(mut self, last_known: Option<LastKnownEngine>) -> Self {
+        self.last_known = last_known;
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::docker_monitor::EngineDetails;
 
     #[test]
     fn test_app_state_default() {
         let state = AppState::default();
         assert_eq!(state.app_metadata.name, "RedSys Desktop Agent");
     }
+
+    #[test]
+    fn test_patch_changes_only_set_fields() {
+        let mut state = AppState::default();
+        let patch: AppStatePatch = serde_json::from_value(serde_json::json!({
+            "app_metadata": { "description": "Render node" }
+        }))
+        .unwrap();
+        state.apply(patch);
+        assert_eq!(state.app_metadata.description, "Render node");
+        assert_eq!(state.app_metadata.name, "RedSys Desktop Agent");
+    }
+
+    #[test]
+    fn test_jobs_section_tracks_active_jobs() {
+        let change = |job_id: &str, state: JobState| JobStateChanged {
+            job_id: job_id.to_string(),
+            state,
+            container_id: None,
+            exit_code: None,
+        };
+        let mut jobs = JobsSection::default();
+        jobs.record(change("render-1", JobState::Queued));
+        jobs.record(change("render-2", JobState::Queued));
+        jobs.record(change("render-1", JobState::Running));
+        assert_eq!(jobs.active.len(), 2);
+        assert_eq!(jobs.active[0].state, JobState::Running);
+
+        jobs.record(change("render-1", JobState::Succeeded));
+        jobs.record(change("render-2", JobState::Failed { error: "boom".to_string() }));
+        assert!(jobs.active.is_empty());
+        assert_eq!((jobs.succeeded, jobs.failed), (1, 1));
+
+        jobs.record(change("render-3", JobState::Running));
+        jobs.record(change("render-3", JobState::Cancelled));
+        assert!(jobs.active.is_empty());
+        assert_eq!((jobs.succeeded, jobs.failed, jobs.cancelled), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_app_state_diff() {
+        let previous = AppState::default();
+        let mut current = previous.clone();
+        current.app_metadata.version = "0.2.0".to_string();
+        current.revision += 1;
+        current.last_updated = Utc::now();
+
+        let change = AppStateChanged::between(&previous, &current);
+        assert_eq!(change.revision, 1);
+        assert_eq!(change.changes.len(), 1);
+        assert_eq!(change.changes["app_metadata.version"], "0.2.0");
+    }
+
+    #[test]
+    fn test_docker_status_changed_payload_inlines_status() {
+        let payload = DockerStatusChangedPayload::new(DockerStatus::Running { version: "27.0.1".to_string(), engine: EngineDetails::default() });
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(value["type"], "Running");
+        assert_eq!(value["version"], "27.0.1");
+        assert!(value["changed_at"].is_string());
+        assert!(value["last_known"].is_null());
+    }
 }
@@ -0,0 +1,427 @@
+//! Job workspaces for RedSys Desktop Agent
+//!
+//! Gives every job a scratch directory for inputs and artifacts, mounted at
+//! [`WORKSPACE_MOUNT_TARGET`] inside the job container.
+//!
+//! ## Quota
+//! All workspaces share a disk quota (`workspace.quota_bytes`). A new
+//! workspace is refused while the quota is used up, and garbage collection
+//! removes the oldest finished workspaces early when usage exceeds it.
+//! Running jobs are held to it as well: the [`JobManager`] checks
+//! [`WorkspaceManager::over_quota`] every minute and stops the jobs whose
+//! workspaces alone exceed the quota, largest first.
+//!
+//! ## Retention
+//! Finished workspaces are kept for `workspace.retention_hours` so results can
+//! be collected, then removed by the hourly garbage collection. A workspace
+//! counts as finished once it has a marker file in [`FINISHED_DIR`], which
+//! lives in the root outside the workspaces, so a job cannot mark or unmark
+//! its own workspace.
+//!
+//! [`JobManager`]: crate::jobs::JobManager
+//!
+//! ## Default Location
+//! - **Linux**: `$XDG_DATA_HOME/tn.com.redsys-desktop-agent/workspaces`
+//! - **macOS**: `~/Library/Application Support/tn.com.redsys-desktop-agent/workspaces`
+//! - **Windows**: `%LOCALAPPDATA%\tn.com.redsys-desktop-agent\workspaces`
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::{task, time::{interval, Duration}};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use serde::Serialize;
+
+use crate::config::WorkspaceConfig;
+use crate::error::{AppError, AppResult};
+use crate::APP_IDENTIFIER;
+
+/// Path of the workspace inside job containers.
+pub const WORKSPACE_MOUNT_TARGET: &str = "/workspace";
+
+/// Directory in the workspace root holding a marker file per finished
+/// workspace. Job IDs cannot start with a dot, so it never is a workspace.
+pub const FINISHED_DIR: &str = ".finished";
+
+/// Interval between garbage collection runs.
+const GC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Disk usage of one workspace.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkspaceInfo {
+    /// Job the workspace belongs to
+    pub job_id: String,
+
+    /// Size of all files in bytes
+    pub size_bytes: u64,
+
+    /// When the job finished, `None` while it is running
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Disk usage of all workspaces.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkspaceUsage {
+    /// Workspace root directory
+    pub root: String,
+
+    /// Configured quota in bytes
+    pub quota_bytes: u64,
+
+    /// Bytes used by all workspaces
+    pub used_bytes: u64,
+
+    /// Individual workspaces, largest first
+    pub workspaces: Vec<WorkspaceInfo>,
+}
+
+/// Result of a garbage collection run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GcReport {
+    /// Workspaces removed
+    pub removed: Vec<String>,
+
+    /// Bytes freed
+    pub freed_bytes: u64,
+}
+
+/// Creates, measures and garbage-collects job workspaces.
+#[derive(Debug)]
+pub struct WorkspaceManager {
+    /// Directory holding one subdirectory per job
+    root: PathBuf,
+
+    /// Total bytes all workspaces may use
+    quota_bytes: u64,
+
+    /// How long finished workspaces are kept
+    retention: Duration,
+
+    /// Cancellation token for graceful shutdown
+    cancellation_token: Arc<CancellationToken>,
+}
+
+impl WorkspaceManager {
+    /// Creates a workspace manager from the `[workspace]` config section.
+    ///
+    /// Returns an error if no root is configured and the platform data
+    /// directory cannot be determined.
+    pub fn new(config: &WorkspaceConfig, cancellation_token: CancellationToken) -> AppResult<Self> {
+        let root = match &config.root {
+            Some(root) => root.clone(),
+            None => Self::default_root()
+                .ok_or_else(|| AppError::Configuration("Cannot determine the workspace directory".to_string()))?,
+        };
+        Ok(Self {
+            root,
+            quota_bytes: config.quota_bytes,
+            retention: Duration::from_secs(config.retention_hours * 60 * 60),
+            cancellation_token: Arc::new(cancellation_token),
+        })
+    }
+
+    /// Gets the platform-specific default workspace root.
+    pub fn default_root() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join(APP_IDENTIFIER).join("workspaces"))
+    }
+
+    /// Gets the workspace root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Gets the directory of a job's workspace.
+    pub fn path(&self, job_id: &str) -> PathBuf {
+        self.root.join(job_id)
+    }
+
+    /// Gets the finished marker of a job's workspace.
+    fn marker(&self, job_id: &str) -> PathBuf {
+        self.root.join(FINISHED_DIR).join(job_id)
+    }
+
+    /// Creates an empty workspace for a job.
+    ///
+    /// Returns an error if the quota is used up or the workspace already exists.
+    pub fn create(&self, job_id: &str) -> AppResult<PathBuf> {
+        let used_bytes = self.usage()?.used_bytes;
+        if used_bytes >= self.quota_bytes {
+            return Err(AppError::InvalidState(format!(
+                "Workspace quota of {} bytes is used up ({used_bytes} bytes in use)", self.quota_bytes
+            )));
+        }
+
+        let path = self.path(job_id);
+        if path.exists() {
+            return Err(AppError::InvalidState(format!("Workspace for job '{job_id}' already exists")));
+        }
+        // Left over from a workspace removed by hand
+        if let Err(e) = std::fs::remove_file(self.marker(job_id)) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(e.into());
+            }
+        }
+        std::fs::create_dir_all(&path)?;
+        info!("Created workspace {}", path.display());
+        Ok(path)
+    }
+
+    /// Marks a job's workspace as finished, starting its retention window.
+    pub fn finish(&self, job_id: &str) -> AppResult<()> {
+        let path = self.path(job_id);
+        if !path.is_dir() {
+            return Err(AppError::NotFound {
+                resource: format!("workspace for job '{job_id}'"),
+            });
+        }
+        std::fs::create_dir_all(self.root.join(FINISHED_DIR))?;
+        std::fs::write(self.marker(job_id), chrono::Utc::now().to_rfc3339())?;
+        debug!("Workspace for job {} finished", job_id);
+        Ok(())
+    }
+
+    /// Measures all workspaces.
+    pub fn usage(&self) -> AppResult<WorkspaceUsage> {
+        let mut workspaces = Vec::new();
+        if self.root.is_dir() {
+            for entry in std::fs::read_dir(&self.root)? {
+                let entry = entry?;
+                let job_id = entry.file_name().to_string_lossy().into_owned();
+                if !entry.file_type()?.is_dir() || job_id.starts_with('.') {
+                    continue;
+                }
+                workspaces.push(WorkspaceInfo {
+                    size_bytes: directory_size(&entry.path())?,
+                    finished_at: finished_at(&self.marker(&job_id)).map(chrono::DateTime::from),
+                    job_id,
+                });
+            }
+        }
+        workspaces.sort_by_key(|workspace| std::cmp::Reverse(workspace.size_bytes));
+
+        Ok(WorkspaceUsage {
+            root: self.root.display().to_string(),
+            quota_bytes: self.quota_bytes,
+            used_bytes: workspaces.iter().map(|workspace| workspace.size_bytes).sum(),
+            workspaces,
+        })
+    }
+
+    /// Removes finished workspaces past their retention window, then the
+    /// oldest finished ones while usage exceeds the quota.
+    pub fn collect_garbage(&self) -> AppResult<GcReport> {
+        let usage = self.usage()?;
+        let now = SystemTime::now();
+
+        let mut finished: Vec<(SystemTime, WorkspaceInfo)> = usage
+            .workspaces
+            .into_iter()
+            .filter_map(|workspace| Some((finished_at(&self.marker(&workspace.job_id))?, workspace)))
+            .collect();
+        finished.sort_by_key(|(finished, _)| *finished);
+
+        let mut report = GcReport::default();
+        let mut used_bytes = usage.used_bytes;
+        for (finished, workspace) in finished {
+            let expired = now.duration_since(finished).unwrap_or_default() >= self.retention;
+            if !expired && used_bytes <= self.quota_bytes {
+                continue;
+            }
+            std::fs::remove_dir_all(self.path(&workspace.job_id))?;
+            std::fs::remove_file(self.marker(&workspace.job_id))?;
+            used_bytes = used_bytes.saturating_sub(workspace.size_bytes);
+            report.freed_bytes += workspace.size_bytes;
+            report.removed.push(workspace.job_id);
+        }
+
+        if used_bytes > self.quota_bytes {
+            warn!("Running job workspaces use {} bytes, over the {} byte quota", used_bytes, self.quota_bytes);
+        }
+        Ok(report)
+    }
+
+    /// Jobs whose running workspaces alone exceed the quota: the largest
+    /// first, as many as have to go to get back within it. Finished
+    /// workspaces are left to garbage collection. Blocking.
+    pub fn over_quota(&self) -> AppResult<Vec<String>> {
+        let running: Vec<WorkspaceInfo> = self
+            .usage()?
+            .workspaces
+            .into_iter()
+            .filter(|workspace| workspace.finished_at.is_none())
+            .collect();
+        let mut used_bytes: u64 = running.iter().map(|workspace| workspace.size_bytes).sum();
+        let mut jobs = Vec::new();
+        for workspace in running {
+            if used_bytes <= self.quota_bytes {
+                break;
+            }
+            used_bytes -= workspace.size_bytes;
+            jobs.push(workspace.job_id);
+        }
+        Ok(jobs)
+    }
+
+    /// Starts hourly garbage collection.
+    pub async fn start_gc(self: Arc<Self>) {
+        let cancellation_token = self.cancellation_token.clone();
+
+        let mut gc_interval = interval(GC_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = gc_interval.tick() => {
+                    let manager = self.clone();
+                    match task::spawn_blocking(move || manager.collect_garbage()).await {
+                        Ok(Ok(report)) if report.removed.is_empty() => debug!("Workspace garbage collection found nothing to remove"),
+                        Ok(Ok(report)) => info!(
+                            "Removed {} workspace(s), freed {} bytes", report.removed.len(), report.freed_bytes
+                        ),
+                        Ok(Err(e)) => warn!("Workspace garbage collection failed: {e}"),
+                        Err(e) => error!("Workspace garbage collection panicked: {e}"),
+                    }
+                }
+                _ = cancellation_token.cancelled() => {
+                    info!("Workspace garbage collection received cancellation signal, shutting down");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Gets when a workspace was marked finished, from its `marker`.
+fn finished_at(marker: &Path) -> Option<SystemTime> {
+    std::fs::metadata(marker).ok()?.modified().ok()
+}
+
+/// Sums the sizes of all files below `path` without following symlinks.
+fn directory_size(path: &Path) -> AppResult<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += directory_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(name: &str, quota_bytes: u64, retention_hours: u64) -> WorkspaceManager {
+        let root = std::env::temp_dir().join(format!("redsys-workspace-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let config = WorkspaceConfig {
+            root: Some(root),
+            quota_bytes,
+            retention_hours,
+        };
+        WorkspaceManager::new(&config, CancellationToken::new()).unwrap()
+    }
+
+    #[test]
+    fn test_create_and_measure() {
+        let manager = manager("usage", 1024, 24);
+        let path = manager.create("job-1").unwrap();
+        std::fs::create_dir(path.join("out")).unwrap();
+        std::fs::write(path.join("out").join("result.bin"), [0u8; 100]).unwrap();
+        assert!(manager.create("job-1").is_err());
+
+        let usage = manager.usage().unwrap();
+        assert_eq!(usage.used_bytes, 100);
+        assert_eq!(usage.workspaces[0].job_id, "job-1");
+        assert_eq!(usage.workspaces[0].finished_at, None);
+
+        std::fs::write(path.join("big.bin"), [0u8; 1024]).unwrap();
+        assert!(manager.create("job-2").is_err());
+
+        std::fs::remove_dir_all(manager.root()).unwrap();
+    }
+
+    /// Backdates the finished marker of a workspace.
+    fn finished_ago(manager: &WorkspaceManager, job_id: &str, hours: u64) {
+        manager.finish(job_id).unwrap();
+        let marker = std::fs::File::options()
+            .write(true)
+            .open(manager.marker(job_id))
+            .unwrap();
+        marker
+            .set_modified(SystemTime::now() - Duration::from_secs(hours * 60 * 60))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_gc_removes_expired_workspaces() {
+        let manager = manager("retention", 1 << 20, 24);
+        for job_id in ["running", "expired", "recent"] {
+            manager.create(job_id).unwrap();
+        }
+        finished_ago(&manager, "expired", 25);
+        finished_ago(&manager, "recent", 1);
+
+        let report = manager.collect_garbage().unwrap();
+        assert_eq!(report.removed, vec!["expired".to_string()]);
+        assert!(manager.path("running").is_dir());
+        assert!(manager.path("recent").is_dir());
+
+        std::fs::remove_dir_all(manager.root()).unwrap();
+    }
+
+    #[test]
+    fn test_gc_enforces_quota_oldest_first() {
+        let manager = manager("quota", 200, 24);
+        for job_id in ["running", "older", "newer"] {
+            let path = manager.create(job_id).unwrap();
+            std::fs::write(path.join("data"), [0u8; 60]).unwrap();
+        }
+        finished_ago(&manager, "older", 2);
+        finished_ago(&manager, "newer", 1);
+
+        // Over quota with three workspaces, within it after dropping one
+        let report = manager.collect_garbage().unwrap();
+        assert_eq!(report.removed, vec!["older".to_string()]);
+        assert!(report.freed_bytes >= 60);
+
+        std::fs::remove_dir_all(manager.root()).unwrap();
+    }
+
+    #[test]
+    fn test_marker_outside_workspace() {
+        let manager = manager("marker", 1 << 20, 24);
+        let path = manager.create("job-1").unwrap();
+        // A job writing its own marker does not finish its workspace
+        std::fs::write(path.join(".redsys-finished"), "").unwrap();
+        assert_eq!(manager.usage().unwrap().workspaces[0].finished_at, None);
+
+        manager.finish("job-1").unwrap();
+        let usage = manager.usage().unwrap();
+        assert_eq!(usage.workspaces.len(), 1);
+        assert!(usage.workspaces[0].finished_at.is_some());
+
+        std::fs::remove_dir_all(manager.root()).unwrap();
+    }
+
+    #[test]
+    fn test_over_quota_picks_largest_running() {
+        let manager = manager("over-quota", 100, 24);
+        for job_id in ["small", "large", "done"] {
+            manager.create(job_id).unwrap();
+        }
+        for (job_id, size) in [("small", 30), ("large", 80), ("done", 500)] {
+            std::fs::write(manager.path(job_id).join("data"), vec![0u8; size]).unwrap();
+        }
+        manager.finish("done").unwrap();
+
+        assert_eq!(manager.over_quota().unwrap(), vec!["large".to_string()]);
+        std::fs::remove_file(manager.path("large").join("data")).unwrap();
+        assert!(manager.over_quota().unwrap().is_empty());
+
+        std::fs::remove_dir_all(manager.root()).unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+//! Integration tests against a real Docker daemon
+//!
+//! Needs a local Docker daemon that allows privileged containers, and runs
+//! only with the `docker-integration` feature:
+//!
+//! ```sh
+//! cargo test --features docker-integration --test docker_integration
+//! ```
+//!
+//! Fixtures are containers on the host daemon, started through Bollard and
+//! force-removed when each test ends:
+//! - `alpine` containers for the container commands and the events stream,
+//!   driven through the host daemon
+//! - A `docker:dind` daemon that is stopped and started to exercise status
+//!   transitions without touching the host daemon
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use bollard::models::{ContainerCreateBody, HostConfig, PortBinding};
+use bollard::query_parameters::{
+    CreateContainerOptions, CreateImageOptionsBuilder, InspectContainerOptions, LogsOptionsBuilder,
+    RemoveContainerOptionsBuilder, StartContainerOptions, StopContainerOptions,
+};
+use bollard::{Docker, API_DEFAULT_VERSION};
+use desktop_agent_lib::docker::{DockerEvent, DockerService};
+use desktop_agent_lib::docker_monitor::{DockerMonitor, DockerStatus};
+use desktop_agent_lib::status::{DaemonState, StatusMachine, TransitionReason};
+use futures::StreamExt;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout, Duration};
+use tokio_util::sync::CancellationToken;
+
+/// Longest wait for an event or a daemon to come up.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Port of the unauthenticated dind API.
+const DIND_PORT: u16 = 2375;
+
+fn host_daemon() -> Arc<Docker> {
+    Arc::new(Docker::connect_with_defaults().expect("local Docker daemon"))
+}
+
+/// Container started for a test, force-removed when dropped.
+struct Fixture {
+    id: String,
+}
+
+impl Fixture {
+    /// Pulls `image` and starts a container of it from `body`.
+    async fn start(image: &str, body: ContainerCreateBody) -> Self {
+        let docker = host_daemon();
+        let options = CreateImageOptionsBuilder::new().from_image(image).build();
+        let mut pull = docker.create_image(Some(options), None, None);
+        while let Some(progress) = pull.next().await {
+            progress.expect("fixture image pull");
+        }
+
+        let body = ContainerCreateBody {
+            image: Some(image.to_string()),
+            ..body
+        };
+        let id = docker
+            .create_container(None::<CreateContainerOptions>, body)
+            .await
+            .expect("fixture container")
+            .id;
+        // Removed on drop even if starting fails
+        let fixture = Self { id };
+        docker
+            .start_container(&fixture.id, None::<StartContainerOptions>)
+            .await
+            .expect("fixture start");
+        fixture
+    }
+
+    async fn stop(&self) {
+        host_daemon()
+            .stop_container(&self.id, None::<StopContainerOptions>)
+            .await
+            .expect("fixture stop");
+    }
+
+    async fn restart(&self) {
+        host_daemon()
+            .start_container(&self.id, None::<StartContainerOptions>)
+            .await
+            .expect("fixture start");
+    }
+
+    /// Waits until the container logs a line containing `message`.
+    async fn wait_for_log(&self, message: &str) {
+        let options = LogsOptionsBuilder::new().follow(true).stdout(true).stderr(true).build();
+        let docker = host_daemon();
+        let mut logs = docker.logs(&self.id, Some(options));
+        timeout(WAIT_TIMEOUT, async {
+            while let Some(output) = logs.next().await {
+                if output.expect("fixture logs").to_string().contains(message) {
+                    return;
+                }
+            }
+            panic!("fixture exited before logging '{message}'");
+        })
+        .await
+        .unwrap_or_else(|_| panic!("fixture did not log '{message}'"));
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        // Drop cannot await, and the test's runtime may be shutting down
+        let id = self.id.clone();
+        let removed = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            let options = RemoveContainerOptionsBuilder::new().force(true).v(true).build();
+            runtime
+                .block_on(host_daemon().remove_container(&id, Some(options)))
+                .map_err(std::io::Error::other)
+        })
+        .join();
+        if let Ok(Err(e)) = removed {
+            eprintln!("Failed to remove fixture {}: {e}", self.id);
+        }
+    }
+}
+
+async fn sleeper() -> Fixture {
+    let body = ContainerCreateBody {
+        cmd: Some(vec!["sleep".to_string(), "infinity".to_string()]),
+        labels: Some(HashMap::from([("tn.redsys.integration".to_string(), "true".to_string())])),
+        ..Default::default()
+    };
+    Fixture::start("alpine:3.20", body).await
+}
+
+async fn dind() -> Fixture {
+    let port = format!("{DIND_PORT}/tcp");
+    let body = ContainerCreateBody {
+        env: Some(vec!["DOCKER_TLS_CERTDIR=".to_string()]),
+        exposed_ports: Some(HashMap::from([(port.clone(), HashMap::new())])),
+        host_config: Some(HostConfig {
+            privileged: Some(true),
+            // Any free host port
+            port_bindings: Some(HashMap::from([(
+                port,
+                Some(vec![PortBinding {
+                    host_ip: Some("127.0.0.1".to_string()),
+                    host_port: None,
+                }]),
+            )])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let daemon = Fixture::start("docker:27-dind", body).await;
+    daemon.wait_for_log("API listen on").await;
+    daemon
+}
+
+/// Connects to the dind daemon at its current host port.
+async fn dind_client(daemon: &Fixture) -> Docker {
+    let inspect = host_daemon()
+        .inspect_container(&daemon.id, None::<InspectContainerOptions>)
+        .await
+        .expect("dind inspect");
+    let port = inspect
+        .network_settings
+        .and_then(|settings| settings.ports)
+        .and_then(|mut ports| ports.remove(&format!("{DIND_PORT}/tcp")).flatten())
+        .and_then(|bindings| bindings.into_iter().find_map(|binding| binding.host_port))
+        .expect("dind port");
+    Docker::connect_with_http(&format!("http://127.0.0.1:{port}"), 10, API_DEFAULT_VERSION).expect("dind client")
+}
+
+/// Waits for an event about `container_id` with `action`.
+async fn next_event(events: &mut broadcast::Receiver<DockerEvent>, container_id: &str, action: &str) -> DockerEvent {
+    timeout(WAIT_TIMEOUT, async {
+        loop {
+            let event = events.recv().await.expect("events channel");
+            if event.actor_id.as_deref() == Some(container_id) && event.action == action {
+                return event;
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("no {action} event for {container_id}"))
+}
+
+#[tokio::test]
+async fn test_container_commands() {
+    let fixture = sleeper().await;
+    let service = DockerService::new(CancellationToken::new()).with_client(host_daemon());
+    let id = fixture.id.as_str();
+
+    let find = || async {
+        service
+            .list_containers(true)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|container| container.id == id)
+            .expect("fixture listed")
+    };
+    let container = find().await;
+    assert_eq!(container.state, "running");
+    assert_eq!(container.labels.get("tn.redsys.integration").map(String::as_str), Some("true"));
+
+    service.stop_container(id, Duration::from_secs(1)).await.unwrap();
+    assert_eq!(find().await.state, "exited");
+
+    service.start_container(id).await.unwrap();
+    assert_eq!(find().await.state, "running");
+
+    assert!(service.start_container("redsys-no-such-container").await.is_err());
+}
+
+#[tokio::test]
+async fn test_events_stream() {
+    let fixture = sleeper().await;
+    let cancellation_token = CancellationToken::new();
+    let service = Arc::new(DockerService::new(cancellation_token.clone()).with_client(host_daemon()));
+    let mut events = service.subscribe_events();
+    let stream = tokio::spawn(service.clone().start_docker_events_stream());
+    // The stream replays from its first connection
+    sleep(Duration::from_secs(2)).await;
+
+    let id = fixture.id.as_str();
+    service.stop_container(id, Duration::from_secs(1)).await.unwrap();
+    let died = next_event(&mut events, id, "die").await;
+    assert_eq!(died.event_type, "container");
+    assert!(died.attributes.contains_key("exitCode"));
+    next_event(&mut events, id, "stop").await;
+
+    service.start_container(id).await.unwrap();
+    let started = next_event(&mut events, id, "start").await;
+    assert!(started.actor_name.is_some());
+    assert!(started.time.is_some());
+
+    cancellation_token.cancel();
+    stream.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_status_transitions() {
+    let daemon = dind().await;
+    let mut machine = StatusMachine::new();
+    let mut daemon_version = None;
+    // Feed checks one second apart, well inside the restart window
+    let start = Instant::now();
+    let mut checks = 0;
+    let mut observe = |checked: DockerStatus| {
+        checks += 1;
+        machine.observe(checked, start + Duration::from_secs(checks))
+    };
+
+    let client = dind_client(&daemon).await;
+    let checked = DockerMonitor::check_client(&client, &mut daemon_version).await.unwrap();
+    assert!(matches!(checked, DockerStatus::Running { .. }));
+    assert!(daemon_version.is_some());
+    observe(checked);
+
+    daemon.stop().await;
+    let checked = DockerMonitor::check_client(&client, &mut daemon_version).await.unwrap();
+    assert!(!matches!(checked, DockerStatus::Running { .. }));
+    assert_eq!(daemon_version, None);
+    assert_eq!(observe(checked).status, DockerStatus::Restarting);
+
+    // The published port changes with the restart
+    daemon.restart().await;
+    let client = dind_client(&daemon).await;
+    let restarted = timeout(WAIT_TIMEOUT, async {
+        loop {
+            if let Ok(checked @ DockerStatus::Running { .. }) = DockerMonitor::check_client(&client, &mut daemon_version).await {
+                return checked;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+    })
+    .await
+    .expect("dind back up");
+    let observation = observe(restarted);
+    assert_eq!(observation.transition.unwrap().reason, TransitionReason::DaemonRestarted);
+    assert_eq!(machine.state(), DaemonState::Running);
+}
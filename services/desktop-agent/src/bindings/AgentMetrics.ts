@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChannelDepths } from "./ChannelDepths";
+
+export type AgentMetrics = { rss_bytes: number | null, uptime_secs: number, runtime_tasks: number | null, running_subsystems: number, subsystem_restarts: number, channels: ChannelDepths, last_poll_latency_ms: number | null, docker_events_reconnects: number, collected_at: string, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AppMetadata } from "./AppMetadata";
+import type { DockerSection } from "./DockerSection";
+import type { JobsSection } from "./JobsSection";
+import type { RequirementsReport } from "./RequirementsReport";
+import type { SystemSection } from "./SystemSection";
+
+export type AppState = { app_metadata: AppMetadata, docker: DockerSection, system: SystemSection, jobs: JobsSection, requirements: RequirementsReport | null, last_updated: string, revision: number, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AppStateChanged = { revision: number, changes: Record<string, unknown>, updated_at: string, };
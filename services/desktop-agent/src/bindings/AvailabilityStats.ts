@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AvailabilityStats = { from: string, to: string, observed_secs: number, uptime_percent: number | null, outages: number, mean_outage_secs: number | null, restarts: number, };
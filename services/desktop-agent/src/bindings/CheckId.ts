@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CheckId = "virtualization" | "docker_installed" | "docker_running" | "socket_permission" | "daemon_exposure" | "gpu_runtime" | "disk_space" | "clock_drift" | "backend_reachable";
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExitFailure } from "./ExitFailure";
+
+export type ContainerFailed = { container_id: string, container_name: string, job_id: string | null, exit_code: number, failure: ExitFailure, failed_at: string, };
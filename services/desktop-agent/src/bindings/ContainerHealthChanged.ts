@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContainerHealth } from "./ContainerHealth";
+
+export type ContainerHealthChanged = { container_id: string, container_name: string | null, health: ContainerHealth, changed_at: string, };
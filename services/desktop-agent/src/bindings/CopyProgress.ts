@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CopyDirection } from "./CopyDirection";
+
+export type CopyProgress = { container: string, container_path: string, direction: CopyDirection, transferred_bytes: number, total_bytes: number | null, done: boolean, };
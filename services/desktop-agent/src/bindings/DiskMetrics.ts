@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type DiskMetrics = { name: string, mount_point: string, file_system: string, total_bytes: number, available_bytes: number, removable: boolean, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContainerHealth } from "./ContainerHealth";
+
+export type DockerEvent = { event_type: string, action: string, actor_id: string | null, actor_name: string | null, attributes: { [key in string]?: string }, health: ContainerHealth | null, time: string | null, };
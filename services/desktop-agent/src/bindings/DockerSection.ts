@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DockerStatus } from "./DockerStatus";
+
+export type DockerSection = { status: DockerStatus, updated_at: string | null, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DockerUnavailableReason } from "./DockerUnavailableReason";
+import type { EngineDetails } from "./EngineDetails";
+
+export type DockerStatus = { "type": "Running", version: string, engine: EngineDetails, } | { "type": "Stopped", reason: DockerUnavailableReason, } | { "type": "Error", message: string, retry_after: string | null, } | { "type": "Paused" } | { "type": "Starting" } | { "type": "Restarting" } | { "type": "Degraded", reason: string, } | { "type": "Unreachable", endpoint: string, reason: DockerUnavailableReason, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DockerStatus } from "./DockerStatus";
+import type { LastKnownEngine } from "./LastKnownEngine";
+
+export type DockerStatusChangedPayload = { changed_at: string, last_known: LastKnownEngine | null, } & DockerStatus;
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SocketPermission } from "./SocketPermission";
+
+export type DockerUnavailableReason = { "type": "NotInstalled" } | { "type": "ServiceStopped" } | { "type": "SocketPermissionDenied" } & SocketPermission | { "type": "WslDisabled" } | { "type": "TlsMisconfigured", message: string, } | { "type": "Unknown" };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CheckId } from "./CheckId";
+import type { CheckStatus } from "./CheckStatus";
+
+export type DoctorCheck = { id: CheckId, status: CheckStatus, summary: string, fix_hint: string | null, };
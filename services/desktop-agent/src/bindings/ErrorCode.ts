@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ErrorCode = "application" | "configuration" | "network" | "timeout" | "permission_denied" | "not_found" | "invalid_state" | "conflict" | "policy_violation" | "serialization" | "io" | "docker_unavailable" | "docker_api" | "docker_timeout" | "docker_socket_permission" | "docker_unsupported_version" | "resource_limit" | "event_emission" | "internal";
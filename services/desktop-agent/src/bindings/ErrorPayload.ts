@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ErrorCode } from "./ErrorCode";
+
+export type ErrorPayload = { code: ErrorCode, message: string, details: Record<string, unknown> | null, retryable: boolean, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LayerInfo } from "./LayerInfo";
+
+export type ImageAnalysis = { reference: string, image_id: string, layers: Array<LayerInfo>, total_bytes: number, shared_bytes: number, wasted_bytes: number, analyzed_at: string, };
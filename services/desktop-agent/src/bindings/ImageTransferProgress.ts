@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImageTransferDirection } from "./ImageTransferDirection";
+
+export type ImageTransferProgress = { path: string, direction: ImageTransferDirection, transferred_bytes: number, total_bytes: number | null, done: boolean, };
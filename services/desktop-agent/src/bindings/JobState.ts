@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type JobState = { "type": "Queued" } | { "type": "Pulling" } | { "type": "Starting" } | { "type": "Running" } | { "type": "Succeeded" } | { "type": "Failed", error: string, } | { "type": "Cancelled" };
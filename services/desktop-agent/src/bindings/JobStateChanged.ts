@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JobState } from "./JobState";
+
+export type JobStateChanged = { job_id: string, state: JobState, container_id: string | null, exit_code: number | null, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JobStateChanged } from "./JobStateChanged";
+
+export type JobsSection = { active: Array<JobStateChanged>, succeeded: number, failed: number, cancelled: number, updated_at: string | null, };
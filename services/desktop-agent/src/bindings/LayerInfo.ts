@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type LayerInfo = { diff_id: string, created_by: string, size_bytes: number, shared: boolean, };
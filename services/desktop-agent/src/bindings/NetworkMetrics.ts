@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type NetworkMetrics = { interface: string, received_bytes_per_sec: number, transmitted_bytes_per_sec: number, total_received_bytes: number, total_transmitted_bytes: number, };
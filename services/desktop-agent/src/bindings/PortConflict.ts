@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PortProtocol } from "./PortProtocol";
+
+export type PortConflict = { job_id: string, host_port: number, protocol: PortProtocol, containers: Array<string>, host_listener: boolean, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PortConflict } from "./PortConflict";
+import type { PublishedPort } from "./PublishedPort";
+
+export type PortOverview = { published: Array<PublishedPort>, conflicts: Array<PortConflict>, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ProbeResult = { target: string, latency_ms: number | null, jitter_ms: number | null, download_bps: number | null, error: string | null, };
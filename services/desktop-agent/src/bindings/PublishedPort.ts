@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PortProtocol } from "./PortProtocol";
+
+export type PublishedPort = { container_id: string, container_name: string, job_id: string | null, host_ip: string | null, host_port: number, container_port: number, protocol: PortProtocol, };
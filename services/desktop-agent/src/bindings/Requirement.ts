@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Requirement = "cpu_cores" | "memory" | "disk_space" | "os_version" | "docker_version";
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { UnmetRequirement } from "./UnmetRequirement";
+
+export type RequirementsReport = { met: boolean, unmet: Array<UnmetRequirement>, checked_at: string, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FindingKind } from "./FindingKind";
+import type { FindingSeverity } from "./FindingSeverity";
+
+export type SecurityFinding = { container_id: string, container_name: string, kind: FindingKind, severity: FindingSeverity, detail: string, };
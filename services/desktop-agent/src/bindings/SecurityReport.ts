@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SecurityFinding } from "./SecurityFinding";
+
+export type SecurityReport = { findings: Array<SecurityFinding>, containers_audited: number, audited_at: string, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SecurityWarningKind } from "./SecurityWarningKind";
+
+export type SecurityWarning = { kind: SecurityWarningKind, message: string, endpoints: Array<string>, detected_at: string, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExitFailure } from "./ExitFailure";
+
+export type ServiceFlapping = { job_id: string, container_id: string, consecutive_restarts: number, max_restarts: number, exit_code: number, failure: ExitFailure | null, detected_at: string, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SignatureVerification = { reference: string | null, verified: boolean, key: string | null, error: string | null, verified_at: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DaemonState } from "./DaemonState";
+import type { DockerStatus } from "./DockerStatus";
+import type { TransitionReason } from "./TransitionReason";
+
+export type StatusTransition = { from: DaemonState, to: DaemonState, reason: TransitionReason, status: DockerStatus | null, at: string, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CpuMetrics } from "./CpuMetrics";
+import type { DiskMetrics } from "./DiskMetrics";
+import type { MemoryMetrics } from "./MemoryMetrics";
+import type { NetworkMetrics } from "./NetworkMetrics";
+
+export type SystemMetrics = { cpu: CpuMetrics, memory: MemoryMetrics, swap: MemoryMetrics, disks: Array<DiskMetrics>, networks: Array<NetworkMetrics>, sampled_at: string, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SystemMetrics } from "./SystemMetrics";
+
+export type SystemSection = { metrics: SystemMetrics | null, updated_at: string | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TransitionReason = "check_started" | "daemon_up" | "daemon_down" | "daemon_went_down" | "daemon_restarted" | "restart_timed_out" | "paused" | "resumed";
@@ -2,6 +2,7 @@ import React from "react";
 import { Sidebar } from "./Sidebar";
 import { StatusBar } from "./StatusBar";
 import { ContentWindow } from "./ContentWindow";
+import { DeepLinkDialog } from "./DeepLinkDialog";
 
 const AppLayout: React.FC = () => {
   return (
@@ -11,6 +12,7 @@ const AppLayout: React.FC = () => {
         <ContentWindow />
       </div>
       <StatusBar />
+      <DeepLinkDialog />
     </div>
   );
 };
@@ -0,0 +1,82 @@
+/**
+ * Deep Link Dialog Component
+ *
+ * Shows `redsys://` links opened from the web dashboard. Any website can
+ * open such a link, so `connect` links only register this machine as a
+ * provider after the user confirms here; job links show the job's logs.
+ *
+ * References:
+ * - [Tauri invoke](https://tauri.app/v2/api/js/core/#invoke)
+ * - [React Bits](https://reactbits.dev/)
+ */
+
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useDeepLinks } from "../../hooks";
+
+/** Fields of a `get_job` record used here */
+interface JobLogs {
+  logs: string | null;
+}
+
+export const DeepLinkDialog: React.FC = () => {
+  // React Bit: Custom hook for deep link requests
+  const { link, error, isBusy, answerConnect, dismiss } = useDeepLinks();
+  const [logs, setLogs] = useState<string | null>(null);
+
+  // React Bit: Side effect for the logs of a linked job
+  useEffect(() => {
+    setLogs(null);
+    if (link?.type !== "JobLogs") return;
+    invoke<JobLogs>("get_job", { id: link.job_id })
+      .then((job) => setLogs(job.logs ?? "No logs yet, the job is still running."))
+      .catch((error) => setLogs(`Failed to load logs: ${error}`));
+  }, [link]);
+
+  if (!link) return null;
+
+  return (
+    <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/50">
+      <div className="w-[480px] max-w-[90vw] bg-[#1e1e1e] border border-white/10 text-[#cccccc]">
+        <div className="px-6 pt-5 pb-4 border-b border-white/5 text-[11px] font-semibold uppercase tracking-[0.2px] text-white opacity-80">
+          {link.type === "Connect" ? "Register as provider" : `Logs of job ${link.job_id}`}
+        </div>
+        <div className="px-6 py-4 text-[13px]">
+          {link.type === "Connect" ? (
+            <p>
+              A RedSys link asks to register this machine as a compute provider. Only continue if you opened it
+              from your RedSys dashboard.
+            </p>
+          ) : (
+            <pre className="max-h-80 overflow-auto whitespace-pre-wrap text-xs text-[#969696]">{logs ?? "Loading…"}</pre>
+          )}
+          {error && <p className="mt-3 text-red-400">{error}</p>}
+        </div>
+        <div className="flex justify-end gap-2 px-6 pb-5">
+          {link.type === "Connect" ? (
+            <>
+              <button
+                className="text-xs px-3 py-1 border border-white/10 hover:bg-[#313244] disabled:opacity-50"
+                disabled={isBusy}
+                onClick={() => answerConnect(false)}
+              >
+                Decline
+              </button>
+              <button
+                className="text-xs px-3 py-1 bg-blue-600 text-white hover:bg-blue-500 disabled:opacity-50"
+                disabled={isBusy}
+                onClick={() => answerConnect(true)}
+              >
+                {isBusy ? "Registering…" : "Register"}
+              </button>
+            </>
+          ) : (
+            <button className="text-xs px-3 py-1 border border-white/10 hover:bg-[#313244]" onClick={dismiss}>
+              Close
+            </button>
+          )}
+        </div>
+      </div>
+    </div>
+  );
+};
@@ -0,0 +1 @@
+export { DeepLinkDialog } from './DeepLinkDialog';
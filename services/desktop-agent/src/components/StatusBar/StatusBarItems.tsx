@@ -1,11 +1,13 @@
 import React from "react";
-import { DockerStatusBarItem, VersionStatusBarItem } from "./items";
+import { DockerStatusBarItem, JobsStatusBarItem, NetworkStatusBarItem, VersionStatusBarItem } from "./items";
 
 export const StatusBarItems: React.FC = () => {
   return (
     <div className="flex items-center space-x-4">
       <VersionStatusBarItem />
       <DockerStatusBarItem />
+      <NetworkStatusBarItem />
+      <JobsStatusBarItem />
       {/* Add more status bar items here if needed */}
     </div>
   );
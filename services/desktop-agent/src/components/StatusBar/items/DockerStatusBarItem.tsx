@@ -22,6 +22,8 @@
 import React, { useCallback } from "react";
 import { useDockerStatus, useTippy } from "../../../hooks";
 import { GlowingCircle, StatusBarItemWrapper } from "../../common";
+import { formatUptime } from "../../../types/docker";
+import { escapeHtml } from "../../../utils";
 
 /**
  * Docker Status Bar Item Component
@@ -91,6 +93,7 @@ export const DockerStatusBarItem: React.FC = () => {
     }
 
     const isRunning = docker.status === "Running" && docker.version;
+    const isDegraded = docker.status === "Degraded";
     return `
       <div style="
         background: #1e1e1e;
@@ -169,13 +172,29 @@ export const DockerStatusBarItem: React.FC = () => {
                 font-size: 13px;
                 font-weight: 500;
               ">
-                ${isRunning ? "Running" : "Not Running"}
+                ${isRunning ? "Running" : isDegraded ? "Degraded" : "Not Running"}
               </span>
             </div>
           </div>
           
           ${
-            isRunning
+            isDegraded && docker.message
+              ? `
+            <!-- Degradation Reason Row -->
+            <div style="
+              color: #969696;
+              font-size: 12px;
+              font-weight: 400;
+              margin-bottom: 12px;
+            ">
+              ${escapeHtml(docker.message)}
+            </div>
+          `
+              : ""
+          }
+          
+          ${
+            docker.version
               ? `
             <!-- Version Row -->
             <div style="
@@ -190,7 +209,7 @@ export const DockerStatusBarItem: React.FC = () => {
                 font-size: 13px;
                 font-weight: 400;
               ">
-                Engine Version
+                ${isRunning || isDegraded ? "Engine Version" : "Last Known Version"}
               </span>
               <span style="
                 color: #ffffff;
@@ -201,7 +220,7 @@ export const DockerStatusBarItem: React.FC = () => {
                 padding: 4px 8px;
                 border-radius: 4px;
               ">
-                ${docker.version}
+                ${docker.version}${docker.startedAt ? ` · ${formatUptime(docker.startedAt)}` : ""}
               </span>
             </div>
           `
@@ -215,7 +234,7 @@ export const DockerStatusBarItem: React.FC = () => {
   // React Bit: Custom hook for tooltip management
   const tooltipRef = useTippy({
     content: getTooltipContent,
-    dependencies: [docker.status, docker.version, docker.color, isLoading],
+    dependencies: [docker.status, docker.version, docker.color, docker.message, isLoading],
   });
 
   return (
@@ -0,0 +1,64 @@
+/**
+ * Jobs Status Bar Item Component
+ *
+ * Displays the number of jobs that have not finished. Clicking the item
+ * opens a list of them, each with a button that cancels the job.
+ *
+ * Features:
+ * - Live job states from `job-state-changed` events
+ * - Cancellation through the `cancel_job` command
+ * - React Bits patterns for reusability
+ *
+ * References:
+ * - [Tauri invoke](https://tauri.app/v2/api/js/core/#invoke)
+ * - [React Bits](https://reactbits.dev/)
+ */
+
+import React, { useState } from "react";
+import { useJobs } from "../../../hooks";
+import { StatusBarItemWrapper } from "../../common";
+
+/**
+ * Jobs Status Bar Item Component
+ */
+export const JobsStatusBarItem: React.FC = () => {
+  // React Bit: Custom hook for job tracking
+  const { jobs, cancelling, cancelJob } = useJobs();
+  const [isOpen, setIsOpen] = useState(false);
+
+  return (
+    <div className="relative">
+      <StatusBarItemWrapper onClick={() => setIsOpen((open) => !open)}>
+        <span className="text-xs font-medium text-[#cccccc] transition-colors duration-200">
+          {jobs.length === 1 ? "1 job" : `${jobs.length} jobs`}
+        </span>
+      </StatusBarItemWrapper>
+      {isOpen && (
+        <div className="absolute bottom-full right-0 mb-2 min-w-[300px] bg-[#1e1e1e] border border-white/10 text-[#cccccc] z-50">
+          <div className="px-6 pt-5 pb-4 border-b border-white/5 text-[11px] font-semibold uppercase tracking-[0.2px] text-white opacity-80">
+            Jobs
+          </div>
+          <div className="px-6 pt-3 pb-4">
+            {jobs.length === 0 ? (
+              <div className="text-[13px] text-[#969696]">No jobs running</div>
+            ) : (
+              jobs.map((job) => (
+                <div key={job.job_id} className="flex items-center justify-between gap-4 py-1.5 border-t border-white/5">
+                  <span className="text-[13px] text-[#969696] truncate">{job.job_id}</span>
+                  <span className="text-[13px] text-white">{job.state.type}</span>
+                  <button
+                    className="text-xs px-2 py-0.5 border border-white/10 hover:bg-[#313244] disabled:opacity-50"
+                    disabled={cancelling.includes(job.job_id)}
+                    onClick={() => cancelJob(job.job_id)}
+                  >
+                    {cancelling.includes(job.job_id) ? "Cancelling…" : "Cancel"}
+                  </button>
+                </div>
+              ))
+            )}
+          </div>
+        </div>
+      )}
+    </div>
+  );
+};
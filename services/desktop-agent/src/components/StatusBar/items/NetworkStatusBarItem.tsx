@@ -0,0 +1,107 @@
+/**
+ * Network Status Bar Item Component
+ *
+ * Displays the latency to the closest network probe target, with the
+ * latency, jitter and throughput of every target in the tooltip. Clicking
+ * the item runs a new probe.
+ *
+ * Features:
+ * - Latest results from `network-probe-completed` events
+ * - On-demand probe on click
+ * - React Bits patterns for reusability
+ *
+ * References:
+ * - [Tauri invoke](https://tauri.app/v2/api/js/core/#invoke)
+ * - [Tippy.js](https://atomiks.github.io/tippyjs/)
+ * - [React Bits](https://reactbits.dev/)
+ */
+
+import React, { useCallback, useMemo } from "react";
+import { useNetworkProbe, useTippy } from "../../../hooks";
+import { StatusBarItemWrapper } from "../../common";
+import { formatThroughput } from "../../../types/docker";
+import { escapeHtml } from "../../../utils";
+
+/**
+ * Network Status Bar Item Component
+ */
+export const NetworkStatusBarItem: React.FC = () => {
+  // React Bit: Custom hook for network probe results
+  const { report, isProbing, runProbe } = useNetworkProbe();
+
+  // React Bit: Memoized lowest latency across targets
+  const bestLatency = useMemo(() => {
+    const latencies = (report?.results ?? [])
+      .map((result) => result.latency_ms)
+      .filter((latency): latency is number => latency !== null);
+    return latencies.length > 0 ? Math.min(...latencies) : null;
+  }, [report]);
+
+  // React Bit: Memoized tooltip content generator
+  const getTooltipContent = useCallback(() => {
+    const rows = (report?.results ?? [])
+      .map(
+        (result) => `
+          <div style="
+            display: flex;
+            align-items: center;
+            justify-content: space-between;
+            gap: 16px;
+            padding: 6px 0;
+            border-top: 1px solid rgba(255, 255, 255, 0.04);
+          ">
+            <span style="color: #969696; font-size: 13px;">${escapeHtml(result.target)}</span>
+            <span style="color: #ffffff; font-size: 13px; font-weight: 500;">
+              ${result.latency_ms !== null ? `${result.latency_ms.toFixed(0)} ms ± ${(result.jitter_ms ?? 0).toFixed(0)}` : "—"}
+              ${result.download_bps !== null ? ` · ${formatThroughput(result.download_bps)}` : ""}
+            </span>
+          </div>
+        `
+      )
+      .join("");
+
+    return `
+      <div style="
+        background: #1e1e1e;
+        border: 1px solid rgba(255, 255, 255, 0.08);
+        min-width: 300px;
+        font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+        color: #cccccc;
+      ">
+        <div style="
+          padding: 20px 24px 16px 24px;
+          border-bottom: 1px solid rgba(255, 255, 255, 0.06);
+          font-weight: 600;
+          font-size: 11px;
+          color: #ffffff;
+          letter-spacing: 0.2px;
+          text-transform: uppercase;
+          opacity: 0.8;
+        ">
+          Network
+        </div>
+        <div style="padding: 12px 24px 16px 24px;">
+          ${
+            isProbing
+              ? `<div style="color: #969696; font-size: 13px;">Measuring...</div>`
+              : rows || `<div style="color: #969696; font-size: 13px;">No measurements yet, click to measure</div>`
+          }
+        </div>
+      </div>
+    `;
+  }, [report, isProbing]);
+
+  // React Bit: Custom hook for tooltip management
+  const tooltipRef = useTippy({
+    content: getTooltipContent,
+    dependencies: [report, isProbing],
+  });
+
+  return (
+    <StatusBarItemWrapper tooltipRef={tooltipRef} onClick={isProbing ? undefined : runProbe}>
+      <span className="text-xs font-medium text-[#cccccc] transition-colors duration-200">
+        {isProbing ? "Measuring…" : bestLatency !== null ? `${bestLatency.toFixed(0)} ms` : "Network"}
+      </span>
+    </StatusBarItemWrapper>
+  );
+};
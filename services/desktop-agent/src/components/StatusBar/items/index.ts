@@ -1,3 +1,5 @@
 // StatusBar items barrel export
 export { DockerStatusBarItem } from './DockerStatusBarItem';
+export { JobsStatusBarItem } from './JobsStatusBarItem';
+export { NetworkStatusBarItem } from './NetworkStatusBarItem';
 export { VersionStatusBarItem } from './VersionStatusBarItem'; 
\ No newline at end of file
@@ -15,11 +15,13 @@ import { StatusBarItemProps } from "../../types";
 export const StatusBarItemWrapper: React.FC<StatusBarItemProps> = ({
   children,
   tooltipRef,
+  onClick,
   className = "",
 }) => {
   return (
     <div
       ref={tooltipRef}
+      onClick={onClick}
       className={`status-bar-item flex items-center cursor-pointer px-3 py-1.5 select-none transition-all duration-200 hover:bg-[#313244] ${className}`}
     >
       {children}
@@ -1,3 +1,6 @@
 // Hooks barrel export
 export * from './useTippy';
-export * from './useDockerStatus'; 
\ No newline at end of file
+export * from './useDockerStatus';
+export * from './useNetworkProbe';
+export * from './useJobs';
+export * from './useDeepLinks';
\ No newline at end of file
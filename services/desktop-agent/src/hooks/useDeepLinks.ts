@@ -0,0 +1,66 @@
+/**
+ * useDeepLinks Hook - React Bit Pattern
+ *
+ * Keeps the latest `redsys://` link announced by `deep-link-opened` until it
+ * is handled. A `connect` link carries no token: `answerConnect` tells the
+ * agent whether the user agreed, and the agent registers with the token it
+ * kept.
+ *
+ * React Bits Pattern: Custom Hook for State Management
+ * Reference: https://reactbits.dev/
+ */
+
+import { useEffect, useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import type { DeepLink } from "../types/docker";
+
+export const useDeepLinks = () => {
+  const [link, setLink] = useState<DeepLink | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [isBusy, setIsBusy] = useState(false);
+
+  const dismiss = useCallback(() => {
+    setLink(null);
+    setError(null);
+  }, []);
+
+  // React Bit: Memoized answer to a connect link
+  const answerConnect = useCallback(async (approved: boolean) => {
+    setIsBusy(true);
+    try {
+      await invoke("confirm_deep_link", { approved });
+      setLink(null);
+      setError(null);
+    } catch (error) {
+      console.error("Failed to answer deep link:", error);
+      setError(String(error));
+    } finally {
+      setIsBusy(false);
+    }
+  }, []);
+
+  // React Bit: Side effect for event listeners
+  useEffect(() => {
+    let unlisten: UnlistenFn | undefined;
+
+    const setupEventListeners = async () => {
+      try {
+        unlisten = await listen<DeepLink>("deep-link-opened", (event) => {
+          setLink(event.payload);
+          setError(null);
+        });
+      } catch (error) {
+        console.error("Failed to setup deep link event listener:", error);
+      }
+    };
+
+    setupEventListeners();
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  return { link, error, isBusy, answerConnect, dismiss };
+};
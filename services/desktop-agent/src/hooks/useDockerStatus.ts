@@ -12,6 +12,7 @@ import { useEffect, useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import type {
+  DockerStatusChangedPayload,
   DockerStatusPayload,
   ProcessedDockerStatus,
 } from "../types/docker";
@@ -22,6 +23,7 @@ export const useDockerStatus = () => {
     status: "Loading",
     color: "#6b7280", // Gray for loading state
     version: null,
+    startedAt: null,
     message: null,
   });
   const [isLoading, setIsLoading] = useState(true);
@@ -40,6 +42,7 @@ export const useDockerStatus = () => {
         status: "Error",
         color: "#ef4444",
         version: null,
+        startedAt: null,
         message: "Failed to connect to backend",
       });
     } finally {
@@ -48,11 +51,16 @@ export const useDockerStatus = () => {
   }, []);
 
   // React Bit: Memoized event handler
-  const handleDockerStatusChange = useCallback((event: { payload: DockerStatusPayload }) => {
+  const handleDockerStatusChange = useCallback((event: { payload: DockerStatusChangedPayload }) => {
     console.log("Docker status changed event:", event.payload);
     const processed = processDockerPayload(event.payload);
     console.log("Processed status from event:", processed);
-    setDocker(processed);
+    // A degraded daemon still runs the engine shown before
+    setDocker((previous) =>
+      processed.status === "Degraded" && !processed.version
+        ? { ...processed, version: previous.version }
+        : processed
+    );
   }, []);
 
   // React Bit: Side effect for initial status
@@ -66,8 +74,8 @@ export const useDockerStatus = () => {
     
     const setupEventListeners = async () => {
       try {
-        unlisten = await listen<DockerStatusPayload>(
-          "docker_status_changed",
+        unlisten = await listen<DockerStatusChangedPayload>(
+          "docker-status-changed",
           handleDockerStatusChange
         );
       } catch (error) {
@@ -0,0 +1,99 @@
+/**
+ * useJobs Hook - React Bit Pattern
+ *
+ * Keeps the jobs that have not finished, fetched on mount and updated by
+ * `job-state-changed` events. `cancelJob` stops a job's container.
+ *
+ * React Bits Pattern: Custom Hook for State Management
+ * Reference: https://reactbits.dev/
+ */
+
+import { useEffect, useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import type { JobState, JobStateChanged } from "../types/docker";
+
+/** Fields of a `list_jobs` record used here */
+interface JobRecord {
+  spec: { id: string };
+  state: JobState;
+  container_id: string | null;
+  exit_code: number | null;
+}
+
+const isFinished = (state: JobState) =>
+  state.type === "Succeeded" || state.type === "Failed" || state.type === "Cancelled";
+
+export const useJobs = () => {
+  const [jobs, setJobs] = useState<JobStateChanged[]>([]);
+  const [cancelling, setCancelling] = useState<string[]>([]);
+
+  // React Bit: Memoized job list fetcher
+  const fetchJobs = useCallback(async () => {
+    try {
+      const records = await invoke<JobRecord[]>("list_jobs");
+      setJobs(
+        records
+          .filter((record) => !isFinished(record.state))
+          .map((record) => ({
+            job_id: record.spec.id,
+            state: record.state,
+            container_id: record.container_id,
+            exit_code: record.exit_code,
+          }))
+      );
+    } catch (error) {
+      console.error("Failed to fetch jobs:", error);
+    }
+  }, []);
+
+  // React Bit: Memoized cancellation
+  const cancelJob = useCallback(async (jobId: string) => {
+    setCancelling((ids) => [...ids, jobId]);
+    try {
+      await invoke("cancel_job", { id: jobId });
+    } catch (error) {
+      console.error(`Failed to cancel job ${jobId}:`, error);
+      setCancelling((ids) => ids.filter((id) => id !== jobId));
+    }
+  }, []);
+
+  // React Bit: Side effect for initial jobs
+  useEffect(() => {
+    fetchJobs();
+  }, [fetchJobs]);
+
+  // React Bit: Side effect for event listeners
+  useEffect(() => {
+    let unlisten: UnlistenFn | undefined;
+
+    const setupEventListeners = async () => {
+      try {
+        unlisten = await listen<JobStateChanged>("job-state-changed", (event) => {
+          const change = event.payload;
+          setJobs((jobs) => {
+            if (isFinished(change.state)) {
+              return jobs.filter((job) => job.job_id !== change.job_id);
+            }
+            return jobs.some((job) => job.job_id === change.job_id)
+              ? jobs.map((job) => (job.job_id === change.job_id ? change : job))
+              : [...jobs, change];
+          });
+          if (isFinished(change.state)) {
+            setCancelling((ids) => ids.filter((id) => id !== change.job_id));
+          }
+        });
+      } catch (error) {
+        console.error("Failed to setup job event listener:", error);
+      }
+    };
+
+    setupEventListeners();
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  return { jobs, cancelling, cancelJob };
+};
@@ -0,0 +1,67 @@
+/**
+ * useNetworkProbe Hook - React Bit Pattern
+ *
+ * Keeps the latest network latency and throughput report, fetched on mount
+ * and updated by `network-probe-completed` events. `runProbe` measures now.
+ *
+ * React Bits Pattern: Custom Hook for State Management
+ * Reference: https://reactbits.dev/
+ */
+
+import { useEffect, useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import type { ProbeReport } from "../types/docker";
+
+export const useNetworkProbe = () => {
+  const [report, setReport] = useState<ProbeReport | null>(null);
+  const [isProbing, setIsProbing] = useState(false);
+
+  // React Bit: Memoized history fetcher
+  const fetchReports = useCallback(async () => {
+    try {
+      const reports = await invoke<ProbeReport[]>("get_network_probe_results");
+      setReport(reports.length > 0 ? reports[reports.length - 1] : null);
+    } catch (error) {
+      console.error("Failed to fetch network probe results:", error);
+    }
+  }, []);
+
+  // React Bit: Memoized on-demand probe
+  const runProbe = useCallback(async () => {
+    setIsProbing(true);
+    try {
+      setReport(await invoke<ProbeReport>("run_network_probe"));
+    } catch (error) {
+      console.error("Network probe failed:", error);
+    } finally {
+      setIsProbing(false);
+    }
+  }, []);
+
+  // React Bit: Side effect for initial results
+  useEffect(() => {
+    fetchReports();
+  }, [fetchReports]);
+
+  // React Bit: Side effect for event listeners
+  useEffect(() => {
+    let unlisten: UnlistenFn | undefined;
+
+    const setupEventListeners = async () => {
+      try {
+        unlisten = await listen<ProbeReport>("network-probe-completed", (event) => setReport(event.payload));
+      } catch (error) {
+        console.error("Failed to setup network probe event listener:", error);
+      }
+    };
+
+    setupEventListeners();
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  return { report, isProbing, runProbe };
+};
@@ -1,27 +1,112 @@
 /**
- * TypeScript discriminated union for Docker status that matches Rust enum serialization.
- * 
- * Uses `#[serde(tag = "type")]` in Rust which serializes to JSON with a "type" field.
- * This enables compile-time type safety and runtime type checking.
- * 
+ * Docker types generated from the Rust structs by ts-rs into `../bindings`.
+ * Regenerate with `cargo test --features ts-bindings export_bindings` in
+ * `src-tauri` after changing them.
+ *
  * References:
+ * - [ts-rs](https://github.com/Aleph-Alpha/ts-rs)
  * - [TypeScript Discriminated Unions](https://www.typescriptlang.org/docs/handbook/2/narrowing.html#discriminated-unions)
- * - [Serde Enum Representations](https://serde.rs/enum-representations.html)
  */
 
-export type DockerStatusPayload =
-  | { type: "Running"; version: string }
-  | { type: "Stopped" }
-  | { type: "Error"; message: string };
+import type { DockerStatus } from "../bindings/DockerStatus";
+import type { DockerStatusChangedPayload as GeneratedDockerStatusChangedPayload } from "../bindings/DockerStatusChangedPayload";
+
+export type { AgentMetrics } from "../bindings/AgentMetrics";
+export type { AppMetadata } from "../bindings/AppMetadata";
+export type { AppState } from "../bindings/AppState";
+export type { AppStateChanged } from "../bindings/AppStateChanged";
+export type { AvailabilityStats } from "../bindings/AvailabilityStats";
+export type { DockerSection } from "../bindings/DockerSection";
+export type { ImageAnalysis } from "../bindings/ImageAnalysis";
+export type { ImageTransferDirection } from "../bindings/ImageTransferDirection";
+export type { ImageTransferProgress } from "../bindings/ImageTransferProgress";
+export type { ImageUpdate } from "../bindings/ImageUpdate";
+export type { JobsSection } from "../bindings/JobsSection";
+export type { JobState } from "../bindings/JobState";
+export type { JobStateChanged } from "../bindings/JobStateChanged";
+export type { SystemMetrics } from "../bindings/SystemMetrics";
+export type { SystemSection } from "../bindings/SystemSection";
+export type { ContainerFailed } from "../bindings/ContainerFailed";
+export type { ContainerHealth } from "../bindings/ContainerHealth";
+export type { ContainerHealthChanged } from "../bindings/ContainerHealthChanged";
+export type { ContainerProcesses } from "../bindings/ContainerProcesses";
+export type { BuildProgress } from "../bindings/BuildProgress";
+export type { ChannelDepths } from "../bindings/ChannelDepths";
+export type { CheckId } from "../bindings/CheckId";
+export type { CheckStatus } from "../bindings/CheckStatus";
+export type { ConnectivityChanged } from "../bindings/ConnectivityChanged";
+export type { CopyDirection } from "../bindings/CopyDirection";
+export type { CopyProgress } from "../bindings/CopyProgress";
+export type { DaemonRestarted } from "../bindings/DaemonRestarted";
+export type { DaemonState } from "../bindings/DaemonState";
+export type { DeepLink } from "../bindings/DeepLink";
+export type { DoctorCheck } from "../bindings/DoctorCheck";
+export type { DoctorReport } from "../bindings/DoctorReport";
+export type { DockerEvent } from "../bindings/DockerEvent";
+export type { DockerStatus } from "../bindings/DockerStatus";
+export type { DockerUnavailableReason } from "../bindings/DockerUnavailableReason";
+export type { EngineDetails } from "../bindings/EngineDetails";
+export type { ErrorCode } from "../bindings/ErrorCode";
+export type { ErrorPayload } from "../bindings/ErrorPayload";
+export type { EventBatch } from "../bindings/EventBatch";
+export type { ExitFailure } from "../bindings/ExitFailure";
+export type { FileChange } from "../bindings/FileChange";
+export type { FileChangeKind } from "../bindings/FileChangeKind";
+export type { FindingKind } from "../bindings/FindingKind";
+export type { FindingSeverity } from "../bindings/FindingSeverity";
+export type { GcReport } from "../bindings/GcReport";
+export type { LastKnownEngine } from "../bindings/LastKnownEngine";
+export type { LayerInfo } from "../bindings/LayerInfo";
+export type { NetworkInterface } from "../bindings/NetworkInterface";
+export type { PortConflict } from "../bindings/PortConflict";
+export type { ProbeReport } from "../bindings/ProbeReport";
+export type { ProbeResult } from "../bindings/ProbeResult";
+export type { PortOverview } from "../bindings/PortOverview";
+export type { PortProtocol } from "../bindings/PortProtocol";
+export type { PublishedPort } from "../bindings/PublishedPort";
+export type { Requirement } from "../bindings/Requirement";
+export type { RequirementsReport } from "../bindings/RequirementsReport";
+export type { SecurityFinding } from "../bindings/SecurityFinding";
+export type { SecurityReport } from "../bindings/SecurityReport";
+export type { SecurityWarning } from "../bindings/SecurityWarning";
+export type { SecurityWarningKind } from "../bindings/SecurityWarningKind";
+export type { ServiceFlapping } from "../bindings/ServiceFlapping";
+export type { SignatureVerification } from "../bindings/SignatureVerification";
+export type { SocketPermission } from "../bindings/SocketPermission";
+export type { StatusTransition } from "../bindings/StatusTransition";
+export type { TransitionReason } from "../bindings/TransitionReason";
+export type { UnmetRequirement } from "../bindings/UnmetRequirement";
+
+/**
+ * Docker status as returned by `get_docker_status`.
+ */
+export type DockerStatusPayload = DockerStatus;
+
+/**
+ * Fields the agent adds to every event payload.
+ */
+export interface EventEnvelope {
+  contract_version: number;
+  correlation_id?: string;
+}
+
+/**
+ * Payload of the `docker-status-changed` event: the status plus when the
+ * monitor observed the change.
+ */
+export type DockerStatusChangedPayload = GeneratedDockerStatusChangedPayload & EventEnvelope;
 
 /**
  * Processed Docker status for UI consumption.
  * Extracts and normalizes data from the discriminated union.
  */
 export interface ProcessedDockerStatus {
-  status: "Running" | "Stopped" | "Error" | "Loading" | "Unknown";
+  status: "Running" | "Degraded" | "Stopped" | "Error" | "Paused" | "Loading" | "Unknown";
   color: string;
+  /** Running engine version, or the last known one while it is down */
   version: string | null;
+  /** When the daemon started, if the agent could tell */
+  startedAt: string | null;
   message: string | null;
 }
 
@@ -29,36 +114,98 @@ export interface ProcessedDockerStatus {
  * Processes Docker status payload into UI-friendly format.
  * 
  * Maps technical states to user-friendly status:
- * - "Running" → "Running" (with version)
- * - "Stopped", "Error" → "Stopped" (user doesn't need technical details)
+ * - "Running" → "Running" (with version and start time)
+ * - "Degraded" → "Degraded" (the daemon answers but is impaired, with the
+ *   reason as message)
+ * - "Stopped", "Error", "Starting", "Restarting", "Unreachable"
+ *   → "Stopped" (user doesn't need technical details)
+ * - "Paused" → "Paused" (monitoring suspended by the user)
+ *
+ * Outside "Running", `version` is the last known version from the
+ * `last_known` field of `docker-status-changed`, if any.
  * 
  * @param payload - Raw Docker status from Rust backend
  * @returns Processed status for UI rendering
  */
-export function processDockerPayload(payload: DockerStatusPayload): ProcessedDockerStatus {
+export function processDockerPayload(
+  payload: DockerStatusPayload | DockerStatusChangedPayload
+): ProcessedDockerStatus {
+  const lastKnownVersion = "last_known" in payload ? payload.last_known?.version ?? null : null;
   switch (payload.type) {
     case "Running":
       return {
         status: "Running",
         color: "#22c55e", // Green
         version: payload.version,
+        startedAt: payload.engine.started_at,
         message: null,
       };
+    case "Paused":
+      return {
+        status: "Paused",
+        color: "#f59e0b", // Amber
+        version: lastKnownVersion,
+        startedAt: null,
+        message: null,
+      };
+    case "Degraded":
+      return {
+        status: "Degraded",
+        color: "#fbbf24", // Light amber
+        version: lastKnownVersion,
+        startedAt: null,
+        message: payload.reason,
+      };
     case "Stopped":
     case "Error":
+    case "Starting":
+    case "Restarting":
+    case "Unreachable":
       // All non-running states are treated as "Stopped" for user-friendly display
       return {
         status: "Stopped",
         color: "#ef4444", // Red
-        version: null,
+        version: lastKnownVersion,
+        startedAt: null,
         message: null,
       };
     default:
       return {
         status: "Stopped",
         color: "#ef4444", // Red
-        version: null,
+        version: lastKnownVersion,
+        startedAt: null,
         message: null,
       };
   }
-} 
\ No newline at end of file
+}
+
+/**
+ * Formats how long the daemon has been up, e.g. "up 3 days".
+ *
+ * @param startedAt - Daemon start time as an ISO 8601 string
+ * @param now - Current time in milliseconds since the epoch
+ * @returns Uptime in the largest whole unit
+ */
+export function formatUptime(startedAt: string, now: number = Date.now()): string {
+  const minutes = Math.max(0, Math.floor((now - Date.parse(startedAt)) / 60_000));
+  const [value, unit]: [number, string] =
+    minutes >= 24 * 60
+      ? [Math.floor(minutes / (24 * 60)), "day"]
+      : minutes >= 60
+        ? [Math.floor(minutes / 60), "hour"]
+        : [minutes, "minute"];
+  return `up ${value} ${unit}${value === 1 ? "" : "s"}`;
+}
+
+/**
+ * Formats a network probe throughput for display.
+ *
+ * @param bitsPerSecond - Throughput in bits per second
+ * @returns Throughput in Mbit/s or Gbit/s
+ */
+export function formatThroughput(bitsPerSecond: number): string {
+  return bitsPerSecond >= 1e9
+    ? `${(bitsPerSecond / 1e9).toFixed(2)} Gbit/s`
+    : `${(bitsPerSecond / 1e6).toFixed(1)} Mbit/s`;
+}
@@ -13,6 +13,7 @@ export interface StatusBarItemProps {
   children?: React.ReactNode;
   className?: string;
   tooltipRef?: React.RefObject<HTMLDivElement>;
+  onClick?: () => void;
 }
 
 /**
@@ -25,4 +25,16 @@ export const validateStatusBarItem = (item: StatusBarItemConfig): boolean => {
  */
 export const generateStatusBarItemId = (prefix: string): string => {
   return `${prefix}-${Date.now()}-${Math.random().toString(36).substr(2, 9)}`;
-}; 
\ No newline at end of file
+}; 
+
+/**
+ * Escape text for interpolation into tooltip HTML
+ */
+export const escapeHtml = (text: string): string => {
+  return text
+    .replace(/&/g, '&amp;')
+    .replace(/</g, '&lt;')
+    .replace(/>/g, '&gt;')
+    .replace(/"/g, '&quot;')
+    .replace(/'/g, '&#39;');
+};
\ No newline at end of file